@@ -0,0 +1,139 @@
+//! Parse and evaluation benchmarks over the repository's Astra corpus
+//!
+//! Run with `cargo bench --bench corpus`. The `parse` and `eval` phases parse
+//! or run the tests of every `.astra` file under `examples/` and
+//! `tests/runtime/`; the `calls` phase runs `benches/calls.astra`, which
+//! calls a function thousands of times and so measures function lookup and
+//! capture rather than the corpus's short tests. Each phase reports the
+//! fastest of several rounds.
+//!
+//! `cargo bench --bench corpus -- --save-baseline` records the timings in
+//! `target/corpus-baseline.txt`; later runs print the change against them,
+//! so the two sides of a change can be compared on one machine.
+
+use astra::interpreter::Interpreter;
+use astra::parser::{parse_source, Item, Module};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const ROUNDS: usize = 5;
+const ITERATIONS: usize = 20;
+const CALL_ITERATIONS: usize = 5;
+
+fn corpus() -> Vec<(PathBuf, String)> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut files = Vec::new();
+    for dir in ["examples", "tests/runtime"] {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(root.join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "astra"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let source = std::fs::read_to_string(&path).unwrap();
+            files.push((path, source));
+        }
+    }
+    files
+}
+
+/// The fastest of [`ROUNDS`] timings of `work`
+fn measure(mut work: impl FnMut()) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let started = Instant::now();
+            work();
+            started.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn run_tests(module: &Module) {
+    for item in &module.items {
+        if let Item::Test(test) = item {
+            let mut interpreter = Interpreter::new();
+            // Tests that need capabilities fail fast; the rest run in full
+            if interpreter.load_module(module).is_ok() {
+                let _ = interpreter.eval_block(&test.body);
+            }
+        }
+    }
+}
+
+/// Timings from an earlier `--save-baseline` run, by phase
+fn read_baseline(path: &Path) -> Vec<(String, Duration)> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    text.lines()
+        .filter_map(|line| {
+            let (phase, nanos) = line.split_once(' ')?;
+            Some((phase.to_string(), Duration::from_nanos(nanos.parse().ok()?)))
+        })
+        .collect()
+}
+
+fn main() {
+    let save_baseline = std::env::args().any(|arg| arg == "--save-baseline");
+    let baseline_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/corpus-baseline.txt");
+    let files = corpus();
+    let modules: Vec<Module> = files
+        .iter()
+        .filter_map(|(path, source)| parse_source(source, path).ok())
+        .collect();
+
+    let parse = measure(|| {
+        for _ in 0..ITERATIONS {
+            for (path, source) in &files {
+                let _ = parse_source(source, path);
+            }
+        }
+    });
+    let eval = measure(|| {
+        for _ in 0..ITERATIONS {
+            for module in &modules {
+                run_tests(module);
+            }
+        }
+    });
+
+    let calls_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/calls.astra");
+    let calls_module = parse_source(&std::fs::read_to_string(&calls_path).unwrap(), &calls_path)
+        .expect("benches/calls.astra parses");
+    let calls = measure(|| {
+        for _ in 0..CALL_ITERATIONS {
+            Interpreter::new().eval_module(&calls_module).unwrap();
+        }
+    });
+
+    println!(
+        "corpus: {} files, {} iterations, best of {} rounds",
+        files.len(),
+        ITERATIONS,
+        ROUNDS
+    );
+    let timings = [("parse", parse), ("eval", eval), ("calls", calls)];
+    let baseline = read_baseline(&baseline_path);
+    for (phase, time) in timings {
+        match baseline.iter().find(|(name, _)| name == phase) {
+            Some((_, before)) if !save_baseline => {
+                let change = (time.as_secs_f64() / before.as_secs_f64() - 1.0) * 100.0;
+                println!(
+                    "{:<6} {:>10.2?}  (baseline {:.2?}, {:+.1}%)",
+                    phase, time, before, change
+                );
+            }
+            _ => println!("{:<6} {:>10.2?}", phase, time),
+        }
+    }
+    if save_baseline {
+        let text: String = timings
+            .iter()
+            .map(|(phase, time)| format!("{} {}\n", phase, time.as_nanos()))
+            .collect();
+        std::fs::write(&baseline_path, text).unwrap();
+        println!("saved baseline to {}", baseline_path.display());
+    }
+}
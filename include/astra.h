@@ -0,0 +1,58 @@
+/*
+ * C interface to the Astra toolchain.
+ *
+ * Build the shared library with `cargo build --release --features astra-ffi`
+ * (libastra.so, libastra.dylib, or astra.dll). All strings are NUL-terminated
+ * UTF-8. Functions returning `char *` return a JSON document owned by the
+ * caller, which must be released with astra_string_free(). Errors in the
+ * arguments are reported in the JSON as
+ * {"ok": false, "error": {"code": "FFI", "message": "..."}}.
+ */
+#ifndef ASTRA_H
+#define ASTRA_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Version of the JSON schema returned by astra_check() and astra_run() */
+#define ASTRA_FFI_VERSION 1
+
+/*
+ * Parse and type check a module.
+ * Returns {"ok": bool, "diagnostics": [...]}.
+ */
+char *astra_check(const char *source);
+
+/*
+ * Run a module's main function in a sandbox.
+ *
+ * caps_json grants capabilities; NULL or "{}" grants none:
+ *   {"console": true,                  capture output in "output"
+ *    "fs": {"path": "contents"},       in-memory files, returned in "files"
+ *    "net": {"url": "response"},       canned responses
+ *    "clock": 1700000000000,           fixed Clock.now() in milliseconds
+ *    "env": {"vars": {}, "args": []},
+ *    "rand": true}                     Rand seeded with seed
+ *
+ * Returns {"ok": bool, "value": "...", "output": "...", "files": {...},
+ *          "error": {"code", "message"}, "diagnostics": [...]}.
+ */
+char *astra_run(const char *source, uint64_t seed, const char *caps_json);
+
+/* Release a string returned by astra_check() or astra_run(). NULL is ignored. */
+void astra_string_free(char *string);
+
+/* The toolchain version, e.g. "1.0.0". Static; do not free. */
+const char *astra_version(void);
+
+/* ASTRA_FFI_VERSION of the loaded library */
+uint32_t astra_ffi_version(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ASTRA_H */
@@ -0,0 +1,169 @@
+//! Diagnostics baselines for incremental adoption of `astra check --strict`.
+//!
+//! A baseline records the diagnostics a project already has so that later
+//! checks only report new ones. Entries are matched by code and a fingerprint
+//! of the diagnostic's message and source text, so they survive unrelated edits
+//! that shift line numbers.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::diagnostics::Diagnostic;
+
+/// Current baseline file format version
+const BASELINE_VERSION: u32 = 1;
+
+/// A single recorded diagnostic
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    /// File path relative to the project root
+    pub file: String,
+    /// Diagnostic code (e.g., "W0001")
+    pub code: String,
+    /// Stable fingerprint of the diagnostic (see [`fingerprint`])
+    pub fingerprint: String,
+    /// Diagnostic message, kept for readability of the baseline file
+    pub message: String,
+}
+
+/// The on-disk baseline structure
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub entries: Vec<BaselineEntry>,
+}
+
+impl Default for Baseline {
+    fn default() -> Self {
+        Self {
+            version: BASELINE_VERSION,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl Baseline {
+    /// Load a baseline file. Returns `Ok(None)` if the file does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read baseline {:?}: {}", path, e))?;
+        let baseline: Baseline = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid baseline {:?}: {}", path, e))?;
+        if baseline.version != BASELINE_VERSION {
+            return Err(format!(
+                "Unsupported baseline version {} in {:?} (expected {})",
+                baseline.version, path, BASELINE_VERSION
+            ));
+        }
+        Ok(Some(baseline))
+    }
+
+    /// Save the baseline file, with entries sorted for stable diffs.
+    pub fn save(&mut self, path: &Path) -> Result<(), String> {
+        self.entries.sort();
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize baseline: {}", e))?;
+        std::fs::write(path, json + "\n")
+            .map_err(|e| format!("Failed to write baseline {:?}: {}", path, e))
+    }
+
+    /// Record a diagnostic from `file` (relative to the project root).
+    pub fn record(&mut self, file: &str, diagnostic: &Diagnostic, source: &str) {
+        self.entries.push(BaselineEntry {
+            file: file.to_string(),
+            code: diagnostic.code.clone(),
+            fingerprint: fingerprint(diagnostic, source),
+            message: diagnostic.message.clone(),
+        });
+    }
+
+    /// Build a matcher that removes baselined diagnostics.
+    pub fn matcher(&self) -> BaselineMatcher {
+        let mut remaining = HashMap::new();
+        for entry in &self.entries {
+            *remaining
+                .entry((
+                    entry.file.clone(),
+                    entry.code.clone(),
+                    entry.fingerprint.clone(),
+                ))
+                .or_insert(0) += 1;
+        }
+        BaselineMatcher { remaining }
+    }
+}
+
+/// Matches diagnostics against a baseline. Each entry absorbs at most one
+/// diagnostic, so a second occurrence of a baselined problem is still reported.
+#[derive(Debug)]
+pub struct BaselineMatcher {
+    remaining: HashMap<(String, String, String), usize>,
+}
+
+impl BaselineMatcher {
+    /// Returns `true` (and consumes the entry) if the diagnostic is in the baseline.
+    pub fn matches(&mut self, file: &str, diagnostic: &Diagnostic, source: &str) -> bool {
+        let key = (
+            file.to_string(),
+            diagnostic.code.clone(),
+            fingerprint(diagnostic, source),
+        );
+        match self.remaining.get_mut(&key) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Compute a stable fingerprint for a diagnostic.
+///
+/// Uses the code, message, and whitespace-normalized first line of the source
+/// text under the primary span — not line numbers — so it is unaffected by
+/// edits elsewhere in the file. (Spans can extend into the following token, so
+/// only the first line is considered.) FNV-1a is used because it is stable
+/// across toolchains.
+pub fn fingerprint(diagnostic: &Diagnostic, source: &str) -> String {
+    let snippet = source
+        .get(diagnostic.span.start..diagnostic.span.end)
+        .unwrap_or("");
+    let first_line = snippet.lines().next().unwrap_or("");
+    let normalized = first_line.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in [
+        diagnostic.code.as_str(),
+        diagnostic.message.as_str(),
+        normalized.as_str(),
+    ] {
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+/// Path of `file` relative to `project_root`, with `/` separators.
+pub fn relative_path(file: &Path, project_root: &Path) -> String {
+    let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+    let root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    let relative = file.strip_prefix(&root).unwrap_or(&file);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+#[path = "baseline_tests.rs"]
+mod tests;
@@ -0,0 +1,59 @@
+use super::*;
+use crate::diagnostics::Span;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn diag_at(code: &str, message: &str, start: usize, end: usize) -> Diagnostic {
+    Diagnostic::warning(code)
+        .message(message)
+        .span(Span::new(PathBuf::from("a.astra"), start, end, 1, 1, 1, 1))
+        .build()
+}
+
+#[test]
+fn test_fingerprint_ignores_position() {
+    let before = "let x = 1\n  2";
+    let after = "\n\n  let x =   1\n  let y = 2";
+    let d1 = diag_at("W0001", "Unused variable `x`", 0, 13);
+    let d2 = diag_at("W0001", "Unused variable `x`", 4, 25);
+    assert_eq!(fingerprint(&d1, before), fingerprint(&d2, after));
+}
+
+#[test]
+fn test_fingerprint_differs_by_code() {
+    let source = "let x = 1";
+    let d1 = diag_at("W0001", "msg", 0, 9);
+    let d2 = diag_at("W0006", "msg", 0, 9);
+    assert_ne!(fingerprint(&d1, source), fingerprint(&d2, source));
+}
+
+#[test]
+fn test_matcher_consumes_each_entry_once() {
+    let source = "let x = 1\nlet x = 1\n";
+    let d1 = diag_at("W0001", "Unused variable `x`", 0, 9);
+    let d2 = diag_at("W0001", "Unused variable `x`", 10, 19);
+
+    let mut baseline = Baseline::default();
+    baseline.record("a.astra", &d1, source);
+
+    let mut matcher = baseline.matcher();
+    assert!(matcher.matches("a.astra", &d1, source));
+    assert!(!matcher.matches("a.astra", &d2, source));
+}
+
+#[test]
+fn test_baseline_round_trip() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("baseline.json");
+    assert!(Baseline::load(&path).unwrap().is_none());
+
+    let source = "let x = 1";
+    let d = diag_at("W0001", "Unused variable `x`", 0, 9);
+    let mut baseline = Baseline::default();
+    baseline.record("a.astra", &d, source);
+    baseline.save(&path).unwrap();
+
+    let loaded = Baseline::load(&path).unwrap().unwrap();
+    assert_eq!(loaded.entries, baseline.entries);
+    assert!(loaded.matcher().matches("a.astra", &d, source));
+}
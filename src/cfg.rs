@@ -0,0 +1,122 @@
+//! Conditional compilation with `@cfg(...)` attributes
+//!
+//! An item marked `@cfg(test)` or `@cfg(feature = "net")` is only part of
+//! the program when every condition of its attributes holds. Inactive items
+//! are skipped by the type checker and the interpreter, and `astra package`
+//! removes them from the bundled sources.
+
+use crate::parser::ast::{Attribute, Item, Module};
+use std::collections::BTreeSet;
+
+/// The flag that is set while running `astra test` and `astra check`
+pub const TEST: &str = "test";
+
+/// The configuration that `@cfg(...)` attributes are evaluated against
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cfg {
+    /// Flags such as `test`, matched by `@cfg(name)`
+    flags: BTreeSet<String>,
+    /// Enabled features, matched by `@cfg(feature = "name")`
+    features: BTreeSet<String>,
+}
+
+impl Cfg {
+    /// Configuration for test builds, with the `test` flag set
+    pub fn test() -> Self {
+        let mut cfg = Self::default();
+        cfg.flags.insert(TEST.to_string());
+        cfg
+    }
+
+    /// Enable the given features
+    pub fn with_features<I, S>(mut self, features: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.features.extend(features.into_iter().map(Into::into));
+        self
+    }
+
+    /// Enabled features, in sorted order
+    pub fn features(&self) -> impl Iterator<Item = &str> {
+        self.features.iter().map(String::as_str)
+    }
+
+    /// Whether an item with these attributes is active.
+    ///
+    /// Every argument of every `@cfg` attribute must hold; unknown flags and
+    /// keys never do.
+    pub fn is_active(&self, attributes: &[Attribute]) -> bool {
+        attributes
+            .iter()
+            .filter(|attr| attr.name == "cfg")
+            .all(|attr| {
+                attr.args.iter().all(|flag| self.flags.contains(flag))
+                    && attr
+                        .named_args
+                        .iter()
+                        .all(|(key, value)| key == "feature" && self.features.contains(value))
+            })
+    }
+
+    /// Whether an item is part of the program under this configuration
+    pub fn includes(&self, item: &Item) -> bool {
+        self.is_active(item.attributes())
+    }
+
+    /// Remove the text of inactive items from `source`, along with their
+    /// attributes and doc comments. Test and property blocks and test hooks
+    /// are removed as well unless the `test` flag is set.
+    pub fn strip_source(&self, source: &str, module: &Module) -> String {
+        let testing = self.flags.contains(TEST);
+        let mut removed: Vec<(usize, usize)> = module
+            .items
+            .iter()
+            .filter(|item| match item {
+                Item::Test(_) | Item::Property(_) | Item::TestHook(_) => !testing,
+                _ => !self.includes(item),
+            })
+            .map(|item| {
+                let start = item
+                    .attributes()
+                    .first()
+                    .map_or(item.span().start, |attr| attr.span.start);
+                (leading_docs_start(source, start), item.span().end)
+            })
+            .collect();
+        removed.sort();
+
+        let mut stripped = String::with_capacity(source.len());
+        let mut pos = 0;
+        for (start, end) in removed {
+            stripped.push_str(&source[pos..start]);
+            // Drop the rest of the item's last line
+            pos = source[end..]
+                .find('\n')
+                .map_or(source.len(), |i| end + i + 1);
+        }
+        stripped.push_str(&source[pos..]);
+        stripped
+    }
+}
+
+/// The start of the `##` doc comment lines directly above the line that
+/// contains `offset`, or the start of that line if there are none
+fn leading_docs_start(source: &str, offset: usize) -> usize {
+    let mut start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    while start > 0 {
+        let prev_start = source[..start - 1].rfind('\n').map_or(0, |i| i + 1);
+        let line = source[prev_start..start].trim();
+        if line.starts_with("##") && !line.starts_with("##!") {
+            start = prev_start;
+        } else {
+            break;
+        }
+    }
+    start
+}
+
+#[cfg(test)]
+#[path = "cfg_tests.rs"]
+mod tests;
@@ -0,0 +1,104 @@
+use super::*;
+use crate::parser::parse_source;
+use std::path::Path;
+
+fn parse(source: &str) -> Module {
+    parse_source(source, Path::new("test.astra")).unwrap()
+}
+
+fn fn_attributes(module: &Module, name: &str) -> Vec<Attribute> {
+    module
+        .items
+        .iter()
+        .find_map(|item| match item {
+            Item::FnDef(def) if def.name == name => Some(def.attributes.clone()),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_is_active() {
+    let module = parse(
+        r#"module app
+
+@cfg(test)
+fn helper() -> Int { 1 }
+
+@cfg(feature = "net")
+fn fetch() -> Int { 2 }
+
+@cfg(test, feature = "net")
+fn both() -> Int { 3 }
+
+@cfg(unknown)
+fn never() -> Int { 4 }
+
+fn always() -> Int { 5 }
+"#,
+    );
+    let release = Cfg::default();
+    let test = Cfg::test();
+    let net = Cfg::default().with_features(["net"]);
+    let test_net = Cfg::test().with_features(["net"]);
+
+    let helper = fn_attributes(&module, "helper");
+    assert!(!release.is_active(&helper));
+    assert!(test.is_active(&helper));
+
+    let fetch = fn_attributes(&module, "fetch");
+    assert!(!test.is_active(&fetch));
+    assert!(net.is_active(&fetch));
+
+    let both = fn_attributes(&module, "both");
+    assert!(!test.is_active(&both));
+    assert!(!net.is_active(&both));
+    assert!(test_net.is_active(&both));
+
+    assert!(!test_net.is_active(&fn_attributes(&module, "never")));
+    assert!(release.is_active(&fn_attributes(&module, "always")));
+}
+
+#[test]
+fn test_unknown_cfg_key_is_inactive() {
+    let module = parse("module app\n@cfg(platform = \"wasm\")\nfn f() -> Int { 1 }\n");
+    assert!(!Cfg::test()
+        .with_features(["wasm"])
+        .is_active(&fn_attributes(&module, "f")));
+}
+
+#[test]
+fn test_strip_source_removes_inactive_items_and_tests() {
+    let source = r#"module app
+
+## Doubles a number
+fn double(x: Int) -> Int { x * 2 }
+
+## Only for tests
+@cfg(test)
+fn fixture() -> Int { 21 }
+
+@cfg(feature = "net")
+fn fetch() -> Int { 1 }
+
+test "double" {
+  assert_eq(double(fixture()), 42)
+}
+"#;
+    let module = parse(source);
+    let stripped = Cfg::default().strip_source(source, &module);
+    assert_eq!(
+        stripped,
+        "module app\n\n## Doubles a number\nfn double(x: Int) -> Int { x * 2 }\n\n\n\n"
+    );
+    assert!(parse_source(&stripped, Path::new("test.astra")).is_ok());
+
+    let with_net = Cfg::default().with_features(["net"]);
+    assert!(with_net
+        .strip_source(source, &module)
+        .contains("fn fetch() -> Int { 1 }"));
+
+    // Test builds keep everything
+    let test = Cfg::test().with_features(["net"]);
+    assert_eq!(test.strip_source(source, &module), source);
+}
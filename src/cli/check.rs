@@ -0,0 +1,371 @@
+//! `astra check`: type checking files, with caching, baselines, and typed holes
+
+use super::*;
+
+pub(super) fn run_check(
+    paths: &[PathBuf],
+    strict: bool,
+    no_cache: bool,
+    baseline_path: Option<&Path>,
+    format: OutputFormat,
+    features: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::cache::{self, CachedFileResult, CheckCache};
+
+    let project_root = cache::find_project_root(
+        paths
+            .first()
+            .map(|p| p.as_path())
+            .unwrap_or_else(|| std::path::Path::new(".")),
+    );
+    let mut cache = if no_cache {
+        CheckCache::default()
+    } else {
+        CheckCache::load(&project_root)
+    };
+    let lint_config = load_lint_config(&project_root)?;
+    let cfg = project_cfg(Cfg::test(), &project_root, features);
+    let features_key = cfg.features().collect::<Vec<_>>().join(",");
+
+    // Existing baseline filters known diagnostics; a missing one is recorded
+    let (mut baseline_matcher, mut new_baseline) = match baseline_path {
+        Some(path) => match Baseline::load(path)? {
+            Some(baseline) => (Some(baseline.matcher()), None),
+            None => (None, Some(Baseline::default())),
+        },
+        None => (None, None),
+    };
+
+    let mut total_errors = 0;
+    let mut total_warnings = 0;
+    let mut total_baselined = 0;
+    let mut files_checked = 0;
+    let mut files_cached = 0;
+    let mut envelope = Envelope::new("check");
+
+    let astra_files = collect_astra_files(paths)?;
+    if format == OutputFormat::Jsonl {
+        emit_event(
+            "start",
+            serde_json::json!({
+                "schema_version": envelope::SCHEMA_VERSION,
+                "command": "check",
+                "files": astra_files.len(),
+            }),
+        );
+    }
+    for file_path in &astra_files {
+        let source = std::fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read {:?}: {}", file_path, e))?;
+        // Enabled features change which items are checked
+        let content_hash = cache::hash_content(&source) ^ cache::hash_content(&features_key);
+        files_checked += 1;
+
+        // Try cache lookup (the cache stores diagnostics before lint levels apply)
+        let cached = if no_cache {
+            None
+        } else {
+            cache.lookup(file_path, content_hash).map(|cached| {
+                cached
+                    .diagnostics
+                    .iter()
+                    .filter_map(|d| serde_json::from_str::<Diagnostic>(d).ok())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        let from_cache = cached.is_some();
+        let diagnostics = match cached {
+            Some(diagnostics) => {
+                files_cached += 1;
+                diagnostics
+            }
+            None => {
+                let diagnostics = check_file(file_path, &source, &cfg);
+                cache.store(
+                    file_path,
+                    CachedFileResult {
+                        content_hash,
+                        errors: diagnostics.iter().filter(|d| d.is_error()).count(),
+                        warnings: diagnostics
+                            .iter()
+                            .filter(|d| matches!(d.severity, Severity::Warning))
+                            .count(),
+                        diagnostics: diagnostics.iter().map(|d| d.to_json()).collect(),
+                    },
+                );
+                diagnostics
+            }
+        };
+
+        let mut diagnostics = lint_config.apply(diagnostics);
+        if baseline_path.is_some() {
+            let relative = crate::baseline::relative_path(file_path, &project_root);
+            if let Some(baseline) = new_baseline.as_mut() {
+                for d in &diagnostics {
+                    baseline.record(&relative, d, &source);
+                }
+                total_baselined += diagnostics.len();
+                diagnostics.clear();
+            }
+            if let Some(matcher) = baseline_matcher.as_mut() {
+                let before = diagnostics.len();
+                diagnostics.retain(|d| !matcher.matches(&relative, d, &source));
+                total_baselined += before - diagnostics.len();
+            }
+        }
+        report_diagnostics(&diagnostics, &source, format, &mut envelope);
+        if format == OutputFormat::Jsonl {
+            emit_event(
+                "progress",
+                serde_json::json!({
+                    "file": file_path.display().to_string(),
+                    "cached": from_cache,
+                    "diagnostics": diagnostics.len(),
+                    "checked": files_checked,
+                    "total": astra_files.len(),
+                }),
+            );
+        }
+        total_errors += diagnostics.iter().filter(|d| d.is_error()).count();
+        total_warnings += diagnostics
+            .iter()
+            .filter(|d| matches!(d.severity, Severity::Warning))
+            .count();
+    }
+
+    // Save cache (unless --no-cache)
+    if !no_cache {
+        cache.prune();
+        if let Err(e) = cache.save(&project_root) {
+            eprintln!("Warning: failed to save check cache: {}", e);
+        }
+    }
+
+    if let (Some(path), Some(mut baseline)) = (baseline_path, new_baseline) {
+        baseline.save(path)?;
+        if format == OutputFormat::Jsonl {
+            emit_event(
+                "summary",
+                serde_json::json!({
+                    "files": files_checked,
+                    "baseline_recorded": total_baselined,
+                    "baseline": path.display().to_string(),
+                    "success": true,
+                }),
+            );
+            return Ok(());
+        }
+        if format == OutputFormat::Json {
+            envelope.artifact("baseline", path.display());
+            envelope.data = serde_json::json!({
+                "files": files_checked,
+                "baseline_recorded": total_baselined,
+            });
+            envelope.emit(Status::Success);
+            return Ok(());
+        }
+        println!(
+            "Checked {} file(s), recorded {} diagnostic(s) in baseline {:?}",
+            files_checked, total_baselined, path
+        );
+        return Ok(());
+    }
+
+    let has_issues = total_errors > 0 || (strict && total_warnings > 0);
+
+    if format == OutputFormat::Jsonl {
+        emit_event(
+            "summary",
+            serde_json::json!({
+                "files": files_checked,
+                "cached": files_cached,
+                "errors": total_errors,
+                "warnings": total_warnings,
+                "baselined": total_baselined,
+                "success": !has_issues,
+            }),
+        );
+        if has_issues {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if format == OutputFormat::Json {
+        envelope.data = serde_json::json!({
+            "files": files_checked,
+            "cached": files_cached,
+            "errors": total_errors,
+            "warnings": total_warnings,
+            "baselined": total_baselined,
+        });
+        envelope.emit(if has_issues {
+            Status::Failure
+        } else {
+            Status::Success
+        });
+        return Ok(());
+    }
+
+    if has_issues {
+        let mut parts = Vec::new();
+        if total_errors > 0 {
+            parts.push(format!("{} error(s)", total_errors));
+        }
+        if total_warnings > 0 {
+            if strict {
+                parts.push(format!(
+                    "{} warning(s) [treated as errors with --strict]",
+                    total_warnings
+                ));
+            } else {
+                parts.push(format!("{} warning(s)", total_warnings));
+            }
+        }
+        eprintln!(
+            "\nChecked {} file(s), found {}",
+            files_checked,
+            parts.join(", ")
+        );
+        std::process::exit(1);
+    } else if total_warnings > 0 {
+        let cache_note = check_summary_note(files_cached, total_baselined);
+        println!(
+            "Checked {} file(s){}, no errors ({} warning(s))",
+            files_checked, cache_note, total_warnings
+        );
+    } else {
+        let cache_note = check_summary_note(files_cached, total_baselined);
+        println!(
+            "Checked {} file(s){}, no errors found",
+            files_checked, cache_note
+        );
+    }
+
+    Ok(())
+}
+
+/// Parenthesized note for the `astra check` summary line (cached/baselined counts)
+pub(super) fn check_summary_note(files_cached: usize, baselined: usize) -> String {
+    let mut parts = Vec::new();
+    if files_cached > 0 {
+        parts.push(format!("{} cached", files_cached));
+    }
+    if baselined > 0 {
+        parts.push(format!("{} baselined diagnostic(s) hidden", baselined));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", parts.join(", "))
+    }
+}
+
+/// Add diagnostics to the JSON envelope, print them as `diagnostic` events
+/// (stdout), or print human-readable text (stderr), grouping repeated
+/// diagnostics under their first occurrence
+pub(super) fn report_diagnostics(
+    diagnostics: &[Diagnostic],
+    source: &str,
+    format: OutputFormat,
+    envelope: &mut Envelope,
+) {
+    let grouped = crate::diagnostics::group_diagnostics(
+        diagnostics.to_vec(),
+        crate::diagnostics::MAX_GROUPED_REPEATS,
+    );
+    for d in grouped {
+        match format {
+            OutputFormat::Json => envelope.diagnostics.push(d),
+            OutputFormat::Jsonl => {
+                emit_event("diagnostic", serde_json::to_value(&d).unwrap_or_default())
+            }
+            OutputFormat::Human => eprintln!("{}", d.render(source, render_options())),
+        }
+    }
+}
+
+/// Run `astra check --holes`: report the typed holes of each file, or the
+/// parse errors of files that do not parse
+pub(super) fn run_check_holes(
+    paths: &[PathBuf],
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let project_root = crate::cache::find_project_root(
+        paths
+            .first()
+            .map(|p| p.as_path())
+            .unwrap_or_else(|| std::path::Path::new(".")),
+    );
+    let cfg = project_cfg(Cfg::test(), &project_root, &[]);
+    let mut envelope = Envelope::new("check");
+    let mut files = 0;
+    let mut holes = 0;
+    let mut errors = 0;
+    for file_path in &collect_astra_files(paths)? {
+        let source = std::fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read {:?}: {}", file_path, e))?;
+        files += 1;
+        let diagnostics = check_file(file_path, &source, &cfg);
+        let parse_failed = diagnostics
+            .iter()
+            .any(|d| d.is_error() && d.code.starts_with("E0"));
+        let reported: Vec<Diagnostic> = diagnostics
+            .into_iter()
+            .filter(|d| d.hole.is_some() || (parse_failed && d.is_error()))
+            .collect();
+        holes += reported.iter().filter(|d| d.hole.is_some()).count();
+        errors += reported.iter().filter(|d| d.is_error()).count();
+        report_diagnostics(&reported, &source, format, &mut envelope);
+    }
+
+    match format {
+        OutputFormat::Json => {
+            envelope.data = serde_json::json!({
+                "files": files,
+                "holes": holes,
+                "errors": errors,
+            });
+            envelope.emit(if errors > 0 {
+                Status::Failure
+            } else {
+                Status::Success
+            });
+            return Ok(());
+        }
+        OutputFormat::Jsonl => emit_event(
+            "summary",
+            serde_json::json!({
+                "files": files,
+                "holes": holes,
+                "errors": errors,
+                "success": errors == 0,
+            }),
+        ),
+        OutputFormat::Human => {
+            println!("Checked {} file(s), found {} hole(s)", files, holes);
+        }
+    }
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Parse and type-check a single file, returning all diagnostics (errors + warnings)
+pub(super) fn check_file(path: &Path, source: &str, cfg: &Cfg) -> Vec<Diagnostic> {
+    let source_file = SourceFile::new(path.to_path_buf(), source.to_string());
+    let lexer = Lexer::new(&source_file);
+    let mut parser = AstraParser::new(lexer, source_file.clone());
+    match parser.parse_module() {
+        Ok(module) => {
+            // Run type checking (includes exhaustiveness + effect + lint enforcement)
+            let mut checker = crate::typechecker::TypeChecker::new();
+            checker.set_cfg(cfg.clone());
+            configure_checker_search_paths(&mut checker, path.parent());
+            let _type_result = checker.check_module(&module);
+            checker.diagnostics().diagnostics().to_vec()
+        }
+        Err(e) => e.diagnostics().to_vec(),
+    }
+}
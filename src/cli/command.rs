@@ -0,0 +1,523 @@
+//! The `astra` subcommands and their arguments
+
+use super::*;
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Format Astra source files
+    Fmt {
+        /// Files or directories to format
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Check formatting without modifying files
+        #[arg(long)]
+        check: bool,
+
+        /// Format each file twice without writing, reporting output that
+        /// changes on the second pass or no longer parses to the same program
+        #[arg(long, conflicts_with = "check")]
+        verify: bool,
+    },
+
+    /// Check for errors without running
+    Check {
+        /// Files or directories to check
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Treat warnings as errors (like strict mypy / ruff enforcement)
+        #[arg(long)]
+        strict: bool,
+
+        /// Bypass the incremental cache (re-check all files)
+        #[arg(long)]
+        no_cache: bool,
+
+        /// Watch for file changes and re-check automatically
+        #[arg(long)]
+        watch: bool,
+
+        /// Only report diagnostics not recorded in this baseline file
+        /// (the file is created from the current diagnostics if missing)
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<PathBuf>,
+
+        /// Output format (defaults to `json` with --json, otherwise `human`)
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+
+        /// Report only typed holes (`???`) with their expected type, the
+        /// bindings that fit, and the enclosing function's contract
+        #[arg(long, conflicts_with_all = ["watch", "baseline"])]
+        holes: bool,
+
+        /// Enable features for `@cfg(feature = "...")` items, on top of the
+        /// manifest's default features
+        #[arg(long, value_delimiter = ',', value_name = "FEATURES")]
+        features: Vec<String>,
+    },
+
+    /// Report lints (W-codes) at the levels configured in astra.toml
+    Lint {
+        /// Files or directories to lint
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Allow (suppress) these lints, by name or code (comma-separated)
+        #[arg(long, short = 'A', value_name = "LINTS")]
+        allow: Vec<String>,
+
+        /// Report these lints as warnings, by name or code (comma-separated)
+        #[arg(long, short = 'W', value_name = "LINTS")]
+        warn: Vec<String>,
+
+        /// Report these lints as errors, by name or code (comma-separated)
+        #[arg(long, short = 'D', value_name = "LINTS")]
+        deny: Vec<String>,
+
+        /// Output format (defaults to `json` with --json, otherwise `human`)
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+
+    /// Run tests
+    Test {
+        /// Filter tests by qualified name (`module::test`); `module::` runs
+        /// every test in a module and its submodules
+        #[arg()]
+        filter: Option<String>,
+
+        /// Same as the positional filter
+        #[arg(long = "filter", value_name = "FILTER", conflicts_with = "filter")]
+        filter_flag: Option<String>,
+
+        /// Random seed for deterministic tests
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Watch for file changes and re-run tests automatically
+        #[arg(long)]
+        watch: bool,
+
+        /// Write the actual values to golden files instead of comparing
+        /// them in `assert_matches_file`
+        #[arg(long)]
+        update_golden: bool,
+
+        /// Only replay the property-test failures recorded in `.astra-failures`
+        #[arg(long)]
+        replay_failures: bool,
+
+        /// Enable features for `@cfg(feature = "...")` items, on top of the
+        /// manifest's default features
+        #[arg(long, value_delimiter = ',', value_name = "FEATURES")]
+        features: Vec<String>,
+
+        /// Number of slowest tests to list after the run
+        #[arg(long, value_name = "N", default_value_t = 5)]
+        slowest: usize,
+
+        /// Warn about tests slower than this, e.g. `500ms` or `2s`
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        warn_slower_than: Option<std::time::Duration>,
+
+        /// Skip the remaining tests after the first failure
+        #[arg(long)]
+        fail_fast: bool,
+
+        /// Which contracts to check: `off`, `boundary` (only public functions
+        /// and trait methods), or `all` (defaults to the manifest's
+        /// `[contracts] mode`, or `all`)
+        #[arg(long, value_name = "MODE")]
+        contracts: Option<ContractMode>,
+
+        /// Output format (defaults to `json` with --json, otherwise `human`);
+        /// `jsonl` streams an event as each test starts and finishes
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+
+        /// Report the files read and written, hosts contacted, and random
+        /// values and clock reads of all tests, by call site (`audit` in the
+        /// JSON summary)
+        #[arg(long)]
+        audit: bool,
+
+        /// Deepest nesting of function calls before a program stops with a
+        /// stack overflow error (E4028)
+        #[arg(long, default_value_t = DEFAULT_MAX_CALL_DEPTH)]
+        max_call_depth: usize,
+    },
+
+    /// Fuzz the toolchain with generated and mutated sources
+    Fuzz {
+        /// Stage to fuzz
+        #[arg(value_enum, default_value = "parser")]
+        target: FuzzTarget,
+
+        /// Seed for generating inputs (defaults to one based on the time)
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Number of inputs to try
+        #[arg(long, default_value_t = 1000)]
+        iterations: u64,
+    },
+
+    /// Run an Astra program
+    Run {
+        /// File to run (defaults to the manifest's binary target)
+        file: Option<PathBuf>,
+
+        /// Binary target from astra.toml to run
+        #[arg(long)]
+        bin: Option<String>,
+
+        /// Enable features for `@cfg(feature = "...")` items, on top of the
+        /// manifest's default features
+        #[arg(long, value_delimiter = ',', value_name = "FEATURES")]
+        features: Vec<String>,
+
+        /// Report steps executed, effect calls, and peak memory (`cost` in
+        /// the JSON envelope)
+        #[arg(long)]
+        report_cost: bool,
+
+        /// Which contracts to check: `off`, `boundary` (only public functions
+        /// and trait methods), or `all` (defaults to the manifest's
+        /// `[contracts] mode`, or `all`)
+        #[arg(long, value_name = "MODE")]
+        contracts: Option<ContractMode>,
+
+        /// After `main` returns, serve the routes it registered with
+        /// `Serve.route` on this address (`:8080` listens on all interfaces)
+        #[arg(long, value_name = "ADDR")]
+        serve: Option<String>,
+
+        /// Hold back file writes and report them (paths, sizes, and diffs)
+        /// instead of making them (`dry_run` in the JSON envelope)
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Report the files read and written, hosts contacted, and random
+        /// values and clock reads, by call site (`audit` in the JSON envelope)
+        #[arg(long)]
+        audit: bool,
+
+        /// Arguments to pass to the program
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+
+        /// Deepest nesting of function calls before a program stops with a
+        /// stack overflow error (E4028)
+        #[arg(long, default_value_t = DEFAULT_MAX_CALL_DEPTH)]
+        max_call_depth: usize,
+    },
+
+    /// Run an Astra program under the interactive step debugger
+    Debug {
+        /// File to debug (defaults to the manifest's binary target)
+        file: Option<PathBuf>,
+
+        /// Binary target from astra.toml to debug
+        #[arg(long)]
+        bin: Option<String>,
+
+        /// Pause at a line, as `file:line` or `line`; may be repeated
+        #[arg(long = "break", short = 'b', value_name = "FILE:LINE")]
+        breakpoints: Vec<String>,
+
+        /// Enable features for `@cfg(feature = "...")` items, on top of the
+        /// manifest's default features
+        #[arg(long, value_delimiter = ',', value_name = "FEATURES")]
+        features: Vec<String>,
+
+        /// Arguments to pass to the program
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+
+        /// Deepest nesting of function calls before a program stops with a
+        /// stack overflow error (E4028)
+        #[arg(long, default_value_t = DEFAULT_MAX_CALL_DEPTH)]
+        max_call_depth: usize,
+    },
+
+    /// Start interactive REPL
+    Repl {
+        /// Deepest nesting of function calls before a program stops with a
+        /// stack overflow error (E4028)
+        #[arg(long, default_value_t = DEFAULT_MAX_CALL_DEPTH)]
+        max_call_depth: usize,
+    },
+
+    /// Create a distributable package
+    Package {
+        /// Output directory
+        #[arg(long, short, default_value = "build")]
+        output: PathBuf,
+
+        /// Target format (wasm, native)
+        #[arg(long, default_value = "wasm")]
+        target: String,
+
+        /// Enable features for `@cfg(feature = "...")` items, on top of the
+        /// manifest's default features
+        #[arg(long, value_delimiter = ',', value_name = "FEATURES")]
+        features: Vec<String>,
+    },
+
+    /// Initialize a new Astra project
+    Init {
+        /// Project name (defaults to current directory name)
+        #[arg()]
+        name: Option<String>,
+
+        /// Create a library project (no main function)
+        #[arg(long)]
+        lib: bool,
+    },
+
+    /// Generate API documentation from doc comments
+    Doc {
+        /// Files or directories to document
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Output directory for generated docs
+        #[arg(long, short, default_value = "docs/api")]
+        output: PathBuf,
+
+        /// Output format (markdown or html)
+        #[arg(long, default_value = "markdown")]
+        format: String,
+    },
+
+    /// Auto-apply diagnostic fix suggestions
+    Fix {
+        /// Files or directories to fix
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Only apply fixes for specific error/warning codes (e.g., W0001,E1002)
+        #[arg(long)]
+        only: Option<String>,
+
+        /// Show what would be fixed without modifying files
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Also apply fixes that may change behavior and need review
+        #[arg(long)]
+        unsafe_fixes: bool,
+    },
+
+    /// Dump the parsed module as JSON (node kinds, spans, and node IDs)
+    Ast {
+        /// File to parse
+        file: PathBuf,
+
+        /// Type-check the module and include each expression's inferred type
+        #[arg(long)]
+        resolve: bool,
+    },
+
+    /// Dump the token stream with spans and trivia (whitespace, comments)
+    Tokens {
+        /// File to tokenize
+        file: PathBuf,
+
+        /// Classify spans semantically (function, parameter, type, effect, ...)
+        #[arg(long)]
+        semantic: bool,
+    },
+
+    /// Report semantic changes between two versions of a module
+    Diff {
+        /// Old version of the module
+        old: PathBuf,
+
+        /// New version of the module
+        new: PathBuf,
+    },
+
+    /// List functions, types, enums, traits, and effects with their signatures
+    Symbols {
+        /// Files or directories to index
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Index every file in the project containing the current directory
+        #[arg(long)]
+        workspace: bool,
+    },
+
+    /// Export the call graph or the module dependency graph
+    Graph {
+        /// Files or directories to analyze
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Export the inter-function call graph, including effect calls
+        #[arg(long, required_unless_present = "modules", conflicts_with = "modules")]
+        calls: bool,
+
+        /// Export the module import graph, reporting import cycles
+        #[arg(long)]
+        modules: bool,
+
+        /// Output in Graphviz DOT format
+        #[arg(long)]
+        dot: bool,
+    },
+
+    /// Find the declaration and all uses of the name at a location
+    Refs {
+        /// Location of the name, as `file.astra:line:col`
+        location: String,
+    },
+
+    /// Rename the name at a location and all of its uses in the project
+    Rename {
+        /// Location of the name, as `file.astra:line:col`
+        location: String,
+
+        /// The new name
+        new_name: String,
+
+        /// Rewrite the files in place instead of printing a diff
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Apply a patch of span-based edits, then check the patched files
+    Apply {
+        /// JSON file of edits (`-` for stdin), as in diagnostic suggestions
+        patch: PathBuf,
+
+        /// Check and print the patched files as a diff without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Explain an error or warning code in detail
+    Explain {
+        /// Error code to explain (e.g., E1001, W0001)
+        code: String,
+    },
+
+    /// Start Language Server Protocol server (for IDE integration)
+    Lsp,
+
+    /// Start Debug Adapter Protocol server (for debugging in editors)
+    Dap {
+        /// Deepest nesting of function calls before a program stops with a
+        /// stack overflow error (E4028)
+        #[arg(long, default_value_t = DEFAULT_MAX_CALL_DEPTH)]
+        max_call_depth: usize,
+    },
+
+    /// Start a Jupyter kernel (for notebooks and `jupyter console`)
+    Kernel {
+        /// Connection file written by Jupyter
+        connection_file: Option<PathBuf>,
+
+        /// Install the kernel spec so Jupyter can start this kernel
+        #[arg(long, conflicts_with = "connection_file")]
+        install: bool,
+
+        /// Deepest nesting of function calls before a program stops with a
+        /// stack overflow error (E4028)
+        #[arg(long, default_value_t = DEFAULT_MAX_CALL_DEPTH)]
+        max_call_depth: usize,
+    },
+
+    /// v1.1: Package management commands
+    Pkg {
+        #[command(subcommand)]
+        action: PkgAction,
+    },
+
+    /// Run a script as `astra <file> [args...]`, which is what a
+    /// `#!/usr/bin/env astra` line does
+    #[command(external_subcommand)]
+    Script(Vec<String>),
+}
+
+/// v1.1: Package management actions
+#[derive(Subcommand, Debug)]
+pub enum PkgAction {
+    /// Install dependencies from astra.toml
+    Install,
+
+    /// Add a dependency to astra.toml
+    Add {
+        /// Package name
+        name: String,
+
+        /// Version requirement (e.g., "1.0", "^2.0")
+        #[arg(long)]
+        version: Option<String>,
+
+        /// Git repository URL
+        #[arg(long)]
+        git: Option<String>,
+
+        /// Local path
+        #[arg(long)]
+        path: Option<String>,
+    },
+
+    /// Remove a dependency from astra.toml
+    Remove {
+        /// Package name to remove
+        name: String,
+    },
+
+    /// List installed packages
+    List,
+}
+
+impl Command {
+    /// The call depth limit for commands that evaluate code, which run on a
+    /// thread with enough stack to reach it; `None` for the rest
+    pub(super) fn max_call_depth(&self) -> Option<usize> {
+        match self {
+            Command::Test { max_call_depth, .. }
+            | Command::Run { max_call_depth, .. }
+            | Command::Debug { max_call_depth, .. }
+            | Command::Repl { max_call_depth }
+            | Command::Dap { max_call_depth }
+            | Command::Kernel { max_call_depth, .. } => Some(*max_call_depth),
+            Command::Script(_) => Some(DEFAULT_MAX_CALL_DEPTH),
+            _ => None,
+        }
+    }
+
+    /// Name used in the `--json` envelope, for commands that emit one
+    pub(super) fn envelope_name(&self) -> Option<&'static str> {
+        match self {
+            Command::Fmt { .. } => Some("fmt"),
+            Command::Check { format, .. } if format.is_none_or(|f| f == OutputFormat::Json) => {
+                Some("check")
+            }
+            Command::Lint { format, .. } if format.is_none_or(|f| f == OutputFormat::Json) => {
+                Some("lint")
+            }
+            Command::Test { format, .. } if format.is_none_or(|f| f == OutputFormat::Json) => {
+                Some("test")
+            }
+            Command::Fuzz { .. } => Some("fuzz"),
+            Command::Run { .. } | Command::Script(_) => Some("run"),
+            Command::Package { .. } => Some("package"),
+            Command::Ast { .. } => Some("ast"),
+            Command::Tokens { .. } => Some("tokens"),
+            Command::Diff { .. } => Some("diff"),
+            Command::Symbols { .. } => Some("symbols"),
+            Command::Graph { .. } => Some("graph"),
+            Command::Refs { .. } => Some("refs"),
+            Command::Rename { .. } => Some("rename"),
+            Command::Apply { .. } => Some("apply"),
+            _ => None,
+        }
+    }
+}
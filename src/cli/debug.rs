@@ -0,0 +1,59 @@
+//! `astra debug`: running a program under the step debugger
+
+use super::*;
+
+pub(super) fn run_debug(
+    file: Option<PathBuf>,
+    bin: Option<&str>,
+    breakpoints: &[String],
+    features: &[String],
+    args: Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::debugger::{Breakpoint, Debugger};
+
+    let RunTarget {
+        file,
+        entry,
+        bin,
+        args,
+    } = resolve_run_target(file, bin, args)?;
+    let source = std::fs::read_to_string(&file)
+        .map_err(|e| format!("Failed to read file {:?}: {}", file, e))?;
+    let module = crate::parser::parse_source(&source, &file)
+        .map_err(|e| format!("Parse error:\n{}", e.render(&source, render_options())))?;
+
+    let mut debugger = Debugger::new(
+        Box::new(std::io::BufReader::new(std::io::stdin())),
+        Box::new(std::io::stdout()),
+    );
+    debugger.add_source(&file, &source);
+    debugger.stepper().set_entry(&entry);
+    if !breakpoints.is_empty() {
+        debugger.stepper().run_to_breakpoint();
+    }
+    for breakpoint in breakpoints {
+        debugger
+            .stepper()
+            .add_breakpoint(Breakpoint::parse(breakpoint)?);
+    }
+
+    let mut interpreter = new_interpreter(real_capabilities(args));
+    interpreter.set_cfg(project_cfg(
+        Cfg::default(),
+        &crate::cache::find_project_root(&file),
+        features,
+    ));
+    configure_search_paths(&mut interpreter, file.parent());
+    interpreter.set_debug_hook(Box::new(debugger));
+    match interpreter
+        .eval_module_entry(&module, &entry)
+        .map_err(|e| in_bin_target(e, bin.as_deref()))
+    {
+        Ok(_) => {
+            println!("Program finished");
+            Ok(())
+        }
+        Err(e) if e.code == "E4023" => Ok(()),
+        Err(e) => Err(format!("Runtime error: {}", e).into()),
+    }
+}
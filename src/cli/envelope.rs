@@ -0,0 +1,122 @@
+//! Versioned JSON envelope shared by the `--json` output of every command.
+//!
+//! `fmt`, `check`, `lint`, `test`, `run`, and `package` print exactly one
+//! [`Envelope`] document on stdout, so agent integrations parse one schema
+//! instead of one per command. Command-specific results go in `data`.
+
+use serde::Serialize;
+use std::time::Instant;
+
+use crate::diagnostics::Diagnostic;
+
+/// Version of the envelope schema; bumped on breaking changes
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Outcome of a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// The command ran and found no problems
+    Success,
+    /// The command ran and found problems (errors, failing tests, unformatted files)
+    Failure,
+    /// The command could not run to completion
+    Error,
+}
+
+impl Status {
+    /// Process exit code for this status
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Status::Success => 0,
+            Status::Failure | Status::Error => 1,
+        }
+    }
+}
+
+/// A file produced or modified by a command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Artifact {
+    /// What the artifact is (e.g., "formatted", "package", "baseline")
+    pub kind: String,
+    /// Path of the artifact
+    pub path: String,
+}
+
+/// The machine-readable result of a command
+#[derive(Debug, Serialize)]
+pub struct Envelope {
+    pub schema_version: u32,
+    pub command: String,
+    pub status: Status,
+    pub duration_ms: u64,
+    pub exit_code: i32,
+    pub diagnostics: Vec<Diagnostic>,
+    pub artifacts: Vec<Artifact>,
+    /// Error message when the command could not run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Command-specific results (counts, test results, ...)
+    pub data: serde_json::Value,
+    #[serde(skip)]
+    started: Instant,
+}
+
+impl Envelope {
+    /// Start an envelope for `command`; the duration is measured from now
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            command: command.into(),
+            status: Status::Success,
+            duration_ms: 0,
+            exit_code: 0,
+            diagnostics: Vec::new(),
+            artifacts: Vec::new(),
+            error: None,
+            data: serde_json::Value::Null,
+            started: Instant::now(),
+        }
+    }
+
+    /// Record a file the command produced or modified
+    pub fn artifact(&mut self, kind: &str, path: impl std::fmt::Display) {
+        self.artifacts.push(Artifact {
+            kind: kind.to_string(),
+            path: path.to_string(),
+        });
+    }
+
+    /// Set the final status and duration, returning the JSON document
+    pub fn finish(&mut self, status: Status) -> serde_json::Value {
+        self.status = status;
+        self.exit_code = status.exit_code();
+        self.duration_ms = self.started.elapsed().as_millis() as u64;
+        serde_json::to_value(&*self).unwrap_or_default()
+    }
+
+    /// Print the envelope on stdout and exit the process on a non-zero exit code
+    pub fn emit(mut self, status: Status) {
+        println!("{}", self.finish(status));
+        if self.exit_code != 0 {
+            std::process::exit(self.exit_code);
+        }
+    }
+
+    /// Print the envelope with a command-specific exit code for `status`
+    /// and exit the process when it is non-zero
+    pub fn emit_with_code(mut self, status: Status, exit_code: i32) {
+        self.finish(status);
+        self.exit_code = exit_code;
+        println!("{}", serde_json::to_value(&self).unwrap_or_default());
+        if exit_code != 0 {
+            std::process::exit(exit_code);
+        }
+    }
+
+    /// Print an envelope for a command that failed with `error`
+    pub fn emit_error(mut self, error: impl std::fmt::Display) {
+        self.error = Some(error.to_string());
+        self.emit(Status::Error);
+    }
+}
@@ -0,0 +1,915 @@
+//! `astra explain`: long-form explanations of error and warning codes
+
+pub(super) fn run_explain(code: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let explanation = get_error_explanation(code);
+    match explanation {
+        Some(text) => {
+            println!("{}", text);
+        }
+        None => {
+            eprintln!("Unknown error code: {}", code);
+            eprintln!();
+            eprintln!("Valid error codes:");
+            eprintln!("  E0xxx  Syntax/parsing errors (E0001-E0015)");
+            eprintln!("  E1xxx  Type errors (E1001-E1018)");
+            eprintln!("  E2xxx  Effect errors (E2001-E2007)");
+            eprintln!("  E3xxx  Contract violations (E3001-E3006)");
+            eprintln!("  E4xxx  Runtime errors (E4001-E4008, E4024-E4029)");
+            eprintln!("  W0xxx  Warnings (W0001-W0012)");
+            eprintln!("  H0xxx  Typed holes (H0001)");
+            eprintln!("  E9xxx  Internal errors (E9001-E9003)");
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// Get a detailed explanation for an error code.
+pub(super) fn get_error_explanation(code: &str) -> Option<String> {
+    let explanation = match code {
+        // Syntax errors
+        "E0001" => {
+            r#"E0001: Unexpected token
+
+The parser encountered a token that doesn't fit the expected grammar.
+
+Example:
+  fn add(a Int) -> Int {  # expected ':', found 'Int'
+    a
+  }
+
+Fix: Add the missing punctuation or correct the syntax.
+"#
+        }
+        "E0002" => {
+            r#"E0002: Unterminated string literal
+
+A string literal was opened with `"` but never closed.
+
+Example:
+  let s = "hello
+
+Fix: Close the string with a matching `"`.
+"#
+        }
+        "E0003" => {
+            r#"E0003: Invalid number literal
+
+A number literal contains invalid characters, or its value doesn't fit its type.
+
+Example:
+  let n = 123abc
+  let d = 99999999999999999999999999999999d
+
+Fix: Ensure numbers contain only digits (and optionally one `.` for floats), and
+that the value fits its type.
+"#
+        }
+        "E0004" => {
+            r#"E0004: Missing closing delimiter
+
+An opening bracket, brace, or parenthesis was not closed.
+
+Example:
+  fn foo() {
+    let x = (1 + 2
+  }
+
+Fix: Add the matching closing delimiter `)`, `]`, or `}`.
+"#
+        }
+        "E0005" => {
+            r#"E0005: Invalid identifier
+
+An identifier contains invalid characters or starts incorrectly.
+
+Fix: Identifiers must start with a letter or underscore, followed by
+letters, digits, or underscores.
+"#
+        }
+        "E0006" => {
+            r#"E0006: Reserved keyword used as identifier
+
+A reserved keyword cannot be used as a variable or function name.
+
+Example:
+  let match = 5  # 'match' is reserved
+
+Fix: Choose a different name that isn't a keyword.
+"#
+        }
+        "E0007" => {
+            r#"E0007: Invalid escape sequence
+
+A string contains an unrecognized escape sequence.
+
+Example:
+  let s = "hello\q"  # \q is not valid
+
+Valid escape sequences: \n, \r, \t, \\, \"
+
+Fix: Use a valid escape sequence or remove the backslash.
+"#
+        }
+        "E0008" => {
+            r#"E0008: Unexpected end of file
+
+The parser reached the end of the file while still expecting more tokens.
+
+Example:
+  fn foo() {
+
+Fix: Ensure all blocks and expressions are properly completed.
+"#
+        }
+        "E0009" => {
+            r#"E0009: Invalid module declaration
+
+The module declaration is malformed.
+
+Example:
+  module    # missing module name
+
+Fix: Provide a valid module path like `module my_project.utils`.
+"#
+        }
+        "E0010" => {
+            r#"E0010: Duplicate module declaration
+
+Two modules with the same name exist in the project.
+
+Fix: Rename one of the modules to avoid the conflict.
+"#
+        }
+        "E0011" => {
+            r#"E0011: Module not found
+
+An import refers to a module that doesn't exist.
+
+Example:
+  import std.nonexistent
+
+Fix: Check the module name and ensure it exists. Available stdlib modules:
+  std.core, std.list, std.math, std.option, std.result, std.string,
+  std.collections, std.json, std.io, std.iter, std.error, std.prelude
+"#
+        }
+        "E0012" => {
+            r#"E0012: Unknown macro
+
+A `name!(...)` invocation names a macro that isn't defined in this module.
+
+Example:
+  let m = maximum!(a, b)   # no `macro maximum` in the module
+
+Fix: Define the macro with `macro name(...) => template` or fix the name.
+Macros are local to the module that defines them.
+"#
+        }
+        "E0013" => {
+            r#"E0013: No matching macro rule
+
+None of the macro's rules accept the invocation's arguments, or an
+argument can't go where the template puts it.
+
+Example:
+  macro swap($x: ident, $y: ident) => { ... }
+  swap!(a, 2)   # `2` is not an identifier
+
+Fix: Pass arguments that one of the rules accepts. A `$name: ident`
+parameter takes only a plain name; a trailing `$rest...` takes any number
+of arguments.
+"#
+        }
+        "E0014" => {
+            r#"E0014: Macro recursion limit
+
+Expanding an invocation produced macro invocations nested more than 64
+deep, usually because a recursive macro has no rule that stops.
+
+Example:
+  macro forever($x) => forever!($x)
+
+Fix: Give recursive macros a base-case rule that doesn't invoke the macro,
+and make each step pass fewer arguments.
+"#
+        }
+        "E0015" => {
+            r#"E0015: Duplicate macro
+
+Two `macro` items in a module have the same name.
+
+Fix: Merge their rules into one definition or rename one of them.
+"#
+        }
+
+        // Type errors
+        "E1001" => {
+            r#"E1001: Type mismatch
+
+The type of an expression doesn't match what was expected.
+
+Example:
+  fn add(a: Int, b: Int) -> Int {
+    "hello"  # expected Int, got Text
+  }
+
+Fix: Ensure the expression has the expected type. The compiler often
+suggests the correct type in the error message.
+"#
+        }
+        "E1002" => {
+            r#"E1002: Unknown identifier
+
+A name was used that hasn't been defined in the current scope.
+
+Example:
+  fn foo() -> Int {
+    bar  # 'bar' is not defined
+  }
+
+Fix: Define the variable before use, check for typos, or import the
+necessary module. The compiler may suggest similar names.
+
+This diagnostic often includes an auto-fix suggestion that can be
+applied with `astra fix`.
+"#
+        }
+        "E1003" => {
+            r#"E1003: Missing type annotation
+
+A type annotation is required but was not provided.
+
+Fix: Add an explicit type annotation where the compiler indicates.
+"#
+        }
+        "E1004" => {
+            r#"E1004: Non-exhaustive match
+
+A `match` expression doesn't cover all possible cases.
+
+Example:
+  match opt {
+    Some(x) => x
+    # missing: None => ...
+  }
+
+Fix: Add the missing patterns. The compiler lists which cases are
+missing. This diagnostic may include an auto-fix suggestion.
+"#
+        }
+        "E1005" => {
+            r#"E1005: Duplicate field
+
+A record type or literal has the same field name twice.
+
+Fix: Remove or rename the duplicate field.
+"#
+        }
+        "E1006" => {
+            r#"E1006: Unknown field
+
+A field name was used that doesn't exist in the record type.
+
+Fix: Check the field name for typos and verify it exists in the type definition.
+When exactly one field is close to the name used, `astra fix` renames it.
+"#
+        }
+        "E1007" => {
+            r#"E1007: Wrong argument count
+
+A function was called with the wrong number of arguments.
+
+Example:
+  fn add(a: Int, b: Int) -> Int { a + b }
+  add(1)  # expected 2 args, got 1
+
+Fix: Provide the correct number of arguments.
+"#
+        }
+        "E1008" => {
+            r#"E1008: Cannot infer type
+
+The compiler cannot determine the type of an expression.
+
+Fix: Add an explicit type annotation to help the compiler.
+"#
+        }
+        "E1009" => {
+            r#"E1009: Recursive type
+
+A type definition is recursive in a way that creates an infinite type.
+
+Fix: Use an enum with a base case to break the recursion (e.g., a linked list).
+"#
+        }
+        "E1010" => {
+            r#"E1010: Invalid type application
+
+Type arguments were applied to a type that doesn't accept them.
+
+Fix: Remove the type arguments or check the type definition.
+"#
+        }
+        "E1011" => {
+            r#"E1011: Duplicate type
+
+A type with this name is already defined.
+
+Fix: Rename one of the type definitions.
+"#
+        }
+        "E1012" => {
+            r#"E1012: Unknown type
+
+A type name was used that hasn't been defined.
+
+Fix: Define the type, check for typos, or import the necessary module.
+"#
+        }
+        "E1013" => {
+            r#"E1013: Expected function
+
+A non-function value was called as if it were a function.
+
+Fix: Ensure the expression being called is actually a function.
+"#
+        }
+        "E1014" => {
+            r#"E1014: Expected record
+
+An expression was used in a record context but isn't a record type.
+
+Fix: Ensure the expression is a record type with the expected fields.
+"#
+        }
+        "E1015" => {
+            r#"E1015: Expected enum
+
+An expression was used in an enum context but isn't an enum type.
+
+Fix: Ensure the expression is an enum type with the expected variants.
+"#
+        }
+        "E1016" => {
+            r#"E1016: Trait constraint not satisfied
+
+A generic function requires a type to implement a trait, but the
+concrete type used at the call site doesn't.
+
+Example:
+  fn sort[T: Ord](items: List[T]) -> List[T] { ... }
+  sort(["a", "b"])  # Text doesn't implement Ord
+
+Fix: Use a type that implements the required trait, or add an
+`impl TraitName for YourType` block.
+"#
+        }
+        "E1017" => {
+            r#"E1017: Assignment to immutable binding
+
+Only bindings declared with `let mut` can be assigned to. Plain `let`
+bindings, parameters, and names bound by patterns never change.
+
+Example:
+  let total = 0
+  total = total + 1  # `total` is not `mut`
+
+Fix: Declare the binding with `let mut`. For a parameter or pattern
+binding, copy it into a `let mut` binding first.
+"#
+        }
+
+        "E1018" => {
+            r#"E1018: Unknown method
+
+A method was called on a built-in effect or namespace that doesn't have
+it, such as `Console.printn` or `Duration.secs`.
+
+Example:
+  Console.printline("hi")  # did you mean `println`?
+
+Fix: Use one of the effect's methods. When one name is clearly meant,
+the diagnostic carries a rename that `astra fix` applies.
+"#
+        }
+
+        // Effect errors
+        "E2001" => {
+            r#"E2001: Effect not declared
+
+A function uses an effect that isn't listed in its `effects(...)` clause.
+
+Example:
+  fn greet() {        # missing effects(Console)
+    println("hello")  # uses Console effect
+  }
+
+Fix: Add the missing effect to the function's effects clause.
+This diagnostic includes an auto-fix suggestion.
+"#
+        }
+        "E2002" => {
+            r#"E2002: Unknown effect
+
+An effect name was used that doesn't exist.
+
+Fix: Check the effect name. Built-in effects: Console, Fs, Net, Clock, Rand, Env, Crypto, Serve, Db,
+Progress, Metrics.
+"#
+        }
+        "E2003" => {
+            r#"E2003: Capability not available
+
+A function requires an effect capability that isn't provided at runtime.
+
+Fix: Ensure the capability is provided when running the program, or mock
+it in test contexts with `using effects(EffectName = ...)`.
+"#
+        }
+        "E2004" => {
+            r#"E2004: Effectful call in pure context
+
+An effectful function was called from a function that doesn't declare effects.
+
+Fix: Add the necessary effects to the calling function's signature.
+"#
+        }
+        "E2005" => {
+            r#"E2005: Effect mismatch
+
+The effects used by a function don't match its declaration.
+
+Fix: Update the effects clause to match actual usage.
+"#
+        }
+        "E2006" => {
+            r#"E2006: Effect not mockable
+
+An effect was used in a test's `using effects(...)` clause that can't be mocked.
+
+Fix: Use the correct mock constructor (e.g., Clock.fixed(100), Rand.seeded(42)).
+"#
+        }
+        "E2007" => {
+            r#"E2007: Invalid capability injection
+
+A capability was injected incorrectly in a `using effects(...)` clause.
+
+Fix: Check the syntax and use the correct constructor.
+"#
+        }
+
+        // Contract errors
+        "E3001" => {
+            r#"E3001: Precondition violation
+
+A function's `requires` contract was violated at call time.
+
+Example:
+  fn divide(a: Int, b: Int) -> Int
+    requires b != 0
+  { a / b }
+
+  divide(10, 0)  # E3001: requires b != 0
+
+Fix: Ensure the arguments satisfy the precondition before calling.
+"#
+        }
+        "E3002" => {
+            r#"E3002: Postcondition violation
+
+A function's `ensures` contract was violated on return.
+
+Fix: The function's implementation doesn't satisfy its contract. Fix the
+implementation to ensure the return value meets the postcondition.
+"#
+        }
+        "E3003" => {
+            r#"E3003: Invariant violation
+
+A type's invariant was violated during construction.
+
+Example:
+  type Positive = Int invariant self > 0
+  let p: Positive = -5  # E3003: invariant self > 0 violated
+
+Fix: Ensure the value satisfies the type's invariant.
+"#
+        }
+        "E3004" => {
+            r#"E3004: Invalid contract expression
+
+A contract expression (requires/ensures/invariant) is malformed.
+
+Fix: Ensure the contract is a valid boolean expression.
+"#
+        }
+        "E3005" => {
+            r#"E3005: Contract binding unavailable
+
+A contract references a variable that isn't in scope, such as a trait
+method clause inherited by an impl with a different number of parameters.
+
+Fix: Only reference function parameters in `requires`, and `result` plus
+parameters in `ensures`. Give implementations of a trait method with a
+contract the same parameters as the trait declares.
+"#
+        }
+        "E3006" => {
+            r#"E3006: Precondition always violated
+
+The checker can tell from the arguments of a call that one of the callee's
+`requires` clauses is false, so the call would fail every time it runs.
+Literals, immutable `let` bindings, the caller's own `requires`, and the
+conditions of enclosing `if`s and early returns are taken into account.
+
+Fix: Pass an argument that satisfies the precondition, or check it first,
+e.g. `if b != 0 { divide(a, b) } else { 0 }`.
+"#
+        }
+
+        // Runtime errors
+        "E4001" => {
+            r#"E4001: Division by zero
+
+An integer or float division by zero was attempted.
+
+Fix: Check that the divisor is non-zero before dividing.
+Use a `requires` contract to enforce this statically.
+"#
+        }
+        "E4002" => {
+            r#"E4002: Index out of bounds
+
+A list or string was accessed with an index outside its valid range.
+
+Fix: Ensure the index is within bounds (0 to len-1).
+"#
+        }
+        "E4003" => {
+            r#"E4003: Contract violation
+
+A contract check failed at runtime (general).
+
+Fix: See E3001-E3003 for specific contract violation types.
+"#
+        }
+        "E4004" => {
+            r#"E4004: Resource limit exceeded
+
+A resource limit (memory, recursion depth, etc.) was exceeded.
+
+Fix: Reduce the size of the computation or optimize the algorithm.
+"#
+        }
+        "E4005" => {
+            r#"E4005: Capability denied
+
+An effect capability was requested but not available.
+
+Fix: Ensure the required capability is provided. When running with
+`astra run`, all capabilities are available. In tests, mock them with
+`using effects(...)`.
+"#
+        }
+        "E4006" => {
+            r#"E4006: Integer overflow
+
+An integer operation overflowed the 64-bit range, or a Decimal result
+needed more than 96 bits or 28 digits after the point.
+
+Fix: Use smaller values or check for overflow before the operation.
+"#
+        }
+        "E4007" => {
+            r#"E4007: No pattern matched
+
+A `match` expression received a value that none of its arms match.
+
+Fix: Add an arm for the missing case, or a wildcard `_` arm at the end.
+"#
+        }
+        "E4008" => {
+            r#"E4008: Assertion failed
+
+An `assert` expression evaluated to false.
+
+Example:
+  assert(x > 0, "x must be positive")
+
+Fix: Ensure the asserted condition holds, or fix the logic that
+produces the incorrect value.
+"#
+        }
+        "E4022" => {
+            r#"E4022: Evaluation limit exceeded
+
+A program run under evaluation limits, such as an input from
+`astra fuzz`, evaluated more expressions or nested more calls than
+the limits allow. The message names the limit: `evaluation steps`
+or `call depth`.
+
+Fix: Bound the loop or recursion that runs away.
+"#
+        }
+        "E4023" => {
+            r#"E4023: Stopped by the debugger
+
+The program was ended from `astra debug` with `quit`, or by a
+debug adapter client that disconnected. The debugger reports this
+as the end of the session, not as a failure.
+"#
+        }
+        "E4024" => {
+            r#"E4024: Text index out of bounds
+
+A Text method was given an index below zero or past the end of the
+text. Text indices count characters, not bytes, and run from 0 to
+`len()`.
+
+Example:
+  "héllo".substring(2, 9)  # the text has only 5 characters
+
+Fix: Clamp the index to `0..=text.len()`, or use `char_at`, which
+returns None instead of failing.
+"#
+        }
+        "E4025" => {
+            r#"E4025: Invalid text range
+
+A Text range was given a start index after its end index.
+
+Example:
+  "hello".substring(4, 1)
+
+Fix: Pass the smaller index first.
+"#
+        }
+        "E4026" => {
+            r#"E4026: Invalid base64
+
+`base64_decode` was given text that is not standard base64. This
+code is returned in the `code` field of the Err, along with the
+character `index` of the first problem; it does not stop the program.
+
+Example:
+  base64_decode("aGV!bG8=")  # `!` is not in the base64 alphabet
+
+Fix: Check that the text uses the standard alphabet (A-Z, a-z, 0-9,
+`+`, `/`) with at most two `=` at the end. URL-safe base64 uses `-`
+and `_`; replace them with `+` and `/` first.
+"#
+        }
+        "E4027" => {
+            r#"E4027: Invalid hex
+
+`hex_decode` was given text with a character that is not a hex digit,
+or an odd number of digits. The code is returned in the `code` field
+of the Err, along with the character `index` of the first problem.
+
+Example:
+  hex_decode("abc")  # three digits cannot make whole bytes
+
+Fix: Strip separators such as spaces or `0x` prefixes, and make sure
+every byte has two digits.
+"#
+        }
+        "E4028" => {
+            r#"E4028: Stack overflow
+
+Function calls nested deeper than the call depth limit (1000 by
+default). The program stops with a stack trace instead of crashing.
+
+Fix: Use tail recursion (the compiler optimizes tail-recursive calls
+automatically) or convert to an iterative approach. If the depth is
+expected, raise the limit with `--max-call-depth`.
+"#
+        }
+        "E4029" => {
+            r#"E4029: Entry function not defined
+
+The function a program starts from is missing or takes parameters:
+`main` for `astra run <file>`, or the `main` of the `[[bin]]` target
+in astra.toml.
+
+Example:
+  [[bin]]
+  name = "migrate"
+  path = "src/tools.astra"
+  main = "migrat"  # no function by this name
+
+Fix: Define the function without parameters, or correct the name in
+the manifest.
+"#
+        }
+        "E4030" => {
+            r#"E4030: Budget exceeded
+
+The run went over a budget from the `[limits]` section of astra.toml:
+`max_steps`, `max_memory`, or `max_effect_calls.<effect>`. The
+message names the limit and its value.
+
+Example:
+  [limits]
+  max_effect_calls.net = 10  # the 11th Net call stops the run
+
+Fix: Reduce the work the program does, or raise the limit in the
+manifest.
+"#
+        }
+        "E4031" => {
+            r#"E4031: Assignment to immutable binding
+
+A program run without the type checker assigned to a binding that
+is not declared with `let mut`: a plain `let`, a parameter, or a
+name bound by a pattern. The checker reports the same mistake as
+E1017.
+
+Fix: Declare the binding with `let mut`.
+"#
+        }
+
+        // Warnings
+        "W0001" => {
+            r#"W0001: Unused variable
+
+A variable was defined but never used.
+
+Example:
+  let x = 42  # x is never used
+
+Fix: Remove the variable, or prefix its name with `_` to indicate
+it's intentionally unused.
+
+This warning includes an auto-fix suggestion (`astra fix`).
+"#
+        }
+        "W0002" => {
+            r#"W0002: Unused import
+
+An import statement brings a name into scope that is never used.
+
+Fix: Remove the unused import.
+
+This warning includes an auto-fix suggestion (`astra fix`).
+"#
+        }
+        "W0003" => {
+            r#"W0003: Unreachable code
+
+Code after a `return` statement can never be executed.
+
+Example:
+  fn foo() -> Int {
+    return 42
+    let x = 10  # unreachable
+  }
+
+Fix: Remove the unreachable code or restructure the control flow.
+"#
+        }
+        "W0004" => {
+            r#"W0004: Deprecated
+
+The code uses a function, type, or enum marked `@deprecated`, in this
+module or an imported one. The attribute's message is shown as a note.
+
+Example:
+  @deprecated("use area instead")
+  fn old_area(r: Int) -> Int { r * r }
+
+  fn main() -> Int { old_area(2) }   # `old_area` is deprecated
+
+Fix: Use the recommended replacement, or add `@allow(deprecated)` to
+the using item while migrating.
+"#
+        }
+        "W0005" => {
+            r#"W0005: Wildcard match
+
+A match expression uses a wildcard `_` pattern. While valid, this may
+hide missing cases when new variants are added to an enum.
+
+Fix: Consider matching all variants explicitly for better exhaustiveness.
+"#
+        }
+        "W0006" => {
+            r#"W0006: Shadowed binding
+
+A new variable shadows an existing binding with the same name.
+
+Fix: Rename the inner variable to avoid confusion, or prefix the
+outer one with `_` if it's intentionally replaced.
+"#
+        }
+        "W0007" => {
+            r#"W0007: Redundant type annotation
+
+A type annotation is provided but matches the inferred type exactly.
+
+Fix: Remove the type annotation to reduce visual noise, or keep it
+for documentation purposes.
+"#
+        }
+        "W0008" => {
+            r#"W0008: Unused function
+
+A private function is defined but never called within its module.
+
+Example:
+  fn unused_helper() -> Int {
+    42
+  }
+
+  fn main() -> Int {
+    0  # unused_helper is never called
+  }
+
+Fix: Remove the function, prefix its name with `_` to indicate it's
+intentionally unused, or make it `public` if it's part of the module's API.
+"#
+        }
+        "W0009" => {
+            r#"W0009: Unused suppression
+
+An `@allow(...)` attribute names a lint that is not reported anywhere
+in the attributed item or statement, or names an unknown lint.
+
+Example:
+  fn main() -> Int {
+    @allow(unused_variable)
+    let x = 1
+    x  # x is used, so the suppression does nothing
+  }
+
+Fix: Remove the stale suppression so that future problems at this
+location are reported again.
+"#
+        }
+        "W0010" => {
+            r#"W0010: dbg call left in code
+
+A `dbg(expr)` call prints the expression, its value, and its location
+to stderr and returns the value unchanged. It is meant for temporary
+debugging and should not be committed.
+
+Example:
+  fn area(w: Int, h: Int) -> Int {
+    dbg(w) * h  # prints "[src/main.astra:2:9] w = 3"
+  }
+
+Fix: Remove the `dbg(...)` wrapper, keeping its argument.
+"#
+        }
+        "W0012" => {
+            r#"W0012: Ref escapes pure function
+
+A function without effects that returns a `Ref` hands its callers a
+mutable cell. They can change state through it that no effect declares,
+so the function's signature no longer tells the whole story.
+
+Example:
+  fn counter() -> Ref[Int] {
+    Ref.new(0)  # callers mutate the cell freely
+  }
+
+Fix: Return the value itself, or keep the Ref inside a function that
+declares effects.
+"#
+        }
+        // Typed holes
+        "H0001" => {
+            r#"H0001: Typed hole
+
+`???` marks an expression still to be written. The checker reports the
+pub(super) type the surrounding code expects, the local bindings of that type, and
+the enclosing function's contract. Holes are not errors.
+
+Example:
+  fn clamp(x: Int, lo: Int, hi: Int) -> Int {
+    if x < lo { ??? } else { x }  # hole of type Int; fits x, lo, hi
+  }
+
+Fix: Replace the hole with an expression of the expected type. Run
+`astra check --holes` to list every hole with its context.
+"#
+        }
+        "E9001" | "E9002" | "E9003" => {
+            r#"E9001-E9003: Formatter instability
+
+`astra fmt --verify` formats each file twice and reports:
+  E9001  a second pass changes already formatted output
+  E9002  the formatted file parses to a different program
+  E9003  the formatted output does not parse
+
+These are formatter bugs, not problems in your code.
+
+Fix: Report the bug with the source file. Until it is fixed, leave the
+file unformatted or rewrite the affected item so it formats stably.
+"#
+        }
+        _ => return None,
+    };
+    Some(explanation.to_string())
+}
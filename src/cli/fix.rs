@@ -0,0 +1,152 @@
+//! `astra fix`: applying the suggested fixes of diagnostics
+
+use super::*;
+
+pub(super) fn run_fix(
+    paths: &[PathBuf],
+    only: Option<&str>,
+    dry_run: bool,
+    unsafe_fixes: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Parse --only filter into a set of codes
+    let code_filter: Option<HashSet<&str>> = only.map(|codes| codes.split(',').collect());
+
+    // Collect all .astra files
+    let mut astra_files = Vec::new();
+    for path in paths {
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "astra") {
+            astra_files.push(path.clone());
+        } else if path.is_dir() {
+            for entry in walkdir(path)? {
+                if entry.extension().is_some_and(|ext| ext == "astra") {
+                    astra_files.push(entry);
+                }
+            }
+        }
+    }
+
+    let mut total_fixes = 0;
+    let mut files_fixed = 0;
+    let mut skipped_unsafe = 0;
+
+    for file_path in &astra_files {
+        let source = std::fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read {:?}: {}", file_path, e))?;
+
+        // Parse and check
+        let source_file = SourceFile::new(file_path.clone(), source.clone());
+        let lexer = Lexer::new(&source_file);
+        let mut parser = AstraParser::new(lexer, source_file.clone());
+        let module = match parser.parse_module() {
+            Ok(m) => m,
+            Err(_) => continue, // Skip files with parse errors (can't fix syntax errors)
+        };
+
+        let mut checker = crate::typechecker::TypeChecker::new();
+        configure_checker_search_paths(&mut checker, file_path.parent());
+        let _type_result = checker.check_module(&module);
+        let all_diags = checker.diagnostics();
+
+        // Collect all edits from suggestions, grouped by file
+        let mut edits: Vec<crate::diagnostics::Edit> = Vec::new();
+        for diag in all_diags.diagnostics() {
+            // Apply code filter if specified
+            if let Some(ref filter) = code_filter {
+                if !filter.contains(diag.code.as_str()) {
+                    continue;
+                }
+            }
+
+            for suggestion in &diag.suggestions {
+                if suggestion.edits.is_empty() {
+                    continue;
+                }
+                // Only machine-applicable fixes are applied unless opted in
+                if !suggestion.is_machine_applicable() && !unsafe_fixes {
+                    skipped_unsafe += 1;
+                    continue;
+                }
+                for edit in &suggestion.edits {
+                    edits.push(edit.clone());
+                }
+            }
+        }
+
+        if edits.is_empty() {
+            continue;
+        }
+
+        // Sort edits by start position (descending) so we apply from end to start
+        // This avoids offset invalidation
+        edits.sort_by_key(|e| std::cmp::Reverse(e.span.start));
+
+        // Deduplicate edits at the same span
+        edits.dedup_by(|a, b| a.span.start == b.span.start && a.span.end == b.span.end);
+
+        let fix_count = edits.len();
+        let mut fixed_source = source.clone();
+
+        for edit in &edits {
+            // Apply edit: replace bytes from span.start..span.end with replacement
+            if edit.span.start <= fixed_source.len() && edit.span.end <= fixed_source.len() {
+                fixed_source.replace_range(edit.span.start..edit.span.end, &edit.replacement);
+            }
+        }
+
+        if fixed_source != source {
+            total_fixes += fix_count;
+            files_fixed += 1;
+
+            if dry_run {
+                if json {
+                    println!(
+                        "{{\"file\":{},\"fixes\":{}}}",
+                        serde_json::to_string(&file_path.display().to_string()).unwrap_or_default(),
+                        fix_count
+                    );
+                } else {
+                    println!(
+                        "Would fix {} issue(s) in {:?}",
+                        fix_count,
+                        file_path.display()
+                    );
+                }
+            } else {
+                std::fs::write(file_path, &fixed_source)
+                    .map_err(|e| format!("Failed to write {:?}: {}", file_path, e))?;
+                if json {
+                    println!(
+                        "{{\"file\":{},\"fixes\":{}}}",
+                        serde_json::to_string(&file_path.display().to_string()).unwrap_or_default(),
+                        fix_count
+                    );
+                } else {
+                    println!("Fixed {} issue(s) in {:?}", fix_count, file_path.display());
+                }
+            }
+        }
+    }
+
+    if dry_run {
+        println!(
+            "\nDry run: {} fix(es) would be applied across {} file(s)",
+            total_fixes, files_fixed
+        );
+    } else if total_fixes > 0 {
+        println!(
+            "\nApplied {} fix(es) across {} file(s)",
+            total_fixes, files_fixed
+        );
+    } else {
+        println!("No auto-fixable issues found");
+    }
+    if skipped_unsafe > 0 {
+        println!(
+            "{} unsafe fix(es) skipped; re-run with --unsafe-fixes to apply them",
+            skipped_unsafe
+        );
+    }
+
+    Ok(())
+}
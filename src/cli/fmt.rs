@@ -0,0 +1,249 @@
+//! `astra fmt`: formatting files, `--check`, and `--verify`
+
+use super::*;
+
+/// `astra fmt --check` exit code when some files are not formatted
+pub(super) const FMT_EXIT_UNFORMATTED: i32 = 1;
+/// `astra fmt` exit code when a file could not be read or parsed
+pub(super) const FMT_EXIT_ERROR: i32 = 2;
+
+pub(super) fn run_fmt(
+    paths: &[PathBuf],
+    check: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut envelope = Envelope::new("fmt");
+    let mut files_formatted = 0;
+    let mut files_changed = 0;
+    let mut files_failed = 0;
+    let mut changed_files = Vec::new();
+
+    for path in &collect_astra_files(paths)? {
+        let result = match fmt_file(path, check, json) {
+            Ok(result) => result,
+            Err(e) if json => {
+                envelope.error = Some(e.to_string());
+                envelope.emit_with_code(Status::Error, FMT_EXIT_ERROR);
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(FMT_EXIT_ERROR);
+            }
+        };
+        match result {
+            FmtResult::Unchanged => files_formatted += 1,
+            FmtResult::Changed { hunks } => {
+                files_formatted += 1;
+                files_changed += 1;
+                changed_files.push(serde_json::json!({
+                    "path": path.display().to_string(),
+                    "hunks": hunks,
+                }));
+                if !check {
+                    envelope.artifact("formatted", path.display());
+                }
+            }
+            FmtResult::Error(diagnostics) => {
+                files_failed += 1;
+                envelope.diagnostics.extend(diagnostics);
+            }
+        }
+    }
+
+    let exit_code = if files_failed > 0 {
+        FMT_EXIT_ERROR
+    } else if check && files_changed > 0 {
+        FMT_EXIT_UNFORMATTED
+    } else {
+        0
+    };
+    if json {
+        envelope.data = serde_json::json!({
+            "checked": files_formatted,
+            "changed": files_changed,
+            "errors": files_failed,
+            "files": changed_files,
+        });
+        let status = match exit_code {
+            0 => Status::Success,
+            FMT_EXIT_UNFORMATTED => Status::Failure,
+            _ => Status::Error,
+        };
+        envelope.emit_with_code(status, exit_code);
+        return Ok(());
+    }
+
+    if check {
+        if files_changed > 0 {
+            println!(
+                "{} file(s) would be reformatted ({} checked)",
+                files_changed, files_formatted
+            );
+        } else {
+            println!("{} file(s) already formatted", files_formatted);
+        }
+    } else {
+        println!(
+            "Formatted {} file(s) ({} changed)",
+            files_formatted, files_changed
+        );
+    }
+    if files_failed > 0 {
+        eprintln!("{} file(s) could not be parsed", files_failed);
+    }
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
+
+/// Run `astra fmt --verify`: check that formatting is stable for each file
+pub(super) fn run_fmt_verify(
+    paths: &[PathBuf],
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut envelope = Envelope::new("fmt");
+    let mut files_checked = 0;
+    let mut files_failed = 0;
+    let mut unstable_files = Vec::new();
+
+    for path in &collect_astra_files(paths)? {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        let diagnostics = match crate::formatter::verify(path, &source) {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => {
+                files_failed += 1;
+                if !json {
+                    eprintln!(
+                        "Parse error in {:?}:\n{}",
+                        path,
+                        e.render(&source, render_options())
+                    );
+                }
+                envelope.diagnostics.extend(e.diagnostics().to_vec());
+                continue;
+            }
+        };
+        files_checked += 1;
+        if diagnostics.is_empty() {
+            continue;
+        }
+        unstable_files.push(serde_json::json!({
+            "path": path.display().to_string(),
+            "codes": diagnostics.iter().map(|d| d.code.clone()).collect::<Vec<_>>(),
+        }));
+        if !json {
+            for d in &diagnostics {
+                eprintln!("{}", d.render(&source, render_options()));
+            }
+        }
+        envelope.diagnostics.extend(diagnostics);
+    }
+
+    let exit_code = if files_failed > 0 {
+        FMT_EXIT_ERROR
+    } else if !unstable_files.is_empty() {
+        FMT_EXIT_UNFORMATTED
+    } else {
+        0
+    };
+    if json {
+        envelope.data = serde_json::json!({
+            "checked": files_checked,
+            "unstable": unstable_files.len(),
+            "errors": files_failed,
+            "files": unstable_files,
+        });
+        let status = match exit_code {
+            0 => Status::Success,
+            FMT_EXIT_UNFORMATTED => Status::Failure,
+            _ => Status::Error,
+        };
+        envelope.emit_with_code(status, exit_code);
+        return Ok(());
+    }
+
+    if unstable_files.is_empty() {
+        println!("Formatting is stable for {} file(s)", files_checked);
+    } else {
+        println!(
+            "Formatting is unstable for {} of {} file(s)",
+            unstable_files.len(),
+            files_checked
+        );
+    }
+    if files_failed > 0 {
+        eprintln!("{} file(s) could not be parsed", files_failed);
+    }
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
+
+pub(super) enum FmtResult {
+    Unchanged,
+    /// The formatted text differs from the source in `hunks` places
+    Changed {
+        hunks: usize,
+    },
+    /// The file could not be parsed
+    Error(Vec<Diagnostic>),
+}
+
+pub(super) fn fmt_file(
+    path: &PathBuf,
+    check: bool,
+    json: bool,
+) -> Result<FmtResult, Box<dyn std::error::Error>> {
+    let source =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+
+    let source_file = SourceFile::new(path.clone(), source.clone());
+    let lexer = Lexer::new(&source_file);
+    let mut parser = AstraParser::new(lexer, source_file.clone());
+
+    let module = match parser.parse_module() {
+        Ok(m) => m,
+        Err(e) => {
+            if !json {
+                eprintln!(
+                    "Parse error in {:?}:\n{}",
+                    path,
+                    e.render(&source, render_options())
+                );
+            }
+            return Ok(FmtResult::Error(e.diagnostics().to_vec()));
+        }
+    };
+
+    let mut formatter = crate::formatter::Formatter::new();
+    let formatted = formatter.format_module(&module);
+
+    if formatted == source {
+        return Ok(FmtResult::Unchanged);
+    }
+    let diff = crate::text_diff::TextDiff::new(&source, &formatted);
+    // Changes in trailing newlines alone do not show up as line hunks
+    let hunks = diff.hunks.len().max(1);
+
+    if check {
+        if !json {
+            if diff.is_empty() {
+                println!("{}: trailing newlines differ", path.display());
+            } else {
+                let (old, new) = diff_labels(path);
+                print!("{}", diff.unified(&old, &new));
+            }
+        }
+    } else {
+        std::fs::write(path, &formatted)
+            .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+        if !json {
+            println!("Formatted: {:?}", path);
+        }
+    }
+    Ok(FmtResult::Changed { hunks })
+}
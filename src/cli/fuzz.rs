@@ -0,0 +1,93 @@
+//! `astra fuzz`: fuzzing the toolchain itself
+
+use super::*;
+
+pub(super) fn run_fuzz(
+    target: FuzzTarget,
+    seed: Option<u64>,
+    iterations: u64,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut envelope = Envelope::new("fuzz");
+    let seed = seed.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    });
+
+    // Mutate the project's own sources as well as the built-in programs
+    let current_dir = std::env::current_dir()?;
+    let corpus: Vec<String> = walkdir(&current_dir)?
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|ext| ext == "astra"))
+        .filter_map(|p| std::fs::read_to_string(p).ok())
+        .collect();
+
+    if !json {
+        println!(
+            "Fuzzing the {} with seed {} ({} inputs)",
+            target.name(),
+            seed,
+            iterations
+        );
+    }
+    // Deeply nested inputs need more stack than the main thread has
+    let report = std::thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(move || crate::fuzz::fuzz(target, seed, iterations, &corpus))?
+        .join()
+        .map_err(|_| "fuzzer thread panicked")?;
+
+    let Some(crash) = report.crash else {
+        if json {
+            envelope.data = serde_json::json!({
+                "target": target.name(),
+                "seed": seed,
+                "cases": report.cases,
+            });
+            envelope.emit(Status::Success);
+        } else {
+            println!("No failures in {} inputs", report.cases);
+        }
+        return Ok(());
+    };
+
+    // Keep the minimized input around to reproduce the failure
+    let crash_dir = current_dir.join("fuzz-crashes");
+    std::fs::create_dir_all(&crash_dir)?;
+    let crash_path = crash_dir.join(format!(
+        "{}-{}-{}.astra",
+        target.name(),
+        seed,
+        crash.iteration
+    ));
+    std::fs::write(&crash_path, &crash.minimized)?;
+
+    if json {
+        envelope.artifact("crash", crash_path.display());
+        envelope.data = serde_json::json!({
+            "target": target.name(),
+            "seed": seed,
+            "cases": report.cases,
+            "crash": {
+                "iteration": crash.iteration,
+                "failure": crash.failure.to_string(),
+                "input": crash.input,
+                "minimized": crash.minimized,
+            },
+        });
+        envelope.emit(Status::Failure);
+    } else {
+        eprintln!(
+            "FAIL: input {} {}\n\nMinimized input ({} of {} bytes), written to {}:\n{}",
+            crash.iteration,
+            crash.failure,
+            crash.minimized.len(),
+            crash.input.len(),
+            crash_path.display(),
+            crash.minimized
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
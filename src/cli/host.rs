@@ -0,0 +1,396 @@
+//! Real capabilities, backed by the terminal, filesystem, network, and clock
+
+use super::*;
+
+/// All real capabilities, as provided by `astra run`
+pub(super) fn real_capabilities(args: Vec<String>) -> Capabilities {
+    Capabilities {
+        console: Some(Box::new(RealConsole)),
+        env: Some(Box::new(RealEnv::new(args))),
+        fs: Some(Box::new(RealFs)),
+        net: Some(Box::new(RealNet)),
+        clock: Some(Box::new(RealClock)),
+        rand: Some(Box::new(RealRand::new())),
+        crypto: Some(Box::new(StandardCrypto)),
+        serve: None,
+        db: None,
+        progress: Some(Box::new(TerminalProgress::new())),
+        metrics: None,
+    }
+}
+
+/// Progress capability that draws a bar on stderr, redrawn in place on a
+/// terminal and as a line per start and finish otherwise
+pub(super) struct TerminalProgress {
+    tty: bool,
+}
+
+impl TerminalProgress {
+    fn new() -> Self {
+        use std::io::IsTerminal;
+        Self {
+            tty: std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+/// `task [#####---------------] 5/20`, or `task 5` when the total isn't known
+pub(super) fn progress_line(task: &str, done: i64, total: i64) -> String {
+    const WIDTH: i64 = 20;
+    if total <= 0 {
+        return format!("{} {}", task, done);
+    }
+    let filled = (done.clamp(0, total) * WIDTH / total) as usize;
+    format!(
+        "{} [{}{}] {}/{}",
+        task,
+        "#".repeat(filled),
+        "-".repeat(WIDTH as usize - filled),
+        done,
+        total
+    )
+}
+
+impl ProgressCapability for TerminalProgress {
+    fn report(&self, event: &ProgressEvent) {
+        let (line, last) = match event {
+            ProgressEvent::Start { task, total } => (progress_line(task, 0, *total), false),
+            ProgressEvent::Advance { task, done, total } => {
+                if !self.tty {
+                    return;
+                }
+                (progress_line(task, *done, *total), false)
+            }
+            ProgressEvent::Finish { task, done, total } => {
+                (progress_line(task, *done, *total), true)
+            }
+        };
+        if self.tty {
+            eprint!("\r\x1b[K{}{}", line, if last { "\n" } else { "" });
+        } else {
+            eprintln!("{}", line);
+        }
+    }
+}
+
+/// Progress capability for `--json`: a JSON Lines event per change on
+/// stderr, leaving stdout to the program and the envelope
+pub(super) struct JsonProgress;
+
+impl ProgressCapability for JsonProgress {
+    fn report(&self, event: &ProgressEvent) {
+        if let Ok(line) = serde_json::to_string(event) {
+            eprintln!("{}", line);
+        }
+    }
+}
+
+/// Real console capability that prints to stdout
+pub(super) struct RealConsole;
+
+impl ConsoleCapability for RealConsole {
+    fn print(&self, text: &str) {
+        print!("{}", text);
+    }
+
+    fn println(&self, text: &str) {
+        println!("{}", text);
+    }
+
+    fn read_line(&self) -> Option<String> {
+        let mut line = String::new();
+        match std::io::stdin().read_line(&mut line) {
+            Ok(0) => None, // EOF
+            Ok(_) => Some(line.trim_end().to_string()),
+            Err(_) => None,
+        }
+    }
+
+    fn is_tty(&self) -> bool {
+        use std::io::IsTerminal;
+        std::io::stdout().is_terminal()
+    }
+
+    /// On a terminal unless NO_COLOR is set; FORCE_COLOR turns it on anywhere
+    fn color(&self) -> bool {
+        let set = |name| std::env::var_os(name).is_some_and(|v| !v.is_empty());
+        set("FORCE_COLOR") || (!set("NO_COLOR") && self.is_tty())
+    }
+}
+
+/// Real environment capability
+pub(super) struct RealEnv {
+    args: Vec<String>,
+}
+
+impl RealEnv {
+    fn new(args: Vec<String>) -> Self {
+        Self { args }
+    }
+}
+
+impl EnvCapability for RealEnv {
+    fn get(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+
+    fn args(&self) -> Vec<String> {
+        self.args.clone()
+    }
+}
+
+/// Real filesystem capability that performs actual I/O
+pub(super) struct RealFs;
+
+impl FsCapability for RealFs {
+    fn read(&self, path: &str) -> Result<String, String> {
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path, e))
+    }
+
+    fn write(&self, path: &str, content: &str) -> Result<(), String> {
+        std::fs::write(path, content).map_err(|e| format!("Failed to write '{}': {}", path, e))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        std::path::Path::new(path).exists()
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>, String> {
+        std::fs::read(path).map_err(|e| format!("Failed to read '{}': {}", path, e))
+    }
+
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<(), String> {
+        std::fs::write(path, content).map_err(|e| format!("Failed to write '{}': {}", path, e))
+    }
+
+    fn resolve(&self, path: &str) -> String {
+        crate::interpreter::resolve_existing_prefix(path)
+    }
+}
+
+/// Real network capability using ureq for HTTP
+pub(super) struct RealNet;
+
+impl NetCapability for RealNet {
+    fn get(&self, url: &str) -> Result<Value, String> {
+        match ureq::get(url).call() {
+            Ok(response) => {
+                let body = response
+                    .into_string()
+                    .map_err(|e| format!("Failed to read response body: {}", e))?;
+                Ok(Value::Text(body))
+            }
+            Err(e) => Err(format!("HTTP GET failed: {}", e)),
+        }
+    }
+
+    fn post(&self, url: &str, body: &str) -> Result<Value, String> {
+        match ureq::post(url).send_string(body) {
+            Ok(response) => {
+                let body = response
+                    .into_string()
+                    .map_err(|e| format!("Failed to read response body: {}", e))?;
+                Ok(Value::Text(body))
+            }
+            Err(e) => Err(format!("HTTP POST failed: {}", e)),
+        }
+    }
+
+    fn ws_connect(&self, url: &str) -> Result<Box<dyn WebSocketConnection>, String> {
+        let (socket, _) = tungstenite::connect(url)
+            .map_err(|e| format!("WebSocket connection to {} failed: {}", url, e))?;
+        Ok(Box::new(RealWebSocket { socket }))
+    }
+}
+
+/// WebSocket connection over TCP, or TLS for `wss://` URLs
+pub(super) struct RealWebSocket {
+    socket: tungstenite::WebSocket<tungstenite::stream::MaybeTlsStream<std::net::TcpStream>>,
+}
+
+impl RealWebSocket {
+    fn set_read_timeout(&mut self, timeout_ms: Option<u64>) -> std::io::Result<()> {
+        use tungstenite::stream::MaybeTlsStream;
+
+        // A zero timeout means "block" to the OS, so wait at least a millisecond
+        let timeout = timeout_ms.map(|ms| std::time::Duration::from_millis(ms.max(1)));
+        match self.socket.get_mut() {
+            MaybeTlsStream::Plain(stream) => stream.set_read_timeout(timeout),
+            MaybeTlsStream::Rustls(stream) => stream.get_mut().set_read_timeout(timeout),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl WebSocketConnection for RealWebSocket {
+    fn send(&mut self, message: &str) -> Result<(), String> {
+        self.socket
+            .send(tungstenite::Message::text(message))
+            .map_err(|e| format!("WebSocket send failed: {}", e))
+    }
+
+    fn recv(&mut self, timeout_ms: Option<u64>) -> Result<Option<String>, String> {
+        use tungstenite::{Error, Message};
+
+        self.set_read_timeout(timeout_ms)
+            .map_err(|e| format!("WebSocket receive failed: {}", e))?;
+        loop {
+            match self.socket.read() {
+                Ok(Message::Text(text)) => return Ok(Some(text)),
+                Ok(Message::Binary(bytes)) => {
+                    return Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+                }
+                // Pings are answered by tungstenite itself
+                Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_)) => continue,
+                Ok(Message::Close(_)) => {
+                    // Send the close reply tungstenite queued, finishing the handshake
+                    let _ = self.socket.flush();
+                    return Err("connection closed".to_string());
+                }
+                Err(Error::ConnectionClosed | Error::AlreadyClosed) => {
+                    return Err("connection closed".to_string())
+                }
+                Err(Error::Io(e))
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Ok(None)
+                }
+                Err(e) => return Err(format!("WebSocket receive failed: {}", e)),
+            }
+        }
+    }
+
+    fn close(&mut self) -> Result<(), String> {
+        match self.socket.close(None) {
+            Ok(()) | Err(tungstenite::Error::ConnectionClosed) => Ok(()),
+            Err(e) => Err(format!("WebSocket close failed: {}", e)),
+        }
+    }
+}
+
+/// `:8080` listens on every interface; a full `host:port` is used as given
+pub(super) fn listen_address(addr: &str) -> String {
+    match addr.strip_prefix(':') {
+        Some(port) => format!("0.0.0.0:{}", port),
+        None => addr.to_string(),
+    }
+}
+
+/// Real HTTP server for `astra run --serve`, answering one request at a time
+pub(super) struct HttpServe {
+    server: tiny_http::Server,
+    pending: std::cell::RefCell<Option<tiny_http::Request>>,
+}
+
+impl HttpServe {
+    pub(super) fn bind(addr: &str) -> Result<Self, String> {
+        let addr = listen_address(addr);
+        let server = tiny_http::Server::http(&addr)
+            .map_err(|e| format!("Failed to bind to {}: {}", addr, e))?;
+        Ok(Self {
+            server,
+            pending: std::cell::RefCell::new(None),
+        })
+    }
+}
+
+impl ServeCapability for HttpServe {
+    fn next_request(&self) -> Option<HttpRequest> {
+        let mut request = self.server.recv().ok()?;
+        let mut body = String::new();
+        let _ = request.as_reader().read_to_string(&mut body);
+        let http_request = HttpRequest {
+            method: request.method().to_string().to_uppercase(),
+            url: request.url().to_string(),
+            headers: request
+                .headers()
+                .iter()
+                .map(|h| (h.field.to_string(), h.value.as_str().to_string()))
+                .collect(),
+            body,
+        };
+        *self.pending.borrow_mut() = Some(request);
+        Some(http_request)
+    }
+
+    fn respond(&self, response: HttpResponse) {
+        let Some(request) = self.pending.borrow_mut().take() else {
+            return;
+        };
+        let mut reply = tiny_http::Response::from_string(response.body)
+            .with_status_code(tiny_http::StatusCode(response.status));
+        for (name, value) in &response.headers {
+            if let Ok(header) = tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+                reply.add_header(header);
+            }
+        }
+        if let Err(e) = request.respond(reply) {
+            eprintln!("Failed to send response: {}", e);
+        }
+    }
+}
+
+/// Real clock capability using system time
+pub(super) struct RealClock;
+
+impl ClockCapability for RealClock {
+    fn now(&self) -> i64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64
+    }
+
+    fn sleep(&self, millis: u64) {
+        std::thread::sleep(std::time::Duration::from_millis(millis));
+    }
+}
+
+/// Real random capability using system randomness
+pub(super) struct RealRand {
+    seed: std::cell::Cell<u64>,
+}
+
+impl RealRand {
+    fn new() -> Self {
+        // Seed from system time for non-deterministic randomness
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        Self {
+            seed: std::cell::Cell::new(seed),
+        }
+    }
+
+    fn next(&self) -> u64 {
+        let mut x = self.seed.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.seed.set(x);
+        x
+    }
+}
+
+impl RandCapability for RealRand {
+    fn int(&self, min: i64, max: i64) -> i64 {
+        if max <= min {
+            return min;
+        }
+        let range = (max - min + 1) as u64;
+        let r = self.next() % range;
+        min + r as i64
+    }
+
+    fn bool(&self) -> bool {
+        self.next().is_multiple_of(2)
+    }
+
+    fn float(&self) -> f64 {
+        (self.next() as f64) / (u64::MAX as f64)
+    }
+}
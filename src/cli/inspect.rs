@@ -0,0 +1,421 @@
+//! Commands that export what the toolchain sees: `ast`, `tokens`, `diff`, `symbols`, and `graph`
+
+use super::*;
+
+/// Run `astra ast` — print the parsed module as JSON, optionally with inferred types.
+pub(super) fn run_ast(
+    file: &Path,
+    resolve: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut envelope = Envelope::new("ast");
+    let source = std::fs::read_to_string(file)
+        .map_err(|e| format!("Failed to read file {:?}: {}", file, e))?;
+    let module = match crate::parser::parse_source(&source, file) {
+        Ok(module) => module,
+        Err(e) if json => {
+            envelope.diagnostics = e.diagnostics().to_vec();
+            envelope.emit(Status::Failure);
+            return Ok(());
+        }
+        Err(e) => {
+            return Err(format!("Parse error:\n{}", e.render(&source, render_options())).into())
+        }
+    };
+
+    let mut tree = serde_json::to_value(&module)?;
+    if resolve {
+        let mut checker = crate::typechecker::TypeChecker::new();
+        configure_checker_search_paths(&mut checker, file.parent());
+        let _ = checker.check_module(&module);
+        annotate_inferred_types(&mut tree, &checker.expr_type_names());
+        envelope.diagnostics = checker.diagnostics().diagnostics().to_vec();
+    }
+
+    if json {
+        envelope.data = serde_json::json!({
+            "file": file.display().to_string(),
+            "module": tree,
+        });
+        envelope.emit(Status::Success);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&tree)?);
+    }
+    Ok(())
+}
+
+/// Add an `inferred_type` field to every serialized node whose `id` has a type
+pub(super) fn annotate_inferred_types(
+    value: &mut serde_json::Value,
+    types: &HashMap<crate::parser::ast::NodeId, String>,
+) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let ty = map
+                .get("id")
+                .and_then(|id| id.as_u64())
+                .and_then(|id| types.get(&crate::parser::ast::NodeId(id)));
+            if let Some(ty) = ty {
+                map.insert(
+                    "inferred_type".to_string(),
+                    serde_json::Value::from(ty.as_str()),
+                );
+            }
+            for child in map.values_mut() {
+                annotate_inferred_types(child, types);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for child in items {
+                annotate_inferred_types(child, types);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Run `astra tokens` — print every token with its kind, text, span, and leading trivia.
+pub(super) fn run_tokens(file: &Path, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut envelope = Envelope::new("tokens");
+    let source = std::fs::read_to_string(file)
+        .map_err(|e| format!("Failed to read file {:?}: {}", file, e))?;
+    let source_file = SourceFile::new(file.to_path_buf(), source);
+    let tokens = crate::parser::lexer::tokenize_lossless(&source_file);
+
+    if json {
+        let tokens: Vec<serde_json::Value> = tokens
+            .iter()
+            .map(|token| {
+                serde_json::json!({
+                    "kind": token.kind_name(),
+                    "text": token.text,
+                    "span": token.span,
+                    "leading_trivia": token.leading_trivia,
+                })
+            })
+            .collect();
+        envelope.data = serde_json::json!({
+            "file": file.display().to_string(),
+            "tokens": tokens,
+        });
+        envelope.emit(Status::Success);
+        return Ok(());
+    }
+
+    for token in &tokens {
+        let trivia: Vec<String> = token
+            .leading_trivia
+            .iter()
+            .map(|t| format!("{:?} {:?}", t.kind, t.text))
+            .collect();
+        let location = format!("{}:{}", token.span.start_line, token.span.start_col);
+        if trivia.is_empty() {
+            println!("{:<8} {:<14} {:?}", location, token.kind_name(), token.text);
+        } else {
+            println!(
+                "{:<8} {:<14} {:?}  (after {})",
+                location,
+                token.kind_name(),
+                token.text,
+                trivia.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Run `astra tokens --semantic` — classify each span by what it names.
+pub(super) fn run_semantic_tokens(
+    file: &Path,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut envelope = Envelope::new("tokens");
+    let source = std::fs::read_to_string(file)
+        .map_err(|e| format!("Failed to read file {:?}: {}", file, e))?;
+    let module = crate::parser::parse_source(&source, file).ok();
+    let source_file = SourceFile::new(file.to_path_buf(), source.clone());
+    let tokens = crate::semantic_tokens::classify(&source_file, module.as_ref());
+
+    if json {
+        let tokens: Vec<serde_json::Value> = tokens
+            .iter()
+            .map(|token| {
+                serde_json::json!({
+                    "kind": token.kind,
+                    "text": &source[token.span.start..token.span.end],
+                    "span": token.span,
+                    "declaration": token.declaration,
+                })
+            })
+            .collect();
+        envelope.data = serde_json::json!({
+            "file": file.display().to_string(),
+            "parsed": module.is_some(),
+            "tokens": tokens,
+        });
+        envelope.emit(Status::Success);
+        return Ok(());
+    }
+
+    for token in &tokens {
+        let location = format!("{}:{}", token.span.start_line, token.span.start_col);
+        let kind = if token.declaration {
+            format!("{} (decl)", token.kind.name())
+        } else {
+            token.kind.name().to_string()
+        };
+        println!(
+            "{:<8} {:<18} {:?}",
+            location,
+            kind,
+            &source[token.span.start..token.span.end]
+        );
+    }
+    Ok(())
+}
+
+/// Run `astra diff` — compare two versions of a module item by item.
+pub(super) fn run_diff(
+    old: &Path,
+    new: &Path,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::semantic_diff::{diff_modules, ChangeKind};
+
+    let mut envelope = Envelope::new("diff");
+    let parse = |path: &Path| -> Result<crate::parser::ast::Module, Box<dyn std::error::Error>> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read file {:?}: {}", path, e))?;
+        crate::parser::parse_source(&source, path).map_err(|e| {
+            format!(
+                "Parse error in {:?}:\n{}",
+                path,
+                e.render(&source, render_options())
+            )
+            .into()
+        })
+    };
+    let changes = diff_modules(&parse(old)?, &parse(new)?);
+
+    if json {
+        envelope.data = serde_json::json!({
+            "old": old.display().to_string(),
+            "new": new.display().to_string(),
+            "breaking": changes.iter().any(|c| c.breaking),
+            "changes": changes,
+        });
+        envelope.emit(Status::Success);
+        return Ok(());
+    }
+
+    if changes.is_empty() {
+        println!("No semantic changes");
+        return Ok(());
+    }
+    for change in &changes {
+        let item = format!("{} {}", change.item.keyword(), change.name);
+        let marker = if change.breaking { " [breaking]" } else { "" };
+        let summary = match change.kind {
+            ChangeKind::Added => format!("+ {}", item),
+            ChangeKind::Removed => format!("- {}", item),
+            ChangeKind::VariantAdded => format!("+ variant {}", change.name),
+            ChangeKind::VariantRemoved => format!("- variant {}", change.name),
+            ChangeKind::EffectsAdded => {
+                format!("~ {}: effects added: {}", item, change.effects.join(", "))
+            }
+            ChangeKind::EffectsRemoved => {
+                format!("~ {}: effects removed: {}", item, change.effects.join(", "))
+            }
+            ChangeKind::VisibilityChanged => format!("~ {}: visibility changed", item),
+            ChangeKind::SignatureChanged => format!("~ {}: signature changed", item),
+            ChangeKind::ContractChanged => format!("~ {}: contract changed", item),
+            ChangeKind::BodyChanged => format!("~ {}: body changed", item),
+            ChangeKind::DefinitionChanged => format!("~ {}: definition changed", item),
+        };
+        println!("{}{}", summary, marker);
+        if !matches!(
+            change.kind,
+            ChangeKind::EffectsAdded | ChangeKind::EffectsRemoved
+        ) {
+            for (sign, text) in [("-", &change.before), ("+", &change.after)] {
+                for line in text.iter().flat_map(|t| t.lines()) {
+                    println!("    {} {}", sign, line);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run `astra symbols` — list the top-level definitions of each file.
+pub(super) fn run_symbols(
+    paths: &[PathBuf],
+    workspace: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut envelope = Envelope::new("symbols");
+    let roots = if workspace {
+        vec![crate::cache::find_project_root(&std::env::current_dir()?)]
+    } else {
+        paths.to_vec()
+    };
+
+    let mut files = collect_astra_files(&roots)?;
+    files.sort();
+    let mut symbols = Vec::new();
+    for file in files {
+        let source = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read {:?}: {}", file, e))?;
+        match crate::parser::parse_source(&source, &file) {
+            Ok(module) => symbols.extend(crate::symbols::collect_symbols(&module)),
+            Err(e) if json => envelope.diagnostics.extend(e.diagnostics().iter().cloned()),
+            Err(e) => eprintln!(
+                "Parse error in {:?}:\n{}",
+                file,
+                e.render(&source, render_options())
+            ),
+        }
+    }
+
+    if json {
+        envelope.data = serde_json::json!({ "symbols": symbols });
+        envelope.emit(Status::Success);
+        return Ok(());
+    }
+    for symbol in &symbols {
+        println!(
+            "{}:{}:{}  {}",
+            symbol.span.file.display(),
+            symbol.span.start_line,
+            symbol.span.start_col,
+            symbol.signature
+        );
+    }
+    Ok(())
+}
+
+pub(super) fn run_graph(
+    paths: &[PathBuf],
+    dot: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut envelope = Envelope::new("graph");
+    let mut files = collect_astra_files(paths)?;
+    files.sort();
+    let mut graph = crate::graph::CallGraph::new();
+    for file in files {
+        let source = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read {:?}: {}", file, e))?;
+        match crate::parser::parse_source(&source, &file) {
+            Ok(module) => graph.add_module(&module),
+            Err(e) if json => envelope.diagnostics.extend(e.diagnostics().iter().cloned()),
+            Err(e) => eprintln!(
+                "Parse error in {:?}:\n{}",
+                file,
+                e.render(&source, render_options())
+            ),
+        }
+    }
+
+    if json {
+        let unreferenced: Vec<&str> = graph.unreferenced().iter().map(|n| n.id.as_str()).collect();
+        envelope.data = serde_json::json!({
+            "nodes": graph.nodes,
+            "edges": graph.edges,
+            "unreferenced": unreferenced,
+        });
+        envelope.emit(Status::Success);
+        return Ok(());
+    }
+    if dot {
+        print!("{}", graph.to_dot());
+        return Ok(());
+    }
+    for edge in &graph.edges {
+        let kind = match edge.kind {
+            crate::graph::CallKind::Local => "",
+            crate::graph::CallKind::External => "  (external)",
+            crate::graph::CallKind::Effect => "  (effect)",
+        };
+        println!("{} -> {}{}", edge.caller, edge.callee, kind);
+    }
+    let unreferenced = graph.unreferenced();
+    if !unreferenced.is_empty() {
+        println!();
+        println!("Unreferenced private functions:");
+        for node in unreferenced {
+            println!(
+                "  {}  {}:{}",
+                node.id,
+                node.span.file.display(),
+                node.span.start_line
+            );
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn run_module_graph(
+    paths: &[PathBuf],
+    dot: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut envelope = Envelope::new("graph");
+    let mut files = collect_astra_files(paths)?;
+    files.sort();
+    let mut graph = crate::graph::ModuleGraph::new();
+    for file in files {
+        let source = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read {:?}: {}", file, e))?;
+        match crate::parser::parse_source(&source, &file) {
+            Ok(module) => graph.add_module(&module),
+            Err(e) if json => envelope.diagnostics.extend(e.diagnostics().iter().cloned()),
+            Err(e) => eprintln!(
+                "Parse error in {:?}:\n{}",
+                file,
+                e.render(&source, render_options())
+            ),
+        }
+    }
+    let cycles = graph.cycles();
+
+    if json {
+        envelope.data = serde_json::json!({
+            "modules": graph.modules,
+            "imports": graph.imports,
+            "external": graph.external(),
+            "cycles": cycles,
+        });
+        let status = if cycles.is_empty() {
+            Status::Success
+        } else {
+            Status::Failure
+        };
+        envelope.emit(status);
+        return Ok(());
+    }
+    if dot {
+        print!("{}", graph.to_dot());
+    } else {
+        for edge in &graph.imports {
+            println!("{} -> {}", edge.from, edge.to);
+        }
+    }
+    if !cycles.is_empty() {
+        eprintln!();
+        eprintln!("Import cycles:");
+        for cycle in &cycles {
+            eprintln!("  {}", cycle.chain_text());
+            for (span, to) in cycle.imports.iter().zip(&cycle.chain[1..]) {
+                eprintln!(
+                    "    {}:{}: import {}",
+                    span.file.display(),
+                    span.start_line,
+                    to
+                );
+            }
+        }
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! `astra lint` and the lint levels from the manifest and command line
+
+use super::*;
+
+/// Run `astra lint` — report only lint diagnostics (W-codes) at their configured levels.
+pub(super) fn run_lint(
+    paths: &[PathBuf],
+    overrides: &[(String, LintLevel)],
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let project_root = crate::cache::find_project_root(
+        paths
+            .first()
+            .map(|p| p.as_path())
+            .unwrap_or_else(|| std::path::Path::new(".")),
+    );
+    let mut lint_config = load_lint_config(&project_root)?;
+    let cfg = project_cfg(Cfg::test(), &project_root, &[]);
+    for (code, level) in overrides {
+        lint_config.rules.insert(code.clone(), *level);
+    }
+
+    let mut denied = 0;
+    let mut warnings = 0;
+    let mut files_checked = 0;
+    let mut envelope = Envelope::new("lint");
+
+    let astra_files = collect_astra_files(paths)?;
+    if format == OutputFormat::Jsonl {
+        emit_event(
+            "start",
+            serde_json::json!({
+                "schema_version": envelope::SCHEMA_VERSION,
+                "command": "lint",
+                "files": astra_files.len(),
+            }),
+        );
+    }
+    for file_path in &astra_files {
+        let source = std::fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read {:?}: {}", file_path, e))?;
+        files_checked += 1;
+
+        let lints: Vec<Diagnostic> = check_file(file_path, &source, &cfg)
+            .into_iter()
+            .filter(|d| is_lint_code(&d.code))
+            .collect();
+        let lints = lint_config.apply(lints);
+        report_diagnostics(&lints, &source, format, &mut envelope);
+        denied += lints.iter().filter(|d| d.is_error()).count();
+        warnings += lints.len() - lints.iter().filter(|d| d.is_error()).count();
+        if format == OutputFormat::Jsonl {
+            emit_event(
+                "progress",
+                serde_json::json!({
+                    "file": file_path.display().to_string(),
+                    "diagnostics": lints.len(),
+                    "checked": files_checked,
+                    "total": astra_files.len(),
+                }),
+            );
+        }
+    }
+
+    if format == OutputFormat::Jsonl {
+        emit_event(
+            "summary",
+            serde_json::json!({
+                "files": files_checked,
+                "denied": denied,
+                "warnings": warnings,
+                "success": denied == 0,
+            }),
+        );
+        if denied > 0 {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if format == OutputFormat::Json {
+        envelope.data = serde_json::json!({
+            "files": files_checked,
+            "denied": denied,
+            "warnings": warnings,
+        });
+        envelope.emit(if denied > 0 {
+            Status::Failure
+        } else {
+            Status::Success
+        });
+        return Ok(());
+    }
+
+    if denied > 0 {
+        eprintln!(
+            "\nLinted {} file(s), found {} denied lint(s), {} warning(s)",
+            files_checked, denied, warnings
+        );
+        std::process::exit(1);
+    }
+    println!("Linted {} file(s), {} warning(s)", files_checked, warnings);
+    Ok(())
+}
+
+/// Parse `--allow`/`--warn`/`--deny` values, lint names or codes, into
+/// per-code overrides. Anything that isn't a known lint is an error.
+pub(super) fn lint_overrides(
+    allow: &[String],
+    warn: &[String],
+    deny: &[String],
+) -> Result<Vec<(String, LintLevel)>, String> {
+    let mut overrides = Vec::new();
+    for (codes, level, flag) in [
+        (allow, LintLevel::Allow, "--allow"),
+        (warn, LintLevel::Warn, "--warn"),
+        (deny, LintLevel::Deny, "--deny"),
+    ] {
+        for name in codes.iter().flat_map(|c| c.split(',')) {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            let code = lint_code(name).ok_or_else(|| {
+                format!(
+                    "unknown lint `{}` in {} (expected a lint name such as `unused_variable` or a code such as `W0001`)",
+                    name, flag
+                )
+            })?;
+            overrides.push((code.to_string(), level));
+        }
+    }
+    Ok(overrides)
+}
+
+/// Load the `[lint]` section of the project's astra.toml (defaults if absent).
+/// An invalid manifest, such as one naming an unknown lint, is an error.
+pub(super) fn load_lint_config(project_root: &std::path::Path) -> Result<LintConfig, String> {
+    let manifest_path = project_root.join("astra.toml");
+    match std::fs::read_to_string(&manifest_path) {
+        Ok(content) => Manifest::parse(&content)
+            .map(|manifest| manifest.lint)
+            .map_err(|e| format!("Invalid lint config in {:?}: {}", manifest_path, e)),
+        Err(_) => Ok(LintConfig::default()),
+    }
+}
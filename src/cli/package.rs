@@ -0,0 +1,343 @@
+//! `astra package` and `astra pkg`: building packages and managing dependencies
+
+use super::*;
+
+/// Report why a package can't be built: in the `--json` envelope as a
+/// failure, otherwise as the command's error
+fn package_failure(
+    mut envelope: Envelope,
+    json: bool,
+    message: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if json {
+        envelope.error = Some(message);
+        envelope.emit(Status::Failure);
+        return Ok(());
+    }
+    Err(message.into())
+}
+
+/// Check that a binary target's file defines its main function
+pub(super) fn check_bin_target(
+    target: &crate::manifest::BinTarget,
+    project_root: &Path,
+    cfg: &Cfg,
+) -> Result<(), String> {
+    let entry_path = project_root.join(&target.path);
+    let source = std::fs::read_to_string(&entry_path).map_err(|e| {
+        format!(
+            "Binary target `{}`: failed to read {:?}: {}",
+            target.name, entry_path, e
+        )
+    })?;
+    let defines_main = crate::parser::parse_source(&source, &entry_path)
+        .map(|module| {
+            module.items.iter().any(|item| {
+                cfg.includes(item)
+                    && matches!(item, crate::parser::ast::Item::FnDef(def) if def.name == target.main)
+            })
+        })
+        .unwrap_or(false);
+    if !defines_main {
+        return Err(format!(
+            "Binary target `{}`: {} does not define `fn {}()`",
+            target.name, target.path, target.main
+        ));
+    }
+    Ok(())
+}
+
+pub(super) fn run_package(
+    output: &PathBuf,
+    target: &str,
+    features: &[String],
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut envelope = Envelope::new("package");
+    // Progress lines are suppressed with --json so stdout holds only the envelope
+    let progress = |line: String| {
+        if !json {
+            println!("{}", line);
+        }
+    };
+
+    // P7.2: Basic package command
+    progress("Packaging project...".to_string());
+
+    // Read project manifest
+    let manifest_path = std::env::current_dir()?.join("astra.toml");
+    if !manifest_path.exists() {
+        return Err(
+            "No astra.toml found in current directory. Run `astra init` to create one.".into(),
+        );
+    }
+
+    let manifest_content = std::fs::read_to_string(&manifest_path)?;
+    progress("  Found manifest: astra.toml".to_string());
+
+    // Collect all .astra source files
+    let current_dir = std::env::current_dir()?;
+    let source_files = walkdir(&current_dir)?
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|ext| ext == "astra"))
+        .collect::<Vec<_>>();
+
+    progress(format!("  Found {} source files", source_files.len()));
+
+    // Items whose `@cfg(...)` doesn't hold for a release build, and tests,
+    // are left out of the packaged sources
+    let cfg = project_cfg(Cfg::default(), &current_dir, features);
+    let mut packaged_sources = Vec::new();
+
+    // Validate all files parse and type-check as packaged
+    let mut errors = 0;
+    for file in &source_files {
+        let source = std::fs::read_to_string(file)?;
+        let packaged = match crate::parser::parse_source(&source, file) {
+            Ok(module) => cfg.strip_source(&source, &module),
+            Err(_) => source,
+        };
+        let source_file = SourceFile::new(file.clone(), packaged.clone());
+        packaged_sources.push(packaged);
+        let lexer = Lexer::new(&source_file);
+        let mut parser = AstraParser::new(lexer, source_file.clone());
+        match parser.parse_module() {
+            Ok(module) => {
+                let mut checker = crate::typechecker::TypeChecker::new();
+                checker.set_cfg(cfg.clone());
+                configure_checker_search_paths(&mut checker, file.parent());
+                if let Err(bag) = checker.check_module(&module) {
+                    if !json {
+                        eprintln!("  Type error in {:?}", file);
+                    }
+                    envelope
+                        .diagnostics
+                        .extend(bag.diagnostics().iter().filter(|d| d.is_error()).cloned());
+                    errors += 1;
+                }
+            }
+            Err(bag) => {
+                if !json {
+                    eprintln!("  Parse error in {:?}", file);
+                }
+                envelope
+                    .diagnostics
+                    .extend(bag.diagnostics().iter().cloned());
+                errors += 1;
+            }
+        }
+    }
+
+    if errors > 0 {
+        let message = format!("{} file(s) have errors. Fix them before packaging.", errors);
+        return package_failure(envelope, json, message);
+    }
+
+    // Every binary target must point at a file defining its main function
+    let manifest = crate::manifest::Manifest::parse(&manifest_content)
+        .map_err(|e| format!("Failed to load manifest: {}", e))?;
+    let bin_targets =
+        if manifest.bins.is_empty() && !current_dir.join(manifest.entry_point()).exists() {
+            // Library packages have no entry point
+            Vec::new()
+        } else {
+            manifest.bin_targets()
+        };
+    for target in &bin_targets {
+        if let Err(message) = check_bin_target(target, &current_dir, &cfg) {
+            return package_failure(envelope, json, message);
+        }
+        progress(format!(
+            "  Binary target `{}` -> {} ({})",
+            target.name, target.path, target.main
+        ));
+    }
+
+    // Create output directory
+    std::fs::create_dir_all(output)?;
+
+    // Write the packaged sources to output
+    for (file, packaged) in source_files.iter().zip(&packaged_sources) {
+        let relative = file.strip_prefix(&current_dir).unwrap_or(file);
+        let dest = output.join(relative);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&dest, packaged)?;
+    }
+
+    // Copy manifest
+    std::fs::copy(&manifest_path, output.join("astra.toml"))?;
+
+    // Write package metadata
+    let bins_section: String = bin_targets
+        .iter()
+        .map(|t| format!("# Bin: {} = {} ({})\n", t.name, t.path, t.main))
+        .collect();
+    let metadata = format!(
+        "# Astra Package\n# Target: {}\n{}# Manifest:\n{}\n",
+        target, bins_section, manifest_content
+    );
+    std::fs::write(output.join("PACKAGE.md"), metadata)?;
+
+    if json {
+        envelope.artifact("package", output.display());
+        envelope.artifact("metadata", output.join("PACKAGE.md").display());
+        envelope.data = serde_json::json!({
+            "target": target,
+            "files": source_files.len(),
+            "bins": bin_targets.iter().map(|t| &t.name).collect::<Vec<_>>(),
+        });
+        envelope.emit(Status::Success);
+        return Ok(());
+    }
+    println!("  Package created at {:?} (target: {})", output, target);
+    println!("  {} files packaged successfully", source_files.len());
+
+    Ok(())
+}
+
+/// v1.1: Run package management commands
+pub(super) fn run_pkg(action: PkgAction) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = std::env::current_dir()?;
+    let manifest_path = cwd.join("astra.toml");
+
+    match action {
+        PkgAction::Install => {
+            if !manifest_path.exists() {
+                return Err(
+                    "No astra.toml found. Run `astra init` to create a project first.".into(),
+                );
+            }
+
+            let manifest = crate::manifest::Manifest::load(&manifest_path)
+                .map_err(|e| format!("Failed to load manifest: {}", e))?;
+
+            let mut registry = crate::manifest::registry::PackageRegistry::new(cwd.clone());
+            let packages = registry
+                .resolve(&manifest)
+                .map_err(|e| format!("Failed to resolve dependencies: {}", e))?;
+
+            if packages.is_empty() {
+                println!("No dependencies to install.");
+                return Ok(());
+            }
+
+            println!("Installing {} dependencies...", packages.len());
+            registry
+                .install(&packages)
+                .map_err(|e| format!("Failed to install: {}", e))?;
+
+            // Generate lockfile
+            let lockfile = registry.generate_lockfile(&packages);
+            let lockfile_path = cwd.join("astra.lock");
+            lockfile
+                .save(&lockfile_path)
+                .map_err(|e| format!("Failed to write lockfile: {}", e))?;
+
+            for pkg in &packages {
+                println!("  Installed {} v{}", pkg.name, pkg.version);
+            }
+            println!("Done.");
+        }
+        PkgAction::Add {
+            name,
+            version,
+            git,
+            path,
+        } => {
+            if !manifest_path.exists() {
+                return Err(
+                    "No astra.toml found. Run `astra init` to create a project first.".into(),
+                );
+            }
+
+            let mut manifest = crate::manifest::Manifest::load(&manifest_path)
+                .map_err(|e| format!("Failed to load manifest: {}", e))?;
+
+            // Build the dependency spec
+            if path.is_some() || git.is_some() {
+                manifest.dependencies.insert(
+                    name.clone(),
+                    crate::manifest::Dependency::Detailed(crate::manifest::DetailedDependency {
+                        version,
+                        git,
+                        branch: None,
+                        tag: None,
+                        rev: None,
+                        path,
+                        features: Vec::new(),
+                        default_features: true,
+                        optional: false,
+                    }),
+                );
+            } else {
+                let ver = version.unwrap_or_else(|| "*".to_string());
+                manifest
+                    .dependencies
+                    .insert(name.clone(), crate::manifest::Dependency::Simple(ver));
+            }
+
+            // Write back
+            let toml_str = manifest
+                .to_toml()
+                .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+            std::fs::write(&manifest_path, toml_str)?;
+            println!("Added `{}` to dependencies.", name);
+        }
+        PkgAction::Remove { name } => {
+            if !manifest_path.exists() {
+                return Err(
+                    "No astra.toml found. Run `astra init` to create a project first.".into(),
+                );
+            }
+
+            let mut manifest = crate::manifest::Manifest::load(&manifest_path)
+                .map_err(|e| format!("Failed to load manifest: {}", e))?;
+
+            if manifest.dependencies.remove(&name).is_some() {
+                let toml_str = manifest
+                    .to_toml()
+                    .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+                std::fs::write(&manifest_path, toml_str)?;
+                println!("Removed `{}` from dependencies.", name);
+            } else {
+                println!("Dependency `{}` not found.", name);
+            }
+        }
+        PkgAction::List => {
+            if !manifest_path.exists() {
+                return Err(
+                    "No astra.toml found. Run `astra init` to create a project first.".into(),
+                );
+            }
+
+            let manifest = crate::manifest::Manifest::load(&manifest_path)
+                .map_err(|e| format!("Failed to load manifest: {}", e))?;
+
+            if manifest.dependencies.is_empty() {
+                println!("No dependencies.");
+            } else {
+                println!("Dependencies:");
+                for (name, dep) in &manifest.dependencies {
+                    match dep {
+                        crate::manifest::Dependency::Simple(v) => {
+                            println!("  {} = \"{}\"", name, v);
+                        }
+                        crate::manifest::Dependency::Detailed(d) => {
+                            if let Some(ref v) = d.version {
+                                println!("  {} = \"{}\"", name, v);
+                            } else if let Some(ref g) = d.git {
+                                println!("  {} (git: {})", name, g);
+                            } else if let Some(ref p) = d.path {
+                                println!("  {} (path: {})", name, p);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+//! Project settings read from `astra.toml`, and finding source files and modules
+
+use super::*;
+
+/// The `@cfg(...)` configuration for a project: `base` with the manifest's
+/// default features and the `--features` given on the command line enabled
+pub(super) fn project_cfg(base: Cfg, project_root: &std::path::Path, features: &[String]) -> Cfg {
+    let defaults = std::fs::read_to_string(project_root.join("astra.toml"))
+        .ok()
+        .and_then(|content| Manifest::parse(&content).ok())
+        .and_then(|manifest| manifest.features.get("default").cloned())
+        .unwrap_or_default();
+    base.with_features(defaults)
+        .with_features(features.iter().cloned())
+}
+
+/// Budgets from the `[limits]` section of the project's manifest, if any
+/// The contract mode for a run: `--contracts` if given, otherwise the
+/// manifest's `[contracts] mode`
+pub(super) fn project_contract_mode(
+    project_root: &std::path::Path,
+    flag: Option<ContractMode>,
+) -> Result<ContractMode, String> {
+    let manifest_path = project_root.join("astra.toml");
+    match flag {
+        Some(mode) => Ok(mode),
+        None if manifest_path.exists() => Manifest::load(&manifest_path)
+            .map(|manifest| manifest.contracts.mode)
+            .map_err(|e| format!("Failed to load manifest: {}", e)),
+        None => Ok(ContractMode::default()),
+    }
+}
+
+pub(super) fn project_budget(project_root: &std::path::Path) -> Result<Option<Budget>, String> {
+    let manifest_path = project_root.join("astra.toml");
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+    let manifest =
+        Manifest::load(&manifest_path).map_err(|e| format!("Failed to load manifest: {}", e))?;
+    Ok((manifest.limits != Default::default()).then(|| manifest.limits.budget()))
+}
+
+/// The database from the manifest's `[db]` section, for `astra run`
+pub(super) fn project_db(
+    project_root: &std::path::Path,
+) -> Result<Option<Box<dyn crate::interpreter::DbCapability>>, String> {
+    let manifest_path = project_root.join("astra.toml");
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+    let manifest =
+        Manifest::load(&manifest_path).map_err(|e| format!("Failed to load manifest: {}", e))?;
+    let Some(db) = manifest.db else {
+        return Ok(None);
+    };
+    #[cfg(feature = "sqlite")]
+    {
+        crate::interpreter::SqliteDb::open(&project_root.join(&db.path))
+            .map(|db| Some(Box::new(db) as _))
+    }
+    #[cfg(not(feature = "sqlite"))]
+    {
+        Err(format!(
+            "astra was built without the `sqlite` feature, so the [db] database '{}' cannot be opened",
+            db.path
+        ))
+    }
+}
+
+/// Collect all .astra files under the given paths
+pub(super) fn collect_astra_files(
+    paths: &[PathBuf],
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut astra_files = Vec::new();
+    for path in paths {
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "astra") {
+            astra_files.push(path.clone());
+        } else if path.is_dir() {
+            for entry in walkdir(path)? {
+                if entry.extension().is_some_and(|ext| ext == "astra") {
+                    astra_files.push(entry);
+                }
+            }
+        }
+    }
+    Ok(astra_files)
+}
+
+/// Simple recursive directory walker
+pub(super) fn walkdir(path: &PathBuf) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut results = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let entry_path = entry.path();
+        if entry_path.is_file() {
+            results.push(entry_path);
+        } else if entry_path.is_dir() {
+            results.extend(walkdir(&entry_path)?);
+        }
+    }
+    Ok(results)
+}
+
+/// Configure standard search paths for module resolution.
+///
+/// Adds the following search paths in order:
+/// 1. The given base directory (usually the source file's parent)
+/// 2. The current working directory
+/// 3. The executable's directory (for finding stdlib relative to the binary)
+///
+/// B1: Configure search paths for the type checker
+pub(super) fn configure_checker_search_paths(
+    checker: &mut crate::typechecker::TypeChecker,
+    base_dir: Option<&std::path::Path>,
+) {
+    if let Some(base) = base_dir {
+        checker.add_search_path(base.to_path_buf());
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        checker.add_search_path(cwd.clone());
+        let mut dir = cwd.as_path();
+        loop {
+            if dir.join("astra.toml").exists() {
+                checker.add_search_path(dir.to_path_buf());
+                break;
+            }
+            match dir.parent() {
+                Some(parent) => dir = parent,
+                None => break,
+            }
+        }
+    }
+    if let Ok(exe_path) = std::env::current_exe() {
+        if let Some(exe_dir) = exe_path.parent() {
+            checker.add_search_path(exe_dir.to_path_buf());
+        }
+    }
+}
+
+pub(super) fn configure_search_paths(
+    interpreter: &mut Interpreter,
+    base_dir: Option<&std::path::Path>,
+) {
+    // Add the base directory first (usually the source file's parent)
+    if let Some(base) = base_dir {
+        interpreter.add_search_path(base.to_path_buf());
+    }
+
+    // Add the current working directory
+    if let Ok(cwd) = std::env::current_dir() {
+        interpreter.add_search_path(cwd.clone());
+
+        // Also check for astra.toml to find the project root
+        // Walk up from cwd to find it
+        let mut dir = cwd.as_path();
+        loop {
+            if dir.join("astra.toml").exists() {
+                interpreter.add_search_path(dir.to_path_buf());
+                break;
+            }
+            match dir.parent() {
+                Some(parent) => dir = parent,
+                None => break,
+            }
+        }
+    }
+
+    // Add the executable's directory as a search path
+    if let Ok(exe_path) = std::env::current_exe() {
+        if let Some(exe_dir) = exe_path.parent() {
+            interpreter.add_search_path(exe_dir.to_path_buf());
+        }
+    }
+}
@@ -0,0 +1,309 @@
+//! Editing commands: `refs`, `rename`, and `apply`
+
+use super::*;
+
+/// Parse a `file:line:col` location with 1-indexed line and column
+pub(super) fn parse_location(location: &str) -> Result<(PathBuf, usize, usize), String> {
+    let invalid = || {
+        format!(
+            "Invalid location '{}': expected file.astra:line:col",
+            location
+        )
+    };
+    let mut parts = location.rsplitn(3, ':');
+    let col = parts
+        .next()
+        .and_then(|c| c.parse().ok())
+        .ok_or_else(invalid)?;
+    let line = parts
+        .next()
+        .and_then(|l| l.parse().ok())
+        .ok_or_else(invalid)?;
+    let file = parts.next().filter(|f| !f.is_empty()).ok_or_else(invalid)?;
+    Ok((PathBuf::from(file), line, col))
+}
+
+pub(super) fn run_refs(location: &str, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut envelope = Envelope::new("refs");
+    let (file, line, col) = parse_location(location)?;
+    let source =
+        std::fs::read_to_string(&file).map_err(|e| format!("Failed to read {:?}: {}", file, e))?;
+    let module = match crate::parser::parse_source(&source, &file) {
+        Ok(module) => module,
+        Err(e) if json => {
+            envelope.diagnostics.extend(e.diagnostics().iter().cloned());
+            envelope.emit(Status::Failure);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!(
+                "Parse error in {:?}:\n{}",
+                file,
+                e.render(&source, render_options())
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let source_file = crate::parser::SourceFile::new(file.clone(), source);
+    let resolution = crate::resolve::resolve_module(&module, &source_file);
+    let Some(index) = resolution.binding_at(line, col) else {
+        return Err(format!("No name found at {}", location).into());
+    };
+    let binding = &resolution.bindings[index];
+    let references = resolution.references_to(index);
+
+    if json {
+        envelope.data = serde_json::json!({
+            "name": binding.name,
+            "kind": binding.kind,
+            "definition": binding.span,
+            "references": references,
+        });
+        envelope.emit(Status::Success);
+        return Ok(());
+    }
+    let print = |span: &crate::diagnostics::Span, suffix: &str| {
+        let text = source_file.get_line(span.start_line).unwrap_or_default();
+        println!(
+            "{}:{}:{}: {}{}",
+            span.file.display(),
+            span.start_line,
+            span.start_col,
+            text.trim(),
+            suffix
+        );
+    };
+    print(&binding.span, "  (definition)");
+    for span in references {
+        print(span, "");
+    }
+    Ok(())
+}
+
+pub(super) fn run_rename(
+    location: &str,
+    new_name: &str,
+    apply: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::rename::RenameError;
+
+    let mut envelope = Envelope::new("rename");
+    let (file, line, col) = parse_location(location)?;
+    let target = file
+        .canonicalize()
+        .map_err(|e| format!("Failed to read {:?}: {}", file, e))?;
+
+    // Every file of the project may use the name
+    let root = crate::cache::find_project_root(&file);
+    let mut files = collect_astra_files(&[root])?;
+    files.retain(|path| path.canonicalize().ok().as_ref() != Some(&target));
+    files.push(file.clone());
+    files.sort();
+    let mut sources = Vec::new();
+    for path in files {
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        sources.push((path, source));
+    }
+
+    let renames = match crate::rename::rename(&sources, &file, line, col, new_name) {
+        Ok(renames) => renames,
+        Err(RenameError::Conflicts(conflicts)) => {
+            if json {
+                envelope.data = serde_json::json!({
+                    "new_name": new_name,
+                    "files": [],
+                    "conflicts": conflicts,
+                });
+                envelope.emit(Status::Failure);
+                return Ok(());
+            }
+            for conflict in &conflicts {
+                eprintln!(
+                    "{}:{}:{}: conflict: {}",
+                    conflict.span.file.display(),
+                    conflict.span.start_line,
+                    conflict.span.start_col,
+                    conflict.message
+                );
+            }
+            eprintln!("Rename to `{}` refused", new_name);
+            std::process::exit(1);
+        }
+        Err(RenameError::NoName) => {
+            return Err(format!("No name found at {}", location).into());
+        }
+        Err(e) => return Err(e.to_string().into()),
+    };
+
+    let occurrences: usize = renames.iter().map(|r| r.edits.len()).sum();
+    if apply {
+        for rename in &renames {
+            std::fs::write(&rename.path, &rename.renamed)
+                .map_err(|e| format!("Failed to write {:?}: {}", rename.path, e))?;
+            envelope.artifact("renamed", rename.path.display());
+        }
+    }
+
+    if json {
+        let diff: String = renames.iter().map(rename_diff).collect();
+        envelope.data = serde_json::json!({
+            "new_name": new_name,
+            "files": renames,
+            "conflicts": [],
+            "occurrences": occurrences,
+            "applied": apply,
+            "diff": diff,
+        });
+        envelope.emit(Status::Success);
+        return Ok(());
+    }
+    if apply {
+        println!(
+            "Renamed {} occurrence(s) in {} file(s)",
+            occurrences,
+            renames.len()
+        );
+    } else {
+        for rename in &renames {
+            print!("{}", rename_diff(rename));
+        }
+    }
+    Ok(())
+}
+
+/// Unified diff of one renamed file
+pub(super) fn rename_diff(rename: &crate::rename::FileRename) -> String {
+    let (old, new) = diff_labels(&rename.path);
+    crate::text_diff::TextDiff::new(&rename.original, &rename.renamed).unified(&old, &new)
+}
+
+/// `---`/`+++` labels for a diff of `path`, with `a/` and `b/` prefixes on
+/// relative paths
+pub(super) fn diff_labels(path: &Path) -> (String, String) {
+    let display = path.display();
+    if path.is_absolute() {
+        (display.to_string(), display.to_string())
+    } else {
+        (format!("a/{}", display), format!("b/{}", display))
+    }
+}
+
+/// Edits of a patch file: a list of edits, or an object with an `edits` list
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+pub(super) enum Patch {
+    Edits(Vec<crate::diagnostics::Edit>),
+    Object {
+        edits: Vec<crate::diagnostics::Edit>,
+    },
+}
+
+/// Run `astra apply`: apply every edit of a patch, refusing the whole patch
+/// if any file's edits overlap, then check the patched files
+pub(super) fn run_apply(
+    patch: &Path,
+    dry_run: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::diagnostics::{apply_edits, remap_offset, Edit};
+    use std::collections::BTreeMap;
+
+    let text = if patch == Path::new("-") {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(patch).map_err(|e| format!("Failed to read {:?}: {}", patch, e))?
+    };
+    let edits = match serde_json::from_str(&text)
+        .map_err(|e| format!("Invalid patch {:?}: {}", patch, e))?
+    {
+        Patch::Edits(edits) | Patch::Object { edits } => edits,
+    };
+    let mut by_file: BTreeMap<PathBuf, Vec<Edit>> = BTreeMap::new();
+    for edit in edits {
+        let file = if edit.file.as_os_str().is_empty() {
+            edit.span.file.clone()
+        } else {
+            edit.file.clone()
+        };
+        if file.as_os_str().is_empty() {
+            return Err("Every edit of a patch needs a `file`".into());
+        }
+        by_file.entry(file).or_default().push(edit);
+    }
+
+    // Patch every file before writing any
+    let mut patched = Vec::new();
+    for (file, edits) in by_file {
+        let original = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read {:?}: {}", file, e))?;
+        let source = apply_edits(&original, &edits)
+            .map_err(|e| format!("Cannot apply patch to {}: {}", file.display(), e))?;
+        patched.push((file, original, source, edits));
+    }
+    if !dry_run {
+        for (file, _, source, _) in &patched {
+            std::fs::write(file, source)
+                .map_err(|e| format!("Failed to write {:?}: {}", file, e))?;
+        }
+    }
+
+    let format = if json {
+        OutputFormat::Json
+    } else {
+        OutputFormat::Human
+    };
+    let mut envelope = Envelope::new("apply");
+    let mut errors = 0;
+    let mut files = Vec::new();
+    for (file, original, source, edits) in &patched {
+        if !dry_run {
+            envelope.artifact("patched", file.display());
+        } else if !json {
+            let (old, new) = diff_labels(file);
+            print!(
+                "{}",
+                crate::text_diff::TextDiff::new(original, source).unified(&old, &new)
+            );
+        }
+        let root = crate::cache::find_project_root(file);
+        let cfg = project_cfg(Cfg::test(), &root, &[]);
+        let diagnostics = check_file(file, source, &cfg);
+        errors += diagnostics.iter().filter(|d| d.is_error()).count();
+        report_diagnostics(&diagnostics, source, format, &mut envelope);
+        // Where each edit's replacement landed in the patched file
+        let ranges: Vec<_> = edits
+            .iter()
+            .map(|edit| {
+                let start = remap_offset(edits, edit.span.start);
+                serde_json::json!({ "start": start, "end": start + edit.replacement.len() })
+            })
+            .collect();
+        files.push(serde_json::json!({
+            "file": file.display().to_string(),
+            "edits": ranges,
+        }));
+    }
+
+    if json {
+        envelope.data = serde_json::json!({
+            "files": files,
+            "applied": !dry_run,
+            "errors": errors,
+        });
+        envelope.emit(if errors > 0 {
+            Status::Failure
+        } else {
+            Status::Success
+        });
+        return Ok(());
+    }
+    let verb = if dry_run { "Would patch" } else { "Patched" };
+    println!("{} {} file(s), {} error(s)", verb, patched.len(), errors);
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,476 @@
+//! `astra run`: running a program or `[[bin]]` target, and the reports after a run
+
+use super::*;
+
+/// What `astra run` runs
+pub(super) struct RunTarget {
+    pub(super) file: PathBuf,
+    /// Function the program starts from
+    pub(super) entry: String,
+    /// Name of the `[[bin]]` target in astra.toml, if it comes from one
+    pub(super) bin: Option<String>,
+    pub(super) args: Vec<String>,
+}
+
+/// Resolve the file and entry function for `astra run`.
+///
+/// An explicit file runs its `main`. Otherwise the binary target is taken
+/// from astra.toml; with `--bin`, a positional value is the first program
+/// argument rather than a file.
+pub(super) fn resolve_run_target(
+    file: Option<PathBuf>,
+    bin: Option<&str>,
+    mut args: Vec<String>,
+) -> Result<RunTarget, Box<dyn std::error::Error>> {
+    if let (Some(file), None) = (&file, bin) {
+        return Ok(RunTarget {
+            file: file.clone(),
+            entry: "main".to_string(),
+            bin: None,
+            args,
+        });
+    }
+    if let Some(arg) = file {
+        args.insert(0, arg.display().to_string());
+    }
+
+    let cwd = std::env::current_dir()?;
+    let project_root = crate::cache::find_project_root(&cwd);
+    let manifest_path = project_root.join("astra.toml");
+    if !manifest_path.exists() {
+        return Err("No file given and no astra.toml found. Usage: astra run <file>".into());
+    }
+    let manifest = crate::manifest::Manifest::load(&manifest_path)
+        .map_err(|e| format!("Failed to load manifest: {}", e))?;
+    let target = manifest.find_bin(bin)?;
+    Ok(RunTarget {
+        file: project_root.join(&target.path),
+        entry: target.main,
+        bin: Some(target.name),
+        args,
+    })
+}
+
+/// Name the `[[bin]]` target in an error about its entry function, which the
+/// manifest is where to fix
+pub(super) fn in_bin_target(mut error: RuntimeError, bin: Option<&str>) -> RuntimeError {
+    if let (Some(bin), crate::diagnostics::error_codes::runtime::MISSING_ENTRY) = (bin, error.code)
+    {
+        error.message = format!(
+            "{} (the `main` of [[bin]] `{}` in astra.toml)",
+            error.message, bin
+        );
+    }
+    error
+}
+
+/// How `astra run` runs the program, beyond what to run
+pub(super) struct RunOptions<'a> {
+    /// Report what the run cost
+    pub(super) report_cost: bool,
+    /// Contract mode from `--contracts`, overriding the manifest's
+    pub(super) contracts: Option<ContractMode>,
+    /// Address to serve the registered routes on after `main` returns
+    pub(super) serve: Option<&'a str>,
+    /// Buffer file writes and report them instead of making them
+    pub(super) dry_run: bool,
+    /// Report the effect operations of the run by call site
+    pub(super) audit: bool,
+}
+
+pub(super) fn run_program(
+    file: Option<PathBuf>,
+    bin: Option<&str>,
+    features: &[String],
+    args: Vec<String>,
+    options: RunOptions,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let RunOptions {
+        report_cost,
+        contracts,
+        serve,
+        dry_run,
+        audit,
+    } = options;
+    let mut envelope = Envelope::new("run");
+    let RunTarget {
+        file,
+        entry,
+        bin,
+        args,
+    } = resolve_run_target(file, bin, args)?;
+    let file = &file;
+    let contract_mode = project_contract_mode(&crate::cache::find_project_root(file), contracts)?;
+
+    // Read the source file
+    let source = std::fs::read_to_string(file)
+        .map_err(|e| format!("Failed to read file {:?}: {}", file, e))?;
+    let script = ScriptManifest::from_source(&source)
+        .map_err(|e| format!("Invalid script manifest in {}: {}", file.display(), e))?;
+    envelope.data = serde_json::json!({
+        "file": file.display().to_string(),
+        "entry": entry,
+        "contracts": contract_mode,
+    });
+
+    // Parse the source
+    let source_file = SourceFile::new(file.clone(), source.clone());
+    let lexer = Lexer::new(&source_file);
+    let mut parser = AstraParser::new(lexer, source_file.clone());
+    let module = match parser.parse_module() {
+        Ok(module) => module,
+        Err(e) if json => {
+            envelope.diagnostics = e.diagnostics().to_vec();
+            envelope.emit(Status::Failure);
+            return Ok(());
+        }
+        Err(e) => {
+            return Err(format!("Parse error:\n{}", e.render(&source, render_options())).into())
+        }
+    };
+    if let Some(script) = &script {
+        check_script_grants(script, &module, &entry)?;
+    }
+
+    // Create interpreter and run
+    let mut capabilities = real_capabilities(args);
+    capabilities.db = project_db(&crate::cache::find_project_root(file))?;
+    if json {
+        capabilities.progress = Some(Box::new(JsonProgress));
+    }
+    let metrics = if json {
+        MemoryMetrics::streaming()
+    } else {
+        MemoryMetrics::default()
+    };
+    capabilities.metrics = Some(Box::new(metrics.clone()));
+    if let Some(addr) = serve {
+        capabilities.serve = Some(Box::new(HttpServe::bind(addr)?));
+    }
+    let transaction = dry_run.then(|| TransactionalFs::new(RealFs));
+    if let Some(fs) = &transaction {
+        capabilities.fs = Some(Box::new(fs.clone()));
+    }
+    if let Some(granted) = script.as_ref().and_then(|s| s.capabilities.as_ref()) {
+        capabilities.restrict_to(granted);
+    }
+    let mut interpreter = new_interpreter(capabilities);
+    interpreter.set_cfg(project_cfg(
+        Cfg::default(),
+        &crate::cache::find_project_root(file),
+        features,
+    ));
+    interpreter.set_contract_mode(contract_mode);
+    configure_search_paths(&mut interpreter, file.parent());
+    if report_cost {
+        interpreter.track_memory();
+    }
+    if audit {
+        interpreter.start_audit();
+    }
+    if let Some(budget) = project_budget(&crate::cache::find_project_root(file))? {
+        interpreter.set_budget(budget);
+    }
+    if let Some(script) = &script {
+        if script.limits != Default::default() {
+            interpreter.set_budget(script.limits.budget());
+        }
+        for path in script_dependency_paths(script, file)? {
+            interpreter.add_search_path(path);
+        }
+    }
+    let result = interpreter
+        .eval_module_entry(&module, &entry)
+        .map_err(|e| in_bin_target(e, bin.as_deref()))
+        .and_then(|value| match serve {
+            Some(addr) => {
+                eprintln!(
+                    "Serving {} route(s) on http://{}",
+                    interpreter.route_count(),
+                    listen_address(addr)
+                );
+                interpreter.serve_requests().map(|()| value)
+            }
+            None => Ok(value),
+        });
+    if report_cost {
+        // Reported for failed runs too, which cost something as well
+        let cost = interpreter.cost();
+        if json {
+            envelope.data["cost"] = serde_json::to_value(&cost)?;
+        } else {
+            eprintln!("{}", format_cost(&cost, contract_mode));
+        }
+    } else if !json && contract_mode != ContractMode::All {
+        // Skipped contracts are worth a line even without a cost report
+        eprintln!("Contracts: {}", contract_mode.as_str());
+    }
+    if let Some(fs) = &transaction {
+        // Also for failed runs, which may have written files before failing
+        let writes = fs.pending();
+        if json {
+            envelope.data["dry_run"] = dry_run_json(&writes);
+        } else {
+            eprint!("{}", format_dry_run(&writes));
+        }
+    }
+    if let Some(audit) = interpreter.audit() {
+        if json {
+            envelope.data["audit"] = serde_json::to_value(audit)?;
+        } else {
+            eprint!("{}", format_audit(audit));
+        }
+    }
+    let report = metrics.report();
+    if !report.is_empty() {
+        if json {
+            envelope.data["metrics"] = serde_json::to_value(&report)?;
+        } else {
+            eprint!("{}", format_metrics(&report));
+        }
+    }
+    match result {
+        Ok(value) => match main_exit_code(&value) {
+            Ok(code) if json => {
+                let status = if code == 0 {
+                    Status::Success
+                } else {
+                    Status::Failure
+                };
+                envelope.emit_with_code(status, code);
+                Ok(())
+            }
+            Ok(0) => Ok(()),
+            Ok(code) => {
+                use std::io::Write;
+                // Output still buffered would be lost on exit
+                let _ = std::io::stdout().flush();
+                std::process::exit(code);
+            }
+            Err(message) if json => {
+                envelope.error = Some(message);
+                envelope.emit(Status::Failure);
+                Ok(())
+            }
+            Err(message) => Err(message.into()),
+        },
+        Err(e) => {
+            let trace = interpreter.format_stack_trace();
+            let mut msg = format!("Runtime error: {}", e);
+            if !trace.is_empty() {
+                msg.push_str(&format!("\n{}", trace));
+            }
+            if json {
+                // The program ran but failed, unlike errors that prevent running
+                if e.code == "E4030" {
+                    envelope
+                        .diagnostics
+                        .push(runtime_error_diagnostic(&e, file));
+                }
+                envelope.error = Some(msg);
+                envelope.emit(Status::Failure);
+                return Ok(());
+            }
+            Err(msg.into())
+        }
+    }
+}
+
+/// The process exit code for what the entry point returned: an `Int` is the
+/// exit status (modulo 256, as a shell sees it), and `Err(e)` is returned as
+/// the error to report with exit code 1. Anything else, such as `Unit` or
+/// `Ok(...)`, exits with 0.
+pub(super) fn main_exit_code(value: &Value) -> Result<i32, String> {
+    match value {
+        Value::Int(code) => Ok(code.rem_euclid(256) as i32),
+        Value::Err(error) => Err(crate::interpreter::format_value(error)),
+        _ => Ok(0),
+    }
+}
+
+/// Refuse to run a script whose entry point declares effects that its inline
+/// manifest withholds, which would otherwise fail at the first call
+pub(super) fn check_script_grants(
+    script: &ScriptManifest,
+    module: &crate::parser::ast::Module,
+    entry: &str,
+) -> Result<(), String> {
+    use crate::parser::ast::Item;
+
+    let declared = module.items.iter().find_map(|item| match item {
+        Item::FnDef(def) if def.name == entry => Some(&def.effects),
+        _ => None,
+    });
+    let withheld = declared
+        .map(|effects| script.withheld(effects))
+        .unwrap_or_default();
+    if withheld.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "`{}` uses effects({}), which the script's `#: capabilities` does not grant",
+        entry,
+        withheld.join(", ")
+    ))
+}
+
+/// Search paths for the dependencies in a script's inline manifest, which are
+/// resolved and installed next to the script
+pub(super) fn script_dependency_paths(
+    script: &ScriptManifest,
+    file: &Path,
+) -> Result<Vec<PathBuf>, String> {
+    if script.dependencies.is_empty() {
+        return Ok(Vec::new());
+    }
+    let root = file
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut registry = crate::manifest::registry::PackageRegistry::new(root.to_path_buf());
+    let packages = registry
+        .resolve_dependencies(&script.dependencies)
+        .map_err(|e| format!("Failed to resolve dependencies: {}", e))?;
+    registry
+        .install(&packages)
+        .map_err(|e| format!("Failed to install: {}", e))?;
+    Ok(registry.search_paths())
+}
+
+/// One-line summary of what a run cost
+pub(super) fn format_cost(cost: &CostReport, contracts: ContractMode) -> String {
+    let mut summary = format!("Cost: {} steps", cost.steps);
+    if let Some(bytes) = cost.peak_memory {
+        summary.push_str(&format!(", peak memory {} bytes", bytes));
+    }
+    if cost.effect_calls.is_empty() {
+        summary.push_str(", no effect calls");
+    } else {
+        let calls: Vec<String> = cost
+            .effect_calls
+            .iter()
+            .map(|(effect, count)| format!("{} {}", effect, count))
+            .collect();
+        summary.push_str(&format!(", effect calls: {}", calls.join(", ")));
+    }
+    summary.push_str(&format!(", contracts: {}", contracts.as_str()));
+    summary
+}
+
+/// The files a dry run would have written, with a diff for each text file
+pub(super) fn format_dry_run(writes: &[PendingWrite]) -> String {
+    if writes.is_empty() {
+        return "Dry run: no files would be written\n".to_string();
+    }
+    let mut report = format!("Dry run: {} file(s) would be written\n", writes.len());
+    for write in writes {
+        match &write.before {
+            Some(before) => report.push_str(&format!(
+                "  modify {} ({} -> {} bytes)\n",
+                write.path,
+                before.len(),
+                write.after.len()
+            )),
+            None => report.push_str(&format!(
+                "  create {} ({} bytes)\n",
+                write.path,
+                write.after.len()
+            )),
+        }
+    }
+    for write in writes {
+        if let Some(diff) = dry_run_diff(write) {
+            report.push('\n');
+            report.push_str(&diff);
+        }
+    }
+    report
+}
+
+/// A unified diff of a pending write, or `None` if either side is binary
+pub(super) fn dry_run_diff(write: &PendingWrite) -> Option<String> {
+    let before = std::str::from_utf8(write.before.as_deref().unwrap_or_default()).ok()?;
+    let after = std::str::from_utf8(&write.after).ok()?;
+    let old_label = match write.before {
+        Some(_) => write.path.clone(),
+        None => "/dev/null".to_string(),
+    };
+    let diff = crate::text_diff::TextDiff::new(before, after);
+    Some(diff.unified(&old_label, &format!("{} (dry run)", write.path)))
+        .filter(|diff| !diff.is_empty())
+}
+
+pub(super) fn dry_run_json(writes: &[PendingWrite]) -> serde_json::Value {
+    writes
+        .iter()
+        .map(|write| {
+            serde_json::json!({
+                "path": write.path,
+                "action": if write.before.is_some() { "modify" } else { "create" },
+                "bytes_before": write.before.as_ref().map(Vec::len),
+                "bytes": write.after.len(),
+                "diff": dry_run_diff(write),
+            })
+        })
+        .collect()
+}
+
+/// What the effect operations of a run touched, then each operation by site
+pub(super) fn format_audit(audit: &EffectAudit) -> String {
+    fn list(items: std::collections::BTreeSet<&str>) -> String {
+        if items.is_empty() {
+            "none".to_string()
+        } else {
+            items.into_iter().collect::<Vec<_>>().join(", ")
+        }
+    }
+    let mut report = "Effect audit:\n".to_string();
+    report.push_str(&format!("  files read: {}\n", list(audit.files_read())));
+    report.push_str(&format!(
+        "  files written: {}\n",
+        list(audit.files_written())
+    ));
+    report.push_str(&format!("  hosts contacted: {}\n", list(audit.hosts())));
+    report.push_str(&format!("  random values: {}\n", audit.random_values()));
+    report.push_str(&format!("  clock reads: {}\n", audit.clock_reads()));
+    for event in audit.events() {
+        let site = if event.site.is_empty() {
+            "<unknown>"
+        } else {
+            &event.site
+        };
+        report.push_str(&format!("  {}  {}", site, event.operation));
+        if let Some(target) = &event.target {
+            report.push_str(&format!(" {}", target));
+        }
+        if event.count > 1 {
+            report.push_str(&format!(" ({} times)", event.count));
+        }
+        report.push('\n');
+    }
+    report
+}
+
+/// The metrics report `astra run` prints when the program ends
+pub(super) fn format_metrics(report: &MetricsReport) -> String {
+    let duration = |millis| crate::interpreter::format_value(&Value::Duration(millis));
+    let mut text = "Metrics:\n".to_string();
+    for (name, n) in &report.counters {
+        text.push_str(&format!("  {}  {}\n", name, n));
+    }
+    for (name, value) in &report.gauges {
+        text.push_str(&format!("  {}  {}\n", name, value));
+    }
+    for (name, stats) in &report.timings {
+        text.push_str(&format!(
+            "  {}  {} samples, mean {}, min {}, max {}\n",
+            name,
+            stats.samples,
+            duration(stats.total_ms / stats.samples.max(1)),
+            duration(stats.min_ms),
+            duration(stats.max_ms)
+        ));
+    }
+    text
+}
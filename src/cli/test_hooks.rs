@@ -0,0 +1,336 @@
+//! Capabilities for tests: `using effects(...)` clauses and `before_each`/`after_each` hooks
+
+use super::*;
+
+/// Mock filesystem capability for tests
+pub(super) struct MockFs;
+
+impl crate::interpreter::FsCapability for MockFs {
+    fn read(&self, _path: &str) -> Result<String, String> {
+        Ok("mocked content".to_string())
+    }
+
+    fn write(&self, _path: &str, _content: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn exists(&self, _path: &str) -> bool {
+        true
+    }
+}
+
+/// Mock network capability for tests
+#[derive(Default)]
+pub(super) struct MockNet {
+    websocket: ScriptedWebSocket,
+}
+
+impl crate::interpreter::NetCapability for MockNet {
+    fn get(&self, _url: &str) -> Result<crate::interpreter::Value, String> {
+        Ok(crate::interpreter::Value::Text(
+            "mocked response".to_string(),
+        ))
+    }
+
+    fn post(&self, _url: &str, _body: &str) -> Result<crate::interpreter::Value, String> {
+        Ok(crate::interpreter::Value::Text(
+            "mocked response".to_string(),
+        ))
+    }
+
+    fn ws_connect(&self, _url: &str) -> Result<Box<dyn WebSocketConnection>, String> {
+        Ok(Box::new(self.websocket.clone()))
+    }
+}
+
+/// The `before_each` and `after_each` hooks of a module
+#[derive(Default)]
+pub(super) struct TestHooks<'a> {
+    before_each: Option<&'a crate::parser::ast::TestHook>,
+    after_each: Option<&'a crate::parser::ast::TestHook>,
+}
+
+impl<'a> TestHooks<'a> {
+    pub(super) fn from_module(module: &'a crate::parser::ast::Module) -> Self {
+        use crate::parser::ast::{HookKind, Item};
+
+        let mut hooks = Self::default();
+        for item in &module.items {
+            if let Item::TestHook(hook) = item {
+                match hook.kind {
+                    HookKind::BeforeEach => hooks.before_each = Some(hook),
+                    HookKind::AfterEach => hooks.after_each = Some(hook),
+                }
+            }
+        }
+        hooks
+    }
+
+    /// Capabilities for a test: the hooks' `using` clauses, overridden by the test's own
+    pub(super) fn capabilities(
+        &self,
+        using: &Option<crate::parser::ast::UsingClause>,
+    ) -> Capabilities {
+        let mut capabilities = build_test_capabilities(&None);
+        for hook in self.before_each.iter().chain(&self.after_each) {
+            apply_using_clause(&mut capabilities, &hook.using);
+        }
+        apply_using_clause(&mut capabilities, using);
+        capabilities
+    }
+
+    /// Run a test body between the hooks. The `before_each` value is bound as
+    /// `fixture`, and `after_each` runs even when the test fails.
+    pub(super) fn run(
+        &self,
+        interpreter: &mut Interpreter,
+        body: &crate::parser::ast::Block,
+    ) -> Result<(), crate::interpreter::RuntimeError> {
+        if let Some(before_each) = self.before_each {
+            let fixture = interpreter.eval_block(&before_each.body)?;
+            interpreter.env.define("fixture".to_string(), fixture);
+        }
+        let result = interpreter.eval_block(body).map(|_| ());
+        match self.after_each {
+            Some(after_each) => result.and(interpreter.eval_block(&after_each.body).map(|_| ())),
+            None => result,
+        }
+    }
+}
+
+/// Build capabilities for a test based on its `using effects(...)` clause.
+///
+/// Supports:
+/// - `Rand = Rand.seeded(<seed>)` or `Rand = seeded_rand(<seed>)` -> SeededRand
+/// - `Clock = Clock.fixed(<time>)` -> FixedClock
+/// - `Fs = Fs.memory()` -> MemoryFs, `Fs = mock_fs` or `Fs = ...` -> MockFs
+/// - `Net = Net.scripted(["msg", ...])` -> MockNet whose WebSockets replay the
+///   messages, `Net = mock_net` or `Net = ...` -> MockNet
+/// - `Crypto = Crypto.standard()` -> StandardCrypto, `Crypto = ...` -> MockCrypto
+/// - `Serve = ...` -> SyntheticServe, answered with `Serve.request(...)`
+/// - `Db = ...` -> a fresh in-memory SQLite database (with the `sqlite` feature)
+/// - `Progress = ...` -> MockProgress, which records the events
+/// - `Metrics = ...` -> MemoryMetrics, read back with `Metrics.snapshot()`
+/// - `Console = Console.scripted([expect_output("..."), send("..."), ...])` ->
+///   MockConsole that answers reads with the sent lines, checking the output
+///   before each; `Console = Console.tty()` -> MockConsole that acts as a
+///   terminal, so `style` adds escape codes; `Console = ...` -> MockConsole
+///   (always provided)
+pub(super) fn build_test_capabilities(
+    using: &Option<crate::parser::ast::UsingClause>,
+) -> Capabilities {
+    let mut capabilities = Capabilities {
+        console: Some(Box::new(MockConsole::new())),
+        ..Default::default()
+    };
+    apply_using_clause(&mut capabilities, using);
+    capabilities
+}
+
+/// Replace the capabilities bound in a `using effects(...)` clause
+pub(super) fn apply_using_clause(
+    capabilities: &mut Capabilities,
+    using: &Option<crate::parser::ast::UsingClause>,
+) {
+    if let Some(clause) = using {
+        for binding in &clause.bindings {
+            match binding.effect.as_str() {
+                "Rand" => {
+                    // Expect: Rand.seeded(<int>) or seeded_rand(<int>)
+                    if let Some(seed) = extract_method_int_arg(&binding.value, "Rand", "seeded") {
+                        capabilities.rand = Some(Box::new(SeededRand::new(seed as u64)));
+                    } else if let Some(seed) = extract_call_int_arg(&binding.value, "seeded_rand") {
+                        capabilities.rand = Some(Box::new(SeededRand::new(seed as u64)));
+                    } else {
+                        // Default seeded rand with seed 42
+                        capabilities.rand = Some(Box::new(SeededRand::new(42)));
+                    }
+                }
+                "Clock" => {
+                    // Expect: Clock.fixed(<int>)
+                    if let Some(time) = extract_method_int_arg(&binding.value, "Clock", "fixed") {
+                        capabilities.clock = Some(Box::new(FixedClock::new(time)));
+                    }
+                }
+                "Fs" => {
+                    // Fs.memory() starts an empty in-memory filesystem; anything
+                    // else gets the mock that answers every read the same way
+                    capabilities.fs = if is_method_call(&binding.value, "Fs", "memory") {
+                        Some(Box::new(MemoryFs::default()))
+                    } else {
+                        Some(Box::new(MockFs))
+                    };
+                }
+                "Net" => {
+                    // Provide mock network; Net.scripted([...]) scripts WebSocket messages
+                    let messages = extract_method_text_list_arg(&binding.value, "Net", "scripted")
+                        .unwrap_or_default();
+                    capabilities.net = Some(Box::new(MockNet {
+                        websocket: ScriptedWebSocket::new(messages),
+                    }));
+                }
+                "Crypto" => {
+                    // Expect: Crypto.standard() for real algorithms, anything else is mocked
+                    capabilities.crypto = if is_method_call(&binding.value, "Crypto", "standard") {
+                        Some(Box::new(StandardCrypto))
+                    } else {
+                        Some(Box::new(MockCrypto))
+                    };
+                }
+                "Serve" => {
+                    // No sockets in tests: requests are sent with Serve.request
+                    capabilities.serve = Some(Box::new(SyntheticServe::default()));
+                }
+                "Metrics" => {
+                    // Aggregated in memory, for `Metrics.snapshot()` to read back
+                    capabilities.metrics = Some(Box::new(MemoryMetrics::default()));
+                }
+                "Progress" => {
+                    // Progress is recorded, not drawn
+                    capabilities.progress = Some(Box::new(MockProgress::default()));
+                }
+                "Db" => {
+                    // Every test gets its own empty database, never the project's file
+                    #[cfg(feature = "sqlite")]
+                    {
+                        capabilities.db = Some(Box::new(crate::interpreter::SqliteDb::in_memory()));
+                    }
+                }
+                "Console" => {
+                    // Console is always provided; Console.scripted([...]) plays a
+                    // script of expected output and input against the program
+                    if let Some(steps) = extract_console_script(&binding.value) {
+                        capabilities.console = Some(Box::new(MockConsole::scripted(steps)));
+                    } else if is_method_call(&binding.value, "Console", "tty") {
+                        capabilities.console = Some(Box::new(MockConsole::tty()));
+                    }
+                }
+                _ => {
+                    // Unknown effect binding - ignore for now
+                }
+            }
+        }
+    }
+}
+
+/// Extract an integer argument from a method call expression like `Foo.bar(42)`.
+pub(super) fn extract_method_int_arg(
+    expr: &crate::parser::ast::Expr,
+    expected_receiver: &str,
+    expected_method: &str,
+) -> Option<i64> {
+    use crate::parser::ast::Expr;
+
+    if let Expr::MethodCall {
+        receiver,
+        method,
+        args,
+        ..
+    } = expr
+    {
+        if method == expected_method {
+            if let Expr::Ident { name, .. } = receiver.as_ref() {
+                if name == expected_receiver {
+                    if let Some(Expr::IntLit { value, .. }) = args.first() {
+                        return Some(*value);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Extract a list of text literals from a method call like `Net.scripted(["a", "b"])`.
+pub(super) fn extract_method_text_list_arg(
+    expr: &crate::parser::ast::Expr,
+    expected_receiver: &str,
+    expected_method: &str,
+) -> Option<Vec<String>> {
+    use crate::parser::ast::Expr;
+
+    let Expr::MethodCall { args, .. } = expr else {
+        return None;
+    };
+    if !is_method_call(expr, expected_receiver, expected_method) {
+        return None;
+    }
+    let Some(Expr::ListLit { elements, .. }) = args.first() else {
+        return None;
+    };
+    elements
+        .iter()
+        .map(|element| match element {
+            Expr::TextLit { value, .. } => Some(value.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Extract the steps of `Console.scripted([expect_output("a"), send("b")])`.
+pub(super) fn extract_console_script(expr: &crate::parser::ast::Expr) -> Option<Vec<ConsoleStep>> {
+    use crate::parser::ast::Expr;
+
+    let Expr::MethodCall { args, .. } = expr else {
+        return None;
+    };
+    if !is_method_call(expr, "Console", "scripted") {
+        return None;
+    }
+    let Some(Expr::ListLit { elements, .. }) = args.first() else {
+        return None;
+    };
+    elements
+        .iter()
+        .map(|element| {
+            let Expr::Call { func, args, .. } = element else {
+                return None;
+            };
+            let (Expr::Ident { name, .. }, [Expr::TextLit { value, .. }]) =
+                (func.as_ref(), args.as_slice())
+            else {
+                return None;
+            };
+            match name.as_str() {
+                "expect_output" => Some(ConsoleStep::ExpectOutput(value.clone())),
+                "send" => Some(ConsoleStep::Send(value.clone())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Whether an expression is the call `Foo.bar(...)`.
+pub(super) fn is_method_call(
+    expr: &crate::parser::ast::Expr,
+    expected_receiver: &str,
+    expected_method: &str,
+) -> bool {
+    use crate::parser::ast::Expr;
+
+    matches!(
+        expr,
+        Expr::MethodCall { receiver, method, .. }
+            if method == expected_method
+                && matches!(receiver.as_ref(), Expr::Ident { name, .. } if name == expected_receiver)
+    )
+}
+
+/// Extract an integer argument from a function call expression like `foo(42)`.
+pub(super) fn extract_call_int_arg(
+    expr: &crate::parser::ast::Expr,
+    expected_fn: &str,
+) -> Option<i64> {
+    use crate::parser::ast::Expr;
+
+    if let Expr::Call { func, args, .. } = expr {
+        if let Expr::Ident { name, .. } = func.as_ref() {
+            if name == expected_fn {
+                if let Some(Expr::IntLit { value, .. }) = args.first() {
+                    return Some(*value);
+                }
+            }
+        }
+    }
+    None
+}
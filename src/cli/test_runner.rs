@@ -0,0 +1,534 @@
+//! `astra test`: running test blocks and property tests and reporting results
+
+use super::*;
+
+/// Options for `astra test`
+pub(super) struct TestOptions<'a> {
+    pub(super) filter: Option<&'a str>,
+    pub(super) seed: Option<u64>,
+    pub(super) update_golden: bool,
+    pub(super) replay_failures: bool,
+    pub(super) features: &'a [String],
+    /// Number of slowest tests to list after the run
+    pub(super) slowest: usize,
+    pub(super) warn_slower_than: Option<std::time::Duration>,
+    /// Skip the remaining tests after the first failure
+    pub(super) fail_fast: bool,
+    /// Contract mode from `--contracts`, overriding the manifest's
+    pub(super) contracts: Option<ContractMode>,
+    /// Report the effect operations of all tests by call site
+    pub(super) audit: bool,
+}
+
+/// Reports each test as it finishes: printed for humans, collected for the
+/// `--json` envelope, or emitted as a `test_*` event with `--format jsonl`
+pub(super) struct TestReporter {
+    format: OutputFormat,
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    /// Test files that failed to parse
+    errors: usize,
+    /// Results for the `--json` envelope
+    results: Vec<serde_json::Value>,
+}
+
+impl TestReporter {
+    pub(super) fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            passed: 0,
+            failed: 0,
+            skipped: 0,
+            errors: 0,
+            results: Vec::new(),
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.passed + self.failed + self.skipped
+    }
+
+    /// Announce a test before it runs (`test_start` events only)
+    fn start(&self, name: &str, module: &str, file: &Path) {
+        if self.format == OutputFormat::Jsonl {
+            emit_event(
+                "test_start",
+                serde_json::json!({
+                    "name": name,
+                    "module": module,
+                    "file": file.display().to_string(),
+                }),
+            );
+        }
+    }
+
+    pub(super) fn pass(&mut self, result: serde_json::Value, line: &str) {
+        self.passed += 1;
+        self.report("pass", result, line);
+    }
+
+    fn fail(&mut self, result: serde_json::Value, line: &str) {
+        self.failed += 1;
+        self.report("fail", result, line);
+    }
+
+    /// Record a test file that failed to parse; its tests can't have passed
+    pub(super) fn file_error(&mut self) {
+        self.errors += 1;
+    }
+
+    /// The outcome of the run: a failing test or an unparseable test file
+    /// fails it
+    pub(super) fn status(&self) -> Status {
+        if self.failed > 0 || self.errors > 0 {
+            Status::Failure
+        } else {
+            Status::Success
+        }
+    }
+
+    fn skip(&mut self, result: serde_json::Value, line: &str) {
+        self.skipped += 1;
+        self.report("skip", result, line);
+    }
+
+    fn report(&mut self, status: &str, mut result: serde_json::Value, line: &str) {
+        result["status"] = serde_json::Value::from(status);
+        match self.format {
+            OutputFormat::Json => self.results.push(result),
+            OutputFormat::Jsonl => emit_event(&format!("test_{}", status), result),
+            OutputFormat::Human if status == "fail" => eprintln!("{}", line),
+            OutputFormat::Human => println!("{}", line),
+        }
+    }
+}
+
+/// Diagnostic for a runtime error that failed a test in `file`
+pub(super) fn runtime_error_diagnostic(
+    error: &crate::interpreter::RuntimeError,
+    file: &Path,
+) -> Diagnostic {
+    let mut diagnostic = Diagnostic::error(error.code)
+        .message(error.message.clone())
+        .span(
+            error
+                .span
+                .clone()
+                .unwrap_or_else(|| crate::diagnostics::Span::file(file)),
+        );
+    if error.code == "E4030" {
+        diagnostic = diagnostic.note(crate::diagnostics::Note::new(
+            "limits are set in the `[limits]` section of astra.toml",
+        ));
+    }
+    diagnostic.build()
+}
+
+pub(super) fn run_test(
+    options: &TestOptions,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::parser::ast::Item;
+    use std::time::Instant;
+
+    let TestOptions {
+        filter,
+        seed,
+        update_golden,
+        replay_failures,
+        features,
+        fail_fast,
+        contracts,
+        audit,
+        ..
+    } = *options;
+
+    // Find all .astra files in current directory
+    let current_dir = std::env::current_dir()?;
+    let project_root = crate::cache::find_project_root(&current_dir);
+    let cfg = project_cfg(Cfg::test(), &project_root, features);
+    let budget = project_budget(&project_root)?;
+    let contract_mode = project_contract_mode(&project_root, contracts)?;
+    // Golden files are confined to the project root
+    let golden_files = || GoldenFiles {
+        fs: Box::new(RootedFs::new(&project_root)),
+        update: update_golden,
+    };
+    let mut failures = FailureStore::load(&project_root);
+    let files = walkdir(&current_dir)?;
+    let astra_files: Vec<_> = files
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|ext| ext == "astra"))
+        .collect();
+
+    let mut envelope = Envelope::new("test");
+    let mut reporter = TestReporter::new(format);
+    let mut timings: Vec<TestTiming> = Vec::new();
+    // Every test's effect operations, including each property case's
+    let effect_audit = std::cell::RefCell::new(EffectAudit::default());
+    let start_audit = |interpreter: &mut Interpreter| {
+        if audit {
+            interpreter.start_audit();
+        }
+    };
+    let record_audit = |interpreter: &Interpreter| {
+        if let Some(audit) = interpreter.audit() {
+            effect_audit.borrow_mut().merge(audit);
+        }
+    };
+
+    if format == OutputFormat::Jsonl {
+        emit_event(
+            "start",
+            serde_json::json!({
+                "schema_version": envelope::SCHEMA_VERSION,
+                "command": "test",
+                "files": astra_files.len(),
+            }),
+        );
+    }
+
+    for path in astra_files {
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+
+        let source_file = SourceFile::new(path.clone(), source.clone());
+        let lexer = Lexer::new(&source_file);
+        let mut parser = AstraParser::new(lexer, source_file.clone());
+
+        let module = match parser.parse_module() {
+            Ok(m) => m,
+            Err(e) => {
+                if format == OutputFormat::Human {
+                    eprintln!(
+                        "Parse error in {:?}:\n{}",
+                        path,
+                        e.render(&source, render_options())
+                    );
+                } else {
+                    report_diagnostics(e.diagnostics(), &source, format, &mut envelope);
+                }
+                reporter.file_error();
+                continue;
+            }
+        };
+
+        // Tests are reported as `module::name`
+        let module_path = module.name.segments.join(".");
+        let file = path.display().to_string();
+
+        let hooks = TestHooks::from_module(&module);
+
+        // Find and run all test blocks
+        for item in &module.items {
+            if let Item::Test(test) = item {
+                // Only property tests record failures to replay
+                if replay_failures {
+                    continue;
+                }
+                let name = qualified_test_name(&module_path, &test.name);
+                // Apply filter if specified
+                if let Some(f) = filter {
+                    if !matches_filter(f, &name) {
+                        continue;
+                    }
+                }
+
+                let result = serde_json::json!({
+                    "name": name,
+                    "module": module_path,
+                    "file": file,
+                });
+                if fail_fast && reporter.failed > 0 {
+                    reporter.skip(result, &format!("  SKIP: {}", name));
+                    continue;
+                }
+                reporter.start(&name, &module_path, &path);
+                let started = Instant::now();
+
+                // Build capabilities from the hooks' and the test's using clauses
+                let capabilities = hooks.capabilities(&test.using);
+
+                let mut interpreter = new_interpreter(capabilities);
+                interpreter.set_cfg(cfg.clone());
+                interpreter.set_golden_files(golden_files());
+                interpreter.set_contract_mode(contract_mode);
+                if let Some(budget) = &budget {
+                    interpreter.set_budget(budget.clone());
+                }
+                configure_search_paths(&mut interpreter, path.parent());
+                start_audit(&mut interpreter);
+                // Load the module functions first, then run the test block
+                let outcome = interpreter
+                    .load_module(&module)
+                    .and_then(|_| hooks.run(&mut interpreter, &test.body))
+                    .and_then(|_| interpreter.finish_console_script());
+                record_audit(&interpreter);
+                let timing = TestTiming {
+                    name: name.clone(),
+                    duration: started.elapsed(),
+                    steps: interpreter.steps(),
+                };
+                let mut result = result;
+                result["duration_ms"] = serde_json::json!(timing.duration.as_millis() as u64);
+                result["steps"] = serde_json::json!(timing.steps);
+                timings.push(timing);
+                match outcome {
+                    Ok(_) => reporter.pass(result, &format!("  PASS: {}", name)),
+                    Err(e) => {
+                        result["error"] = serde_json::json!(e.to_string());
+                        result["diagnostic"] =
+                            serde_json::to_value(runtime_error_diagnostic(&e, &path))
+                                .unwrap_or_default();
+                        reporter.fail(result, &format!("  FAIL: {} - {}", name, e));
+                    }
+                }
+            }
+
+            // P5.1: Property-based tests
+            if let Item::Property(prop) = item {
+                let name = qualified_test_name(&module_path, &prop.name);
+                if let Some(f) = filter {
+                    if !matches_filter(f, &name) {
+                        continue;
+                    }
+                }
+                if replay_failures && failures.get(&name).is_none() {
+                    continue;
+                }
+
+                let mut result = serde_json::json!({
+                    "name": name,
+                    "module": module_path,
+                    "file": file,
+                });
+                if fail_fast && reporter.failed > 0 {
+                    reporter.skip(result, &format!("  SKIP: {}", name));
+                    continue;
+                }
+                reporter.start(&name, &module_path, &path);
+
+                let num_iterations = 100;
+                let seed = seed.unwrap_or(42);
+                let started = Instant::now();
+                // Steps are summed over every case, including shrinking
+                let steps = std::cell::Cell::new(0);
+
+                // Run one case with the given Rand draws, returning the draws
+                // actually made and the error if it fails
+                let run_case = |iter_seed: u64,
+                                script: &[RandDraw]|
+                 -> Option<(Vec<RandDraw>, crate::interpreter::RuntimeError)> {
+                    let rand = ReplayRand::new(iter_seed, script.to_vec());
+                    let drawn = rand.drawn();
+                    let mut capabilities = hooks.capabilities(&prop.using);
+                    capabilities.rand = Some(Box::new(rand));
+
+                    let mut interpreter = new_interpreter(capabilities);
+                    interpreter.set_cfg(cfg.clone());
+                    interpreter.set_golden_files(golden_files());
+                    interpreter.set_contract_mode(contract_mode);
+                    if let Some(budget) = &budget {
+                        interpreter.set_budget(budget.clone());
+                    }
+                    configure_search_paths(&mut interpreter, path.parent());
+                    start_audit(&mut interpreter);
+                    let result = interpreter
+                        .load_module(&module)
+                        .and_then(|_| hooks.run(&mut interpreter, &prop.body));
+                    steps.set(steps.get() + interpreter.steps());
+                    record_audit(&interpreter);
+                    let error = result.err()?;
+                    let inputs = drawn.borrow().clone();
+                    Some((inputs, error))
+                };
+
+                // Replay the recorded failure of this property first
+                let mut failure = None;
+                if let Some(recorded) = failures.get(&name).cloned() {
+                    match run_case(recorded.seed, &recorded.inputs) {
+                        Some((inputs, error)) => {
+                            let recorded = PropertyFailure {
+                                inputs,
+                                error: error.to_string(),
+                                ..recorded
+                            };
+                            failure = Some((recorded, error));
+                        }
+                        None => failures.remove(&name),
+                    }
+                }
+
+                if failure.is_none() && !replay_failures {
+                    for i in 0..num_iterations {
+                        let iter_seed = seed.wrapping_add(i);
+                        if let Some((inputs, error)) = run_case(iter_seed, &[]) {
+                            let (inputs, error) = shrink_draws(inputs, error, 1000, |script| {
+                                run_case(iter_seed, script)
+                            });
+                            let found = PropertyFailure {
+                                test: name.clone(),
+                                seed: iter_seed,
+                                inputs,
+                                error: error.to_string(),
+                            };
+                            failure = Some((found, error));
+                            break;
+                        }
+                    }
+                }
+
+                let timing = TestTiming {
+                    name: name.clone(),
+                    duration: started.elapsed(),
+                    steps: steps.get(),
+                };
+                result["duration_ms"] = serde_json::json!(timing.duration.as_millis() as u64);
+                result["steps"] = serde_json::json!(timing.steps);
+                timings.push(timing);
+                match failure {
+                    None => {
+                        let iterations = if replay_failures { 1 } else { num_iterations };
+                        result["iterations"] = serde_json::json!(iterations);
+                        result["seed"] = serde_json::json!(seed);
+                        let line = if replay_failures {
+                            format!("  PASS: {} (recorded failure fixed)", name)
+                        } else {
+                            format!("  PASS: {} ({} iterations)", name, num_iterations)
+                        };
+                        reporter.pass(result, &line);
+                    }
+                    Some((failure, error)) => {
+                        let inputs: Vec<String> =
+                            failure.inputs.iter().map(ToString::to_string).collect();
+                        result["error"] = serde_json::json!(failure.error);
+                        result["seed"] = serde_json::json!(failure.seed);
+                        result["inputs"] = serde_json::json!(failure.inputs);
+                        result["diagnostic"] =
+                            serde_json::to_value(runtime_error_diagnostic(&error, &path))
+                                .unwrap_or_default();
+                        let mut line = format!(
+                            "  FAIL: {} (seed {}) - {}",
+                            name, failure.seed, failure.error
+                        );
+                        if !inputs.is_empty() {
+                            line.push_str(&format!("\n    input: {}", inputs.join(", ")));
+                        }
+                        reporter.fail(result, &line);
+                        failures.record(failure);
+                    }
+                }
+            }
+        }
+    }
+
+    if let Err(e) = failures.save(&project_root) {
+        eprintln!("warning: {}", e);
+    }
+
+    let timing_json = |timing: &TestTiming| {
+        serde_json::json!({
+            "name": timing.name,
+            "duration_ms": timing.duration.as_millis() as u64,
+            "steps": timing.steps,
+        })
+    };
+    let slow: Vec<&TestTiming> = match options.warn_slower_than {
+        Some(threshold) => timings.iter().filter(|t| t.duration > threshold).collect(),
+        None => Vec::new(),
+    };
+    let total_tests = reporter.total();
+    let status = reporter.status();
+    let TestReporter {
+        passed,
+        failed,
+        skipped,
+        errors,
+        results,
+        ..
+    } = reporter;
+
+    if format == OutputFormat::Human {
+        let slowest = slowest(&timings, options.slowest);
+        if !slowest.is_empty() {
+            println!("\nSlowest tests:");
+            for timing in slowest {
+                println!(
+                    "  {:>8}  {:>10} steps  {}",
+                    format_duration(timing.duration),
+                    timing.steps,
+                    timing.name
+                );
+            }
+        }
+        if let Some(threshold) = options.warn_slower_than {
+            for timing in &slow {
+                eprintln!(
+                    "warning: {} took {}, slower than {}",
+                    timing.name,
+                    format_duration(timing.duration),
+                    format_duration(threshold)
+                );
+            }
+        }
+        if audit {
+            eprint!("\n{}", format_audit(&effect_audit.borrow()));
+        }
+        if skipped > 0 {
+            println!(
+                "\n{} tests: {} passed, {} failed, {} skipped (contracts: {})",
+                total_tests,
+                passed,
+                failed,
+                skipped,
+                contract_mode.as_str()
+            );
+        } else {
+            println!(
+                "\n{} tests: {} passed, {} failed (contracts: {})",
+                total_tests,
+                passed,
+                failed,
+                contract_mode.as_str()
+            );
+        }
+        if errors > 0 {
+            println!("{} test file(s) failed to parse", errors);
+        }
+    } else {
+        let mut data = serde_json::json!({
+            "total": total_tests,
+            "passed": passed,
+            "failed": failed,
+            "skipped": skipped,
+            "errors": errors,
+            "contracts": contract_mode,
+            "slowest": slowest(&timings, options.slowest)
+                .into_iter()
+                .map(timing_json)
+                .collect::<Vec<_>>(),
+        });
+        if let Some(threshold) = options.warn_slower_than {
+            data["slow_threshold_ms"] = serde_json::json!(threshold.as_millis() as u64);
+            data["slow"] = slow.iter().map(|t| timing_json(t)).collect();
+        }
+        if audit {
+            data["audit"] = serde_json::to_value(&*effect_audit.borrow())?;
+        }
+        if format == OutputFormat::Jsonl {
+            data["success"] = serde_json::json!(status == Status::Success);
+            emit_event("summary", data);
+        } else {
+            data["results"] = serde_json::json!(results);
+            envelope.data = data;
+            envelope.emit(status);
+        }
+    }
+
+    if status != Status::Success {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
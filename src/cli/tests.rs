@@ -1,5 +1,6 @@
 use super::*;
 use crate::diagnostics::Span;
+use crate::interpreter::MetricEvent;
 use crate::parser::ast::{Expr, NodeId};
 
 fn dummy_span() -> Span {
@@ -60,6 +61,7 @@ fn test_extract_method_int_arg_match() {
             span: dummy_span(),
             value: 1000,
         }],
+        method_span: None,
     };
     assert_eq!(extract_method_int_arg(&expr, "Clock", "fixed"), Some(1000));
 }
@@ -80,6 +82,7 @@ fn test_extract_method_int_arg_wrong_receiver() {
             span: dummy_span(),
             value: 1000,
         }],
+        method_span: None,
     };
     assert_eq!(extract_method_int_arg(&expr, "Clock", "fixed"), None);
 }
@@ -92,6 +95,57 @@ fn test_build_test_capabilities_default() {
     assert!(caps.clock.is_none());
 }
 
+#[test]
+fn test_hooks_run_around_tests() {
+    let module = crate::parser::parse_source(
+        r#"module example
+
+before_each
+  using effects(Clock = Clock.fixed(1000))
+{
+  { start = Clock.now() }
+}
+
+after_each {
+  assert(fixture.start == 1000, "after_each saw a different fixture")
+}
+
+test "uses fixture" {
+  assert_eq(fixture.start, 1000)
+}
+
+test "fails" {
+  assert(false, "test failed")
+}
+
+test "overrides clock"
+  using effects(Clock = Clock.fixed(5))
+{
+  assert_eq(Clock.now(), 5)
+}
+"#,
+        Path::new("test.astra"),
+    )
+    .unwrap();
+    let hooks = TestHooks::from_module(&module);
+    let run = |index: usize| {
+        let crate::parser::ast::Item::Test(test) = &module.items[index] else {
+            panic!("expected a test");
+        };
+        let mut interpreter = Interpreter::with_capabilities(hooks.capabilities(&test.using));
+        interpreter.load_module(&module).unwrap();
+        hooks.run(&mut interpreter, &test.body)
+    };
+    assert!(run(2).is_ok());
+    // The test's own failure is reported even though after_each ran
+    assert_eq!(run(3).unwrap_err().message, "test failed");
+    // The test's clock overrides the hook's, so the fixture differs
+    assert_eq!(
+        run(4).unwrap_err().message,
+        "after_each saw a different fixture"
+    );
+}
+
 #[test]
 fn test_configure_search_paths() {
     let mut interpreter = Interpreter::new();
@@ -118,11 +172,13 @@ fn test_explain_all_error_codes() {
     // Verify all documented error codes have explanations
     let codes = [
         "E0001", "E0002", "E0003", "E0004", "E0005", "E0006", "E0007", "E0008", "E0009", "E0010",
-        "E0011", "E1001", "E1002", "E1003", "E1004", "E1005", "E1006", "E1007", "E1008", "E1009",
-        "E1010", "E1011", "E1012", "E1013", "E1014", "E1015", "E1016", "E2001", "E2002", "E2003",
-        "E2004", "E2005", "E2006", "E2007", "E3001", "E3002", "E3003", "E3004", "E3005", "E4001",
-        "E4002", "E4003", "E4004", "E4005", "E4006", "E4007", "E4008", "W0001", "W0002", "W0003",
-        "W0004", "W0005", "W0006", "W0007", "W0008",
+        "E0011", "E0012", "E0013", "E0014", "E0015", "E1001", "E1002", "E1003", "E1004", "E1005",
+        "E1006", "E1007", "E1008", "E1009", "E1010", "E1011", "E1012", "E1013", "E1014", "E1015",
+        "E1016", "E1017", "E1018", "E2001", "E2002", "E2003", "E2004", "E2005", "E2006", "E2007",
+        "E3001", "E3002", "E3003", "E3004", "E3005", "E3006", "E4001", "E4002", "E4003", "E4004",
+        "E4005", "E4006", "E4007", "E4008", "E4022", "E4023", "E4024", "E4025", "E4026", "E4027",
+        "E4028", "E4029", "E4030", "E4031", "W0001", "W0002", "W0003", "W0004", "W0005", "W0006",
+        "W0007", "W0008", "W0009", "W0010", "W0012",
     ];
     for code in &codes {
         assert!(
@@ -186,3 +242,651 @@ fn test_init_creates_claude_md() {
 
     let _ = std::fs::remove_dir_all(&tmp);
 }
+
+#[test]
+fn test_lint_overrides_split_codes() {
+    let overrides =
+        lint_overrides(&["w0001,W0002".to_string()], &[], &["W0008".to_string()]).unwrap();
+    assert_eq!(
+        overrides,
+        vec![
+            ("W0001".to_string(), LintLevel::Allow),
+            ("W0002".to_string(), LintLevel::Allow),
+            ("W0008".to_string(), LintLevel::Deny),
+        ]
+    );
+}
+
+#[test]
+fn test_lint_overrides_accept_lint_names() {
+    let overrides = lint_overrides(
+        &[],
+        &["shadowed_binding".to_string()],
+        &["unused_variable".to_string()],
+    )
+    .unwrap();
+    assert_eq!(
+        overrides,
+        vec![
+            ("W0006".to_string(), LintLevel::Warn),
+            ("W0001".to_string(), LintLevel::Deny),
+        ]
+    );
+}
+
+#[test]
+fn test_lint_overrides_reject_unknown_codes() {
+    let err = lint_overrides(&[], &[], &["W9999".to_string()]).unwrap_err();
+    assert!(err.contains("unknown lint `W9999` in --deny"), "{}", err);
+    assert!(lint_overrides(&["E1001".to_string()], &[], &[]).is_err());
+    assert!(lint_overrides(&[], &["unused_thing".to_string()], &[]).is_err());
+}
+
+#[test]
+fn test_output_format_resolution() {
+    assert_eq!(OutputFormat::resolve(None, false), OutputFormat::Human);
+    assert_eq!(OutputFormat::resolve(None, true), OutputFormat::Json);
+    assert_eq!(
+        OutputFormat::resolve(Some(OutputFormat::Jsonl), true),
+        OutputFormat::Jsonl
+    );
+
+    let cli = Cli::try_parse_from(["astra", "check", "--format", "jsonl", "src"]).unwrap();
+    match cli.command {
+        Command::Check { format, .. } => assert_eq!(format, Some(OutputFormat::Jsonl)),
+        other => panic!("expected check command, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_locale_flag() {
+    let cli = Cli::try_parse_from(["astra", "check", "--locale", "es-MX", "src"]).unwrap();
+    assert_eq!(cli.locale, Some(Locale::Spanish));
+    let cli = Cli::try_parse_from(["astra", "--locale", "en", "check", "src"]).unwrap();
+    assert_eq!(cli.locale, Some(Locale::English));
+    assert!(Cli::try_parse_from(["astra", "check", "--locale", "xx", "src"]).is_err());
+}
+
+#[test]
+fn test_max_call_depth_flag() {
+    let cli = Cli::try_parse_from(["astra", "run", "main.astra"]).unwrap();
+    assert_eq!(cli.command.max_call_depth(), Some(DEFAULT_MAX_CALL_DEPTH));
+    let cli =
+        Cli::try_parse_from(["astra", "run", "--max-call-depth", "50000", "main.astra"]).unwrap();
+    assert_eq!(cli.command.max_call_depth(), Some(50_000));
+    let cli = Cli::try_parse_from(["astra", "test", "--max-call-depth", "200"]).unwrap();
+    assert_eq!(cli.command.max_call_depth(), Some(200));
+    assert!(Cli::try_parse_from(["astra", "run", "--max-call-depth", "-1", "main.astra"]).is_err());
+}
+
+#[test]
+fn test_max_call_depth_only_for_evaluating_commands() {
+    let cli = Cli::try_parse_from(["astra", "fmt", "--check", "src"]).unwrap();
+    assert_eq!(cli.command.max_call_depth(), None);
+    let cli = Cli::try_parse_from(["astra", "check", "src"]).unwrap();
+    assert_eq!(cli.command.max_call_depth(), None);
+    assert!(Cli::try_parse_from(["astra", "fmt", "--max-call-depth", "10", "src"]).is_err());
+}
+
+#[test]
+fn test_check_holes_flag() {
+    let cli = Cli::try_parse_from(["astra", "check", "--holes", "src"]).unwrap();
+    match cli.command {
+        Command::Check { holes, .. } => assert!(holes),
+        other => panic!("expected check command, got {:?}", other),
+    }
+    assert!(Cli::try_parse_from(["astra", "check", "--holes", "--watch"]).is_err());
+}
+
+#[test]
+fn test_fmt_verify_flag() {
+    let cli = Cli::try_parse_from(["astra", "fmt", "--verify", "src"]).unwrap();
+    match cli.command {
+        Command::Fmt { verify, check, .. } => assert!(verify && !check),
+        other => panic!("expected fmt command, got {:?}", other),
+    }
+    assert!(Cli::try_parse_from(["astra", "fmt", "--verify", "--check"]).is_err());
+}
+
+#[test]
+fn test_debug_breakpoints_flag() {
+    let cli = Cli::try_parse_from([
+        "astra",
+        "debug",
+        "main.astra",
+        "--break",
+        "main.astra:4",
+        "-b",
+        "9",
+    ])
+    .unwrap();
+    match cli.command {
+        Command::Debug {
+            file, breakpoints, ..
+        } => {
+            assert_eq!(file, Some(PathBuf::from("main.astra")));
+            assert_eq!(breakpoints, vec!["main.astra:4", "9"]);
+        }
+        other => panic!("expected debug command, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_report_cost_flag() {
+    let cli = Cli::try_parse_from(["astra", "run", "--report-cost", "main.astra"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Command::Run {
+            report_cost: true,
+            ..
+        }
+    ));
+    let cost = CostReport {
+        steps: 12,
+        effect_calls: [("Console".to_string(), 2), ("Fs".to_string(), 1)].into(),
+        peak_memory: Some(96),
+    };
+    assert_eq!(
+        format_cost(&cost, ContractMode::All),
+        "Cost: 12 steps, peak memory 96 bytes, effect calls: Console 2, Fs 1, contracts: all"
+    );
+}
+
+#[test]
+fn test_contracts_flag() {
+    let cli =
+        Cli::try_parse_from(["astra", "run", "--contracts", "boundary", "main.astra"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Command::Run {
+            contracts: Some(ContractMode::Boundary),
+            ..
+        }
+    ));
+    let cli = Cli::try_parse_from(["astra", "test", "--contracts=off"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Command::Test {
+            contracts: Some(ContractMode::Off),
+            ..
+        }
+    ));
+    assert!(Cli::try_parse_from(["astra", "test", "--contracts", "some"]).is_err());
+}
+
+#[test]
+fn test_dry_run_report() {
+    let cli = Cli::try_parse_from(["astra", "run", "--dry-run", "main.astra"]).unwrap();
+    assert!(matches!(cli.command, Command::Run { dry_run: true, .. }));
+    assert_eq!(format_dry_run(&[]), "Dry run: no files would be written\n");
+    let writes = [
+        PendingWrite {
+            path: "notes.txt".to_string(),
+            before: Some(b"a\nb\n".to_vec()),
+            after: b"a\nc\n".to_vec(),
+        },
+        PendingWrite {
+            path: "logo.png".to_string(),
+            before: None,
+            after: vec![0x89, 0x50, 0xff],
+        },
+    ];
+    assert_eq!(
+        format_dry_run(&writes),
+        "Dry run: 2 file(s) would be written\n  modify notes.txt (4 -> 4 bytes)\n  create logo.png (3 bytes)\n\n--- notes.txt\n+++ notes.txt (dry run)\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n"
+    );
+    let json = dry_run_json(&writes);
+    assert_eq!(json[0]["action"], "modify");
+    assert_eq!(json[1]["action"], "create");
+    assert_eq!(json[1]["diff"], serde_json::Value::Null);
+}
+
+#[test]
+fn test_audit_report() {
+    let cli = Cli::try_parse_from(["astra", "run", "--audit", "main.astra"]).unwrap();
+    assert!(matches!(cli.command, Command::Run { audit: true, .. }));
+    let cli = Cli::try_parse_from(["astra", "test", "--audit"]).unwrap();
+    assert!(matches!(cli.command, Command::Test { audit: true, .. }));
+    assert_eq!(
+        format_audit(&EffectAudit::default()),
+        "Effect audit:\n  files read: none\n  files written: none\n  hosts contacted: none\n  random values: 0\n  clock reads: 0\n"
+    );
+}
+
+#[test]
+fn test_main_exit_code() {
+    assert_eq!(main_exit_code(&Value::Unit), Ok(0));
+    assert_eq!(main_exit_code(&Value::Int(3)), Ok(3));
+    assert_eq!(main_exit_code(&Value::Int(-1)), Ok(255));
+    assert_eq!(main_exit_code(&Value::Int(256)), Ok(0));
+    assert_eq!(main_exit_code(&Value::Ok(Box::new(Value::Int(5)))), Ok(0));
+    assert_eq!(
+        main_exit_code(&Value::Err(Box::new(Value::Text("no input".to_string())))),
+        Err("no input".to_string())
+    );
+    assert_eq!(main_exit_code(&Value::Text("done".to_string())), Ok(0));
+}
+
+#[test]
+fn test_progress_rendering() {
+    assert_eq!(
+        progress_line("download", 5, 20),
+        "download [#####---------------] 5/20"
+    );
+    assert_eq!(
+        progress_line("download", 25, 20),
+        "download [####################] 25/20"
+    );
+    assert_eq!(progress_line("index", 40, 0), "index 40");
+    let event = ProgressEvent::Advance {
+        task: "download".to_string(),
+        done: 5,
+        total: 20,
+    };
+    assert_eq!(
+        serde_json::to_string(&event).unwrap(),
+        r#"{"event":"progress_advance","task":"download","done":5,"total":20}"#
+    );
+}
+
+#[test]
+fn test_metrics_report() {
+    let mut report = MetricsReport::default();
+    for event in [
+        MetricEvent::Counter {
+            name: "requests".to_string(),
+            n: 2,
+        },
+        MetricEvent::Timing {
+            name: "fetch".to_string(),
+            millis: 10,
+        },
+        MetricEvent::Timing {
+            name: "fetch".to_string(),
+            millis: 30,
+        },
+        MetricEvent::Gauge {
+            name: "queue_depth".to_string(),
+            value: 4.0,
+        },
+    ] {
+        report.record(&event);
+    }
+    assert_eq!(
+        format_metrics(&report),
+        "Metrics:\n  requests  2\n  queue_depth  4\n  fetch  2 samples, mean 20ms, min 10ms, max 30ms\n"
+    );
+    assert_eq!(
+        serde_json::to_value(&report).unwrap()["timings"]["fetch"],
+        serde_json::json!({"samples": 2, "total_ms": 40, "min_ms": 10, "max_ms": 30})
+    );
+}
+
+#[test]
+fn test_script_mode() {
+    let cli = Cli::try_parse_from(["astra", "tool.astra", "--verbose", "in.txt"]).unwrap();
+    match cli.command {
+        Command::Script(args) => assert_eq!(args, ["tool.astra", "--verbose", "in.txt"]),
+        other => panic!("expected script, got {:?}", other),
+    }
+    let source = "#: capabilities = [\"Console\"]\nmodule tool\nfn main() effects(Console, Fs) {\n  Fs.read(\"x\")\n}\n";
+    let module = crate::parser::parse_source(source, Path::new("tool.astra")).unwrap();
+    let script = ScriptManifest::from_source(source).unwrap().unwrap();
+    assert_eq!(
+        check_script_grants(&script, &module, "main").unwrap_err(),
+        "`main` uses effects(Fs), which the script's `#: capabilities` does not grant"
+    );
+    assert!(check_script_grants(&ScriptManifest::default(), &module, "main").is_ok());
+}
+
+#[test]
+fn test_features_flag() {
+    let cli = Cli::try_parse_from(["astra", "run", "--features", "net,tls", "main.astra"]).unwrap();
+    match cli.command {
+        Command::Run { features, file, .. } => {
+            assert_eq!(features, vec!["net", "tls"]);
+            assert_eq!(file, Some(PathBuf::from("main.astra")));
+        }
+        other => panic!("expected run command, got {:?}", other),
+    }
+    for command in ["check", "test", "package"] {
+        assert!(Cli::try_parse_from(["astra", command, "--features", "net"]).is_ok());
+    }
+}
+
+#[test]
+fn test_project_cfg_enables_default_features() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("astra.toml"),
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[features]\ndefault = [\"net\"]\n",
+    )
+    .unwrap();
+    let cfg = project_cfg(Cfg::default(), dir.path(), &["tls".to_string()]);
+    assert_eq!(cfg.features().collect::<Vec<_>>(), vec!["net", "tls"]);
+}
+
+#[test]
+fn test_test_filter_flag() {
+    let cli = Cli::try_parse_from(["astra", "test", "--filter", "app.math::"]).unwrap();
+    match cli.command {
+        Command::Test {
+            filter,
+            filter_flag,
+            ..
+        } => {
+            assert_eq!(filter, None);
+            assert_eq!(filter_flag.as_deref(), Some("app.math::"));
+        }
+        other => panic!("expected test command, got {:?}", other),
+    }
+    assert!(Cli::try_parse_from(["astra", "test", "add", "--filter", "app::"]).is_err());
+}
+
+#[test]
+fn test_update_golden_flag() {
+    let cli = Cli::try_parse_from(["astra", "test", "--update-golden"]).unwrap();
+    match cli.command {
+        Command::Test { update_golden, .. } => assert!(update_golden),
+        other => panic!("expected test command, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_replay_failures_flag() {
+    let cli = Cli::try_parse_from(["astra", "test", "--replay-failures"]).unwrap();
+    match cli.command {
+        Command::Test {
+            replay_failures, ..
+        } => assert!(replay_failures),
+        other => panic!("expected test command, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_test_stream_flags() {
+    let cli = Cli::try_parse_from([
+        "astra",
+        "test",
+        "--format",
+        "json-stream",
+        "--fail-fast",
+        "--warn-slower-than",
+        "500ms",
+    ])
+    .unwrap();
+    assert_eq!(cli.command.envelope_name(), None);
+    match cli.command {
+        Command::Test {
+            format,
+            fail_fast,
+            warn_slower_than,
+            slowest,
+            ..
+        } => {
+            assert_eq!(format, Some(OutputFormat::Jsonl));
+            assert!(fail_fast);
+            assert_eq!(
+                warn_slower_than,
+                Some(std::time::Duration::from_millis(500))
+            );
+            assert_eq!(slowest, 5);
+        }
+        other => panic!("expected test command, got {:?}", other),
+    }
+    assert!(Cli::try_parse_from(["astra", "test", "--warn-slower-than", "500"]).is_err());
+}
+
+#[test]
+fn test_diff_labels() {
+    assert_eq!(
+        diff_labels(Path::new("src/main.astra")),
+        (
+            "a/src/main.astra".to_string(),
+            "b/src/main.astra".to_string()
+        )
+    );
+    assert_eq!(
+        diff_labels(Path::new("/tmp/main.astra")).0,
+        "/tmp/main.astra"
+    );
+}
+
+#[test]
+fn test_envelope_schema() {
+    let mut envelope = Envelope::new("fmt");
+    envelope.artifact("formatted", "src/main.astra");
+    envelope.data = serde_json::json!({ "changed": 1 });
+    let json = envelope.finish(Status::Failure);
+
+    assert_eq!(json["schema_version"], envelope::SCHEMA_VERSION);
+    assert_eq!(json["command"], "fmt");
+    assert_eq!(json["status"], "failure");
+    assert_eq!(json["exit_code"], 1);
+    assert!(json["duration_ms"].is_u64());
+    assert_eq!(json["diagnostics"], serde_json::json!([]));
+    assert_eq!(
+        json["artifacts"],
+        serde_json::json!([{ "kind": "formatted", "path": "src/main.astra" }])
+    );
+    assert_eq!(json["data"]["changed"], 1);
+    assert!(json.get("error").is_none());
+}
+
+#[test]
+fn test_envelope_commands() {
+    let check = Cli::try_parse_from(["astra", "check"]).unwrap();
+    assert_eq!(check.command.envelope_name(), Some("check"));
+    let streaming = Cli::try_parse_from(["astra", "check", "--format", "jsonl"]).unwrap();
+    assert_eq!(streaming.command.envelope_name(), None);
+    let explain = Cli::try_parse_from(["astra", "explain", "E1001"]).unwrap();
+    assert_eq!(explain.command.envelope_name(), None);
+    assert_eq!(Status::Success.exit_code(), 0);
+    assert_eq!(Status::Error.exit_code(), 1);
+}
+
+#[test]
+fn test_annotate_inferred_types() {
+    let source = "module example\n\nfn add(a: Int) -> Int {\n  a + 1\n}\n";
+    let module = crate::parser::parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    let mut checker = crate::typechecker::TypeChecker::new();
+    checker.check_module(&module).unwrap();
+
+    let mut tree = serde_json::to_value(&module).unwrap();
+    annotate_inferred_types(&mut tree, &checker.expr_type_names());
+
+    let body = &tree["items"][0]["body"]["expr"];
+    assert_eq!(body["type"], "Binary");
+    assert_eq!(body["inferred_type"], "Int");
+    assert_eq!(body["left"]["inferred_type"], "Int");
+    // Items are not expressions and carry no inferred type
+    assert!(tree["items"][0].get("inferred_type").is_none());
+}
+
+#[test]
+fn test_graph_requires_kind() {
+    assert!(Cli::try_parse_from(["astra", "graph"]).is_err());
+    let cli = Cli::try_parse_from(["astra", "graph", "--calls", "--dot", "src"]).unwrap();
+    match cli.command {
+        Command::Graph {
+            paths,
+            calls,
+            modules,
+            dot,
+        } => {
+            assert_eq!(paths, vec![PathBuf::from("src")]);
+            assert!(calls && dot && !modules);
+        }
+        _ => panic!("expected graph command"),
+    }
+    assert!(Cli::try_parse_from(["astra", "graph", "--modules"]).is_ok());
+    assert!(Cli::try_parse_from(["astra", "graph", "--calls", "--modules"]).is_err());
+}
+
+#[test]
+fn test_rename_command() {
+    let cli =
+        Cli::try_parse_from(["astra", "rename", "main.astra:3:4", "total", "--apply"]).unwrap();
+    match cli.command {
+        Command::Rename {
+            location,
+            new_name,
+            apply,
+        } => {
+            assert_eq!(location, "main.astra:3:4");
+            assert_eq!(new_name, "total");
+            assert!(apply);
+        }
+        _ => panic!("expected rename command"),
+    }
+    assert!(Cli::try_parse_from(["astra", "rename", "main.astra:3:4"]).is_err());
+}
+
+#[test]
+fn test_parse_location() {
+    assert_eq!(
+        parse_location("src/main.astra:12:5").unwrap(),
+        (PathBuf::from("src/main.astra"), 12, 5)
+    );
+    assert_eq!(
+        parse_location("C:/x.astra:1:2").unwrap(),
+        (PathBuf::from("C:/x.astra"), 1, 2)
+    );
+    assert!(parse_location("main.astra:12").is_err());
+    assert!(parse_location(":1:2").is_err());
+}
+
+#[test]
+fn test_apply_command_and_patch_forms() {
+    let cli = Cli::try_parse_from(["astra", "apply", "fix.json", "--dry-run"]).unwrap();
+    assert_eq!(cli.command.envelope_name(), Some("apply"));
+    assert!(matches!(cli.command, Command::Apply { dry_run: true, .. }));
+
+    let edit = r#"{"file": "main.astra", "span": {"start": 0, "end": 1}, "replacement": "x"}"#;
+    let list: Patch = serde_json::from_str(&format!("[{}]", edit)).unwrap();
+    let object: Patch = serde_json::from_str(&format!(r#"{{"edits": [{}]}}"#, edit)).unwrap();
+    for patch in [list, object] {
+        let (Patch::Edits(edits) | Patch::Object { edits }) = patch;
+        assert_eq!(edits[0].file, PathBuf::from("main.astra"));
+    }
+}
+
+#[test]
+fn test_real_websocket_round_trip() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket = tungstenite::accept(stream).unwrap();
+        let greeting = socket.read().unwrap().into_text().unwrap();
+        socket
+            .send(tungstenite::Message::text(format!("echo: {}", greeting)))
+            .unwrap();
+        // Wait for the client to say goodbye, then hang up
+        socket.read().unwrap();
+        socket.close(None).unwrap();
+        while socket.read().is_ok() {}
+    });
+
+    let mut connection = RealNet.ws_connect(&url).unwrap();
+    connection.send("hi").unwrap();
+    assert_eq!(
+        connection.recv(Some(2000)),
+        Ok(Some("echo: hi".to_string()))
+    );
+    // Nothing more until the client speaks, so a short wait times out
+    assert_eq!(connection.recv(Some(50)), Ok(None));
+    connection.send("bye").unwrap();
+    assert_eq!(
+        connection.recv(Some(2000)),
+        Err("connection closed".to_string())
+    );
+    server.join().unwrap();
+
+    assert!(RealNet.ws_connect("ws://127.0.0.1:1").is_err());
+}
+
+#[test]
+fn test_missing_bin_entry_names_the_target() {
+    let error = in_bin_target(RuntimeError::missing_entry("migrat"), Some("migrate"));
+    assert_eq!(
+        error.message,
+        "entry function `migrat` is not defined (the `main` of [[bin]] `migrate` in astra.toml)"
+    );
+    let error = in_bin_target(RuntimeError::missing_entry("main"), None);
+    assert_eq!(error.message, "entry function `main` is not defined");
+    let error = in_bin_target(RuntimeError::stack_overflow(10), Some("migrate"));
+    assert!(!error.message.contains("astra.toml"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scoped_fs_follows_symlinks_out_of_its_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("data");
+    std::fs::create_dir_all(root.join("inner")).unwrap();
+    std::fs::create_dir(dir.path().join("secret")).unwrap();
+    std::os::unix::fs::symlink(dir.path().join("secret"), root.join("link")).unwrap();
+    let source = format!(
+        r#"module example
+
+fn save(fs: Fs, path: Text) -> Text {{
+  match fs.write(path, "x") {{
+    Ok(_) => "ok"
+    Err(_) => "refused"
+  }}
+}}
+
+fn main() -> Text effects(Fs) {{
+  let data = Fs.scoped("{root}")
+  let inside = save(data, "{root}/inner/a.txt")
+  let escaped = save(data, "{root}/link/b.txt")
+  "${{inside}} ${{escaped}}"
+}}
+"#,
+        root = root.display()
+    );
+    let module = crate::parser::parse_source(&source, Path::new("test.astra")).unwrap();
+    let mut interpreter = Interpreter::with_capabilities(Capabilities {
+        fs: Some(Box::new(RealFs)),
+        ..Default::default()
+    });
+    let result = interpreter.eval_module(&module).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "ok refused"),
+        "{:?}",
+        result
+    );
+    assert!(!dir.path().join("secret/b.txt").exists());
+}
+
+#[test]
+fn test_unparseable_test_file_fails_the_run() {
+    let mut reporter = TestReporter::new(OutputFormat::Json);
+    reporter.pass(serde_json::json!({ "name": "ok" }), "");
+    assert_eq!(reporter.status(), Status::Success);
+    reporter.file_error();
+    assert_eq!(reporter.status(), Status::Failure);
+    assert_eq!(reporter.status().exit_code(), 1);
+}
+
+#[test]
+fn test_check_bin_target_requires_its_main() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("tool.astra"),
+        "module tool\n\nfn helper() -> Int {\n  1\n}\n",
+    )
+    .unwrap();
+    let target = |main: &str| crate::manifest::BinTarget {
+        name: "tool".to_string(),
+        path: "tool.astra".to_string(),
+        main: main.to_string(),
+    };
+    assert!(check_bin_target(&target("helper"), dir.path(), &Cfg::default()).is_ok());
+    let err = check_bin_target(&target("main"), dir.path(), &Cfg::default()).unwrap_err();
+    assert_eq!(
+        err,
+        "Binary target `tool`: tool.astra does not define `fn main()`"
+    );
+}
@@ -0,0 +1,102 @@
+//! `--watch` for `astra check` and `astra test`: rerunning on file changes
+
+use super::*;
+
+/// Run `astra check` in watch mode — re-check on file changes.
+pub(super) fn run_watch_check(
+    paths: &[PathBuf],
+    strict: bool,
+    no_cache: bool,
+    baseline: Option<&Path>,
+    format: OutputFormat,
+    features: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    println!("Watching for changes... (Ctrl+C to stop)\n");
+
+    // Run initial check
+    let _ = run_check(paths, strict, no_cache, baseline, format, features);
+
+    let (tx, rx) = mpsc::channel();
+
+    let mut watcher = RecommendedWatcher::new(
+        move |res: Result<notify::Event, notify::Error>| {
+            if let Ok(event) = res {
+                if event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove() {
+                    let _ = tx.send(());
+                }
+            }
+        },
+        Config::default().with_poll_interval(Duration::from_millis(100)),
+    )?;
+
+    // Watch all provided paths
+    for path in paths {
+        let watch_path = if path.is_file() {
+            path.parent().unwrap_or(path).to_path_buf()
+        } else {
+            path.clone()
+        };
+        watcher.watch(&watch_path, RecursiveMode::Recursive)?;
+    }
+
+    // Debounce: collect events and re-run after quiet period
+    loop {
+        // Wait for first event
+        rx.recv()?;
+
+        // Drain any accumulated events (debounce 200ms)
+        while rx.recv_timeout(Duration::from_millis(200)).is_ok() {}
+
+        // Clear screen and re-run
+        print!("\x1B[2J\x1B[H"); // ANSI clear screen
+        println!("File changed — re-checking...\n");
+        let _ = run_check(paths, strict, no_cache, baseline, format, features);
+        println!("\nWatching for changes... (Ctrl+C to stop)");
+    }
+}
+
+/// Run `astra test` in watch mode — re-run tests on file changes.
+pub(super) fn run_watch_test(
+    options: &TestOptions,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    println!("Watching for changes... (Ctrl+C to stop)\n");
+
+    // Run initial tests
+    let _ = run_test(options, format);
+
+    let (tx, rx) = mpsc::channel();
+
+    let mut watcher = RecommendedWatcher::new(
+        move |res: Result<notify::Event, notify::Error>| {
+            if let Ok(event) = res {
+                if event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove() {
+                    let _ = tx.send(());
+                }
+            }
+        },
+        Config::default().with_poll_interval(Duration::from_millis(100)),
+    )?;
+
+    // Watch current directory
+    let cwd = std::env::current_dir()?;
+    watcher.watch(&cwd, RecursiveMode::Recursive)?;
+
+    loop {
+        rx.recv()?;
+        while rx.recv_timeout(Duration::from_millis(200)).is_ok() {}
+
+        print!("\x1B[2J\x1B[H");
+        println!("File changed — re-running tests...\n");
+        let _ = run_test(options, format);
+        println!("\nWatching for changes... (Ctrl+C to stop)");
+    }
+}
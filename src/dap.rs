@@ -0,0 +1,540 @@
+//! Debug Adapter Protocol server (`astra dap`)
+//!
+//! Lets editors such as VS Code set breakpoints, step, and inspect variables
+//! in Astra programs. Messages use the same `Content-Length` framing as the
+//! LSP server, and the program runs on the thread that reads them: requests
+//! are served before launch and while the program is paused, through the
+//! same [`DebugHook`] and [`Stepper`] as the interactive debugger.
+//!
+//! There is a single thread, and only the innermost frame has variables.
+
+use std::cell::RefCell;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use serde_json::{json, Value as Json};
+
+use crate::debugger::{eval_in_frame, Resume, Stepper, StopReason};
+use crate::diagnostics::{RenderOptions, Span};
+use crate::interpreter::{
+    show_value, ConsoleCapability, DebugHook, Interpreter, RuntimeError, Value,
+};
+use crate::lsp::read_content_length;
+use crate::parser::parse_source;
+
+/// The only thread of a program
+const THREAD_ID: i64 = 1;
+
+/// Variables reference of the paused frame's bindings; structured values
+/// get the references after it
+const LOCALS_REFERENCE: i64 = 1;
+
+/// Run the DAP server on stdin/stdout. `prepare` creates the interpreter for
+/// a launched program, given its path and arguments.
+pub fn run_server(prepare: impl Fn(&Path, Vec<String>) -> Interpreter) -> io::Result<()> {
+    let connection = Connection::new(
+        Box::new(io::BufReader::new(io::stdin())),
+        Box::new(io::stdout()),
+    );
+    serve(connection, prepare)
+}
+
+/// A program the client asked to launch
+struct Launch {
+    program: PathBuf,
+    args: Vec<String>,
+    stop_on_entry: bool,
+}
+
+/// Serve requests until the client disconnects or the input ends
+fn serve(
+    connection: Connection,
+    prepare: impl Fn(&Path, Vec<String>) -> Interpreter,
+) -> io::Result<()> {
+    let connection = Rc::new(RefCell::new(connection));
+    let mut stepper = Stepper::new();
+    let mut launch = None;
+    let mut configured = false;
+
+    loop {
+        let Some(request) = connection.borrow_mut().read()? else {
+            return Ok(());
+        };
+        let mut conn = connection.borrow_mut();
+        match command(&request) {
+            "initialize" => {
+                conn.respond(
+                    &request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsEvaluateForHovers": true,
+                        "supportTerminateDebuggee": true,
+                    }),
+                )?;
+                conn.event("initialized", json!({}))?;
+            }
+            "setBreakpoints" => set_breakpoints(&mut stepper, &mut conn, &request)?,
+            "setExceptionBreakpoints" => conn.respond(&request, json!({}))?,
+            "threads" => conn.respond(&request, threads())?,
+            "launch" => {
+                let args = &request["arguments"];
+                let Some(program) = args["program"].as_str() else {
+                    conn.fail(&request, "launch needs a 'program'")?;
+                    continue;
+                };
+                let program = PathBuf::from(program);
+                launch = Some(Launch {
+                    program: program.canonicalize().unwrap_or(program),
+                    args: args["args"]
+                        .as_array()
+                        .map(|args| {
+                            args.iter()
+                                .filter_map(|arg| arg.as_str().map(str::to_string))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    stop_on_entry: args["stopOnEntry"].as_bool().unwrap_or(false),
+                });
+                conn.respond(&request, json!({}))?;
+            }
+            "configurationDone" => {
+                configured = true;
+                conn.respond(&request, json!({}))?;
+            }
+            "disconnect" | "terminate" => {
+                conn.respond(&request, json!({}))?;
+                return Ok(());
+            }
+            other => conn.fail(&request, &format!("unsupported request '{}'", other))?,
+        }
+        drop(conn);
+
+        if configured {
+            if let Some(launch) = launch.take() {
+                let stepper = std::mem::take(&mut stepper);
+                if !run(&connection, stepper, launch, &prepare)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Run a launched program to the end, returning whether to keep serving
+/// requests: not after the client ended the session from a pause
+fn run(
+    connection: &Rc<RefCell<Connection>>,
+    mut stepper: Stepper,
+    launch: Launch,
+    prepare: &impl Fn(&Path, Vec<String>) -> Interpreter,
+) -> io::Result<bool> {
+    let program = &launch.program;
+    let result = std::fs::read_to_string(program)
+        .map_err(|e| format!("Failed to read file {:?}: {}", program, e))
+        .and_then(|source| {
+            parse_source(&source, program).map_err(|e| {
+                format!(
+                    "Parse error:\n{}",
+                    e.render(&source, RenderOptions::default())
+                )
+            })
+        });
+    let module = match result {
+        Ok(module) => module,
+        Err(message) => return finish(connection, Err(message)),
+    };
+
+    stepper.set_entry("main");
+    if !launch.stop_on_entry {
+        stepper.run_to_breakpoint();
+    }
+    let mut interpreter = prepare(program, launch.args);
+    interpreter.capabilities.console = Some(Box::new(DapConsole(connection.clone())));
+    interpreter.set_debug_hook(Box::new(DapHook {
+        connection: connection.clone(),
+        stepper,
+        containers: Vec::new(),
+    }));
+    match interpreter.eval_module(&module) {
+        Ok(_) => finish(connection, Ok(())),
+        Err(e) if e.code == "E4023" => {
+            connection.borrow_mut().event("terminated", json!({}))?;
+            Ok(false)
+        }
+        Err(e) => finish(connection, Err(format!("Runtime error: {}", e))),
+    }
+}
+
+/// Report how a program ended and that the session can end
+fn finish(connection: &Rc<RefCell<Connection>>, result: Result<(), String>) -> io::Result<bool> {
+    let mut conn = connection.borrow_mut();
+    let exit_code = match result {
+        Ok(()) => 0,
+        Err(message) => {
+            conn.event(
+                "output",
+                json!({ "category": "stderr", "output": format!("{}\n", message) }),
+            )?;
+            1
+        }
+    };
+    conn.event("exited", json!({ "exitCode": exit_code }))?;
+    conn.event("terminated", json!({}))?;
+    Ok(true)
+}
+
+fn command(request: &Json) -> &str {
+    request["command"].as_str().unwrap_or("")
+}
+
+fn threads() -> Json {
+    json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })
+}
+
+/// Replace the breakpoints of the request's source file
+fn set_breakpoints(stepper: &mut Stepper, conn: &mut Connection, request: &Json) -> io::Result<()> {
+    let args = &request["arguments"];
+    let Some(path) = args["source"]["path"].as_str() else {
+        return conn.fail(request, "setBreakpoints needs a source path");
+    };
+    let path = PathBuf::from(path);
+    let path = path.canonicalize().unwrap_or(path);
+    let lines: Vec<usize> = args["breakpoints"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|bp| bp["line"].as_u64())
+        .map(|line| line as usize)
+        .collect();
+    stepper.set_file_breakpoints(&path, &lines);
+    let breakpoints: Vec<Json> = lines
+        .iter()
+        .map(|line| json!({ "verified": true, "line": line }))
+        .collect();
+    conn.respond(request, json!({ "breakpoints": breakpoints }))
+}
+
+/// DAP messages over a `Content-Length` framed stream
+struct Connection {
+    reader: Box<dyn BufRead>,
+    writer: Box<dyn Write>,
+    /// Sequence number of the last message sent
+    seq: i64,
+}
+
+impl Connection {
+    fn new(reader: Box<dyn BufRead>, writer: Box<dyn Write>) -> Self {
+        Self {
+            reader,
+            writer,
+            seq: 0,
+        }
+    }
+
+    /// Read the next message, or `None` at the end of the input. Messages
+    /// that aren't JSON are skipped.
+    fn read(&mut self) -> io::Result<Option<Json>> {
+        loop {
+            let length = match read_content_length(&mut self.reader) {
+                Ok(length) => length,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            let mut body = vec![0u8; length];
+            self.reader.read_exact(&mut body)?;
+            if let Ok(message) = serde_json::from_slice(&body) {
+                return Ok(Some(message));
+            }
+        }
+    }
+
+    fn send(&mut self, mut message: Json) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        let body = serde_json::to_string(&message)?;
+        write!(
+            self.writer,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        self.writer.flush()
+    }
+
+    fn respond(&mut self, request: &Json, body: Json) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }))
+    }
+
+    fn fail(&mut self, request: &Json, message: &str) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }))
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+}
+
+/// Console that sends the program's output to the client, since stdout
+/// carries the protocol
+struct DapConsole(Rc<RefCell<Connection>>);
+
+impl DapConsole {
+    fn output(&self, text: String) {
+        // The program has nowhere to report a broken connection
+        let _ = self
+            .0
+            .borrow_mut()
+            .event("output", json!({ "category": "stdout", "output": text }));
+    }
+}
+
+impl ConsoleCapability for DapConsole {
+    fn print(&self, text: &str) {
+        self.output(text.to_string());
+    }
+
+    fn println(&self, text: &str) {
+        self.output(format!("{}\n", text));
+    }
+
+    fn read_line(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Hook that pauses the program and serves requests until it is resumed
+struct DapHook {
+    connection: Rc<RefCell<Connection>>,
+    stepper: Stepper,
+    /// Structured values shown in the current pause, by variables reference
+    /// after [`LOCALS_REFERENCE`]
+    containers: Vec<Value>,
+}
+
+impl DapHook {
+    /// A variable for the client, with a reference to its children if any
+    fn variable(&mut self, name: String, value: Value) -> Json {
+        let text = show_value(&value);
+        let reference = if children(&value).is_empty() {
+            0
+        } else {
+            self.containers.push(value);
+            LOCALS_REFERENCE + self.containers.len() as i64
+        };
+        json!({ "name": name, "value": text, "variablesReference": reference })
+    }
+
+    /// Serve one request while paused, returning how to resume if it
+    /// resumes the program
+    fn serve(
+        &mut self,
+        interpreter: &mut Interpreter,
+        request: &Json,
+    ) -> Result<Option<Resume>, RuntimeError> {
+        let args = &request["arguments"];
+        let body = match command(request) {
+            "threads" => threads(),
+            "stackTrace" => {
+                let frames: Vec<Json> = self
+                    .stepper
+                    .backtrace(interpreter)
+                    .iter()
+                    .enumerate()
+                    .map(|(id, frame)| stack_frame(id, &frame.name, &frame.span))
+                    .collect();
+                json!({ "stackFrames": frames, "totalFrames": frames.len() })
+            }
+            "scopes" => {
+                let scopes = match args["frameId"].as_i64() {
+                    Some(0) => vec![json!({
+                        "name": "Locals",
+                        "variablesReference": LOCALS_REFERENCE,
+                        "expensive": false,
+                    })],
+                    _ => Vec::new(),
+                };
+                json!({ "scopes": scopes })
+            }
+            "variables" => {
+                let reference = args["variablesReference"].as_i64().unwrap_or(0);
+                let values: Vec<(String, Value)> = if reference == LOCALS_REFERENCE {
+                    interpreter
+                        .frame_bindings()
+                        .into_iter()
+                        .map(|(name, value)| (name.to_string(), value))
+                        .collect()
+                } else {
+                    usize::try_from(reference - LOCALS_REFERENCE - 1)
+                        .ok()
+                        .and_then(|index| self.containers.get(index))
+                        .map(children)
+                        .unwrap_or_default()
+                };
+                let variables: Vec<Json> = values
+                    .into_iter()
+                    .map(|(name, value)| self.variable(name, value))
+                    .collect();
+                json!({ "variables": variables })
+            }
+            "evaluate" => {
+                let expression = args["expression"].as_str().unwrap_or("");
+                match eval_in_frame(interpreter, expression) {
+                    Ok(value) => {
+                        let variable = self.variable(expression.to_string(), value);
+                        json!({
+                            "result": variable["value"],
+                            "variablesReference": variable["variablesReference"],
+                        })
+                    }
+                    Err(message) => {
+                        self.fail(request, &message)?;
+                        return Ok(None);
+                    }
+                }
+            }
+            "setBreakpoints" => {
+                let mut conn = self.connection.borrow_mut();
+                set_breakpoints(&mut self.stepper, &mut conn, request).map_err(io_error)?;
+                return Ok(None);
+            }
+            "continue" => {
+                self.respond(request, json!({ "allThreadsContinued": true }))?;
+                return Ok(Some(Resume::Continue));
+            }
+            "next" | "stepIn" | "stepOut" => {
+                self.respond(request, json!({}))?;
+                return Ok(Some(match command(request) {
+                    "next" => Resume::Next,
+                    "stepIn" => Resume::Step,
+                    _ => Resume::Out,
+                }));
+            }
+            "disconnect" | "terminate" => {
+                self.respond(request, json!({}))?;
+                return Err(RuntimeError::stopped_by_debugger());
+            }
+            other => {
+                self.fail(request, &format!("unsupported request '{}'", other))?;
+                return Ok(None);
+            }
+        };
+        self.respond(request, body)?;
+        Ok(None)
+    }
+
+    fn respond(&self, request: &Json, body: Json) -> Result<(), RuntimeError> {
+        self.connection
+            .borrow_mut()
+            .respond(request, body)
+            .map_err(io_error)
+    }
+
+    fn fail(&self, request: &Json, message: &str) -> Result<(), RuntimeError> {
+        self.connection
+            .borrow_mut()
+            .fail(request, message)
+            .map_err(io_error)
+    }
+}
+
+impl DebugHook for DapHook {
+    fn on_stop(&mut self, interpreter: &mut Interpreter, span: &Span) -> Result<(), RuntimeError> {
+        let Some(reason) = self.stepper.reached(interpreter, span) else {
+            return Ok(());
+        };
+        self.containers.clear();
+        let reason = match reason {
+            StopReason::Entry => "entry",
+            StopReason::Breakpoint => "breakpoint",
+            StopReason::Step => "step",
+        };
+        self.connection
+            .borrow_mut()
+            .event(
+                "stopped",
+                json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+            )
+            .map_err(io_error)?;
+
+        loop {
+            // The borrow ends before the request is served, since evaluating
+            // an expression may print to the console
+            let request = self.connection.borrow_mut().read().map_err(io_error)?;
+            let Some(request) = request else {
+                return Err(RuntimeError::stopped_by_debugger());
+            };
+            if let Some(resume) = self.serve(interpreter, &request)? {
+                self.stepper.resume(resume);
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// A lost connection stops the program, as if the client had disconnected
+fn io_error(_: io::Error) -> RuntimeError {
+    RuntimeError::stopped_by_debugger()
+}
+
+fn stack_frame(id: usize, name: &str, span: &Span) -> Json {
+    json!({
+        "id": id,
+        "name": name,
+        "source": {
+            "name": span.file.file_name().map(|name| name.to_string_lossy()),
+            "path": span.file.display().to_string(),
+        },
+        "line": span.start_line,
+        "column": span.start_col,
+    })
+}
+
+/// The children a client can expand a value into
+fn children(value: &Value) -> Vec<(String, Value)> {
+    match value {
+        Value::List(items) | Value::Set(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (format!("[{}]", i), item.clone()))
+            .collect(),
+        Value::Tuple(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (i.to_string(), item.clone()))
+            .collect(),
+        Value::Record(fields) => {
+            let mut fields: Vec<(String, Value)> = fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+            fields
+        }
+        Value::Map(entries) => entries
+            .iter()
+            .map(|(key, value)| (show_value(key), value.clone()))
+            .collect(),
+        Value::Some(inner) | Value::Ok(inner) | Value::Err(inner) => children(inner),
+        Value::Variant {
+            data: Some(data), ..
+        } => children(data),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+#[path = "dap_tests.rs"]
+mod tests;
@@ -0,0 +1,184 @@
+use super::*;
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+const PROGRAM: &str = "module main
+
+fn square(n: Int) -> Int {
+  let result = n * n
+  result
+}
+
+fn main() effects(Console) {
+  let y = square(3)
+  Console.println(to_text(y))
+}
+";
+
+/// Output stream shared with the test
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serve `requests` (numbered from 1) for a program file, returning the
+/// messages sent and the program's path
+fn session(requests: impl Fn(&Path) -> Vec<Json>) -> (Vec<Json>, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let program = dir.path().join("main.astra");
+    std::fs::write(&program, PROGRAM).unwrap();
+    let program = program.canonicalize().unwrap();
+
+    let mut input = String::new();
+    for (i, mut request) in requests(&program).into_iter().enumerate() {
+        request["seq"] = json!(i + 1);
+        request["type"] = json!("request");
+        let body = request.to_string();
+        input.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+    }
+    let output = SharedOutput::default();
+    let connection = Connection::new(Box::new(io::Cursor::new(input)), Box::new(output.clone()));
+    serve(connection, |_, _| Interpreter::new()).unwrap();
+
+    let output = String::from_utf8(output.0.borrow().clone()).unwrap();
+    let mut messages = Vec::new();
+    let mut rest = output.as_str();
+    while let Some(header) = rest.strip_prefix("Content-Length: ") {
+        let (length, body) = header.split_once("\r\n\r\n").unwrap();
+        let length: usize = length.parse().unwrap();
+        messages.push(serde_json::from_str(&body[..length]).unwrap());
+        rest = &body[length..];
+    }
+    (messages, program)
+}
+
+/// Response bodies by request sequence number
+fn responses(messages: &[Json]) -> HashMap<i64, Json> {
+    messages
+        .iter()
+        .filter(|m| m["type"] == "response")
+        .map(|m| (m["request_seq"].as_i64().unwrap(), m.clone()))
+        .collect()
+}
+
+fn events<'a>(messages: &'a [Json], name: &str) -> Vec<&'a Json> {
+    messages
+        .iter()
+        .filter(|m| m["type"] == "event" && m["event"] == name)
+        .collect()
+}
+
+fn request(command: &str, arguments: Json) -> Json {
+    json!({ "command": command, "arguments": arguments })
+}
+
+#[test]
+fn test_breakpoint_variables_and_evaluate() {
+    let (messages, program) = session(|program| {
+        vec![
+            request("initialize", json!({ "adapterID": "astra" })),
+            request(
+                "setBreakpoints",
+                json!({ "source": { "path": program }, "breakpoints": [{ "line": 4 }] }),
+            ),
+            request("launch", json!({ "program": program })),
+            request("configurationDone", json!({})),
+            request("stackTrace", json!({ "threadId": 1 })),
+            request("scopes", json!({ "frameId": 0 })),
+            request("variables", json!({ "variablesReference": 1 })),
+            request("evaluate", json!({ "expression": "n * 10", "frameId": 0 })),
+            request("evaluate", json!({ "expression": "[n, 4]", "frameId": 0 })),
+            request("variables", json!({ "variablesReference": 2 })),
+            request("evaluate", json!({ "expression": "missing", "frameId": 0 })),
+            request("continue", json!({ "threadId": 1 })),
+            request("disconnect", json!({})),
+        ]
+    });
+    let responses = responses(&messages);
+    assert_eq!(responses[&2]["body"]["breakpoints"][0]["verified"], true);
+    assert_eq!(events(&messages, "initialized").len(), 1);
+    assert_eq!(
+        events(&messages, "stopped")[0]["body"]["reason"],
+        "breakpoint"
+    );
+
+    let frames = &responses[&5]["body"]["stackFrames"];
+    assert_eq!(frames[0]["name"], "square");
+    assert_eq!(frames[0]["line"], 4);
+    assert_eq!(frames[0]["source"]["path"], program.display().to_string());
+    assert_eq!(frames[1]["name"], "main");
+    assert_eq!(frames[1]["line"], 9);
+
+    assert_eq!(responses[&6]["body"]["scopes"][0]["variablesReference"], 1);
+    let variables = &responses[&7]["body"]["variables"];
+    assert_eq!(variables[0]["name"], "n");
+    assert_eq!(variables[0]["value"], "3");
+    assert_eq!(responses[&8]["body"]["result"], "30");
+    assert_eq!(responses[&9]["body"]["variablesReference"], 2);
+    assert_eq!(responses[&10]["body"]["variables"][1]["name"], "[1]");
+    assert_eq!(responses[&10]["body"]["variables"][1]["value"], "4");
+    assert_eq!(responses[&11]["success"], false);
+
+    let output = events(&messages, "output");
+    assert_eq!(output[0]["body"]["output"], "9\n");
+    assert_eq!(events(&messages, "exited")[0]["body"]["exitCode"], 0);
+    assert_eq!(events(&messages, "terminated").len(), 1);
+    assert_eq!(responses[&13]["success"], true);
+}
+
+#[test]
+fn test_stop_on_entry_and_stepping() {
+    let (messages, _) = session(|program| {
+        vec![
+            request("initialize", json!({})),
+            request("launch", json!({ "program": program, "stopOnEntry": true })),
+            request("configurationDone", json!({})),
+            request("stepIn", json!({ "threadId": 1 })),
+            request("stepOut", json!({ "threadId": 1 })),
+            request("disconnect", json!({})),
+        ]
+    });
+    let responses = responses(&messages);
+    let stopped: Vec<&Json> = events(&messages, "stopped")
+        .iter()
+        .map(|e| &e["body"]["reason"])
+        .collect();
+    assert_eq!(stopped, vec!["entry", "step", "step"]);
+
+    // Disconnecting from a pause stops the program without running the rest
+    assert!(events(&messages, "output").is_empty());
+    assert!(events(&messages, "exited").is_empty());
+    assert_eq!(events(&messages, "terminated").len(), 1);
+    assert_eq!(responses[&6]["success"], true);
+}
+
+#[test]
+fn test_unsupported_request_fails() {
+    let (messages, _) = session(|_| vec![request("restartFrame", json!({}))]);
+    let response = &responses(&messages)[&1];
+    assert_eq!(response["success"], false);
+    assert_eq!(response["message"], "unsupported request 'restartFrame'");
+}
+
+#[test]
+fn test_children_of_structured_values() {
+    let record = Value::Record(IndexMap::from([
+        ("b".to_string(), Value::Int(2)),
+        ("a".to_string(), Value::List(vec![Value::Int(1)])),
+    ]));
+    let names: Vec<String> = children(&Value::Some(Box::new(record)))
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(names, vec!["a", "b"]);
+    assert!(children(&Value::Int(1)).is_empty());
+}
@@ -0,0 +1,471 @@
+//! Interactive step debugger (`astra debug`)
+//!
+//! The debugger is a [`DebugHook`] that the interpreter calls before each
+//! statement and block result. It pauses at breakpoints and after `step` or
+//! `next`, then reads commands that inspect the paused frame: its bindings,
+//! the call stack, and the value of any expression evaluated in it.
+
+use crate::diagnostics::Span;
+use crate::interpreter::{show_value, DebugHook, Interpreter, RuntimeError, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Help text for the `help` command
+const HELP: &str = "\
+Commands:
+  break <file:line>, b   Pause when execution reaches a line
+  delete <file:line>     Remove a breakpoint
+  step, s                Run to the next line, entering calls
+  next, n                Run to the next line in this frame, stepping over calls
+  continue, c            Run to the next breakpoint
+  locals, l              Show the bindings of the paused frame
+  backtrace, bt          Show the call stack
+  print <expr>, p        Evaluate an expression in the paused frame
+  quit, q                Stop the program";
+
+/// A line to pause at, optionally restricted to one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    /// File the line is in; any file when unset
+    pub file: Option<PathBuf>,
+    pub line: usize,
+}
+
+impl Breakpoint {
+    /// Parse `file:line` or a bare `line`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (file, line) = match text.rsplit_once(':') {
+            Some((file, line)) => (Some(PathBuf::from(file)), line),
+            None => (None, text),
+        };
+        let line = line
+            .parse()
+            .ok()
+            .filter(|&line| line > 0)
+            .ok_or_else(|| format!("invalid breakpoint '{}': expected file:line", text))?;
+        Ok(Self { file, line })
+    }
+
+    fn matches(&self, span: &Span) -> bool {
+        span.start_line == self.line
+            && self
+                .file
+                .as_ref()
+                .is_none_or(|file| span.file.ends_with(file))
+    }
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}", file.display(), self.line),
+            None => write!(f, "{}", self.line),
+        }
+    }
+}
+
+/// A debugger command read at a pause
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Break(Breakpoint),
+    Delete(Breakpoint),
+    Step,
+    Next,
+    Continue,
+    Locals,
+    Backtrace,
+    Print(String),
+    Help,
+    Quit,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, arg) = line
+            .split_once(char::is_whitespace)
+            .map_or((line, ""), |(name, arg)| (name, arg.trim()));
+        let no_arg = |command: Command| {
+            if arg.is_empty() {
+                Ok(command)
+            } else {
+                Err(format!("'{}' takes no argument", name))
+            }
+        };
+        match name {
+            "break" | "b" => Breakpoint::parse(arg).map(Command::Break),
+            "delete" | "d" => Breakpoint::parse(arg).map(Command::Delete),
+            "step" | "s" => no_arg(Command::Step),
+            "next" | "n" => no_arg(Command::Next),
+            "continue" | "c" => no_arg(Command::Continue),
+            "locals" | "l" => no_arg(Command::Locals),
+            "backtrace" | "bt" => no_arg(Command::Backtrace),
+            "print" | "p" if !arg.is_empty() => Ok(Command::Print(arg.to_string())),
+            "print" | "p" => Err("'print' needs an expression".to_string()),
+            "help" | "h" => no_arg(Command::Help),
+            "quit" | "q" => no_arg(Command::Quit),
+            _ => Err(format!("unknown command '{}' (try 'help')", name)),
+        }
+    }
+}
+
+/// When to pause next, apart from breakpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// At the next line
+    Step,
+    /// At the next line with at most this call depth
+    Next(usize),
+    /// Only at breakpoints
+    Continue,
+}
+
+/// How to resume from a pause
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    /// Pause at the next line, entering calls
+    Step,
+    /// Pause at the next line of the paused call, stepping over calls
+    Next,
+    /// Pause at the next line after the paused call returns
+    Out,
+    /// Pause at the next breakpoint
+    Continue,
+}
+
+/// Why the program paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// Before the first line
+    Entry,
+    Breakpoint,
+    /// After a step
+    Step,
+}
+
+/// A call in progress, as shown in a backtrace
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    pub name: String,
+    /// The line the call has reached, or its body before it reaches one
+    pub span: Span,
+}
+
+/// Breakpoints and stepping state shared by the debugger front ends: the
+/// interactive `astra debug` and the DAP server (`astra dap`)
+pub struct Stepper {
+    breakpoints: Vec<Breakpoint>,
+    mode: Mode,
+    /// File, line, and call depth of the last stop, so that a line is
+    /// paused at once however many statements it holds
+    last_stop: Option<(PathBuf, usize, usize)>,
+    /// Location last reached at each call depth, if any
+    locations: Vec<Option<Span>>,
+    /// Whether the program has paused yet
+    started: bool,
+    /// Name shown for code outside any call, such as the entry function
+    entry: String,
+}
+
+impl Stepper {
+    /// Stepping state that pauses before the first line
+    pub fn new() -> Self {
+        Self {
+            breakpoints: Vec::new(),
+            mode: Mode::Step,
+            last_stop: None,
+            locations: Vec::new(),
+            started: false,
+            entry: "<top level>".to_string(),
+        }
+    }
+
+    /// Pause only at breakpoints, rather than before the first line
+    pub fn run_to_breakpoint(&mut self) {
+        self.mode = Mode::Continue;
+    }
+
+    /// Name the code outside any call in backtraces, for an entry function
+    /// that runs without being called
+    pub fn set_entry(&mut self, name: &str) {
+        self.entry = name.to_string();
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// Remove a breakpoint, returning whether it was set
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints.retain(|bp| bp != breakpoint);
+        self.breakpoints.len() < count
+    }
+
+    /// Replace the breakpoints of one file
+    pub fn set_file_breakpoints(&mut self, file: &Path, lines: &[usize]) {
+        self.breakpoints
+            .retain(|bp| bp.file.as_deref() != Some(file));
+        for &line in lines {
+            self.add_breakpoint(Breakpoint {
+                file: Some(file.to_path_buf()),
+                line,
+            });
+        }
+    }
+
+    /// Record that evaluation reached `span`, returning why to pause there
+    /// if it should
+    pub fn reached(&mut self, interpreter: &Interpreter, span: &Span) -> Option<StopReason> {
+        let depth = interpreter.call_depth();
+        self.locations.resize(depth + 1, None);
+        self.locations[depth] = Some(span.clone());
+
+        let here = (span.file.clone(), span.start_line, depth);
+        if self.last_stop.replace(here.clone()) == Some(here) {
+            return None;
+        }
+        let reason = if self.breakpoints.iter().any(|bp| bp.matches(span)) {
+            StopReason::Breakpoint
+        } else {
+            match self.mode {
+                Mode::Step => StopReason::Step,
+                Mode::Next(max_depth) if depth <= max_depth => StopReason::Step,
+                _ => return None,
+            }
+        };
+        Some(if self.started {
+            reason
+        } else {
+            self.started = true;
+            match reason {
+                StopReason::Step => StopReason::Entry,
+                other => other,
+            }
+        })
+    }
+
+    /// Resume from the current pause
+    pub fn resume(&mut self, resume: Resume) {
+        let depth = self.locations.len().saturating_sub(1);
+        self.mode = match resume {
+            Resume::Step => Mode::Step,
+            Resume::Next => Mode::Next(depth),
+            Resume::Out if depth > 0 => Mode::Next(depth - 1),
+            Resume::Out | Resume::Continue => Mode::Continue,
+        };
+    }
+
+    /// Calls in progress, innermost first
+    pub fn backtrace(&self, interpreter: &Interpreter) -> Vec<StackFrame> {
+        let mut frames = Vec::new();
+        for (depth, location) in self.locations.iter().enumerate().rev() {
+            // Calls that haven't reached a line yet show their body
+            let (name, span) = match depth {
+                0 => (self.entry.as_str(), location.as_ref()),
+                _ => {
+                    let frame = &interpreter.call_stack()[depth - 1];
+                    (
+                        frame.name.as_str(),
+                        location.as_ref().or(frame.span.as_ref()),
+                    )
+                }
+            };
+            if let Some(span) = span {
+                frames.push(StackFrame {
+                    name: name.to_string(),
+                    span: span.clone(),
+                });
+            }
+        }
+        frames
+    }
+}
+
+impl Default for Stepper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Interactive debugger reading commands from `input`
+pub struct Debugger {
+    stepper: Stepper,
+    /// Source lines of files paused in, for showing the current line
+    sources: HashMap<PathBuf, Vec<String>>,
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+}
+
+impl Debugger {
+    /// A debugger that pauses before the first line
+    pub fn new(input: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
+        Self {
+            stepper: Stepper::new(),
+            sources: HashMap::new(),
+            input,
+            output,
+        }
+    }
+
+    /// Breakpoints and stepping state
+    pub fn stepper(&mut self) -> &mut Stepper {
+        &mut self.stepper
+    }
+
+    /// Source of a file, used instead of reading it from disk
+    pub fn add_source(&mut self, path: &Path, source: &str) {
+        self.sources.insert(
+            path.to_path_buf(),
+            source.lines().map(str::to_string).collect(),
+        );
+    }
+
+    fn source_line(&mut self, file: &Path, line: usize) -> Option<&str> {
+        let lines = self.sources.entry(file.to_path_buf()).or_insert_with(|| {
+            std::fs::read_to_string(file)
+                .map(|source| source.lines().map(str::to_string).collect())
+                .unwrap_or_default()
+        });
+        lines.get(line - 1).map(String::as_str)
+    }
+
+    fn show_location(&mut self, span: &Span) {
+        let mut text = format!(
+            "Paused at {}:{}:{}",
+            span.file.display(),
+            span.start_line,
+            span.start_col
+        );
+        if let Some(line) = self.source_line(&span.file, span.start_line) {
+            text.push_str(&format!("\n{:>4} | {}", span.start_line, line));
+        }
+        self.say(&text);
+    }
+
+    fn show_locals(&mut self, interpreter: &Interpreter) {
+        let bindings: Vec<String> = interpreter
+            .frame_bindings()
+            .into_iter()
+            .map(|(name, value)| format!("  {} = {}", name, show_value(&value)))
+            .collect();
+        if bindings.is_empty() {
+            self.say("No local bindings");
+        } else {
+            self.say(&bindings.join("\n"));
+        }
+    }
+
+    fn show_backtrace(&mut self, interpreter: &Interpreter) {
+        let lines: Vec<String> = self
+            .stepper
+            .backtrace(interpreter)
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                format!(
+                    "  {}: {} ({}:{}:{})",
+                    i,
+                    frame.name,
+                    frame.span.file.display(),
+                    frame.span.start_line,
+                    frame.span.start_col
+                )
+            })
+            .collect();
+        self.say(&lines.join("\n"));
+    }
+
+    fn say(&mut self, text: &str) {
+        // The debugger has nowhere to report a broken output stream
+        let _ = writeln!(self.output, "{}", text);
+    }
+
+    /// Read the next command, or `None` at the end of the input
+    fn read_command(&mut self) -> Option<String> {
+        let _ = write!(self.output, "(debug) ");
+        let _ = self.output.flush();
+        let mut line = String::new();
+        match self.input.read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line),
+        }
+    }
+}
+
+impl DebugHook for Debugger {
+    fn on_stop(&mut self, interpreter: &mut Interpreter, span: &Span) -> Result<(), RuntimeError> {
+        if self.stepper.reached(interpreter, span).is_none() {
+            return Ok(());
+        }
+        self.show_location(span);
+
+        loop {
+            let Some(line) = self.read_command() else {
+                return Err(RuntimeError::stopped_by_debugger());
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let resume = match Command::parse(&line) {
+                Ok(Command::Break(breakpoint)) => {
+                    self.say(&format!("Breakpoint set at {}", breakpoint));
+                    self.stepper.add_breakpoint(breakpoint);
+                    continue;
+                }
+                Ok(Command::Delete(breakpoint)) => {
+                    if self.stepper.remove_breakpoint(&breakpoint) {
+                        self.say(&format!("Breakpoint at {} deleted", breakpoint));
+                    } else {
+                        self.say(&format!("No breakpoint at {}", breakpoint));
+                    }
+                    continue;
+                }
+                Ok(Command::Step) => Resume::Step,
+                Ok(Command::Next) => Resume::Next,
+                Ok(Command::Continue) => Resume::Continue,
+                Ok(Command::Locals) => {
+                    self.show_locals(interpreter);
+                    continue;
+                }
+                Ok(Command::Backtrace) => {
+                    self.show_backtrace(interpreter);
+                    continue;
+                }
+                Ok(Command::Print(source)) => {
+                    match eval_in_frame(interpreter, &source) {
+                        Ok(value) => self.say(&show_value(&value)),
+                        Err(error) => self.say(&format!("error: {}", error)),
+                    }
+                    continue;
+                }
+                Ok(Command::Help) => {
+                    self.say(HELP);
+                    continue;
+                }
+                Ok(Command::Quit) => return Err(RuntimeError::stopped_by_debugger()),
+                Err(error) => {
+                    self.say(&error);
+                    continue;
+                }
+            };
+            self.stepper.resume(resume);
+            return Ok(());
+        }
+    }
+}
+
+/// Evaluate an expression in the environment of the paused frame
+pub fn eval_in_frame(interpreter: &mut Interpreter, source: &str) -> Result<Value, String> {
+    interpreter
+        .eval_str(source)
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+#[path = "debugger_tests.rs"]
+mod tests;
@@ -0,0 +1,130 @@
+use super::*;
+use crate::parser::parse_source;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const SOURCE: &str = "module main
+
+fn square(n: Int) -> Int {
+  let result = n * n
+  result
+}
+
+fn main() -> Int {
+  let x = 3
+  let y = square(x)
+  y + 1
+}
+";
+
+/// Output stream shared with the test
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Run `main` of [`SOURCE`] with the debugger reading `commands`, returning
+/// the result and the debugger's output
+fn debug(
+    commands: &str,
+    breakpoints: &[&str],
+) -> (Result<crate::interpreter::Value, RuntimeError>, String) {
+    let path = Path::new("main.astra");
+    let module = parse_source(SOURCE, path).unwrap();
+    let output = SharedOutput::default();
+    let mut debugger = Debugger::new(
+        Box::new(std::io::Cursor::new(commands.to_string())),
+        Box::new(output.clone()),
+    );
+    debugger.add_source(path, SOURCE);
+    debugger.stepper().set_entry("main");
+    if !breakpoints.is_empty() {
+        debugger.stepper().run_to_breakpoint();
+    }
+    for breakpoint in breakpoints {
+        debugger
+            .stepper()
+            .add_breakpoint(Breakpoint::parse(breakpoint).unwrap());
+    }
+    let mut interpreter = Interpreter::new();
+    interpreter.set_debug_hook(Box::new(debugger));
+    let result = interpreter.eval_module(&module);
+    let text = String::from_utf8(output.0.borrow().clone()).unwrap();
+    (result, text)
+}
+
+#[test]
+fn test_parse_breakpoint() {
+    assert_eq!(
+        Breakpoint::parse("src/main.astra:12"),
+        Ok(Breakpoint {
+            file: Some(PathBuf::from("src/main.astra")),
+            line: 12
+        })
+    );
+    assert_eq!(
+        Breakpoint::parse("7"),
+        Ok(Breakpoint {
+            file: None,
+            line: 7
+        })
+    );
+    assert!(Breakpoint::parse("main.astra:0").is_err());
+    assert!(Breakpoint::parse("main.astra").is_err());
+}
+
+#[test]
+fn test_parse_command() {
+    assert_eq!(Command::parse("s"), Ok(Command::Step));
+    assert_eq!(Command::parse(" next "), Ok(Command::Next));
+    assert_eq!(
+        Command::parse("print x + 1"),
+        Ok(Command::Print("x + 1".to_string()))
+    );
+    assert!(Command::parse("print").is_err());
+    assert!(Command::parse("step 2").is_err());
+    assert!(Command::parse("jump").is_err());
+}
+
+#[test]
+fn test_step_enters_calls_and_next_steps_over_them() {
+    let (result, output) = debug("s\ns\nc\n", &[]);
+    assert!(matches!(result, Ok(crate::interpreter::Value::Int(10))));
+    assert!(output.contains("Paused at main.astra:9:3\n   9 |   let x = 3"));
+    assert!(output.contains("Paused at main.astra:10:3"));
+    assert!(output.contains("Paused at main.astra:4:3"));
+
+    let (_, output) = debug("n\nn\nc\n", &[]);
+    assert!(output.contains("Paused at main.astra:11:3"));
+    assert!(!output.contains("main.astra:4:3"));
+}
+
+#[test]
+fn test_breakpoint_shows_locals_backtrace_and_values() {
+    let (result, output) = debug("locals\nbt\np n * 10\np missing\nc\n", &["main.astra:4"]);
+    assert!(matches!(result, Ok(crate::interpreter::Value::Int(10))));
+    assert!(output.starts_with("Paused at main.astra:4:3"));
+    assert!(output.contains("  n = 3\n"));
+    assert!(!output.contains("x = 3"));
+    assert!(output.contains("  0: square (main.astra:4:3)\n  1: main (main.astra:10:3)"));
+    assert!(output.contains("(debug) 30\n"));
+    assert!(output.contains("error: "));
+}
+
+#[test]
+fn test_quit_stops_the_program() {
+    let (result, _) = debug("q\n", &[]);
+    assert_eq!(result.unwrap_err().code, "E4023");
+    // The end of the input quits as well
+    let (result, _) = debug("", &[]);
+    assert_eq!(result.unwrap_err().code, "E4023");
+}
@@ -0,0 +1,219 @@
+//! Message catalog: the text of diagnostics, keyed by error code.
+//!
+//! A diagnostic built with [`DiagnosticBuilder::catalog`](super::DiagnosticBuilder::catalog)
+//! names a catalog entry and the arguments to fill into it. Its `message` is
+//! always the English text, so JSON output and anything matching on messages
+//! stay the same in every locale; only terminal rendering looks the message up
+//! again in the selected [`Locale`], falling back to English for entries that
+//! have no translation.
+//!
+//! Entry ids are the error code, with a `.form` suffix when one code has
+//! several messages (`E1001.argument`). Templates fill `{name}` from the
+//! arguments; `{{` and `}}` are literal braces.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A language diagnostics can be rendered in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Locale {
+    /// Every supported locale, by tag
+    pub const ALL: [(&'static str, Locale); 2] = [("en", Locale::English), ("es", Locale::Spanish)];
+
+    /// The locale for a tag like `es`, `es-MX`, or `es_ES.UTF-8`
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let language = tag
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        Self::ALL
+            .iter()
+            .find(|(known, _)| *known == language)
+            .map(|(_, locale)| *locale)
+    }
+
+    /// The locale named by `ASTRA_LANG`, or English
+    pub fn from_env() -> Locale {
+        std::env::var("ASTRA_LANG")
+            .ok()
+            .and_then(|tag| Self::from_tag(&tag))
+            .unwrap_or_default()
+    }
+}
+
+/// The catalog entry a diagnostic message was made from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogMessage {
+    /// Entry id, e.g. `E1002` or `E1001.argument`
+    pub id: String,
+
+    /// Values for the template's parameters
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub args: BTreeMap<String, String>,
+}
+
+impl CatalogMessage {
+    /// The message in `locale`, or in English if the entry has no
+    /// translation; `None` for an id the catalog doesn't know
+    pub fn text(&self, locale: Locale) -> Option<String> {
+        template(&self.id, locale).map(|template| interpolate(template, &self.args))
+    }
+}
+
+/// Entry id, English template, Spanish template
+const MESSAGES: &[(&str, &str, Option<&str>)] = &[
+    (
+        "E0011",
+        "Module not found: `std.{module}`",
+        Some("Módulo no encontrado: `std.{module}`"),
+    ),
+    (
+        "E1001.annotation",
+        "Expected type {expected}, found {found}",
+        Some("Se esperaba el tipo {expected}, se encontró {found}"),
+    ),
+    (
+        "E1001.argument",
+        "Type mismatch in argument {index}: expected `{expected}`, found `{found}`",
+        Some("Tipos incompatibles en el argumento {index}: se esperaba `{expected}`, se encontró `{found}`"),
+    ),
+    (
+        "E1001.condition",
+        "Condition must be Bool",
+        Some("La condición debe ser Bool"),
+    ),
+    (
+        "E1001.if_branches",
+        "If branches have different types",
+        Some("Las ramas del `if` tienen tipos distintos"),
+    ),
+    (
+        "E1001.operator",
+        "Cannot apply `{op}` to `{left}` and `{right}`",
+        Some("No se puede aplicar `{op}` a `{left}` y `{right}`"),
+    ),
+    (
+        "E1002",
+        "Unknown identifier: {name}",
+        Some("Identificador desconocido: {name}"),
+    ),
+    (
+        "E1004",
+        "Non-exhaustive match: missing pattern(s) `{missing}`",
+        Some("Match no exhaustivo: faltan los patrones `{missing}`"),
+    ),
+    (
+        "E1006",
+        "Unknown field '{field}' on type {type}",
+        Some("Campo desconocido '{field}' en el tipo {type}"),
+    ),
+    (
+        "E1007",
+        "Expected {expected} argument(s) but found {found}",
+        Some("Se esperaban {expected} argumento(s) pero se encontraron {found}"),
+    ),
+    (
+        "E1007.method",
+        "`{receiver}.{method}` expects {expected} argument(s) but found {found}",
+        Some("`{receiver}.{method}` espera {expected} argumento(s) pero se encontraron {found}"),
+    ),
+    (
+        "E1017",
+        "Cannot assign to `{name}`, which is not `mut`",
+        Some("No se puede asignar a `{name}`, que no es `mut`"),
+    ),
+    (
+        "E1018",
+        "Unknown method `{method}` on `{receiver}`",
+        Some("Método desconocido `{method}` en `{receiver}`"),
+    ),
+    (
+        "E2001",
+        "Effect `{effect}` used but not declared in function `{function}`",
+        Some("El efecto `{effect}` se usa pero no está declarado en la función `{function}`"),
+    ),
+    (
+        "W0001",
+        "Unused variable `{name}`",
+        Some("Variable `{name}` sin usar"),
+    ),
+    (
+        "W0002",
+        "Unused import `{name}`",
+        Some("Importación `{name}` sin usar"),
+    ),
+    (
+        "W0003",
+        "Unreachable code after return statement",
+        Some("Código inalcanzable después de `return`"),
+    ),
+    (
+        "W0003.expression",
+        "Unreachable expression after return statement",
+        Some("Expresión inalcanzable después de `return`"),
+    ),
+    (
+        "W0006",
+        "Variable `{name}` shadows a previous binding in the same scope",
+        Some("La variable `{name}` oculta una declaración anterior del mismo ámbito"),
+    ),
+    (
+        "W0008",
+        "Function `{name}` is defined but never used",
+        Some("La función `{name}` está definida pero nunca se usa"),
+    ),
+    (
+        "W0010",
+        "`dbg` call left in code",
+        Some("Llamada a `dbg` olvidada en el código"),
+    ),
+];
+
+/// The template for entry `id` in `locale`, falling back to English
+pub fn template(id: &str, locale: Locale) -> Option<&'static str> {
+    let (_, english, spanish) = MESSAGES.iter().find(|(known, _, _)| *known == id)?;
+    Some(match locale {
+        Locale::English => english,
+        Locale::Spanish => spanish.unwrap_or(english),
+    })
+}
+
+/// Fill `{name}` placeholders in `template` from `args`. A placeholder
+/// without an argument is kept as written.
+pub fn interpolate(template: &str, args: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(at) = rest.find(['{', '}']) {
+        out.push_str(&rest[..at]);
+        let tail = &rest[at..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        match (tail.starts_with('{'), tail.find('}')) {
+            (true, Some(end)) => {
+                let name = &tail[1..end];
+                match args.get(name) {
+                    Some(value) => out.push_str(value),
+                    None => out.push_str(&tail[..=end]),
+                }
+                rest = &tail[end + 1..];
+            }
+            _ => {
+                out.push_str(&tail[..1]);
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
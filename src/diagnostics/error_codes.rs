@@ -13,6 +13,10 @@ pub mod syntax {
     pub const INVALID_MODULE: &str = "E0009";
     pub const DUPLICATE_MODULE: &str = "E0010";
     pub const MODULE_NOT_FOUND: &str = "E0011";
+    pub const UNKNOWN_MACRO: &str = "E0012";
+    pub const NO_MATCHING_MACRO_RULE: &str = "E0013";
+    pub const MACRO_RECURSION_LIMIT: &str = "E0014";
+    pub const DUPLICATE_MACRO: &str = "E0015";
 }
 
 /// Type errors (E1xxx)
@@ -33,6 +37,8 @@ pub mod types {
     pub const EXPECTED_RECORD: &str = "E1014";
     pub const EXPECTED_ENUM: &str = "E1015";
     pub const TRAIT_CONSTRAINT_NOT_SATISFIED: &str = "E1016";
+    pub const ASSIGN_TO_IMMUTABLE: &str = "E1017";
+    pub const UNKNOWN_METHOD: &str = "E1018";
 }
 
 /// Effect errors (E2xxx)
@@ -53,6 +59,7 @@ pub mod contracts {
     pub const INVARIANT_VIOLATION: &str = "E3003";
     pub const INVALID_CONTRACT_EXPR: &str = "E3004";
     pub const CONTRACT_BINDING_UNAVAILABLE: &str = "E3005";
+    pub const PRECONDITION_ALWAYS_VIOLATED: &str = "E3006";
 }
 
 /// Runtime errors (E4xxx)
@@ -63,8 +70,18 @@ pub mod runtime {
     pub const RESOURCE_LIMIT_EXCEEDED: &str = "E4004";
     pub const CAPABILITY_DENIED: &str = "E4005";
     pub const INTEGER_OVERFLOW: &str = "E4006";
-    pub const STACK_OVERFLOW: &str = "E4007";
+    pub const MATCH_FAILURE: &str = "E4007";
     pub const ASSERTION_FAILED: &str = "E4008";
+    pub const EVAL_LIMIT_EXCEEDED: &str = "E4022";
+    pub const STOPPED_BY_DEBUGGER: &str = "E4023";
+    pub const TEXT_INDEX_OUT_OF_BOUNDS: &str = "E4024";
+    pub const INVALID_TEXT_RANGE: &str = "E4025";
+    pub const INVALID_BASE64: &str = "E4026";
+    pub const INVALID_HEX: &str = "E4027";
+    pub const STACK_OVERFLOW: &str = "E4028";
+    pub const MISSING_ENTRY: &str = "E4029";
+    pub const BUDGET_EXCEEDED: &str = "E4030";
+    pub const IMMUTABLE_ASSIGNMENT: &str = "E4031";
 }
 
 /// Warnings (W0xxx)
@@ -77,4 +94,37 @@ pub mod warnings {
     pub const SHADOWED_BINDING: &str = "W0006";
     pub const REDUNDANT_TYPE_ANNOTATION: &str = "W0007";
     pub const UNUSED_FUNCTION: &str = "W0008";
+    pub const UNUSED_SUPPRESSION: &str = "W0009";
+    pub const DBG_CALL: &str = "W0010";
+    pub const REF_ESCAPE: &str = "W0012";
+
+    /// Resolve a lint name (as used in `@allow(...)`) or code to its code
+    pub fn code_for_name(name: &str) -> Option<&'static str> {
+        match name {
+            "unused_variable" | "W0001" => Some(UNUSED_VARIABLE),
+            "unused_import" | "W0002" => Some(UNUSED_IMPORT),
+            "unreachable_code" | "W0003" => Some(UNREACHABLE_CODE),
+            "deprecated" | "W0004" => Some(DEPRECATED),
+            "wildcard_match" | "W0005" => Some(WILDCARD_MATCH),
+            "shadowed_binding" | "W0006" => Some(SHADOWED_BINDING),
+            "redundant_type_annotation" | "W0007" => Some(REDUNDANT_TYPE_ANNOTATION),
+            "unused_function" | "W0008" => Some(UNUSED_FUNCTION),
+            "unused_suppression" | "W0009" => Some(UNUSED_SUPPRESSION),
+            "dbg_call" | "W0010" => Some(DBG_CALL),
+            "ref_escape" | "W0012" => Some(REF_ESCAPE),
+            _ => None,
+        }
+    }
+}
+
+/// Typed holes (H0xxx)
+pub mod holes {
+    pub const TYPED_HOLE: &str = "H0001";
+}
+
+/// Internal toolchain errors (E9xxx), reported when a tool misbehaves
+pub mod internal {
+    pub const FORMATTER_NOT_IDEMPOTENT: &str = "E9001";
+    pub const FORMATTER_CHANGED_AST: &str = "E9002";
+    pub const FORMATTER_INVALID_OUTPUT: &str = "E9003";
 }
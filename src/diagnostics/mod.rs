@@ -4,15 +4,24 @@
 //! source spans, and machine-readable JSON output.
 
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
 
+pub mod catalog;
 pub mod error_codes;
+pub mod render;
+pub use catalog::{CatalogMessage, Locale};
 pub use error_codes::*;
+pub use render::RenderOptions;
 
-/// A source location span
+/// A source location span. When read from JSON (as in patches for
+/// `astra apply`), only the byte offsets are required.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Span {
     /// Source file path
+    #[serde(default)]
     pub file: PathBuf,
 
     /// Start byte offset (0-indexed)
@@ -22,15 +31,19 @@ pub struct Span {
     pub end: usize,
 
     /// Start line (1-indexed)
+    #[serde(default)]
     pub start_line: usize,
 
     /// Start column (1-indexed)
+    #[serde(default)]
     pub start_col: usize,
 
     /// End line (1-indexed)
+    #[serde(default)]
     pub end_line: usize,
 
     /// End column (1-indexed)
+    #[serde(default)]
     pub end_col: usize,
 }
 
@@ -76,19 +89,32 @@ impl Span {
             start: self.start.min(other.start),
             end: self.end.max(other.end),
             start_line: self.start_line.min(other.start_line),
-            start_col: if self.start_line <= other.start_line {
+            start_col: if (self.start_line, self.start_col) <= (other.start_line, other.start_col) {
                 self.start_col
             } else {
                 other.start_col
             },
             end_line: self.end_line.max(other.end_line),
-            end_col: if self.end_line >= other.end_line {
+            end_col: if (self.end_line, self.end_col) >= (other.end_line, other.end_col) {
                 self.end_col
             } else {
                 other.end_col
             },
         }
     }
+
+    /// An empty span at the end of this span, for insertions
+    pub fn end_point(&self) -> Span {
+        Span {
+            file: self.file.clone(),
+            start: self.end,
+            end: self.end,
+            start_line: self.end_line,
+            start_col: self.end_col,
+            end_line: self.end_line,
+            end_col: self.end_col,
+        }
+    }
 }
 
 /// Severity level for diagnostics
@@ -128,6 +154,39 @@ impl Note {
     }
 }
 
+/// A labeled secondary span (e.g., "previous binding here")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Label {
+    /// The related source location
+    pub span: Span,
+
+    /// What this location has to do with the diagnostic
+    pub message: String,
+}
+
+impl Label {
+    /// Create a new label
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+/// How confident a suggestion is that its edits are correct
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Applicability {
+    /// The edits are definitely what the user intended and can be applied
+    /// automatically
+    MachineApplicable,
+    /// The edits may not match the user's intent and should be reviewed
+    #[default]
+    MaybeIncorrect,
+    /// The suggestion contains placeholders the user has to fill in
+    HasPlaceholders,
+}
+
 /// A suggested code fix
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Suggestion {
@@ -136,6 +195,10 @@ pub struct Suggestion {
 
     /// Edits to apply
     pub edits: Vec<Edit>,
+
+    /// Whether the edits are safe to apply without review
+    #[serde(default)]
+    pub applicability: Applicability,
 }
 
 impl Suggestion {
@@ -144,6 +207,7 @@ impl Suggestion {
         Self {
             title: title.into(),
             edits: Vec::new(),
+            applicability: Applicability::default(),
         }
     }
 
@@ -152,6 +216,17 @@ impl Suggestion {
         self.edits.push(edit);
         self
     }
+
+    /// Set the applicability of this suggestion
+    pub fn with_applicability(mut self, applicability: Applicability) -> Self {
+        self.applicability = applicability;
+        self
+    }
+
+    /// Whether `astra fix` may apply this suggestion without `--unsafe-fixes`
+    pub fn is_machine_applicable(&self) -> bool {
+        self.applicability == Applicability::MachineApplicable
+    }
 }
 
 /// A code edit (replacement)
@@ -178,6 +253,107 @@ impl Edit {
     }
 }
 
+/// Why a set of edits could not be applied
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditError {
+    /// The edit at this index replaces text outside the source or splits a
+    /// character
+    OutOfBounds(usize),
+    /// The edits at these indices replace overlapping text
+    Overlap(usize, usize),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::OutOfBounds(index) => {
+                write!(f, "edit {} is outside the source", index)
+            }
+            EditError::Overlap(first, second) => {
+                write!(f, "edits {} and {} overlap", first, second)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// Apply `edits` to `source`. Spans are byte offsets into the original
+/// source, so the edits can come in any order; identical edits apply once,
+/// and insertions at the same offset apply in the order given. Edits whose
+/// spans overlap are refused rather than applied in some arbitrary order.
+pub fn apply_edits(source: &str, edits: &[Edit]) -> Result<String, EditError> {
+    let order = edit_order(source, edits)?;
+    let mut result = String::with_capacity(source.len());
+    let mut at = 0;
+    for index in order {
+        let edit = &edits[index];
+        result.push_str(&source[at..edit.span.start]);
+        result.push_str(&edit.replacement);
+        at = edit.span.end;
+    }
+    result.push_str(&source[at..]);
+    Ok(result)
+}
+
+/// Where `offset` in the original source ends up after `edits`, which must
+/// apply cleanly. Offsets inside replaced text map to the start of its
+/// replacement; an offset where text is inserted maps to the start of the
+/// inserted text.
+pub fn remap_offset(edits: &[Edit], offset: usize) -> usize {
+    let mut shift: isize = 0;
+    let mut seen: Vec<&Edit> = Vec::new();
+    for edit in edits {
+        if seen.iter().any(|other| same_edit(other, edit)) {
+            continue;
+        }
+        seen.push(edit);
+        let inserted_here = edit.span.start == offset && edit.span.end == offset;
+        if edit.span.end <= offset && !inserted_here {
+            shift += edit.replacement.len() as isize - (edit.span.end - edit.span.start) as isize;
+        } else if edit.span.start < offset {
+            // Inside replaced text: land where the replacement starts
+            shift -= (offset - edit.span.start) as isize;
+        }
+    }
+    (offset as isize + shift) as usize
+}
+
+/// Indices of the edits to apply, in source order, without duplicates
+fn edit_order(source: &str, edits: &[Edit]) -> Result<Vec<usize>, EditError> {
+    for (index, edit) in edits.iter().enumerate() {
+        let Span { start, end, .. } = edit.span;
+        if start > end
+            || end > source.len()
+            || !source.is_char_boundary(start)
+            || !source.is_char_boundary(end)
+        {
+            return Err(EditError::OutOfBounds(index));
+        }
+    }
+    let mut order: Vec<usize> = (0..edits.len()).collect();
+    order.sort_by_key(|&index| (edits[index].span.start, edits[index].span.end));
+    order.dedup_by(|later, earlier| same_edit(&edits[*later], &edits[*earlier]));
+
+    // The edit reaching furthest so far; the next one may start at its end
+    let mut furthest: Option<usize> = None;
+    for &index in &order {
+        if let Some(previous) = furthest {
+            if edits[index].span.start < edits[previous].span.end {
+                return Err(EditError::Overlap(previous.min(index), previous.max(index)));
+            }
+        }
+        if furthest.is_none_or(|previous| edits[index].span.end >= edits[previous].span.end) {
+            furthest = Some(index);
+        }
+    }
+    Ok(order)
+}
+
+fn same_edit(a: &Edit, b: &Edit) -> bool {
+    a.span.start == b.span.start && a.span.end == b.span.end && a.replacement == b.replacement
+}
+
 /// A compiler diagnostic
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Diagnostic {
@@ -187,12 +363,21 @@ pub struct Diagnostic {
     /// Severity level
     pub severity: Severity,
 
-    /// Primary message
+    /// Primary message, in English
     pub message: String,
 
+    /// The catalog entry the message was made from, for rendering it in
+    /// another locale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog: Option<CatalogMessage>,
+
     /// Primary source span
     pub span: Span,
 
+    /// Labeled secondary spans
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<Label>,
+
     /// Additional notes
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub notes: Vec<Note>,
@@ -200,6 +385,50 @@ pub struct Diagnostic {
     /// Suggested fixes
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub suggestions: Vec<Suggestion>,
+
+    /// Secondary diagnostics grouped under this one (see [`group_diagnostics`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<Diagnostic>,
+
+    /// What is known at a typed hole (`???`), for H0001 diagnostics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hole: Option<HoleContext>,
+}
+
+/// The context of a typed hole: what it must be and what could fill it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HoleContext {
+    /// Type the surrounding code expects, when it determines one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_type: Option<String>,
+
+    /// Local bindings in scope whose type fits the hole
+    #[serde(default)]
+    pub bindings: Vec<HoleBinding>,
+
+    /// Well-typed expressions that could fill the hole, best first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<String>,
+
+    /// Signature of the enclosing function
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+
+    /// Preconditions of the enclosing function
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+
+    /// Postconditions of the enclosing function
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ensures: Vec<String>,
+}
+
+/// A binding in scope at a typed hole
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HoleBinding {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
 }
 
 impl Diagnostic {
@@ -209,9 +438,12 @@ impl Diagnostic {
             code: code.into(),
             severity: Severity::Error,
             message: String::new(),
+            catalog: None,
             span: None,
+            labels: Vec::new(),
             notes: Vec::new(),
             suggestions: Vec::new(),
+            hole: None,
         }
     }
 
@@ -221,9 +453,12 @@ impl Diagnostic {
             code: code.into(),
             severity: Severity::Warning,
             message: String::new(),
+            catalog: None,
             span: None,
+            labels: Vec::new(),
             notes: Vec::new(),
             suggestions: Vec::new(),
+            hole: None,
         }
     }
 
@@ -233,9 +468,12 @@ impl Diagnostic {
             code: code.into(),
             severity: Severity::Info,
             message: String::new(),
+            catalog: None,
             span: None,
+            labels: Vec::new(),
             notes: Vec::new(),
             suggestions: Vec::new(),
+            hole: None,
         }
     }
 
@@ -249,60 +487,23 @@ impl Diagnostic {
         serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
     }
 
-    /// Format as human-readable string
-    pub fn to_human_readable(&self, source: &str) -> String {
-        let severity = match self.severity {
-            Severity::Error => "error",
-            Severity::Warning => "warning",
-            Severity::Info => "info",
-            Severity::Hint => "hint",
-        };
-
-        let mut output = format!(
-            "{}[{}]: {}\n  --> {}:{}:{}\n",
-            severity,
-            self.code,
-            self.message,
-            self.span.file.display(),
-            self.span.start_line,
-            self.span.start_col
-        );
-
-        // Show source context
-        let lines: Vec<&str> = source.lines().collect();
-        if self.span.start_line > 0 && self.span.start_line <= lines.len() {
-            let line = lines[self.span.start_line - 1];
-            output.push_str(&format!(
-                "   |\n{:>3} | {}\n   |",
-                self.span.start_line, line
-            ));
-
-            // Underline the error
-            let underline_start = self.span.start_col.saturating_sub(1);
-            let underline_len = if self.span.end_line == self.span.start_line {
-                self.span.end_col.saturating_sub(self.span.start_col).max(1)
-            } else {
-                line.len().saturating_sub(underline_start)
-            };
-
-            output.push_str(&format!(
-                " {}{}\n",
-                " ".repeat(underline_start),
-                "^".repeat(underline_len)
-            ));
-        }
-
-        // Add notes
-        for note in &self.notes {
-            output.push_str(&format!("   = note: {}\n", note.message));
+    /// The message in `locale`, when it came from the catalog and the entry
+    /// is translated; the English message otherwise
+    pub fn localized_message(&self, locale: Locale) -> Cow<'_, str> {
+        match self.catalog.as_ref().and_then(|entry| entry.text(locale)) {
+            Some(text) => Cow::Owned(text),
+            None => Cow::Borrowed(&self.message),
         }
+    }
 
-        // Add suggestions
-        for suggestion in &self.suggestions {
-            output.push_str(&format!("   = help: {}\n", suggestion.title));
-        }
+    /// Format as human-readable string
+    pub fn to_human_readable(&self, source: &str) -> String {
+        self.render(source, RenderOptions::default())
+    }
 
-        output
+    /// Format as human-readable string with a code frame, optionally colored
+    pub fn render(&self, source: &str, options: RenderOptions) -> String {
+        render::render(self, source, options)
     }
 }
 
@@ -311,9 +512,12 @@ pub struct DiagnosticBuilder {
     code: String,
     severity: Severity,
     message: String,
+    catalog: Option<CatalogMessage>,
     span: Option<Span>,
+    labels: Vec<Label>,
     notes: Vec<Note>,
     suggestions: Vec<Suggestion>,
+    hole: Option<HoleContext>,
 }
 
 impl DiagnosticBuilder {
@@ -323,12 +527,36 @@ impl DiagnosticBuilder {
         self
     }
 
+    /// Take the message from catalog entry `id`, filled in with the
+    /// arguments given by [`arg`](Self::arg)
+    pub fn catalog(mut self, id: impl Into<String>) -> Self {
+        self.catalog = Some(CatalogMessage {
+            id: id.into(),
+            args: Default::default(),
+        });
+        self
+    }
+
+    /// Set a parameter of the catalog message
+    pub fn arg(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        if let Some(entry) = &mut self.catalog {
+            entry.args.insert(name.into(), value.to_string());
+        }
+        self
+    }
+
     /// Set the primary span
     pub fn span(mut self, span: Span) -> Self {
         self.span = Some(span);
         self
     }
 
+    /// Add a labeled secondary span
+    pub fn label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label::new(span, message));
+        self
+    }
+
     /// Add a note
     pub fn note(mut self, note: Note) -> Self {
         self.notes.push(note);
@@ -341,19 +569,87 @@ impl DiagnosticBuilder {
         self
     }
 
+    /// Attach the context of a typed hole
+    pub fn hole(mut self, hole: HoleContext) -> Self {
+        self.hole = Some(hole);
+        self
+    }
+
     /// Build the diagnostic
     pub fn build(self) -> Diagnostic {
+        let message = match self
+            .catalog
+            .as_ref()
+            .and_then(|entry| entry.text(Locale::English))
+        {
+            Some(text) => text,
+            None => self.message,
+        };
         Diagnostic {
             code: self.code,
             severity: self.severity,
-            message: self.message,
+            message,
+            catalog: self.catalog,
             span: self.span.unwrap_or_else(|| Span::file("")),
+            labels: self.labels,
             notes: self.notes,
             suggestions: self.suggestions,
+            related: Vec::new(),
+            hole: self.hole,
         }
     }
 }
 
+/// Default cap on repeated diagnostics kept under a primary one
+pub const MAX_GROUPED_REPEATS: usize = 5;
+
+/// Group diagnostics that repeat an earlier one (same file, code, and message)
+/// under the first occurrence, so a single root cause — e.g., a misspelled
+/// name used throughout a file — is reported once.
+///
+/// At most `max_repeats` repeats are kept in `related`; the rest are counted
+/// in a note on the primary diagnostic.
+pub fn group_diagnostics(diagnostics: Vec<Diagnostic>, max_repeats: usize) -> Vec<Diagnostic> {
+    let mut grouped: Vec<Diagnostic> = Vec::new();
+    let mut omitted: Vec<usize> = Vec::new();
+    let mut index: HashMap<(PathBuf, String, String), usize> = HashMap::new();
+
+    for diagnostic in diagnostics {
+        // Every hole is a separate site to fill
+        if diagnostic.hole.is_some() {
+            grouped.push(diagnostic);
+            omitted.push(0);
+            continue;
+        }
+        let key = (
+            diagnostic.span.file.clone(),
+            diagnostic.code.clone(),
+            diagnostic.message.clone(),
+        );
+        match index.get(&key) {
+            Some(&i) if grouped[i].related.len() < max_repeats => {
+                grouped[i].related.push(diagnostic);
+            }
+            Some(&i) => omitted[i] += 1,
+            None => {
+                index.insert(key, grouped.len());
+                grouped.push(diagnostic);
+                omitted.push(0);
+            }
+        }
+    }
+
+    for (diagnostic, count) in grouped.iter_mut().zip(omitted) {
+        if count > 0 {
+            diagnostic.notes.push(Note::new(format!(
+                "{} more identical diagnostic(s) omitted",
+                count
+            )));
+        }
+    }
+    grouped
+}
+
 /// A collection of diagnostics
 #[derive(Debug, Default, Clone)]
 pub struct DiagnosticBag {
@@ -429,9 +725,14 @@ impl DiagnosticBag {
 
     /// Format all diagnostics as human-readable text
     pub fn format_text(&self, source: &str) -> String {
+        self.render(source, RenderOptions::default())
+    }
+
+    /// Format all diagnostics as human-readable text, optionally colored
+    pub fn render(&self, source: &str, options: RenderOptions) -> String {
         self.diagnostics
             .iter()
-            .map(|d| d.to_human_readable(source))
+            .map(|d| d.render(source, options))
             .collect::<Vec<_>>()
             .join("\n\n")
     }
@@ -0,0 +1,327 @@
+//! Terminal rendering of diagnostics with rustc-style code frames.
+//!
+//! Produces a header, a `-->` location line, the source lines covered by the
+//! primary span (`^`) and any labeled secondary spans (`-`), each with an
+//! underline or a `/ | |_^` bracket for multi-line spans, and aligned
+//! `= note:` / `= help:` lines. ANSI colors keyed to
+//! severity are emitted only when [`RenderOptions::color`] is set. Messages
+//! from the catalog are shown in [`RenderOptions::locale`].
+
+use super::{Diagnostic, Locale, Severity, Span};
+
+/// Options for rendering diagnostics as text
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderOptions {
+    /// Emit ANSI color escapes
+    pub color: bool,
+    /// Language of the messages that come from the catalog
+    pub locale: Locale,
+}
+
+/// Maximum number of source lines shown for a multi-line span before eliding
+const MAX_FRAME_LINES: usize = 6;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const GUTTER: &str = "\x1b[1;34m";
+
+fn severity_style(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "\x1b[1;31m",
+        Severity::Warning => "\x1b[1;33m",
+        Severity::Info => "\x1b[1;36m",
+        Severity::Hint => "\x1b[1;32m",
+    }
+}
+
+fn severity_label(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "info",
+        Severity::Hint => "hint",
+    }
+}
+
+/// Wraps text in ANSI styles when color is enabled
+struct Painter {
+    color: bool,
+}
+
+impl Painter {
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// An underlined span inside a code frame
+struct Annotation<'a> {
+    span: &'a Span,
+    underline: char,
+    style: &'static str,
+    message: Option<&'a str>,
+}
+
+/// Shared state for drawing a code frame
+struct Frame<'a> {
+    p: Painter,
+    lines: Vec<&'a str>,
+    width: usize,
+}
+
+impl Frame<'_> {
+    fn pad(&self) -> String {
+        " ".repeat(self.width)
+    }
+
+    fn bar(&self) -> String {
+        self.p.paint(GUTTER, "|")
+    }
+
+    fn gutter(&self, n: usize) -> String {
+        self.p
+            .paint(GUTTER, &format!("{:>width$} |", n, width = self.width))
+    }
+
+    fn last_line(&self, span: &Span) -> usize {
+        span.end_line
+            .clamp(span.start_line, self.lines.len().max(1))
+    }
+
+    /// Draw single-line annotations that share a source line: the line once,
+    /// then one underline row per annotation
+    fn annotate_line(&self, output: &mut String, line_no: usize, annotations: &[&Annotation]) {
+        let line = self.lines[line_no - 1];
+        output.push_str(&format!("{} {}\n", self.gutter(line_no), line));
+        for a in annotations {
+            let span = a.span;
+            let start = span.start_col.saturating_sub(1);
+            let len = if span.end_line == span.start_line {
+                span.end_col.saturating_sub(span.start_col).max(1)
+            } else {
+                line.chars().count().saturating_sub(start).max(1)
+            };
+            let message = a.message.map(|m| format!(" {}", m)).unwrap_or_default();
+            output.push_str(&format!(
+                "{} {} {}{}\n",
+                self.pad(),
+                self.bar(),
+                " ".repeat(start),
+                self.p.paint(
+                    a.style,
+                    &format!("{}{}", a.underline.to_string().repeat(len), message)
+                )
+            ));
+        }
+    }
+
+    /// Draw a multi-line annotation as a `/ | |_^` bracket
+    fn annotate_multiline(&self, output: &mut String, a: &Annotation) {
+        let span = a.span;
+        let last_line = self.last_line(span);
+        let message = a.message.map(|m| format!(" {}", m)).unwrap_or_default();
+
+        let shown: Vec<usize> = (span.start_line..=last_line).collect();
+        let elide = shown.len() > MAX_FRAME_LINES;
+        for (i, &n) in shown.iter().enumerate() {
+            if elide && i == MAX_FRAME_LINES - 2 {
+                output.push_str(&format!(
+                    "{} {}\n",
+                    self.p.paint(GUTTER, "..."),
+                    self.p.paint(a.style, "|")
+                ));
+            }
+            if elide && i >= MAX_FRAME_LINES - 2 && i < shown.len() - 2 {
+                continue;
+            }
+            let marker = if i == 0 { "/" } else { "|" };
+            output.push_str(&format!(
+                "{} {} {}\n",
+                self.gutter(n),
+                self.p.paint(a.style, marker),
+                self.lines[n - 1]
+            ));
+        }
+        let end = span.end_col.saturating_sub(1).max(1);
+        output.push_str(&format!(
+            "{} {} {}\n",
+            self.pad(),
+            self.bar(),
+            self.p.paint(
+                a.style,
+                &format!("|{}{}{}", "_".repeat(end), a.underline, message)
+            )
+        ));
+    }
+}
+
+/// Render a diagnostic as human-readable text
+pub fn render(diagnostic: &Diagnostic, source: &str, options: RenderOptions) -> String {
+    let p = Painter {
+        color: options.color,
+    };
+    let style = severity_style(diagnostic.severity);
+    let span = &diagnostic.span;
+    let lines: Vec<&str> = source.lines().collect();
+
+    // Diagnostics built without a span carry an empty file path: no frame
+    let in_source = |s: &Span| {
+        !s.file.as_os_str().is_empty() && s.start_line > 0 && s.start_line <= lines.len()
+    };
+    let has_frame = in_source(span);
+
+    // Primary span plus labels in the same file, drawn in line order
+    let mut annotations = Vec::new();
+    if has_frame {
+        annotations.push(Annotation {
+            span,
+            underline: '^',
+            style,
+            message: None,
+        });
+    }
+    let mut foreign_labels = Vec::new();
+    for label in &diagnostic.labels {
+        if has_frame && label.span.file == span.file && in_source(&label.span) {
+            annotations.push(Annotation {
+                span: &label.span,
+                underline: '-',
+                style: GUTTER,
+                message: Some(&label.message),
+            });
+        } else {
+            foreign_labels.push(label);
+        }
+    }
+    annotations.sort_by_key(|a| a.span.start_line);
+
+    let max_line = annotations
+        .iter()
+        .map(|a| a.span.end_line.clamp(a.span.start_line, lines.len().max(1)))
+        .max()
+        .unwrap_or(1);
+    let frame = Frame {
+        p,
+        lines,
+        width: max_line.to_string().len(),
+    };
+    let pad = frame.pad();
+
+    let mut output = format!(
+        "{}: {}\n",
+        frame.p.paint(
+            style,
+            &format!(
+                "{}[{}]",
+                severity_label(diagnostic.severity),
+                diagnostic.code
+            )
+        ),
+        frame
+            .p
+            .paint(BOLD, &diagnostic.localized_message(options.locale)),
+    );
+    if !span.file.as_os_str().is_empty() {
+        output.push_str(&format!(
+            "{}{} {}:{}:{}\n",
+            pad,
+            frame.p.paint(GUTTER, "-->"),
+            span.file.display(),
+            span.start_line,
+            span.start_col
+        ));
+    }
+
+    if !annotations.is_empty() {
+        output.push_str(&format!("{} {}\n", pad, frame.bar()));
+        let mut previous_end: Option<usize> = None;
+        let mut i = 0;
+        while i < annotations.len() {
+            let a = &annotations[i];
+            if previous_end.is_some_and(|end| a.span.start_line > end + 1) {
+                output.push_str(&format!("{}\n", frame.p.paint(GUTTER, "...")));
+            }
+            if frame.last_line(a.span) == a.span.start_line {
+                let mut same_line: Vec<&Annotation> = annotations[i..]
+                    .iter()
+                    .take_while(|b| {
+                        b.span.start_line == a.span.start_line
+                            && frame.last_line(b.span) == b.span.start_line
+                    })
+                    .collect();
+                i += same_line.len();
+                same_line.sort_by_key(|b| b.span.start_col);
+                frame.annotate_line(&mut output, a.span.start_line, &same_line);
+            } else {
+                frame.annotate_multiline(&mut output, a);
+                i += 1;
+            }
+            previous_end = Some(frame.last_line(a.span));
+        }
+    }
+
+    for label in foreign_labels {
+        output.push_str(&format!(
+            "{}{} {}:{}:{}\n",
+            pad,
+            frame.p.paint(GUTTER, ":::"),
+            label.span.file.display(),
+            label.span.start_line,
+            label.span.start_col
+        ));
+        output.push_str(&label_line(&frame.p, &pad, "label", &label.message));
+    }
+    for related in &diagnostic.related {
+        output.push_str(&label_line(
+            &frame.p,
+            &pad,
+            "also at",
+            &format!(
+                "{}:{}:{}",
+                related.span.file.display(),
+                related.span.start_line,
+                related.span.start_col
+            ),
+        ));
+    }
+    for note in &diagnostic.notes {
+        output.push_str(&label_line(&frame.p, &pad, "note", &note.message));
+    }
+    for suggestion in &diagnostic.suggestions {
+        output.push_str(&label_line(&frame.p, &pad, "help", &suggestion.title));
+    }
+
+    output
+}
+
+/// Format an `= label: text` line, aligning continuation lines under the text
+fn label_line(p: &Painter, pad: &str, label: &str, text: &str) -> String {
+    let prefix = format!("{} = {}: ", pad, label);
+    let indent = " ".repeat(prefix.len());
+    let mut out = String::new();
+    for (i, line) in text.lines().enumerate() {
+        if i == 0 {
+            out.push_str(&format!(
+                "{} {} {}",
+                pad,
+                p.paint(BOLD, &format!("= {}:", label)),
+                line
+            ));
+        } else {
+            out.push_str(&format!("{}{}", indent, line));
+        }
+        out.push('\n');
+    }
+    if out.is_empty() {
+        out.push_str(&format!(
+            "{} {}\n",
+            pad,
+            p.paint(BOLD, &format!("= {}:", label))
+        ));
+    }
+    out
+}
@@ -49,6 +49,24 @@ fn test_diagnostic_with_suggestion() {
     assert!(json.contains("Int"));
 }
 
+#[test]
+fn test_suggestion_applicability_json() {
+    let suggestion =
+        Suggestion::new("Remove this import").with_applicability(Applicability::MachineApplicable);
+    assert!(suggestion.is_machine_applicable());
+    let json = serde_json::to_string(&suggestion).unwrap();
+    assert!(json.contains("\"applicability\":\"MachineApplicable\""));
+
+    // Suggestions default to needing review, including when deserialized
+    // from JSON written before applicability existed
+    assert_eq!(
+        Suggestion::new("Rename").applicability,
+        Applicability::MaybeIncorrect
+    );
+    let old: Suggestion = serde_json::from_str(r#"{"title":"Rename","edits":[]}"#).unwrap();
+    assert_eq!(old.applicability, Applicability::MaybeIncorrect);
+}
+
 #[test]
 fn test_diagnostic_human_readable() {
     let diag = Diagnostic::error("E1001")
@@ -142,3 +160,307 @@ fn test_diagnostic_note() {
     let json = diag.to_json();
     assert!(json.contains("Additional context"));
 }
+
+fn unknown_ident_at(line: usize) -> Diagnostic {
+    Diagnostic::error("E1002")
+        .message("Unknown identifier: foo")
+        .span(Span::new(PathBuf::from("a.astra"), 0, 3, line, 1, line, 4))
+        .build()
+}
+
+#[test]
+fn test_group_diagnostics_nests_repeats() {
+    let diags = vec![
+        unknown_ident_at(1),
+        Diagnostic::error("E1001").message("Type mismatch").build(),
+        unknown_ident_at(2),
+        unknown_ident_at(3),
+    ];
+    let grouped = group_diagnostics(diags, MAX_GROUPED_REPEATS);
+    assert_eq!(grouped.len(), 2);
+    assert_eq!(grouped[0].code, "E1002");
+    assert_eq!(grouped[0].related.len(), 2);
+    assert_eq!(grouped[0].related[1].span.start_line, 3);
+
+    let text = grouped[0].to_human_readable("foo\nfoo\nfoo\n");
+    assert!(text.contains("also at: a.astra:2:1"));
+}
+
+#[test]
+fn test_group_diagnostics_caps_repeats() {
+    let diags = (1..=10).map(unknown_ident_at).collect();
+    let grouped = group_diagnostics(diags, 3);
+    assert_eq!(grouped.len(), 1);
+    assert_eq!(grouped[0].related.len(), 3);
+    assert!(grouped[0]
+        .notes
+        .iter()
+        .any(|n| n.message == "6 more identical diagnostic(s) omitted"));
+    assert!(grouped[0].to_json().contains("\"related\""));
+}
+
+#[test]
+fn test_render_single_line_frame() {
+    let source = "module m\n\nlet value = 1\n";
+    let diag = Diagnostic::warning("W0001")
+        .message("Unused variable `value`")
+        .span(Span::new(PathBuf::from("m.astra"), 14, 19, 3, 5, 3, 10))
+        .note(Note::new("first line\nsecond line"))
+        .build();
+    let output = diag.to_human_readable(source);
+    assert_eq!(
+        output,
+        "warning[W0001]: Unused variable `value`\n \
+         --> m.astra:3:5\n  \
+         |\n\
+         3 | let value = 1\n  \
+         |     ^^^^^\n  \
+         = note: first line\n          \
+         second line\n"
+    );
+}
+
+#[test]
+fn test_render_multi_line_frame() {
+    let source = "fn f() -> Int {\n  foo\n}\n";
+    let diag = Diagnostic::warning("W0008")
+        .message("Function `f` is defined but never used")
+        .span(Span::new(PathBuf::from("m.astra"), 0, 23, 1, 1, 3, 2))
+        .build();
+    let output = diag.to_human_readable(source);
+    assert!(output.contains("1 | / fn f() -> Int {\n"));
+    assert!(output.contains("2 | |   foo\n"));
+    assert!(output.contains("3 | | }\n"));
+    assert!(output.contains("  | |_^\n"));
+}
+
+#[test]
+fn test_render_elides_long_spans() {
+    let source = (1..=20)
+        .map(|i| format!("line{}", i))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let diag = Diagnostic::error("E0001")
+        .message("long")
+        .span(Span::new(PathBuf::from("m.astra"), 0, 10, 1, 1, 20, 7))
+        .build();
+    let output = diag.to_human_readable(&source);
+    assert!(output.contains("...") && output.contains("line20"));
+    assert!(!output.contains("line10"));
+}
+
+#[test]
+fn test_render_color_toggle() {
+    let diag = Diagnostic::error("E1001")
+        .message("Type mismatch")
+        .span(Span::new(PathBuf::from("m.astra"), 0, 3, 1, 1, 1, 4))
+        .build();
+    let plain = diag.render("foo", RenderOptions::default());
+    let colored = diag.render(
+        "foo",
+        RenderOptions {
+            color: true,
+            ..Default::default()
+        },
+    );
+    assert!(!plain.contains('\x1b'));
+    assert!(colored.contains("\x1b[1;31merror[E1001]\x1b[0m"));
+}
+
+#[test]
+fn test_render_without_span_has_no_frame() {
+    let diag = Diagnostic::error("E1001").message("Type mismatch").build();
+    let output = diag.to_human_readable("module m\n");
+    assert_eq!(output, "error[E1001]: Type mismatch\n");
+}
+
+#[test]
+fn test_span_merge_same_line_keeps_later_end_col() {
+    let first = Span::new(PathBuf::from("t.astra"), 2, 5, 4, 3, 4, 6);
+    let last = Span::new(PathBuf::from("t.astra"), 10, 11, 4, 11, 4, 12);
+    let merged = first.merge(&last);
+    assert_eq!((merged.start_col, merged.end_col), (3, 12));
+}
+
+#[test]
+fn test_render_labels_in_line_order() {
+    let source = "let x = 1\nlet y = 2\nlet x = 3\n";
+    let diag = Diagnostic::warning("W0006")
+        .message("Variable `x` shadows a previous binding in the same scope")
+        .span(Span::new(PathBuf::from("m.astra"), 20, 29, 3, 1, 3, 10))
+        .label(
+            Span::new(PathBuf::from("m.astra"), 0, 9, 1, 1, 1, 10),
+            "previous binding here",
+        )
+        .build();
+    let output = diag.to_human_readable(source);
+    let label_pos = output.find("--------- previous binding here").unwrap();
+    let primary_pos = output.find("^^^^^^^^^").unwrap();
+    assert!(label_pos < primary_pos);
+    assert!(output.contains("...\n"));
+    assert!(diag.to_json().contains("\"labels\""));
+}
+
+#[test]
+fn test_render_labels_on_same_line_share_source_line() {
+    let source = "let y: Int = \"a\"";
+    let diag = Diagnostic::error("E1001")
+        .message("Expected type Int, found Text")
+        .span(Span::new(PathBuf::from("m.astra"), 13, 16, 1, 14, 1, 17))
+        .label(
+            Span::new(PathBuf::from("m.astra"), 7, 10, 1, 8, 1, 11),
+            "expected because of this annotation",
+        )
+        .build();
+    let output = diag.to_human_readable(source);
+    assert_eq!(output.matches("let y: Int").count(), 1);
+    assert!(output.contains("       --- expected because of this annotation\n"));
+}
+
+#[test]
+fn test_render_label_in_other_file() {
+    let diag = Diagnostic::error("E1001")
+        .message("mismatch")
+        .span(Span::new(PathBuf::from("a.astra"), 0, 3, 1, 1, 1, 4))
+        .label(
+            Span::new(PathBuf::from("b.astra"), 0, 3, 2, 5, 2, 8),
+            "declared here",
+        )
+        .build();
+    let output = diag.to_human_readable("foo");
+    assert!(output.contains("::: b.astra:2:5\n"));
+    assert!(output.contains("= label: declared here"));
+}
+
+fn edit(start: usize, end: usize, replacement: &str) -> Edit {
+    Edit::new(
+        Span::new(PathBuf::from("test.astra"), start, end, 1, 1, 1, 1),
+        replacement,
+    )
+}
+
+#[test]
+fn test_apply_edits_in_any_order() {
+    let source = "let x = 1 + 2";
+    let edits = [
+        edit(12, 13, "20"),
+        edit(4, 5, "total"),
+        edit(8, 8, "("),
+        edit(13, 13, ")"),
+        edit(4, 5, "total"),
+    ];
+    assert_eq!(apply_edits(source, &edits).unwrap(), "let total = (1 + 20)");
+    // Insertions at one offset keep their order
+    let edits = [edit(0, 0, "a"), edit(0, 0, "b")];
+    assert_eq!(apply_edits("c", &edits).unwrap(), "abc");
+}
+
+#[test]
+fn test_apply_edits_refuses_overlaps_and_bad_spans() {
+    let source = "let x = 1 + 2";
+    assert_eq!(
+        apply_edits(
+            source,
+            &[edit(8, 13, "3"), edit(4, 5, "y"), edit(12, 13, "4")]
+        ),
+        Err(EditError::Overlap(0, 2))
+    );
+    assert_eq!(
+        apply_edits(source, &[edit(8, 13, "3"), edit(10, 10, "-")]),
+        Err(EditError::Overlap(0, 1))
+    );
+    assert_eq!(
+        apply_edits(source, &[edit(8, 14, "3")]),
+        Err(EditError::OutOfBounds(0))
+    );
+    assert_eq!(
+        apply_edits("é", &[edit(1, 1, "x")]),
+        Err(EditError::OutOfBounds(0))
+    );
+}
+
+#[test]
+fn test_remap_offset() {
+    let source = "let x = 1 + 2";
+    let edits = [edit(4, 5, "total"), edit(8, 9, "10"), edit(12, 12, "(")];
+    let edited = apply_edits(source, &edits).unwrap();
+    assert_eq!(edited, "let total = 10 + (2");
+    assert_eq!(remap_offset(&edits, 0), 0);
+    assert_eq!(remap_offset(&edits, 6), 10);
+    assert_eq!(&edited[remap_offset(&edits, 10)..], "+ (2");
+    assert_eq!(&edited[remap_offset(&edits, 12)..], "(2");
+    // Inside replaced text
+    assert_eq!(remap_offset(&[edit(4, 9, "y")], 7), 4);
+}
+
+#[test]
+fn test_edit_spans_need_only_offsets_in_json() {
+    let edit: Edit = serde_json::from_str(
+        r#"{"file": "a.astra", "span": {"start": 1, "end": 2}, "replacement": "x"}"#,
+    )
+    .unwrap();
+    assert_eq!((edit.span.start, edit.span.end), (1, 2));
+}
+
+#[test]
+fn test_catalog_message_renders_in_locale() {
+    let diag = Diagnostic::error("E1002")
+        .catalog("E1002")
+        .arg("name", "totl")
+        .span(Span::new(PathBuf::from("m.astra"), 0, 4, 1, 1, 1, 5))
+        .build();
+    // The message itself is always English
+    assert_eq!(diag.message, "Unknown identifier: totl");
+    let spanish = RenderOptions {
+        locale: Locale::Spanish,
+        ..Default::default()
+    };
+    let rendered = diag.render("totl", spanish);
+    assert!(rendered.starts_with("error[E1002]: Identificador desconocido: totl\n"));
+    assert!(diag
+        .render("totl", RenderOptions::default())
+        .starts_with("error[E1002]: Unknown identifier: totl\n"));
+
+    // Messages not from the catalog stay English
+    let plain = Diagnostic::error("E1001").message("Type mismatch").build();
+    assert!(plain
+        .render("", spanish)
+        .starts_with("error[E1001]: Type mismatch"));
+}
+
+#[test]
+fn test_catalog_message_json_is_additive() {
+    let diag = Diagnostic::warning("W0001")
+        .catalog("W0001")
+        .arg("name", "x")
+        .build();
+    let json: serde_json::Value = serde_json::from_str(&diag.to_json()).unwrap();
+    assert_eq!(json["code"], "W0001");
+    assert_eq!(json["message"], "Unused variable `x`");
+    assert_eq!(json["catalog"]["id"], "W0001");
+    assert_eq!(json["catalog"]["args"]["name"], "x");
+    // A cached diagnostic can still be rendered in another locale
+    let back: Diagnostic = serde_json::from_value(json).unwrap();
+    assert_eq!(
+        back.localized_message(Locale::Spanish),
+        "Variable `x` sin usar"
+    );
+
+    let plain = Diagnostic::error("E1001").message("Type mismatch").build();
+    assert!(!plain.to_json().contains("catalog"));
+}
+
+#[test]
+fn test_catalog_interpolate() {
+    let args = std::collections::BTreeMap::from([("name".to_string(), "x".to_string())]);
+    assert_eq!(catalog::interpolate("`{name}` {{ok}}", &args), "`x` {ok}");
+    assert_eq!(catalog::interpolate("{missing} {", &args), "{missing} {");
+}
+
+#[test]
+fn test_locale_from_tag() {
+    assert_eq!(Locale::from_tag("es"), Some(Locale::Spanish));
+    assert_eq!(Locale::from_tag("es_MX.UTF-8"), Some(Locale::Spanish));
+    assert_eq!(Locale::from_tag("EN-us"), Some(Locale::English));
+    assert_eq!(Locale::from_tag("fr"), None);
+}
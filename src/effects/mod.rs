@@ -19,6 +19,16 @@ pub enum Effect {
     Env,
     /// Console I/O capability
     Console,
+    /// Signing and message authentication capability
+    Crypto,
+    /// HTTP route handling capability
+    Serve,
+    /// Database access capability
+    Db,
+    /// Progress reporting capability
+    Progress,
+    /// Metrics reporting capability
+    Metrics,
     /// Custom user-defined effect
     Custom(String),
 }
@@ -33,6 +43,11 @@ impl Effect {
             "Rand" => Some(Effect::Rand),
             "Env" => Some(Effect::Env),
             "Console" => Some(Effect::Console),
+            "Crypto" => Some(Effect::Crypto),
+            "Serve" => Some(Effect::Serve),
+            "Db" => Some(Effect::Db),
+            "Progress" => Some(Effect::Progress),
+            "Metrics" => Some(Effect::Metrics),
             _ => Some(Effect::Custom(name.to_string())),
         }
     }
@@ -46,6 +61,11 @@ impl Effect {
             Effect::Rand => "Rand",
             Effect::Env => "Env",
             Effect::Console => "Console",
+            Effect::Crypto => "Crypto",
+            Effect::Serve => "Serve",
+            Effect::Db => "Db",
+            Effect::Progress => "Progress",
+            Effect::Metrics => "Metrics",
             Effect::Custom(name) => name,
         }
     }
@@ -0,0 +1,266 @@
+//! Embedding API for hosts that drive the toolchain from another language
+//!
+//! [`check`], [`fmt`], and [`run`] take source text and return serializable
+//! results, so the C ABI (`astra-ffi`), Python (`python`), and WebAssembly
+//! (`wasm`) bindings only translate values.
+//! Runs are sandboxed: a program gets only the capabilities granted in its
+//! [`CapabilityConfig`], backed by in-memory or fixed implementations, and
+//! `Rand` is seeded so the same inputs always produce the same run.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::Diagnostic;
+use crate::formatter::Formatter;
+use crate::interpreter::{
+    format_value, stack_size_for, Capabilities, ConsoleCapability, FixedClock, FixedEnv,
+    Interpreter, MemoryFs, MockNet, SeededRand, StandardCrypto, DEFAULT_MAX_CALL_DEPTH,
+};
+use crate::parser::parse_source;
+use crate::typechecker::TypeChecker;
+
+/// File name used in diagnostics for embedded sources
+const SOURCE_NAME: &str = "main.astra";
+
+/// Result of type checking a source
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    /// No errors were found (warnings are allowed)
+    pub ok: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Result of formatting a source
+#[derive(Debug, Clone, Serialize)]
+pub struct FormatResult {
+    /// The source parsed
+    pub ok: bool,
+    /// The formatted source, if it parsed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<String>,
+    /// Formatting changed the source
+    pub changed: bool,
+    /// Parse errors that prevented formatting
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Capabilities granted to an embedded run; absent ones are denied
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CapabilityConfig {
+    /// Capture `Console` output in [`RunResult::output`]
+    #[serde(default)]
+    pub console: bool,
+    /// In-memory files by path for `Fs`
+    pub fs: Option<BTreeMap<String, String>>,
+    /// Canned `Net` responses by URL
+    pub net: Option<BTreeMap<String, String>>,
+    /// Fixed `Clock.now()` in milliseconds
+    pub clock: Option<i64>,
+    /// Variables and arguments for `Env`
+    pub env: Option<EnvConfig>,
+    /// `Rand`, seeded with the run's seed
+    #[serde(default)]
+    pub rand: bool,
+    /// `Crypto` with the standard algorithms, which are deterministic
+    #[serde(default)]
+    pub crypto: bool,
+}
+
+/// Contents of the `Env` capability
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnvConfig {
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// A runtime error, as reported to hosts
+#[derive(Debug, Clone, Serialize)]
+pub struct RunError {
+    pub code: String,
+    pub message: String,
+}
+
+/// Result of running a source's `main`
+#[derive(Debug, Clone, Serialize)]
+pub struct RunResult {
+    /// The program parsed and ran without a runtime error
+    pub ok: bool,
+    /// What `main` returned, formatted as by `to_text`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Console output, when `console` was granted
+    pub output: String,
+    /// The in-memory files after the run, when `fs` was granted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RunError>,
+    /// Parse errors that prevented the run
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Parse and type check `source`
+pub fn check(source: &str) -> CheckResult {
+    let module = match parse_source(source, Path::new(SOURCE_NAME)) {
+        Ok(module) => module,
+        Err(bag) => {
+            return CheckResult {
+                ok: false,
+                diagnostics: bag.diagnostics().to_vec(),
+            }
+        }
+    };
+    let mut checker = TypeChecker::new();
+    let _ = checker.check_module(&module);
+    CheckResult {
+        ok: !checker.diagnostics().has_errors(),
+        diagnostics: checker.diagnostics().diagnostics().to_vec(),
+    }
+}
+
+/// Format `source` as `astra fmt` would
+pub fn fmt(source: &str) -> FormatResult {
+    match parse_source(source, Path::new(SOURCE_NAME)) {
+        Ok(module) => {
+            let formatted = Formatter::new().format_module(&module);
+            FormatResult {
+                ok: true,
+                changed: formatted != source,
+                formatted: Some(formatted),
+                diagnostics: Vec::new(),
+            }
+        }
+        Err(bag) => FormatResult {
+            ok: false,
+            formatted: None,
+            changed: false,
+            diagnostics: bag.diagnostics().to_vec(),
+        },
+    }
+}
+
+/// Parse `source` and run its `main` with the granted capabilities
+pub fn run(source: &str, seed: u64, config: &CapabilityConfig) -> RunResult {
+    run_with(source, seed, config, Capabilities::default())
+}
+
+/// Like [`run`], with capabilities implemented by the host taking the place
+/// of those in `config`
+pub fn run_with(
+    source: &str,
+    seed: u64,
+    config: &CapabilityConfig,
+    host: Capabilities,
+) -> RunResult {
+    let mut result = RunResult {
+        ok: false,
+        value: None,
+        output: String::new(),
+        files: None,
+        error: None,
+        diagnostics: Vec::new(),
+    };
+    let module = match parse_source(source, Path::new(SOURCE_NAME)) {
+        Ok(module) => module,
+        Err(bag) => {
+            result.diagnostics = bag.diagnostics().to_vec();
+            return result;
+        }
+    };
+
+    let output = Rc::new(RefCell::new(String::new()));
+    let fs = match host.fs {
+        Some(_) => None,
+        None => config.fs.clone().map(MemoryFs::new),
+    };
+    let capabilities = Capabilities {
+        console: host.console.or_else(|| {
+            config.console.then(|| {
+                Box::new(CapturedConsole {
+                    output: output.clone(),
+                }) as Box<dyn ConsoleCapability>
+            })
+        }),
+        fs: host.fs.or_else(|| fs.clone().map(|fs| Box::new(fs) as _)),
+        net: host.net.or_else(|| {
+            config
+                .net
+                .clone()
+                .map(|net| Box::new(MockNet::new(net)) as _)
+        }),
+        clock: host
+            .clock
+            .or_else(|| config.clock.map(|now| Box::new(FixedClock::new(now)) as _)),
+        env: host.env.or_else(|| {
+            config
+                .env
+                .clone()
+                .map(|env| Box::new(FixedEnv::new(env.vars, env.args)) as _)
+        }),
+        rand: host
+            .rand
+            .or_else(|| config.rand.then(|| Box::new(SeededRand::new(seed)) as _)),
+        crypto: host
+            .crypto
+            .or_else(|| config.crypto.then(|| Box::new(StandardCrypto) as _)),
+        serve: host.serve,
+        db: host.db,
+        progress: host.progress,
+        metrics: host.metrics,
+    };
+
+    let mut interpreter = Interpreter::with_capabilities(capabilities);
+    // The host's stack may be far smaller than the call depth limit needs,
+    // so the run gets a stack of its own, sized as the CLI sizes its thread
+    let outcome = stacker::grow(stack_size_for(DEFAULT_MAX_CALL_DEPTH), || {
+        interpreter.eval_module(&module)
+    });
+    match outcome {
+        Ok(value) => {
+            result.ok = true;
+            result.value = Some(format_value(&value));
+        }
+        Err(e) => {
+            result.error = Some(RunError {
+                code: e.code.to_string(),
+                message: e.message,
+            });
+        }
+    }
+    result.output = output.take();
+    result.files = fs.map(|fs| fs.files());
+    result
+}
+
+/// Console that appends everything printed to a shared buffer
+struct CapturedConsole {
+    output: Rc<RefCell<String>>,
+}
+
+impl ConsoleCapability for CapturedConsole {
+    fn print(&self, text: &str) {
+        self.output.borrow_mut().push_str(text);
+    }
+
+    fn println(&self, text: &str) {
+        let mut output = self.output.borrow_mut();
+        output.push_str(text);
+        output.push('\n');
+    }
+
+    fn read_line(&self) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+#[path = "embed_tests.rs"]
+mod tests;
@@ -0,0 +1,101 @@
+use super::*;
+
+#[test]
+fn test_check_reports_type_errors() {
+    let result = check("module m\n\nfn main() -> Int {\n  let x: Int = \"a\"\n  x\n}\n");
+    assert!(!result.ok);
+    assert!(result.diagnostics.iter().any(|d| d.code == "E1001"));
+
+    let result = check("module m\n\nfn main() -> Int {\n  1\n}\n");
+    assert!(result.ok);
+}
+
+#[test]
+fn test_check_reports_parse_errors() {
+    let result = check("module m\n\nfn main( {\n");
+    assert!(!result.ok);
+    assert!(!result.diagnostics.is_empty());
+}
+
+#[test]
+fn test_run_with_sandboxed_capabilities() {
+    let source = r#"module m
+
+fn main() -> Int effects(Console, Fs, Rand) {
+  let text = Fs.read("in.txt").unwrap()
+  Fs.write("out.txt", text + "!")
+  Console.println(text)
+  Rand.int(1, 100)
+}
+"#;
+    let config: CapabilityConfig =
+        serde_json::from_str(r#"{"console": true, "fs": {"in.txt": "hello"}, "rand": true}"#)
+            .unwrap();
+    let first = run(source, 7, &config);
+    assert!(first.ok, "{:?}", first.error);
+    assert_eq!(first.output, "hello\n");
+    assert_eq!(first.files.as_ref().unwrap()["out.txt"], "hello!");
+
+    // The same seed gives the same run
+    let second = run(source, 7, &config);
+    assert_eq!(first.value, second.value);
+}
+
+#[test]
+fn test_run_grants_crypto_when_configured() {
+    let source = "module m\n\nfn main() -> Text effects(Crypto) {\n  hex_encode(Crypto.hmac_sha256(\"key\", \"data\"))\n}\n";
+    let config: CapabilityConfig = serde_json::from_str(r#"{"crypto": true}"#).unwrap();
+    let result = run(source, 0, &config);
+    assert_eq!(
+        result.value.as_deref(),
+        Some("5031fe3d989c6d1537a013fa6e739da23463fdaec3b70137d828e36ace221bd0")
+    );
+
+    let result = run(source, 0, &CapabilityConfig::default());
+    assert_eq!(result.error.unwrap().code, "E4004");
+}
+
+#[test]
+fn test_run_denies_ungranted_capabilities() {
+    let source = "module m\n\nfn main() -> Unit effects(Console) {\n  Console.println(\"hi\")\n}\n";
+    let result = run(source, 0, &CapabilityConfig::default());
+    assert!(!result.ok);
+    assert!(result.error.is_some());
+    assert!(result.output.is_empty());
+}
+
+#[test]
+fn test_fmt_formats_or_reports_parse_errors() {
+    let result = fmt("module m\nfn main()->Int{1}\n");
+    assert!(result.ok);
+    assert!(result.changed);
+    assert!(result
+        .formatted
+        .as_deref()
+        .unwrap()
+        .contains("fn main() -> Int"));
+    assert!(!fmt(result.formatted.as_deref().unwrap()).changed);
+
+    let result = fmt("module m\nfn main( {\n");
+    assert!(!result.ok);
+    assert!(result.formatted.is_none());
+}
+
+#[test]
+fn test_run_recurses_to_the_call_depth_limit() {
+    // The run's own stack fits the limit in debug and release builds alike,
+    // whatever the stack of the thread calling `run`
+    let source = |depth: usize| {
+        format!(
+            "module m\n\nfn deep(n: Int) -> Int {{\n  if n == 0 {{ 0 }} else {{ 1 + deep(n - 1) }}\n}}\n\nfn main() -> Int {{\n  deep({})\n}}\n",
+            depth
+        )
+    };
+    let config = CapabilityConfig::default();
+    let result = run(&source(DEFAULT_MAX_CALL_DEPTH - 10), 0, &config);
+    assert!(result.ok, "{:?}", result.error);
+    assert_eq!(result.value.as_deref(), Some("990"));
+
+    let result = run(&source(DEFAULT_MAX_CALL_DEPTH + 10), 0, &config);
+    assert_eq!(result.error.unwrap().code, "E4028");
+}
@@ -0,0 +1,109 @@
+//! C ABI for embedding the toolchain (feature `astra-ffi`)
+//!
+//! Every function takes NUL-terminated UTF-8 strings and returns a JSON
+//! document as a newly allocated string that the caller releases with
+//! [`astra_string_free`]. Invalid arguments and panics are reported as
+//! `{"ok": false, "error": {"code": "FFI", "message": ...}}` instead of
+//! crossing the boundary. The declarations are in `include/astra.h`.
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::embed::{self, CapabilityConfig};
+
+/// Version of the JSON returned by these functions; bumped on breaking changes
+pub const ASTRA_FFI_VERSION: u32 = 1;
+
+/// Type check a source. Returns `{"ok", "diagnostics"}`.
+///
+/// # Safety
+///
+/// `source` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn astra_check(source: *const c_char) -> *mut c_char {
+    respond(|| {
+        let source = unsafe { read(source, "source") }?;
+        Ok(serde_json::to_value(embed::check(source)).unwrap_or_default())
+    })
+}
+
+/// Run a source's `main` with the capabilities described by `caps_json` (null
+/// or `{}` grants none) and `Rand` seeded with `seed`. Returns `{"ok", "value",
+/// "output", "files", "error", "diagnostics"}`.
+///
+/// # Safety
+///
+/// `source` and `caps_json` must be null or point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn astra_run(
+    source: *const c_char,
+    seed: u64,
+    caps_json: *const c_char,
+) -> *mut c_char {
+    respond(|| {
+        let source = unsafe { read(source, "source") }?;
+        let config: CapabilityConfig = if caps_json.is_null() {
+            CapabilityConfig::default()
+        } else {
+            let caps = unsafe { read(caps_json, "caps_json") }?;
+            serde_json::from_str(caps).map_err(|e| format!("invalid caps_json: {}", e))?
+        };
+        Ok(serde_json::to_value(embed::run(source, seed, &config)).unwrap_or_default())
+    })
+}
+
+/// Release a string returned by this library. Null is ignored.
+///
+/// # Safety
+///
+/// `string` must be null or a pointer returned by this library that has not
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn astra_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+/// The toolchain version, as a static string that must not be freed
+#[no_mangle]
+pub extern "C" fn astra_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Version of the JSON schema returned by [`astra_check`] and [`astra_run`]
+#[no_mangle]
+pub extern "C" fn astra_ffi_version() -> u32 {
+    ASTRA_FFI_VERSION
+}
+
+/// Borrow a C string argument as UTF-8
+unsafe fn read<'a>(string: *const c_char, name: &str) -> Result<&'a str, String> {
+    if string.is_null() {
+        return Err(format!("{} is null", name));
+    }
+    unsafe { CStr::from_ptr(string) }
+        .to_str()
+        .map_err(|_| format!("{} is not valid UTF-8", name))
+}
+
+/// Run `work` and hand its JSON result, or the error, to the caller
+fn respond(work: impl FnOnce() -> Result<serde_json::Value, String>) -> *mut c_char {
+    let result = catch_unwind(AssertUnwindSafe(work))
+        .unwrap_or_else(|_| Err("internal error: the toolchain panicked".to_string()));
+    let json = match result {
+        Ok(value) => value,
+        Err(message) => serde_json::json!({
+            "ok": false,
+            "error": {"code": "FFI", "message": message},
+        }),
+    };
+    // JSON escapes control characters, so it never contains a NUL byte
+    CString::new(json.to_string())
+        .unwrap_or_default()
+        .into_raw()
+}
+
+#[cfg(test)]
+#[path = "ffi_tests.rs"]
+mod tests;
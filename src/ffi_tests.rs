@@ -0,0 +1,46 @@
+use super::*;
+
+/// Call an FFI function and take ownership of its JSON result
+fn json(result: *mut c_char) -> serde_json::Value {
+    let text = unsafe { CStr::from_ptr(result) }
+        .to_str()
+        .unwrap()
+        .to_string();
+    unsafe { astra_string_free(result) };
+    serde_json::from_str(&text).unwrap()
+}
+
+#[test]
+fn test_check_and_run_over_the_c_abi() {
+    let source = CString::new(
+        "module m\n\nfn main() -> Int effects(Console) {\n  Console.println(\"hi\")\n  42\n}\n",
+    )
+    .unwrap();
+    let checked = json(unsafe { astra_check(source.as_ptr()) });
+    assert_eq!(checked["ok"], true);
+
+    let caps = CString::new(r#"{"console": true}"#).unwrap();
+    let ran = json(unsafe { astra_run(source.as_ptr(), 1, caps.as_ptr()) });
+    assert_eq!(ran["ok"], true);
+    assert_eq!(ran["value"], "42");
+    assert_eq!(ran["output"], "hi\n");
+}
+
+#[test]
+fn test_invalid_arguments_are_reported_as_json() {
+    let missing = json(unsafe { astra_check(std::ptr::null()) });
+    assert_eq!(missing["ok"], false);
+    assert_eq!(missing["error"]["message"], "source is null");
+
+    let source = CString::new("module m\n\nfn main() -> Int {\n  1\n}\n").unwrap();
+    let caps = CString::new(r#"{"disk": true}"#).unwrap();
+    let bad_caps = json(unsafe { astra_run(source.as_ptr(), 0, caps.as_ptr()) });
+    assert_eq!(bad_caps["error"]["code"], "FFI");
+}
+
+#[test]
+fn test_version_is_a_static_string() {
+    let version = unsafe { CStr::from_ptr(astra_version()) };
+    assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+    assert_eq!(astra_ffi_version(), ASTRA_FFI_VERSION);
+}
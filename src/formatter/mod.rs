@@ -1,8 +1,13 @@
 //! Canonical formatter for Astra source code
 //!
 //! Produces a single, deterministic representation of any valid Astra program.
+//! [`emit`] renders ASTs built programmatically the same way.
 
+use std::path::Path;
+
+use crate::diagnostics::{Diagnostic, DiagnosticBag, Note, Span};
 use crate::parser::ast::*;
+use crate::parser::parse_source;
 
 /// Configuration for the formatter
 #[derive(Debug, Clone)]
@@ -49,7 +54,15 @@ impl Formatter {
         self.output.clear();
         self.indent_level = 0;
 
+        // Script header, kept as written
+        if let Some(header) = &module.header {
+            self.output.push_str(header.trim_end());
+            self.newline();
+            self.newline();
+        }
+
         // Module declaration
+        self.format_doc(module.doc.as_deref(), "##!");
         self.write("module ");
         self.format_module_path(&module.name);
         self.newline();
@@ -76,6 +89,21 @@ impl Formatter {
     }
 
     fn format_item(&mut self, item: &Item) {
+        match item {
+            Item::TypeDef(TypeDef { doc, .. })
+            | Item::EnumDef(EnumDef { doc, .. })
+            | Item::FnDef(FnDef { doc, .. })
+            | Item::TraitDef(TraitDef { doc, .. })
+            | Item::MacroDef(MacroDef { doc, .. }) => self.format_doc(doc.as_deref(), "##"),
+            _ => {}
+        }
+        match item {
+            Item::Import(ImportDecl { attributes, .. })
+            | Item::TypeDef(TypeDef { attributes, .. })
+            | Item::EnumDef(EnumDef { attributes, .. })
+            | Item::FnDef(FnDef { attributes, .. }) => self.format_attributes(attributes),
+            _ => {}
+        }
         match item {
             Item::Import(import) => self.format_import(import),
             Item::TypeDef(typedef) => self.format_typedef(typedef),
@@ -86,6 +114,59 @@ impl Formatter {
             Item::EffectDef(effect_def) => self.format_effect_def(effect_def),
             Item::Test(test) => self.format_test(test),
             Item::Property(property) => self.format_property(property),
+            Item::TestHook(hook) => self.format_test_hook(hook),
+            Item::MacroDef(def) => self.format_macro_def(def),
+        }
+    }
+
+    /// Write each line of a doc comment after `marker`
+    fn format_doc(&mut self, doc: Option<&str>, marker: &str) {
+        let Some(doc) = doc else {
+            return;
+        };
+        for line in doc.split('\n') {
+            self.write_indent();
+            self.write(marker);
+            if !line.is_empty() {
+                self.write(" ");
+                self.write(line);
+            }
+            self.newline();
+        }
+    }
+
+    fn format_attributes(&mut self, attributes: &[Attribute]) {
+        for attr in attributes {
+            self.write_indent();
+            self.write("@");
+            self.write(&attr.name);
+            if !attr.args.is_empty() || !attr.named_args.is_empty() {
+                let args: Vec<String> = attr
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        let is_ident = arg
+                            .chars()
+                            .next()
+                            .is_some_and(|c| c.is_alphabetic() || c == '_')
+                            && arg.chars().all(|c| c.is_alphanumeric() || c == '_');
+                        if is_ident {
+                            arg.clone()
+                        } else {
+                            format!("\"{}\"", escape_string(arg))
+                        }
+                    })
+                    .chain(
+                        attr.named_args
+                            .iter()
+                            .map(|(key, value)| format!("{} = \"{}\"", key, escape_string(value))),
+                    )
+                    .collect();
+                self.write("(");
+                self.write(&args.join(", "));
+                self.write(")");
+            }
+            self.newline();
         }
     }
 
@@ -169,11 +250,22 @@ impl Formatter {
 
     fn format_fndef(&mut self, fndef: &FnDef) {
         self.write_indent();
+        self.format_fn_header(fndef);
+        self.newline();
+        self.format_block(&fndef.body);
+        self.newline();
+    }
 
+    /// The signature and contracts of a function, up to its body
+    fn format_fn_header(&mut self, fndef: &FnDef) {
         if fndef.visibility == Visibility::Public {
             self.write("public ");
         }
 
+        if fndef.is_async {
+            self.write("async ");
+        }
+
         self.write("fn ");
         self.write(&fndef.name);
         self.format_type_params(&fndef.type_params);
@@ -231,10 +323,6 @@ impl Formatter {
             self.format_expr(ens);
             self.dedent();
         }
-
-        self.newline();
-        self.format_block(&fndef.body);
-        self.newline();
     }
 
     fn format_trait_def(&mut self, trait_def: &TraitDef) {
@@ -267,6 +355,19 @@ impl Formatter {
                 self.write(" -> ");
                 self.format_type_expr(ret);
             }
+            for (keyword, clause) in method
+                .requires
+                .iter()
+                .map(|c| ("requires ", c))
+                .chain(method.ensures.iter().map(|c| ("ensures ", c)))
+            {
+                self.newline();
+                self.indent();
+                self.write_indent();
+                self.write(keyword);
+                self.format_expr(clause);
+                self.dedent();
+            }
             self.newline();
         }
         self.indent_level -= 1;
@@ -327,6 +428,50 @@ impl Formatter {
         self.newline();
     }
 
+    /// A single rule uses the `macro name(...) => template` shorthand
+    fn format_macro_def(&mut self, def: &MacroDef) {
+        self.write_indent();
+        self.write("macro ");
+        self.write(&def.name);
+        if let [rule] = def.rules.as_slice() {
+            self.format_macro_rule(rule);
+            self.newline();
+            return;
+        }
+        self.write(" {");
+        self.newline();
+        self.indent_level += 1;
+        for rule in &def.rules {
+            self.write_indent();
+            self.format_macro_rule(rule);
+            self.write(",");
+            self.newline();
+        }
+        self.indent_level -= 1;
+        self.write_indent();
+        self.write("}");
+        self.newline();
+    }
+
+    fn format_macro_rule(&mut self, rule: &MacroRule) {
+        self.write("(");
+        for (i, param) in rule.params.iter().enumerate() {
+            if i > 0 {
+                self.write(", ");
+            }
+            self.write("$");
+            self.write(&param.name);
+            if param.fragment == MacroFragment::Ident {
+                self.write(": ident");
+            }
+            if param.variadic {
+                self.write("...");
+            }
+        }
+        self.write(") => ");
+        self.format_expr(&rule.template);
+    }
+
     fn format_test(&mut self, test: &TestBlock) {
         self.write_indent();
         self.write("test \"");
@@ -359,6 +504,20 @@ impl Formatter {
         self.newline();
     }
 
+    fn format_test_hook(&mut self, hook: &TestHook) {
+        self.write_indent();
+        self.write(hook.kind.keyword());
+
+        if let Some(using) = &hook.using {
+            self.write(" ");
+            self.format_using(using);
+        }
+
+        self.write(" ");
+        self.format_block(&hook.body);
+        self.newline();
+    }
+
     fn format_using(&mut self, using: &UsingClause) {
         self.write("using effects(");
         for (i, binding) in using.bindings.iter().enumerate() {
@@ -477,6 +636,9 @@ impl Formatter {
     }
 
     fn format_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Let { attributes, .. } | Stmt::LetPattern { attributes, .. } = stmt {
+            self.format_attributes(attributes);
+        }
         self.write_indent();
         match stmt {
             Stmt::Let {
@@ -529,13 +691,32 @@ impl Formatter {
         self.newline();
     }
 
+    /// Format an operand, parenthesized if it binds looser than `min_prec`.
+    /// `?else` takes everything after it, so it is always parenthesized.
+    fn format_operand(&mut self, expr: &Expr, min_prec: u8) {
+        // A quantifier's condition would swallow whatever follows it
+        if expr_precedence(expr) < min_prec
+            || matches!(expr, Expr::TryElse { .. } | Expr::Quantifier { .. })
+        {
+            self.write("(");
+            self.format_expr(expr);
+            self.write(")");
+        } else {
+            self.format_expr(expr);
+        }
+    }
+
     fn format_expr(&mut self, expr: &Expr) {
         match expr {
             Expr::IntLit { value, .. } => {
                 self.write(&value.to_string());
             }
             Expr::FloatLit { value, .. } => {
-                self.write(&format!("{}", value));
+                self.write(&float_text(*value));
+            }
+            Expr::DecimalLit { value, .. } => {
+                self.write(&value.to_string());
+                self.write("d");
             }
             Expr::BoolLit { value, .. } => {
                 self.write(if *value { "true" } else { "false" });
@@ -569,25 +750,27 @@ impl Formatter {
                 self.write(" }");
             }
             Expr::FieldAccess { expr, field, .. } => {
-                self.format_expr(expr);
+                self.format_operand(expr, POSTFIX_PREC);
                 self.write(".");
                 self.write(field);
             }
             Expr::Binary {
                 op, left, right, ..
             } => {
-                self.format_expr(left);
+                // Operators are left-associative
+                let prec = binary_precedence(*op);
+                self.format_operand(left, prec);
                 self.write(" ");
                 self.write(op.as_str());
                 self.write(" ");
-                self.format_expr(right);
+                self.format_operand(right, prec + 1);
             }
             Expr::Unary { op, expr, .. } => {
                 self.write(op.as_str());
-                self.format_expr(expr);
+                self.format_operand(expr, PREFIX_PREC);
             }
             Expr::Call { func, args, .. } => {
-                self.format_expr(func);
+                self.format_operand(func, POSTFIX_PREC);
                 self.write("(");
                 for (i, arg) in args.iter().enumerate() {
                     if i > 0 {
@@ -603,7 +786,7 @@ impl Formatter {
                 args,
                 ..
             } => {
-                self.format_expr(receiver);
+                self.format_operand(receiver, POSTFIX_PREC);
                 self.write(".");
                 self.write(method);
                 self.write("(");
@@ -630,7 +813,18 @@ impl Formatter {
                     match else_expr.as_ref() {
                         Expr::If { .. } => self.format_expr(else_expr),
                         Expr::Block { block, .. } => self.format_block(block),
-                        _ => self.format_expr(else_expr),
+                        // Only built ASTs have other else branches: brace them
+                        _ => {
+                            self.write("{");
+                            self.newline();
+                            self.indent();
+                            self.write_indent();
+                            self.format_expr(else_expr);
+                            self.newline();
+                            self.dedent();
+                            self.write_indent();
+                            self.write("}");
+                        }
                     }
                 }
             }
@@ -659,13 +853,13 @@ impl Formatter {
                 self.format_block(block);
             }
             Expr::Try { expr, .. } => {
-                self.format_expr(expr);
+                self.format_operand(expr, POSTFIX_PREC);
                 self.write("?");
             }
             Expr::TryElse {
                 expr, else_expr, ..
             } => {
-                self.format_expr(expr);
+                self.format_operand(expr, POSTFIX_PREC);
                 self.write(" ?else ");
                 self.format_expr(else_expr);
             }
@@ -738,7 +932,7 @@ impl Formatter {
                 self.write("\"");
                 for part in parts {
                     match part {
-                        StringPart::Literal(s) => self.write(&s.replace('"', "\\\"")),
+                        StringPart::Literal(s) => self.write(&escape_string(s)),
                         StringPart::Expr(expr) => {
                             self.write("${");
                             self.format_expr(expr);
@@ -778,27 +972,53 @@ impl Formatter {
                 inclusive,
                 ..
             } => {
-                self.format_expr(start);
+                self.format_operand(start, RANGE_PREC + 1);
                 if *inclusive {
                     self.write("..=");
                 } else {
                     self.write("..");
                 }
-                self.format_expr(end);
+                self.format_operand(end, RANGE_PREC + 1);
             }
             Expr::IndexAccess { expr, index, .. } => {
-                self.format_expr(expr);
+                self.format_operand(expr, POSTFIX_PREC);
                 self.write("[");
                 self.format_expr(index);
                 self.write("]");
             }
             Expr::Await { expr, .. } => {
                 self.write("await ");
-                self.format_expr(expr);
+                self.format_operand(expr, PREFIX_PREC);
+            }
+            Expr::Quantifier {
+                kind,
+                binding,
+                iter,
+                body,
+                ..
+            } => {
+                self.write(kind.as_str());
+                self.write(" ");
+                self.write(binding);
+                self.write(" in ");
+                self.format_expr(iter);
+                self.write(": ");
+                self.format_expr(body);
             }
             Expr::Hole { .. } => {
                 self.write("???");
             }
+            Expr::MacroCall { name, args, .. } => {
+                self.write(name);
+                self.write("!(");
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.format_expr(arg);
+                }
+                self.write(")");
+            }
         }
     }
 
@@ -811,7 +1031,7 @@ impl Formatter {
                 self.write(&value.to_string());
             }
             Pattern::FloatLit { value, .. } => {
-                self.write(&format!("{}", value));
+                self.write(&float_text(*value));
             }
             Pattern::BoolLit { value, .. } => {
                 self.write(if *value { "true" } else { "false" });
@@ -837,7 +1057,7 @@ impl Formatter {
                     self.write(")");
                 }
             }
-            Pattern::Record { fields, .. } => {
+            Pattern::Record { fields, rest, .. } => {
                 self.write("{ ");
                 for (i, (name, pattern)) in fields.iter().enumerate() {
                     if i > 0 {
@@ -845,10 +1065,13 @@ impl Formatter {
                     }
                     self.write(name);
                     if !matches!(pattern, Pattern::Ident { name: n, .. } if n == name) {
-                        self.write(" = ");
+                        self.write(": ");
                         self.format_pattern(pattern);
                     }
                 }
+                if *rest {
+                    self.write(if fields.is_empty() { ".." } else { ", .." });
+                }
                 self.write(" }");
             }
             Pattern::Tuple { elements, .. } => {
@@ -864,6 +1087,76 @@ impl Formatter {
         }
     }
 
+    /// Format a single type expression as canonical text
+    pub fn type_expr_text(&mut self, ty: &TypeExpr) -> String {
+        self.capture(|f| f.format_type_expr(ty))
+    }
+
+    /// Format a single expression as canonical text
+    pub fn expr_text(&mut self, expr: &Expr) -> String {
+        self.capture(|f| f.format_expr(expr))
+    }
+
+    /// Format a block (including braces) as canonical text
+    pub fn block_text(&mut self, block: &Block) -> String {
+        self.capture(|f| f.format_block(block))
+    }
+
+    /// Format a function's signature on one line, without contracts or body
+    /// (e.g., `public fn read[T](path: Text) -> T effects(Fs)`)
+    pub fn fn_signature(&mut self, fndef: &FnDef) -> String {
+        self.capture(|f| {
+            if fndef.visibility == Visibility::Public {
+                f.write("public ");
+            }
+            if fndef.is_async {
+                f.write("async ");
+            }
+            f.write("fn ");
+            f.write(&fndef.name);
+            f.format_type_params(&fndef.type_params);
+            f.write("(");
+            for (i, param) in fndef.params.iter().enumerate() {
+                if i > 0 {
+                    f.write(", ");
+                }
+                if let Some(ref pattern) = param.pattern {
+                    f.format_pattern(pattern);
+                } else {
+                    f.write(&param.name);
+                }
+                f.write(": ");
+                f.format_type_expr(&param.ty);
+            }
+            f.write(")");
+            if let Some(ret) = &fndef.return_type {
+                f.write(" -> ");
+                f.format_type_expr(ret);
+            }
+            if !fndef.effects.is_empty() {
+                f.write(" effects(");
+                f.write(&fndef.effects.join(", "));
+                f.write(")");
+            }
+        })
+    }
+
+    /// Format a function's signature and contracts as they are laid out
+    /// before its body, at the top level
+    /// (e.g., `fn greet()\n  effects(Console)`)
+    pub fn fn_header(&mut self, fndef: &FnDef) -> String {
+        self.capture(|f| f.format_fn_header(fndef))
+    }
+
+    /// Run a formatting step into a fresh buffer and return what it wrote
+    fn capture(&mut self, step: impl FnOnce(&mut Self)) -> String {
+        let saved_output = std::mem::take(&mut self.output);
+        let saved_indent = std::mem::replace(&mut self.indent_level, 0);
+        step(self);
+        self.indent_level = saved_indent;
+        std::mem::replace(&mut self.output, saved_output)
+    }
+
     // Helper methods
 
     fn write(&mut self, s: &str) {
@@ -897,17 +1190,168 @@ impl Default for Formatter {
 
 fn escape_string(s: &str) -> String {
     let mut result = String::new();
-    for c in s.chars() {
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
         match c {
             '"' => result.push_str("\\\""),
             '\\' => result.push_str("\\\\"),
             '\n' => result.push_str("\\n"),
             '\r' => result.push_str("\\r"),
             '\t' => result.push_str("\\t"),
+            '$' if chars.peek() == Some(&'{') => result.push_str("\\$"),
             c => result.push(c),
         }
     }
     result
 }
+
+/// Binding strength of ranges, prefix operators, and postfix forms (calls,
+/// field and index access, `?`), relative to the binary operators
+const RANGE_PREC: u8 = 1;
+const PREFIX_PREC: u8 = 8;
+const POSTFIX_PREC: u8 = 9;
+
+/// Precedence of a binary operator, as the parser assigns it
+fn binary_precedence(op: BinaryOp) -> u8 {
+    match op {
+        BinaryOp::Pipe => 0,
+        BinaryOp::Or => 2,
+        BinaryOp::And => 3,
+        BinaryOp::Eq | BinaryOp::Ne => 4,
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 5,
+        BinaryOp::Add | BinaryOp::Sub => 6,
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 7,
+    }
+}
+
+/// How tightly an expression binds when it appears as an operand
+fn expr_precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Binary { op, .. } => binary_precedence(*op),
+        Expr::Range { .. } => RANGE_PREC,
+        Expr::Unary { .. } | Expr::Await { .. } => PREFIX_PREC,
+        _ => POSTFIX_PREC,
+    }
+}
+
+/// A float literal that lexes back as a float: whole numbers keep a `.0`
+fn float_text(value: f64) -> String {
+    crate::interpreter::value::format_float(value)
+}
+
+/// Render a module as canonical source.
+///
+/// The module need not come from the parser: code generators can build the
+/// AST by hand and emit it instead of templating text. Spans and node IDs
+/// are ignored, and parentheses and escapes are added wherever the tree
+/// needs them to parse back to itself.
+pub fn emit(module: &Module) -> String {
+    Formatter::new().format_module(module)
+}
+
+/// Check that formatting `source` is stable: the formatted text parses to
+/// the same program, and formatting it again changes nothing.
+///
+/// Returns a diagnostic for each instability found, which is a formatter
+/// bug rather than a problem in the source. A `source` that does not parse
+/// is returned as its parse errors.
+pub fn verify(path: &Path, source: &str) -> Result<Vec<Diagnostic>, DiagnosticBag> {
+    use crate::diagnostics::error_codes::internal;
+
+    let original = parse_source(source, path)?;
+    let first = Formatter::new().format_module(&original);
+    let bug = |code: &str, message: &str, span: Span| {
+        Diagnostic::error(code)
+            .message(message)
+            .span(span)
+            .note(Note::new(
+                "this is a formatter bug; please report it with the source file",
+            ))
+    };
+
+    let reparsed = match parse_source(&first, path) {
+        Ok(module) => module,
+        Err(errors) => {
+            let detail = errors
+                .diagnostics()
+                .first()
+                .map(|d| format!("formatted output: {}", d.message))
+                .unwrap_or_default();
+            return Ok(vec![bug(
+                internal::FORMATTER_INVALID_OUTPUT,
+                "Formatted output does not parse",
+                original.span.clone(),
+            )
+            .note(Note::new(detail))
+            .build()]);
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+    let before: Vec<serde_json::Value> = original.items.iter().map(shape).collect();
+    let after: Vec<serde_json::Value> = reparsed.items.iter().map(shape).collect();
+    if before != after {
+        // Point at the first item whose structure changed
+        let changed = before
+            .iter()
+            .zip(&after)
+            .position(|(a, b)| a != b)
+            .unwrap_or(before.len().min(after.len()));
+        let span = original
+            .items
+            .get(changed)
+            .map_or_else(|| original.span.clone(), |item| item.span().clone());
+        diagnostics.push(
+            bug(
+                internal::FORMATTER_CHANGED_AST,
+                "Formatting changes the meaning of this item",
+                span,
+            )
+            .note(Note::new(format!(
+                "the formatted file parses to {} item(s) that differ from the original {}",
+                after.len(),
+                before.len()
+            )))
+            .build(),
+        );
+    }
+
+    let second = Formatter::new().format_module(&reparsed);
+    if second != first {
+        let diff = crate::text_diff::TextDiff::new(&first, &second);
+        diagnostics.push(
+            bug(
+                internal::FORMATTER_NOT_IDEMPOTENT,
+                "Formatting is not idempotent: a second pass changes the output",
+                original.span.clone(),
+            )
+            .note(Note::new(diff.unified("first pass", "second pass")))
+            .build(),
+        );
+    }
+    Ok(diagnostics)
+}
+
+/// The structure of an item, without spans and node IDs
+fn shape(item: &Item) -> serde_json::Value {
+    fn strip(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                map.retain(|key, _| key != "id" && key != "span" && !key.ends_with("_span"));
+                // Destructured parameters get a name derived from a node ID
+                if map.get("pattern").is_some_and(|p| !p.is_null()) {
+                    map.remove("name");
+                }
+                map.values_mut().for_each(strip);
+            }
+            serde_json::Value::Array(values) => values.iter_mut().for_each(strip),
+            _ => {}
+        }
+    }
+    let mut value = serde_json::to_value(item).unwrap_or_default();
+    strip(&mut value);
+    value
+}
+
 #[cfg(test)]
 mod tests;
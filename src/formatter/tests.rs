@@ -82,6 +82,26 @@ fn test_format_match() {
     assert!(output.contains("Some(v) => v"));
 }
 
+#[test]
+fn test_format_record_pattern_rest() {
+    let output = format_source(
+        "module example\n\nfn f(p: { x: Int, y: Int }) -> Int {\n  match p {\n    {x, y: v, ..} => x + v\n  }\n}\n",
+    );
+    assert!(
+        output.contains("{ x, y: v, .. } => x + v"),
+        "output: {}",
+        output
+    );
+}
+
+#[test]
+fn test_format_record_pattern_rewrites_equals() {
+    let output = format_source(
+        "module example\n\nfn f(p: { x: Int, y: Int }) -> Int {\n  let { x = a, y } = p\n  a + y\n}\n",
+    );
+    assert!(output.contains("let { x: a, y } = p"), "output: {}", output);
+}
+
 #[test]
 fn test_format_idempotent() {
     let input = "module example\n\nfn add(a: Int, b: Int) -> Int {\n  a + b\n}\n";
@@ -102,6 +122,20 @@ fn test_format_trait_def() {
     );
 }
 
+#[test]
+fn test_format_quantifiers() {
+    let source = "module example\n\nfn sorted(xs: List[Int]) -> Bool\n  requires forall x in xs: x >= 0\n{\n  (exists x in xs: x > 1) or xs.is_empty()\n}\n";
+    let output = format_source(source);
+    assert_eq!(output, source);
+}
+
+#[test]
+fn test_format_trait_method_contract() {
+    let source = "module example\n\ntrait Stack {\n  fn pop(self: Self, n: Int) -> Int\n    requires n > 0\n    ensures result >= 0\n}\n";
+    let output = format_source(source);
+    assert_eq!(output, source);
+}
+
 #[test]
 fn test_format_effect_def() {
     let output =
@@ -177,3 +211,248 @@ fn test_escape_string_function() {
     assert_eq!(escape_string("tab\there"), "tab\\there");
     assert_eq!(escape_string("back\\slash"), "back\\\\slash");
 }
+
+#[test]
+fn test_format_preserves_attributes() {
+    let source = "module example\n\n@allow(unused_function)\nfn helper() -> Int {\n  @allow(unused_variable)\n  let x = 1\n  2\n}\n";
+    let output = format_source(source);
+    assert!(output.contains("@allow(unused_function)\nfn helper() -> Int"));
+    assert!(output.contains("  @allow(unused_variable)\n  let x = 1"));
+}
+
+#[test]
+fn test_format_preserves_cfg_attributes() {
+    let source = "module example\n\n@cfg( test )\nfn fixture() -> Int {\n  1\n}\n\n@cfg(feature=\"net\")\nfn fetch() -> Int {\n  2\n}\n";
+    let output = format_source(source);
+    assert!(output.contains("@cfg(test)\nfn fixture() -> Int"));
+    assert!(output.contains("@cfg(feature = \"net\")\nfn fetch() -> Int"));
+    assert_eq!(format_source(&output), output);
+}
+
+#[test]
+fn test_format_test_hooks() {
+    let source = "module example\n\nbefore_each using effects(Clock = Clock.fixed(1)) {\n  { start = Clock.now() }\n}\n\nafter_each {\n  assert(true)\n}\n";
+    assert_eq!(format_source(source), source);
+}
+
+#[test]
+fn test_fn_signature_text() {
+    let module = crate::parser::parse_source(
+        "module m\n\npublic fn load[T](path: Text, n: Int) -> Option[T] effects(Fs, Console)\n  requires n > 0\n{\n  None\n}\n",
+        &std::path::PathBuf::from("test.astra"),
+    )
+    .unwrap();
+    let Item::FnDef(def) = &module.items[0] else {
+        panic!("expected a function");
+    };
+    let mut formatter = Formatter::new();
+    assert_eq!(
+        formatter.fn_signature(def),
+        "public fn load[T](path: Text, n: Int) -> Option[T] effects(Fs, Console)"
+    );
+    assert_eq!(formatter.expr_text(&def.requires[0]), "n > 0");
+}
+
+#[test]
+fn test_float_text_keeps_decimal_point() {
+    assert_eq!(float_text(0.0), "0.0");
+    assert_eq!(float_text(2.0), "2.0");
+    assert_eq!(float_text(3.25), "3.25");
+}
+
+#[test]
+fn test_format_keeps_needed_parentheses() {
+    let output = format_source(
+        "module example\n\nfn f(a: Int, b: Int) -> Int {\n  ((a + 1) * (b - a)) - (a - b) + -(a + b)\n}\n",
+    );
+    assert!(output.contains("  (a + 1) * (b - a) - (a - b) + -(a + b)\n"));
+}
+
+#[test]
+fn test_format_async_fn() {
+    let output = format_source("module example\n\nasync fn answer() -> Int {\n  42\n}\n");
+    assert!(output.contains("async fn answer() -> Int"));
+}
+
+#[test]
+fn test_format_preserves_doc_comments() {
+    let source = "##! Geometry.\n##!\n##! More.\nmodule example\n\n## Area of a square.\n##   side squared\n@allow(unused_function)\nfn area(side: Int) -> Int\n{\n  side * side\n}\n\n## Named things\ntrait Named {\n  fn name(self: Self) -> Text\n}\n";
+    assert_eq!(format_source(source), source);
+}
+
+#[test]
+fn test_format_preserves_script_header() {
+    let source = "#!/usr/bin/env astra\n#: capabilities = [\"Console\"]\nmodule script\nfn main() effects(Console) { Console.println(\"hi\") }\n";
+    let formatted = format_source(source);
+    assert!(
+        formatted.starts_with(
+            "#!/usr/bin/env astra\n#: capabilities = [\"Console\"]\n\nmodule script\n"
+        ),
+        "{}",
+        formatted
+    );
+    assert_eq!(format_source(&formatted), formatted);
+}
+
+#[test]
+fn test_verify_stable_source() {
+    let source = "module example\n\nenum Shape =\n  | Circle(r: Float)\n  | Empty\n\nfn area(s: Shape) -> Float {\n  match s {\n    Circle(r) => r * r\n    Empty => 0.0\n  }\n}\n";
+    let path = PathBuf::from("test.astra");
+    assert!(verify(&path, source).unwrap().is_empty());
+    // Unformatted input is also stable once formatted
+    assert!(verify(&path, "module example\nfn f()->Int{ 1+2 }")
+        .unwrap()
+        .is_empty());
+    assert!(verify(&path, "module example\n\nfn f( {").is_err());
+    // Destructured parameters are compared by pattern, not synthetic name
+    assert!(verify(
+        &path,
+        "module example\nfn f({x, y}: {x: Int, y: Int}) -> Int { x }"
+    )
+    .unwrap()
+    .is_empty());
+}
+
+#[test]
+fn test_shape_ignores_layout() {
+    let parse = |source: &str| {
+        crate::parser::parse_source(source, &PathBuf::from("test.astra"))
+            .unwrap()
+            .items
+    };
+    let compact = parse("module m\nfn f(x: Int) -> Int { x + 1 }");
+    let spread = parse("module m\n\nfn f(x: Int) -> Int\n{\n  x + 1\n}\n");
+    let changed = parse("module m\nfn f(x: Int) -> Int { x + 2 }");
+    assert_eq!(shape(&compact[0]), shape(&spread[0]));
+    assert_ne!(shape(&compact[0]), shape(&changed[0]));
+}
+
+#[test]
+fn test_emit_built_module_parses_back() {
+    let span = || Span::file("gen.astra");
+    let ident = |name: &str| Expr::Ident {
+        id: NodeId::new(),
+        span: span(),
+        name: name.to_string(),
+    };
+    let text = |value: &str| Expr::TextLit {
+        id: NodeId::new(),
+        span: span(),
+        value: value.to_string(),
+    };
+    let int_type = TypeExpr::Named {
+        id: NodeId::new(),
+        span: span(),
+        name: "Int".to_string(),
+        args: Vec::new(),
+    };
+    // `(n ?else 0) * 2` and an `else` branch that isn't a block
+    let body = Expr::If {
+        id: NodeId::new(),
+        span: span(),
+        cond: Box::new(Expr::Binary {
+            id: NodeId::new(),
+            span: span(),
+            op: BinaryOp::Mul,
+            left: Box::new(Expr::TryElse {
+                id: NodeId::new(),
+                span: span(),
+                expr: Box::new(ident("n")),
+                else_expr: Box::new(Expr::IntLit {
+                    id: NodeId::new(),
+                    span: span(),
+                    value: 0,
+                }),
+            }),
+            right: Box::new(Expr::IntLit {
+                id: NodeId::new(),
+                span: span(),
+                value: 2,
+            }),
+        }),
+        then_branch: Box::new(Block {
+            id: NodeId::new(),
+            span: span(),
+            stmts: Vec::new(),
+            expr: Some(Box::new(text("costs ${n}\n"))),
+        }),
+        else_branch: Some(Box::new(Expr::StringInterp {
+            id: NodeId::new(),
+            span: span(),
+            parts: vec![
+                StringPart::Literal("\"".to_string()),
+                StringPart::Expr(Box::new(ident("n"))),
+            ],
+        })),
+    };
+    let module = Module {
+        id: NodeId::new(),
+        span: span(),
+        name: ModulePath {
+            id: NodeId::new(),
+            span: span(),
+            segments: vec!["gen".to_string()],
+        },
+        header: None,
+        doc: None,
+        items: vec![Item::FnDef(FnDef {
+            id: NodeId::new(),
+            span: span(),
+            visibility: Visibility::Public,
+            is_async: false,
+            name: "label".to_string(),
+            type_params: Vec::new(),
+            type_param_bounds: Vec::new(),
+            params: vec![Param {
+                id: NodeId::new(),
+                span: span(),
+                name: "n".to_string(),
+                ty: int_type,
+                pattern: None,
+            }],
+            return_type: None,
+            effects: Vec::new(),
+            effects_span: None,
+            requires: Vec::new(),
+            ensures: Vec::new(),
+            body: Block {
+                id: NodeId::new(),
+                span: span(),
+                stmts: Vec::new(),
+                expr: Some(Box::new(body)),
+            },
+            attributes: Vec::new(),
+            doc: None,
+        })],
+    };
+
+    let source = emit(&module);
+    assert!(source.contains("if (n ?else 0) * 2 {"), "{}", source);
+    assert!(source.contains(r#""costs \${n}\n""#), "{}", source);
+    assert!(source.contains(r#"} else {"#), "{}", source);
+    assert!(source.contains(r#""\"${n}""#), "{}", source);
+
+    // Parses back to the same tree, with the `else` branch now a block
+    let parsed = crate::parser::parse_source(&source, &PathBuf::from("gen.astra")).unwrap();
+    let Item::FnDef(label) = &parsed.items[0] else {
+        panic!("expected a function");
+    };
+    let Some(Expr::If {
+        cond,
+        then_branch,
+        else_branch: Some(else_branch),
+        ..
+    }) = label.body.expr.as_deref()
+    else {
+        panic!("expected an if");
+    };
+    assert!(
+        matches!(cond.as_ref(), Expr::Binary { left, .. } if matches!(**left, Expr::TryElse { .. }))
+    );
+    assert!(matches!(
+        then_branch.expr.as_deref(),
+        Some(Expr::TextLit { value, .. }) if value == "costs ${n}\n"
+    ));
+    assert!(matches!(else_branch.as_ref(), Expr::Block { .. }));
+    assert_eq!(emit(&parsed), source);
+}
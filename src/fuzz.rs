@@ -0,0 +1,518 @@
+//! Fuzzing of the toolchain itself (`astra fuzz`)
+//!
+//! Generates seeded random and mutated Astra sources and feeds them to the
+//! parser, the type checker, or the interpreter. A case fails when any stage
+//! panics, or when the formatted output of a module no longer parses. Failing
+//! inputs are minimized before they are reported, and the same seed always
+//! produces the same cases.
+
+use crate::formatter::Formatter;
+use crate::interpreter::{EvalLimits, Interpreter, RandCapability, SeededRand};
+use crate::parser::ast::{Item, Module};
+use crate::parser::parse_source;
+use crate::typechecker::TypeChecker;
+use std::cell::RefCell;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+
+/// The stage of the toolchain to fuzz. Each target also runs the stages
+/// before it.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuzzTarget {
+    /// Parse, then check that the formatted module parses again
+    Parser,
+    /// Parse and type check
+    Typechecker,
+    /// Parse, type check, and run `main` and the test blocks
+    Interpreter,
+}
+
+impl FuzzTarget {
+    pub fn name(self) -> &'static str {
+        match self {
+            FuzzTarget::Parser => "parser",
+            FuzzTarget::Typechecker => "typechecker",
+            FuzzTarget::Interpreter => "interpreter",
+        }
+    }
+}
+
+/// Why a fuzz case failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FuzzFailure {
+    /// A stage panicked
+    Panic {
+        message: String,
+        /// `file:line:column` of the panic in the toolchain
+        location: Option<String>,
+    },
+    /// The formatter produced a module that doesn't parse
+    FormatRoundTrip { error: String },
+}
+
+impl FuzzFailure {
+    /// Whether two failures are the same bug, so that minimizing an input
+    /// doesn't wander off to a different one
+    fn same_bug(&self, other: &FuzzFailure) -> bool {
+        match (self, other) {
+            (FuzzFailure::Panic { location: a, .. }, FuzzFailure::Panic { location: b, .. }) => {
+                a == b
+            }
+            (FuzzFailure::FormatRoundTrip { .. }, FuzzFailure::FormatRoundTrip { .. }) => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for FuzzFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FuzzFailure::Panic {
+                message,
+                location: Some(location),
+            } => write!(f, "panicked at {}: {}", location, message),
+            FuzzFailure::Panic { message, .. } => write!(f, "panicked: {}", message),
+            FuzzFailure::FormatRoundTrip { error } => {
+                write!(f, "formatted output does not parse: {}", error)
+            }
+        }
+    }
+}
+
+/// A failing case found by [`fuzz`]
+#[derive(Debug, Clone)]
+pub struct FuzzCrash {
+    /// Iteration that produced the failing input
+    pub iteration: u64,
+    pub failure: FuzzFailure,
+    /// The generated input
+    pub input: String,
+    /// The smallest input found that still fails the same way
+    pub minimized: String,
+}
+
+/// Result of a fuzzing run
+#[derive(Debug, Clone)]
+pub struct FuzzReport {
+    /// Number of cases run
+    pub cases: u64,
+    /// The first failing case, which ends the run
+    pub crash: Option<FuzzCrash>,
+}
+
+/// Limits that keep generated programs from hanging the interpreter
+const LIMITS: EvalLimits = EvalLimits {
+    max_steps: 100_000,
+    max_call_depth: 200,
+};
+
+/// Maximum length of a generated input, in characters
+const MAX_INPUT_LEN: usize = 4096;
+
+/// Maximum number of cases to run while minimizing one input
+const MAX_MINIMIZE_RUNS: usize = 2000;
+
+/// Programs that mutations start from, along with any corpus passed to [`fuzz`]
+const BUILTIN_CORPUS: &[&str] = &[
+    r#"module fuzz
+
+fn add(a: Int, b: Int) -> Int {
+  a + b
+}
+
+fn main() -> Int {
+  add(1, 2)
+}
+"#,
+    r#"module fuzz
+
+enum Shape =
+  | Circle(r: Float)
+  | Rect(w: Float, h: Float)
+
+fn area(s: Shape) -> Float {
+  match s {
+    Circle(r) => 3.14 * r * r
+    Rect(w, h) => w * h
+  }
+}
+
+test "area" {
+  assert_eq(area(Rect(2.0, 3.0)), 6.0)
+}
+"#,
+    r#"module fuzz
+
+type Point = { x: Int, y: Int }
+
+fn shift(p: Point, dx: Int) -> Point {
+  { x = p.x + dx, y = p.y }
+}
+
+fn main() -> Int {
+  let p = shift({ x = 1, y = 2 }, 3)
+  let xs = [p.x, p.y, 5]
+  let mut total = 0
+  for x in xs {
+    total = total + x
+  }
+  total
+}
+"#,
+    r#"module fuzz
+
+fn find(xs: List[Int], target: Int) -> Option[Int]
+  requires target >= 0
+{
+  let mut i = 0
+  while i < xs.len() {
+    if xs.get(i) == Some(target) {
+      return Some(i)
+    }
+    i = i + 1
+  }
+  None
+}
+
+fn parse(text: Text) -> Result[Int, Text] {
+  if text.len() > 0 { Ok(text.len()) } else { Err("empty") }
+}
+
+property "found" {
+  let n = Rand.int(0, 10)
+  assert(find([n], n) == Some(0))
+}
+"#,
+    r#"module fuzz
+
+trait Describe {
+  fn describe(self) -> Text
+}
+
+impl Describe for Int {
+  fn describe(self) -> Text {
+    "int ${self}"
+  }
+}
+
+fn twice(f: (Int) -> Int, x: Int) -> Int {
+  f(f(x))
+}
+
+fn main() -> Text {
+  let pair = (twice(fn(x) { x * 2 }, 3), "done")
+  pair.0.describe()
+}
+"#,
+];
+
+/// Source fragments that random inputs and insertions are built from
+const FRAGMENTS: &[&str] = &[
+    "module fuzz\n",
+    "fn ",
+    "f",
+    "x",
+    "main",
+    "(",
+    ")",
+    "{",
+    "}",
+    "[",
+    "]",
+    "<",
+    ">",
+    ",",
+    ":",
+    ".",
+    "..",
+    "=",
+    "==",
+    "=>",
+    "->",
+    "|",
+    "?",
+    "+",
+    "-",
+    "*",
+    "/",
+    "%",
+    "let ",
+    "mut ",
+    "if ",
+    "else ",
+    "match ",
+    "for ",
+    "in ",
+    "while ",
+    "return ",
+    "break",
+    "type ",
+    "enum ",
+    "trait ",
+    "impl ",
+    "effect ",
+    "using effects(Rand)",
+    "requires ",
+    "ensures ",
+    "test ",
+    "property ",
+    "before_each ",
+    "assert ",
+    "import ",
+    "public ",
+    "@cfg(test)\n",
+    "@deprecated(\"old\")\n",
+    "## doc\n",
+    "# comment\n",
+    "Int",
+    "Text",
+    "Bool",
+    "Option[Int]",
+    "Some(",
+    "None",
+    "Ok(",
+    "Err(",
+    "0",
+    "1",
+    "-1",
+    "9223372036854775807",
+    "1.5",
+    "true",
+    "\"text\"",
+    "\"${x}\"",
+    "\"\"\"\n",
+    "\\",
+    "$",
+    "'",
+    "\"",
+    "\n",
+    " ",
+    "\t",
+    "é",
+];
+
+/// Generate the input for one fuzz case
+pub fn generate(rng: &SeededRand, corpus: &[String]) -> String {
+    let pick = |len: usize| rng.int(0, len as i64 - 1) as usize;
+    let mut chars: Vec<char> = if corpus.is_empty() || rng.int(0, 3) == 0 {
+        // Random fragments, usually behind a module header
+        let mut text = if rng.bool() {
+            "module fuzz\n\n".to_string()
+        } else {
+            String::new()
+        };
+        for _ in 0..rng.int(1, 80) {
+            text.push_str(FRAGMENTS[pick(FRAGMENTS.len())]);
+        }
+        text.chars().collect()
+    } else {
+        corpus[pick(corpus.len())].chars().collect()
+    };
+
+    for _ in 0..rng.int(0, 4) {
+        mutate(rng, &mut chars);
+    }
+    chars.truncate(MAX_INPUT_LEN);
+    chars.into_iter().collect()
+}
+
+/// Apply one random mutation
+fn mutate(rng: &SeededRand, chars: &mut Vec<char>) {
+    let len = chars.len() as i64;
+    let pos = |rng: &SeededRand| rng.int(0, len) as usize;
+    match rng.int(0, 5) {
+        // Delete a range
+        0 if len > 0 => {
+            let start = pos(rng);
+            let end = (start + rng.int(1, 16) as usize).min(chars.len());
+            chars.drain(start..end);
+        }
+        // Duplicate a range
+        1 if len > 0 => {
+            let start = pos(rng);
+            let end = (start + rng.int(1, 32) as usize).min(chars.len());
+            let copy: Vec<char> = chars[start..end].to_vec();
+            let at = pos(rng);
+            chars.splice(at..at, copy);
+        }
+        // Replace a character
+        2 if len > 0 => {
+            let at = rng.int(0, len - 1) as usize;
+            chars[at] = char::from_u32(rng.int(0, 0x7f) as u32).unwrap_or(' ');
+        }
+        // Swap two lines
+        3 => {
+            let text: String = chars.iter().collect();
+            let mut lines: Vec<&str> = text.split('\n').collect();
+            if lines.len() > 1 {
+                let a = rng.int(0, lines.len() as i64 - 1) as usize;
+                let b = rng.int(0, lines.len() as i64 - 1) as usize;
+                lines.swap(a, b);
+            }
+            *chars = lines.join("\n").chars().collect();
+        }
+        // Truncate
+        4 if len > 0 => chars.truncate(pos(rng)),
+        // Insert a fragment
+        _ => {
+            let fragment = FRAGMENTS[rng.int(0, FRAGMENTS.len() as i64 - 1) as usize];
+            let at = pos(rng);
+            chars.splice(at..at, fragment.chars());
+        }
+    }
+}
+
+thread_local! {
+    /// Location of the last panic on this thread, recorded by the hook that
+    /// [`fuzz`] installs
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Run one input through `target`, reporting panics and formatter round-trip
+/// failures. Ordinary diagnostics and runtime errors are not failures.
+pub fn run_case(target: FuzzTarget, source: &str) -> Result<(), FuzzFailure> {
+    PANIC_LOCATION.with(|location| location.borrow_mut().take());
+    match std::panic::catch_unwind(AssertUnwindSafe(|| run_stages(target, source))) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Box<dyn Any>".to_string());
+            Err(FuzzFailure::Panic {
+                message,
+                location: PANIC_LOCATION.with(|location| location.borrow_mut().take()),
+            })
+        }
+    }
+}
+
+fn run_stages(target: FuzzTarget, source: &str) -> Result<(), FuzzFailure> {
+    let path = Path::new("fuzz.astra");
+    let Ok(module) = parse_source(source, path) else {
+        return Ok(());
+    };
+
+    if target == FuzzTarget::Parser {
+        let formatted = Formatter::new().format_module(&module);
+        if let Err(e) = parse_source(&formatted, path) {
+            let error = e
+                .diagnostics()
+                .first()
+                .map_or_else(String::new, |d| d.message.clone());
+            return Err(FuzzFailure::FormatRoundTrip { error });
+        }
+        return Ok(());
+    }
+
+    let _ = TypeChecker::new().check_module(&module);
+
+    // Programs run even when they don't type check, as with `astra run`
+    if target == FuzzTarget::Interpreter {
+        run_program(&module);
+    }
+    Ok(())
+}
+
+fn run_program(module: &Module) {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(LIMITS);
+    if interpreter.eval_module(module).is_err() {
+        return;
+    }
+    for item in &module.items {
+        if let Item::Test(test) = item {
+            interpreter.env.push_scope();
+            let _ = interpreter.eval_block(&test.body);
+            interpreter.env.pop_scope();
+        }
+    }
+}
+
+/// Shrink a failing input by deleting lines, then characters, for as long as
+/// it keeps failing with the same bug
+pub fn minimize(target: FuzzTarget, source: &str, failure: &FuzzFailure) -> String {
+    let mut runs = 0;
+    let mut still_fails = |candidate: &str| {
+        runs += 1;
+        runs <= MAX_MINIMIZE_RUNS
+            && run_case(target, candidate).is_err_and(|found| found.same_bug(failure))
+    };
+
+    let lines: Vec<String> = source.split_inclusive('\n').map(String::from).collect();
+    let lines = reduce(lines, &mut still_fails);
+    let chars: Vec<String> = lines.concat().chars().map(String::from).collect();
+    reduce(chars, &mut still_fails).concat()
+}
+
+/// Remove chunks of `units`, halving the chunk size each round, keeping each
+/// removal after which the joined text still fails
+fn reduce(mut units: Vec<String>, still_fails: &mut impl FnMut(&str) -> bool) -> Vec<String> {
+    let mut chunk = units.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start < units.len() {
+            let end = (start + chunk).min(units.len());
+            let candidate = [&units[..start], &units[end..]].concat();
+            if still_fails(&candidate.concat()) {
+                units = candidate;
+            } else {
+                start = end;
+            }
+        }
+        chunk /= 2;
+    }
+    units
+}
+
+/// Run `iterations` generated cases against `target`, stopping at the first
+/// failure. Mutations start from the built-in programs and `corpus`.
+pub fn fuzz(target: FuzzTarget, seed: u64, iterations: u64, corpus: &[String]) -> FuzzReport {
+    let corpus: Vec<String> = BUILTIN_CORPUS
+        .iter()
+        .map(|s| s.to_string())
+        .chain(corpus.iter().cloned())
+        .collect();
+
+    // Panics are reported as failures, so keep them off stderr
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|info| {
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        PANIC_LOCATION.with(|slot| *slot.borrow_mut() = location);
+    }));
+
+    let mut report = FuzzReport {
+        cases: 0,
+        crash: None,
+    };
+    for iteration in 0..iterations {
+        // Each case has its own generator, so any case can be reproduced
+        let rng = SeededRand::new(
+            seed.wrapping_add(iteration)
+                .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                | 1,
+        );
+        let input = generate(&rng, &corpus);
+        report.cases += 1;
+        if let Err(failure) = run_case(target, &input) {
+            let minimized = minimize(target, &input, &failure);
+            report.crash = Some(FuzzCrash {
+                iteration,
+                failure,
+                input,
+                minimized,
+            });
+            break;
+        }
+    }
+
+    std::panic::set_hook(previous_hook);
+    report
+}
+
+#[cfg(test)]
+#[path = "fuzz_tests.rs"]
+mod tests;
@@ -0,0 +1,105 @@
+use super::*;
+
+#[test]
+fn test_generate_is_deterministic() {
+    let corpus = vec!["module a\n\nfn main() -> Int { 1 }\n".to_string()];
+    for seed in 1..20 {
+        let first = generate(&SeededRand::new(seed), &corpus);
+        let second = generate(&SeededRand::new(seed), &corpus);
+        assert_eq!(first, second);
+        assert!(first.chars().count() <= MAX_INPUT_LEN);
+    }
+}
+
+#[test]
+fn test_builtin_corpus_round_trips() {
+    for source in BUILTIN_CORPUS {
+        parse_source(source, Path::new("fuzz.astra")).unwrap();
+        assert_eq!(run_case(FuzzTarget::Parser, source), Ok(()));
+    }
+}
+
+#[test]
+fn test_invalid_input_is_not_a_failure() {
+    for target in [
+        FuzzTarget::Parser,
+        FuzzTarget::Typechecker,
+        FuzzTarget::Interpreter,
+    ] {
+        assert_eq!(run_case(target, "fn ( { ] \"unterminated"), Ok(()));
+        assert_eq!(run_case(target, ""), Ok(()));
+    }
+}
+
+#[test]
+fn test_runaway_program_is_stopped() {
+    let source = r#"module fuzz
+
+fn forever(n: Int) -> Int {
+  forever(n + 1)
+}
+
+fn main() -> Int {
+  let mut i = 0
+  while true {
+    i = i + 1
+  }
+  forever(0)
+}
+
+test "recursion" {
+  assert_eq(forever(0), 0)
+}
+"#;
+    // Run in a thread with a larger stack, as `astra fuzz` does
+    let handle = std::thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(move || run_case(FuzzTarget::Interpreter, source))
+        .unwrap();
+    assert_eq!(handle.join().unwrap(), Ok(()));
+}
+
+#[test]
+fn test_failure_identity() {
+    let failure = FuzzFailure::Panic {
+        message: "boom".to_string(),
+        location: Some("src/parser/mod.rs:1:1".to_string()),
+    };
+    assert_eq!(
+        failure.to_string(),
+        "panicked at src/parser/mod.rs:1:1: boom"
+    );
+    assert!(failure.same_bug(&FuzzFailure::Panic {
+        message: "other".to_string(),
+        location: Some("src/parser/mod.rs:1:1".to_string()),
+    }));
+    assert!(!failure.same_bug(&FuzzFailure::FormatRoundTrip {
+        error: String::new()
+    }));
+}
+
+#[test]
+fn test_reduce_keeps_failing_units() {
+    let units: Vec<String> = "abcXdefYghi".chars().map(String::from).collect();
+    let mut still_fails = |text: &str| text.contains('X') && text.contains('Y');
+    assert_eq!(reduce(units, &mut still_fails).concat(), "XY");
+}
+
+#[test]
+fn test_fuzz_is_reproducible() {
+    let run = || {
+        std::thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(|| fuzz(FuzzTarget::Interpreter, 7, 50, &[]))
+            .unwrap()
+            .join()
+            .unwrap()
+    };
+    let first = run();
+    let second = run();
+    assert_eq!(first.cases, second.cases);
+    assert_eq!(
+        first.crash.map(|c| c.minimized),
+        second.crash.map(|c| c.minimized)
+    );
+}
@@ -0,0 +1,535 @@
+//! Dependency graphs: which functions call which, and which modules import
+//! which.
+//!
+//! Calls are resolved syntactically over the AST. A call to a function
+//! defined in the same module is a local edge; a call through an import (an
+//! imported item, or `module.fn(...)` on an imported module or alias) is an
+//! external edge; a call on an effect (`Console.println(...)`, or an
+//! operation of a declared effect) is an effect edge. Calls through values,
+//! such as lambdas and trait methods, cannot be resolved without types and
+//! are left out. Used by `astra graph --calls` for impact analysis and
+//! dead-code review.
+//!
+//! The module graph links each module to the modules it imports and reports
+//! import cycles with the chain of imports that forms them. Used by
+//! `astra graph --modules`.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::diagnostics::Span;
+use crate::formatter::Formatter;
+use crate::parser::ast::*;
+use crate::parser::visit::{walk_block, walk_fn};
+
+/// Effects provided by the runtime
+const BUILTIN_EFFECTS: [&str; 11] = [
+    "Console", "Fs", "Net", "Clock", "Rand", "Env", "Crypto", "Serve", "Db", "Progress", "Metrics",
+];
+
+/// Kind of a call graph node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    /// A top-level function
+    Fn,
+    /// A method in an `impl` block
+    Method,
+    /// A `test` or `property` block
+    Test,
+}
+
+/// A function (or test) that makes calls
+#[derive(Debug, Clone, Serialize)]
+pub struct CallNode {
+    /// Qualified name (`module.fn`, `module.Type.method`, `module.test "name"`)
+    pub id: String,
+    pub name: String,
+    pub module: String,
+    pub kind: NodeKind,
+    pub public: bool,
+    pub span: Span,
+}
+
+/// How a call was resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CallKind {
+    /// A function in the same module
+    Local,
+    /// A function in an imported module
+    External,
+    /// An effect operation
+    Effect,
+}
+
+/// A call from one function to another
+#[derive(Debug, Clone, Serialize)]
+pub struct CallEdge {
+    /// Id of the calling node
+    pub caller: String,
+    /// Qualified name of the callee (`module.fn` or `Effect.operation`)
+    pub callee: String,
+    pub kind: CallKind,
+    /// Location of the first call from `caller` to `callee`
+    pub span: Span,
+}
+
+/// Call graph over one or more modules
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CallGraph {
+    pub nodes: Vec<CallNode>,
+    pub edges: Vec<CallEdge>,
+}
+
+impl CallGraph {
+    /// Create an empty call graph
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the functions of `module` and the calls they make
+    pub fn add_module(&mut self, module: &Module) {
+        let resolver = Resolver::new(module);
+        let mut f = Formatter::new();
+
+        for item in &module.items {
+            match item {
+                Item::FnDef(def) => {
+                    let node = resolver.node(&def.name, NodeKind::Fn, def);
+                    resolver.calls_in_fn(&node.id, def, &mut self.edges);
+                    self.nodes.push(node);
+                }
+                Item::ImplBlock(block) => {
+                    let target = f.type_expr_text(&block.target_type);
+                    for method in &block.methods {
+                        let name = format!("{}.{}", target, method.name);
+                        let node = resolver.node(&name, NodeKind::Method, method);
+                        resolver.calls_in_fn(&node.id, method, &mut self.edges);
+                        self.nodes.push(node);
+                    }
+                }
+                Item::Test(test) => self.push_test(&resolver, &test.name, &test.span, &test.body),
+                Item::Property(prop) => {
+                    self.push_test(&resolver, &prop.name, &prop.span, &prop.body)
+                }
+                Item::TestHook(hook) => {
+                    self.push_test(&resolver, hook.kind.keyword(), &hook.span, &hook.body)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn push_test(&mut self, resolver: &Resolver, name: &str, span: &Span, body: &Block) {
+        let node = CallNode {
+            id: format!("{}.test \"{}\"", resolver.module, name),
+            name: name.to_string(),
+            module: resolver.module.clone(),
+            kind: NodeKind::Test,
+            public: false,
+            span: span.clone(),
+        };
+        resolver.calls_in_block(&node.id, body, &HashSet::new(), &mut self.edges);
+        self.nodes.push(node);
+    }
+
+    /// Private functions that no function, method, or test in the graph calls
+    pub fn unreferenced(&self) -> Vec<&CallNode> {
+        let called: HashSet<&str> = self.edges.iter().map(|e| e.callee.as_str()).collect();
+        self.nodes
+            .iter()
+            .filter(|n| {
+                n.kind == NodeKind::Fn
+                    && !n.public
+                    && n.name != "main"
+                    && !called.contains(n.id.as_str())
+            })
+            .collect()
+    }
+
+    /// Render the graph in Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph calls {\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Test => " [shape=note]",
+                _ => "",
+            };
+            out.push_str(&format!("  {}{};\n", dot_id(&node.id), shape));
+        }
+        let mut targets = HashSet::new();
+        for edge in &self.edges {
+            if edge.kind == CallKind::Effect && targets.insert(edge.callee.as_str()) {
+                out.push_str(&format!("  {} [shape=box];\n", dot_id(&edge.callee)));
+            }
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                CallKind::Local => "",
+                CallKind::External => " [style=dashed]",
+                CallKind::Effect => " [style=dotted]",
+            };
+            out.push_str(&format!(
+                "  {} -> {}{};\n",
+                dot_id(&edge.caller),
+                dot_id(&edge.callee),
+                style
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Build the call graph of a single module
+pub fn call_graph(module: &Module) -> CallGraph {
+    let mut graph = CallGraph::new();
+    graph.add_module(module);
+    graph
+}
+
+fn dot_id(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Names in scope at module level, used to resolve callees
+struct Resolver {
+    module: String,
+    /// Functions defined in the module
+    functions: HashSet<String>,
+    /// Effects declared in the module
+    effects: HashSet<String>,
+    /// Imported modules by the name they are referenced as
+    modules: HashMap<String, String>,
+    /// Imported items by name, with their qualified names
+    items: HashMap<String, String>,
+}
+
+impl Resolver {
+    fn new(module: &Module) -> Self {
+        let mut resolver = Self {
+            module: module.name.segments.join("."),
+            functions: HashSet::new(),
+            effects: HashSet::new(),
+            modules: HashMap::new(),
+            items: HashMap::new(),
+        };
+        for item in &module.items {
+            match item {
+                Item::FnDef(def) => {
+                    resolver.functions.insert(def.name.clone());
+                }
+                Item::EffectDef(def) => {
+                    resolver.effects.insert(def.name.clone());
+                }
+                Item::Import(import) => {
+                    let path = import.path.segments.join(".");
+                    match &import.kind {
+                        ImportKind::Module => {
+                            if let Some(last) = import.path.segments.last() {
+                                resolver.modules.insert(last.clone(), path);
+                            }
+                        }
+                        ImportKind::Alias(alias) => {
+                            resolver.modules.insert(alias.clone(), path);
+                        }
+                        ImportKind::Items(names) => {
+                            for name in names {
+                                resolver
+                                    .items
+                                    .insert(name.clone(), format!("{}.{}", path, name));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        resolver
+    }
+
+    fn node(&self, name: &str, kind: NodeKind, def: &FnDef) -> CallNode {
+        CallNode {
+            id: format!("{}.{}", self.module, name),
+            name: name.to_string(),
+            module: self.module.clone(),
+            kind,
+            public: def.visibility == Visibility::Public,
+            span: def.span.clone(),
+        }
+    }
+
+    fn calls_in_fn(&self, caller: &str, def: &FnDef, edges: &mut Vec<CallEdge>) {
+        // Parameters shadow functions and imports of the same name
+        let params: HashSet<String> = def.params.iter().map(|p| p.name.clone()).collect();
+        let mut found = Vec::new();
+        walk_fn(def, &mut |expr| {
+            if let Some(call) = self.resolve(expr, &params) {
+                found.push(call);
+            }
+        });
+        push_edges(caller, found, edges);
+    }
+
+    fn calls_in_block(
+        &self,
+        caller: &str,
+        block: &Block,
+        params: &HashSet<String>,
+        edges: &mut Vec<CallEdge>,
+    ) {
+        let mut found = Vec::new();
+        walk_block(block, &mut |expr| {
+            if let Some(call) = self.resolve(expr, params) {
+                found.push(call);
+            }
+        });
+        push_edges(caller, found, edges);
+    }
+
+    /// Resolve a call expression to its callee, if it names a known function
+    /// or effect
+    fn resolve(&self, expr: &Expr, params: &HashSet<String>) -> Option<(String, CallKind, Span)> {
+        match expr {
+            Expr::Call { func, span, .. } => match func.as_ref() {
+                Expr::Ident { name, .. } if params.contains(name) => None,
+                Expr::Ident { name, .. } if self.functions.contains(name) => Some((
+                    format!("{}.{}", self.module, name),
+                    CallKind::Local,
+                    span.clone(),
+                )),
+                Expr::Ident { name, .. } => self
+                    .items
+                    .get(name)
+                    .map(|qualified| (qualified.clone(), CallKind::External, span.clone())),
+                Expr::QualifiedIdent { module, name, .. } => {
+                    let path = self.modules.get(module).unwrap_or(module);
+                    Some((
+                        format!("{}.{}", path, name),
+                        CallKind::External,
+                        span.clone(),
+                    ))
+                }
+                _ => None,
+            },
+            Expr::MethodCall {
+                receiver,
+                method,
+                span,
+                ..
+            } => {
+                let Expr::Ident { name, .. } = receiver.as_ref() else {
+                    return None;
+                };
+                if params.contains(name) {
+                    None
+                } else if BUILTIN_EFFECTS.contains(&name.as_str()) || self.effects.contains(name) {
+                    Some((
+                        format!("{}.{}", name, method),
+                        CallKind::Effect,
+                        span.clone(),
+                    ))
+                } else {
+                    self.modules.get(name).map(|path| {
+                        (
+                            format!("{}.{}", path, method),
+                            CallKind::External,
+                            span.clone(),
+                        )
+                    })
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Record one edge per distinct callee, keeping the first call site
+fn push_edges(caller: &str, found: Vec<(String, CallKind, Span)>, edges: &mut Vec<CallEdge>) {
+    let mut seen = HashSet::new();
+    for (callee, kind, span) in found {
+        if seen.insert(callee.clone()) {
+            edges.push(CallEdge {
+                caller: caller.to_string(),
+                callee,
+                kind,
+                span,
+            });
+        }
+    }
+}
+
+/// A module in the module graph
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleNode {
+    /// Module name (e.g., `app.models`)
+    pub name: String,
+    /// Span of the module declaration
+    pub span: Span,
+}
+
+/// An import of one module by another
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleEdge {
+    /// Importing module
+    pub from: String,
+    /// Imported module
+    pub to: String,
+    /// Span of the import declaration
+    pub span: Span,
+}
+
+/// A cycle of imports
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportCycle {
+    /// Modules along the cycle, starting and ending with the same module
+    pub chain: Vec<String>,
+    /// Import declarations forming the cycle, one per step of `chain`
+    pub imports: Vec<Span>,
+}
+
+impl ImportCycle {
+    /// The chain as `a -> b -> a`
+    pub fn chain_text(&self) -> String {
+        self.chain.join(" -> ")
+    }
+}
+
+/// Import graph over the modules of a project
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModuleGraph {
+    pub modules: Vec<ModuleNode>,
+    pub imports: Vec<ModuleEdge>,
+}
+
+impl ModuleGraph {
+    /// Create an empty module graph
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `module` and the modules it imports
+    pub fn add_module(&mut self, module: &Module) {
+        let name = module.name.segments.join(".");
+        for item in &module.items {
+            if let Item::Import(import) = item {
+                let to = import.path.segments.join(".");
+                // Importing several items from one module is a single dependency
+                if !self.imports.iter().any(|e| e.from == name && e.to == to) {
+                    self.imports.push(ModuleEdge {
+                        from: name.clone(),
+                        to,
+                        span: import.span.clone(),
+                    });
+                }
+            }
+        }
+        self.modules.push(ModuleNode {
+            name,
+            span: module.name.span.clone(),
+        });
+    }
+
+    /// Imported modules that are not part of the graph (e.g., the standard
+    /// library), in order of first import
+    pub fn external(&self) -> Vec<&str> {
+        let mut external: Vec<&str> = Vec::new();
+        for edge in &self.imports {
+            if !self.contains(&edge.to) && !external.contains(&edge.to.as_str()) {
+                external.push(&edge.to);
+            }
+        }
+        external
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.modules.iter().any(|m| m.name == name)
+    }
+
+    /// Import cycles between modules of the graph, each reported once
+    pub fn cycles(&self) -> Vec<ImportCycle> {
+        let mut cycles = Vec::new();
+        let mut done = HashSet::new();
+        for module in &self.modules {
+            let mut stack = Vec::new();
+            self.find_cycles(&module.name, &mut stack, &mut done, &mut cycles);
+        }
+        cycles
+    }
+
+    /// Depth-first search recording a cycle for every import back onto the
+    /// current path
+    fn find_cycles<'a>(
+        &'a self,
+        name: &'a str,
+        stack: &mut Vec<&'a ModuleEdge>,
+        done: &mut HashSet<&'a str>,
+        cycles: &mut Vec<ImportCycle>,
+    ) {
+        if done.contains(name) {
+            return;
+        }
+        for edge in self.imports.iter().filter(|e| e.from == name) {
+            if !self.contains(&edge.to) {
+                continue;
+            }
+            if let Some(start) = stack.iter().position(|e| e.from == edge.to) {
+                let steps: Vec<&ModuleEdge> =
+                    stack[start..].iter().copied().chain([edge]).collect();
+                let mut chain: Vec<String> = steps.iter().map(|e| e.from.clone()).collect();
+                chain.push(edge.to.clone());
+                cycles.push(ImportCycle {
+                    chain,
+                    imports: steps.iter().map(|e| e.span.clone()).collect(),
+                });
+            } else if edge.to == name {
+                cycles.push(ImportCycle {
+                    chain: vec![edge.from.clone(), edge.to.clone()],
+                    imports: vec![edge.span.clone()],
+                });
+            } else {
+                stack.push(edge);
+                self.find_cycles(&edge.to, stack, done, cycles);
+                stack.pop();
+            }
+        }
+        done.insert(name);
+    }
+
+    /// Render the graph in Graphviz DOT format, with external modules dashed
+    /// and imports that are part of a cycle in red
+    pub fn to_dot(&self) -> String {
+        let cycles = self.cycles();
+        let in_cycle = |edge: &ModuleEdge| {
+            cycles.iter().any(|c| {
+                c.chain
+                    .windows(2)
+                    .any(|w| w[0] == edge.from && w[1] == edge.to)
+            })
+        };
+
+        let mut out = String::from("digraph modules {\n");
+        for module in &self.modules {
+            out.push_str(&format!("  {};\n", dot_id(&module.name)));
+        }
+        for name in self.external() {
+            out.push_str(&format!("  {} [style=dashed];\n", dot_id(name)));
+        }
+        for edge in &self.imports {
+            let style = if in_cycle(edge) { " [color=red]" } else { "" };
+            out.push_str(&format!(
+                "  {} -> {}{};\n",
+                dot_id(&edge.from),
+                dot_id(&edge.to),
+                style
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+#[cfg(test)]
+#[path = "graph_tests.rs"]
+mod tests;
@@ -0,0 +1,212 @@
+use super::*;
+use crate::parser::parse_source;
+use std::path::PathBuf;
+
+fn graph(source: &str) -> CallGraph {
+    call_graph(&parse_source(source, &PathBuf::from("test.astra")).unwrap())
+}
+
+fn edges(graph: &CallGraph) -> Vec<(&str, &str, CallKind)> {
+    graph
+        .edges
+        .iter()
+        .map(|e| (e.caller.as_str(), e.callee.as_str(), e.kind))
+        .collect()
+}
+
+#[test]
+fn test_call_graph_edges() {
+    let g = graph(
+        r#"module app
+
+import std.math
+import std.list as l
+import std.text.{trim}
+
+effect Logger {
+  fn log(msg: Text) -> Unit
+}
+
+fn double(x: Int) -> Int {
+  x * 2
+}
+
+fn main() effects(Console, Logger) {
+  let y = double(math.abs(-3))
+  let s = trim(" hi ")
+  Logger.log(s)
+  Console.println("${double(y)}")
+  l.head([y])
+}
+"#,
+    );
+    assert_eq!(
+        edges(&g),
+        vec![
+            ("app.main", "app.double", CallKind::Local),
+            ("app.main", "std.math.abs", CallKind::External),
+            ("app.main", "std.text.trim", CallKind::External),
+            ("app.main", "Logger.log", CallKind::Effect),
+            ("app.main", "Console.println", CallKind::Effect),
+            ("app.main", "std.list.head", CallKind::External),
+        ]
+    );
+}
+
+#[test]
+fn test_call_graph_skips_values_and_shadowed_names() {
+    let g = graph(
+        r#"module app
+
+fn helper() -> Int {
+  1
+}
+
+fn apply(helper: (Int) -> Int, xs: List[Int]) -> Int {
+  let f = fn(x: Int) { x }
+  f(xs.len()) + helper(1)
+}
+"#,
+    );
+    assert!(edges(&g).is_empty());
+}
+
+#[test]
+fn test_call_graph_methods_and_tests() {
+    let g = graph(
+        r#"module app
+
+trait Show {
+  fn show(self) -> Text
+}
+
+fn label(n: Int) -> Text {
+  "n"
+}
+
+impl Show for Int {
+  fn show(self) -> Text {
+    label(self)
+  }
+}
+
+test "label works" {
+  assert_eq(label(1), "n")
+}
+"#,
+    );
+    let nodes: Vec<(&str, NodeKind)> = g.nodes.iter().map(|n| (n.id.as_str(), n.kind)).collect();
+    assert_eq!(
+        nodes,
+        vec![
+            ("app.label", NodeKind::Fn),
+            ("app.Int.show", NodeKind::Method),
+            ("app.test \"label works\"", NodeKind::Test),
+        ]
+    );
+    assert_eq!(
+        edges(&g),
+        vec![
+            ("app.Int.show", "app.label", CallKind::Local),
+            ("app.test \"label works\"", "app.label", CallKind::Local),
+        ]
+    );
+}
+
+#[test]
+fn test_call_graph_dedups_edges_and_finds_unreferenced() {
+    let g = graph(
+        r#"module app
+
+fn used() -> Int {
+  1
+}
+
+fn unused() -> Int {
+  2
+}
+
+public fn exported() -> Int {
+  used() + used()
+}
+"#,
+    );
+    assert_eq!(g.edges.len(), 1);
+    let unreferenced: Vec<&str> = g.unreferenced().iter().map(|n| n.id.as_str()).collect();
+    assert_eq!(unreferenced, vec!["app.unused"]);
+}
+
+#[test]
+fn test_call_graph_dot() {
+    let g = graph(
+        r#"module app
+
+fn main() effects(Console) {
+  Console.println("hi")
+}
+"#,
+    );
+    assert_eq!(
+        g.to_dot(),
+        "digraph calls {\n  \"app.main\";\n  \"Console.println\" [shape=box];\n  \"app.main\" -> \"Console.println\" [style=dotted];\n}\n"
+    );
+}
+
+fn module_graph(sources: &[&str]) -> ModuleGraph {
+    let mut graph = ModuleGraph::new();
+    for source in sources {
+        graph.add_module(&parse_source(source, &PathBuf::from("test.astra")).unwrap());
+    }
+    graph
+}
+
+#[test]
+fn test_module_graph_imports() {
+    let g = module_graph(&[
+        "module app\n\nimport app.models\nimport std.math\nimport app.models.{User}\n",
+        "module app.models\n\nimport std.math as m\n",
+    ]);
+    let imports: Vec<(&str, &str)> = g
+        .imports
+        .iter()
+        .map(|e| (e.from.as_str(), e.to.as_str()))
+        .collect();
+    assert_eq!(
+        imports,
+        vec![
+            ("app", "app.models"),
+            ("app", "std.math"),
+            ("app.models", "std.math"),
+        ]
+    );
+    assert_eq!(g.external(), vec!["std.math"]);
+    assert!(g.cycles().is_empty());
+}
+
+#[test]
+fn test_module_graph_cycles() {
+    let g = module_graph(&[
+        "module a\n\nimport b\n",
+        "module b\n\nimport c\nimport std.math\n",
+        "module c\n\nimport a\n",
+        "module d\n\nimport d\nimport a\n",
+    ]);
+    let cycles = g.cycles();
+    let chains: Vec<String> = cycles.iter().map(|c| c.chain_text()).collect();
+    assert_eq!(chains, vec!["a -> b -> c -> a", "d -> d"]);
+    // One import declaration per step, pointing at the importing module
+    assert_eq!(cycles[0].imports.len(), 3);
+    assert_eq!(cycles[0].imports[0].start_line, 3);
+}
+
+#[test]
+fn test_module_graph_dot() {
+    let g = module_graph(&[
+        "module a\n\nimport b\nimport std.io\n",
+        "module b\n\nimport a\n",
+    ]);
+    assert_eq!(
+        g.to_dot(),
+        "digraph modules {\n  \"a\";\n  \"b\";\n  \"std.io\" [style=dashed];\n  \"a\" -> \"b\" [color=red];\n  \"a\" -> \"std.io\";\n  \"b\" -> \"a\" [color=red];\n}\n"
+    );
+}
@@ -0,0 +1,332 @@
+//! `Args`: command-line parsing for Astra programs.
+//!
+//! A spec is a record whose fields are made with `Args.flag`, `Args.option`,
+//! `Args.positional`, and `Args.rest`. `Args.parse(spec)` reads `Env.args()`
+//! and returns a record with the same fields holding what was passed, or
+//! `Err({ message, code })` with the help text for `--help` (code 0) or a
+//! usage error (code 2), ready to print and return from `main`.
+
+use indexmap::IndexMap;
+
+use super::error::RuntimeError;
+use super::value::{format_value, Value};
+use super::Interpreter;
+
+/// Exit code for a usage error, as in most command-line tools
+const USAGE_ERROR: i64 = 2;
+
+/// What an argument is, from the `Args` method that made it
+#[derive(Debug, Clone)]
+enum Kind {
+    /// `--name`, false unless given
+    Flag,
+    /// `--name value` or `--name=value`, parsed like its default
+    Option(Value),
+    /// The next argument that isn't an option
+    Positional,
+    /// Every argument left after the positionals
+    Rest,
+}
+
+/// One field of a spec
+#[derive(Debug, Clone)]
+struct ArgSpec {
+    field: String,
+    kind: Kind,
+    help: String,
+}
+
+impl ArgSpec {
+    /// `--dry-run` for the field `dry_run`
+    fn long(&self) -> String {
+        format!("--{}", self.field.replace('_', "-"))
+    }
+
+    /// How the argument appears in the usage line and help
+    fn label(&self) -> String {
+        match &self.kind {
+            Kind::Flag => self.long(),
+            Kind::Option(default) => format!("{} <{}>", self.long(), placeholder(default)),
+            Kind::Positional => format!("<{}>", self.field),
+            Kind::Rest => format!("[{}]...", self.field),
+        }
+    }
+
+    fn initial(&self) -> Value {
+        match &self.kind {
+            Kind::Flag => Value::Bool(false),
+            Kind::Option(default) => default.clone(),
+            Kind::Positional => Value::Text(String::new()),
+            Kind::Rest => Value::List(Vec::new()),
+        }
+    }
+}
+
+fn placeholder(default: &Value) -> &'static str {
+    match default {
+        Value::Int(_) => "INT",
+        Value::Float(_) => "FLOAT",
+        Value::Bool(_) => "BOOL",
+        _ => "TEXT",
+    }
+}
+
+/// Parse an option's value like its default
+fn convert(text: &str, default: &Value) -> Option<Value> {
+    match default {
+        Value::Int(_) => text.parse().ok().map(Value::Int),
+        Value::Float(_) => text.parse().ok().map(Value::Float),
+        Value::Bool(_) => text.parse().ok().map(Value::Bool),
+        _ => Some(Value::Text(text.to_string())),
+    }
+}
+
+/// The fields of a spec record, in the order their specs were made
+fn arg_specs(spec: &Value) -> Result<Vec<ArgSpec>, RuntimeError> {
+    let Value::Record(fields) = spec else {
+        return Err(RuntimeError::type_mismatch(
+            "record of Args specs",
+            &format_value(spec),
+        ));
+    };
+    let mut specs = Vec::new();
+    for (field, value) in fields {
+        let invalid = || RuntimeError::type_mismatch("Args spec", &format_value(value));
+        let Value::Record(parts) = value else {
+            return Err(invalid());
+        };
+        let (Some(Value::Text(kind)), Some(Value::Text(help)), Some(Value::Int(order))) =
+            (parts.get("kind"), parts.get("help"), parts.get("order"))
+        else {
+            return Err(invalid());
+        };
+        let kind = match (kind.as_str(), parts.get("default")) {
+            ("flag", _) => Kind::Flag,
+            ("option", Some(default)) => Kind::Option(default.clone()),
+            ("positional", _) => Kind::Positional,
+            ("rest", _) => Kind::Rest,
+            _ => return Err(invalid()),
+        };
+        let spec = ArgSpec {
+            field: field.clone(),
+            kind,
+            help: help.clone(),
+        };
+        specs.push((*order, spec));
+    }
+    specs.sort_by_key(|(order, _)| *order);
+    Ok(specs.into_iter().map(|(_, spec)| spec).collect())
+}
+
+fn usage(program: &str, specs: &[ArgSpec]) -> String {
+    let mut usage = format!("Usage: {} [OPTIONS]", program);
+    for spec in specs {
+        if matches!(spec.kind, Kind::Positional | Kind::Rest) {
+            usage.push(' ');
+            usage.push_str(&spec.label());
+        }
+    }
+    usage
+}
+
+/// The `--help` text: what the program is about, its usage, and a line per
+/// argument
+fn help(program: &str, about: Option<&str>, specs: &[ArgSpec]) -> String {
+    let arguments: Vec<(String, String)> = specs
+        .iter()
+        .filter(|spec| matches!(spec.kind, Kind::Positional | Kind::Rest))
+        .map(|spec| (spec.label(), spec.help.clone()))
+        .collect();
+    let mut options: Vec<(String, String)> = specs
+        .iter()
+        .filter_map(|spec| match &spec.kind {
+            Kind::Flag => Some((spec.label(), spec.help.clone())),
+            Kind::Option(Value::Text(default)) if default.is_empty() => {
+                Some((spec.label(), spec.help.clone()))
+            }
+            Kind::Option(default) => Some((
+                spec.label(),
+                format!("{} [default: {}]", spec.help, format_value(default)),
+            )),
+            _ => None,
+        })
+        .collect();
+    options.push(("-h, --help".to_string(), "Print help".to_string()));
+    let width = arguments
+        .iter()
+        .chain(&options)
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or_default();
+
+    let mut text = String::new();
+    if let Some(about) = about {
+        text.push_str(about);
+        text.push_str("\n\n");
+    }
+    text.push_str(&usage(program, specs));
+    text.push('\n');
+    for (heading, lines) in [("Arguments", &arguments), ("Options", &options)] {
+        if lines.is_empty() {
+            continue;
+        }
+        text.push_str(&format!("\n{}:\n", heading));
+        for (label, help) in lines {
+            text.push_str(&format!("  {:width$}  {}", label, help, width = width));
+            text.push('\n');
+        }
+    }
+    text.truncate(text.trim_end().len());
+    text
+}
+
+/// Match `argv` against the specs: the value of each field, or the message
+/// and exit code to stop with
+fn parse(
+    specs: &[ArgSpec],
+    argv: &[String],
+    program: &str,
+    about: Option<&str>,
+) -> Result<IndexMap<String, Value>, (String, i64)> {
+    let error = |message: String| {
+        (
+            format!("error: {}\n\n{}", message, usage(program, specs)),
+            USAGE_ERROR,
+        )
+    };
+    let mut values: IndexMap<String, Value> = specs
+        .iter()
+        .map(|spec| (spec.field.clone(), spec.initial()))
+        .collect();
+    let mut positionals = specs
+        .iter()
+        .filter(|spec| matches!(spec.kind, Kind::Positional))
+        .peekable();
+    let rest = specs.iter().find(|spec| matches!(spec.kind, Kind::Rest));
+    let mut only_positionals = false;
+    let mut argv = argv.iter();
+    while let Some(arg) = argv.next() {
+        let is_option =
+            !only_positionals && arg.starts_with('-') && arg != "-" && arg.parse::<f64>().is_err();
+        if !is_option {
+            if let Some(spec) = positionals.next() {
+                values.insert(spec.field.clone(), Value::Text(arg.clone()));
+            } else if let Some(Value::List(items)) =
+                rest.and_then(|spec| values.get_mut(&spec.field))
+            {
+                items.push(Value::Text(arg.clone()));
+            } else {
+                return Err(error(format!("unexpected argument `{}`", arg)));
+            }
+            continue;
+        }
+        if arg == "--" {
+            only_positionals = true;
+            continue;
+        }
+        if arg == "--help" || arg == "-h" {
+            return Err((help(program, about, specs), 0));
+        }
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let Some(spec) = specs
+            .iter()
+            .find(|spec| matches!(spec.kind, Kind::Flag | Kind::Option(_)) && spec.long() == name)
+        else {
+            return Err(error(format!("unknown option `{}`", name)));
+        };
+        let value = match (&spec.kind, inline) {
+            (Kind::Option(default), inline) => {
+                let Some(text) = inline.or_else(|| argv.next().cloned()) else {
+                    return Err(error(format!("`{}` needs a value", name)));
+                };
+                convert(&text, default).ok_or_else(|| {
+                    error(format!(
+                        "invalid value `{}` for `{}`: expected {}",
+                        text,
+                        name,
+                        placeholder(default)
+                    ))
+                })?
+            }
+            (_, Some(_)) => return Err(error(format!("`{}` takes no value", name))),
+            (_, None) => Value::Bool(true),
+        };
+        values.insert(spec.field.clone(), value);
+    }
+    if let Some(missing) = positionals.peek() {
+        return Err(error(format!("missing argument {}", missing.label())));
+    }
+    Ok(values)
+}
+
+impl Interpreter {
+    /// Call an `Args` method: the spec constructors, and `parse`, which reads
+    /// the arguments through the Env capability
+    pub(super) fn call_args_method(
+        &mut self,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match (method, args.as_slice()) {
+            ("flag" | "positional" | "rest", [Value::Text(help)]) => {
+                Ok(self.arg_spec(method, help, None))
+            }
+            (
+                "option",
+                [default @ (Value::Int(_) | Value::Float(_) | Value::Text(_) | Value::Bool(_)), Value::Text(help)],
+            ) => Ok(self.arg_spec(method, help, Some(default.clone()))),
+            ("option", [default, Value::Text(_)]) => Err(RuntimeError::type_mismatch(
+                "Int, Float, Text, or Bool",
+                &format_value(default),
+            )),
+            ("parse", [spec]) | ("parse", [spec, Value::Text(_)]) => {
+                let specs = arg_specs(spec)?;
+                let about = match args.get(1) {
+                    Some(Value::Text(about)) => Some(about.as_str()),
+                    _ => None,
+                };
+                let argv: Vec<String> = match self.call_effect("Env", "args", Vec::new())? {
+                    Value::List(items) => items
+                        .into_iter()
+                        .map(|item| match item {
+                            Value::Text(arg) => arg,
+                            other => format_value(&other),
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                let program = self.program_name.as_deref().unwrap_or("program");
+                Ok(match parse(&specs, &argv, program, about) {
+                    Ok(values) => Value::Ok(Box::new(Value::Record(values))),
+                    Err((message, code)) => Value::Err(Box::new(Value::Record(IndexMap::from([
+                        ("message".to_string(), Value::Text(message)),
+                        ("code".to_string(), Value::Int(code)),
+                    ])))),
+                })
+            }
+            ("flag" | "positional" | "rest" | "parse", _) => {
+                Err(RuntimeError::type_mismatch("Text", "other"))
+            }
+            ("option", _) => Err(RuntimeError::arity_mismatch(2, args.len())),
+            _ => Err(RuntimeError::unknown_method("Args", method)),
+        }
+    }
+
+    /// A spec value, numbered so that `parse` sees positionals in the order
+    /// they were written
+    fn arg_spec(&mut self, kind: &str, help: &str, default: Option<Value>) -> Value {
+        self.next_arg_spec += 1;
+        let mut fields = IndexMap::from([
+            ("kind".to_string(), Value::Text(kind.to_string())),
+            ("help".to_string(), Value::Text(help.to_string())),
+            ("order".to_string(), Value::Int(self.next_arg_spec)),
+        ]);
+        if let Some(default) = default {
+            fields.insert("default".to_string(), default);
+        }
+        Value::Record(fields)
+    }
+}
@@ -0,0 +1,180 @@
+//! Effect audits: what a run did through its capabilities.
+//!
+//! Once [`Interpreter::start_audit`] is called, the effect dispatch methods
+//! record each file read or written, each host contacted over Net, and each
+//! random value drawn or clock read, against the call site that caused it.
+//! Other effects are counted by [`CostReport`](super::CostReport) only.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::diagnostics::Span;
+
+use super::Interpreter;
+
+/// Effect operations recorded by call site, from [`Interpreter::audit`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EffectAudit {
+    events: BTreeMap<(Site, String, Option<String>), u64>,
+}
+
+/// File, line, and column of a call; empty when it isn't known
+type Site = (String, usize, usize);
+
+/// One operation at one call site
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct AuditEvent {
+    /// `file:line:col` of the call, or empty if it isn't known
+    pub site: String,
+    /// The effect method, as in `Fs.read`
+    pub operation: String,
+    /// The path or host it touched, if any
+    pub target: Option<String>,
+    /// How many times it happened there
+    pub count: u64,
+}
+
+impl EffectAudit {
+    fn record(&mut self, site: Site, operation: &str, target: Option<&str>) {
+        *self
+            .events
+            .entry((site, operation.to_string(), target.map(str::to_string)))
+            .or_default() += 1;
+    }
+
+    /// Add the operations of another audit, such as one per test
+    pub fn merge(&mut self, other: &EffectAudit) {
+        for (key, count) in &other.events {
+            *self.events.entry(key.clone()).or_default() += count;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Every operation, ordered by call site
+    pub fn events(&self) -> Vec<AuditEvent> {
+        self.events
+            .iter()
+            .map(|((site, operation, target), count)| AuditEvent {
+                site: match site {
+                    (file, _, _) if file.is_empty() => String::new(),
+                    (file, line, col) => format!("{}:{}:{}", file, line, col),
+                },
+                operation: operation.clone(),
+                target: target.clone(),
+                count: *count,
+            })
+            .collect()
+    }
+
+    /// Paths read with `Fs.read`, `Fs.read_gzip`, or `Crypto.key_from_file`
+    pub fn files_read(&self) -> BTreeSet<&str> {
+        self.targets(&["Fs.read", "Fs.read_gzip", "Crypto.key_from_file"])
+    }
+
+    /// Paths written with `Fs.write` or `Fs.write_gzip`
+    pub fn files_written(&self) -> BTreeSet<&str> {
+        self.targets(&["Fs.write", "Fs.write_gzip"])
+    }
+
+    /// Hosts contacted over Net
+    pub fn hosts(&self) -> BTreeSet<&str> {
+        self.events
+            .keys()
+            .filter(|(_, operation, _)| operation.starts_with("Net."))
+            .filter_map(|(_, _, target)| target.as_deref())
+            .collect()
+    }
+
+    /// Random values drawn
+    pub fn random_values(&self) -> u64 {
+        self.count(|operation| operation.starts_with("Rand."))
+    }
+
+    /// Reads of the clock; `Clock.sleep` doesn't read it
+    pub fn clock_reads(&self) -> u64 {
+        self.count(|operation| operation.starts_with("Clock.") && operation != "Clock.sleep")
+    }
+
+    fn targets(&self, operations: &[&str]) -> BTreeSet<&str> {
+        self.events
+            .keys()
+            .filter(|(_, operation, _)| operations.contains(&operation.as_str()))
+            .filter_map(|(_, _, target)| target.as_deref())
+            .collect()
+    }
+
+    fn count(&self, matches: impl Fn(&str) -> bool) -> u64 {
+        self.events
+            .iter()
+            .filter(|((_, operation, _), _)| matches(operation))
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+impl serde::Serialize for EffectAudit {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(serde::Serialize)]
+        struct Report<'a> {
+            files_read: BTreeSet<&'a str>,
+            files_written: BTreeSet<&'a str>,
+            hosts: BTreeSet<&'a str>,
+            random_values: u64,
+            clock_reads: u64,
+            events: Vec<AuditEvent>,
+        }
+        Report {
+            files_read: self.files_read(),
+            files_written: self.files_written(),
+            hosts: self.hosts(),
+            random_values: self.random_values(),
+            clock_reads: self.clock_reads(),
+            events: self.events(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// The host of a URL: what follows the scheme, up to the path
+pub(super) fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host)
+}
+
+impl Interpreter {
+    /// Record the effect operations of the rest of the run, for [`Self::audit`]
+    pub fn start_audit(&mut self) {
+        self.audit = Some(EffectAudit::default());
+    }
+
+    /// The operations recorded since [`Self::start_audit`], if auditing
+    pub fn audit(&self) -> Option<&EffectAudit> {
+        self.audit.as_ref()
+    }
+
+    /// Attribute the effect operations that follow to the call at `span`
+    pub(super) fn at_call_site(&mut self, span: &Span) {
+        if self.audit.is_some() {
+            self.audit_site = Some(span.clone());
+        }
+    }
+
+    /// Record a call to an effect method at the current call site, if auditing
+    pub(super) fn audit_effect(&mut self, effect: &str, method: &str, target: Option<&str>) {
+        if let Some(audit) = &mut self.audit {
+            let site = self.audit_site.as_ref().map_or_else(Site::default, |span| {
+                (
+                    span.file.display().to_string(),
+                    span.start_line,
+                    span.start_col,
+                )
+            });
+            audit.record(site, &format!("{}.{}", effect, method), target);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Shared function bodies and pooled evaluation temporaries
+//!
+//! Function values used to own a deep copy of their body, so every lookup of
+//! a function name and every closure capture cloned a slice of the AST. The
+//! interpreter now builds each body once, caches it in a [`NodeCache`] keyed
+//! by the defining node, and hands out shared handles. The parser's AST is
+//! not arena-allocated; it is built with `Box` and `Vec` as before.
+//! Short-lived vectors such as call arguments come from a [`Scratch`] pool
+//! and are reused between calls.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::diagnostics::Span;
+use crate::parser::ast::NodeId;
+
+/// Most entries a [`NodeCache`] holds before it starts over, so that
+/// long-lived sessions creating fresh lambdas don't grow it without bound
+const CACHE_ENTRIES: usize = 4096;
+
+/// A cache of values built from AST nodes, one shared allocation per
+/// defining node
+#[derive(Debug)]
+pub struct NodeCache<T> {
+    nodes: HashMap<NodeId, (Span, Rc<T>)>,
+}
+
+impl<T> NodeCache<T> {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self {
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// The value built for the node `id` at `span`, building it with `make`
+    /// the first time. Node IDs can repeat in cloned or deserialized ASTs,
+    /// so an entry for the same ID at another span is rebuilt.
+    pub fn alloc(&mut self, id: NodeId, span: &Span, make: impl FnOnce() -> T) -> Rc<T> {
+        if let Some((cached_span, value)) = self.nodes.get(&id) {
+            if cached_span == span {
+                return value.clone();
+            }
+        }
+        if self.nodes.len() >= CACHE_ENTRIES {
+            self.nodes.clear();
+        }
+        let value = Rc::new(make());
+        self.nodes.insert(id, (span.clone(), value.clone()));
+        value
+    }
+
+    /// Drop every entry. Values already handed out stay valid.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+}
+
+impl<T> Default for NodeCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A pool of empty vectors whose buffers are reused for temporaries
+#[derive(Debug)]
+pub struct Scratch<T> {
+    free: Vec<Vec<T>>,
+}
+
+/// Most buffers a [`Scratch`] pool keeps; deeper nesting allocates afresh
+const SCRATCH_BUFFERS: usize = 64;
+
+impl<T> Scratch<T> {
+    /// Create an empty pool
+    pub fn new() -> Self {
+        Self { free: Vec::new() }
+    }
+
+    /// An empty vector, reusing a returned buffer when one is available
+    pub fn take(&mut self) -> Vec<T> {
+        self.free.pop().unwrap_or_default()
+    }
+
+    /// Return a vector's buffer to the pool
+    pub fn give(&mut self, mut buffer: Vec<T>) {
+        if self.free.len() < SCRATCH_BUFFERS && buffer.capacity() > 0 {
+            buffer.clear();
+            self.free.push(buffer);
+        }
+    }
+}
+
+impl<T> Default for Scratch<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,69 @@
+//! The host's capabilities as narrowed by a [`CapabilityValue`]
+
+use super::value::CapabilityValue;
+use super::FsCapability;
+
+/// The host's filesystem as seen through a [`CapabilityValue`]
+pub(in crate::interpreter) struct AttenuatedFs<'a> {
+    pub host: &'a dyn FsCapability,
+    pub grant: &'a CapabilityValue,
+}
+
+impl AttenuatedFs<'_> {
+    fn check_read(&self, path: &str) -> Result<(), String> {
+        if self.grant.allows(self.host, path) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Failed to read '{}': outside the directories this capability allows",
+                path
+            ))
+        }
+    }
+
+    fn check_write(&self, path: &str) -> Result<(), String> {
+        if self.grant.read_only {
+            Err(format!(
+                "Failed to write '{}': the capability is read-only",
+                path
+            ))
+        } else if !self.grant.allows(self.host, path) {
+            Err(format!(
+                "Failed to write '{}': outside the directories this capability allows",
+                path
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl FsCapability for AttenuatedFs<'_> {
+    fn read(&self, path: &str) -> Result<String, String> {
+        self.check_read(path)?;
+        self.host.read(path)
+    }
+
+    fn write(&self, path: &str, content: &str) -> Result<(), String> {
+        self.check_write(path)?;
+        self.host.write(path, content)
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.grant.allows(self.host, path) && self.host.exists(path)
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.check_read(path)?;
+        self.host.read_bytes(path)
+    }
+
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<(), String> {
+        self.check_write(path)?;
+        self.host.write_bytes(path, content)
+    }
+}
+
+#[cfg(test)]
+#[path = "attenuation_tests.rs"]
+mod tests;
@@ -0,0 +1,53 @@
+use super::*;
+use crate::interpreter::MemoryFs;
+
+#[test]
+fn test_read_only_grant_refuses_writes() {
+    let host = MemoryFs::new([("/a.txt".to_string(), "x".to_string())].into());
+    let grant = CapabilityValue {
+        read_only: true,
+        ..CapabilityValue::host("Fs")
+    };
+    let fs = AttenuatedFs {
+        host: &host,
+        grant: &grant,
+    };
+    assert_eq!(fs.read("/a.txt").unwrap(), "x");
+    assert_eq!(
+        fs.write("/a.txt", "y").unwrap_err(),
+        "Failed to write '/a.txt': the capability is read-only"
+    );
+    assert!(fs.write_bytes("/b.bin", &[1]).is_err());
+    assert_eq!(host.files()["/a.txt"], "x");
+}
+
+#[test]
+fn test_scoped_grant_hides_files_outside_its_roots() {
+    let host = MemoryFs::new(
+        [
+            ("/data/a.txt".to_string(), "in".to_string()),
+            ("/etc/x".to_string(), "out".to_string()),
+        ]
+        .into(),
+    );
+    let grant = CapabilityValue {
+        roots: vec!["/data".to_string()],
+        ..CapabilityValue::host("Fs")
+    };
+    let fs = AttenuatedFs {
+        host: &host,
+        grant: &grant,
+    };
+    assert!(fs.exists("/data/a.txt"));
+    assert!(!fs.exists("/etc/x"));
+    assert_eq!(
+        fs.read("/etc/x").unwrap_err(),
+        "Failed to read '/etc/x': outside the directories this capability allows"
+    );
+    fs.write("/data/b.txt", "new").unwrap();
+    assert!(fs.write("/data/../etc/y", "new").is_err());
+    assert_eq!(
+        host.files().keys().collect::<Vec<_>>(),
+        vec!["/data/a.txt", "/data/b.txt", "/etc/x"]
+    );
+}
@@ -0,0 +1,160 @@
+//! Console capability for tests, with scripted input and expected output
+
+use super::ConsoleCapability;
+use crate::interpreter::style::{parse_styles, StyledText};
+
+/// One step of a console script, written `expect_output(text)` or `send(text)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleStep {
+    /// The program prints `text` before its next read
+    ExpectOutput(String),
+    /// The program's next `read_line` returns `text`
+    Send(String),
+}
+
+/// Console I/O in the order it happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleEvent {
+    Output(String),
+    Input(String),
+}
+
+/// Mock console capability for testing. Output is recorded without its
+/// escape codes, and the styled runs in it separately; a scripted console
+/// also answers reads from its script, checking along the way that the
+/// program printed what the script expects.
+#[derive(Default)]
+pub struct MockConsole {
+    events: std::cell::RefCell<Vec<ConsoleEvent>>,
+    styles: std::cell::RefCell<Vec<StyledText>>,
+    /// Whether to act as a terminal, so that `style` adds escape codes
+    tty: bool,
+    /// The script's remaining steps, if it has one
+    script: Option<std::cell::RefCell<std::collections::VecDeque<ConsoleStep>>>,
+    /// Output after the last text an `expect_output` step matched
+    unmatched: std::cell::RefCell<String>,
+    failure: std::cell::RefCell<Option<String>>,
+}
+
+impl MockConsole {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A console that plays `steps` against the program
+    pub fn scripted(steps: Vec<ConsoleStep>) -> Self {
+        Self {
+            script: Some(std::cell::RefCell::new(steps.into())),
+            ..Self::default()
+        }
+    }
+
+    /// A console that acts as a terminal
+    pub fn tty() -> Self {
+        Self {
+            tty: true,
+            ..Self::default()
+        }
+    }
+
+    /// Everything printed, a chunk per `print` or `println`
+    pub fn output(&self) -> Vec<String> {
+        self.events
+            .borrow()
+            .iter()
+            .filter_map(|event| match event {
+                ConsoleEvent::Output(text) => Some(text.clone()),
+                ConsoleEvent::Input(_) => None,
+            })
+            .collect()
+    }
+
+    /// Output and input, interleaved as they happened
+    pub fn events(&self) -> Vec<ConsoleEvent> {
+        self.events.borrow().clone()
+    }
+
+    /// The colored or bold runs of text printed, in order
+    pub fn styles(&self) -> Vec<StyledText> {
+        self.styles.borrow().clone()
+    }
+
+    fn write(&self, printed: String) {
+        let (text, styles) = parse_styles(&printed);
+        self.styles.borrow_mut().extend(styles);
+        self.unmatched.borrow_mut().push_str(&text);
+        self.events.borrow_mut().push(ConsoleEvent::Output(text));
+    }
+
+    /// Match the `expect_output` steps at the front of the script against the
+    /// output so far, stopping at the first failure
+    fn expect_output(&self, script: &mut std::collections::VecDeque<ConsoleStep>) -> bool {
+        while let Some(ConsoleStep::ExpectOutput(expected)) = script.front() {
+            let mut unmatched = self.unmatched.borrow_mut();
+            let Some(at) = unmatched.find(expected.as_str()) else {
+                self.fail(format!(
+                    "expected output {:?}, but the program printed {:?}",
+                    expected, *unmatched
+                ));
+                return false;
+            };
+            unmatched.drain(..at + expected.len());
+            script.pop_front();
+        }
+        true
+    }
+
+    fn fail(&self, message: String) {
+        self.failure.borrow_mut().get_or_insert(message);
+    }
+}
+
+impl ConsoleCapability for MockConsole {
+    fn print(&self, text: &str) {
+        self.write(text.to_string());
+    }
+
+    fn println(&self, text: &str) {
+        self.write(format!("{}\n", text));
+    }
+
+    fn read_line(&self) -> Option<String> {
+        let mut script = self.script.as_ref()?.borrow_mut();
+        if self.failure.borrow().is_some() || !self.expect_output(&mut script) {
+            return None;
+        }
+        match script.pop_front() {
+            Some(ConsoleStep::Send(input)) => {
+                self.unmatched.borrow_mut().clear();
+                self.events
+                    .borrow_mut()
+                    .push(ConsoleEvent::Input(input.clone()));
+                Some(input)
+            }
+            _ => {
+                self.fail("the program read input after the script ended".to_string());
+                None
+            }
+        }
+    }
+
+    fn is_tty(&self) -> bool {
+        self.tty
+    }
+
+    fn script_failure(&self) -> Option<String> {
+        self.failure.borrow().clone()
+    }
+
+    fn finish_script(&self) -> Option<String> {
+        if let Some(script) = &self.script {
+            let mut script = script.borrow_mut();
+            if self.expect_output(&mut script) {
+                if let Some(ConsoleStep::Send(input)) = script.front() {
+                    self.fail(format!("the program never read the input {:?}", input));
+                }
+            }
+        }
+        self.script_failure()
+    }
+}
@@ -0,0 +1,96 @@
+//! Crypto capabilities: real HMAC and ed25519, and a keyless one for tests
+
+use super::CryptoCapability;
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    use hmac::{Hmac, Mac};
+
+    // HMAC accepts keys of any length, so this can't fail
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(key).expect("HMAC takes any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn signing_key(private_key: &[u8]) -> Result<ed25519_dalek::SigningKey, String> {
+    let seed: [u8; 32] = private_key.try_into().map_err(|_| {
+        format!(
+            "ed25519 private keys are 32 bytes, got {}",
+            private_key.len()
+        )
+    })?;
+    Ok(ed25519_dalek::SigningKey::from_bytes(&seed))
+}
+
+/// HMAC-SHA256 and ed25519, as provided by `astra run`. Both are
+/// deterministic, so this is also what sandboxed runs get.
+#[derive(Default)]
+pub struct StandardCrypto;
+
+impl CryptoCapability for StandardCrypto {
+    fn hmac_sha256(&self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        hmac_sha256(key, data)
+    }
+
+    fn public_key(&self, private_key: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(signing_key(private_key)?
+            .verifying_key()
+            .to_bytes()
+            .to_vec())
+    }
+
+    fn sign(&self, private_key: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+        use ed25519_dalek::Signer;
+
+        Ok(signing_key(private_key)?.sign(data).to_bytes().to_vec())
+    }
+
+    fn verify(&self, public_key: &[u8], data: &[u8], signature: &[u8]) -> bool {
+        let (Ok(public_key), Ok(signature)) = (
+            <[u8; 32]>::try_from(public_key),
+            ed25519_dalek::Signature::from_slice(signature),
+        ) else {
+            return false;
+        };
+        ed25519_dalek::VerifyingKey::from_bytes(&public_key)
+            .is_ok_and(|key| key.verify_strict(data, &signature).is_ok())
+    }
+}
+
+/// Crypto capability for tests: keys of any length are accepted and
+/// signatures are SHA-256 digests, so tests don't need real key material.
+/// `verify` accepts exactly the signatures `sign` makes for the matching
+/// public key. HMAC is computed for real.
+#[derive(Default)]
+pub struct MockCrypto;
+
+impl CryptoCapability for MockCrypto {
+    fn hmac_sha256(&self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        hmac_sha256(key, data)
+    }
+
+    fn public_key(&self, private_key: &[u8]) -> Result<Vec<u8>, String> {
+        use sha2::Digest;
+
+        Ok(sha2::Sha256::digest(private_key).to_vec())
+    }
+
+    fn sign(&self, private_key: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+        let public_key = self.public_key(private_key)?;
+        Ok(mock_signature(&public_key, data))
+    }
+
+    fn verify(&self, public_key: &[u8], data: &[u8], signature: &[u8]) -> bool {
+        mock_signature(public_key, data) == signature
+    }
+}
+
+/// A 64-byte stand-in for a signature, the size of an ed25519 one
+fn mock_signature(public_key: &[u8], data: &[u8]) -> Vec<u8> {
+    use sha2::Digest;
+
+    let digest = sha2::Sha256::new()
+        .chain_update(public_key)
+        .chain_update(data)
+        .finalize();
+    [digest.as_slice(), digest.as_slice()].concat()
+}
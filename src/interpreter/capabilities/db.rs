@@ -0,0 +1,92 @@
+//! Db capability backed by SQLite
+
+use super::{DbCapability, DbRow};
+use crate::interpreter::value::Value;
+
+/// Db capability backed by SQLite, either a file or a private in-memory
+/// database. An in-memory database starts empty every time, so tests that
+/// create their own tables are deterministic.
+pub struct SqliteDb {
+    connection: rusqlite::Connection,
+}
+
+impl SqliteDb {
+    /// Open a database file, creating it if it doesn't exist
+    pub fn open(path: &std::path::Path) -> Result<Self, String> {
+        rusqlite::Connection::open(path)
+            .map(|connection| Self { connection })
+            .map_err(|e| format!("Failed to open database '{}': {}", path.display(), e))
+    }
+
+    /// A fresh, empty in-memory database
+    pub fn in_memory() -> Self {
+        Self {
+            connection: rusqlite::Connection::open_in_memory()
+                .expect("SQLite can always open an in-memory database"),
+        }
+    }
+
+    fn bind(params: &[Value]) -> Result<Vec<rusqlite::types::Value>, String> {
+        use rusqlite::types::Value as Sql;
+
+        params
+            .iter()
+            .map(|param| match param {
+                Value::Int(n) => Ok(Sql::Integer(*n)),
+                Value::Float(f) => Ok(Sql::Real(*f)),
+                Value::Bool(b) => Ok(Sql::Integer(*b as i64)),
+                Value::Text(s) => Ok(Sql::Text(s.clone())),
+                Value::Bytes(bytes) => Ok(Sql::Blob(bytes.clone())),
+                Value::None | Value::Unit => Ok(Sql::Null),
+                Value::Some(inner) => {
+                    Self::bind(std::slice::from_ref(inner)).map(|mut bound| bound.remove(0))
+                }
+                other => Err(format!(
+                    "cannot bind {} as a query parameter",
+                    crate::interpreter::value::format_value(other)
+                )),
+            })
+            .collect()
+    }
+}
+
+impl DbCapability for SqliteDb {
+    fn query(&self, sql: &str, params: &[Value]) -> Result<Vec<DbRow>, String> {
+        use rusqlite::types::ValueRef;
+
+        let mut statement = self.connection.prepare(sql).map_err(|e| e.to_string())?;
+        let columns: Vec<String> = statement
+            .column_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let bound = Self::bind(params)?;
+        let mut rows = statement
+            .query(rusqlite::params_from_iter(bound))
+            .map_err(|e| e.to_string())?;
+        let mut result = Vec::new();
+        while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            let mut values = Vec::with_capacity(columns.len());
+            for (index, column) in columns.iter().enumerate() {
+                let value = match row.get_ref(index).map_err(|e| e.to_string())? {
+                    ValueRef::Null => Value::None,
+                    ValueRef::Integer(n) => Value::Int(n),
+                    ValueRef::Real(f) => Value::Float(f),
+                    ValueRef::Text(text) => Value::Text(String::from_utf8_lossy(text).into_owned()),
+                    ValueRef::Blob(bytes) => Value::Bytes(bytes.to_vec()),
+                };
+                values.push((column.clone(), value));
+            }
+            result.push(values);
+        }
+        Ok(result)
+    }
+
+    fn execute(&self, sql: &str, params: &[Value]) -> Result<i64, String> {
+        let bound = Self::bind(params)?;
+        self.connection
+            .execute(sql, rusqlite::params_from_iter(bound))
+            .map(|changed| changed as i64)
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,251 @@
+//! Filesystem capabilities: the project-rooted, in-memory, and dry-run
+//! filesystems, and symlink resolution for the real one
+
+use super::FsCapability;
+
+/// Resolve the symlinks, `.` and `..` in the longest prefix of `path` that
+/// exists on the host filesystem, keeping the rest as written. A path with no
+/// existing prefix is returned unchanged.
+pub fn resolve_existing_prefix(path: &str) -> String {
+    let path = std::path::Path::new(path);
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        let prefix = if existing.as_os_str().is_empty() {
+            std::path::Path::new(".")
+        } else {
+            existing
+        };
+        if let Ok(mut resolved) = prefix.canonicalize() {
+            resolved.extend(rest.iter().rev());
+            return resolved.to_string_lossy().into_owned();
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name);
+                existing = parent;
+            }
+            _ => return path.to_string_lossy().into_owned(),
+        }
+    }
+}
+
+/// Filesystem capability confined to a root directory. Paths are relative to
+/// the root; absolute paths, `..` components, and symlinks that lead out of
+/// the root are refused.
+pub struct RootedFs {
+    root: std::path::PathBuf,
+}
+
+impl RootedFs {
+    pub fn new(root: impl Into<std::path::PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn resolve(&self, path: &str) -> Result<std::path::PathBuf, String> {
+        let relative = std::path::Path::new(path);
+        let escapes = relative.components().any(|component| {
+            !matches!(
+                component,
+                std::path::Component::Normal(_) | std::path::Component::CurDir
+            )
+        });
+        if escapes {
+            return Err(format!("'{}' is outside the project root", path));
+        }
+        // A symlink inside the root can still point out of it
+        let full_path = resolve_existing_prefix(&self.root.join(relative).to_string_lossy());
+        let root = resolve_existing_prefix(&self.root.to_string_lossy());
+        if !std::path::Path::new(&full_path).starts_with(&root) {
+            return Err(format!("'{}' is outside the project root", path));
+        }
+        Ok(full_path.into())
+    }
+}
+
+impl FsCapability for RootedFs {
+    fn read(&self, path: &str) -> Result<String, String> {
+        std::fs::read_to_string(self.resolve(path)?)
+            .map_err(|e| format!("Failed to read '{}': {}", path, e))
+    }
+
+    fn write(&self, path: &str, content: &str) -> Result<(), String> {
+        let full_path = self.resolve(path)?;
+        if let Some(parent) = full_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to write '{}': {}", path, e))?;
+        }
+        std::fs::write(full_path, content).map_err(|e| format!("Failed to write '{}': {}", path, e))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.resolve(path).is_ok_and(|full_path| full_path.exists())
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>, String> {
+        std::fs::read(self.resolve(path)?).map_err(|e| format!("Failed to read '{}': {}", path, e))
+    }
+
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<(), String> {
+        let full_path = self.resolve(path)?;
+        if let Some(parent) = full_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to write '{}': {}", path, e))?;
+        }
+        std::fs::write(full_path, content).map_err(|e| format!("Failed to write '{}': {}", path, e))
+    }
+}
+
+/// Filesystem capability held in memory, for sandboxed runs and tests.
+/// Files hold bytes, so binary files work too. Clones share the same files.
+#[derive(Clone, Default)]
+pub struct MemoryFs {
+    files: std::rc::Rc<std::cell::RefCell<std::collections::BTreeMap<String, Vec<u8>>>>,
+}
+
+impl MemoryFs {
+    pub fn new(files: std::collections::BTreeMap<String, String>) -> Self {
+        let files = files
+            .into_iter()
+            .map(|(path, content)| (path, content.into_bytes()))
+            .collect();
+        Self {
+            files: std::rc::Rc::new(std::cell::RefCell::new(files)),
+        }
+    }
+
+    /// The files as they are now, including writes. Bytes that aren't UTF-8
+    /// are replaced with U+FFFD.
+    pub fn files(&self) -> std::collections::BTreeMap<String, String> {
+        self.files
+            .borrow()
+            .iter()
+            .map(|(path, content)| (path.clone(), String::from_utf8_lossy(content).into_owned()))
+            .collect()
+    }
+}
+
+impl FsCapability for MemoryFs {
+    fn read(&self, path: &str) -> Result<String, String> {
+        String::from_utf8(self.read_bytes(path)?)
+            .map_err(|_| format!("Failed to read '{}': not valid UTF-8", path))
+    }
+
+    fn write(&self, path: &str, content: &str) -> Result<(), String> {
+        self.write_bytes(path, content.as_bytes())
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.files.borrow().contains_key(path)
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.files
+            .borrow()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| format!("Failed to read '{}': no such file", path))
+    }
+
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<(), String> {
+        self.files
+            .borrow_mut()
+            .insert(path.to_string(), content.to_vec());
+        Ok(())
+    }
+}
+
+/// A file write a [`TransactionalFs`] is holding back
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingWrite {
+    pub path: String,
+    /// The file's contents on the host, or `None` if it would be created
+    pub before: Option<Vec<u8>>,
+    pub after: Vec<u8>,
+}
+
+/// Filesystem capability that buffers writes instead of making them, for
+/// dry runs. Reads see the buffered writes; the host is only read until
+/// [`TransactionalFs::commit`]. Clones share the same buffer.
+#[derive(Clone)]
+pub struct TransactionalFs {
+    host: std::rc::Rc<dyn FsCapability>,
+    writes: std::rc::Rc<std::cell::RefCell<std::collections::BTreeMap<String, Vec<u8>>>>,
+}
+
+impl TransactionalFs {
+    pub fn new(host: impl FsCapability + 'static) -> Self {
+        Self {
+            host: std::rc::Rc::new(host),
+            writes: Default::default(),
+        }
+    }
+
+    /// The buffered writes that would change the host, by path
+    pub fn pending(&self) -> Vec<PendingWrite> {
+        self.writes
+            .borrow()
+            .iter()
+            .map(|(path, after)| PendingWrite {
+                path: path.clone(),
+                before: self
+                    .host
+                    .exists(path)
+                    .then(|| self.host.read_bytes(path).ok())
+                    .flatten(),
+                after: after.clone(),
+            })
+            .filter(|write| write.before.as_ref() != Some(&write.after))
+            .collect()
+    }
+
+    /// Make the buffered writes on the host, stopping at the first failure.
+    /// Writes that were made leave the buffer.
+    pub fn commit(&self) -> Result<(), String> {
+        let mut writes = self.writes.borrow_mut();
+        while let Some((path, content)) = writes.pop_first() {
+            if let Err(e) = self.host.write_bytes(&path, &content) {
+                writes.insert(path, content);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FsCapability for TransactionalFs {
+    fn read(&self, path: &str) -> Result<String, String> {
+        String::from_utf8(self.read_bytes(path)?)
+            .map_err(|_| format!("Failed to read '{}': not valid UTF-8", path))
+    }
+
+    fn write(&self, path: &str, content: &str) -> Result<(), String> {
+        self.write_bytes(path, content.as_bytes())
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.writes.borrow().contains_key(path) || self.host.exists(path)
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>, String> {
+        match self.writes.borrow().get(path) {
+            Some(content) => Ok(content.clone()),
+            None => self.host.read_bytes(path),
+        }
+    }
+
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<(), String> {
+        self.writes
+            .borrow_mut()
+            .insert(path.to_string(), content.to_vec());
+        Ok(())
+    }
+
+    fn resolve(&self, path: &str) -> String {
+        self.host.resolve(path)
+    }
+}
+
+#[cfg(test)]
+#[path = "fs_tests.rs"]
+mod tests;
@@ -0,0 +1,79 @@
+use super::*;
+
+#[test]
+fn test_rooted_fs_stays_in_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let fs = RootedFs::new(dir.path());
+    assert!(fs.write("sub/file.txt", "x").is_ok());
+    assert_eq!(fs.read("sub/file.txt").unwrap(), "x");
+    assert!(fs.exists("./sub/file.txt"));
+    assert!(fs.write("../escape.txt", "x").is_err());
+    assert!(fs.read("/etc/hostname").is_err());
+    assert!(!fs.exists("sub/../../escape.txt"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_rooted_fs_refuses_symlinks_out_of_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    std::fs::create_dir_all(root.join("inner")).unwrap();
+    std::fs::create_dir(dir.path().join("secret")).unwrap();
+    std::fs::write(dir.path().join("secret/key.txt"), "hidden").unwrap();
+    std::os::unix::fs::symlink(dir.path().join("secret"), root.join("link")).unwrap();
+    std::os::unix::fs::symlink(root.join("inner"), root.join("alias")).unwrap();
+    let fs = RootedFs::new(&root);
+    assert!(fs.read("link/key.txt").is_err());
+    assert!(fs.write("link/new/file.txt", "x").is_err());
+    assert!(!dir.path().join("secret/new").exists());
+    assert!(!fs.exists("link/key.txt"));
+    // Links that stay inside the root still work
+    assert!(fs.write("alias/file.txt", "x").is_ok());
+    assert_eq!(fs.read("inner/file.txt").unwrap(), "x");
+}
+
+#[test]
+fn test_memory_fs_clones_share_files() {
+    let fs = MemoryFs::default();
+    let clone = fs.clone();
+    clone.write_bytes("data.bin", &[0xff, 0x00]).unwrap();
+    assert!(fs.exists("data.bin"));
+    assert_eq!(fs.read_bytes("data.bin").unwrap(), vec![0xff, 0x00]);
+    assert!(fs.read("missing.txt").is_err());
+}
+
+#[test]
+fn test_transactional_fs_commit() {
+    let host = MemoryFs::new([("a.txt".to_string(), "old".to_string())].into());
+    let fs = TransactionalFs::new(host.clone());
+    fs.write("a.txt", "new").unwrap();
+    fs.write("b.txt", "created").unwrap();
+    assert_eq!(fs.read("a.txt").unwrap(), "new");
+    assert_eq!(host.files()["a.txt"], "old");
+    assert_eq!(fs.pending().len(), 2);
+
+    fs.commit().unwrap();
+    assert_eq!(host.files()["a.txt"], "new");
+    assert_eq!(host.files()["b.txt"], "created");
+    assert!(fs.pending().is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_resolve_existing_prefix_follows_symlinks() {
+    let dir = tempfile::tempdir().unwrap();
+    let real = dir.path().canonicalize().unwrap();
+    std::fs::create_dir(real.join("target")).unwrap();
+    std::os::unix::fs::symlink(real.join("target"), real.join("link")).unwrap();
+    let resolve = |path: std::path::PathBuf| resolve_existing_prefix(&path.to_string_lossy());
+
+    // The part that doesn't exist yet is kept as written
+    assert_eq!(
+        resolve(real.join("link/new/file.txt")),
+        real.join("target/new/file.txt").to_string_lossy()
+    );
+    assert_eq!(
+        resolve(real.join("target/../link")),
+        real.join("target").to_string_lossy()
+    );
+}
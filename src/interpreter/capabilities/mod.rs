@@ -0,0 +1,382 @@
+//! Capability traits and mock implementations for the Astra effect system.
+
+mod attenuation;
+mod console;
+mod crypto;
+#[cfg(feature = "sqlite")]
+mod db;
+mod fs;
+mod net;
+mod rand;
+mod value;
+
+pub(super) use attenuation::AttenuatedFs;
+pub use console::{ConsoleEvent, ConsoleStep, MockConsole};
+pub use crypto::{MockCrypto, StandardCrypto};
+#[cfg(feature = "sqlite")]
+pub use db::SqliteDb;
+pub use fs::{resolve_existing_prefix, MemoryFs, PendingWrite, RootedFs, TransactionalFs};
+pub use net::{MockNet, ScriptedWebSocket};
+pub use rand::{RandDraw, ReplayRand, SeededRand};
+pub use value::CapabilityValue;
+
+use super::metrics::{MetricEvent, MetricsReport};
+use super::value::Value;
+
+/// Capability interface for Net effect
+pub trait NetCapability {
+    fn get(&self, url: &str) -> Result<Value, String>;
+    fn post(&self, url: &str, body: &str) -> Result<Value, String>;
+
+    /// Open a WebSocket connection. Capabilities without WebSocket support
+    /// can rely on the default, which refuses.
+    fn ws_connect(&self, url: &str) -> Result<Box<dyn WebSocketConnection>, String> {
+        Err(format!(
+            "WebSocket connections are not available for '{}'",
+            url
+        ))
+    }
+}
+
+/// An open WebSocket connection from `Net.ws_connect`
+pub trait WebSocketConnection {
+    fn send(&mut self, message: &str) -> Result<(), String>;
+    /// The next message, waiting at most `timeout_ms` if given, or `None`
+    /// if none arrived in time. Fails once the connection is closed.
+    fn recv(&mut self, timeout_ms: Option<u64>) -> Result<Option<String>, String>;
+    fn close(&mut self) -> Result<(), String>;
+}
+
+/// Capability interface for Fs effect
+pub trait FsCapability {
+    fn read(&self, path: &str) -> Result<String, String>;
+    fn write(&self, path: &str, content: &str) -> Result<(), String>;
+    fn exists(&self, path: &str) -> bool;
+
+    /// Read a file's raw bytes, for binary formats like gzip. Filesystems
+    /// that only hold text can rely on the default.
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.read(path).map(String::into_bytes)
+    }
+
+    /// Write raw bytes. The default can only write bytes that are UTF-8.
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<(), String> {
+        let text = std::str::from_utf8(content)
+            .map_err(|_| format!("Failed to write '{}': binary data is not supported", path))?;
+        self.write(path, text)
+    }
+
+    /// The path that `path` actually reaches, for checking it against the
+    /// directories a scoped capability allows. Filesystems with symlinks
+    /// resolve them here; the default is the path as given.
+    fn resolve(&self, path: &str) -> String {
+        path.to_string()
+    }
+}
+
+/// Capability interface for Clock effect
+pub trait ClockCapability {
+    fn now(&self) -> i64;
+    fn sleep(&self, millis: u64);
+    /// Return the current date as "YYYY-MM-DD" string
+    fn today(&self) -> String {
+        // Default implementation: derive from now() millis
+        let millis = self.now();
+        let secs = millis / 1000;
+        // Convert unix timestamp to date components
+        let days = secs / 86400;
+        // Civil date from days since epoch (algorithm from Howard Hinnant)
+        let z = days + 719468;
+        let era = if z >= 0 { z } else { z - 146096 } / 146097;
+        let doe = (z - era * 146097) as u64; // day of era [0, 146096]
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365; // year of era [0, 399]
+        let y = (yoe as i64) + era * 400;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // day of year [0, 365]
+        let mp = (5 * doy + 2) / 153; // month index [0, 11]
+        let d = doy - (153 * mp + 2) / 5 + 1; // day [1, 31]
+        let m = if mp < 10 { mp + 3 } else { mp - 9 }; // month [1, 12]
+        let y = if m <= 2 { y + 1 } else { y };
+        format!("{:04}-{:02}-{:02}", y, m, d)
+    }
+}
+
+/// Capability interface for Rand effect
+pub trait RandCapability {
+    fn int(&self, min: i64, max: i64) -> i64;
+    fn bool(&self) -> bool;
+    fn float(&self) -> f64;
+    /// `n` random bytes, drawn one `int(0, 255)` at a time so seeded and
+    /// replayed generators stay deterministic
+    fn bytes(&self, n: usize) -> Vec<u8> {
+        (0..n).map(|_| self.int(0, 255) as u8).collect()
+    }
+}
+
+/// Capability interface for Console effect
+pub trait ConsoleCapability {
+    fn print(&self, text: &str);
+    fn println(&self, text: &str);
+    fn read_line(&self) -> Option<String>;
+
+    /// Whether output goes to a terminal
+    fn is_tty(&self) -> bool {
+        false
+    }
+
+    /// Whether `style` should add ANSI escape codes to text
+    fn color(&self) -> bool {
+        self.is_tty()
+    }
+
+    /// For a scripted console, why the I/O so far strays from the script
+    fn script_failure(&self) -> Option<String> {
+        None
+    }
+
+    /// For a scripted console, the steps of the script the run never reached
+    fn finish_script(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Capability interface for Env effect
+pub trait EnvCapability {
+    fn get(&self, name: &str) -> Option<String>;
+    fn args(&self) -> Vec<String>;
+}
+
+/// Capability interface for Crypto effect. Keys and signatures are raw bytes;
+/// signing keys are 32-byte ed25519 seeds.
+pub trait CryptoCapability {
+    fn hmac_sha256(&self, key: &[u8], data: &[u8]) -> Vec<u8>;
+    fn public_key(&self, private_key: &[u8]) -> Result<Vec<u8>, String>;
+    fn sign(&self, private_key: &[u8], data: &[u8]) -> Result<Vec<u8>, String>;
+    fn verify(&self, public_key: &[u8], data: &[u8], signature: &[u8]) -> bool;
+}
+
+/// An HTTP request delivered by a [`ServeCapability`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpRequest {
+    /// Uppercase method, such as `GET`
+    pub method: String,
+    /// Path with the query string, if any
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// The response a program gave to an [`HttpRequest`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// Capability interface for Serve effect: where requests for the routes a
+/// program registers come from, and where its responses go
+pub trait ServeCapability {
+    /// Wait for the next request, or None when there will be no more
+    fn next_request(&self) -> Option<HttpRequest>;
+    /// Answer the request last returned by `next_request`
+    fn respond(&self, response: HttpResponse);
+}
+
+/// A row returned by a query: column names and values, in column order
+pub type DbRow = Vec<(String, Value)>;
+
+/// Capability interface for Db effect. Parameters bind to `?` placeholders in
+/// order; values are Int, Float, Text, Bytes, or None for NULL.
+pub trait DbCapability {
+    /// Rows returned by a statement
+    fn query(&self, sql: &str, params: &[Value]) -> Result<Vec<DbRow>, String>;
+    /// Run a statement, returning the number of rows it changed
+    fn execute(&self, sql: &str, params: &[Value]) -> Result<i64, String>;
+}
+
+/// A change in the progress of a task, as reported to a [`ProgressCapability`]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "event")]
+pub enum ProgressEvent {
+    /// `Progress.start(task, total)`; a total of 0 means it isn't known
+    #[serde(rename = "progress_start")]
+    Start { task: String, total: i64 },
+    /// `Progress.advance(n)` moved the task to `done`
+    #[serde(rename = "progress_advance")]
+    Advance { task: String, done: i64, total: i64 },
+    /// `Progress.finish()`, or a new `Progress.start`
+    #[serde(rename = "progress_finish")]
+    Finish { task: String, done: i64, total: i64 },
+}
+
+/// Capability interface for Progress effect: where a program's progress
+/// through its tasks is shown, such as a terminal bar or an orchestrator
+pub trait ProgressCapability {
+    fn report(&self, event: &ProgressEvent);
+}
+
+/// Progress capability for testing that records every event; clones share
+/// the record
+#[derive(Clone, Default)]
+pub struct MockProgress {
+    events: std::rc::Rc<std::cell::RefCell<Vec<ProgressEvent>>>,
+}
+
+impl MockProgress {
+    pub fn events(&self) -> Vec<ProgressEvent> {
+        self.events.borrow().clone()
+    }
+}
+
+impl ProgressCapability for MockProgress {
+    fn report(&self, event: &ProgressEvent) {
+        self.events.borrow_mut().push(event.clone());
+    }
+}
+
+/// Capability interface for Metrics effect: where counters, gauges, and
+/// timings go, and the aggregate of those recorded so far
+pub trait MetricsCapability {
+    fn record(&self, event: &MetricEvent);
+    fn report(&self) -> MetricsReport;
+}
+
+/// Runtime capabilities
+#[derive(Default)]
+pub struct Capabilities {
+    pub net: Option<Box<dyn NetCapability>>,
+    pub fs: Option<Box<dyn FsCapability>>,
+    pub clock: Option<Box<dyn ClockCapability>>,
+    pub rand: Option<Box<dyn RandCapability>>,
+    pub console: Option<Box<dyn ConsoleCapability>>,
+    pub env: Option<Box<dyn EnvCapability>>,
+    pub crypto: Option<Box<dyn CryptoCapability>>,
+    pub serve: Option<Box<dyn ServeCapability>>,
+    pub db: Option<Box<dyn DbCapability>>,
+    pub progress: Option<Box<dyn ProgressCapability>>,
+    pub metrics: Option<Box<dyn MetricsCapability>>,
+}
+
+impl Capabilities {
+    /// Drop every capability whose effect is not named in `effects`
+    pub fn restrict_to(&mut self, effects: &[String]) {
+        let granted = |effect: &str| effects.iter().any(|e| e == effect);
+        if !granted("Net") {
+            self.net = None;
+        }
+        if !granted("Fs") {
+            self.fs = None;
+        }
+        if !granted("Clock") {
+            self.clock = None;
+        }
+        if !granted("Rand") {
+            self.rand = None;
+        }
+        if !granted("Console") {
+            self.console = None;
+        }
+        if !granted("Env") {
+            self.env = None;
+        }
+        if !granted("Crypto") {
+            self.crypto = None;
+        }
+        if !granted("Serve") {
+            self.serve = None;
+        }
+        if !granted("Db") {
+            self.db = None;
+        }
+        if !granted("Progress") {
+            self.progress = None;
+        }
+        if !granted("Metrics") {
+            self.metrics = None;
+        }
+    }
+}
+
+/// Fixed clock capability for deterministic testing
+pub struct FixedClock {
+    time: i64,
+}
+
+impl FixedClock {
+    pub fn new(time: i64) -> Self {
+        Self { time }
+    }
+}
+
+impl ClockCapability for FixedClock {
+    fn now(&self) -> i64 {
+        self.time
+    }
+
+    fn sleep(&self, _millis: u64) {
+        // No-op for fixed clock
+    }
+}
+
+/// Environment capability with fixed variables and arguments
+#[derive(Default)]
+pub struct FixedEnv {
+    vars: std::collections::BTreeMap<String, String>,
+    args: Vec<String>,
+}
+
+impl FixedEnv {
+    pub fn new(vars: std::collections::BTreeMap<String, String>, args: Vec<String>) -> Self {
+        Self { vars, args }
+    }
+}
+
+impl EnvCapability for FixedEnv {
+    fn get(&self, name: &str) -> Option<String> {
+        self.vars.get(name).cloned()
+    }
+
+    fn args(&self) -> Vec<String> {
+        self.args.clone()
+    }
+}
+
+/// Serve capability without sockets, for tests: it delivers a fixed list of
+/// requests and records the responses. Clones share the same queue.
+#[derive(Clone, Default)]
+pub struct SyntheticServe {
+    requests: std::rc::Rc<std::cell::RefCell<std::collections::VecDeque<HttpRequest>>>,
+    responses: std::rc::Rc<std::cell::RefCell<Vec<HttpResponse>>>,
+}
+
+impl SyntheticServe {
+    pub fn new(requests: Vec<HttpRequest>) -> Self {
+        Self {
+            requests: std::rc::Rc::new(std::cell::RefCell::new(requests.into())),
+            ..Default::default()
+        }
+    }
+
+    /// Responses so far, in the order the requests were delivered
+    pub fn responses(&self) -> Vec<HttpResponse> {
+        self.responses.borrow().clone()
+    }
+}
+
+impl ServeCapability for SyntheticServe {
+    fn next_request(&self) -> Option<HttpRequest> {
+        self.requests.borrow_mut().pop_front()
+    }
+
+    fn respond(&self, response: HttpResponse) {
+        self.responses.borrow_mut().push(response);
+    }
+}
+
+/// Golden files for `assert_matches_file`, available while running tests
+pub struct GoldenFiles {
+    /// Where golden files are read and written, usually a [`RootedFs`] at the project root
+    pub fs: Box<dyn FsCapability>,
+    /// Overwrite golden files with the actual values instead of comparing (`--update-golden`)
+    pub update: bool,
+}
@@ -0,0 +1,105 @@
+//! Network capabilities for sandboxed runs: canned responses and scripted WebSockets
+
+use super::{NetCapability, WebSocketConnection};
+use crate::interpreter::value::Value;
+
+/// Network capability answering from canned responses by URL, for sandboxed
+/// runs; other URLs fail
+#[derive(Default)]
+pub struct MockNet {
+    responses: std::collections::BTreeMap<String, String>,
+    websocket: Option<ScriptedWebSocket>,
+}
+
+impl MockNet {
+    pub fn new(responses: std::collections::BTreeMap<String, String>) -> Self {
+        Self {
+            responses,
+            websocket: None,
+        }
+    }
+
+    /// Answer `ws_connect` to any URL with this scripted connection
+    pub fn with_websocket(mut self, websocket: ScriptedWebSocket) -> Self {
+        self.websocket = Some(websocket);
+        self
+    }
+
+    fn respond(&self, url: &str) -> Result<Value, String> {
+        self.responses
+            .get(url)
+            .map(|body| Value::Text(body.clone()))
+            .ok_or_else(|| format!("no mock response for '{}'", url))
+    }
+}
+
+impl NetCapability for MockNet {
+    fn get(&self, url: &str) -> Result<Value, String> {
+        self.respond(url)
+    }
+
+    fn post(&self, url: &str, _body: &str) -> Result<Value, String> {
+        self.respond(url)
+    }
+
+    fn ws_connect(&self, url: &str) -> Result<Box<dyn WebSocketConnection>, String> {
+        match &self.websocket {
+            Some(websocket) => Ok(Box::new(websocket.clone())),
+            None => Err(format!("no mock WebSocket for '{}'", url)),
+        }
+    }
+}
+
+/// WebSocket connection for tests: it delivers a fixed list of messages and
+/// records what is sent. Once the script runs out, `recv` times out at once
+/// instead of waiting. Clones share the same state.
+#[derive(Clone, Default)]
+pub struct ScriptedWebSocket {
+    incoming: std::rc::Rc<std::cell::RefCell<std::collections::VecDeque<String>>>,
+    sent: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+    closed: std::rc::Rc<std::cell::Cell<bool>>,
+}
+
+impl ScriptedWebSocket {
+    pub fn new(messages: Vec<String>) -> Self {
+        Self {
+            incoming: std::rc::Rc::new(std::cell::RefCell::new(messages.into())),
+            ..Default::default()
+        }
+    }
+
+    /// Messages sent so far, in order
+    pub fn sent(&self) -> Vec<String> {
+        self.sent.borrow().clone()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+
+    fn check_open(&self) -> Result<(), String> {
+        if self.closed.get() {
+            Err("connection closed".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl WebSocketConnection for ScriptedWebSocket {
+    fn send(&mut self, message: &str) -> Result<(), String> {
+        self.check_open()?;
+        self.sent.borrow_mut().push(message.to_string());
+        Ok(())
+    }
+
+    fn recv(&mut self, _timeout_ms: Option<u64>) -> Result<Option<String>, String> {
+        self.check_open()?;
+        Ok(self.incoming.borrow_mut().pop_front())
+    }
+
+    fn close(&mut self) -> Result<(), String> {
+        self.closed.set(true);
+        Ok(())
+    }
+}
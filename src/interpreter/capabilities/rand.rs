@@ -0,0 +1,145 @@
+//! Deterministic Rand capabilities: seeded, and replaying recorded draws
+
+use super::RandCapability;
+
+/// Seeded random capability for deterministic testing
+pub struct SeededRand {
+    seed: std::cell::Cell<u64>,
+}
+
+impl SeededRand {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed: std::cell::Cell::new(seed),
+        }
+    }
+
+    fn next(&self) -> u64 {
+        // Simple xorshift64
+        let mut x = self.seed.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.seed.set(x);
+        x
+    }
+}
+
+impl RandCapability for SeededRand {
+    fn int(&self, min: i64, max: i64) -> i64 {
+        let range = (max - min + 1) as u64;
+        let r = self.next() % range;
+        min + r as i64
+    }
+
+    fn bool(&self) -> bool {
+        self.next().is_multiple_of(2)
+    }
+
+    fn float(&self) -> f64 {
+        (self.next() as f64) / (u64::MAX as f64)
+    }
+}
+
+/// A value drawn from the Rand effect, as recorded for a property test
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RandDraw {
+    /// `Rand.int(min, max)` returned `value`
+    Int { value: i64, min: i64, max: i64 },
+    /// `Rand.bool()`
+    Bool(bool),
+    /// `Rand.float()`
+    Float(f64),
+}
+
+impl std::fmt::Display for RandDraw {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RandDraw::Int { value, min, max } => {
+                write!(f, "Rand.int({}, {}) = {}", min, max, value)
+            }
+            RandDraw::Bool(value) => write!(f, "Rand.bool() = {}", value),
+            RandDraw::Float(value) => write!(f, "Rand.float() = {}", value),
+        }
+    }
+}
+
+/// Rand capability that replays a script of draws and records every value
+/// it returns.
+///
+/// Draws come from the script while it lasts and its next entry has the
+/// requested kind; after that they come from a seeded generator. Replayed
+/// ints are clamped to the requested range.
+pub struct ReplayRand {
+    script: std::cell::RefCell<std::collections::VecDeque<RandDraw>>,
+    fallback: SeededRand,
+    drawn: std::rc::Rc<std::cell::RefCell<Vec<RandDraw>>>,
+}
+
+impl ReplayRand {
+    pub fn new(seed: u64, script: Vec<RandDraw>) -> Self {
+        Self {
+            script: std::cell::RefCell::new(script.into()),
+            fallback: SeededRand::new(seed),
+            drawn: Default::default(),
+        }
+    }
+
+    /// Handle to the draws made so far, usable after the capability has been
+    /// moved into an interpreter
+    pub fn drawn(&self) -> std::rc::Rc<std::cell::RefCell<Vec<RandDraw>>> {
+        self.drawn.clone()
+    }
+
+    fn next_scripted(&self) -> Option<RandDraw> {
+        self.script.borrow_mut().pop_front()
+    }
+
+    /// Stop replaying once the script no longer matches what the test draws
+    fn stop_replay(&self) {
+        self.script.borrow_mut().clear();
+    }
+
+    fn record(&self, draw: RandDraw) {
+        self.drawn.borrow_mut().push(draw);
+    }
+}
+
+impl RandCapability for ReplayRand {
+    fn int(&self, min: i64, max: i64) -> i64 {
+        let value = match self.next_scripted() {
+            Some(RandDraw::Int { value, .. }) => value.clamp(min, max),
+            _ => {
+                self.stop_replay();
+                self.fallback.int(min, max)
+            }
+        };
+        self.record(RandDraw::Int { value, min, max });
+        value
+    }
+
+    fn bool(&self) -> bool {
+        let value = match self.next_scripted() {
+            Some(RandDraw::Bool(value)) => value,
+            _ => {
+                self.stop_replay();
+                self.fallback.bool()
+            }
+        };
+        self.record(RandDraw::Bool(value));
+        value
+    }
+
+    fn float(&self) -> f64 {
+        let value = match self.next_scripted() {
+            Some(RandDraw::Float(value)) => value,
+            _ => {
+                self.stop_replay();
+                self.fallback.float()
+            }
+        };
+        self.record(RandDraw::Float(value));
+        value
+    }
+}
@@ -0,0 +1,57 @@
+//! Capabilities held as values, and the checks their attenuations make
+
+use super::FsCapability;
+
+/// A capability held as a value, such as the argument of `fn backup(fs: Fs)`.
+/// It grants what the host's capability for `effect` grants, narrowed by the
+/// attenuations applied to it in Astra code.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CapabilityValue {
+    pub effect: String,
+    /// Set by `fs.read_only()`: writes are refused
+    pub read_only: bool,
+    /// Added to by `fs.scoped(dir)`: a path must lie inside every one
+    pub roots: Vec<String>,
+}
+
+impl CapabilityValue {
+    /// The host's capability for `effect`, unrestricted
+    pub fn host(effect: &str) -> Self {
+        Self {
+            effect: effect.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Whether `path` lies inside every root, after the host resolves both
+    pub(super) fn allows(&self, host: &dyn FsCapability, path: &str) -> bool {
+        let path = normalize_path(&host.resolve(path));
+        self.roots.iter().all(|root| {
+            let root = normalize_path(&host.resolve(root));
+            path.0 == root.0 && path.1.starts_with(&root.1)
+        })
+    }
+}
+
+/// Whether a path is absolute, and its components with `.` and `..` resolved
+/// lexically. Leading `..` components of a relative path are kept.
+fn normalize_path(path: &str) -> (bool, Vec<String>) {
+    use std::path::Component;
+    let path = std::path::Path::new(path);
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::ParentDir if parts.last().is_some_and(|last| last != "..") => {
+                parts.pop();
+            }
+            Component::ParentDir if !path.has_root() => parts.push("..".to_string()),
+            _ => {}
+        }
+    }
+    (path.has_root(), parts)
+}
+
+#[cfg(test)]
+#[path = "value_tests.rs"]
+mod tests;
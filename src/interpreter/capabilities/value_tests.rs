@@ -0,0 +1,48 @@
+use super::*;
+use crate::interpreter::MemoryFs;
+
+fn scoped(roots: &[&str]) -> CapabilityValue {
+    CapabilityValue {
+        roots: roots.iter().map(|root| root.to_string()).collect(),
+        ..CapabilityValue::host("Fs")
+    }
+}
+
+#[test]
+fn test_unscoped_value_allows_any_path() {
+    let host = MemoryFs::default();
+    assert!(CapabilityValue::host("Fs").allows(&host, "/etc/passwd"));
+    assert!(CapabilityValue::host("Fs").allows(&host, "../up.txt"));
+}
+
+#[test]
+fn test_scoped_value_resolves_dots_before_comparing() {
+    let host = MemoryFs::default();
+    let data = scoped(&["/data"]);
+    assert!(data.allows(&host, "/data/a.txt"));
+    assert!(data.allows(&host, "/data/./sub/../b.txt"));
+    assert!(!data.allows(&host, "/data/../etc/x"));
+    assert!(!data.allows(&host, "/database/x"));
+    // An absolute root never covers a relative path
+    assert!(!data.allows(&host, "data/a.txt"));
+}
+
+#[test]
+fn test_every_root_must_allow_the_path() {
+    let host = MemoryFs::default();
+    let nested = scoped(&["/data", "/"]);
+    assert!(nested.allows(&host, "/data/a.txt"));
+    assert!(!nested.allows(&host, "/c.txt"));
+}
+
+#[test]
+fn test_normalize_path_keeps_leading_parent_dirs() {
+    assert_eq!(
+        normalize_path("../a/./b/../c"),
+        (
+            false,
+            vec!["..".to_string(), "a".to_string(), "c".to_string()]
+        )
+    );
+    assert_eq!(normalize_path("/../a"), (true, vec!["a".to_string()]));
+}
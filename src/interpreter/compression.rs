@@ -0,0 +1,30 @@
+//! gzip compression for `compress`/`decompress` and `Fs.read_gzip`/`write_gzip`.
+//!
+//! Output is plain gzip (RFC 1952) with no timestamp or file name in the
+//! header, so compressing the same data always gives the same bytes.
+
+use std::io::{Read, Write};
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// Compress bytes to gzip at the default level
+pub(super) fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    // Writing to a Vec can't fail
+    encoder
+        .write_all(bytes)
+        .and_then(|_| encoder.finish())
+        .expect("gzip into memory")
+}
+
+/// Decompress gzip, including files made of several concatenated members
+/// like those `cat a.gz b.gz` produces
+pub(super) fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    MultiGzDecoder::new(bytes)
+        .read_to_end(&mut out)
+        .map_err(|e| format!("invalid gzip data: {}", e))?;
+    Ok(out)
+}
@@ -0,0 +1,104 @@
+//! Exact decimal arithmetic for the `Decimal` type.
+//!
+//! A Decimal is a 96-bit integer scaled by a power of ten, with up to 28 digits
+//! after the point, so `0.10d + 0.20d` is exactly `0.30d`. Arithmetic is
+//! checked: a result that doesn't fit is an error, never a rounded value.
+
+use std::str::FromStr;
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::parser::ast::BinaryOp;
+
+use super::error::RuntimeError;
+use super::value::Value;
+
+/// Most digits a Decimal can keep after the point
+pub(super) const MAX_SCALE: i64 = 28;
+
+/// Convert an Int or a finite Float to a Decimal. Floats go through their
+/// shortest round-trip digits, so `to_decimal(0.1)` is `0.1`, not the binary
+/// approximation.
+pub(super) fn to_decimal(value: &Value) -> Result<Value, RuntimeError> {
+    match value {
+        Value::Int(n) => Ok(Value::Decimal(Decimal::from(*n))),
+        Value::Decimal(d) => Ok(Value::Decimal(*d)),
+        Value::Float(f) if f.is_finite() => Decimal::from_str(&f.to_string())
+            .map(Value::Decimal)
+            .map_err(|_| RuntimeError::arithmetic_overflow("decimal", "to_decimal")),
+        Value::Float(_) => Err(RuntimeError::type_mismatch(
+            "finite Float",
+            &super::value::format_value(value),
+        )),
+        other => Err(RuntimeError::type_mismatch(
+            "Int or Float",
+            &format!("{:?}", other),
+        )),
+    }
+}
+
+/// Apply a binary operator to two Decimals. Ints are promoted to Decimal by
+/// the caller; Floats never are, since mixing them would bring back rounding.
+pub(super) fn decimal_binary_op(
+    op: BinaryOp,
+    a: Decimal,
+    b: Decimal,
+) -> Result<Value, RuntimeError> {
+    let checked = |result: Option<Decimal>, operation: &str| {
+        result
+            .map(Value::Decimal)
+            .ok_or_else(|| RuntimeError::arithmetic_overflow("decimal", operation))
+    };
+    match op {
+        BinaryOp::Add => checked(a.checked_add(b), "addition"),
+        BinaryOp::Sub => checked(a.checked_sub(b), "subtraction"),
+        BinaryOp::Mul => checked(a.checked_mul(b), "multiplication"),
+        BinaryOp::Div | BinaryOp::Mod if b.is_zero() => Err(RuntimeError::division_by_zero()),
+        BinaryOp::Div => checked(a.checked_div(b), "division"),
+        BinaryOp::Mod => checked(a.checked_rem(b), "remainder"),
+        BinaryOp::Eq => Ok(Value::Bool(a == b)),
+        BinaryOp::Ne => Ok(Value::Bool(a != b)),
+        BinaryOp::Lt => Ok(Value::Bool(a < b)),
+        BinaryOp::Le => Ok(Value::Bool(a <= b)),
+        BinaryOp::Gt => Ok(Value::Bool(a > b)),
+        BinaryOp::Ge => Ok(Value::Bool(a >= b)),
+        BinaryOp::And | BinaryOp::Or | BinaryOp::Pipe => Err(RuntimeError::type_mismatch(
+            &format!("compatible types for {:?}", op),
+            "Decimal",
+        )),
+    }
+}
+
+/// Built-in methods on Decimal values
+pub(super) fn decimal_method(
+    d: Decimal,
+    method: &str,
+    args: &[Value],
+) -> Result<Value, RuntimeError> {
+    match method {
+        "abs" => Ok(Value::Decimal(d.abs())),
+        "scale" => Ok(Value::Int(d.scale() as i64)),
+        "to_float" => Ok(Value::Float(d.to_f64().unwrap_or(f64::NAN))),
+        "round" => match args.first() {
+            Some(Value::Int(places)) if (0..=MAX_SCALE).contains(places) => Ok(Value::Decimal(
+                d.round_dp_with_strategy(*places as u32, RoundingStrategy::MidpointAwayFromZero),
+            )),
+            Some(Value::Int(places)) => Err(RuntimeError::type_mismatch(
+                &format!("Int between 0 and {}", MAX_SCALE),
+                &places.to_string(),
+            )),
+            _ => Err(RuntimeError::type_mismatch("Int", "other")),
+        },
+        _ => Err(RuntimeError::unknown_method("Decimal", method)),
+    }
+}
+
+/// Render a Decimal with the scale it carries (`1.50`), without a sign on zero
+pub(super) fn format_decimal(d: &Decimal) -> String {
+    if d.is_zero() {
+        d.abs().to_string()
+    } else {
+        d.to_string()
+    }
+}
@@ -0,0 +1,141 @@
+//! `Duration` and `Deadline` values.
+//!
+//! A Duration is a signed span of milliseconds, the unit `Clock.now()` uses,
+//! and a Deadline is the instant, in milliseconds since the Unix epoch, when
+//! a duration measured from `Clock.deadline(d)` runs out. Keeping them apart
+//! from Int means a timeout can't be added to a count by accident.
+
+use crate::parser::ast::BinaryOp;
+
+use super::error::RuntimeError;
+use super::value::Value;
+
+const SECOND: i64 = 1000;
+const MINUTE: i64 = 60 * SECOND;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+fn overflow(operation: &str) -> RuntimeError {
+    RuntimeError::arithmetic_overflow("duration", operation)
+}
+
+/// `Duration.millis(n)`, `Duration.seconds(n)`, and the other constructors
+pub(super) fn duration_constructor(method: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+    let unit = match method {
+        "millis" => 1,
+        "seconds" => SECOND,
+        "minutes" => MINUTE,
+        "hours" => HOUR,
+        "days" => DAY,
+        _ => return Err(RuntimeError::unknown_method("Duration", method)),
+    };
+    match args {
+        [Value::Int(n)] => n
+            .checked_mul(unit)
+            .map(Value::Duration)
+            .ok_or_else(|| overflow(method)),
+        [_] => Err(RuntimeError::type_mismatch("Int", "other")),
+        _ => Err(RuntimeError::arity_mismatch(1, args.len())),
+    }
+}
+
+/// Apply a binary operator where at least one side is a Duration. Durations
+/// add to and compare with Durations, scale by Ints, and divide into a
+/// Duration to give a whole count.
+pub(super) fn duration_binary_op(
+    op: BinaryOp,
+    left: &Value,
+    right: &Value,
+) -> Result<Value, RuntimeError> {
+    let result = match (op, left, right) {
+        (BinaryOp::Add, Value::Duration(a), Value::Duration(b)) => a.checked_add(*b),
+        (BinaryOp::Sub, Value::Duration(a), Value::Duration(b)) => a.checked_sub(*b),
+        (BinaryOp::Mul, Value::Duration(d), Value::Int(n))
+        | (BinaryOp::Mul, Value::Int(n), Value::Duration(d)) => d.checked_mul(*n),
+        (BinaryOp::Div | BinaryOp::Mod, Value::Duration(_), Value::Int(0))
+        | (BinaryOp::Div | BinaryOp::Mod, Value::Duration(_), Value::Duration(0)) => {
+            return Err(RuntimeError::division_by_zero())
+        }
+        (BinaryOp::Div, Value::Duration(d), Value::Int(n)) => d.checked_div(*n),
+        (BinaryOp::Div, Value::Duration(a), Value::Duration(b)) => {
+            return a
+                .checked_div(*b)
+                .map(Value::Int)
+                .ok_or_else(|| overflow("division"))
+        }
+        (BinaryOp::Mod, Value::Duration(a), Value::Duration(b)) => a.checked_rem(*b),
+        (
+            BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge,
+            Value::Duration(a),
+            Value::Duration(b),
+        ) => {
+            let order = a.cmp(b);
+            return Ok(Value::Bool(match op {
+                BinaryOp::Eq => order.is_eq(),
+                BinaryOp::Ne => order.is_ne(),
+                BinaryOp::Lt => order.is_lt(),
+                BinaryOp::Le => order.is_le(),
+                BinaryOp::Gt => order.is_gt(),
+                _ => order.is_ge(),
+            }));
+        }
+        _ => {
+            return Err(RuntimeError::type_mismatch(
+                &format!("compatible types for {:?}", op),
+                &format!("{:?} and {:?}", left, right),
+            ))
+        }
+    };
+    result.map(Value::Duration).ok_or_else(|| {
+        overflow(match op {
+            BinaryOp::Add => "addition",
+            BinaryOp::Sub => "subtraction",
+            BinaryOp::Mul => "multiplication",
+            _ => "division",
+        })
+    })
+}
+
+/// Built-in methods on Duration values
+pub(super) fn duration_method(millis: i64, method: &str) -> Result<Value, RuntimeError> {
+    match method {
+        "as_millis" => Ok(Value::Int(millis)),
+        "as_seconds" => Ok(Value::Int(millis / SECOND)),
+        "as_minutes" => Ok(Value::Int(millis / MINUTE)),
+        "as_hours" => Ok(Value::Int(millis / HOUR)),
+        "as_days" => Ok(Value::Int(millis / DAY)),
+        "abs" => millis
+            .checked_abs()
+            .map(Value::Duration)
+            .ok_or_else(|| overflow("abs")),
+        _ => Err(RuntimeError::unknown_method("Duration", method)),
+    }
+}
+
+/// Render a Duration compactly, largest unit first: `1h30m`, `2.5s`, `250ms`, `0s`
+pub(super) fn format_duration(millis: i64) -> String {
+    if millis == 0 {
+        return "0s".to_string();
+    }
+    let sign = if millis < 0 { "-" } else { "" };
+    let total = millis.unsigned_abs();
+    let (hours, minutes) = (total / HOUR as u64, total % HOUR as u64 / MINUTE as u64);
+    let (seconds, fraction) = (total % MINUTE as u64 / 1000, total % 1000);
+    if total < SECOND as u64 {
+        return format!("{}{}ms", sign, total);
+    }
+    let mut text = sign.to_string();
+    if hours > 0 {
+        text.push_str(&format!("{}h", hours));
+    }
+    if minutes > 0 {
+        text.push_str(&format!("{}m", minutes));
+    }
+    if fraction > 0 {
+        let digits = format!("{:03}", fraction);
+        text.push_str(&format!("{}.{}s", seconds, digits.trim_end_matches('0')));
+    } else if seconds > 0 {
+        text.push_str(&format!("{}s", seconds));
+    }
+    text
+}
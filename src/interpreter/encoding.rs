@@ -0,0 +1,128 @@
+//! Base64 and hex encoding of Text and Bytes.
+//!
+//! Encoders take Text (as its UTF-8 bytes) or Bytes and never fail. Decoders
+//! return `Result[Bytes, EncodingError]`, where the error is a record
+//! `{code, message, index}`: the dedicated error code (E4026 for base64,
+//! E4027 for hex), a message, and the character index of the first problem.
+
+use indexmap::IndexMap;
+
+use crate::diagnostics::error_codes::runtime::{INVALID_BASE64, INVALID_HEX};
+
+use super::error::RuntimeError;
+use super::value::Value;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The bytes an encoder works on: Text as UTF-8, or Bytes as-is
+pub(super) fn encoder_input(value: &Value) -> Result<&[u8], RuntimeError> {
+    match value {
+        Value::Text(s) => Ok(s.as_bytes()),
+        Value::Bytes(bytes) => Ok(bytes),
+        other => Err(RuntimeError::type_mismatch(
+            "Text or Bytes",
+            &format!("{:?}", other),
+        )),
+    }
+}
+
+/// Standard base64 (RFC 4648) with `=` padding
+pub(super) fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode standard base64; the trailing `=` padding is optional
+pub(super) fn base64_decode(text: &str) -> Value {
+    let chars: Vec<char> = text.chars().collect();
+    let padding = chars.iter().rev().take_while(|c| **c == '=').count();
+    let data = &chars[..chars.len() - padding];
+    if padding > 2 || (padding > 0 && !chars.len().is_multiple_of(4)) {
+        return encoding_error(
+            INVALID_BASE64,
+            format!("misplaced `=` padding at index {}", data.len()),
+            data.len(),
+        );
+    }
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for (index, c) in data.iter().enumerate() {
+        let Some(sextet) = BASE64_ALPHABET.iter().position(|a| *a as char == *c) else {
+            return encoding_error(
+                INVALID_BASE64,
+                format!("invalid base64 character `{}` at index {}", c, index),
+                index,
+            );
+        };
+        buffer = buffer << 6 | sextet as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    if data.len() % 4 == 1 {
+        return encoding_error(
+            INVALID_BASE64,
+            format!(
+                "base64 text ends with a partial byte at index {}",
+                data.len() - 1
+            ),
+            data.len() - 1,
+        );
+    }
+    Value::Ok(Box::new(Value::Bytes(bytes)))
+}
+
+/// Lowercase hexadecimal digits, two per byte
+pub(super) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode hex digits in either case, two per byte
+pub(super) fn hex_decode(text: &str) -> Value {
+    let chars: Vec<char> = text.chars().collect();
+    if let Some(index) = chars.iter().position(|c| !c.is_ascii_hexdigit()) {
+        return encoding_error(
+            INVALID_HEX,
+            format!("invalid hex digit `{}` at index {}", chars[index], index),
+            index,
+        );
+    }
+    if chars.len() % 2 == 1 {
+        return encoding_error(
+            INVALID_HEX,
+            format!("odd number of hex digits ({})", chars.len()),
+            chars.len(),
+        );
+    }
+    let bytes = chars
+        .chunks(2)
+        .map(|pair| {
+            (pair[0].to_digit(16).unwrap_or(0) * 16 + pair[1].to_digit(16).unwrap_or(0)) as u8
+        })
+        .collect();
+    Value::Ok(Box::new(Value::Bytes(bytes)))
+}
+
+fn encoding_error(code: &str, message: String, index: usize) -> Value {
+    Value::Err(Box::new(Value::Record(IndexMap::from([
+        ("code".to_string(), Value::Text(code.to_string())),
+        ("message".to_string(), Value::Text(message)),
+        ("index".to_string(), Value::Int(index as i64)),
+    ]))))
+}
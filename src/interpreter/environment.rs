@@ -1,62 +1,169 @@
 //! Execution environment for the Astra interpreter.
+//!
+//! Closures capture by name: a lambda keeps the bindings its body mentions,
+//! not a copy of every scope. A plain `let` binding is captured by value,
+//! which is indistinguishable from sharing since it never changes. A `let mut`
+//! binding lives in a shared cell, so a closure that captures one sees the
+//! enclosing scope's later assignments, and the enclosing scope sees the
+//! closure's. Only `let mut` bindings can be assigned to.
 
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
-use super::value::Value;
+use super::value::{value_size, Value};
+
+/// Storage for one variable
+#[derive(Debug, Clone)]
+enum Binding {
+    /// A `let` binding or parameter, copied into closures that capture it
+    Value(Value),
+    /// A `let mut` binding, shared with closures that capture it
+    Cell(Rc<RefCell<Value>>),
+}
+
+impl Binding {
+    fn get(&self) -> Value {
+        match self {
+            Binding::Value(value) => value.clone(),
+            Binding::Cell(cell) => cell.borrow().clone(),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Binding::Value(value) => value_size(value),
+            Binding::Cell(cell) => value_size(&cell.borrow()),
+        }
+    }
+}
+
+type Scope = HashMap<String, Binding>;
+
+/// Why [`Environment::update`] could not assign to a variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateError {
+    /// No variable has that name
+    Undefined,
+    /// The variable was not declared with `let mut`
+    Immutable,
+}
 
 /// Execution environment using a scope stack for O(1) scope creation.
 #[derive(Debug, Clone, Default)]
 pub struct Environment {
-    /// Stack of variable binding scopes (top = innermost scope)
-    scopes: Vec<HashMap<String, Value>>,
+    /// Stack of variable binding scopes (top = innermost scope). A scope is
+    /// shared between environments until one of them writes to it; closures
+    /// share the bottom (global) scope this way.
+    scopes: Vec<Rc<Scope>>,
+    /// Estimated bytes bound in all scopes, when memory is tracked
+    bytes: Option<usize>,
 }
 
 impl Environment {
     /// Create a new environment with one empty scope
     pub fn new() -> Self {
         Self {
-            scopes: vec![HashMap::new()],
+            scopes: vec![Rc::default()],
+            bytes: None,
         }
     }
 
     /// Push a new empty scope onto the stack (O(1))
     pub fn push_scope(&mut self) {
-        self.scopes.push(HashMap::new());
+        self.scopes.push(Rc::default());
     }
 
     /// Pop the top scope off the stack (O(1))
     pub fn pop_scope(&mut self) {
         if self.scopes.len() > 1 {
-            self.scopes.pop();
+            if let (Some(scope), Some(bytes)) = (self.scopes.pop(), self.bytes.as_mut()) {
+                *bytes -= scope.values().map(Binding::size).sum::<usize>();
+            }
         }
     }
 
     /// Define a variable in the top scope
     pub fn define(&mut self, name: String, value: Value) {
+        self.bind(name, Binding::Value(value));
+    }
+
+    /// Define a `let mut` variable in the top scope. Closures that capture it
+    /// share its value instead of copying it.
+    pub fn define_mut(&mut self, name: String, value: Value) {
+        self.bind(name, Binding::Cell(Rc::new(RefCell::new(value))));
+    }
+
+    fn bind(&mut self, name: String, binding: Binding) {
         if let Some(top) = self.scopes.last_mut() {
-            top.insert(name, value);
+            if let Some(bytes) = self.bytes.as_mut() {
+                *bytes += binding.size();
+            }
+            let old = Rc::make_mut(top).insert(name, binding);
+            if let (Some(old), Some(bytes)) = (old, self.bytes.as_mut()) {
+                *bytes -= old.size();
+            }
         }
     }
 
     /// Look up a variable, searching from top scope to bottom
-    pub fn lookup(&self, name: &str) -> Option<&Value> {
-        for scope in self.scopes.iter().rev() {
-            if let Some(val) = scope.get(name) {
-                return Some(val);
+    pub fn lookup(&self, name: &str) -> Option<Value> {
+        self.find(name).map(Binding::get)
+    }
+
+    /// Check if a variable is bound in any scope
+    pub fn contains(&self, name: &str) -> bool {
+        self.find(name).is_some()
+    }
+
+    fn find(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Assign to a `let mut` variable, searching from top scope to bottom
+    pub fn update(&mut self, name: &str, value: Value) -> Result<(), UpdateError> {
+        match self.find(name) {
+            Some(Binding::Cell(cell)) => {
+                let cell = Rc::clone(cell);
+                if let Some(bytes) = self.bytes.as_mut() {
+                    *bytes = *bytes + value_size(&value) - value_size(&cell.borrow());
+                }
+                *cell.borrow_mut() = value;
+                Ok(())
             }
+            Some(Binding::Value(_)) => Err(UpdateError::Immutable),
+            None => Err(UpdateError::Undefined),
         }
-        None
     }
 
-    /// Update a mutable variable, searching from top scope to bottom
-    pub fn update(&mut self, name: &str, value: Value) -> bool {
-        for scope in self.scopes.iter_mut().rev() {
-            if scope.contains_key(name) {
-                scope.insert(name.to_string(), value);
-                return true;
+    /// The environment a closure runs in: the bottom (global) scope, shared
+    /// rather than copied, and one scope holding the bindings named in
+    /// `names` from the scopes above it. `let mut` bindings stay shared.
+    pub fn capture<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Environment {
+        let mut captured = Scope::new();
+        for name in names {
+            if let Some(binding) = self
+                .scopes
+                .iter()
+                .skip(1)
+                .rev()
+                .find_map(|scope| scope.get(name))
+            {
+                captured.insert(name.to_string(), binding.clone());
             }
         }
-        false
+        let scopes = vec![
+            self.scopes.first().cloned().unwrap_or_default(),
+            Rc::new(captured),
+        ];
+        let bytes = self.bytes.map(|_| {
+            scopes
+                .iter()
+                .flat_map(|scope| scope.values())
+                .map(Binding::size)
+                .sum()
+        });
+        Environment { scopes, bytes }
     }
 
     /// Check if the environment has no user-defined bindings
@@ -65,6 +172,39 @@ impl Environment {
         self.scopes.iter().all(|s| s.is_empty())
     }
 
+    /// Bindings visible from the top scope down to scope `from` (0 is the
+    /// bottom), innermost first and sorted by name within each scope
+    pub fn bindings_from(&self, from: usize) -> Vec<(&str, Value)> {
+        let mut seen = HashSet::new();
+        let mut bindings = Vec::new();
+        for scope in self.scopes.iter().skip(from).rev() {
+            let mut names: Vec<&String> = scope.keys().collect();
+            names.sort();
+            for name in names {
+                if seen.insert(name.as_str()) {
+                    bindings.push((name.as_str(), scope[name].get()));
+                }
+            }
+        }
+        bindings
+    }
+
+    /// Start estimating the bytes bound in this environment
+    pub fn track_memory(&mut self) {
+        let bytes = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.values())
+            .map(Binding::size)
+            .sum();
+        self.bytes = Some(bytes);
+    }
+
+    /// Estimated bytes bound in this environment, if tracked
+    pub fn tracked_bytes(&self) -> Option<usize> {
+        self.bytes
+    }
+
     /// Get the number of scopes
     pub fn scope_depth(&self) -> usize {
         self.scopes.len()
@@ -21,6 +21,8 @@ pub struct RuntimeError {
     pub is_return: bool,
     /// B5: Source location where the error occurred
     pub span: Option<crate::diagnostics::Span>,
+    /// Whether the stack trace has been appended to the message
+    pub has_stack_trace: bool,
 }
 
 impl RuntimeError {
@@ -34,6 +36,7 @@ impl RuntimeError {
             is_continue: false,
             is_return: false,
             span: None,
+            has_stack_trace: false,
         }
     }
 
@@ -53,6 +56,7 @@ impl RuntimeError {
             is_continue: false,
             is_return: false,
             span: None,
+            has_stack_trace: false,
         }
     }
 
@@ -66,6 +70,7 @@ impl RuntimeError {
             is_continue: false,
             is_return: false,
             span: None,
+            has_stack_trace: false,
         }
     }
 
@@ -79,6 +84,7 @@ impl RuntimeError {
             is_continue: true,
             is_return: false,
             span: None,
+            has_stack_trace: false,
         }
     }
 
@@ -92,6 +98,7 @@ impl RuntimeError {
             is_continue: false,
             is_return: true,
             span: None,
+            has_stack_trace: false,
         }
     }
 
@@ -143,6 +150,55 @@ impl RuntimeError {
         Self::new("E4006", format!("unknown method: {}.{}", receiver, method))
     }
 
+    /// Evaluation limit error, for programs stopped by [`super::EvalLimits`]
+    pub fn resource_limit_exceeded(resource: &str) -> Self {
+        Self::new(
+            crate::diagnostics::error_codes::runtime::EVAL_LIMIT_EXCEEDED,
+            format!("resource limit exceeded: {}", resource),
+        )
+    }
+
+    /// Error for a run that went over a budget from the manifest's `[limits]`
+    pub fn budget_exceeded(limit: &str, max: u64) -> Self {
+        Self::new(
+            crate::diagnostics::error_codes::runtime::BUDGET_EXCEEDED,
+            format!("budget exceeded: `{}` = {}", limit, max),
+        )
+    }
+
+    /// Error for a call nested deeper than the interpreter's maximum call
+    /// depth, raised before the host stack can overflow
+    pub fn stack_overflow(max_depth: usize) -> Self {
+        Self::new(
+            "E4028",
+            format!("stack overflow: more than {} nested calls", max_depth),
+        )
+    }
+
+    /// The function a program is run from doesn't exist
+    pub fn missing_entry(entry: &str) -> Self {
+        Self::new(
+            "E4029",
+            format!("entry function `{}` is not defined", entry),
+        )
+    }
+
+    /// The function a program is run from takes parameters
+    pub fn entry_takes_parameters(entry: &str) -> Self {
+        Self::new(
+            "E4029",
+            format!("entry function `{}` must not take parameters", entry),
+        )
+    }
+
+    /// The program was stopped from `astra debug`
+    pub fn stopped_by_debugger() -> Self {
+        Self::new(
+            crate::diagnostics::error_codes::runtime::STOPPED_BY_DEBUGGER,
+            "program stopped by the debugger",
+        )
+    }
+
     /// Pattern match failure error
     pub fn match_failure() -> Self {
         Self::new("E4007", "no pattern matched")
@@ -176,11 +232,66 @@ impl RuntimeError {
         Self::new("E4012", format!("tried to unwrap Err: {}", msg))
     }
 
+    /// Assignment to a binding not declared with `let mut`
+    pub fn immutable_assignment(name: &str) -> Self {
+        Self::new(
+            crate::diagnostics::error_codes::runtime::IMMUTABLE_ASSIGNMENT,
+            format!(
+                "cannot assign to `{}`: it is not declared with `let mut`",
+                name
+            ),
+        )
+    }
+
     /// Hole encountered error
     pub fn hole_encountered() -> Self {
         Self::new("E4013", "encountered incomplete code (hole)")
     }
 
+    /// A Decimal or Duration result too large, or too precise, to represent
+    pub fn arithmetic_overflow(kind: &str, operation: &str) -> Self {
+        Self::new(
+            crate::diagnostics::error_codes::runtime::INTEGER_OVERFLOW,
+            format!("{} overflow in {}", kind, operation),
+        )
+    }
+
+    /// A Text index outside `0..=len`, counted in characters
+    pub fn text_index_out_of_bounds(index: i64, len: usize) -> Self {
+        Self::new(
+            crate::diagnostics::error_codes::runtime::TEXT_INDEX_OUT_OF_BOUNDS,
+            format!("text index {} out of bounds for length {}", index, len),
+        )
+    }
+
+    /// A Text range whose start is after its end
+    pub fn invalid_text_range(start: i64, end: i64) -> Self {
+        Self::new(
+            crate::diagnostics::error_codes::runtime::INVALID_TEXT_RANGE,
+            format!("text range start {} is after end {}", start, end),
+        )
+    }
+
+    /// Error for source that doesn't parse, from the parser's first
+    /// diagnostic
+    pub fn syntax_error(diagnostic: &crate::diagnostics::Diagnostic) -> Self {
+        use crate::diagnostics::error_codes::syntax::*;
+        let code = [
+            UNEXPECTED_TOKEN,
+            UNTERMINATED_STRING,
+            INVALID_NUMBER,
+            MISSING_DELIMITER,
+            INVALID_IDENTIFIER,
+            RESERVED_KEYWORD,
+            INVALID_ESCAPE,
+            UNEXPECTED_EOF,
+        ]
+        .into_iter()
+        .find(|code| *code == diagnostic.code)
+        .unwrap_or(UNEXPECTED_TOKEN);
+        Self::new(code, diagnostic.message.clone()).with_span(diagnostic.span.clone())
+    }
+
     /// Precondition violation error
     pub fn precondition_violated(fn_name: &str) -> Self {
         Self::new(
@@ -233,6 +344,8 @@ pub fn check_arity<T>(args: &[T], expected: usize) -> Result<(), RuntimeError> {
 pub struct CallFrame {
     pub name: String,
     pub span: Option<crate::diagnostics::Span>,
+    /// Environment scope holding the call's parameters
+    pub scope_depth: usize,
 }
 
 impl CallFrame {
@@ -240,6 +353,7 @@ impl CallFrame {
         Self {
             name: name.into(),
             span,
+            scope_depth: 0,
         }
     }
 }
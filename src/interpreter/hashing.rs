@@ -0,0 +1,154 @@
+//! Deterministic hashing for `hash(value)` and `sha256(data)`.
+//!
+//! Both results are stable: the same value hashes the same way on every run,
+//! platform, and release, so they can key caches and fingerprint snapshots.
+//! `hash` feeds a canonical encoding of the value to SHA-256 and keeps the
+//! first eight bytes; changing that encoding is a breaking change.
+
+use sha2::{Digest, Sha256};
+
+use super::error::RuntimeError;
+use super::value::Value;
+
+/// Structural hash of a value, consistent with `==`: equal values hash equally
+pub(super) fn structural_hash(value: &Value) -> Result<i64, RuntimeError> {
+    let mut hasher = Sha256::new();
+    encode(value, &mut hasher)?;
+    let digest = hasher.finalize();
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    Ok(i64::from_be_bytes(prefix))
+}
+
+/// SHA-256 digest of the given bytes as lowercase hex
+pub(super) fn sha256_hex(bytes: &[u8]) -> String {
+    super::encoding::hex_encode(&Sha256::digest(bytes))
+}
+
+/// Write a tag byte and then the value's contents. Variable-length parts are
+/// prefixed with their length, so no two different values share an encoding.
+fn encode(value: &Value, out: &mut Sha256) -> Result<(), RuntimeError> {
+    match value {
+        Value::Unit => out.update([0]),
+        Value::Bool(b) => out.update([1, *b as u8]),
+        Value::Int(n) => {
+            out.update([2]);
+            out.update(n.to_be_bytes());
+        }
+        Value::Float(f) => {
+            // `0.0 == -0.0`, and every NaN is the same NaN
+            let canonical = if *f == 0.0 {
+                0.0
+            } else if f.is_nan() {
+                f64::NAN
+            } else {
+                *f
+            };
+            out.update([3]);
+            out.update(canonical.to_bits().to_be_bytes());
+        }
+        Value::Text(s) => {
+            out.update([4]);
+            encode_bytes(s.as_bytes(), out);
+        }
+        Value::None => out.update([5]),
+        Value::Some(inner) => encode_wrapped(6, inner, out)?,
+        Value::Ok(inner) => encode_wrapped(7, inner, out)?,
+        Value::Err(inner) => encode_wrapped(8, inner, out)?,
+        Value::Tuple(items) => encode_seq(9, items, out)?,
+        Value::List(items) => encode_seq(10, items, out)?,
+        Value::Record(fields) => {
+            // Field order in the record literal doesn't change equality
+            let mut names: Vec<&String> = fields.keys().collect();
+            names.sort();
+            out.update([11]);
+            out.update((names.len() as u64).to_be_bytes());
+            for name in names {
+                encode_bytes(name.as_bytes(), out);
+                encode(&fields[name], out)?;
+            }
+        }
+        Value::Variant { name, data } => {
+            out.update([12]);
+            encode_bytes(name.as_bytes(), out);
+            match data {
+                Some(inner) => encode_wrapped(1, inner, out)?,
+                None => out.update([0]),
+            }
+        }
+        // Maps and Sets are kept sorted, so their order is already canonical
+        Value::Map(entries) => {
+            out.update([13]);
+            out.update((entries.len() as u64).to_be_bytes());
+            for (key, val) in entries {
+                encode(key, out)?;
+                encode(val, out)?;
+            }
+        }
+        Value::Set(items) => encode_seq(14, items, out)?,
+        Value::Decimal(d) => {
+            // `1.50d == 1.5d`, so drop trailing zeros first
+            out.update([18]);
+            encode_bytes(d.normalize().to_string().as_bytes(), out);
+        }
+        Value::Duration(ms) => {
+            out.update([19]);
+            out.update(ms.to_be_bytes());
+        }
+        Value::Deadline(ms) => {
+            out.update([20]);
+            out.update(ms.to_be_bytes());
+        }
+        Value::Bytes(bytes) => {
+            out.update([21]);
+            encode_bytes(bytes, out);
+        }
+        Value::Closure { .. } | Value::VariantConstructor { .. } | Value::Future { .. } => {
+            return Err(RuntimeError::type_mismatch(
+                "a value without functions",
+                "a function",
+            ))
+        }
+        // A connection is live state, not data
+        Value::WebSocket(_) => {
+            return Err(RuntimeError::type_mismatch(
+                "a value without connections",
+                "a WebSocket",
+            ))
+        }
+        Value::Capability(cap) => {
+            return Err(RuntimeError::type_mismatch(
+                "a value without capabilities",
+                &format!("the {} capability", cap.effect),
+            ))
+        }
+        Value::Namespace(name) => {
+            return Err(RuntimeError::type_mismatch(
+                "a value without namespaces",
+                &format!("`{}`", name),
+            ))
+        }
+        // A Ref is equal only to itself, which no stable hash can capture
+        Value::Ref(_) => return Err(RuntimeError::type_mismatch("a value without Refs", "a Ref")),
+    }
+    Ok(())
+}
+
+fn encode_bytes(bytes: &[u8], out: &mut Sha256) {
+    out.update((bytes.len() as u64).to_be_bytes());
+    out.update(bytes);
+}
+
+fn encode_wrapped(tag: u8, inner: &Value, out: &mut Sha256) -> Result<(), RuntimeError> {
+    out.update([tag]);
+    encode(inner, out)
+}
+
+fn encode_seq(tag: u8, items: &[Value], out: &mut Sha256) -> Result<(), RuntimeError> {
+    out.update([tag]);
+    out.update((items.len() as u64).to_be_bytes());
+    for item in items {
+        encode(item, out)?;
+    }
+    Ok(())
+}
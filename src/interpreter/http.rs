@@ -0,0 +1,131 @@
+//! HTTP requests and responses as Astra values, shared by `Net.serve` and
+//! the `Serve` capability.
+//!
+//! A handler receives a request record `{method, path, query, headers, body,
+//! params}` and returns a response record `{status, headers, body}`, where
+//! `status` defaults to 200 and `headers` and `body` may be left out.
+
+use indexmap::IndexMap;
+
+use super::capabilities::{HttpRequest, HttpResponse};
+use super::error::RuntimeError;
+use super::url_decode;
+use super::value::{format_value, sorted_map_from, Value};
+
+/// The request record a handler receives. `params` holds the `:name`
+/// segments of the route that matched.
+pub(super) fn request_value(request: &HttpRequest, params: Vec<(String, String)>) -> Value {
+    let (path, query) = match request.url.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (request.url.as_str(), ""),
+    };
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (url_decode(key), url_decode(value))
+        });
+    Value::Record(IndexMap::from([
+        ("method".to_string(), Value::Text(request.method.clone())),
+        ("path".to_string(), Value::Text(path.to_string())),
+        ("query".to_string(), text_map(query)),
+        (
+            "headers".to_string(),
+            text_map(request.headers.iter().cloned()),
+        ),
+        ("body".to_string(), Value::Text(request.body.clone())),
+        ("params".to_string(), text_map(params.into_iter())),
+    ]))
+}
+
+/// Read a handler's response record
+pub(super) fn response_from_value(value: &Value) -> Result<HttpResponse, RuntimeError> {
+    let Value::Record(fields) = value else {
+        return Err(RuntimeError::type_mismatch(
+            "a response record",
+            &format_value(value),
+        ));
+    };
+    let status = match fields.get("status") {
+        Some(Value::Int(status)) if (100..=999).contains(status) => *status as u16,
+        Some(other) => {
+            return Err(RuntimeError::type_mismatch(
+                "a status from 100 to 999",
+                &format_value(other),
+            ))
+        }
+        None => 200,
+    };
+    let headers = match fields.get("headers") {
+        Some(Value::Map(entries)) => entries
+            .iter()
+            .filter_map(|(key, value)| match (key, value) {
+                (Value::Text(key), Value::Text(value)) => Some((key.clone(), value.clone())),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let body = match fields.get("body") {
+        Some(Value::Text(body)) => body.clone(),
+        _ => String::new(),
+    };
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// The response record `Serve.request` gives back, with every field present
+pub(super) fn response_value(response: &HttpResponse) -> Value {
+    Value::Record(IndexMap::from([
+        ("status".to_string(), Value::Int(response.status as i64)),
+        (
+            "headers".to_string(),
+            text_map(response.headers.iter().cloned()),
+        ),
+        ("body".to_string(), Value::Text(response.body.clone())),
+    ]))
+}
+
+/// A plain-text response, for requests no handler answered
+pub(super) fn text_response(status: u16, body: &str) -> HttpResponse {
+    HttpResponse {
+        status,
+        headers: vec![(
+            "Content-Type".to_string(),
+            "text/plain; charset=utf-8".to_string(),
+        )],
+        body: body.to_string(),
+    }
+}
+
+/// Match a path against a route pattern such as `/users/:id`, returning the
+/// values of its `:name` segments. A trailing `*` segment matches the rest.
+pub(super) fn match_route(pattern: &str, path: &str) -> Option<Vec<(String, String)>> {
+    let mut params = Vec::new();
+    let mut path_segments = path.trim_matches('/').split('/');
+    for segment in pattern.trim_matches('/').split('/') {
+        if segment == "*" {
+            return Some(params);
+        }
+        let actual = path_segments.next()?;
+        match segment.strip_prefix(':') {
+            Some(name) if !actual.is_empty() => params.push((name.to_string(), url_decode(actual))),
+            Some(_) => return None,
+            None if segment == actual => {}
+            None => return None,
+        }
+    }
+    path_segments.next().is_none().then_some(params)
+}
+
+fn text_map(entries: impl Iterator<Item = (String, String)>) -> Value {
+    Value::Map(sorted_map_from(
+        entries
+            .map(|(key, value)| (Value::Text(key), Value::Text(value)))
+            .collect(),
+    ))
+}
@@ -0,0 +1,101 @@
+//! Interceptors: host code that wraps every capability operation.
+//!
+//! An [`EffectInterceptor`] added with [`Interpreter::add_interceptor`] sees
+//! each call to a host capability (`Console`, `Fs`, `Net`, ...) before it runs
+//! and its result after, so a host can enforce a policy, serve answers from a
+//! cache, or inject faults without wrapping each capability implementation.
+//! Handlers of user-defined effects are Astra code and aren't intercepted.
+//!
+//! Interceptors nest in the order they were added: the first one's `before`
+//! runs first and its `after` runs last. One that answers a call in `before`
+//! skips the operation and every interceptor added after it; only the ones
+//! before it see the answer in `after`.
+
+use super::error::RuntimeError;
+use super::value::Value;
+use super::Interpreter;
+
+/// What an interceptor decides before an operation runs
+#[derive(Debug, Clone)]
+pub enum Intercept {
+    /// Run the operation with the arguments as the interceptor left them
+    Proceed,
+    /// Skip the operation and answer with this result; an `Err` vetoes the
+    /// call with that runtime error
+    Respond(Result<Value, RuntimeError>),
+}
+
+/// Host code run around every capability operation
+pub trait EffectInterceptor {
+    /// Called before `effect.method(args)` runs; may rewrite `args`, or
+    /// answer the call itself
+    fn before(&mut self, _effect: &str, _method: &str, _args: &mut Vec<Value>) -> Intercept {
+        Intercept::Proceed
+    }
+
+    /// Called with the result of `effect.method`; may replace it
+    fn after(
+        &mut self,
+        _effect: &str,
+        _method: &str,
+        result: Result<Value, RuntimeError>,
+    ) -> Result<Value, RuntimeError> {
+        result
+    }
+}
+
+/// The host effect a method receiver stands for, if any
+pub(super) fn host_effect(receiver: &Value) -> Option<String> {
+    match receiver {
+        Value::Capability(cap) => Some(cap.effect.clone()),
+        Value::WebSocket(_) => Some("Net".to_string()),
+        _ => None,
+    }
+}
+
+impl Interpreter {
+    /// Run `interceptor` around every capability operation from now on,
+    /// inside the interceptors added before it
+    pub fn add_interceptor(&mut self, interceptor: Box<dyn EffectInterceptor>) {
+        self.interceptors.push(interceptor);
+    }
+
+    /// Call `receiver.method(args)` on the host capability for `effect`
+    /// through the interceptors. They are detached while their hooks run,
+    /// and attached again for the operation, so effects it performs in turn
+    /// (like a `Serve` handler printing) are intercepted too.
+    pub(super) fn call_intercepted(
+        &mut self,
+        effect: &str,
+        receiver: &Value,
+        method: &str,
+        mut args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let mut interceptors = std::mem::take(&mut self.interceptors);
+        let mut entered = 0;
+        let mut answer = None;
+        for interceptor in interceptors.iter_mut() {
+            match interceptor.before(effect, method, &mut args) {
+                Intercept::Proceed => entered += 1,
+                Intercept::Respond(result) => {
+                    answer = Some(result);
+                    break;
+                }
+            }
+        }
+        let mut result = match answer {
+            Some(result) => result,
+            None => {
+                self.interceptors = interceptors;
+                let result = self.dispatch_method(receiver, method, args);
+                interceptors = std::mem::take(&mut self.interceptors);
+                result
+            }
+        };
+        for interceptor in interceptors[..entered].iter_mut().rev() {
+            result = interceptor.after(effect, method, result);
+        }
+        self.interceptors = interceptors;
+        result
+    }
+}
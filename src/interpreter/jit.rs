@@ -0,0 +1,743 @@
+//! JIT tier for hot numeric functions (feature `jit`)
+//!
+//! A function whose parameters and result are `Int`, `Float`, or `Bool`, that
+//! declares no effects, type parameters, or contracts, and whose body uses
+//! only arithmetic, comparisons, `let`, assignment, `if`, `while`, and calls
+//! to itself is compiled to native code with Cranelift once it has been called
+//! [`JIT_THRESHOLD`] times. Everything else runs in the interpreter, and so
+//! does any call whose native code hits a runtime error such as division by
+//! zero, so errors are reported exactly as without the JIT. Compiled
+//! recursion counts against the interpreter's call depth limit: code that
+//! nests deeper than the frames left reports a stack overflow instead of
+//! recursing natively until the host stack runs out.
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use cranelift_codegen::ir::condcodes::{FloatCC, IntCC};
+use cranelift_codegen::ir::{
+    self, types, AbiParam, Block as IrBlock, FuncRef, InstBuilder, MemFlags, UserFuncName,
+};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Module};
+
+use crate::parser::ast::*;
+
+use super::value::{ClosureBody, Value};
+
+/// Interpreted calls after which a compilable function is compiled
+pub const JIT_THRESHOLD: u32 = 100;
+
+/// Entry point of a compiled function: arguments as raw 64-bit words, the
+/// nested calls it may make, a flag the code sets when it stops early, and
+/// the result as a raw word
+type Trampoline = unsafe extern "C" fn(*const u64, u64, *mut u8) -> u64;
+
+/// Bail flag value for code that hit something only the interpreter handles
+const BAILED: i64 = 1;
+/// Bail flag value for code that ran out of call depth
+const TOO_DEEP: i64 = 2;
+
+/// How a call run natively ended
+pub enum Native {
+    Returned(Value),
+    /// Its recursion nested deeper than the depth it was given
+    TooDeep,
+}
+
+/// Types the JIT can hold in registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scalar {
+    Int,
+    Float,
+    Bool,
+    Unit,
+}
+
+impl Scalar {
+    /// The scalar a parameter or return type annotation names, if any
+    fn of(ty: &TypeExpr) -> Option<Self> {
+        match ty {
+            TypeExpr::Named { name, args, .. } if args.is_empty() => match name.as_str() {
+                "Int" => Some(Scalar::Int),
+                "Float" => Some(Scalar::Float),
+                "Bool" => Some(Scalar::Bool),
+                "Unit" => Some(Scalar::Unit),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn ir_type(self) -> ir::Type {
+        match self {
+            Scalar::Int => types::I64,
+            Scalar::Float => types::F64,
+            Scalar::Bool | Scalar::Unit => types::I8,
+        }
+    }
+}
+
+/// Name and scalar types of a compilable function
+#[derive(Debug, Clone)]
+struct FnSignature {
+    name: String,
+    params: Vec<(String, Scalar)>,
+    result: Scalar,
+}
+
+impl FnSignature {
+    /// The signature of `fn_def` if its interface is one the JIT can compile
+    fn of(fn_def: &FnDef) -> Option<Self> {
+        if fn_def.is_async
+            || !fn_def.type_params.is_empty()
+            || !fn_def.effects.is_empty()
+            || !fn_def.requires.is_empty()
+            || !fn_def.ensures.is_empty()
+        {
+            return None;
+        }
+        let mut params = Vec::new();
+        for param in &fn_def.params {
+            match Scalar::of(&param.ty) {
+                Some(ty) if ty != Scalar::Unit && param.pattern.is_none() => {
+                    params.push((param.name.clone(), ty));
+                }
+                _ => return None,
+            }
+        }
+        let result = Scalar::of(fn_def.return_type.as_ref()?)?;
+        Some(Self {
+            name: fn_def.name.clone(),
+            params,
+            result,
+        })
+    }
+}
+
+enum State {
+    /// Interpreted calls so far
+    Counting(u32),
+    Compiled(Trampoline),
+    /// The body is outside the compilable subset
+    Rejected,
+}
+
+struct Entry {
+    /// Keeps the body, and so the key it is registered under, alive
+    body: Rc<ClosureBody>,
+    signature: FnSignature,
+    state: State,
+}
+
+/// Call counters and compiled code for the functions of one interpreter
+pub struct Jit {
+    /// Created on the first compilation; `None` until then or if the host
+    /// is unsupported
+    module: Option<JITModule>,
+    functions: HashMap<*const ClosureBody, Entry>,
+    threshold: u32,
+}
+
+impl Jit {
+    pub fn new() -> Self {
+        Self {
+            module: None,
+            functions: HashMap::new(),
+            threshold: JIT_THRESHOLD,
+        }
+    }
+
+    /// Compile functions after `calls` interpreted calls instead of
+    /// [`JIT_THRESHOLD`]
+    pub fn set_threshold(&mut self, calls: u32) {
+        self.threshold = calls.max(1);
+    }
+
+    /// Track calls to the function defined by `fn_def` if its signature is
+    /// one the JIT can compile
+    pub fn register(&mut self, body: &Rc<ClosureBody>, fn_def: &FnDef) {
+        if let Some(signature) = FnSignature::of(fn_def) {
+            self.functions
+                .entry(Rc::as_ptr(body))
+                .or_insert_with(|| Entry {
+                    body: body.clone(),
+                    signature,
+                    state: State::Counting(0),
+                });
+        }
+    }
+
+    /// Names of the functions compiled so far, sorted
+    pub fn compiled(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .functions
+            .values()
+            .filter(|entry| matches!(entry.state, State::Compiled(_)))
+            .map(|entry| entry.signature.name.as_str())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Run a call natively with room for `depth` nested calls including this
+    /// one, compiling the function once it is hot. `None` means the
+    /// interpreter has to run the call.
+    pub fn call(&mut self, body: &Rc<ClosureBody>, args: &[Value], depth: usize) -> Option<Native> {
+        let entry = self.functions.get_mut(&Rc::as_ptr(body))?;
+        if let State::Counting(calls) = &mut entry.state {
+            *calls += 1;
+            if *calls < self.threshold {
+                return None;
+            }
+            let module = match &mut self.module {
+                Some(module) => Some(module),
+                module @ None => {
+                    *module = new_module();
+                    module.as_mut()
+                }
+            };
+            entry.state = module
+                .and_then(|module| compile(module, &entry.signature, &entry.body.block))
+                .map_or(State::Rejected, State::Compiled);
+        }
+        let State::Compiled(code) = entry.state else {
+            return None;
+        };
+
+        let mut raw = Vec::with_capacity(args.len());
+        for ((_, ty), arg) in entry.signature.params.iter().zip(args) {
+            raw.push(match (ty, arg) {
+                (Scalar::Int, Value::Int(n)) => *n as u64,
+                (Scalar::Float, Value::Float(f)) => f.to_bits(),
+                (Scalar::Bool, Value::Bool(b)) => *b as u64,
+                _ => return None,
+            });
+        }
+        let mut bailed = 0u8;
+        // SAFETY: the code was compiled for exactly these parameter types,
+        // reads one word per parameter, and `module` lives as long as `self`
+        let result = unsafe { code(raw.as_ptr(), depth as u64, &mut bailed) };
+        match bailed as i64 {
+            0 => {}
+            TOO_DEEP => return Some(Native::TooDeep),
+            _ => return None,
+        }
+        Some(Native::Returned(match entry.signature.result {
+            Scalar::Int => Value::Int(result as i64),
+            Scalar::Float => Value::Float(f64::from_bits(result)),
+            Scalar::Bool => Value::Bool(result != 0),
+            Scalar::Unit => Value::Unit,
+        }))
+    }
+}
+
+impl Default for Jit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A JIT module for the host, or `None` if Cranelift doesn't support it
+fn new_module() -> Option<JITModule> {
+    let mut flags = settings::builder();
+    flags.set("use_colocated_libcalls", "false").ok()?;
+    flags.set("is_pic", "false").ok()?;
+    flags.set("opt_level", "speed").ok()?;
+    let isa = cranelift_native::builder()
+        .ok()?
+        .finish(settings::Flags::new(flags))
+        .ok()?;
+    Some(JITModule::new(JITBuilder::with_isa(
+        isa,
+        default_libcall_names(),
+    )))
+}
+
+/// Compile a function body and the trampoline that calls it, or `None` if
+/// the body is outside the compilable subset
+fn compile(module: &mut JITModule, signature: &FnSignature, body: &Block) -> Option<Trampoline> {
+    let pointer = module.target_config().pointer_type();
+    let mut ctx = module.make_context();
+    let mut builder_ctx = FunctionBuilderContext::new();
+
+    // The function itself, with native parameter types plus the depth left
+    // and the bail flag
+    let mut sig = module.make_signature();
+    for (_, ty) in &signature.params {
+        sig.params.push(AbiParam::new(ty.ir_type()));
+    }
+    sig.params.push(AbiParam::new(types::I64));
+    sig.params.push(AbiParam::new(pointer));
+    sig.returns.push(AbiParam::new(signature.result.ir_type()));
+    let func_id = module.declare_anonymous_function(&sig).ok()?;
+    ctx.func.signature = sig;
+    ctx.func.name = UserFuncName::user(0, func_id.as_u32());
+    let translated = {
+        let builder = FunctionBuilder::new(&mut ctx.func, &mut builder_ctx);
+        let this = module.declare_func_in_func(func_id, builder.func);
+        Translator::new(builder, this, signature).translate(body)
+    };
+    if translated.is_none() {
+        module.clear_context(&mut ctx);
+        return None;
+    }
+    module.define_function(func_id, &mut ctx).ok()?;
+    module.clear_context(&mut ctx);
+
+    // The trampoline, unpacking raw words into typed arguments
+    let mut sig = module.make_signature();
+    sig.params.push(AbiParam::new(pointer));
+    sig.params.push(AbiParam::new(types::I64));
+    sig.params.push(AbiParam::new(pointer));
+    sig.returns.push(AbiParam::new(types::I64));
+    let trampoline_id = module.declare_anonymous_function(&sig).ok()?;
+    ctx.func.signature = sig;
+    ctx.func.name = UserFuncName::user(0, trampoline_id.as_u32());
+    {
+        let mut builder = FunctionBuilder::new(&mut ctx.func, &mut builder_ctx);
+        let callee = module.declare_func_in_func(func_id, builder.func);
+        let block = builder.create_block();
+        builder.append_block_params_for_function_params(block);
+        builder.switch_to_block(block);
+        let (words, depth, bail) = (
+            builder.block_params(block)[0],
+            builder.block_params(block)[1],
+            builder.block_params(block)[2],
+        );
+        let mut args = Vec::new();
+        for (index, (_, ty)) in signature.params.iter().enumerate() {
+            let word = builder
+                .ins()
+                .load(types::I64, MemFlags::trusted(), words, index as i32 * 8);
+            args.push(match ty {
+                Scalar::Float => builder.ins().bitcast(types::F64, MemFlags::new(), word),
+                Scalar::Bool | Scalar::Unit => builder.ins().ireduce(types::I8, word),
+                Scalar::Int => word,
+            });
+        }
+        args.push(depth);
+        args.push(bail);
+        let call = builder.ins().call(callee, &args);
+        let result = builder.inst_results(call)[0];
+        let word = match signature.result {
+            Scalar::Float => builder.ins().bitcast(types::I64, MemFlags::new(), result),
+            Scalar::Bool | Scalar::Unit => builder.ins().uextend(types::I64, result),
+            Scalar::Int => result,
+        };
+        builder.ins().return_(&[word]);
+        builder.seal_all_blocks();
+        builder.finalize();
+    }
+    module.define_function(trampoline_id, &mut ctx).ok()?;
+    module.clear_context(&mut ctx);
+    module.finalize_definitions().ok()?;
+
+    let code = module.get_finalized_function(trampoline_id);
+    // SAFETY: the trampoline was declared with exactly this signature
+    Some(unsafe { std::mem::transmute::<*const u8, Trampoline>(code) })
+}
+
+/// Translates one function body to Cranelift IR
+struct Translator<'a> {
+    builder: FunctionBuilder<'a>,
+    /// The function being compiled, for self-calls
+    this: FuncRef,
+    signature: &'a FnSignature,
+    /// Loop head that self tail calls jump back to, with one parameter per
+    /// function parameter
+    head: IrBlock,
+    /// Block that sets the bail flag and returns
+    bail: IrBlock,
+    /// Block that flags running out of call depth and returns
+    too_deep: IrBlock,
+    /// Block that returns after a self-call stopped early, keeping its flag
+    unwind: IrBlock,
+    bail_flag: ir::Value,
+    /// Nested calls this call may still make, itself included
+    depth: ir::Value,
+    scopes: Vec<HashMap<String, (Variable, Scalar)>>,
+    /// Indices of the variables declared with `let mut`, the only ones that
+    /// can be assigned
+    mutable: HashSet<u32>,
+    variables: u32,
+}
+
+impl<'a> Translator<'a> {
+    fn new(mut builder: FunctionBuilder<'a>, this: FuncRef, signature: &'a FnSignature) -> Self {
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        let params = builder.block_params(entry).to_vec();
+        let depth = params[signature.params.len()];
+        let bail_flag = params[signature.params.len() + 1];
+
+        let head = builder.create_block();
+        for (_, ty) in &signature.params {
+            builder.append_block_param(head, ty.ir_type());
+        }
+        builder.ins().jump(head, &params[..signature.params.len()]);
+
+        let [bail, too_deep] = [BAILED, TOO_DEEP].map(|flag| {
+            let block = builder.create_block();
+            builder.switch_to_block(block);
+            let flag = builder.ins().iconst(types::I8, flag);
+            builder.ins().store(MemFlags::trusted(), flag, bail_flag, 0);
+            let zero = Self::zero(&mut builder, signature.result);
+            builder.ins().return_(&[zero]);
+            block
+        });
+
+        let unwind = builder.create_block();
+        builder.switch_to_block(unwind);
+        let zero = Self::zero(&mut builder, signature.result);
+        builder.ins().return_(&[zero]);
+
+        Self {
+            builder,
+            this,
+            signature,
+            head,
+            bail,
+            too_deep,
+            unwind,
+            bail_flag,
+            depth,
+            scopes: Vec::new(),
+            mutable: HashSet::new(),
+            variables: 0,
+        }
+    }
+
+    fn translate(mut self, body: &Block) -> Option<()> {
+        self.builder.switch_to_block(self.head);
+        let args = self.builder.block_params(self.head).to_vec();
+        let mut scope = HashMap::new();
+        for ((name, ty), arg) in self.signature.params.iter().zip(args) {
+            let var = self.variable(*ty);
+            self.builder.def_var(var, arg);
+            scope.insert(name.clone(), (var, *ty));
+        }
+        self.scopes.push(scope);
+        let (value, ty) = self.block(body, true)?;
+        if ty != self.signature.result {
+            return None;
+        }
+        self.builder.ins().return_(&[value]);
+        self.builder.seal_all_blocks();
+        self.builder.finalize();
+        Some(())
+    }
+
+    fn zero(builder: &mut FunctionBuilder, ty: Scalar) -> ir::Value {
+        match ty {
+            Scalar::Int => builder.ins().iconst(types::I64, 0),
+            Scalar::Float => builder.ins().f64const(0.0),
+            Scalar::Bool | Scalar::Unit => builder.ins().iconst(types::I8, 0),
+        }
+    }
+
+    fn unit(&mut self) -> (ir::Value, Scalar) {
+        (Self::zero(&mut self.builder, Scalar::Unit), Scalar::Unit)
+    }
+
+    fn variable(&mut self, ty: Scalar) -> Variable {
+        let var = Variable::from_u32(self.variables);
+        self.variables += 1;
+        self.builder.declare_var(var, ty.ir_type());
+        var
+    }
+
+    fn lookup(&self, name: &str) -> Option<(Variable, Scalar)> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    /// Continue only if `cond` is false, bailing out otherwise
+    fn bail_if(&mut self, cond: ir::Value) {
+        let next = self.builder.create_block();
+        self.builder.ins().brif(cond, self.bail, &[], next, &[]);
+        self.builder.switch_to_block(next);
+    }
+
+    fn block(&mut self, block: &Block, tail: bool) -> Option<(ir::Value, Scalar)> {
+        self.scopes.push(HashMap::new());
+        for stmt in &block.stmts {
+            self.stmt(stmt)?;
+        }
+        let result = match &block.expr {
+            Some(expr) => self.expr(expr, tail)?,
+            None => self.unit(),
+        };
+        self.scopes.pop();
+        Some(result)
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Option<()> {
+        match stmt {
+            Stmt::Let {
+                name,
+                mutable,
+                value,
+                ..
+            } => {
+                let (value, ty) = self.expr(value, false)?;
+                let var = self.variable(ty);
+                self.builder.def_var(var, value);
+                if *mutable {
+                    self.mutable.insert(var.as_u32());
+                }
+                self.scopes.last_mut()?.insert(name.clone(), (var, ty));
+            }
+            Stmt::Assign { target, value, .. } => {
+                let Expr::Ident { name, .. } = target.as_ref() else {
+                    return None;
+                };
+                let (var, ty) = self.lookup(name)?;
+                let (value, value_ty) = self.expr(value, false)?;
+                // Assigning a plain `let` is a runtime error the interpreter reports
+                if value_ty != ty || !self.mutable.contains(&var.as_u32()) {
+                    return None;
+                }
+                self.builder.def_var(var, value);
+            }
+            Stmt::Expr { expr, .. } => {
+                self.expr(expr, false)?;
+            }
+            Stmt::LetPattern { .. } | Stmt::Return { .. } => return None,
+        }
+        Some(())
+    }
+
+    fn expr(&mut self, expr: &Expr, tail: bool) -> Option<(ir::Value, Scalar)> {
+        match expr {
+            Expr::IntLit { value, .. } => {
+                Some((self.builder.ins().iconst(types::I64, *value), Scalar::Int))
+            }
+            Expr::FloatLit { value, .. } => {
+                Some((self.builder.ins().f64const(*value), Scalar::Float))
+            }
+            Expr::BoolLit { value, .. } => Some((
+                self.builder.ins().iconst(types::I8, *value as i64),
+                Scalar::Bool,
+            )),
+            Expr::UnitLit { .. } => Some(self.unit()),
+            Expr::Ident { name, .. } => {
+                let (var, ty) = self.lookup(name)?;
+                Some((self.builder.use_var(var), ty))
+            }
+            Expr::Unary { op, expr, .. } => {
+                let (value, ty) = self.expr(expr, false)?;
+                let ins = self.builder.ins();
+                match (op, ty) {
+                    (UnaryOp::Neg, Scalar::Int) => Some((ins.ineg(value), ty)),
+                    (UnaryOp::Neg, Scalar::Float) => Some((ins.fneg(value), ty)),
+                    (UnaryOp::Not, Scalar::Bool) => Some((ins.bxor_imm(value, 1), ty)),
+                    _ => None,
+                }
+            }
+            Expr::Binary {
+                op, left, right, ..
+            } => {
+                let (left, left_ty) = self.expr(left, false)?;
+                let (right, right_ty) = self.expr(right, false)?;
+                if left_ty != right_ty {
+                    return None;
+                }
+                self.binary(*op, left, right, left_ty)
+            }
+            Expr::If {
+                cond,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let (cond, Scalar::Bool) = self.expr(cond, false)? else {
+                    return None;
+                };
+                let then_block = self.builder.create_block();
+                let else_block = self.builder.create_block();
+                let merge = self.builder.create_block();
+                self.builder
+                    .ins()
+                    .brif(cond, then_block, &[], else_block, &[]);
+
+                self.builder.switch_to_block(then_block);
+                let (then_value, ty) = self.block(then_branch, tail)?;
+                let ty = if else_branch.is_some() {
+                    ty
+                } else {
+                    Scalar::Unit
+                };
+                let then_value = if ty == Scalar::Unit {
+                    self.unit().0
+                } else {
+                    then_value
+                };
+                self.builder.append_block_param(merge, ty.ir_type());
+                self.builder.ins().jump(merge, &[then_value]);
+
+                self.builder.switch_to_block(else_block);
+                let else_value = match else_branch {
+                    Some(else_expr) => {
+                        let (value, else_ty) = self.expr(else_expr, tail)?;
+                        if else_ty != ty {
+                            return None;
+                        }
+                        value
+                    }
+                    None => self.unit().0,
+                };
+                self.builder.ins().jump(merge, &[else_value]);
+
+                self.builder.switch_to_block(merge);
+                Some((self.builder.block_params(merge)[0], ty))
+            }
+            Expr::Block { block, .. } => self.block(block, tail),
+            Expr::While { cond, body, .. } => {
+                let head = self.builder.create_block();
+                let body_block = self.builder.create_block();
+                let exit = self.builder.create_block();
+                self.builder.ins().jump(head, &[]);
+
+                self.builder.switch_to_block(head);
+                let (cond, Scalar::Bool) = self.expr(cond, false)? else {
+                    return None;
+                };
+                self.builder.ins().brif(cond, body_block, &[], exit, &[]);
+
+                self.builder.switch_to_block(body_block);
+                self.block(body, false)?;
+                self.builder.ins().jump(head, &[]);
+
+                self.builder.switch_to_block(exit);
+                Some(self.unit())
+            }
+            Expr::Call { func, args, .. } => {
+                let Expr::Ident { name, .. } = func.as_ref() else {
+                    return None;
+                };
+                if *name != self.signature.name
+                    || self.lookup(name).is_some()
+                    || args.len() != self.signature.params.len()
+                {
+                    return None;
+                }
+                let mut values = Vec::with_capacity(args.len() + 1);
+                for (arg, (_, ty)) in args.iter().zip(&self.signature.params) {
+                    let (value, arg_ty) = self.expr(arg, false)?;
+                    if arg_ty != *ty {
+                        return None;
+                    }
+                    values.push(value);
+                }
+                let result = self.signature.result;
+                if tail {
+                    // Self tail calls loop instead of growing the stack
+                    self.builder.ins().jump(self.head, &values);
+                    let unreachable = self.builder.create_block();
+                    self.builder.switch_to_block(unreachable);
+                    return Some((Self::zero(&mut self.builder, result), result));
+                }
+                // The callee gets one frame less, and a call with none left
+                // is a stack overflow
+                let depth = self.builder.ins().iadd_imm(self.depth, -1);
+                let next = self.builder.create_block();
+                self.builder
+                    .ins()
+                    .brif(depth, next, &[], self.too_deep, &[]);
+                self.builder.switch_to_block(next);
+                values.push(depth);
+                values.push(self.bail_flag);
+                let call = self.builder.ins().call(self.this, &values);
+                let value = self.builder.inst_results(call)[0];
+                let stopped =
+                    self.builder
+                        .ins()
+                        .load(types::I8, MemFlags::trusted(), self.bail_flag, 0);
+                let next = self.builder.create_block();
+                self.builder
+                    .ins()
+                    .brif(stopped, self.unwind, &[], next, &[]);
+                self.builder.switch_to_block(next);
+                Some((value, result))
+            }
+            _ => None,
+        }
+    }
+
+    fn binary(
+        &mut self,
+        op: BinaryOp,
+        left: ir::Value,
+        right: ir::Value,
+        ty: Scalar,
+    ) -> Option<(ir::Value, Scalar)> {
+        let int_cc = |op| match op {
+            BinaryOp::Eq => Some(IntCC::Equal),
+            BinaryOp::Ne => Some(IntCC::NotEqual),
+            BinaryOp::Lt => Some(IntCC::SignedLessThan),
+            BinaryOp::Le => Some(IntCC::SignedLessThanOrEqual),
+            BinaryOp::Gt => Some(IntCC::SignedGreaterThan),
+            BinaryOp::Ge => Some(IntCC::SignedGreaterThanOrEqual),
+            _ => None,
+        };
+        match (ty, op) {
+            (Scalar::Int, BinaryOp::Add) => Some((self.builder.ins().iadd(left, right), ty)),
+            (Scalar::Int, BinaryOp::Sub) => Some((self.builder.ins().isub(left, right), ty)),
+            (Scalar::Int, BinaryOp::Mul) => Some((self.builder.ins().imul(left, right), ty)),
+            (Scalar::Int, BinaryOp::Div | BinaryOp::Mod) => {
+                // Division by zero is an interpreter error and `MIN / -1`
+                // overflows; both trap in native code, so bail out first
+                let zero = self.builder.ins().icmp_imm(IntCC::Equal, right, 0);
+                self.bail_if(zero);
+                let min = self.builder.ins().icmp_imm(IntCC::Equal, left, i64::MIN);
+                let minus_one = self.builder.ins().icmp_imm(IntCC::Equal, right, -1);
+                let overflow = self.builder.ins().band(min, minus_one);
+                self.bail_if(overflow);
+                let value = if op == BinaryOp::Div {
+                    self.builder.ins().sdiv(left, right)
+                } else {
+                    self.builder.ins().srem(left, right)
+                };
+                Some((value, ty))
+            }
+            (Scalar::Int, _) => {
+                let cc = int_cc(op)?;
+                Some((self.builder.ins().icmp(cc, left, right), Scalar::Bool))
+            }
+            (Scalar::Float, BinaryOp::Add) => Some((self.builder.ins().fadd(left, right), ty)),
+            (Scalar::Float, BinaryOp::Sub) => Some((self.builder.ins().fsub(left, right), ty)),
+            (Scalar::Float, BinaryOp::Mul) => Some((self.builder.ins().fmul(left, right), ty)),
+            (Scalar::Float, BinaryOp::Div) => {
+                let zero = self.builder.ins().f64const(0.0);
+                let is_zero = self.builder.ins().fcmp(FloatCC::Equal, right, zero);
+                self.bail_if(is_zero);
+                Some((self.builder.ins().fdiv(left, right), ty))
+            }
+            (Scalar::Float, _) => {
+                let cc = match op {
+                    BinaryOp::Eq => FloatCC::Equal,
+                    BinaryOp::Ne => FloatCC::NotEqual,
+                    BinaryOp::Lt => FloatCC::LessThan,
+                    BinaryOp::Le => FloatCC::LessThanOrEqual,
+                    BinaryOp::Gt => FloatCC::GreaterThan,
+                    BinaryOp::Ge => FloatCC::GreaterThanOrEqual,
+                    _ => return None,
+                };
+                Some((self.builder.ins().fcmp(cc, left, right), Scalar::Bool))
+            }
+            (Scalar::Bool, BinaryOp::And) => Some((self.builder.ins().band(left, right), ty)),
+            (Scalar::Bool, BinaryOp::Or) => Some((self.builder.ins().bor(left, right), ty)),
+            (Scalar::Bool, BinaryOp::Eq | BinaryOp::Ne) => {
+                let cc = int_cc(op)?;
+                Some((self.builder.ins().icmp(cc, left, right), ty))
+            }
+            _ => None,
+        }
+    }
+}
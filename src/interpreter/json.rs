@@ -1,7 +1,7 @@
 //! JSON parsing and stringifying for Astra values.
 
 use super::error::RuntimeError;
-use super::value::{format_value, sorted_map_from, Value};
+use super::value::{format_float, format_value, sorted_map_from, with_ref_contents, Value};
 
 /// Parse a JSON string into an Astra Value
 pub(super) fn json_parse_value(input: &str) -> Result<Value, RuntimeError> {
@@ -21,7 +21,7 @@ pub(super) fn json_parse_value(input: &str) -> Result<Value, RuntimeError> {
 }
 
 /// Stringify an Astra value to a JSON string
-pub(super) fn json_stringify_value(value: &Value) -> String {
+pub fn json_stringify_value(value: &Value) -> String {
     match value {
         Value::Unit => "null".to_string(),
         Value::Int(n) => n.to_string(),
@@ -29,15 +29,12 @@ pub(super) fn json_stringify_value(value: &Value) -> String {
             if f.is_infinite() || f.is_nan() {
                 "null".to_string()
             } else {
-                let s = f.to_string();
-                // Ensure float representation
-                if s.contains('.') || s.contains('e') || s.contains('E') {
-                    s
-                } else {
-                    format!("{}.0", s)
-                }
+                format_float(*f)
             }
         }
+        Value::Decimal(d) => super::decimal::format_decimal(d),
+        Value::Duration(millis) | Value::Deadline(millis) => millis.to_string(),
+        Value::Bytes(bytes) => format!("\"{}\"", super::encoding::base64_encode(bytes)),
         Value::Bool(b) => b.to_string(),
         Value::Text(s) => {
             let escaped = s
@@ -110,9 +107,13 @@ pub(super) fn json_stringify_value(value: &Value) -> String {
                 format!("\"{}\"", name)
             }
         }
-        Value::Closure { .. } | Value::VariantConstructor { .. } | Value::Future { .. } => {
-            "null".to_string()
-        }
+        Value::Ref(cell) => with_ref_contents(cell, json_stringify_value, "null".to_string()),
+        Value::Closure { .. }
+        | Value::VariantConstructor { .. }
+        | Value::Future { .. }
+        | Value::WebSocket(_)
+        | Value::Capability(_)
+        | Value::Namespace(_) => "null".to_string(),
     }
 }
 
@@ -3,15 +3,30 @@
 //! Contains all method call handling: effect methods, value methods,
 //! higher-order methods, and trait dispatch.
 
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
 
+use indexmap::IndexMap;
+
+use super::audit::url_host;
+use super::compression::{gunzip, gzip};
+use super::decimal::decimal_method;
+use super::duration::{duration_constructor, duration_method};
+use super::encoding::{base64_decode, encoder_input, hex_decode, hex_encode};
 use super::error::{check_arity, RuntimeError};
+use super::http::{request_value, response_from_value, response_value, text_response};
+use super::intercept::host_effect;
 use super::regex::{regex_find_all, regex_is_match, regex_match, regex_replace, regex_split};
+use super::rpc;
 use super::value::{
-    compare_values, format_value, map_get, map_remove, map_set, set_add, set_contains, set_remove,
-    sorted_map_from, sorted_set_from, values_equal, Value,
+    compare_values, format_fixed, format_precision, format_value, map_get, map_remove, map_search,
+    map_set, set_add, set_contains, set_remove, sorted_map_from, sorted_set_from, values_equal,
+    Value,
+};
+use super::{
+    AttenuatedFs, CapabilityValue, FsCapability, HttpRequest, Interpreter, ProgressEvent, Route,
 };
-use super::Interpreter;
+use crate::parser::ast::BinaryOp;
 
 impl Interpreter {
     /// Call a method on a receiver (for effects like Console.println)
@@ -21,35 +36,107 @@ impl Interpreter {
         method: &str,
         args: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
-        // Check if receiver is an effect identifier
+        if !self.interceptors.is_empty() {
+            if let Some(effect) = host_effect(receiver) {
+                return self.call_intercepted(&effect, receiver, method, args);
+            }
+        }
+        self.dispatch_method(receiver, method, args)
+    }
+
+    /// Call an operation of the host capability for `effect`, like the
+    /// `read_file` builtin does
+    pub(super) fn call_effect(
+        &mut self,
+        effect: &str,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        self.call_method(
+            &Value::Capability(CapabilityValue::host(effect)),
+            method,
+            args,
+        )
+    }
+
+    /// Call a method on a receiver, without interceptors
+    pub(super) fn dispatch_method(
+        &mut self,
+        receiver: &Value,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
         match receiver {
-            Value::Text(name) if name == "Console.println" || name.starts_with("Console") => {
-                self.call_console_method(method, args)
-            }
-            Value::Text(name) if name.starts_with("Fs") => self.call_fs_method(method, args),
-            Value::Text(name) if name.starts_with("Net") => self.call_net_method(method, args),
-            Value::Text(name) if name.starts_with("Clock") => self.call_clock_method(method, args),
-            Value::Text(name) if name.starts_with("Rand") => self.call_rand_method(method, args),
-            Value::Text(name) if name.starts_with("Env") => self.call_env_method(method, args),
+            Value::WebSocket(id) => self.call_websocket_method(*id, method, args),
+            Value::Capability(cap) => self.call_capability_method(cap, method, args),
+            Value::Namespace(name) => self.call_namespace_method(name, method, args),
+            // Check if this is an Option/Result method
+            _ => self.call_value_method(receiver, method, args),
+        }
+    }
+
+    /// Call an operation of a builtin namespace like `Map`, or of a
+    /// user-defined effect
+    fn call_namespace_method(
+        &mut self,
+        name: &str,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match name {
             // Map/Set static constructors
-            Value::Text(name) if name == "Map" => self.call_map_static_method(method, args),
-            Value::Text(name) if name == "Set" => self.call_set_static_method(method, args),
-            // For direct calls like Console.println()
-            _ => {
-                // Try to interpret receiver as effect name
-                if let Value::Text(ref s) = receiver {
-                    if s == "Console" {
-                        return self.call_console_method(method, args);
-                    }
-                    // P6.2: User-defined effect dispatch
-                    // If the receiver name matches a user-defined effect, look for a handler
-                    if self.effect_defs.contains_key(s) {
-                        return self.call_user_effect_method(s, method, args);
-                    }
-                }
-                // Check if this is an Option/Result method
-                self.call_value_method(receiver, method, args)
-            }
+            "Map" => self.call_map_static_method(method, args),
+            "Set" => self.call_set_static_method(method, args),
+            "Duration" => duration_constructor(method, &args),
+            "Args" => self.call_args_method(method, args),
+            "Ref" => match (method, args.as_slice()) {
+                ("new", [value]) => Ok(Value::Ref(Rc::new(RefCell::new(value.clone())))),
+                ("new", _) => Err(RuntimeError::arity_mismatch(1, args.len())),
+                _ => Err(RuntimeError::unknown_method("Ref", method)),
+            },
+            "Bytes" => match (method, args.as_slice()) {
+                ("from_list", [Value::List(items)]) => items
+                    .iter()
+                    .map(|item| match item {
+                        Value::Int(n) if (0..=255).contains(n) => Ok(*n as u8),
+                        other => Err(RuntimeError::type_mismatch(
+                            "Int between 0 and 255",
+                            &format_value(other),
+                        )),
+                    })
+                    .collect::<Result<Vec<u8>, _>>()
+                    .map(Value::Bytes),
+                ("from_list", _) => Err(RuntimeError::type_mismatch("List[Int]", "other")),
+                _ => Err(RuntimeError::unknown_method("Bytes", method)),
+            },
+            // P6.2: User-defined effect dispatch
+            _ if self.effect_defs.contains_key(name) => {
+                self.call_user_effect_method(name, method, args)
+            }
+            _ => Err(RuntimeError::unknown_method(name, method)),
+        }
+    }
+
+    /// Call an operation of the host capability for `effect`
+    fn call_host_method(
+        &mut self,
+        effect: &str,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match effect {
+            "Console" => self.call_console_method(method, args),
+            "Fs" => self.call_fs_method(method, args),
+            "Net" => self.call_net_method(method, args),
+            "Clock" => self.call_clock_method(method, args),
+            "Rand" => self.call_rand_method(method, args),
+            "Env" => self.call_env_method(method, args),
+            "Crypto" => self.call_crypto_method(method, args),
+            "Serve" => self.call_serve_method(method, args),
+            "Db" => self.call_db_method(method, args),
+            "Progress" => self.call_progress_method(method, args),
+            "Metrics" => self.call_metrics_method(method, args),
+            _ => Err(RuntimeError::unknown_method(effect, method)),
         }
     }
 
@@ -59,6 +146,7 @@ impl Interpreter {
         method: &str,
         args: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
+        self.count_effect("Console")?;
         let console = self
             .capabilities
             .console
@@ -91,26 +179,77 @@ impl Interpreter {
             }
             "read_line" => {
                 let line = console.read_line();
+                if let Some(failure) = console.script_failure() {
+                    return Err(RuntimeError::new("E4020", failure));
+                }
                 match line {
                     Some(s) => Ok(Value::Some(Box::new(Value::Text(s)))),
                     None => Ok(Value::None),
                 }
             }
+            "is_tty" => Ok(Value::Bool(console.is_tty())),
             _ => Err(RuntimeError::unknown_method("Console", method)),
         }
     }
 
+    /// Call a method on a capability value. `read_only()` and `scoped(dir)`
+    /// narrow an Fs capability; everything else is the effect's own method,
+    /// within what the capability allows.
+    fn call_capability_method(
+        &mut self,
+        cap: &CapabilityValue,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match (cap.effect.as_str(), method, args.as_slice()) {
+            ("Fs", "read_only", []) => Ok(Value::Capability(CapabilityValue {
+                read_only: true,
+                ..cap.clone()
+            })),
+            ("Fs", "scoped", [Value::Text(dir)]) => {
+                let mut scoped = cap.clone();
+                scoped.roots.push(dir.clone());
+                Ok(Value::Capability(scoped))
+            }
+            ("Fs", "read_only", _) => Err(RuntimeError::arity_mismatch(0, args.len())),
+            ("Fs", "scoped", [_]) => Err(RuntimeError::type_mismatch("Text", "other")),
+            ("Fs", "scoped", _) => Err(RuntimeError::arity_mismatch(1, args.len())),
+            ("Fs", _, _) => self.call_fs_method_within(cap, method, args),
+            (effect, _, _) => self.call_host_method(effect, method, args),
+        }
+    }
+
     /// Call a Fs effect method
     pub(super) fn call_fs_method(
-        &self,
+        &mut self,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        self.call_fs_method_within(&CapabilityValue::host("Fs"), method, args)
+    }
+
+    /// Call a Fs effect method through a capability value
+    fn call_fs_method_within(
+        &mut self,
+        grant: &CapabilityValue,
         method: &str,
         args: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
-        let fs = self
+        self.count_effect("Fs")?;
+        let path = match args.first() {
+            Some(Value::Text(path)) => Some(path.as_str()),
+            _ => None,
+        };
+        self.audit_effect("Fs", method, path);
+        let host = self
             .capabilities
             .fs
             .as_ref()
             .ok_or_else(|| RuntimeError::capability_not_available("Fs"))?;
+        let fs = AttenuatedFs {
+            host: host.as_ref(),
+            grant,
+        };
 
         match method {
             "read" => {
@@ -146,6 +285,35 @@ impl Interpreter {
                     Err(RuntimeError::type_mismatch("Text", "other"))
                 }
             }
+            "read_gzip" => match args.as_slice() {
+                [Value::Text(path)] => {
+                    let text = fs.read_bytes(path).and_then(|bytes| {
+                        let bytes = gunzip(&bytes)
+                            .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+                        String::from_utf8(bytes)
+                            .map_err(|_| format!("Failed to read '{}': not valid UTF-8", path))
+                    });
+                    Ok(match text {
+                        Ok(text) => Value::Ok(Box::new(Value::Text(text))),
+                        Err(e) => Value::Err(Box::new(Value::Text(e))),
+                    })
+                }
+                [_] => Err(RuntimeError::type_mismatch("Text", "other")),
+                _ => Err(RuntimeError::arity_mismatch(1, args.len())),
+            },
+            "write_gzip" => match args.as_slice() {
+                [Value::Text(path), content] => {
+                    Ok(match fs.write_bytes(path, &gzip(encoder_input(content)?)) {
+                        Ok(()) => Value::Ok(Box::new(Value::Unit)),
+                        Err(e) => Value::Err(Box::new(Value::Text(e))),
+                    })
+                }
+                [_, _] => Err(RuntimeError::type_mismatch(
+                    "(Text, Text or Bytes)",
+                    "other",
+                )),
+                _ => Err(RuntimeError::arity_mismatch(2, args.len())),
+            },
             _ => Err(RuntimeError::unknown_method("Fs", method)),
         }
     }
@@ -156,6 +324,12 @@ impl Interpreter {
         method: &str,
         args: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
+        self.count_effect("Net")?;
+        let host = match args.first() {
+            Some(Value::Text(url)) => Some(url_host(url)),
+            _ => None,
+        };
+        self.audit_effect("Net", method, host);
         let net = self
             .capabilities
             .net
@@ -189,6 +363,19 @@ impl Interpreter {
                     Err(RuntimeError::arity_mismatch(2, args.len()))
                 }
             }
+            "ws_connect" => match args.as_slice() {
+                [Value::Text(url)] => match net.ws_connect(url) {
+                    Ok(connection) => {
+                        let id = self.next_websocket;
+                        self.next_websocket += 1;
+                        self.websockets.insert(id, connection);
+                        Ok(Value::Ok(Box::new(Value::WebSocket(id))))
+                    }
+                    Err(e) => Ok(Value::Err(Box::new(Value::Text(e)))),
+                },
+                [_] => Err(RuntimeError::type_mismatch("Text", "other")),
+                _ => Err(RuntimeError::arity_mismatch(1, args.len())),
+            },
             "serve" => {
                 if args.len() != 2 {
                     return Err(RuntimeError::arity_mismatch(2, args.len()));
@@ -207,109 +394,42 @@ impl Interpreter {
                 eprintln!("Astra server listening on http://0.0.0.0:{}", port);
 
                 for mut request in server.incoming_requests() {
-                    let method_str = Value::Text(request.method().to_string().to_uppercase());
-                    let full_url = request.url().to_string();
-
-                    // Split path and query string
-                    let (path_str, query_map) = if let Some(qmark) = full_url.find('?') {
-                        let path = &full_url[..qmark];
-                        let qs = &full_url[qmark + 1..];
-                        let pairs: Vec<(Value, Value)> = qs
-                            .split('&')
-                            .filter(|s: &&str| !s.is_empty())
-                            .map(|pair: &str| {
-                                let mut parts = pair.splitn(2, '=');
-                                let key = super::url_decode(parts.next().unwrap_or(""));
-                                let val = super::url_decode(parts.next().unwrap_or(""));
-                                (Value::Text(key), Value::Text(val))
-                            })
-                            .collect();
-                        (path.to_string(), Value::Map(pairs))
-                    } else {
-                        (full_url.clone(), Value::Map(Vec::new()))
+                    let mut body = String::new();
+                    let _ = request.as_reader().read_to_string(&mut body);
+                    let http_request = HttpRequest {
+                        method: request.method().to_string().to_uppercase(),
+                        url: request.url().to_string(),
+                        headers: request
+                            .headers()
+                            .iter()
+                            .map(|h| (h.field.to_string(), h.value.as_str().to_string()))
+                            .collect(),
+                        body,
                     };
+                    let req_record = request_value(&http_request, Vec::new());
+                    let response = self
+                        .call_function(handler.clone(), vec![req_record])
+                        .and_then(|value| response_from_value(&value));
 
-                    // Extract headers
-                    let headers_map: Vec<(Value, Value)> = request
-                        .headers()
-                        .iter()
-                        .map(|h| {
-                            (
-                                Value::Text(h.field.to_string()),
-                                Value::Text(h.value.as_str().to_string()),
-                            )
-                        })
-                        .collect();
-
-                    // Read body
-                    let mut body_buf = String::new();
-                    let _ = request.as_reader().read_to_string(&mut body_buf);
-
-                    let req_record = Value::Record({
-                        let mut m = std::collections::HashMap::new();
-                        m.insert("method".to_string(), method_str);
-                        m.insert("path".to_string(), Value::Text(path_str));
-                        m.insert("body".to_string(), Value::Text(body_buf));
-                        m.insert("query".to_string(), query_map);
-                        m.insert("headers".to_string(), Value::Map(headers_map));
-                        m
-                    });
-
-                    let response_val = self.call_function(handler.clone(), vec![req_record]);
-
-                    match response_val {
-                        Ok(Value::Record(fields)) => {
-                            let status_code = match fields.get("status") {
-                                Some(Value::Int(s)) => *s as u16,
-                                _ => 200u16,
-                            };
-                            let body = match fields.get("body") {
-                                Some(Value::Text(b)) => b.clone(),
-                                _ => String::new(),
-                            };
-
-                            let mut response = tiny_http::Response::from_string(body)
-                                .with_status_code(tiny_http::StatusCode(status_code));
-
-                            if let Some(Value::Map(headers)) = fields.get("headers") {
-                                for (k, v) in headers {
-                                    if let (Value::Text(hk), Value::Text(hv)) = (k, v) {
-                                        if let Ok(header) = tiny_http::Header::from_bytes(
-                                            hk.as_bytes(),
-                                            hv.as_bytes(),
-                                        ) {
-                                            response.add_header(header);
-                                        }
-                                    }
-                                }
-                            }
-
-                            if let Err(e) = request.respond(response) {
-                                eprintln!("Failed to send response: {}", e);
-                            }
-                        }
-                        Ok(other) => {
-                            eprintln!("Handler returned non-record value: {:?}", other);
-                            let response = tiny_http::Response::from_string(
-                                "Internal Server Error: handler did not return a Response record",
-                            )
-                            .with_status_code(tiny_http::StatusCode(500));
-                            if let Err(e) = request.respond(response) {
-                                eprintln!("Failed to send error response: {}", e);
-                            }
-                        }
+                    let response = match response {
+                        Ok(response) => response,
                         Err(e) => {
                             eprintln!("Handler error: {}", e);
-                            let response = tiny_http::Response::from_string(format!(
-                                "Internal Server Error: {}",
-                                e
-                            ))
-                            .with_status_code(tiny_http::StatusCode(500));
-                            if let Err(e) = request.respond(response) {
-                                eprintln!("Failed to send error response: {}", e);
-                            }
+                            text_response(500, &format!("Internal Server Error: {}", e))
+                        }
+                    };
+                    let mut reply = tiny_http::Response::from_string(response.body)
+                        .with_status_code(tiny_http::StatusCode(response.status));
+                    for (name, value) in &response.headers {
+                        if let Ok(header) =
+                            tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes())
+                        {
+                            reply.add_header(header);
                         }
                     }
+                    if let Err(e) = request.respond(reply) {
+                        eprintln!("Failed to send response: {}", e);
+                    }
                 }
 
                 Ok(Value::Unit)
@@ -320,10 +440,12 @@ impl Interpreter {
 
     /// Call a Clock effect method
     pub(super) fn call_clock_method(
-        &self,
+        &mut self,
         method: &str,
         args: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
+        self.count_effect("Clock")?;
+        self.audit_effect("Clock", method, None);
         let clock = self
             .capabilities
             .clock
@@ -332,25 +454,44 @@ impl Interpreter {
 
         match method {
             "now" => Ok(Value::Int(clock.now())),
-            "sleep" => {
-                if let Some(Value::Int(millis)) = args.first() {
-                    clock.sleep(*millis as u64);
+            "sleep" => match args.first() {
+                Some(Value::Duration(millis)) | Some(Value::Int(millis)) => {
+                    clock.sleep((*millis).max(0) as u64);
                     Ok(Value::Unit)
-                } else {
-                    Err(RuntimeError::type_mismatch("Int", "other"))
                 }
-            }
+                _ => Err(RuntimeError::type_mismatch("Duration", "other")),
+            },
             "today" => Ok(Value::Text(clock.today())),
+            "deadline" => match args.first() {
+                Some(Value::Duration(millis)) => clock
+                    .now()
+                    .checked_add(*millis)
+                    .map(Value::Deadline)
+                    .ok_or_else(|| RuntimeError::arithmetic_overflow("duration", "deadline")),
+                _ => Err(RuntimeError::type_mismatch("Duration", "other")),
+            },
+            "remaining" => match args.first() {
+                Some(Value::Deadline(at)) => {
+                    Ok(Value::Duration(at.saturating_sub(clock.now()).max(0)))
+                }
+                _ => Err(RuntimeError::type_mismatch("Deadline", "other")),
+            },
+            "expired" => match args.first() {
+                Some(Value::Deadline(at)) => Ok(Value::Bool(clock.now() >= *at)),
+                _ => Err(RuntimeError::type_mismatch("Deadline", "other")),
+            },
             _ => Err(RuntimeError::unknown_method("Clock", method)),
         }
     }
 
     /// Call a Rand effect method
     pub(super) fn call_rand_method(
-        &self,
+        &mut self,
         method: &str,
         args: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
+        self.count_effect("Rand")?;
+        self.audit_effect("Rand", method, None);
         let rand = self
             .capabilities
             .rand
@@ -376,16 +517,44 @@ impl Interpreter {
                 let f = rand.float();
                 Ok(Value::Float(f))
             }
+            "uuid" => {
+                // Version 4 (random) with the RFC 4122 variant bits
+                let mut bytes = rand.bytes(16);
+                bytes[6] = (bytes[6] & 0x0f) | 0x40;
+                bytes[8] = (bytes[8] & 0x3f) | 0x80;
+                let hex = hex_encode(&bytes);
+                Ok(Value::Text(format!(
+                    "{}-{}-{}-{}-{}",
+                    &hex[0..8],
+                    &hex[8..12],
+                    &hex[12..16],
+                    &hex[16..20],
+                    &hex[20..32]
+                )))
+            }
+            "hex" => match args.first() {
+                Some(Value::Int(n)) if *n >= 0 => {
+                    let mut hex = hex_encode(&rand.bytes((*n as usize).div_ceil(2)));
+                    hex.truncate(*n as usize);
+                    Ok(Value::Text(hex))
+                }
+                Some(Value::Int(n)) => Err(RuntimeError::type_mismatch(
+                    "non-negative Int",
+                    &n.to_string(),
+                )),
+                _ => Err(RuntimeError::type_mismatch("Int", "other")),
+            },
             _ => Err(RuntimeError::unknown_method("Rand", method)),
         }
     }
 
     /// Call an Env effect method
     pub(super) fn call_env_method(
-        &self,
+        &mut self,
         method: &str,
         args: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
+        self.count_effect("Env")?;
         let env_cap = self
             .capabilities
             .env
@@ -411,6 +580,329 @@ impl Interpreter {
         }
     }
 
+    /// Call a Crypto effect method. Loading a key also uses the Env or Fs
+    /// capability it reads from.
+    pub(super) fn call_crypto_method(
+        &mut self,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        self.count_effect("Crypto")?;
+        if self.capabilities.crypto.is_none() {
+            return Err(RuntimeError::capability_not_available("Crypto"));
+        }
+        let key_source = match (method, args.as_slice()) {
+            ("key_from_env", [Value::Text(name), Value::Text(encoding)]) => {
+                self.count_effect("Env")?;
+                let env = self
+                    .capabilities
+                    .env
+                    .as_ref()
+                    .ok_or_else(|| RuntimeError::capability_not_available("Env"))?;
+                let source = format!("environment variable `{}`", name);
+                Some((
+                    env.get(name).ok_or(format!("{} is not set", source)),
+                    source,
+                    encoding,
+                ))
+            }
+            ("key_from_file", [Value::Text(path), Value::Text(encoding)]) => {
+                self.count_effect("Fs")?;
+                self.audit_effect("Crypto", method, Some(path));
+                let fs = self
+                    .capabilities
+                    .fs
+                    .as_ref()
+                    .ok_or_else(|| RuntimeError::capability_not_available("Fs"))?;
+                Some((fs.read(path), format!("key file `{}`", path), encoding))
+            }
+            ("key_from_env" | "key_from_file", [_, _]) => {
+                return Err(RuntimeError::type_mismatch("(Text, Text)", "other"))
+            }
+            ("key_from_env" | "key_from_file", _) => {
+                return Err(RuntimeError::arity_mismatch(2, args.len()))
+            }
+            _ => None,
+        };
+        if let Some((text, source, encoding)) = key_source {
+            return match text {
+                Ok(text) => decode_key(text.trim(), encoding, &source),
+                Err(e) => Ok(Value::Err(Box::new(Value::Text(e)))),
+            };
+        }
+
+        let crypto = self.capabilities.crypto.as_ref().expect("checked above");
+        let result = |result: Result<Vec<u8>, String>| match result {
+            Ok(bytes) => Value::Ok(Box::new(Value::Bytes(bytes))),
+            Err(e) => Value::Err(Box::new(Value::Text(e))),
+        };
+        match (method, args.as_slice()) {
+            ("hmac_sha256", [key, data]) => Ok(Value::Bytes(
+                crypto.hmac_sha256(encoder_input(key)?, encoder_input(data)?),
+            )),
+            ("public_key", [Value::Bytes(private_key)]) => {
+                Ok(result(crypto.public_key(private_key)))
+            }
+            ("sign", [Value::Bytes(private_key), data]) => {
+                Ok(result(crypto.sign(private_key, encoder_input(data)?)))
+            }
+            ("verify", [Value::Bytes(public_key), data, Value::Bytes(signature)]) => Ok(
+                Value::Bool(crypto.verify(public_key, encoder_input(data)?, signature)),
+            ),
+            ("public_key", [_]) | ("sign", [_, _]) | ("verify", [_, _, _]) => {
+                Err(RuntimeError::type_mismatch("Bytes", "other"))
+            }
+            ("hmac_sha256" | "sign", _) => Err(RuntimeError::arity_mismatch(2, args.len())),
+            ("public_key", _) => Err(RuntimeError::arity_mismatch(1, args.len())),
+            ("verify", _) => Err(RuntimeError::arity_mismatch(3, args.len())),
+            _ => Err(RuntimeError::unknown_method("Crypto", method)),
+        }
+    }
+
+    /// Send JSON-RPC calls to an endpoint with `Net.post`, one request or a
+    /// batch, and decode each call's result in order
+    pub(super) fn send_rpc(
+        &mut self,
+        endpoint: &str,
+        calls: Vec<(String, Value)>,
+        batch: bool,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        // An empty batch is invalid JSON-RPC, and there is nothing to send
+        if calls.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.count_effect("Net")?;
+        self.audit_effect("Net", "post", Some(url_host(endpoint)));
+        let net = self
+            .capabilities
+            .net
+            .as_ref()
+            .ok_or_else(|| RuntimeError::capability_not_available("Net"))?;
+        let mut ids = Vec::with_capacity(calls.len());
+        let mut requests = Vec::with_capacity(calls.len());
+        for (method, params) in &calls {
+            let id = self.next_rpc_id;
+            self.next_rpc_id += 1;
+            ids.push(id);
+            requests.push(rpc::request_json(id, method, params)?);
+        }
+        let body = if batch {
+            format!("[{}]", requests.join(","))
+        } else {
+            requests.remove(0)
+        };
+        Ok(match net.post(endpoint, &body) {
+            Ok(Value::Text(reply)) => rpc::decode_reply(&reply, &ids, batch),
+            Ok(other) => rpc::decode_reply(&format_value(&other), &ids, batch),
+            Err(e) => ids
+                .iter()
+                .map(|_| rpc::rpc_error(rpc::TRANSPORT_ERROR, e.clone()))
+                .collect(),
+        })
+    }
+
+    /// Call a method on a connection from `Net.ws_connect`. Each call counts
+    /// as a Net effect; a closed connection answers every call with an Err.
+    pub(super) fn call_websocket_method(
+        &mut self,
+        id: u64,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        self.count_effect("Net")?;
+        let result = match (method, args.as_slice()) {
+            ("send", [Value::Text(message)]) => self
+                .websockets
+                .get_mut(&id)
+                .ok_or_else(|| "connection closed".to_string())
+                .and_then(|connection| connection.send(message))
+                .map(|()| Value::Unit),
+            ("recv", [] | [Value::Duration(_)]) => {
+                let timeout = match args.first() {
+                    Some(Value::Duration(ms)) => Some((*ms).max(0) as u64),
+                    _ => None,
+                };
+                self.websockets
+                    .get_mut(&id)
+                    .ok_or_else(|| "connection closed".to_string())
+                    .and_then(|connection| connection.recv(timeout))
+                    .map(|message| match message {
+                        Some(text) => Value::Some(Box::new(Value::Text(text))),
+                        None => Value::None,
+                    })
+            }
+            ("close", []) => match self.websockets.remove(&id) {
+                Some(mut connection) => connection.close().map(|()| Value::Unit),
+                // Closing twice is harmless
+                None => Ok(Value::Unit),
+            },
+            ("send", [_]) => return Err(RuntimeError::type_mismatch("Text", "other")),
+            ("recv", [_]) => return Err(RuntimeError::type_mismatch("Duration", "other")),
+            ("send", _) => return Err(RuntimeError::arity_mismatch(1, args.len())),
+            ("recv", _) => return Err(RuntimeError::arity_mismatch(1, args.len())),
+            ("close", _) => return Err(RuntimeError::arity_mismatch(0, args.len())),
+            _ => return Err(RuntimeError::unknown_method("WebSocket", method)),
+        };
+        Ok(match result {
+            Ok(value) => Value::Ok(Box::new(value)),
+            Err(e) => Value::Err(Box::new(Value::Text(e))),
+        })
+    }
+
+    /// Call a Db effect method. Rows come back as records keyed by column
+    /// name; SQL errors are returned as `Err`, not raised.
+    pub(super) fn call_db_method(
+        &mut self,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        self.count_effect("Db")?;
+        let db = self
+            .capabilities
+            .db
+            .as_ref()
+            .ok_or_else(|| RuntimeError::capability_not_available("Db"))?;
+        let (sql, params) = match args.as_slice() {
+            [Value::Text(sql)] => (sql, &[][..]),
+            [Value::Text(sql), Value::List(params)] => (sql, params.as_slice()),
+            [_] | [_, _] => return Err(RuntimeError::type_mismatch("(Text, List)", "other")),
+            _ => return Err(RuntimeError::arity_mismatch(2, args.len())),
+        };
+        let result = match method {
+            "query" => db.query(sql, params).map(|rows| {
+                Value::List(
+                    rows.into_iter()
+                        .map(|row| Value::Record(row.into_iter().collect()))
+                        .collect(),
+                )
+            }),
+            "execute" => db.execute(sql, params).map(Value::Int),
+            _ => return Err(RuntimeError::unknown_method("Db", method)),
+        };
+        Ok(match result {
+            Ok(value) => Value::Ok(Box::new(value)),
+            Err(e) => Value::Err(Box::new(Value::Text(e))),
+        })
+    }
+
+    /// Call a Progress effect method. Starting a task finishes the one before
+    /// it; advancing or finishing when no task is running does nothing.
+    pub(super) fn call_progress_method(
+        &mut self,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        self.count_effect("Progress")?;
+        if self.capabilities.progress.is_none() {
+            return Err(RuntimeError::capability_not_available("Progress"));
+        }
+        let event = match (method, args.as_slice()) {
+            ("start", [Value::Text(task), Value::Int(total)]) if *total >= 0 => {
+                self.finish_progress_task();
+                self.progress_task = Some((task.clone(), *total, 0));
+                Some(ProgressEvent::Start {
+                    task: task.clone(),
+                    total: *total,
+                })
+            }
+            ("advance", [Value::Int(n)]) => {
+                self.progress_task.as_mut().map(|(task, total, done)| {
+                    *done = done.saturating_add(*n);
+                    ProgressEvent::Advance {
+                        task: task.clone(),
+                        done: *done,
+                        total: *total,
+                    }
+                })
+            }
+            ("finish", []) => {
+                self.finish_progress_task();
+                None
+            }
+            ("start", [Value::Text(_), Value::Int(total)]) => {
+                return Err(RuntimeError::type_mismatch(
+                    "a total of 0 or more",
+                    &total.to_string(),
+                ))
+            }
+            ("start", [_, _]) => return Err(RuntimeError::type_mismatch("(Text, Int)", "other")),
+            ("start", _) => return Err(RuntimeError::arity_mismatch(2, args.len())),
+            ("advance", [_]) => return Err(RuntimeError::type_mismatch("Int", "other")),
+            ("advance", _) => return Err(RuntimeError::arity_mismatch(1, args.len())),
+            ("finish", _) => return Err(RuntimeError::arity_mismatch(0, args.len())),
+            _ => return Err(RuntimeError::unknown_method("Progress", method)),
+        };
+        if let (Some(event), Some(progress)) = (event, &self.capabilities.progress) {
+            progress.report(&event);
+        }
+        Ok(Value::Unit)
+    }
+
+    /// Report the running task as finished, if there is one
+    fn finish_progress_task(&mut self) {
+        if let (Some((task, total, done)), Some(progress)) =
+            (self.progress_task.take(), &self.capabilities.progress)
+        {
+            progress.report(&ProgressEvent::Finish { task, done, total });
+        }
+    }
+
+    /// Call a Serve effect method: register a route, or answer a request
+    /// in-process with the routes registered so far
+    pub(super) fn call_serve_method(
+        &mut self,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        if self.capabilities.serve.is_none() {
+            return Err(RuntimeError::capability_not_available("Serve"));
+        }
+        match (method, args.as_slice()) {
+            ("route", [Value::Text(http_method), Value::Text(pattern), handler]) => {
+                self.count_effect("Serve")?;
+                self.routes.push(Route {
+                    method: http_method.to_uppercase(),
+                    pattern: pattern.clone(),
+                    handler: handler.clone(),
+                });
+                Ok(Value::Unit)
+            }
+            (
+                "request",
+                [Value::Text(http_method), Value::Text(url), Value::Text(body), rest @ ..],
+            ) if rest.len() <= 1 => {
+                let headers = match rest.first() {
+                    Some(Value::Map(entries)) => entries
+                        .iter()
+                        .map(|(name, value)| match (name, value) {
+                            (Value::Text(name), Value::Text(value)) => {
+                                Ok((name.clone(), value.clone()))
+                            }
+                            _ => Err(RuntimeError::type_mismatch("Map[Text, Text]", "other")),
+                        })
+                        .collect::<Result<_, _>>()?,
+                    Some(_) => return Err(RuntimeError::type_mismatch("Map[Text, Text]", "other")),
+                    None => Vec::new(),
+                };
+                let request = HttpRequest {
+                    method: http_method.to_uppercase(),
+                    url: url.clone(),
+                    headers,
+                    body: body.clone(),
+                };
+                Ok(response_value(&self.dispatch_request(&request)?))
+            }
+            ("route", [_, _, _]) => Err(RuntimeError::type_mismatch("(Text, Text, fn)", "other")),
+            ("route", _) => Err(RuntimeError::arity_mismatch(3, args.len())),
+            ("request", [_, _, _] | [_, _, _, _]) => Err(RuntimeError::type_mismatch(
+                "(Text, Text, Text, Map[Text, Text])",
+                "other",
+            )),
+            ("request", _) => Err(RuntimeError::arity_mismatch(3, args.len())),
+            _ => Err(RuntimeError::unknown_method("Serve", method)),
+        }
+    }
+
     /// P6.2: Call a method on a user-defined effect.
     ///
     /// Looks up an effect handler in the environment as a record with method fields.
@@ -422,9 +914,10 @@ impl Interpreter {
         method: &str,
         args: Vec<Value>,
     ) -> Result<Value, RuntimeError> {
+        self.count_effect(effect_name)?;
         // Look for a handler bound in the environment
         let handler_name = format!("__handler_{}", effect_name);
-        if let Some(handler) = self.env.lookup(&handler_name).cloned() {
+        if let Some(handler) = self.env.lookup(&handler_name) {
             match handler {
                 Value::Record(fields) => {
                     if let Some(func) = fields.get(method) {
@@ -563,6 +1056,54 @@ impl Interpreter {
                 let items = items.clone();
                 Some(self.ho_list_find(&items, args))
             }
+            (Value::List(items), "sort_by") => {
+                let items = items.clone();
+                Some(self.ho_list_sort_by(items, args))
+            }
+            (Value::List(items), "sort_by_key") => {
+                let items = items.clone();
+                Some(self.ho_list_sort_by_key(items, args))
+            }
+            (Value::List(items), "min_by") | (Value::List(items), "max_by") => {
+                let items = items.clone();
+                Some(self.ho_list_extreme_by(items, method == "max_by", args))
+            }
+            (Value::List(items), "group_by") => {
+                let items = items.clone();
+                Some(self.ho_list_group_by(items, args))
+            }
+            (Value::List(items), "partition") => {
+                let items = items.clone();
+                Some(self.ho_list_partition(items, args))
+            }
+            (Value::Map(entries), "map_values") => {
+                let entries = entries.clone();
+                Some(self.ho_map_map_values(entries, args))
+            }
+            (Value::Map(entries), "filter") => {
+                let entries = entries.clone();
+                Some(self.ho_map_filter(entries, args))
+            }
+            (Value::Map(entries), "fold") => {
+                let entries = entries.clone();
+                Some(self.ho_map_fold(entries, args))
+            }
+            (Value::Map(entries), "merge") => {
+                let entries = entries.clone();
+                Some(self.ho_map_merge(entries, args))
+            }
+            (Value::Map(entries), "update") => {
+                let entries = entries.clone();
+                Some(self.ho_map_update(entries, args))
+            }
+            (Value::Set(elements), "map") => {
+                let elements = elements.clone();
+                Some(self.ho_set_map(&elements, args))
+            }
+            (Value::Set(elements), "filter") => {
+                let elements = elements.clone();
+                Some(self.ho_set_filter(&elements, args))
+            }
             (Value::Some(inner), "map") => {
                 let inner = (**inner).clone();
                 Some(self.ho_option_map(inner, args))
@@ -690,6 +1231,237 @@ impl Interpreter {
         Ok(Value::None)
     }
 
+    /// Order two values with a user comparator, which returns a negative
+    /// Int, zero, or a positive Int
+    fn compare_with(
+        &mut self,
+        func: &Value,
+        a: &Value,
+        b: &Value,
+    ) -> Result<std::cmp::Ordering, RuntimeError> {
+        match self.call_function(func.clone(), vec![a.clone(), b.clone()])? {
+            Value::Int(n) => Ok(n.cmp(&0)),
+            other => Err(RuntimeError::type_mismatch("Int", &format!("{:?}", other))),
+        }
+    }
+
+    /// Stable sort with a comparator; the first comparator error aborts
+    /// the sort
+    fn ho_list_sort_by(
+        &mut self,
+        mut items: Vec<Value>,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(&args, 1)?;
+        let func = &args[0];
+        let mut error = None;
+        items.sort_by(|a, b| {
+            if error.is_some() {
+                return std::cmp::Ordering::Equal;
+            }
+            self.compare_with(func, a, b).unwrap_or_else(|e| {
+                error = Some(e);
+                std::cmp::Ordering::Equal
+            })
+        });
+        match error {
+            Some(e) => Err(e),
+            None => Ok(Value::List(items)),
+        }
+    }
+
+    /// Stable sort by a key computed once per element
+    fn ho_list_sort_by_key(
+        &mut self,
+        items: Vec<Value>,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(&args, 1)?;
+        let mut keyed = Vec::with_capacity(items.len());
+        for item in items {
+            let key = self.call_function(args[0].clone(), vec![item.clone()])?;
+            keyed.push((key, item));
+        }
+        keyed.sort_by(|(a, _), (b, _)| compare_values(a, b));
+        Ok(Value::List(
+            keyed.into_iter().map(|(_, item)| item).collect(),
+        ))
+    }
+
+    /// The least (or greatest) element under a comparator. Ties go to the
+    /// element a stable `sort_by` would put first (or last)
+    fn ho_list_extreme_by(
+        &mut self,
+        items: Vec<Value>,
+        greatest: bool,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(&args, 1)?;
+        let mut items = items.into_iter();
+        let Some(mut best) = items.next() else {
+            return Ok(Value::None);
+        };
+        for item in items {
+            let ordering = self.compare_with(&args[0], &item, &best)?;
+            if ordering.is_lt() && !greatest || ordering.is_ge() && greatest {
+                best = item;
+            }
+        }
+        Ok(Value::Some(Box::new(best)))
+    }
+
+    /// Group elements into a Map from key to the elements with that key,
+    /// each group in its original order
+    fn ho_list_group_by(
+        &mut self,
+        items: Vec<Value>,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(&args, 1)?;
+        let mut groups: Vec<(Value, Value)> = Vec::new();
+        for item in items {
+            let key = self.call_function(args[0].clone(), vec![item.clone()])?;
+            match map_search(&groups, &key) {
+                Ok(i) => {
+                    if let Value::List(group) = &mut groups[i].1 {
+                        group.push(item);
+                    }
+                }
+                Err(i) => groups.insert(i, (key, Value::List(vec![item]))),
+            }
+        }
+        Ok(Value::Map(groups))
+    }
+
+    /// Split into the elements matching a predicate and the rest
+    fn ho_list_partition(
+        &mut self,
+        items: Vec<Value>,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(&args, 1)?;
+        let (mut matching, mut rest) = (Vec::new(), Vec::new());
+        for item in items {
+            match self.call_function(args[0].clone(), vec![item.clone()])? {
+                Value::Bool(true) => matching.push(item),
+                Value::Bool(false) => rest.push(item),
+                other => return Err(RuntimeError::type_mismatch("Bool", &format!("{:?}", other))),
+            }
+        }
+        Ok(Value::Tuple(vec![Value::List(matching), Value::List(rest)]))
+    }
+
+    fn ho_map_map_values(
+        &mut self,
+        mut entries: Vec<(Value, Value)>,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(&args, 1)?;
+        for (_, value) in &mut entries {
+            *value = self.call_function(args[0].clone(), vec![value.clone()])?;
+        }
+        Ok(Value::Map(entries))
+    }
+
+    /// Keep the entries for which `f(key, value)` is true
+    fn ho_map_filter(
+        &mut self,
+        entries: Vec<(Value, Value)>,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(&args, 1)?;
+        let mut kept = Vec::new();
+        for (key, value) in entries {
+            match self.call_function(args[0].clone(), vec![key.clone(), value.clone()])? {
+                Value::Bool(true) => kept.push((key, value)),
+                Value::Bool(false) => {}
+                other => return Err(RuntimeError::type_mismatch("Bool", &format!("{:?}", other))),
+            }
+        }
+        Ok(Value::Map(kept))
+    }
+
+    /// Fold over the entries in key order with `f(acc, key, value)`
+    fn ho_map_fold(
+        &mut self,
+        entries: Vec<(Value, Value)>,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(&args, 2)?;
+        let mut args_iter = args.into_iter();
+        let mut acc = args_iter.next().unwrap();
+        let func = args_iter.next().unwrap();
+        for (key, value) in entries {
+            acc = self.call_function(func.clone(), vec![acc, key, value])?;
+        }
+        Ok(acc)
+    }
+
+    /// Union of two maps; keys present in both take
+    /// `conflict(key, ours, theirs)`
+    fn ho_map_merge(
+        &mut self,
+        mut entries: Vec<(Value, Value)>,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(&args, 2)?;
+        let Value::Map(other) = &args[0] else {
+            return Err(RuntimeError::type_mismatch(
+                "Map",
+                &format!("{:?}", args[0]),
+            ));
+        };
+        for (key, theirs) in other {
+            match map_search(&entries, key) {
+                Ok(i) => {
+                    let ours = entries[i].1.clone();
+                    entries[i].1 = self
+                        .call_function(args[1].clone(), vec![key.clone(), ours, theirs.clone()])?;
+                }
+                Err(i) => entries.insert(i, (key.clone(), theirs.clone())),
+            }
+        }
+        Ok(Value::Map(entries))
+    }
+
+    /// Set `key` to `f(current)`, where `current` is `Some(value)` or
+    /// `None` when the key is absent
+    fn ho_map_update(
+        &mut self,
+        entries: Vec<(Value, Value)>,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(&args, 2)?;
+        let current = match map_get(&entries, &args[0]) {
+            Some(value) => Value::Some(Box::new(value.clone())),
+            None => Value::None,
+        };
+        let updated = self.call_function(args[1].clone(), vec![current])?;
+        Ok(Value::Map(map_set(&entries, args[0].clone(), updated)))
+    }
+
+    /// Map each element, re-sorting and deduplicating the results since
+    /// distinct elements may map to equal values
+    fn ho_set_map(&mut self, elements: &[Value], args: Vec<Value>) -> Result<Value, RuntimeError> {
+        match self.ho_list_map(elements, args)? {
+            Value::List(mapped) => Ok(Value::Set(sorted_set_from(mapped))),
+            other => Ok(other),
+        }
+    }
+
+    /// Keep the elements matching a predicate; a subset of a sorted set is
+    /// still sorted
+    fn ho_set_filter(
+        &mut self,
+        elements: &[Value],
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match self.ho_list_filter(elements, args)? {
+            Value::List(kept) => Ok(Value::Set(kept)),
+            other => Ok(other),
+        }
+    }
+
     fn ho_option_map(&mut self, inner: Value, args: Vec<Value>) -> Result<Value, RuntimeError> {
         let func = args
             .into_iter()
@@ -905,12 +1677,7 @@ impl Interpreter {
                 let pairs: Vec<Value> = items
                     .iter()
                     .enumerate()
-                    .map(|(i, v)| {
-                        let mut fields = HashMap::new();
-                        fields.insert("index".to_string(), Value::Int(i as i64));
-                        fields.insert("value".to_string(), v.clone());
-                        Value::Record(fields)
-                    })
+                    .map(|(i, v)| Value::Tuple(vec![Value::Int(i as i64), v.clone()]))
                     .collect();
                 Ok(Value::List(pairs))
             }
@@ -920,7 +1687,7 @@ impl Interpreter {
                         .iter()
                         .zip(other.iter())
                         .map(|(a, b)| {
-                            let mut fields = HashMap::new();
+                            let mut fields = IndexMap::new();
                             fields.insert("first".to_string(), a.clone());
                             fields.insert("second".to_string(), b.clone());
                             Value::Record(fields)
@@ -931,9 +1698,130 @@ impl Interpreter {
                     Err(RuntimeError::type_mismatch("List", "other"))
                 }
             }
+            (Value::List(items), "dedup") => {
+                let mut unique: Vec<Value> = Vec::new();
+                for item in items {
+                    if !unique.iter().any(|u| values_equal(u, item)) {
+                        unique.push(item.clone());
+                    }
+                }
+                Ok(Value::List(unique))
+            }
+            (Value::List(items), "chunks") | (Value::List(items), "windows") => {
+                let size = match args.first() {
+                    Some(Value::Int(n)) if *n > 0 => *n as usize,
+                    Some(Value::Int(n)) => {
+                        return Err(RuntimeError::type_mismatch("positive Int", &n.to_string()))
+                    }
+                    _ => return Err(RuntimeError::type_mismatch("Int", "other")),
+                };
+                let parts: Vec<Value> = if method == "chunks" {
+                    items
+                        .chunks(size)
+                        .map(|c| Value::List(c.to_vec()))
+                        .collect()
+                } else {
+                    items
+                        .windows(size)
+                        .map(|w| Value::List(w.to_vec()))
+                        .collect()
+                };
+                Ok(Value::List(parts))
+            }
+            (Value::List(items), "sum") | (Value::List(items), "product") => {
+                let (op, identity) = if method == "sum" {
+                    (BinaryOp::Add, Value::Int(0))
+                } else {
+                    (BinaryOp::Mul, Value::Int(1))
+                };
+                let mut total = identity;
+                for item in items {
+                    if !matches!(item, Value::Int(_) | Value::Float(_)) {
+                        return Err(RuntimeError::type_mismatch(
+                            "List of Int or Float",
+                            &format!("{:?}", item),
+                        ));
+                    }
+                    total = self.eval_binary_op(op, &total, item)?;
+                }
+                Ok(total)
+            }
+            (Value::List(items), "flatten") => {
+                let mut flat = Vec::new();
+                for item in items {
+                    match item {
+                        Value::List(inner) => flat.extend(inner.iter().cloned()),
+                        other => {
+                            return Err(RuntimeError::type_mismatch(
+                                "List of List",
+                                &format!("{:?}", other),
+                            ))
+                        }
+                    }
+                }
+                Ok(Value::List(flat))
+            }
+
+            // Float methods
+            (Value::Float(f), "is_nan") => Ok(Value::Bool(f.is_nan())),
+            (Value::Float(f), "is_infinite") => Ok(Value::Bool(f.is_infinite())),
+            (Value::Float(f), "is_finite") => Ok(Value::Bool(f.is_finite())),
+            (Value::Float(f), "to_fixed") | (Value::Float(f), "to_precision") => {
+                let min = if method == "to_fixed" { 0 } else { 1 };
+                match args.first() {
+                    Some(Value::Int(n)) if (min..=100).contains(n) => {
+                        if method == "to_fixed" {
+                            Ok(Value::Text(format_fixed(*f, *n as usize)))
+                        } else {
+                            Ok(Value::Text(format_precision(*f, *n as usize)))
+                        }
+                    }
+                    Some(Value::Int(n)) => Err(RuntimeError::type_mismatch(
+                        &format!("Int between {} and 100", min),
+                        &n.to_string(),
+                    )),
+                    _ => Err(RuntimeError::type_mismatch("Int", "other")),
+                }
+            }
+
+            // Decimal methods
+            (Value::Decimal(d), "abs" | "scale" | "to_float" | "round") => {
+                decimal_method(*d, method, &args)
+            }
+
+            // Bytes methods
+            (Value::Bytes(bytes), "len") => Ok(Value::Int(bytes.len() as i64)),
+            (Value::Bytes(bytes), "is_empty") => Ok(Value::Bool(bytes.is_empty())),
+            (Value::Bytes(bytes), "to_list") => Ok(Value::List(
+                bytes.iter().map(|b| Value::Int(*b as i64)).collect(),
+            )),
+            (Value::Bytes(bytes), "to_text") => Ok(match String::from_utf8(bytes.clone()) {
+                Ok(text) => Value::Some(Box::new(Value::Text(text))),
+                Err(_) => Value::None,
+            }),
+            (Value::Text(s), "to_bytes") => Ok(Value::Bytes(s.as_bytes().to_vec())),
+
+            // Ref methods
+            (Value::Ref(cell), "get") => Ok(cell.borrow().clone()),
+            (Value::Ref(cell), "set") => match args.as_slice() {
+                [value] => {
+                    *cell.borrow_mut() = value.clone();
+                    Ok(Value::Unit)
+                }
+                _ => Err(RuntimeError::arity_mismatch(1, args.len())),
+            },
+
+            // Duration methods
+            (
+                Value::Duration(millis),
+                "as_millis" | "as_seconds" | "as_minutes" | "as_hours" | "as_days" | "abs",
+            ) => duration_method(*millis, method),
 
             // Text methods
-            (Value::Text(s), "len") => Ok(Value::Int(s.len() as i64)),
+            (Value::Text(s), "len") | (Value::Text(s), "char_len") => {
+                Ok(Value::Int(s.chars().count() as i64))
+            }
+            (Value::Text(s), "byte_len") => Ok(Value::Int(s.len() as i64)),
             (Value::Text(s), "to_upper") => Ok(Value::Text(s.to_uppercase())),
             (Value::Text(s), "to_lower") => Ok(Value::Text(s.to_lowercase())),
             (Value::Text(s), "trim") => Ok(Value::Text(s.trim().to_string())),
@@ -997,7 +1885,9 @@ impl Interpreter {
             (Value::Text(s), "index_of") => {
                 if let Some(Value::Text(needle)) = args.first() {
                     match s.find(needle.as_str()) {
-                        Some(pos) => Ok(Value::Some(Box::new(Value::Int(pos as i64)))),
+                        Some(pos) => Ok(Value::Some(Box::new(Value::Int(
+                            s[..pos].chars().count() as i64,
+                        )))),
                         None => Ok(Value::None),
                     }
                 } else {
@@ -1009,14 +1899,7 @@ impl Interpreter {
                     if let (Some(Value::Int(start)), Some(Value::Int(end))) =
                         (args.first(), args.get(1))
                     {
-                        let start = (*start).max(0) as usize;
-                        let end = (*end).max(0) as usize;
-                        let end = end.min(s.len());
-                        if start <= end && start <= s.len() {
-                            Ok(Value::Text(s[start..end].to_string()))
-                        } else {
-                            Ok(Value::Text(String::new()))
-                        }
+                        text_slice(s, *start, *end).map(Value::Text)
                     } else {
                         Err(RuntimeError::type_mismatch("(Int, Int)", "other"))
                     }
@@ -1024,6 +1907,61 @@ impl Interpreter {
                     Err(RuntimeError::arity_mismatch(2, args.len()))
                 }
             }
+            (Value::Text(s), "trim_start") => Ok(Value::Text(s.trim_start().to_string())),
+            (Value::Text(s), "trim_end") => Ok(Value::Text(s.trim_end().to_string())),
+            (Value::Text(s), "pad_start") | (Value::Text(s), "pad_end") => {
+                let (width, fill) = match args.as_slice() {
+                    [Value::Int(width)] => (*width, " "),
+                    [Value::Int(width), Value::Text(fill)] => (*width, fill.as_str()),
+                    [_] | [_, _] => {
+                        return Err(RuntimeError::type_mismatch("(Int, Text)", "other"))
+                    }
+                    _ => return Err(RuntimeError::arity_mismatch(2, args.len())),
+                };
+                let missing = (width.max(0) as usize).saturating_sub(s.chars().count());
+                if missing == 0 || fill.is_empty() {
+                    return Ok(Value::Text(s.clone()));
+                }
+                let padding: String = fill.chars().cycle().take(missing).collect();
+                if method == "pad_start" {
+                    Ok(Value::Text(padding + s))
+                } else {
+                    Ok(Value::Text(s.clone() + &padding))
+                }
+            }
+            (Value::Text(s), "lines") => Ok(Value::List(
+                s.lines().map(|l| Value::Text(l.to_string())).collect(),
+            )),
+            (Value::Text(s), "split_whitespace") => Ok(Value::List(
+                s.split_whitespace()
+                    .map(|w| Value::Text(w.to_string()))
+                    .collect(),
+            )),
+            (Value::Text(s), "char_at") => {
+                if let Some(Value::Int(i)) = args.first() {
+                    let c = usize::try_from(*i).ok().and_then(|i| s.chars().nth(i));
+                    match c {
+                        Some(c) => Ok(Value::Some(Box::new(Value::Text(c.to_string())))),
+                        None => Ok(Value::None),
+                    }
+                } else {
+                    Err(RuntimeError::type_mismatch("Int", "other"))
+                }
+            }
+            (Value::Text(s), "count") => {
+                if let Some(Value::Text(needle)) = args.first() {
+                    Ok(Value::Int(s.matches(needle.as_str()).count() as i64))
+                } else {
+                    Err(RuntimeError::type_mismatch("Text", "other"))
+                }
+            }
+            (Value::Text(s), "equals_ignore_case") => {
+                if let Some(Value::Text(other)) = args.first() {
+                    Ok(Value::Bool(s.to_lowercase() == other.to_lowercase()))
+                } else {
+                    Err(RuntimeError::type_mismatch("Text", "other"))
+                }
+            }
             // v1.1: Regex text methods
             (Value::Text(s), "matches") => {
                 if let Some(Value::Text(pattern)) = args.first() {
@@ -1181,6 +2119,43 @@ impl Interpreter {
                     Err(RuntimeError::type_mismatch("Set", "other"))
                 }
             }
+            (Value::Set(elements), "difference") => {
+                if let Some(Value::Set(other)) = args.first() {
+                    let result: Vec<Value> = elements
+                        .iter()
+                        .filter(|e| !set_contains(other, e))
+                        .cloned()
+                        .collect();
+                    Ok(Value::Set(result))
+                } else {
+                    Err(RuntimeError::type_mismatch("Set", "other"))
+                }
+            }
+            (Value::Set(elements), "symmetric_difference") => {
+                if let Some(Value::Set(other)) = args.first() {
+                    let result: Vec<Value> = elements
+                        .iter()
+                        .filter(|e| !set_contains(other, e))
+                        .chain(other.iter().filter(|e| !set_contains(elements, e)))
+                        .cloned()
+                        .collect();
+                    Ok(Value::Set(sorted_set_from(result)))
+                } else {
+                    Err(RuntimeError::type_mismatch("Set", "other"))
+                }
+            }
+            (Value::Set(elements), "is_subset") | (Value::Set(elements), "is_superset") => {
+                if let Some(Value::Set(other)) = args.first() {
+                    let (small, large) = if method == "is_subset" {
+                        (elements, other)
+                    } else {
+                        (other, elements)
+                    };
+                    Ok(Value::Bool(small.iter().all(|e| set_contains(large, e))))
+                } else {
+                    Err(RuntimeError::type_mismatch("Set", "other"))
+                }
+            }
 
             _ => {
                 // Try trait method dispatch before failing
@@ -1200,6 +2175,10 @@ impl Interpreter {
         match value {
             Value::Int(_) => "Int",
             Value::Float(_) => "Float",
+            Value::Decimal(_) => "Decimal",
+            Value::Duration(_) => "Duration",
+            Value::Deadline(_) => "Deadline",
+            Value::Bytes(_) => "Bytes",
             Value::Bool(_) => "Bool",
             Value::Text(_) => "Text",
             Value::Unit => "Unit",
@@ -1213,6 +2192,10 @@ impl Interpreter {
             Value::Variant { .. } | Value::VariantConstructor { .. } => "Variant",
             Value::Closure { .. } => "Closure",
             Value::Future { .. } => "Future",
+            Value::WebSocket(_) => "WebSocket",
+            Value::Ref(_) => "Ref",
+            Value::Capability(_) => "Capability",
+            Value::Namespace(_) => "Namespace",
         }
     }
 
@@ -1252,3 +2235,52 @@ impl Interpreter {
         None
     }
 }
+
+/// The characters of `s` from `start` (inclusive) to `end` (exclusive),
+/// counted in chars rather than bytes so that slicing never splits one
+fn text_slice(s: &str, start: i64, end: i64) -> Result<String, RuntimeError> {
+    let len = s.chars().count();
+    for index in [start, end] {
+        if index < 0 || index as usize > len {
+            return Err(RuntimeError::text_index_out_of_bounds(index, len));
+        }
+    }
+    if start > end {
+        return Err(RuntimeError::invalid_text_range(start, end));
+    }
+    Ok(s.chars()
+        .skip(start as usize)
+        .take((end - start) as usize)
+        .collect())
+}
+
+/// Decode key text loaded by `Crypto.key_from_env`/`key_from_file` into
+/// `Ok(Bytes)`, or `Err(Text)` naming where the key came from
+fn decode_key(text: &str, encoding: &str, source: &str) -> Result<Value, RuntimeError> {
+    let decoded = match encoding {
+        "raw" => return Ok(Value::Ok(Box::new(Value::Bytes(text.as_bytes().to_vec())))),
+        "hex" => hex_decode(text),
+        "base64" => base64_decode(text),
+        _ => {
+            return Err(RuntimeError::type_mismatch(
+                "\"raw\", \"hex\" or \"base64\"",
+                &format!("{:?}", encoding),
+            ))
+        }
+    };
+    Ok(match decoded {
+        Value::Err(error) => {
+            let message = match *error {
+                Value::Record(fields) => fields.get("message").map(format_value),
+                _ => None,
+            };
+            Value::Err(Box::new(Value::Text(format!(
+                "{} is not valid {}: {}",
+                source,
+                encoding,
+                message.unwrap_or_default()
+            ))))
+        }
+        ok => ok,
+    })
+}
@@ -0,0 +1,206 @@
+//! `Metrics`: counters, gauges, and timings a program reports about itself.
+//!
+//! Every metric is aggregated in memory by [`MemoryMetrics`]; `astra run`
+//! prints the [`MetricsReport`] when the program ends, and with `--json` also
+//! streams each [`MetricEvent`] to stderr as it happens. Tests read the
+//! aggregate back with `Metrics.snapshot()`.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
+use super::error::RuntimeError;
+use super::value::{format_value, Value};
+use super::{Interpreter, MetricsCapability};
+
+/// One metric as reported by the program
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "event")]
+pub enum MetricEvent {
+    /// `Metrics.counter(name, n)` added `n`
+    #[serde(rename = "metric_counter")]
+    Counter { name: String, n: i64 },
+    /// `Metrics.gauge(name, value)` set the current value
+    #[serde(rename = "metric_gauge")]
+    Gauge { name: String, value: f64 },
+    /// `Metrics.timing(name, duration)` took `millis`
+    #[serde(rename = "metric_timing")]
+    Timing { name: String, millis: i64 },
+}
+
+/// The timings recorded under one name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct TimingStats {
+    pub samples: i64,
+    pub total_ms: i64,
+    pub min_ms: i64,
+    pub max_ms: i64,
+}
+
+/// Every metric of a run, aggregated by name
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct MetricsReport {
+    /// The sum of each counter
+    pub counters: BTreeMap<String, i64>,
+    /// The last value of each gauge
+    pub gauges: BTreeMap<String, f64>,
+    pub timings: BTreeMap<String, TimingStats>,
+}
+
+impl MetricsReport {
+    pub fn record(&mut self, event: &MetricEvent) {
+        match event {
+            MetricEvent::Counter { name, n } => {
+                let counter = self.counters.entry(name.clone()).or_default();
+                *counter = counter.saturating_add(*n);
+            }
+            MetricEvent::Gauge { name, value } => {
+                self.gauges.insert(name.clone(), *value);
+            }
+            MetricEvent::Timing { name, millis } => {
+                let stats = self.timings.entry(name.clone()).or_insert(TimingStats {
+                    samples: 0,
+                    total_ms: 0,
+                    min_ms: *millis,
+                    max_ms: *millis,
+                });
+                stats.samples += 1;
+                stats.total_ms = stats.total_ms.saturating_add(*millis);
+                stats.min_ms = stats.min_ms.min(*millis);
+                stats.max_ms = stats.max_ms.max(*millis);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty() && self.gauges.is_empty() && self.timings.is_empty()
+    }
+
+    /// The report as `Metrics.snapshot()` returns it
+    fn to_value(&self) -> Value {
+        let map = |entries: Vec<(String, Value)>| {
+            Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(name, value)| (Value::Text(name), value))
+                    .collect(),
+            )
+        };
+        let counters = self
+            .counters
+            .iter()
+            .map(|(name, n)| (name.clone(), Value::Int(*n)))
+            .collect();
+        let gauges = self
+            .gauges
+            .iter()
+            .map(|(name, value)| (name.clone(), Value::Float(*value)))
+            .collect();
+        let timings = self
+            .timings
+            .iter()
+            .map(|(name, stats)| {
+                let stats = IndexMap::from([
+                    ("samples".to_string(), Value::Int(stats.samples)),
+                    ("total".to_string(), Value::Duration(stats.total_ms)),
+                    ("min".to_string(), Value::Duration(stats.min_ms)),
+                    ("max".to_string(), Value::Duration(stats.max_ms)),
+                ]);
+                (name.clone(), Value::Record(stats))
+            })
+            .collect();
+        Value::Record(IndexMap::from([
+            ("counters".to_string(), map(counters)),
+            ("gauges".to_string(), map(gauges)),
+            ("timings".to_string(), map(timings)),
+        ]))
+    }
+}
+
+/// Metrics capability that aggregates in memory, optionally writing each
+/// event to stderr as a JSON line too; clones share the report
+#[derive(Clone, Default)]
+pub struct MemoryMetrics {
+    report: Rc<RefCell<MetricsReport>>,
+    stream: bool,
+}
+
+impl MemoryMetrics {
+    /// Metrics that also stream JSON Lines events to stderr
+    pub fn streaming() -> Self {
+        Self {
+            stream: true,
+            ..Self::default()
+        }
+    }
+}
+
+impl MetricsCapability for MemoryMetrics {
+    fn record(&self, event: &MetricEvent) {
+        self.report.borrow_mut().record(event);
+        if self.stream {
+            if let Ok(line) = serde_json::to_string(event) {
+                eprintln!("{}", line);
+            }
+        }
+    }
+
+    fn report(&self) -> MetricsReport {
+        self.report.borrow().clone()
+    }
+}
+
+impl Interpreter {
+    /// Call a Metrics effect method: record a counter, gauge, or timing, or
+    /// read back what was recorded
+    pub(super) fn call_metrics_method(
+        &mut self,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        self.count_effect("Metrics")?;
+        let metrics = self
+            .capabilities
+            .metrics
+            .as_ref()
+            .ok_or_else(|| RuntimeError::capability_not_available("Metrics"))?;
+        let event = match (method, args.as_slice()) {
+            ("counter", [Value::Text(name), Value::Int(n)]) => MetricEvent::Counter {
+                name: name.clone(),
+                n: *n,
+            },
+            ("gauge", [Value::Text(name), Value::Int(value)]) => MetricEvent::Gauge {
+                name: name.clone(),
+                value: *value as f64,
+            },
+            ("gauge", [Value::Text(name), Value::Float(value)]) => MetricEvent::Gauge {
+                name: name.clone(),
+                value: *value,
+            },
+            ("timing", [Value::Text(name), Value::Duration(millis)]) => MetricEvent::Timing {
+                name: name.clone(),
+                millis: *millis,
+            },
+            ("snapshot", []) => return Ok(metrics.report().to_value()),
+            ("counter", [_, _]) => return Err(RuntimeError::type_mismatch("(Text, Int)", "other")),
+            ("gauge", [_, value]) => {
+                return Err(RuntimeError::type_mismatch(
+                    "(Text, Int or Float)",
+                    &format_value(value),
+                ))
+            }
+            ("timing", [_, _]) => {
+                return Err(RuntimeError::type_mismatch("(Text, Duration)", "other"))
+            }
+            ("counter" | "gauge" | "timing", _) => {
+                return Err(RuntimeError::arity_mismatch(2, args.len()))
+            }
+            ("snapshot", _) => return Err(RuntimeError::arity_mismatch(0, args.len())),
+            _ => return Err(RuntimeError::unknown_method("Metrics", method)),
+        };
+        metrics.record(&event);
+        Ok(Value::Unit)
+    }
+}
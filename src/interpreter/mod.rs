@@ -2,28 +2,53 @@
 //!
 //! Executes Astra code with capability-controlled effects.
 
+mod args;
+mod audit;
+mod body_cache;
 pub mod capabilities;
+mod compression;
+mod decimal;
+mod duration;
+mod encoding;
 pub mod environment;
 pub mod error;
+mod hashing;
+mod http;
+mod intercept;
+#[cfg(feature = "jit")]
+mod jit;
 mod json;
 mod methods;
+mod metrics;
 mod modules;
+mod numbers;
 mod pattern;
 mod regex;
+mod rpc;
+mod style;
 pub mod value;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
+use indexmap::IndexMap;
+
+use crate::diagnostics::Span;
 use crate::parser::ast::*;
 
+pub use audit::{AuditEvent, EffectAudit};
 pub use capabilities::*;
-pub use environment::Environment;
+pub use environment::{Environment, UpdateError};
 pub use error::{check_arity, CallFrame, RuntimeError};
+pub use intercept::{EffectInterceptor, Intercept};
+pub use json::json_stringify_value;
+pub use metrics::{MemoryMetrics, MetricEvent, MetricsReport, TimingStats};
 pub use pattern::match_pattern;
+pub use style::StyledText;
 pub use value::*;
 
-use json::{json_parse_value, json_stringify_value};
+use body_cache::{NodeCache, Scratch};
+use json::json_parse_value;
 use regex::{regex_find_all, regex_is_match, regex_match, regex_replace, regex_split};
 
 /// Decode a percent-encoded URL component (e.g. `hello%20world` → `hello world`).
@@ -47,6 +72,72 @@ pub(crate) fn url_decode(s: &str) -> String {
     String::from_utf8_lossy(&result).into_owned()
 }
 
+/// Most differences listed in an `assert_eq` failure
+const MAX_DIFF_LINES: usize = 20;
+
+/// Innermost and outermost calls shown in a stack trace; deeper stacks elide
+/// the calls between them
+const STACK_TRACE_INNERMOST: usize = 10;
+const STACK_TRACE_OUTERMOST: usize = 5;
+
+/// Failure message for `assert_eq`: both values when they are simple, or the
+/// paths at which they differ
+fn assert_eq_message(left: &Value, right: &Value) -> String {
+    let diff = value_diff(left, right);
+    if diff.len() == 1 && diff[0].starts_with("value: ") {
+        return format!(
+            "assertion failed: {} != {}",
+            show_value(left),
+            show_value(right)
+        );
+    }
+    let mut message = "assertion failed: left != right".to_string();
+    for line in diff.iter().take(MAX_DIFF_LINES) {
+        message.push_str("\n  ");
+        message.push_str(line);
+    }
+    if diff.len() > MAX_DIFF_LINES {
+        message.push_str(&format!(
+            "\n  ... and {} more differences",
+            diff.len() - MAX_DIFF_LINES
+        ));
+    }
+    message
+}
+
+/// The names a lambda captures: every identifier its body mentions, other
+/// than its parameters, sorted and without repeats. Names bound inside the
+/// body are included too; capturing them is harmless, since the body binds
+/// them again before use.
+fn captured_names(params: &[String], body: &Block) -> Vec<String> {
+    let mut names = std::collections::BTreeSet::new();
+    crate::parser::visit::walk_block(body, &mut |expr| {
+        if let Expr::Ident { name, .. } = expr {
+            if !params.contains(name) {
+                names.insert(name.clone());
+            }
+        }
+    });
+    names.into_iter().collect()
+}
+
+/// The elements `for` and quantifiers iterate over: a Map's entries as
+/// `(key, value)` tuples and Text's characters as one-character Text
+fn iteration_items(value: Value) -> Result<Vec<Value>, RuntimeError> {
+    match value {
+        Value::List(items) | Value::Set(items) => Ok(items),
+        Value::Map(entries) => Ok(entries
+            .into_iter()
+            .map(|(k, v)| Value::Tuple(vec![k, v]))
+            .collect()),
+        Value::Text(s) => Ok(s.chars().map(|c| Value::Text(c.to_string())).collect()),
+        other => Err(RuntimeError::type_mismatch(
+            "List, Set, Map, or Text",
+            &format!("{:?}", other),
+        )),
+    }
+}
+
 /// Result of evaluating an expression with TCO awareness (P6.4)
 #[allow(clippy::large_enum_variant)]
 enum TcoResult {
@@ -56,6 +147,16 @@ enum TcoResult {
     TailCall(Vec<Value>),
 }
 
+/// A handler registered with `Serve.route`
+#[derive(Debug, Clone)]
+struct Route {
+    /// Uppercase method, or `*` for any
+    method: String,
+    /// Path pattern, such as `/users/:id`
+    pattern: String,
+    handler: Value,
+}
+
 /// A registered trait implementation for runtime dispatch
 #[derive(Debug, Clone)]
 struct RuntimeTraitImpl {
@@ -84,10 +185,178 @@ pub struct Interpreter {
     type_defs: HashMap<String, TypeDef>,
     /// Effect definitions (P6.2)
     effect_defs: HashMap<String, EffectDecl>,
+    /// Trait definitions, whose method contracts every impl inherits
+    trait_defs: HashMap<String, TraitDef>,
     /// Trait implementations for method dispatch
     trait_impls: Vec<RuntimeTraitImpl>,
     /// v1.1: Set of async function names
     async_fns: std::collections::HashSet<String>,
+    /// Routes registered with `Serve.route`, in registration order
+    routes: Vec<Route>,
+    /// Open connections from `Net.ws_connect`, by the id in their `Value::WebSocket`
+    websockets: BTreeMap<u64, Box<dyn WebSocketConnection>>,
+    /// Id for the next WebSocket connection
+    next_websocket: u64,
+    /// Id for the next JSON-RPC request, unique for the whole run
+    next_rpc_id: i64,
+    /// Number of the last spec made with `Args`, which orders positionals
+    next_arg_spec: i64,
+    /// Name of the module whose entry point is running, for `Args` usage lines
+    program_name: Option<String>,
+    /// The task from `Progress.start`, its total, and how much of it is done
+    progress_task: Option<(String, i64, i64)>,
+    /// Configuration that decides which `@cfg(...)` items are loaded
+    cfg: crate::cfg::Cfg,
+    /// Golden files for `assert_matches_file`, set by `astra test`
+    golden_files: Option<GoldenFiles>,
+    /// Limits on evaluation, if any
+    limits: Option<EvalLimits>,
+    /// Deepest nesting of function calls before a stack overflow error
+    max_call_depth: usize,
+    /// Budgets from the manifest's `[limits]`, if any
+    budget: Option<Budget>,
+    /// Which contracts are checked on calls
+    contract_mode: ContractMode,
+    /// Expressions evaluated so far
+    steps: u64,
+    /// Effect calls made so far, by effect
+    effect_calls: BTreeMap<String, u64>,
+    /// Effect operations by call site, when auditing
+    audit: Option<EffectAudit>,
+    /// The call that effect operations are attributed to, when auditing
+    audit_site: Option<Span>,
+    /// Most bytes bound in the environment so far, when memory is tracked
+    peak_memory: Option<usize>,
+    /// Captured `dbg` traces; without capture they go to stderr
+    debug_output: Option<std::rc::Rc<std::cell::RefCell<Vec<String>>>>,
+    /// Debugger called before each statement and block result, if any
+    debug_hook: Option<Box<dyn DebugHook>>,
+    /// Host code run around every capability operation, outermost first
+    interceptors: Vec<Box<dyn EffectInterceptor>>,
+    /// Function bodies, shared by every closure over the same definition
+    bodies: NodeCache<ClosureBody>,
+    /// Reusable buffers for call arguments
+    scratch: Scratch<Value>,
+    /// Native code for hot numeric functions
+    #[cfg(feature = "jit")]
+    jit: jit::Jit,
+}
+
+/// Budgets declared in the `[limits]` section of `astra.toml`, checked as
+/// steps, memory, and effect calls are counted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Budget {
+    pub max_steps: Option<u64>,
+    /// Most estimated bytes bound in the environment at once
+    pub max_memory: Option<usize>,
+    /// Most calls per effect, keyed by lowercase effect name
+    pub max_effect_calls: BTreeMap<String, u64>,
+}
+
+/// Which `requires`/`ensures` clauses are evaluated when functions are called
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContractMode {
+    /// No contracts, for speed
+    Off,
+    /// Only the contracts of `public` functions and trait methods, which
+    /// guard a module's interface
+    Boundary,
+    /// Every contract
+    #[default]
+    All,
+}
+
+impl ContractMode {
+    /// The mode's name, as written on the command line and in `astra.toml`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContractMode::Off => "off",
+            ContractMode::Boundary => "boundary",
+            ContractMode::All => "all",
+        }
+    }
+
+    /// Whether a function's contract is checked in this mode
+    fn checks(self, body: &ClosureBody) -> bool {
+        match self {
+            ContractMode::Off => false,
+            ContractMode::Boundary => body.public,
+            ContractMode::All => true,
+        }
+    }
+}
+
+impl std::str::FromStr for ContractMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(ContractMode::Off),
+            "boundary" => Ok(ContractMode::Boundary),
+            "all" => Ok(ContractMode::All),
+            other => Err(format!(
+                "unknown contract mode `{}` (expected off, boundary, or all)",
+                other
+            )),
+        }
+    }
+}
+
+/// What a run has cost so far, counted the same way on every run of the same
+/// program with the same inputs
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CostReport {
+    /// Expressions evaluated
+    pub steps: u64,
+    /// Effect calls, by effect (`Console`, `Fs`, ...)
+    pub effect_calls: BTreeMap<String, u64>,
+    /// Most bytes bound in the environment at once, estimated with
+    /// [`value_size`], if memory was tracked
+    pub peak_memory: Option<usize>,
+}
+
+/// Hook for stepping through evaluation, used by `astra debug`
+pub trait DebugHook {
+    /// Called before the statement or block result at `span` is evaluated.
+    /// The hook is detached while it runs, so it can inspect the environment
+    /// and evaluate expressions in the paused frame without pausing again;
+    /// an error stops the program.
+    fn on_stop(&mut self, interpreter: &mut Interpreter, span: &Span) -> Result<(), RuntimeError>;
+}
+
+/// Call depth at which [`Interpreter::set_max_call_depth`] stops a program
+/// unless told otherwise. The thread running the interpreter needs enough
+/// stack for this many nested calls; see [`stack_size_for`].
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// Stack reserved per nested call: each Astra call nests several interpreter
+/// frames, which are much larger in debug builds
+const STACK_PER_CALL: usize = if cfg!(debug_assertions) {
+    1024 * 1024
+} else {
+    64 * 1024
+};
+
+/// Stack for everything besides nested calls
+const BASE_STACK: usize = 16 * 1024 * 1024;
+
+/// Stack a run needs to reach `max_call_depth` nested calls without
+/// overflowing the host stack
+pub fn stack_size_for(max_call_depth: usize) -> usize {
+    max_call_depth
+        .saturating_mul(STACK_PER_CALL)
+        .saturating_add(BASE_STACK)
+}
+
+/// Limits that stop runaway programs with a runtime error instead of
+/// hanging or overflowing the stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalLimits {
+    /// Maximum number of expressions to evaluate
+    pub max_steps: u64,
+    /// Maximum depth of nested function calls
+    pub max_call_depth: usize,
 }
 
 impl Interpreter {
@@ -108,17 +377,63 @@ impl Interpreter {
             call_stack: Vec::new(),
             type_defs: HashMap::new(),
             effect_defs: HashMap::new(),
+            trait_defs: HashMap::new(),
             trait_impls: Vec::new(),
             async_fns: std::collections::HashSet::new(),
+            routes: Vec::new(),
+            websockets: BTreeMap::new(),
+            next_websocket: 0,
+            next_rpc_id: 1,
+            next_arg_spec: 0,
+            program_name: None,
+            progress_task: None,
+            cfg: crate::cfg::Cfg::default(),
+            golden_files: None,
+            limits: None,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            budget: None,
+            contract_mode: ContractMode::default(),
+            steps: 0,
+            effect_calls: BTreeMap::new(),
+            audit: None,
+            audit_site: None,
+            peak_memory: None,
+            debug_output: None,
+            debug_hook: None,
+            interceptors: Vec::new(),
+            bodies: NodeCache::new(),
+            scratch: Scratch::new(),
+            #[cfg(feature = "jit")]
+            jit: jit::Jit::new(),
+        }
+    }
+
+    /// Parse `source` as a single expression and evaluate it in the current
+    /// environment, so it sees the loaded module's functions and, while a
+    /// call is paused, that call's locals. A `?` or `return` at the top of the
+    /// expression gives the value it returns.
+    pub fn eval_str(&mut self, source: &str) -> Result<Value, RuntimeError> {
+        let expr = crate::parser::parse_expression(source, std::path::Path::new("<eval>"))
+            .map_err(|errors| match errors.diagnostics().first() {
+                Some(diagnostic) => RuntimeError::syntax_error(diagnostic),
+                None => RuntimeError::new("E0001", "expected an expression"),
+            })?;
+        match self.eval_expr(&expr) {
+            Err(error) if error.early_return.is_some() => {
+                Ok(error.get_early_return().unwrap_or(Value::Unit))
+            }
+            result => result,
         }
     }
 
     /// Evaluate an expression
     pub fn eval_expr(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        self.count_step()?;
         match expr {
             // Literals
             Expr::IntLit { value, .. } => Ok(Value::Int(*value)),
             Expr::FloatLit { value, .. } => Ok(Value::Float(*value)),
+            Expr::DecimalLit { value, .. } => Ok(Value::Decimal(*value)),
             Expr::BoolLit { value, .. } => Ok(Value::Bool(*value)),
             Expr::TextLit { value, .. } => Ok(Value::Text(value.clone())),
             Expr::UnitLit { .. } => Ok(Value::Unit),
@@ -127,8 +442,13 @@ impl Interpreter {
             Expr::Ident { name, .. } => {
                 // Check for effect names first
                 match name.as_str() {
-                    "Console" | "Fs" | "Net" | "Clock" | "Rand" | "Env" | "Map" | "Set" => {
-                        Ok(Value::Text(name.clone()))
+                    "Console" | "Fs" | "Net" | "Clock" | "Rand" | "Env" | "Crypto" | "Serve"
+                    | "Db" | "Progress" | "Metrics" => {
+                        Ok(Value::Capability(CapabilityValue::host(name)))
+                    }
+                    "Map" | "Set" => Ok(Value::Namespace(name.clone())),
+                    "Duration" | "Bytes" | "Ref" | "Args" if !self.env.contains(name) => {
+                        Ok(Value::Namespace(name.clone()))
                     }
                     // Option/Result constructors
                     "None" => Ok(Value::None),
@@ -147,11 +467,10 @@ impl Interpreter {
                     _ => {
                         // P6.2: Check user-defined effect names
                         if self.effect_defs.contains_key(name) {
-                            return Ok(Value::Text(name.clone()));
+                            return Ok(Value::Namespace(name.clone()));
                         }
                         self.env
                             .lookup(name)
-                            .cloned()
                             .ok_or_else(|| RuntimeError::undefined_variable(name))
                     }
                 }
@@ -234,12 +553,145 @@ impl Interpreter {
                             return if values_equal(&left, &right) {
                                 Ok(Value::Unit)
                             } else {
+                                Err(RuntimeError::new("E4021", assert_eq_message(&left, &right))
+                                    .with_span(call_span))
+                            };
+                        }
+                        "assert_ne" => {
+                            check_arity(args, 2)?;
+                            let left = self.eval_expr(&args[0])?;
+                            let right = self.eval_expr(&args[1])?;
+                            return if values_equal(&left, &right) {
                                 Err(RuntimeError::new(
                                     "E4021",
-                                    format!("assertion failed: {:?} != {:?}", left, right),
+                                    format!(
+                                        "assertion failed: both values are {}",
+                                        show_value(&left)
+                                    ),
                                 )
                                 .with_span(call_span))
+                            } else {
+                                Ok(Value::Unit)
+                            };
+                        }
+                        "assert_approx_eq" => {
+                            check_arity(args, 3)?;
+                            let mut numbers = Vec::with_capacity(3);
+                            for arg in args {
+                                numbers.push(match self.eval_expr(arg)? {
+                                    Value::Float(f) => f,
+                                    Value::Int(n) => n as f64,
+                                    other => {
+                                        return Err(RuntimeError::type_mismatch(
+                                            "Float",
+                                            &format!("{:?}", other),
+                                        )
+                                        .with_span(call_span))
+                                    }
+                                });
+                            }
+                            let (left, right, epsilon) = (numbers[0], numbers[1], numbers[2]);
+                            return if (left - right).abs() <= epsilon {
+                                Ok(Value::Unit)
+                            } else {
+                                Err(RuntimeError::new(
+                                    "E4021",
+                                    format!(
+                                        "assertion failed: {} and {} differ by {}, more than {}",
+                                        left,
+                                        right,
+                                        (left - right).abs(),
+                                        epsilon
+                                    ),
+                                )
+                                .with_span(call_span))
+                            };
+                        }
+                        "assert_contains" => {
+                            check_arity(args, 2)?;
+                            let haystack = self.eval_expr(&args[0])?;
+                            let needle = self.eval_expr(&args[1])?;
+                            let contains = match (&haystack, &needle) {
+                                (Value::Text(text), Value::Text(part)) => {
+                                    text.contains(part.as_str())
+                                }
+                                (Value::List(items), _) | (Value::Set(items), _) => {
+                                    items.iter().any(|item| values_equal(item, &needle))
+                                }
+                                (Value::Map(entries), _) => {
+                                    entries.iter().any(|(key, _)| values_equal(key, &needle))
+                                }
+                                _ => {
+                                    return Err(RuntimeError::type_mismatch(
+                                        "Text, List, Set, or Map",
+                                        &format!("{:?}", haystack),
+                                    )
+                                    .with_span(call_span))
+                                }
+                            };
+                            return if contains {
+                                Ok(Value::Unit)
+                            } else {
+                                Err(RuntimeError::new(
+                                    "E4020",
+                                    format!(
+                                        "assertion failed: {} does not contain {}",
+                                        show_value(&haystack),
+                                        show_value(&needle)
+                                    ),
+                                )
+                                .with_span(call_span))
+                            };
+                        }
+                        "assert_err_code" => {
+                            check_arity(args, 2)?;
+                            let code = match self.eval_expr(&args[1])? {
+                                Value::Text(code) => code,
+                                other => {
+                                    return Err(RuntimeError::type_mismatch(
+                                        "Text",
+                                        &format!("{:?}", other),
+                                    )
+                                    .with_span(call_span))
+                                }
+                            };
+                            let message = match self.eval_expr(&args[0]) {
+                                Err(e) if e.is_control_flow() => return Err(e),
+                                Err(e) if e.code == code => return Ok(Value::Unit),
+                                Err(e) => format!(
+                                    "assertion failed: expected error {}, got {}: {}",
+                                    code, e.code, e.message
+                                ),
+                                Ok(value) => format!(
+                                    "assertion failed: expected error {}, got {}",
+                                    code,
+                                    show_value(&value)
+                                ),
+                            };
+                            return Err(RuntimeError::new("E4020", message).with_span(call_span));
+                        }
+                        "dbg" => {
+                            check_arity(args, 1)?;
+                            let value = self.eval_expr(&args[0])?;
+                            self.trace(&args[0], &value);
+                            return Ok(value);
+                        }
+                        "assert_matches_file" => {
+                            check_arity(args, 2)?;
+                            let value = self.eval_expr(&args[0])?;
+                            let path = match self.eval_expr(&args[1])? {
+                                Value::Text(path) => path,
+                                other => {
+                                    return Err(RuntimeError::type_mismatch(
+                                        "Text",
+                                        &format!("{:?}", other),
+                                    )
+                                    .with_span(call_span))
+                                }
                             };
+                            return self
+                                .assert_matches_file(&value, &path)
+                                .map_err(|e| e.with_span(call_span));
                         }
                         // Option/Result constructors
                         "Some" => {
@@ -269,6 +721,7 @@ impl Interpreter {
                             }
                             if let Some(console) = &self.capabilities.console {
                                 console.print(&output);
+                                self.count_effect("Console")?;
                             }
                             return Ok(Value::Unit);
                         }
@@ -284,6 +737,7 @@ impl Interpreter {
                             }
                             if let Some(console) = &self.capabilities.console {
                                 console.println(&output);
+                                self.count_effect("Console")?;
                             }
                             return Ok(Value::Unit);
                         }
@@ -292,7 +746,7 @@ impl Interpreter {
                             check_arity(args, 1)?;
                             let val = self.eval_expr(&args[0])?;
                             return match val {
-                                Value::Text(s) => Ok(Value::Int(s.len() as i64)),
+                                Value::Text(s) => Ok(Value::Int(s.chars().count() as i64)),
                                 Value::List(l) => Ok(Value::Int(l.len() as i64)),
                                 Value::Tuple(t) => Ok(Value::Int(t.len() as i64)),
                                 Value::Map(m) => Ok(Value::Int(m.len() as i64)),
@@ -420,6 +874,94 @@ impl Interpreter {
                                 )),
                             };
                         }
+                        // User functions named `parse_int`/`parse_float` predate these
+                        "parse_int" if !self.env.contains(name) => {
+                            check_arity(args, 2)?;
+                            let text = self.eval_expr(&args[0])?;
+                            let base = self.eval_expr(&args[1])?;
+                            return match (&text, &base) {
+                                (Value::Text(s), Value::Int(base)) => numbers::parse_int(s, *base),
+                                _ => Err(RuntimeError::type_mismatch("(Text, Int)", "other")),
+                            };
+                        }
+                        "parse_float" if !self.env.contains(name) => {
+                            check_arity(args, 1)?;
+                            let val = self.eval_expr(&args[0])?;
+                            return match val {
+                                Value::Text(s) => Ok(numbers::parse_float(&s)),
+                                _ => {
+                                    Err(RuntimeError::type_mismatch("Text", &format!("{:?}", val)))
+                                }
+                            };
+                        }
+                        "base64_encode" | "hex_encode" => {
+                            check_arity(args, 1)?;
+                            let val = self.eval_expr(&args[0])?;
+                            let bytes = encoding::encoder_input(&val)?;
+                            return Ok(Value::Text(if name == "base64_encode" {
+                                encoding::base64_encode(bytes)
+                            } else {
+                                encoding::hex_encode(bytes)
+                            }));
+                        }
+                        "hash" if !self.env.contains(name) => {
+                            check_arity(args, 1)?;
+                            let val = self.eval_expr(&args[0])?;
+                            return hashing::structural_hash(&val).map(Value::Int);
+                        }
+                        "compress" if !self.env.contains(name) => {
+                            check_arity(args, 1)?;
+                            let val = self.eval_expr(&args[0])?;
+                            let bytes = encoding::encoder_input(&val)?;
+                            return Ok(Value::Bytes(compression::gzip(bytes)));
+                        }
+                        "decompress" if !self.env.contains(name) => {
+                            check_arity(args, 1)?;
+                            let val = self.eval_expr(&args[0])?;
+                            return match val {
+                                Value::Bytes(bytes) => Ok(match compression::gunzip(&bytes) {
+                                    Ok(out) => Value::Ok(Box::new(Value::Bytes(out))),
+                                    Err(e) => Value::Err(Box::new(Value::Text(e))),
+                                }),
+                                _ => {
+                                    Err(RuntimeError::type_mismatch("Bytes", &format!("{:?}", val)))
+                                }
+                            };
+                        }
+                        "sha256" => {
+                            check_arity(args, 1)?;
+                            let val = self.eval_expr(&args[0])?;
+                            let bytes = encoding::encoder_input(&val)?;
+                            return Ok(Value::Text(hashing::sha256_hex(bytes)));
+                        }
+                        "base64_decode" | "hex_decode" => {
+                            check_arity(args, 1)?;
+                            let val = self.eval_expr(&args[0])?;
+                            return match val {
+                                Value::Text(s) if name == "base64_decode" => {
+                                    Ok(encoding::base64_decode(&s))
+                                }
+                                Value::Text(s) => Ok(encoding::hex_decode(&s)),
+                                _ => {
+                                    Err(RuntimeError::type_mismatch("Text", &format!("{:?}", val)))
+                                }
+                            };
+                        }
+                        "to_decimal" => {
+                            check_arity(args, 1)?;
+                            let val = self.eval_expr(&args[0])?;
+                            return decimal::to_decimal(&val);
+                        }
+                        "parse_decimal" => {
+                            check_arity(args, 1)?;
+                            let val = self.eval_expr(&args[0])?;
+                            return match val {
+                                Value::Text(s) => Ok(numbers::parse_decimal(&s)),
+                                _ => {
+                                    Err(RuntimeError::type_mismatch("Text", &format!("{:?}", val)))
+                                }
+                            };
+                        }
                         // P3.6: Math functions for Float
                         "sqrt" => {
                             check_arity(args, 1)?;
@@ -471,7 +1013,8 @@ impl Interpreter {
                             check_arity(args, 1)?;
                             let path = self.eval_expr(&args[0])?;
                             if let Value::Text(p) = path {
-                                return self.call_fs_method("read", vec![Value::Text(p)]);
+                                self.at_call_site(&call_span);
+                                return self.call_effect("Fs", "read", vec![Value::Text(p)]);
                             }
                             return Err(RuntimeError::type_mismatch(
                                 "Text",
@@ -482,13 +1025,15 @@ impl Interpreter {
                             check_arity(args, 2)?;
                             let path = self.eval_expr(&args[0])?;
                             let content = self.eval_expr(&args[1])?;
-                            return self.call_fs_method("write", vec![path, content]);
+                            self.at_call_site(&call_span);
+                            return self.call_effect("Fs", "write", vec![path, content]);
                         }
                         "http_get" => {
                             check_arity(args, 1)?;
                             let url = self.eval_expr(&args[0])?;
                             if let Value::Text(u) = url {
-                                return self.call_net_method("get", vec![Value::Text(u)]);
+                                self.at_call_site(&call_span);
+                                return self.call_effect("Net", "get", vec![Value::Text(u)]);
                             }
                             return Err(RuntimeError::type_mismatch("Text", &format!("{:?}", url)));
                         }
@@ -496,30 +1041,98 @@ impl Interpreter {
                             check_arity(args, 2)?;
                             let url = self.eval_expr(&args[0])?;
                             let body = self.eval_expr(&args[1])?;
-                            return self.call_net_method("post", vec![url, body]);
+                            self.at_call_site(&call_span);
+                            return self.call_effect("Net", "post", vec![url, body]);
+                        }
+                        "rpc_call" if !self.env.contains(name) => {
+                            check_arity(args, 3)?;
+                            let endpoint = self.eval_expr(&args[0])?;
+                            let method = self.eval_expr(&args[1])?;
+                            let params = self.eval_expr(&args[2])?;
+                            self.at_call_site(&call_span);
+                            return match (endpoint, method) {
+                                (Value::Text(endpoint), Value::Text(method)) => self
+                                    .send_rpc(&endpoint, vec![(method, params)], false)
+                                    .map(|mut results| results.remove(0)),
+                                _ => {
+                                    Err(RuntimeError::type_mismatch("(Text, Text, Json)", "other"))
+                                }
+                            };
+                        }
+                        "rpc_batch" if !self.env.contains(name) => {
+                            check_arity(args, 2)?;
+                            let endpoint = self.eval_expr(&args[0])?;
+                            let calls = self.eval_expr(&args[1])?;
+                            let (Value::Text(endpoint), Value::List(calls)) = (endpoint, calls)
+                            else {
+                                return Err(RuntimeError::type_mismatch(
+                                    "(Text, List[(Text, Json)])",
+                                    "other",
+                                ));
+                            };
+                            let calls = calls
+                                .into_iter()
+                                .map(|call| match call {
+                                    Value::Tuple(mut pair) if pair.len() == 2 => {
+                                        match (pair.remove(0), pair.remove(0)) {
+                                            (Value::Text(method), params) => Ok((method, params)),
+                                            (other, _) => Err(RuntimeError::type_mismatch(
+                                                "Text method name",
+                                                &format_value(&other),
+                                            )),
+                                        }
+                                    }
+                                    other => Err(RuntimeError::type_mismatch(
+                                        "(Text, Json) call",
+                                        &format_value(&other),
+                                    )),
+                                })
+                                .collect::<Result<Vec<_>, _>>()?;
+                            self.at_call_site(&call_span);
+                            return self.send_rpc(&endpoint, calls, true).map(Value::List);
                         }
                         "random_int" => {
                             check_arity(args, 2)?;
                             let min = self.eval_expr(&args[0])?;
                             let max = self.eval_expr(&args[1])?;
-                            return self.call_rand_method("int", vec![min, max]);
+                            return self.call_effect("Rand", "int", vec![min, max]);
                         }
                         "random_bool" => {
                             if !args.is_empty() {
                                 return Err(RuntimeError::arity_mismatch(0, args.len()));
                             }
-                            return self.call_rand_method("bool", vec![]);
+                            return self.call_effect("Rand", "bool", vec![]);
                         }
                         "current_time_millis" => {
                             if !args.is_empty() {
                                 return Err(RuntimeError::arity_mismatch(0, args.len()));
                             }
-                            return self.call_clock_method("now", vec![]);
+                            return self.call_effect("Clock", "now", vec![]);
                         }
                         "get_env" => {
                             check_arity(args, 1)?;
                             let name = self.eval_expr(&args[0])?;
-                            return self.call_env_method("get", vec![name]);
+                            return self.call_effect("Env", "get", vec![name]);
+                        }
+                        "style" if !self.env.contains(name) => {
+                            check_arity(args, 3)?;
+                            let text = self.eval_expr(&args[0])?;
+                            let color = self.eval_expr(&args[1])?;
+                            let bold = self.eval_expr(&args[2])?;
+                            let (Value::Text(text), Value::Text(color), Value::Bool(bold)) =
+                                (text, color, bold)
+                            else {
+                                return Err(RuntimeError::type_mismatch(
+                                    "(Text, Text, Bool)",
+                                    "other",
+                                ));
+                            };
+                            let console =
+                                self.capabilities.console.as_ref().ok_or_else(|| {
+                                    RuntimeError::capability_not_available("Console")
+                                })?;
+                            return style::style(&text, &color, bold, console.color())
+                                .map(Value::Text);
                         }
                         // v1.1: Full JSON parsing
                         "json_parse" => {
@@ -595,7 +1208,7 @@ impl Interpreter {
                 }
 
                 let func_val = self.eval_expr(func)?;
-                let mut arg_vals = Vec::new();
+                let mut arg_vals = self.scratch.take();
                 for arg in args {
                     arg_vals.push(self.eval_expr(arg)?);
                 }
@@ -629,6 +1242,7 @@ impl Interpreter {
                 receiver,
                 method,
                 args,
+                span,
                 ..
             } => {
                 let recv = self.eval_expr(receiver)?;
@@ -636,6 +1250,7 @@ impl Interpreter {
                 for arg in args {
                     arg_vals.push(self.eval_expr(arg)?);
                 }
+                self.at_call_site(span);
                 self.call_method(&recv, method, arg_vals)
             }
 
@@ -668,34 +1283,16 @@ impl Interpreter {
                 let val = self.eval_expr(expr)?;
                 for arm in arms {
                     if let Some(bindings) = match_pattern(&arm.pattern, &val) {
-                        // Create new scope with pattern bindings
+                        // The pattern's bindings live in a scope of their own
                         self.env.push_scope();
                         for (name, value) in bindings {
                             self.env.define(name, value);
                         }
-
-                        // Check guard if present
-                        if let Some(guard) = &arm.guard {
-                            let guard_val = self.eval_expr(guard)?;
-                            match guard_val {
-                                Value::Bool(true) => {}
-                                Value::Bool(false) => {
-                                    self.env.pop_scope();
-                                    continue;
-                                }
-                                _ => {
-                                    self.env.pop_scope();
-                                    return Err(RuntimeError::type_mismatch(
-                                        "Bool",
-                                        &format!("{:?}", guard_val),
-                                    ));
-                                }
-                            }
-                        }
-
-                        let result = self.eval_expr(&arm.body);
+                        let result = self.eval_match_arm(arm);
                         self.env.pop_scope();
-                        return result;
+                        if let Some(value) = result? {
+                            return Ok(value);
+                        }
                     }
                 }
                 Err(RuntimeError::match_failure())
@@ -732,7 +1329,7 @@ impl Interpreter {
 
             // Record construction
             Expr::Record { fields, .. } => {
-                let mut field_values = HashMap::new();
+                let mut field_values = IndexMap::new();
                 for (name, value_expr) in fields {
                     let value = self.eval_expr(value_expr)?;
                     field_values.insert(name.clone(), value);
@@ -810,17 +1407,27 @@ impl Interpreter {
             }
 
             // Lambda expression
-            Expr::Lambda { params, body, .. } => {
+            Expr::Lambda {
+                id,
+                span,
+                params,
+                body,
+                ..
+            } => {
                 let param_names: Vec<String> = params.iter().map(|p| p.name.clone()).collect();
+                let body = self.bodies.alloc(*id, span, || ClosureBody {
+                    block: (**body).clone(),
+                    requires: Vec::new(),
+                    ensures: Vec::new(),
+                    public: false,
+                    captures: captured_names(&param_names, body),
+                });
+                let env = self.env.capture(body.captures.iter().map(String::as_str));
                 Ok(Value::Closure {
                     name: None,
                     params: param_names,
-                    body: ClosureBody {
-                        block: (**body).clone(),
-                        requires: Vec::new(),
-                        ensures: Vec::new(),
-                    },
-                    env: self.env.clone(),
+                    body,
+                    env,
                 })
             }
 
@@ -833,49 +1440,63 @@ impl Interpreter {
                 ..
             } => {
                 let iter_val = self.eval_expr(iter)?;
-                match iter_val {
-                    Value::List(items) => {
-                        'for_loop: for item in &items {
-                            // E8: If there's a destructuring pattern, match it
-                            if let Some(pat) = pattern {
-                                if let Some(bindings) = match_pattern(pat, item) {
-                                    for (name, val) in &bindings {
-                                        self.env.define(name.clone(), val.clone());
-                                    }
-                                } else {
-                                    return Err(RuntimeError::new(
-                                        "E4015",
-                                        "for loop destructuring pattern did not match value",
-                                    ));
-                                }
-                            } else {
-                                self.env.define(binding.clone(), item.clone());
-                            }
-                            for stmt in &body.stmts {
-                                match self.eval_stmt(stmt) {
-                                    Ok(()) => {}
-                                    Err(e) if e.is_break => break 'for_loop,
-                                    Err(e) if e.is_continue => continue 'for_loop,
-                                    Err(e) if e.is_return => return Err(e),
-                                    Err(e) => return Err(e),
-                                }
-                            }
-                            if let Some(expr) = &body.expr {
-                                match self.eval_expr(expr) {
-                                    Ok(_) => {}
-                                    Err(e) if e.is_break => break 'for_loop,
-                                    Err(e) if e.is_continue => continue 'for_loop,
-                                    Err(e) => return Err(e),
-                                }
-                            }
+                for item in iteration_items(iter_val)? {
+                    // E8: If there's a destructuring pattern, match it
+                    let bindings = match pattern {
+                        Some(pat) => match_pattern(pat, &item).ok_or_else(|| {
+                            RuntimeError::new(
+                                "E4015",
+                                "for loop destructuring pattern did not match value",
+                            )
+                        })?,
+                        None => vec![(binding.clone(), item)],
+                    };
+                    // Each iteration binds in a fresh scope, so the loop never
+                    // touches outer variables of the same name, and closures
+                    // created in the body capture that iteration's values
+                    self.env.push_scope();
+                    for (name, value) in bindings {
+                        self.env.define(name, value);
+                    }
+                    let result = self.eval_block(body);
+                    self.env.pop_scope();
+                    match result {
+                        Ok(_) => {}
+                        Err(e) if e.is_break => break,
+                        Err(e) if e.is_continue => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+                Ok(Value::Unit)
+            }
+
+            // Bounded quantifier: stops at the first element that settles it
+            Expr::Quantifier {
+                kind,
+                binding,
+                iter,
+                body,
+                ..
+            } => {
+                let iter_val = self.eval_expr(iter)?;
+                let settled_by = *kind == QuantifierKind::Exists;
+                for item in iteration_items(iter_val)? {
+                    self.env.push_scope();
+                    self.env.define(binding.clone(), item);
+                    let result = self.eval_expr(body);
+                    self.env.pop_scope();
+                    match result? {
+                        Value::Bool(b) if b == settled_by => return Ok(Value::Bool(settled_by)),
+                        Value::Bool(_) => {}
+                        other => {
+                            return Err(RuntimeError::type_mismatch(
+                                "Bool",
+                                &format!("{:?}", other),
+                            ))
                         }
-                        Ok(Value::Unit)
                     }
-                    _ => Err(RuntimeError::type_mismatch(
-                        "List",
-                        &format!("{:?}", iter_val),
-                    )),
                 }
+                Ok(Value::Bool(!settled_by))
             }
 
             // While loop
@@ -883,24 +1504,12 @@ impl Interpreter {
                 loop {
                     let condition = self.eval_expr(cond)?;
                     match condition {
-                        Value::Bool(true) => {
-                            for stmt in &body.stmts {
-                                match self.eval_stmt(stmt) {
-                                    Ok(()) => {}
-                                    Err(e) if e.is_break => return Ok(Value::Unit),
-                                    Err(e) if e.is_continue => break,
-                                    Err(e) => return Err(e),
-                                }
-                            }
-                            if let Some(expr) = &body.expr {
-                                match self.eval_expr(expr) {
-                                    Ok(_) => {}
-                                    Err(e) if e.is_break => return Ok(Value::Unit),
-                                    Err(e) if e.is_continue => {}
-                                    Err(e) => return Err(e),
-                                }
-                            }
-                        }
+                        Value::Bool(true) => match self.eval_block(body) {
+                            Ok(_) => {}
+                            Err(e) if e.is_break => break,
+                            Err(e) if e.is_continue => {}
+                            Err(e) => return Err(e),
+                        },
                         Value::Bool(false) => break,
                         _ => {
                             return Err(RuntimeError::type_mismatch(
@@ -977,15 +1586,13 @@ impl Interpreter {
                             Ok(items[actual_idx as usize].clone())
                         }
                     }
-                    (Value::Map(entries), key) => {
-                        match map_get(entries, key) {
-                            Some(v) => Ok(v.clone()),
-                            None => Err(RuntimeError::new(
-                                "E4002",
-                                format!("key not found in map: {}", format_value(key)),
-                            )),
-                        }
-                    }
+                    (Value::Map(entries), key) => match map_get(entries, key) {
+                        Some(v) => Ok(v.clone()),
+                        None => Err(RuntimeError::new(
+                            "E4002",
+                            format!("key not found in map: {}", format_value(key)),
+                        )),
+                    },
                     (Value::Text(s), Value::Int(i)) => {
                         let i = *i;
                         let chars: Vec<char> = s.chars().collect();
@@ -1040,14 +1647,29 @@ impl Interpreter {
             }
 
             Expr::Hole { .. } => Err(RuntimeError::hole_encountered()),
+
+            Expr::MacroCall {
+                name, expansion, ..
+            } => match expansion {
+                Some(expansion) => self.eval_expr(expansion),
+                None => Err(RuntimeError::new(
+                    "E0012",
+                    format!("macro `{}!` was never expanded", name),
+                )),
+            },
         }
     }
 
     /// Evaluate a statement
     pub fn eval_stmt(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
+        self.debug_stop(stmt.span())?;
         match stmt {
             Stmt::Let {
-                name, value, ty, ..
+                name,
+                value,
+                ty,
+                mutable,
+                ..
             } => {
                 let mut val = self.eval_expr(value)?;
                 // P2.3: Check type invariant if type annotation matches a type with invariant
@@ -1068,7 +1690,11 @@ impl Interpreter {
                         *closure_name = Some(name.clone());
                     }
                 }
-                self.env.define(name.clone(), val);
+                if *mutable {
+                    self.env.define_mut(name.clone(), val);
+                } else {
+                    self.env.define(name.clone(), val);
+                }
                 Ok(())
             }
             Stmt::LetPattern { pattern, value, .. } => {
@@ -1088,12 +1714,10 @@ impl Interpreter {
             Stmt::Assign { target, value, .. } => {
                 let val = self.eval_expr(value)?;
                 match target.as_ref() {
-                    Expr::Ident { name, .. } => {
-                        if !self.env.update(name, val) {
-                            return Err(RuntimeError::undefined_variable(name));
-                        }
-                        Ok(())
-                    }
+                    Expr::Ident { name, .. } => self.env.update(name, val).map_err(|e| match e {
+                        UpdateError::Undefined => RuntimeError::undefined_variable(name),
+                        UpdateError::Immutable => RuntimeError::immutable_assignment(name),
+                    }),
                     _ => Err(RuntimeError::new("E4014", "invalid assignment target")),
                 }
             }
@@ -1112,6 +1736,19 @@ impl Interpreter {
         }
     }
 
+    /// The value of a match arm whose pattern matched, with its bindings in
+    /// scope, or `None` if its guard is false
+    fn eval_match_arm(&mut self, arm: &MatchArm) -> Result<Option<Value>, RuntimeError> {
+        if let Some(guard) = &arm.guard {
+            match self.eval_expr(guard)? {
+                Value::Bool(true) => {}
+                Value::Bool(false) => return Ok(None),
+                other => return Err(RuntimeError::type_mismatch("Bool", &format!("{:?}", other))),
+            }
+        }
+        self.eval_expr(&arm.body).map(Some)
+    }
+
     /// Evaluate a block
     pub fn eval_block(&mut self, block: &Block) -> Result<Value, RuntimeError> {
         // Create a new scope for the block
@@ -1130,7 +1767,10 @@ impl Interpreter {
 
         // Evaluate trailing expression or return Unit
         let result = if let Some(expr) = &block.expr {
-            match self.eval_expr(expr) {
+            match self
+                .debug_stop(expr.span())
+                .and_then(|()| self.eval_expr(expr))
+            {
                 Ok(val) => val,
                 Err(e) => {
                     self.env.pop_scope();
@@ -1193,6 +1833,28 @@ impl Interpreter {
             (BinaryOp::Div, Value::Int(a), Value::Float(b)) => Ok(Value::Float(*a as f64 / b)),
             (BinaryOp::Div, Value::Float(a), Value::Int(b)) => Ok(Value::Float(a / *b as f64)),
 
+            // Decimal arithmetic and comparison (Int promotes to Decimal, Float never does)
+            (_, Value::Decimal(a), Value::Decimal(b)) => decimal::decimal_binary_op(op, *a, *b),
+            (_, Value::Decimal(a), Value::Int(b)) => {
+                decimal::decimal_binary_op(op, *a, rust_decimal::Decimal::from(*b))
+            }
+            (_, Value::Int(a), Value::Decimal(b)) => {
+                decimal::decimal_binary_op(op, rust_decimal::Decimal::from(*a), *b)
+            }
+
+            // Duration arithmetic and comparison
+            (_, Value::Duration(_), _) | (_, _, Value::Duration(_))
+                if !matches!(op, BinaryOp::Eq | BinaryOp::Ne)
+                    || matches!((left, right), (Value::Duration(_), Value::Duration(_))) =>
+            {
+                duration::duration_binary_op(op, left, right)
+            }
+
+            // Byte concatenation
+            (BinaryOp::Add, Value::Bytes(a), Value::Bytes(b)) => {
+                Ok(Value::Bytes([a.as_slice(), b.as_slice()].concat()))
+            }
+
             // String concatenation
             (BinaryOp::Add, Value::Text(a), Value::Text(b)) => {
                 Ok(Value::Text(format!("{}{}", a, b)))
@@ -1245,6 +1907,11 @@ impl Interpreter {
         match (op, val) {
             (UnaryOp::Neg, Value::Int(n)) => Ok(Value::Int(-n)),
             (UnaryOp::Neg, Value::Float(n)) => Ok(Value::Float(-n)),
+            (UnaryOp::Neg, Value::Decimal(n)) => Ok(Value::Decimal(-n)),
+            (UnaryOp::Neg, Value::Duration(n)) => n
+                .checked_neg()
+                .map(Value::Duration)
+                .ok_or_else(|| RuntimeError::arithmetic_overflow("duration", "negation")),
             (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
             _ => Err(RuntimeError::type_mismatch(
                 &format!("valid type for {:?}", op),
@@ -1268,7 +1935,7 @@ impl Interpreter {
                     })
                 } else {
                     // Multi-field variant: store as Record
-                    let mut fields = HashMap::new();
+                    let mut fields = IndexMap::new();
                     for (field_name, arg) in field_names.iter().zip(args) {
                         fields.insert(field_name.clone(), arg);
                     }
@@ -1288,17 +1955,53 @@ impl Interpreter {
                     return Err(RuntimeError::arity_mismatch(params.len(), args.len()));
                 }
 
+                if self
+                    .limits
+                    .is_some_and(|limits| self.call_stack.len() >= limits.max_call_depth)
+                {
+                    return Err(RuntimeError::resource_limit_exceeded("call depth"));
+                }
+                if self.call_stack.len() >= self.max_call_depth {
+                    return Err(RuntimeError::stack_overflow(self.max_call_depth));
+                }
+
+                // Limits, budgets, cost reports, and the debugger count or stop
+                // at every step, so they keep the whole run interpreted
+                #[cfg(feature = "jit")]
+                if self.limits.is_none()
+                    && self.budget.is_none()
+                    && self.peak_memory.is_none()
+                    && self.debug_hook.is_none()
+                {
+                    let depth = self.max_call_depth - self.call_stack.len();
+                    match self.jit.call(&body, &args, depth) {
+                        Some(jit::Native::Returned(value)) => {
+                            self.scratch.give(args);
+                            return Ok(value);
+                        }
+                        Some(jit::Native::TooDeep) => {
+                            return Err(RuntimeError::stack_overflow(self.max_call_depth));
+                        }
+                        None => {}
+                    }
+                }
+
                 let fn_name = name.as_deref().unwrap_or("<anonymous>");
 
                 // Push call stack frame (P5.2: stack traces)
                 let frame_span = Some(body.block.span.clone());
-                self.call_stack
-                    .push(CallFrame::new(fn_name, frame_span));
+                self.call_stack.push(CallFrame::new(fn_name, frame_span));
+
+                let (requires, ensures): (&[Expr], &[Expr]) = if self.contract_mode.checks(&body) {
+                    (&body.requires, &body.ensures)
+                } else {
+                    (&[], &[])
+                };
 
                 // P6.4: TCO - detect simple self-recursive tail calls
                 let use_tco = name.is_some()
-                    && body.requires.is_empty()
-                    && body.ensures.is_empty()
+                    && requires.is_empty()
+                    && ensures.is_empty()
                     && Self::has_self_tail_call(&body.block, fn_name);
 
                 let mut current_args = args;
@@ -1314,10 +2017,13 @@ impl Interpreter {
 
                     // Push a new scope for params and local bindings
                     self.env.push_scope();
+                    if let Some(frame) = self.call_stack.last_mut() {
+                        frame.scope_depth = self.env.scope_depth() - 1;
+                    }
 
                     // For named closures, define self in the call env to enable recursion
                     if let Some(ref fn_name_str) = name {
-                        if self.env.lookup(fn_name_str).is_none() {
+                        if !self.env.contains(fn_name_str) {
                             self.env.define(
                                 fn_name_str.clone(),
                                 Value::Closure {
@@ -1334,8 +2040,8 @@ impl Interpreter {
                     }
 
                     // Check preconditions (requires clauses)
-                    if !body.requires.is_empty() {
-                        for req_expr in &body.requires {
+                    if !requires.is_empty() {
+                        for req_expr in requires {
                             let cond = self.eval_expr(req_expr)?;
                             match cond {
                                 Value::Bool(true) => {}
@@ -1430,11 +2136,13 @@ impl Interpreter {
                     Err(e) if e.is_return => Ok(e.get_early_return().unwrap_or(Value::Unit)),
                     Err(e) if e.is_early_return() => Ok(e.get_early_return().unwrap()),
                     Err(mut e) => {
-                        // Attach stack trace to error (P5.2)
-                        if !e.is_break && !e.is_continue {
+                        // Attach stack trace to error (P5.2), once, from the
+                        // innermost frame it passes through
+                        if !e.is_break && !e.is_continue && !e.has_stack_trace {
                             let trace = self.format_stack_trace();
                             if !trace.is_empty() {
                                 e.message = format!("{}\n{}", e.message, trace);
+                                e.has_stack_trace = true;
                             }
                         }
                         self.call_stack.pop();
@@ -1444,7 +2152,7 @@ impl Interpreter {
                 }?;
 
                 // Check postconditions (ensures clauses)
-                if !body.ensures.is_empty() {
+                if !ensures.is_empty() {
                     // Re-evaluate in closure env with params + result
                     let saved_env = if uses_closure_env {
                         Some(std::mem::replace(&mut self.env, env.clone()))
@@ -1456,7 +2164,7 @@ impl Interpreter {
                         self.env.define(param.clone(), arg.clone());
                     }
                     self.env.define("result".to_string(), result.clone());
-                    for ens_expr in &body.ensures {
+                    for ens_expr in ensures {
                         let cond = self.eval_expr(ens_expr)?;
                         match cond {
                             Value::Bool(true) => {}
@@ -1487,6 +2195,7 @@ impl Interpreter {
 
                 // Pop call stack frame
                 self.call_stack.pop();
+                self.scratch.give(current_args);
                 Ok(result)
             }
             _ => Err(RuntimeError::not_callable()),
@@ -1525,6 +2234,7 @@ impl Interpreter {
 
     /// P6.4: Evaluate an expression with TCO awareness
     fn eval_expr_tco(&mut self, expr: &Expr, fn_name: &str) -> Result<TcoResult, RuntimeError> {
+        self.count_step()?;
         match expr {
             Expr::Call { func, args, .. } => {
                 if let Expr::Ident { name, .. } = func.as_ref() {
@@ -1602,13 +2312,313 @@ impl Interpreter {
         }
     }
 
-    /// Get the current call stack as a formatted string
+    /// Count one evaluation step, checking it against the limits if any
+    fn count_step(&mut self) -> Result<(), RuntimeError> {
+        self.steps += 1;
+        if let (Some(peak), Some(bytes)) = (self.peak_memory.as_mut(), self.env.tracked_bytes()) {
+            *peak = (*peak).max(bytes);
+        }
+        if let Some(budget) = &self.budget {
+            if let Some(max) = budget.max_steps.filter(|&max| self.steps > max) {
+                return Err(RuntimeError::budget_exceeded("max_steps", max));
+            }
+            if let Some(max) = budget
+                .max_memory
+                .filter(|&max| self.peak_memory.is_some_and(|peak| peak > max))
+            {
+                return Err(RuntimeError::budget_exceeded("max_memory", max as u64));
+            }
+        }
+        if self
+            .limits
+            .is_some_and(|limits| self.steps > limits.max_steps)
+        {
+            return Err(RuntimeError::resource_limit_exceeded("evaluation steps"));
+        }
+        Ok(())
+    }
+
+    /// Number of expressions evaluated so far
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Fail if a scripted console has steps the run never reached, such as
+    /// output it expected after the last read
+    pub fn finish_console_script(&self) -> Result<(), RuntimeError> {
+        match self
+            .capabilities
+            .console
+            .as_ref()
+            .and_then(|console| console.finish_script())
+        {
+            Some(failure) => Err(RuntimeError::new("E4020", failure)),
+            None => Ok(()),
+        }
+    }
+
+    /// Count a call to an effect operation, checking it against the budget
+    pub(crate) fn count_effect(&mut self, effect: &str) -> Result<(), RuntimeError> {
+        let calls = self.effect_calls.entry(effect.to_string()).or_default();
+        *calls += 1;
+        if let Some(budget) = &self.budget {
+            let key = effect.to_ascii_lowercase();
+            if let Some(&max) = budget.max_effect_calls.get(&key) {
+                if *calls > max {
+                    return Err(RuntimeError::budget_exceeded(
+                        &format!("max_effect_calls.{}", key),
+                        max,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Choose which `requires`/`ensures` clauses are checked from now on
+    pub fn set_contract_mode(&mut self, mode: ContractMode) {
+        self.contract_mode = mode;
+    }
+
+    /// Which contracts are being checked
+    pub fn contract_mode(&self) -> ContractMode {
+        self.contract_mode
+    }
+
+    /// Enforce budgets from the manifest's `[limits]`
+    pub fn set_budget(&mut self, budget: Budget) {
+        if budget.max_memory.is_some() && self.peak_memory.is_none() {
+            self.track_memory();
+        }
+        self.budget = Some(budget);
+    }
+
+    /// Estimate the memory bound in the environment from now on, for
+    /// [`CostReport::peak_memory`]
+    pub fn track_memory(&mut self) {
+        self.env.track_memory();
+        self.peak_memory = self.env.tracked_bytes();
+    }
+
+    /// Number of routes registered with `Serve.route`
+    pub fn route_count(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Answer one request with the first registered route whose pattern
+    /// matches its path: 404 if none does, 405 if none allows its method.
+    /// Errors in the handler are returned rather than turned into a response.
+    pub fn dispatch_request(
+        &mut self,
+        request: &HttpRequest,
+    ) -> Result<HttpResponse, RuntimeError> {
+        self.count_effect("Serve")?;
+        let path = request.url.split('?').next().unwrap_or_default();
+        let mut path_matched = false;
+        let mut matched = None;
+        for route in &self.routes {
+            if let Some(params) = http::match_route(&route.pattern, path) {
+                path_matched = true;
+                if route.method == "*" || route.method == request.method {
+                    matched = Some((route.handler.clone(), params));
+                    break;
+                }
+            }
+        }
+        let Some((handler, params)) = matched else {
+            return Ok(if path_matched {
+                http::text_response(405, "Method Not Allowed")
+            } else {
+                http::text_response(404, "Not Found")
+            });
+        };
+        let response = self.call_function(handler, vec![http::request_value(request, params)])?;
+        http::response_from_value(&response)
+    }
+
+    /// Answer requests from the Serve capability with the registered routes
+    /// until it has no more. A handler that fails gets a 500 response, and
+    /// serving goes on.
+    pub fn serve_requests(&mut self) -> Result<(), RuntimeError> {
+        loop {
+            let serve = self
+                .capabilities
+                .serve
+                .as_ref()
+                .ok_or_else(|| RuntimeError::capability_not_available("Serve"))?;
+            let Some(request) = serve.next_request() else {
+                return Ok(());
+            };
+            let response = match self.dispatch_request(&request) {
+                Ok(response) => response,
+                // A request over budget ends the run like any other call
+                Err(e) if e.code == "E4030" => return Err(e),
+                Err(e) => {
+                    eprintln!("{} {}: {}", request.method, request.url, e);
+                    http::text_response(500, "Internal Server Error")
+                }
+            };
+            if let Some(serve) = &self.capabilities.serve {
+                serve.respond(response);
+            }
+        }
+    }
+
+    /// Steps, effect calls, and peak memory so far
+    pub fn cost(&self) -> CostReport {
+        CostReport {
+            steps: self.steps,
+            effect_calls: self.effect_calls.clone(),
+            peak_memory: self.peak_memory,
+        }
+    }
+
+    /// Stop evaluation with a runtime error once a limit is exceeded
+    pub fn set_limits(&mut self, limits: EvalLimits) {
+        self.limits = Some(limits);
+    }
+
+    /// Stop a program with a stack overflow error (E4028) when calls nest
+    /// deeper than `depth`, instead of [`DEFAULT_MAX_CALL_DEPTH`]
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    /// Compile hot functions after `calls` interpreted calls instead of
+    /// [`jit::JIT_THRESHOLD`]
+    #[cfg(feature = "jit")]
+    pub fn set_jit_threshold(&mut self, calls: u32) {
+        self.jit.set_threshold(calls);
+    }
+
+    /// Names of the functions the JIT has compiled so far, sorted
+    #[cfg(feature = "jit")]
+    pub fn jit_compiled(&self) -> Vec<&str> {
+        self.jit.compiled()
+    }
+
+    /// Write a `dbg` trace of `expr` and its value to the debug stream
+    fn trace(&self, expr: &Expr, value: &Value) {
+        let span = expr.span();
+        let line = format!(
+            "[{}:{}:{}] {} = {}",
+            span.file.display(),
+            span.start_line,
+            span.start_col,
+            crate::formatter::Formatter::new().expr_text(expr),
+            show_value(value)
+        );
+        match &self.debug_output {
+            Some(output) => output.borrow_mut().push(line),
+            None => eprintln!("{}", line),
+        }
+    }
+
+    /// Pause in the debug hook, if any, before evaluating the code at `span`
+    fn debug_stop(&mut self, span: &Span) -> Result<(), RuntimeError> {
+        if let Some(mut hook) = self.debug_hook.take() {
+            let result = hook.on_stop(self, span);
+            self.debug_hook = Some(hook);
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Step through evaluation with a debugger
+    pub fn set_debug_hook(&mut self, hook: Box<dyn DebugHook>) {
+        self.debug_hook = Some(hook);
+    }
+
+    /// Number of function calls in progress
+    pub fn call_depth(&self) -> usize {
+        self.call_stack.len()
+    }
+
+    /// Bindings of the innermost call in progress (or of the top-level code),
+    /// innermost scope first
+    pub fn frame_bindings(&self) -> Vec<(&str, Value)> {
+        let from = self.call_stack.last().map_or(1, |frame| frame.scope_depth);
+        self.env.bindings_from(from)
+    }
+
+    /// Calls in progress, outermost first
+    pub fn call_stack(&self) -> &[CallFrame] {
+        &self.call_stack
+    }
+
+    /// Collect `dbg` traces instead of writing them to stderr
+    pub fn capture_debug_output(&mut self) -> std::rc::Rc<std::cell::RefCell<Vec<String>>> {
+        self.debug_output
+            .get_or_insert_with(Default::default)
+            .clone()
+    }
+
+    /// Enable `assert_matches_file` with the given golden files
+    pub fn set_golden_files(&mut self, golden_files: GoldenFiles) {
+        self.golden_files = Some(golden_files);
+    }
+
+    /// Compare `value` (its text, or its printed form) with a golden file,
+    /// or write it to the file when golden files are being updated
+    fn assert_matches_file(&self, value: &Value, path: &str) -> Result<Value, RuntimeError> {
+        let golden = self.golden_files.as_ref().ok_or_else(|| {
+            RuntimeError::capability_not_available("golden files (only in `astra test`)")
+        })?;
+        let actual = match value {
+            Value::Text(text) => text.clone(),
+            other => format_value(other),
+        };
+        if golden.update {
+            golden
+                .fs
+                .write(path, &actual)
+                .map_err(|e| RuntimeError::new("E4021", e))?;
+            return Ok(Value::Unit);
+        }
+        if !golden.fs.exists(path) {
+            return Err(RuntimeError::new(
+                "E4021",
+                format!(
+                    "golden file '{}' does not exist (run `astra test --update-golden` to create it)",
+                    path
+                ),
+            ));
+        }
+        let expected = golden
+            .fs
+            .read(path)
+            .map_err(|e| RuntimeError::new("E4021", e))?;
+        if expected == actual {
+            return Ok(Value::Unit);
+        }
+        let diff = crate::text_diff::TextDiff::new(&expected, &actual).unified(path, "actual");
+        Err(RuntimeError::new(
+            "E4021",
+            format!(
+                "value does not match golden file '{}' (run `astra test --update-golden` to accept it)\n{}",
+                path,
+                diff.trim_end()
+            ),
+        ))
+    }
+
+    /// Get the current call stack as a formatted string, innermost call
+    /// first. Deep stacks keep their innermost and outermost frames and
+    /// elide the middle.
     pub fn format_stack_trace(&self) -> String {
         if self.call_stack.is_empty() {
             return String::new();
         }
         let mut trace = String::from("Stack trace:\n");
+        let total = self.call_stack.len();
+        let elided = total.saturating_sub(STACK_TRACE_INNERMOST + STACK_TRACE_OUTERMOST);
         for (i, frame) in self.call_stack.iter().rev().enumerate() {
+            if elided > 0 && i >= STACK_TRACE_INNERMOST && i < total - STACK_TRACE_OUTERMOST {
+                if i == STACK_TRACE_INNERMOST {
+                    trace.push_str(&format!("  ... {} more frames\n", elided));
+                }
+                continue;
+            }
             if let Some(span) = &frame.span {
                 trace.push_str(&format!(
                     "  {}: {} ({}:{}:{})\n",
@@ -92,13 +92,15 @@ impl Interpreter {
 
         let imported_module = self.parse_module_file(&file_path)?;
 
-        self.load_module(&imported_module)?;
+        self.load_module_with_filter(&imported_module, None)?;
         self.loading_modules.remove(&module_key);
         self.loaded_modules.insert(module_key);
         Ok(())
     }
 
     pub fn load_module(&mut self, module: &Module) -> Result<(), RuntimeError> {
+        // Bodies from earlier loads stay with the closures that hold them
+        self.bodies.clear();
         self.load_module_with_filter(module, None)
     }
 
@@ -108,8 +110,15 @@ impl Interpreter {
         module: &Module,
         filter: Option<&[String]>,
     ) -> Result<(), RuntimeError> {
+        // Items whose `@cfg(...)` doesn't hold are never loaded
+        let items: Vec<&Item> = module
+            .items
+            .iter()
+            .filter(|item| self.cfg.includes(item))
+            .collect();
+
         // Process imports first (P4.1: named import resolution)
-        for item in &module.items {
+        for item in &items {
             if let Item::Import(import) = item {
                 match &import.kind {
                     ImportKind::Module => {
@@ -126,8 +135,16 @@ impl Interpreter {
             }
         }
 
+        // Traits come first so impls anywhere in the module inherit their contracts
+        for item in &items {
+            if let Item::TraitDef(trait_def) = item {
+                self.trait_defs
+                    .insert(trait_def.name.clone(), trait_def.clone());
+            }
+        }
+
         // Collect all function and enum definitions into the environment
-        for item in &module.items {
+        for item in &items {
             match item {
                 Item::FnDef(fn_def) => {
                     // Apply filter if specified
@@ -139,37 +156,15 @@ impl Interpreter {
                     let params: Vec<String> =
                         fn_def.params.iter().map(|p| p.name.clone()).collect();
 
-                    // Build body block, prepending destructuring for pattern params
-                    let mut body_block = fn_def.body.clone();
-                    let mut destructure_stmts = Vec::new();
-                    for param in &fn_def.params {
-                        if let Some(ref pattern) = param.pattern {
-                            destructure_stmts.push(Stmt::LetPattern {
-                                id: NodeId::new(),
-                                span: param.span.clone(),
-                                pattern: pattern.clone(),
-                                ty: None,
-                                value: Box::new(Expr::Ident {
-                                    id: NodeId::new(),
-                                    span: param.span.clone(),
-                                    name: param.name.clone(),
-                                }),
-                            });
-                        }
-                    }
-                    if !destructure_stmts.is_empty() {
-                        destructure_stmts.extend(body_block.stmts);
-                        body_block.stmts = destructure_stmts;
-                    }
-
+                    let body = self
+                        .bodies
+                        .alloc(fn_def.id, &fn_def.span, || fn_body(fn_def));
+                    #[cfg(feature = "jit")]
+                    self.jit.register(&body, fn_def);
                     let closure = Value::Closure {
                         name: Some(fn_def.name.clone()),
                         params,
-                        body: ClosureBody {
-                            block: body_block,
-                            requires: fn_def.requires.clone(),
-                            ensures: fn_def.ensures.clone(),
-                        },
+                        body,
                         env: Environment::new(), // Will use global env at call time
                     };
                     // v1.1: Track async functions
@@ -215,18 +210,29 @@ impl Interpreter {
                         _ => "Unknown".to_string(),
                     };
 
+                    let trait_def = self.trait_defs.get(&impl_block.trait_name);
                     let mut methods_map = std::collections::HashMap::new();
                     for method in &impl_block.methods {
                         let params: Vec<String> =
                             method.params.iter().map(|p| p.name.clone()).collect();
+                        // The trait's clauses are checked before the impl's own
+                        let (mut requires, mut ensures) = trait_def
+                            .and_then(|t| t.methods.iter().find(|m| m.name == method.name))
+                            .map(|m| m.contracts_for(method))
+                            .unwrap_or_default();
+                        requires.extend(method.requires.iter().cloned());
+                        ensures.extend(method.ensures.iter().cloned());
                         let closure = Value::Closure {
                             name: Some(method.name.clone()),
                             params,
-                            body: ClosureBody {
+                            body: self.bodies.alloc(method.id, &method.span, || ClosureBody {
                                 block: method.body.clone(),
-                                requires: method.requires.clone(),
-                                ensures: method.ensures.clone(),
-                            },
+                                requires,
+                                ensures,
+                                // Callers reach it through the trait, from anywhere
+                                public: true,
+                                captures: Vec::new(),
+                            }),
                             env: Environment::new(),
                         };
                         // Register with qualified name for backward compat
@@ -307,7 +313,7 @@ impl Interpreter {
             // Module already executed — just import the requested names from cached env
             if let Some(module_env) = self.loaded_module_envs.get(&module_key).cloned() {
                 for name in names {
-                    if let Some(value) = module_env.lookup(name).cloned() {
+                    if let Some(value) = module_env.lookup(name) {
                         let imported_value = rebind_closure_env(value, &module_env);
                         self.env.define(name.clone(), imported_value);
                     }
@@ -327,14 +333,14 @@ impl Interpreter {
         // Load ALL module definitions into a temporary child environment so that
         // internal dependencies between module functions are preserved.
         let saved_env = std::mem::replace(&mut self.env, Environment::new());
-        self.load_module(&imported_module)?;
+        self.load_module_with_filter(&imported_module, None)?;
         let module_env = self.env.clone();
         self.env = saved_env;
 
         // Import only the requested names, capturing the module env in closures
         // so they can access module-internal functions at call time.
         for name in names {
-            if let Some(value) = module_env.lookup(name).cloned() {
+            if let Some(value) = module_env.lookup(name) {
                 let imported_value = rebind_closure_env(value, &module_env);
                 self.env.define(name.clone(), imported_value);
             }
@@ -349,27 +355,54 @@ impl Interpreter {
         self.search_paths.push(path);
     }
 
-    /// Evaluate a module
+    /// Set the configuration for `@cfg(...)` items (none are test-only by default)
+    pub fn set_cfg(&mut self, cfg: crate::cfg::Cfg) {
+        self.cfg = cfg;
+    }
+
+    /// Evaluate a module, running its `main` function if it defines one
     pub fn eval_module(&mut self, module: &Module) -> Result<Value, RuntimeError> {
-        // Load the module definitions
+        self.load_program(module)?;
+        if self.env.lookup("main").is_none() {
+            return Ok(Value::Unit);
+        }
+        self.run_entry("main")
+    }
+
+    /// Evaluate a module, running `entry` as its main function. It is an
+    /// error for the module not to define `entry` without parameters.
+    pub fn eval_module_entry(
+        &mut self,
+        module: &Module,
+        entry: &str,
+    ) -> Result<Value, RuntimeError> {
+        self.load_program(module)?;
+        self.run_entry(entry)
+    }
+
+    /// Load the definitions of a module run as a program
+    fn load_program(&mut self, module: &Module) -> Result<(), RuntimeError> {
         self.load_module(module)?;
+        self.program_name = module.name.segments.last().cloned();
+        Ok(())
+    }
 
-        // Look for and run main function if it exists
-        if let Some(Value::Closure { params, body, .. }) = self.env.lookup("main").cloned() {
-            if params.is_empty() {
-                // Execute in a child of the global environment
-                self.env.push_scope();
-                let result = self.eval_block(&body.block);
-                self.env.pop_scope();
-                // Handle early returns from ? operator
-                return match result {
-                    Err(e) if e.is_early_return() => Ok(e.get_early_return().unwrap()),
-                    other => other,
-                };
-            }
+    fn run_entry(&mut self, entry: &str) -> Result<Value, RuntimeError> {
+        let Some(Value::Closure { params, body, .. }) = self.env.lookup(entry) else {
+            return Err(RuntimeError::missing_entry(entry));
+        };
+        if !params.is_empty() {
+            return Err(RuntimeError::entry_takes_parameters(entry));
+        }
+        // Execute in a child of the global environment
+        self.env.push_scope();
+        let result = self.eval_block(&body.block);
+        self.env.pop_scope();
+        // Handle early returns from ? operator
+        match result {
+            Err(e) if e.is_early_return() => Ok(e.get_early_return().unwrap()),
+            other => other,
         }
-
-        Ok(Value::Unit)
     }
 }
 
@@ -391,3 +424,37 @@ fn rebind_closure_env(value: Value, env: &Environment) -> Value {
         other => other,
     }
 }
+
+/// The body a function value runs, prepending destructuring for pattern params
+fn fn_body(fn_def: &FnDef) -> ClosureBody {
+    let mut body_block = fn_def.body.clone();
+    let mut destructure_stmts = Vec::new();
+    for param in &fn_def.params {
+        if let Some(ref pattern) = param.pattern {
+            destructure_stmts.push(Stmt::LetPattern {
+                id: NodeId::new(),
+                span: param.span.clone(),
+                pattern: pattern.clone(),
+                ty: None,
+                value: Box::new(Expr::Ident {
+                    id: NodeId::new(),
+                    span: param.span.clone(),
+                    name: param.name.clone(),
+                }),
+                attributes: Vec::new(),
+            });
+        }
+    }
+    if !destructure_stmts.is_empty() {
+        destructure_stmts.extend(body_block.stmts);
+        body_block.stmts = destructure_stmts;
+    }
+
+    ClosureBody {
+        block: body_block,
+        requires: fn_def.requires.clone(),
+        ensures: fn_def.ensures.clone(),
+        public: fn_def.visibility == Visibility::Public,
+        captures: Vec::new(),
+    }
+}
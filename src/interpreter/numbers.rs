@@ -0,0 +1,112 @@
+//! Text-to-number parsing for Astra values.
+//!
+//! `parse_int`, `parse_float`, and `parse_decimal` return `Result` values whose errors are
+//! `ParseError` variants: `Empty`, `InvalidDigit(index)` with the index of
+//! the offending character, or `Overflow`.
+
+use rust_decimal::Decimal;
+
+use super::error::RuntimeError;
+use super::value::Value;
+
+fn parse_error(name: &str, index: Option<usize>) -> Value {
+    Value::Err(Box::new(Value::Variant {
+        name: name.to_string(),
+        data: index.map(|i| Box::new(Value::Int(i as i64))),
+    }))
+}
+
+/// Parse `text` as a signed integer in `base` (2 to 36)
+pub(super) fn parse_int(text: &str, base: i64) -> Result<Value, RuntimeError> {
+    if !(2..=36).contains(&base) {
+        return Err(RuntimeError::type_mismatch(
+            "base between 2 and 36",
+            &base.to_string(),
+        ));
+    }
+    if text.is_empty() {
+        return Ok(parse_error("Empty", None));
+    }
+    let negative = text.starts_with('-');
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    let offset = text.len() - digits.len();
+    if digits.is_empty() {
+        return Ok(parse_error("InvalidDigit", Some(0)));
+    }
+    let mut value: i64 = 0;
+    for (i, c) in digits.chars().enumerate() {
+        let Some(digit) = c.to_digit(base as u32) else {
+            return Ok(parse_error("InvalidDigit", Some(i + offset)));
+        };
+        let next = value.checked_mul(base).and_then(|v| {
+            if negative {
+                v.checked_sub(digit as i64)
+            } else {
+                v.checked_add(digit as i64)
+            }
+        });
+        match next {
+            Some(next) => value = next,
+            None => return Ok(parse_error("Overflow", None)),
+        }
+    }
+    Ok(Value::Ok(Box::new(Value::Int(value))))
+}
+
+/// Parse `text` as a decimal floating-point number
+pub(super) fn parse_float(text: &str) -> Value {
+    if text.is_empty() {
+        return parse_error("Empty", None);
+    }
+    match text.parse::<f64>() {
+        Ok(f) if f.is_infinite() && !text.to_ascii_lowercase().contains("inf") => {
+            parse_error("Overflow", None)
+        }
+        Ok(f) => Value::Ok(Box::new(Value::Float(f))),
+        Err(_) => parse_error("InvalidDigit", Some(syntax_end(text, true))),
+    }
+}
+
+/// Parse `text` as an exact decimal (`[+-]digits[.digits]`), keeping its scale
+pub(super) fn parse_decimal(text: &str) -> Value {
+    if text.is_empty() {
+        return parse_error("Empty", None);
+    }
+    let end = syntax_end(text, false);
+    if end < text.chars().count() || !text.chars().any(|c| c.is_ascii_digit()) {
+        return parse_error("InvalidDigit", Some(end));
+    }
+    match Decimal::from_str_exact(text) {
+        Ok(d) => Value::Ok(Box::new(Value::Decimal(d))),
+        Err(_) => parse_error("Overflow", None),
+    }
+}
+
+/// Index of the first character that doesn't fit `[+-]digits[.digits]`, plus
+/// `[e[+-]digits]` when `exponent` is set, or the length of the text when it
+/// fits but stops short (as in `"1e"`)
+fn syntax_end(text: &str, exponent: bool) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    let skip_digits = |i: &mut usize| {
+        while chars.get(*i).is_some_and(char::is_ascii_digit) {
+            *i += 1;
+        }
+    };
+    if matches!(chars.first(), Some('+' | '-')) {
+        i += 1;
+    }
+    skip_digits(&mut i);
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        skip_digits(&mut i);
+    }
+    if exponent && matches!(chars.get(i), Some('e' | 'E')) {
+        i += 1;
+        if matches!(chars.get(i), Some('+' | '-')) {
+            i += 1;
+        }
+        skip_digits(&mut i);
+    }
+    i
+}
@@ -47,8 +47,12 @@ pub fn match_pattern(pattern: &Pattern, value: &Value) -> Option<Vec<(String, Va
         }
 
         // Record pattern
-        Pattern::Record { fields, .. } => {
+        Pattern::Record { fields, rest, .. } => {
             if let Value::Record(val_fields) = value {
+                // Without `..`, the pattern must name every field
+                if !rest && val_fields.len() != fields.len() {
+                    return None;
+                }
                 let mut bindings = Vec::new();
                 for (name, pat) in fields {
                     if let Some(field_val) = val_fields.get(name) {
@@ -127,22 +131,13 @@ pub fn match_pattern(pattern: &Pattern, value: &Value) -> Option<Vec<(String, Va
                                 // Multi-field variant data is stored as a Record
                                 if let Value::Record(ref field_map) = **var_val {
                                     let mut bindings = Vec::new();
-                                    // Positional matching: match patterns to fields by order
-                                    let mut sorted_keys: Vec<_> = field_map.keys().collect();
-                                    sorted_keys.sort();
-                                    if pats.len() != sorted_keys.len() {
+                                    // Positional matching: the record keeps the
+                                    // fields in declaration order
+                                    if pats.len() != field_map.len() {
                                         return None;
                                     }
-                                    for (pat, key) in pats.iter().zip(sorted_keys.iter()) {
-                                        if let Some(val) = field_map.get(*key) {
-                                            if let Some(sub) = match_pattern(pat, val) {
-                                                bindings.extend(sub);
-                                            } else {
-                                                return None;
-                                            }
-                                        } else {
-                                            return None;
-                                        }
+                                    for (pat, val) in pats.iter().zip(field_map.values()) {
+                                        bindings.extend(match_pattern(pat, val)?);
                                     }
                                     Some(bindings)
                                 } else {
@@ -1,10 +1,16 @@
 //! Regular expression support for Astra values.
 
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
 use super::error::RuntimeError;
 use super::value::Value;
 
+/// The character index of a match boundary at byte offset `byte`, so that
+/// match positions can be passed to Text methods
+fn char_index(text: &str, byte: usize) -> Value {
+    Value::Int(text[..byte].chars().count() as i64)
+}
+
 /// Check if a regex pattern matches a string, returns Option with match info
 pub(super) fn regex_match(pattern: &str, text: &str) -> Result<Value, RuntimeError> {
     let re = regex::Regex::new(pattern)
@@ -13,15 +19,15 @@ pub(super) fn regex_match(pattern: &str, text: &str) -> Result<Value, RuntimeErr
     match re.captures(text) {
         Some(caps) => {
             let full_match = caps.get(0).map_or("", |m| m.as_str());
-            let mut fields = HashMap::new();
+            let mut fields = IndexMap::new();
             fields.insert("matched".to_string(), Value::Text(full_match.to_string()));
             fields.insert(
                 "start".to_string(),
-                Value::Int(caps.get(0).map_or(0, |m| m.start()) as i64),
+                char_index(text, caps.get(0).map_or(0, |m| m.start())),
             );
             fields.insert(
                 "end".to_string(),
-                Value::Int(caps.get(0).map_or(0, |m| m.end()) as i64),
+                char_index(text, caps.get(0).map_or(0, |m| m.end())),
             );
 
             // Collect capture groups
@@ -49,10 +55,10 @@ pub(super) fn regex_find_all(pattern: &str, text: &str) -> Result<Value, Runtime
     let matches: Vec<Value> = re
         .find_iter(text)
         .map(|m| {
-            let mut fields = HashMap::new();
+            let mut fields = IndexMap::new();
             fields.insert("matched".to_string(), Value::Text(m.as_str().to_string()));
-            fields.insert("start".to_string(), Value::Int(m.start() as i64));
-            fields.insert("end".to_string(), Value::Int(m.end() as i64));
+            fields.insert("start".to_string(), char_index(text, m.start()));
+            fields.insert("end".to_string(), char_index(text, m.end()));
             Value::Record(fields)
         })
         .collect();
@@ -0,0 +1,140 @@
+//! JSON-RPC 2.0 requests and responses for `rpc_call` and `rpc_batch`, which
+//! send them with `Net.post`.
+//!
+//! Each call gives `Ok(result)` or `Err({code, message, data})`. A server's
+//! error object is decoded as-is. Failures on the client side use codes the
+//! specification leaves to implementations: [`TRANSPORT_ERROR`] when the
+//! request could not be sent, [`PARSE_ERROR`] when the reply is not JSON, and
+//! [`INVALID_RESPONSE`] when it is JSON but not a response to the request.
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+
+use super::error::RuntimeError;
+use super::json::{json_parse_value, json_stringify_value};
+use super::value::{format_value, Value};
+
+/// The request never got a reply: `Net.post` failed
+pub(super) const TRANSPORT_ERROR: i64 = -32000;
+/// The reply was not valid JSON
+pub(super) const PARSE_ERROR: i64 = -32700;
+/// The reply was JSON, but had no result or error for the request
+pub(super) const INVALID_RESPONSE: i64 = -32603;
+
+/// The JSON text of one request. `params` must be a List, Map, or record, or
+/// `None` to leave them out.
+pub(super) fn request_json(id: i64, method: &str, params: &Value) -> Result<String, RuntimeError> {
+    let params = match params {
+        Value::None | Value::Unit => String::new(),
+        Value::List(_) | Value::Tuple(_) | Value::Map(_) | Value::Record(_) => {
+            format!(",\"params\":{}", json_stringify_value(params))
+        }
+        other => {
+            return Err(RuntimeError::type_mismatch(
+                "List, Map, or record of params",
+                &format_value(other),
+            ))
+        }
+    };
+    Ok(format!(
+        "{{\"jsonrpc\":\"2.0\",\"id\":{},\"method\":{}{}}}",
+        id,
+        json_stringify_value(&Value::Text(method.to_string())),
+        params
+    ))
+}
+
+/// The `Err` every call gets when there is no usable reply
+pub(super) fn rpc_error(code: i64, message: impl Into<String>) -> Value {
+    error_value(code, message.into(), Value::None)
+}
+
+/// Match a reply to the requests with these ids, in the same order. A batch
+/// reply is an array of responses in any order; an error object on its own
+/// applies to every request in the batch.
+pub(super) fn decode_reply(reply: &str, ids: &[i64], batch: bool) -> Vec<Value> {
+    let parsed = match json_parse_value(reply) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let message = format!("invalid JSON in response: {}", e.message);
+            return ids
+                .iter()
+                .map(|_| rpc_error(PARSE_ERROR, message.clone()))
+                .collect();
+        }
+    };
+    let responses = match parsed {
+        Value::List(responses) if batch => responses,
+        single => {
+            // A reply to some other request is no answer to these
+            if let Some(Value::Int(id)) = field(&single, "id") {
+                if !ids.contains(id) {
+                    let message = format!("response id {} does not match the request", id);
+                    return ids
+                        .iter()
+                        .map(|_| rpc_error(INVALID_RESPONSE, message.clone()))
+                        .collect();
+                }
+            }
+            vec![single]
+        }
+    };
+    let mut by_id = HashMap::new();
+    let mut unmatched = None;
+    for response in &responses {
+        match field(response, "id") {
+            Some(Value::Int(id)) if batch => {
+                by_id.insert(*id, response);
+            }
+            _ => unmatched = unmatched.or(Some(response)),
+        }
+    }
+    ids.iter()
+        .map(|id| match by_id.get(id).copied().or(unmatched) {
+            Some(response) => decode_response(response),
+            None => rpc_error(INVALID_RESPONSE, format!("no response for request {}", id)),
+        })
+        .collect()
+}
+
+fn decode_response(response: &Value) -> Value {
+    if let Some(error) = field(response, "error") {
+        let code = match field(error, "code") {
+            Some(Value::Int(code)) => *code,
+            _ => INVALID_RESPONSE,
+        };
+        let message = match field(error, "message") {
+            Some(Value::Text(message)) => message.clone(),
+            _ => "error without a message".to_string(),
+        };
+        let data = field(error, "data").cloned().unwrap_or(Value::None);
+        return error_value(code, message, data);
+    }
+    match field(response, "result") {
+        Some(result) => Value::Ok(Box::new(result.clone())),
+        None => rpc_error(
+            INVALID_RESPONSE,
+            "response has neither a result nor an error",
+        ),
+    }
+}
+
+fn error_value(code: i64, message: String, data: Value) -> Value {
+    Value::Err(Box::new(Value::Record(IndexMap::from([
+        ("code".to_string(), Value::Int(code)),
+        ("message".to_string(), Value::Text(message)),
+        ("data".to_string(), data),
+    ]))))
+}
+
+/// A member of a parsed JSON object
+fn field<'a>(object: &'a Value, name: &str) -> Option<&'a Value> {
+    match object {
+        Value::Map(entries) => entries
+            .iter()
+            .find(|(key, _)| matches!(key, Value::Text(key) if key == name))
+            .map(|(_, value)| value),
+        _ => None,
+    }
+}
@@ -0,0 +1,114 @@
+//! `style(text, color, bold)`: ANSI-styled console text.
+//!
+//! Styling only adds escape codes when the console says it takes color (see
+//! [`ConsoleCapability::color`](super::ConsoleCapability::color)), so the
+//! same program prints plain text into a pipe or a log file. The mock console
+//! reads the codes back into [`StyledText`] runs for tests.
+
+use super::error::RuntimeError;
+
+/// The color names `style` takes, with their ANSI foreground codes
+const COLORS: [(&str, u8); 9] = [
+    ("black", 30),
+    ("red", 31),
+    ("green", 32),
+    ("yellow", 33),
+    ("blue", 34),
+    ("magenta", 35),
+    ("cyan", 36),
+    ("white", 37),
+    ("gray", 90),
+];
+
+/// A run of printed text and the style it was printed in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyledText {
+    pub text: String,
+    /// The color name, as passed to `style`
+    pub color: Option<String>,
+    pub bold: bool,
+}
+
+/// `text` in `color` (a name from [`COLORS`], or `""` for none) and bold,
+/// with escape codes only if `enabled`
+pub(super) fn style(
+    text: &str,
+    color: &str,
+    bold: bool,
+    enabled: bool,
+) -> Result<String, RuntimeError> {
+    let code = match color {
+        "" => None,
+        name => match COLORS.iter().find(|(known, _)| *known == name) {
+            Some((_, code)) => Some(code),
+            None => {
+                return Err(RuntimeError::type_mismatch(
+                    &format!("a color ({})", COLORS.map(|(name, _)| name).join(", ")),
+                    &format!("{:?}", name),
+                ))
+            }
+        },
+    };
+    let mut params = Vec::new();
+    if bold {
+        params.push("1".to_string());
+    }
+    params.extend(code.map(u8::to_string));
+    if !enabled || params.is_empty() {
+        return Ok(text.to_string());
+    }
+    Ok(format!("\x1b[{}m{}\x1b[0m", params.join(";"), text))
+}
+
+/// Split printed text into the text without escape codes and the styled runs
+/// in it
+pub(super) fn parse_styles(printed: &str) -> (String, Vec<StyledText>) {
+    let mut plain = String::new();
+    let mut runs = Vec::new();
+    let mut current = StyledText {
+        text: String::new(),
+        color: None,
+        bold: false,
+    };
+    let mut rest = printed;
+    while let Some(at) = rest.find("\x1b[") {
+        current.text.push_str(&rest[..at]);
+        let codes = &rest[at + 2..];
+        let Some(end) = codes.find('m') else {
+            rest = &rest[at..];
+            break;
+        };
+        let mut next = current.clone();
+        next.text.clear();
+        for param in codes[..end].split(';') {
+            match param {
+                "" | "0" => {
+                    next.color = None;
+                    next.bold = false;
+                }
+                "1" => next.bold = true,
+                "22" => next.bold = false,
+                "39" => next.color = None,
+                code => {
+                    if let Some((name, _)) =
+                        COLORS.iter().find(|(_, known)| known.to_string() == code)
+                    {
+                        next.color = Some(name.to_string());
+                    }
+                }
+            }
+        }
+        flush(&mut plain, &mut runs, std::mem::replace(&mut current, next));
+        rest = &codes[end + 1..];
+    }
+    current.text.push_str(rest);
+    flush(&mut plain, &mut runs, current);
+    (plain, runs)
+}
+
+fn flush(plain: &mut String, runs: &mut Vec<StyledText>, run: StyledText) {
+    plain.push_str(&run.text);
+    if !run.text.is_empty() && (run.color.is_some() || run.bold) {
+        runs.push(run);
+    }
+}
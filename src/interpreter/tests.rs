@@ -346,6 +346,33 @@ Err(e) => 0
     assert!(matches!(result, Value::Int(42)));
 }
 
+#[test]
+fn test_replay_rand() {
+    let script = vec![
+        RandDraw::Int {
+            value: 500,
+            min: 0,
+            max: 1000,
+        },
+        RandDraw::Bool(true),
+    ];
+    let rand = ReplayRand::new(42, script);
+    let drawn = rand.drawn();
+
+    // Replayed ints are clamped to the requested range
+    assert_eq!(rand.int(0, 10), 10);
+    assert!(rand.bool());
+    // Past the script, draws come from the seed
+    assert_eq!(rand.int(1, 100), SeededRand::new(42).int(1, 100));
+    assert_eq!(drawn.borrow().len(), 3);
+
+    // A draw of the wrong kind ends the replay
+    let rand = ReplayRand::new(42, vec![RandDraw::Bool(true), RandDraw::Bool(true)]);
+    let seeded = SeededRand::new(42);
+    assert_eq!(rand.int(1, 100), seeded.int(1, 100));
+    assert_eq!(rand.bool(), seeded.bool());
+}
+
 #[test]
 fn test_seeded_rand_deterministic() {
     // Two runs with the same seed should produce the same results
@@ -410,6 +437,53 @@ fn main() effects(Rand) {
     }
 }
 
+#[test]
+fn test_seeded_rand_identifiers() {
+    let source = r#"
+module example
+
+fn main() effects(Rand) {
+  [Rand.uuid(), Rand.uuid(), Rand.hex(7), Rand.hex(0)]
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let lexer = Lexer::new(&source_file);
+    let mut parser = Parser::new(lexer, source_file.clone());
+    let module = parser.parse_module().expect("parse failed");
+
+    let run = |seed| {
+        let caps = Capabilities {
+            rand: Some(Box::new(SeededRand::new(seed))),
+            ..Default::default()
+        };
+        match Interpreter::with_capabilities(caps).eval_module(&module) {
+            Ok(Value::List(items)) => items.iter().map(format_value).collect::<Vec<_>>(),
+            other => panic!("expected a list, got {:?}", other),
+        }
+    };
+    let ids = run(42);
+    assert_eq!(ids, run(42));
+    assert_ne!(ids, run(43));
+
+    let (first, second) = (&ids[0], &ids[1]);
+    assert_ne!(first, second);
+    let groups: Vec<usize> = first.split('-').map(str::len).collect();
+    assert_eq!(groups, vec![8, 4, 4, 4, 12], "{}", first);
+    assert_eq!(&first[14..15], "4", "version nibble in {}", first);
+    assert!(
+        "89ab".contains(&first[19..20]),
+        "variant nibble in {}",
+        first
+    );
+    assert!(first
+        .chars()
+        .all(|c| c == '-' || c.is_ascii_digit() || ('a'..='f').contains(&c)));
+
+    assert_eq!(ids[2].len(), 7);
+    assert!(ids[2].chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(ids[3], "");
+}
+
 #[test]
 fn test_fixed_clock_in_program() {
     let source = r#"
@@ -435,6 +509,36 @@ fn main() effects(Clock) {
     assert!(matches!(result, Value::Int(1700000000)));
 }
 
+#[test]
+fn test_duration_arithmetic_and_rendering() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let d = Duration.minutes(1) + Duration.seconds(30)
+  let shown = "${d} ${Duration.millis(1750)} ${Duration.millis(250)} ${Duration.hours(26)} ${-d} ${Duration.seconds(0)}"
+  let math = "${d * 2} ${3 * Duration.seconds(1)} ${d / 3} ${d / Duration.seconds(20)} ${d % Duration.seconds(40)}"
+  let compare = "${d > Duration.seconds(60)} ${d == Duration.millis(90000)} ${[Duration.hours(1), Duration.millis(5)].sort()}"
+  let units = "${d.as_millis()} ${d.as_seconds()} ${d.as_minutes()} ${(-d).abs()}"
+  shown + " | " + math + " | " + compare + " | " + units
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "1m30s 1.75s 250ms 26h -1m30s 0s | 3m 3s 30s 4 10s | true true [5ms, 1h] | 90000 90 1 1m30s"),
+        "{:?}",
+        result
+    );
+
+    let err = parse_and_eval("module example\nfn main() -> Int {\n  Duration.seconds(1) / 0\n}\n")
+        .unwrap_err();
+    assert!(err.message.contains("division by zero"), "{}", err.message);
+    let err = parse_and_eval(
+        "module example\nfn main() -> Int {\n  Duration.days(9223372036854775807)\n}\n",
+    )
+    .unwrap_err();
+    assert_eq!(err.message, "duration overflow in days");
+}
+
 #[test]
 fn test_requires_passes() {
     let source = r#"
@@ -474,6 +578,44 @@ fn main() -> Int {
     assert_eq!(result.unwrap_err().code, "E3001");
 }
 
+#[test]
+fn test_quantified_contracts() {
+    let source = r#"
+module example
+
+fn first_positive(xs: List[Int]) -> Int
+  requires exists x in xs: x > 0
+  ensures result > 0 and (exists x in xs: x == result)
+{
+  xs.filter(fn(x) { x > 0 })[0]
+}
+
+fn main() -> Int {
+  first_positive([0 - 1, 4, 2])
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Int(4)));
+
+    let none_positive = source.replace("[0 - 1, 4, 2]", "[0 - 1, 0]");
+    assert_eq!(parse_and_eval(&none_positive).unwrap_err().code, "E3001");
+}
+
+#[test]
+fn test_quantifier_stops_at_first_deciding_element() {
+    let source = r#"
+module example
+
+fn main() -> Bool {
+  forall x in [1, 0, 2]: 10 / x > 1
+}
+"#;
+    // `10 / 0` is never evaluated: the first element already makes it false
+    let result = parse_and_eval(&source.replace("[1, 0, 2]", "[20, 0]")).unwrap();
+    assert!(matches!(result, Value::Bool(false)));
+    assert!(parse_and_eval(source).is_err());
+}
+
 #[test]
 fn test_ensures_passes() {
     let source = r#"
@@ -765,6 +907,25 @@ fn main() -> Int {
     assert!(matches!(result, Value::Int(5)));
 }
 
+#[test]
+fn test_text_named_like_effects_is_plain_text() {
+    let source = r#"
+module example
+
+fn main() -> Text {
+  let lens = ["Serverless".len(), "Db".len(), "Metrics!".len(), "Map".len(), "Ref".len()]
+  let map = Map.new()
+  "${lens} ${map.len()}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Text(ref s) if s == "[10, 2, 8, 3, 3] 0"));
+    let result =
+        parse_and_eval("module example\n\nfn main() -> Bool {\n  \"Serverless\".len() == 10\n}\n")
+            .unwrap();
+    assert!(matches!(result, Value::Bool(true)));
+}
+
 // N4: if-then-else expression syntax
 #[test]
 fn test_if_then_else_basic() {
@@ -886,6 +1047,101 @@ fn main() -> Int {
     assert!(matches!(result, Value::Int(15)));
 }
 
+#[test]
+fn test_lambda_shares_mut_bindings() {
+    let source = r#"
+module example
+
+fn make_counter() -> () -> Int {
+  let mut n = 0
+  let next = fn() {
+    n = n + 1
+    n
+  }
+  next
+}
+
+fn main() -> Int {
+  let counter = make_counter()
+  counter()
+  counter()
+  let mut total = counter()
+  let add = fn(x: Int) { total = total + x }
+  add(10)
+  let read = fn() { total }
+  total = total + 100
+  let frozen = 1
+  let get_frozen = fn() { frozen }
+  let frozen = 2
+  read() + get_frozen() + frozen
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Int(116)), "{:?}", result);
+}
+
+#[test]
+fn test_lambda_captures_only_mentioned_bindings() {
+    let source = r#"
+module example
+
+fn main() -> (Int) -> Int {
+  let big = range(0, 1000)
+  let n = 5
+  let add = fn(x: Int) { x + n }
+  add
+}
+"#;
+    let Value::Closure { env, .. } = parse_and_eval(source).unwrap() else {
+        panic!("expected a closure");
+    };
+    assert!(matches!(env.lookup("n"), Some(Value::Int(5))));
+    assert!(env.lookup("big").is_none());
+    assert!(env.contains("main"));
+}
+
+#[test]
+fn test_ref_cells_are_shared() {
+    let source = r#"
+module example
+
+fn main() -> Int {
+  let r = Ref.new(1)
+  let alias = r
+  let add = fn(n: Int) { alias.set(alias.get() + n) }
+  add(10)
+  r.set(r.get() * 2)
+  let other = Ref.new(22)
+  let shared = r == alias and r != other
+  if shared { alias.get() } else { 0 }
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Int(22)), "{:?}", result);
+}
+
+#[test]
+fn test_ref_cycles_format_and_refuse_hashing() {
+    let source = r#"
+module example
+
+fn main() -> Text {
+  let r = Ref.new(None)
+  r.set(Some(r))
+  "${r}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "Ref(Some(Ref(...)))"),
+        "{:?}",
+        result
+    );
+    let err =
+        parse_and_eval("module example\nfn main() -> Int {\n  hash(Ref.new(1))\n}\n").unwrap_err();
+    assert!(err.message.contains("Ref"), "{}", err.message);
+}
+
 #[test]
 fn test_lambda_as_argument() {
     let source = r#"
@@ -1425,6 +1681,29 @@ fn main() -> Int {
     assert!(matches!(result, Value::Int(12)));
 }
 
+#[test]
+fn test_record_pattern_rest_and_rename() {
+    let source = r#"
+module example
+
+fn area(shape: { w: Int, h: Int, label: Text }) -> Int {
+  match shape {
+    { w: width, h = height, .. } => width * height
+  }
+}
+
+fn main() -> Int {
+  let exact = match { x = 1, y = 2 } {
+    { x } => 0,
+    { x, y } => x + y
+  }
+  area({ w = 3, h = 4, label = "box" }) * 10 + exact
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Int(123)), "{:?}", result);
+}
+
 // =========================================================================
 // For loop tests
 // =========================================================================
@@ -1528,6 +1807,47 @@ fn main() -> Int {
     assert!(matches!(result, Value::Int(15)));
 }
 
+#[test]
+fn test_multi_field_variant_matches_in_declaration_order() {
+    // Fields are bound by position even when their names don't sort that way
+    let source = r#"
+module example
+
+enum Span =
+  | Range(to: Int, from: Int)
+
+fn main() -> Int {
+  match Range(10, 3) {
+Range(a, b) => a * 100 + b
+  }
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Int(1003)));
+}
+
+#[test]
+fn test_record_format_keeps_field_order() {
+    let source = r#"
+module example
+
+enum Span =
+  | Range(to: Int, from: Int)
+
+fn main() -> Text {
+  to_text({ zeta = 1, alpha = { y = 2, x = 3 }, mid = Range(4, 5) })
+}
+"#;
+    // Formatting is the same on every run, in the order fields were written
+    for _ in 0..5 {
+        let result = parse_and_eval(source).unwrap();
+        assert_eq!(
+            format_value(&result),
+            "{ zeta: 1, alpha: { y: 2, x: 3 }, mid: Range({ to: 4, from: 5 }) }"
+        );
+    }
+}
+
 #[test]
 fn test_multi_field_variant_single_field() {
     let source = r#"
@@ -1956,6 +2276,113 @@ None => 0,
     assert!(matches!(result, Value::Int(1)));
 }
 
+#[test]
+fn test_list_sort_by_comparator_is_stable() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let people = [
+    { name = "ada", age = 36 },
+    { name = "bo", age = 25 },
+    { name = "cy", age = 36 },
+    { name = "di", age = 25 },
+  ]
+  let direction = -1
+  let oldest_first = people.sort_by(fn(a, b) { (a.age - b.age) * direction })
+  let by_age = people.sort_by_key(fn(p) { p.age })
+  oldest_first.map(fn(p) { p.name }).join(",") + " " + by_age.map(fn(p) { p.name }).join(",")
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Text(ref s) if s == "ada,cy,bo,di bo,di,ada,cy"));
+}
+
+#[test]
+fn test_list_min_by_and_max_by() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let words = ["pear", "fig", "kiwi", "plum", "yam"]
+  let by_len = fn(a, b) { a.len() - b.len() }
+  let shortest = words.min_by(by_len).unwrap()
+  let longest = words.max_by(by_len).unwrap()
+  let none = [].min_by(by_len).is_none()
+  "${shortest} ${longest} ${none}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Text(ref s) if s == "fig plum true"));
+}
+
+#[test]
+fn test_list_sort_by_requires_int_comparator() {
+    let source = r#"
+module example
+fn main() -> List[Int] {
+  [2, 1].sort_by(fn(a, b) { a < b })
+}
+"#;
+    let err = parse_and_eval(source).unwrap_err();
+    assert!(err.message.contains("expected Int"), "{}", err.message);
+}
+
+#[test]
+fn test_list_group_by_and_partition() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let words = ["apple", "bee", "avocado", "cat", "banana"]
+  let groups = words.group_by(fn(w) { w.char_at(0).unwrap() })
+  let (short, long) = words.partition(fn(w) { w.len() <= 3 })
+  let a_words = groups.get("a").unwrap()
+  "${a_words} ${groups.keys()} ${short} ${long.len()}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "[apple, avocado] [a, b, c] [bee, cat] 3"),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_list_chunks_windows_and_dedup() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let xs = [1, 2, 3, 4, 5]
+  "${xs.chunks(2)} ${xs.windows(3)} ${[3, 1, 3, 2, 1].dedup()} ${[[1], [], [2, 3]].flatten()}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "[[1, 2], [3, 4], [5]] [[1, 2, 3], [2, 3, 4], [3, 4, 5]] [3, 1, 2] [1, 2, 3]"),
+        "{:?}",
+        result
+    );
+
+    let err = parse_and_eval("module example\nfn main() -> Int {\n  len([1].chunks(0))\n}\n")
+        .unwrap_err();
+    assert!(err.message.contains("positive Int"), "{}", err.message);
+}
+
+#[test]
+fn test_list_sum_and_product() {
+    let source = r#"
+module example
+fn main() -> Text {
+  "${[1, 2, 3, 4].sum()} ${[1, 2, 3, 4].product()} ${[1, 2.5].sum()} ${[].sum()} ${[].product()}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "10 24 3.5 0 1"),
+        "{:?}",
+        result
+    );
+}
+
 // === P3.2: List methods (take, drop, slice, enumerate, zip, find) ===
 
 #[test]
@@ -2050,76 +2477,160 @@ fn main() -> Text {
     assert!(matches!(result, Value::Text(ref s) if s == "a, b, c"));
 }
 
-// === Else-if chains (P1.4 - already implemented in parser) ===
-
 #[test]
-fn test_else_if_chain() {
+fn test_string_padding_and_trimming() {
     let source = r#"
 module example
-fn classify(n: Int) -> Text {
-  if n < 0 {
-"negative"
-  } else if n == 0 {
-"zero"
-  } else if n < 10 {
-"small"
-  } else {
-"large"
-  }
-}
 fn main() -> Text {
-  classify(5)
+  let id = "42".pad_start(5, "0")
+  let name = "ab".pad_end(4) + "|"
+  let dots = "x".pad_start(4, ".:")
+  id + name + dots + "  in  ".trim_start() + "|" + "  out  ".trim_end()
 }
 "#;
     let result = parse_and_eval(source).unwrap();
-    assert!(matches!(result, Value::Text(ref s) if s == "small"));
+    assert!(matches!(result, Value::Text(ref s) if s == "00042ab  |.:.xin  |  out"));
 }
 
-// === P1.6: Float type ===
-
 #[test]
-fn test_float_literal() {
+fn test_string_lines_words_and_chars() {
     let source = r#"
 module example
-fn main() -> Float {
-  2.75
+fn main() -> Int {
+  let lines = "one\ntwo\r\nthree".lines()
+  let words = "  split   these words ".split_whitespace()
+  let c = match "héllo".char_at(1) {
+    Some(ch) => ch,
+    None => "",
+  }
+  let missing = "abc".char_at(3).is_none() and "abc".char_at(-1).is_none()
+  if c == "é" and missing { lines.len() * 10 + words.len() } else { 0 }
 }
 "#;
     let result = parse_and_eval(source).unwrap();
-    assert!(matches!(result, Value::Float(f) if (f - 2.75).abs() < 0.001));
+    assert!(matches!(result, Value::Int(33)));
 }
 
 #[test]
-fn test_float_arithmetic() {
+fn test_string_count_and_equals_ignore_case() {
     let source = r#"
 module example
-fn main() -> Float {
-  1.5 + 2.5
+fn main() -> Int {
+  let n = "banana".count("an") + "aaaa".count("aa")
+  if "Hello".equals_ignore_case("hELLO") and not "Hello".equals_ignore_case("Help") { n } else { -1 }
 }
 "#;
     let result = parse_and_eval(source).unwrap();
-    assert!(matches!(result, Value::Float(f) if (f - 4.0).abs() < 0.001));
+    assert!(matches!(result, Value::Int(4)));
 }
 
 #[test]
-fn test_float_int_mixed() {
+fn test_string_lengths_and_indices_count_chars() {
     let source = r#"
 module example
-fn main() -> Float {
-  2 * 1.5
+fn main() -> Text {
+  let s = "naïve café"
+  let at = match s.index_of("café") {
+    Some(i) => i,
+    None => -1,
+  }
+  "${s.len()} ${s.char_len()} ${s.byte_len()} ${len(s)} ${at} ${s.substring(2, 5)} ${s.slice(6, 10)}"
 }
 "#;
     let result = parse_and_eval(source).unwrap();
-    assert!(matches!(result, Value::Float(f) if (f - 3.0).abs() < 0.01));
+    assert!(matches!(result, Value::Text(ref s) if s == "10 10 12 10 6 ïve café"));
 }
 
 #[test]
-fn test_float_comparison() {
-    let source = r#"
-module example
-fn main() -> Bool {
-  3.14 > 2.71
-}
+fn test_string_substring_rejects_invalid_indices() {
+    let run = |call: &str| {
+        parse_and_eval(&format!(
+            "module example\nfn main() -> Text {{\n  \"héllo\".{}\n}}\n",
+            call
+        ))
+    };
+    assert!(matches!(run("substring(0, 5)"), Ok(Value::Text(ref s)) if s == "héllo"));
+    assert!(matches!(run("substring(5, 5)"), Ok(Value::Text(ref s)) if s.is_empty()));
+
+    let err = run("substring(2, 9)").unwrap_err();
+    assert_eq!(err.code, "E4024");
+    assert_eq!(err.message, "text index 9 out of bounds for length 5");
+    assert_eq!(run("slice(-1, 2)").unwrap_err().code, "E4024");
+    let err = run("substring(4, 1)").unwrap_err();
+    assert_eq!(err.code, "E4025");
+    assert_eq!(err.message, "text range start 4 is after end 1");
+}
+
+// === Else-if chains (P1.4 - already implemented in parser) ===
+
+#[test]
+fn test_else_if_chain() {
+    let source = r#"
+module example
+fn classify(n: Int) -> Text {
+  if n < 0 {
+"negative"
+  } else if n == 0 {
+"zero"
+  } else if n < 10 {
+"small"
+  } else {
+"large"
+  }
+}
+fn main() -> Text {
+  classify(5)
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Text(ref s) if s == "small"));
+}
+
+// === P1.6: Float type ===
+
+#[test]
+fn test_float_literal() {
+    let source = r#"
+module example
+fn main() -> Float {
+  2.75
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Float(f) if (f - 2.75).abs() < 0.001));
+}
+
+#[test]
+fn test_float_arithmetic() {
+    let source = r#"
+module example
+fn main() -> Float {
+  1.5 + 2.5
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Float(f) if (f - 4.0).abs() < 0.001));
+}
+
+#[test]
+fn test_float_int_mixed() {
+    let source = r#"
+module example
+fn main() -> Float {
+  2 * 1.5
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Float(f) if (f - 3.0).abs() < 0.01));
+}
+
+#[test]
+fn test_float_comparison() {
+    let source = r#"
+module example
+fn main() -> Bool {
+  3.14 > 2.71
+}
 "#;
     let result = parse_and_eval(source).unwrap();
     assert!(matches!(result, Value::Bool(true)));
@@ -2246,6 +2757,111 @@ fn main() -> Int {
     assert!(err.message.contains("custom error message"));
 }
 
+#[test]
+fn test_assert_eq_structural_diff() {
+    let source = r#"
+module example
+fn main() -> Unit {
+  let a = { name = "ann", age = 3, tags = ["x", "y"] }
+  let b = { name = "ann", age = 4, tags = ["x", "z", "w"] }
+  assert_eq(a, b)
+}
+"#;
+    let err = parse_and_eval(source).unwrap_err();
+    assert_eq!(err.code, "E4021");
+    assert_eq!(
+        err.message,
+        "assertion failed: left != right\n  .age: 3 != 4\n  .tags[1]: \"y\" != \"z\"\n  .tags[2]: only in right: \"w\""
+    );
+
+    let err = parse_and_eval("module example\nfn main() -> Unit {\n  assert_eq(\"1\", 1)\n}\n")
+        .unwrap_err();
+    assert_eq!(err.message, "assertion failed: \"1\" != 1");
+}
+
+#[test]
+fn test_value_diff_paths() {
+    let diff = value_diff(
+        &Value::Some(Box::new(Value::Map(vec![
+            (Value::Text("a".into()), Value::Int(1)),
+            (Value::Text("b".into()), Value::Int(2)),
+        ]))),
+        &Value::Some(Box::new(Value::Map(vec![(
+            Value::Text("a".into()),
+            Value::Int(5),
+        )]))),
+    );
+    assert_eq!(
+        diff,
+        vec![
+            ".Some[\"a\"]: 1 != 5".to_string(),
+            ".Some[\"b\"]: only in left: 2".to_string(),
+        ]
+    );
+    assert!(value_diff(&Value::Int(1), &Value::Int(1)).is_empty());
+}
+
+#[test]
+fn test_assertion_builtins() {
+    let passing = r#"
+module example
+fn main() -> Int {
+  assert_ne(1, 2)
+  assert_approx_eq(0.1 + 0.2, 0.3, 0.0001)
+  assert_contains("hello world", "world")
+  assert_contains([1, 2, 3], 2)
+  assert_contains(Set.from([1, 2]), 1)
+  assert_err_code(1 / 0, "E4003")
+  1
+}
+"#;
+    assert!(matches!(parse_and_eval(passing), Ok(Value::Int(1))));
+
+    let fails = |body: &str| {
+        parse_and_eval(&format!(
+            "module example\nfn main() -> Unit {{\n  {}\n}}\n",
+            body
+        ))
+        .unwrap_err()
+    };
+    assert_eq!(
+        fails("assert_ne([1], [1])").message,
+        "assertion failed: both values are [1]"
+    );
+    assert!(fails("assert_approx_eq(1.0, 1.5, 0.1)")
+        .message
+        .contains("differ by 0.5"));
+    assert_eq!(
+        fails("assert_contains([1, 2], 5)").message,
+        "assertion failed: [1, 2] does not contain 5"
+    );
+    assert_eq!(
+        fails("assert_err_code(1 + 1, \"E4003\")").message,
+        "assertion failed: expected error E4003, got 2"
+    );
+    assert!(fails("assert_err_code(1 / 0, \"E4001\")")
+        .message
+        .contains("got E4003"));
+}
+
+#[test]
+fn test_dbg_returns_value_and_traces() {
+    let source_file = SourceFile::new(
+        PathBuf::from("test.astra"),
+        "module example\nfn main() -> Int {\n  dbg(1 + 2) * 2\n}\n".to_string(),
+    );
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let mut interpreter = Interpreter::new();
+    let traces = interpreter.capture_debug_output();
+    let result = interpreter.eval_module(&module).unwrap();
+    assert!(matches!(result, Value::Int(6)));
+    assert_eq!(
+        *traces.borrow(),
+        vec!["[test.astra:3:7] 1 + 2 = 3".to_string()]
+    );
+}
+
 // === P1.7: Tuple type ===
 
 #[test]
@@ -2628,6 +3244,47 @@ fn main() -> Int {
     assert!(matches!(result, Value::Int(3)));
 }
 
+#[test]
+fn test_set_algebra() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let a = Set.from([5, 1, 3, 2])
+  let b = Set.from([4, 3, 2])
+  let diff = a.difference(b).to_list()
+  let sym = a.symmetric_difference(b).to_list()
+  let sub = Set.from([3, 2]).is_subset(a) and not b.is_subset(a)
+  let sup = a.is_superset(Set.from([1, 5])) and a.is_superset(Set.new())
+  "${diff} ${sym} ${sub} ${sup}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "[1, 5] [1, 4, 5] true true"),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_set_map_and_filter_keep_sets_sorted_and_unique() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let s = Set.from([-3, -1, 1, 2, 3])
+  let squares = s.map(fn(x) { x * x })
+  let odd = s.filter(fn(x) { x % 2 != 0 })
+  "${squares.to_list()} ${squares.len()} ${odd.to_list()} ${odd.contains(-1)}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "[1, 4, 9] 3 [-3, -1, 1, 3] true"),
+        "{:?}",
+        result
+    );
+}
+
 #[test]
 fn test_map_entries() {
     let source = r#"
@@ -2641,6 +3298,47 @@ fn main() -> Int {
     assert!(matches!(result, Value::Int(2)));
 }
 
+#[test]
+fn test_map_higher_order_methods() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let prices = Map.from([("apple", 3), ("pear", 5), ("plum", 2)])
+  let doubled = prices.map_values(fn(p) { p * 2 })
+  let cheap = prices.filter(fn(name, p) { p < 4 }).keys()
+  let total = prices.fold(0, fn(acc, name, p) { acc + p })
+  let merged = prices.merge(Map.from([("pear", 1), ("fig", 9)]), fn(name, a, b) { a + b })
+  "${doubled.values()} ${cheap} ${total} ${merged.entries()}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "[6, 10, 4] [apple, plum] 10 [(apple, 3), (fig, 9), (pear, 6), (plum, 2)]"),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_map_update_counts_words() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let mut counts = Map.new()
+  for w in "a b a c a b".split(" ") {
+    counts = counts.update(w, fn(n) { n.unwrap_or(0) + 1 })
+  }
+  "${counts.entries()}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "[(a, 3), (b, 2), (c, 1)]"),
+        "{:?}",
+        result
+    );
+}
+
 #[test]
 fn test_tuple_to_list() {
     let source = r#"
@@ -2914,6 +3612,50 @@ fn main() -> Text {
     assert!(matches!(result, Value::Text(ref s) if s == "hello"));
 }
 
+#[test]
+fn test_impl_inherits_trait_method_contract() {
+    let source = r#"
+module example
+
+trait Sized {
+  fn take(self: Self, n: Int) -> Int
+    requires n >= 0
+    ensures result <= n
+}
+
+impl Sized for Int {
+  fn take(self: Int, count: Int) -> Int
+    requires count < 100
+  {
+    if count > self then self else count
+  }
+}
+
+impl Sized for Text {
+  fn take(self: Text, n: Int) -> Int {
+    n + 1
+  }
+}
+
+fn main() -> Int {
+  let x = 5
+  x.take(3)
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Int(3)));
+
+    // The trait's precondition holds for `count`, the impl's name for `n`
+    let negative = source.replace("x.take(3)", "x.take(0 - 1)");
+    assert_eq!(parse_and_eval(&negative).unwrap_err().code, "E3001");
+    // The impl's own precondition still applies
+    let large = source.replace("x.take(3)", "x.take(200)");
+    assert_eq!(parse_and_eval(&large).unwrap_err().code, "E3001");
+    // An impl without clauses of its own still has the trait's postcondition
+    let text = source.replace("let x = 5", "let x = \"abc\"");
+    assert_eq!(parse_and_eval(&text).unwrap_err().code, "E3002");
+}
+
 // Parameter destructuring tests
 
 #[test]
@@ -3055,7 +3797,7 @@ fn test_compound_assignment_operators() {
     let source = r#"
 module example
 fn main() -> Int {
-  let x = 10
+  let mut x = 10
   x += 5
   x -= 3
   x *= 2
@@ -3069,6 +3811,21 @@ fn main() -> Int {
     assert!(matches!(result, Value::Int(1)));
 }
 
+#[test]
+fn test_assigning_immutable_binding_fails() {
+    let source = r#"
+module example
+fn main() -> Int {
+  let x = 1
+  x = 2
+  x
+}
+"#;
+    let err = parse_and_eval(source).unwrap_err();
+    assert_eq!(err.code, "E4031");
+    assert!(err.message.contains("`x`"), "{}", err.message);
+}
+
 #[test]
 fn test_index_access_list() {
     let source = r#"
@@ -3124,13 +3881,40 @@ fn main() -> Int {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_loop_and_match_bindings_stay_in_their_scope() {
+    let source = r#"
+module example
+fn main() -> Int {
+  let item = 1
+  let doubled = 0
+  for item in [5, 6] {
+    let doubled = item * 2
+  }
+  let mut n = 0
+  while n < 3 {
+    let doubled = n
+    n += 1
+  }
+  let found = match Some(7) {
+    Some(item) if item > 10 => item,
+    Some(item) => item + 1,
+    None => 0
+  }
+  item * 100 + doubled * 10 + found
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Int(108)), "{:?}", result);
+}
+
 #[test]
 fn test_for_loop_destructuring() {
     let source = r#"
 module example
 fn main() -> Int {
   let pairs = [(1, 10), (2, 20), (3, 30)]
-  let total = 0
+  let mut total = 0
   for (key, val) in pairs {
 total += val
   }
@@ -3141,6 +3925,68 @@ total += val
     assert!(matches!(result, Value::Int(60)));
 }
 
+#[test]
+fn test_for_loop_destructures_entries_and_enumerate() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let scores = Map.from([("ann", 3), ("bob", 5)])
+  let mut out = ""
+  for (name, score) in scores.entries() {
+    out = out + "${name}=${score} "
+  }
+  for (i, (a, b)) in [("x", 1), ("y", 2)].enumerate() {
+    out = out + "${i}:${a}${b} "
+  }
+  out
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "ann=3 bob=5 0:x1 1:y2 "),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_for_loop_iterates_text_sets_maps_and_ranges() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let mut out = ""
+  for c in "héy" {
+    out = out + c + "."
+  }
+  for x in Set.from([3, 1, 3, 2]) {
+    out = out + "${x}"
+  }
+  for (k, v) in Map.from([("b", 2), ("a", 1)]) {
+    out = out + " ${k}${v}"
+  }
+  let mut total = 0
+  for i in 1..=4 {
+    total = total + i
+  }
+  "${out} ${total}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "h.é.y.123 a1 b2 10"),
+        "{:?}",
+        result
+    );
+
+    let err = parse_and_eval("module example\nfn main() -> Int {\n  for x in 5 {\n  }\n  0\n}\n")
+        .unwrap_err();
+    assert!(
+        err.message.contains("List, Set, Map, or Text"),
+        "{}",
+        err.message
+    );
+}
+
 #[test]
 fn test_structural_equality_records() {
     let source = r#"
@@ -3201,43 +4047,1258 @@ fn main() -> Bool {
 // --- JSON Parsing ---
 
 #[test]
-fn test_json_parse_int() {
+fn test_float_rendering_is_deterministic() {
+    let cases = [
+        (1.0, "1.0"),
+        (-0.0, "-0.0"),
+        (0.1 + 0.2, "0.30000000000000004"),
+        (1.5e9, "1500000000.0"),
+        (1e20, "100000000000000000000.0"),
+        (1e21, "1e21"),
+        (1.5e300, "1.5e300"),
+        (0.000001, "0.000001"),
+        (1.25e-7, "1.25e-7"),
+        (f64::NAN, "NaN"),
+        (f64::INFINITY, "inf"),
+        (f64::NEG_INFINITY, "-inf"),
+    ];
+    for (value, expected) in cases {
+        assert_eq!(format_value(&Value::Float(value)), expected);
+    }
+}
+
+#[test]
+fn test_float_special_values_and_formatting() {
     let source = r#"
 module example
-fn main() -> Int {
-  json_parse("42")
+fn main() -> Text {
+  let nan = match parse_float("NaN") {
+    Ok(f) => f
+    Err(_) => 0.0
+  }
+  let inf = 1e308 * 10.0
+  let checks = "${nan.is_nan()} ${inf.is_infinite()} ${inf.is_finite()} ${2.5.is_nan()}"
+  let fixed = "${3.14159.to_fixed(2)} ${2.0.to_fixed(0)} ${1e21.to_fixed(1)} ${nan.to_fixed(2)}"
+  let precise = "${3.14159.to_precision(3)} ${123456.0.to_precision(2)} ${0.00001234.to_precision(2)} ${9.99.to_precision(2)}"
+  checks + " | " + fixed + " | " + precise
 }
 "#;
     let result = parse_and_eval(source).unwrap();
-    assert!(matches!(result, Value::Int(42)));
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "true true false false | 3.14 2 1000000000000000000000.0 NaN | 3.14 1.2e5 0.000012 10"),
+        "{:?}",
+        result
+    );
+    let err =
+        parse_and_eval("module example\nfn main() -> Text {\n  1.5.to_fixed(-1)\n}\n").unwrap_err();
+    assert!(err.message.contains("between 0 and 100"), "{}", err.message);
 }
 
 #[test]
-fn test_json_parse_float() {
+fn test_parse_int_reports_why_it_failed() {
     let source = r#"
 module example
-fn main() -> Float {
-  json_parse("1.23")
+fn describe(r: Result[Int, ParseError]) -> Text {
+  match r {
+    Ok(n) => "${n}",
+    Err(Empty) => "empty",
+    Err(InvalidDigit(i)) => "digit@${i}",
+    Err(Overflow) => "overflow",
+  }
+}
+fn main() -> Text {
+  let results = [
+    parse_int("-42", 10),
+    parse_int("+ff", 16),
+    parse_int("101", 2),
+    parse_int("", 10),
+    parse_int("12x4", 10),
+    parse_int("-", 10),
+    parse_int("9223372036854775807", 10),
+    parse_int("-9223372036854775808", 10),
+    parse_int("9223372036854775808", 10),
+  ]
+  results.map(describe).join(" ")
 }
 "#;
     let result = parse_and_eval(source).unwrap();
-    if let Value::Float(f) = result {
-        assert!((f - 1.23).abs() < 0.001);
-    } else {
-        panic!("Expected Float, got {:?}", result);
-    }
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "-42 255 5 empty digit@2 digit@0 9223372036854775807 -9223372036854775808 overflow"),
+        "{:?}",
+        result
+    );
+    let err =
+        parse_and_eval("module example\nfn main() -> Int {\n  parse_int(\"1\", 40).unwrap()\n}\n")
+            .unwrap_err();
+    assert!(
+        err.message.contains("base between 2 and 36"),
+        "{}",
+        err.message
+    );
 }
 
 #[test]
-fn test_json_parse_string() {
+fn test_parse_float_reports_why_it_failed() {
     let source = r#"
 module example
 fn main() -> Text {
-  json_parse("\"hello world\"")
+  let results = [parse_float("1.5e3"), parse_float("-0.25"), parse_float(""), parse_float("1.5x"), parse_float("1e"), parse_float("1e999")]
+  results.map(fn(r) { "${r}" }).join(" ")
 }
 "#;
     let result = parse_and_eval(source).unwrap();
-    assert!(matches!(result, Value::Text(ref s) if s == "hello world"));
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "Ok(1500.0) Ok(-0.25) Err(Empty) Err(InvalidDigit(3)) Err(InvalidDigit(2)) Err(Overflow)"),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_decimal_arithmetic_is_exact() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let sum = 0.10d + 0.20d
+  let total = [19.99d, 5.01d, 0.50d].fold(0.00d, fn(acc, p) { acc + p })
+  let exact = "${sum} ${sum == 0.3d} ${total} ${1.50d} ${-2.5d * 3} ${10 - 0.01d}"
+  let compare = "${1.5d < 2} ${2.50d == 2.5d} ${[3.0d, 1.25d, 2d].sort()}"
+  let third = 1.00d / 3
+  let methods = "${third.round(4)} ${2.345d.round(2)} ${(-2.345d).round(2)} ${1.50d.scale()} ${(-1.5d).abs()} ${0.25d.to_float()}"
+  exact + " | " + compare + " | " + methods
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "0.30 true 25.50 1.50 -7.5 9.99 | true true [1.25, 2, 3.0] | 0.3333 2.35 -2.35 2 1.5 0.25"),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_decimal_conversions_and_errors() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let converted = "${to_decimal(0.1)} ${to_decimal(42)} ${to_text(12.340d)}"
+  let results = [parse_decimal("12.340"), parse_decimal("-0.5"), parse_decimal(""), parse_decimal("1e5"), parse_decimal("99999999999999999999999999999999")]
+  converted + " | " + results.map(fn(r) { "${r}" }).join(" ")
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "0.1 42 12.340 | Ok(12.340) Ok(-0.5) Err(Empty) Err(InvalidDigit(1)) Err(Overflow)"),
+        "{:?}",
+        result
+    );
+
+    let err =
+        parse_and_eval("module example\nfn main() -> Decimal {\n  1.5d / 0\n}\n").unwrap_err();
+    assert!(err.message.contains("division by zero"), "{}", err.message);
+    let err = parse_and_eval(
+        "module example\nfn main() -> Decimal {\n  79228162514264337593543950335d + 1\n}\n",
+    )
+    .unwrap_err();
+    assert_eq!(err.message, "decimal overflow in addition");
+}
+
+#[test]
+fn test_base64_and_hex_round_trip() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let vectors = ["", "f", "fo", "foo", "foob", "fooba", "foobar"].map(fn(s) { base64_encode(s) })
+  let data = "héllo".to_bytes()
+  let encoded = base64_encode(data)
+  let hex = hex_encode(data)
+  let unpadded = base64_decode("Zm8")
+  let upper = hex_decode("DEADbeef")
+  let round_trip = "${base64_decode(encoded)} ${hex_decode(hex)} ${unpadded} ${upper}"
+  let bytes = Bytes.from_list([104, 105]) + "!".to_bytes()
+  "${vectors} | ${encoded} ${hex} | ${round_trip} | ${bytes.to_text()} ${bytes.len()} ${bytes.to_list()}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "[, Zg==, Zm8=, Zm9v, Zm9vYg==, Zm9vYmE=, Zm9vYmFy] | aMOpbGxv 68c3a96c6c6f | Ok(Bytes(68c3a96c6c6f)) Ok(Bytes(68c3a96c6c6f)) Ok(Bytes(666f)) Ok(Bytes(deadbeef)) | Some(hi!) 3 [104, 105, 33]"),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_decoding_errors_carry_codes() {
+    let source = r#"
+module example
+fn describe(result: Result[Bytes, { code: Text, index: Int, message: Text }]) -> Text {
+  match result {
+    Ok(bytes) => "ok ${bytes}"
+    Err(e) => "${e.code}@${e.index}: ${e.message}"
+  }
+}
+fn main() -> Text {
+  let results = [base64_decode("aGV!bG8="), base64_decode("Zg="), base64_decode("Zm9vY"), hex_decode("abc"), hex_decode("0g")]
+  results.map(fn(r) { describe(r) }).join(" | ")
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "E4026@3: invalid base64 character `!` at index 3 | E4026@2: misplaced `=` padding at index 2 | E4026@4: base64 text ends with a partial byte at index 4 | E4027@3: odd number of hex digits (3) | E4027@1: invalid hex digit `g` at index 1"),
+        "{:?}",
+        result
+    );
+
+    let err =
+        parse_and_eval("module example\nfn main() -> Bytes {\n  Bytes.from_list([1, 256])\n}\n")
+            .unwrap_err();
+    assert!(err.message.contains("between 0 and 255"), "{}", err.message);
+}
+
+#[test]
+fn test_hashes_are_stable() {
+    // These values are documented as stable; a change here breaks caches
+    let source = r#"
+module example
+fn main() -> Text {
+  let digests = [sha256(""), sha256("abc"), sha256("abc".to_bytes())]
+  let pinned = [hash(1), hash("x"), hash([1, 2])]
+  let equal = [
+    hash({ a = 1, b = "x" }) == hash({ b = "x", a = 1 }),
+    hash(1.50d) == hash(1.5d),
+    hash(0.0) == hash(-0.0),
+    hash(Set.from([3, 1, 2])) == hash(Set.from([1, 2, 3])),
+  ]
+  let distinct = [hash([1, 2]) == hash([2, 1]), hash(1) == hash(1.0), hash(Some(1)) == hash(Ok(1))]
+  "${digests} ${pinned} ${equal} ${distinct}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "[e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855, ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad, ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad] [-558621639795882932, -2634177170866355064, 495899850270663631] [true, true, true, true] [false, false, false]"),
+        "{:?}",
+        result
+    );
+
+    let err =
+        parse_and_eval("module example\nfn main() -> Int {\n  hash(fn(x) { x })\n}\n").unwrap_err();
+    assert!(err.message.contains("function"), "{}", err.message);
+}
+
+#[test]
+fn test_crypto_matches_reference_vectors() {
+    // The common "quick brown fox" HMAC example, and ed25519 from RFC 8032 test 1
+    let source = r#"
+module example
+fn main() -> Text effects(Crypto) {
+  let mac = hex_encode(Crypto.hmac_sha256("key", "The quick brown fox jumps over the lazy dog"))
+  let secret = hex_decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60").unwrap()
+  let verifying = Crypto.public_key(secret).unwrap()
+  let signature = Crypto.sign(secret, "").unwrap()
+  let checks = [Crypto.verify(verifying, "", signature), Crypto.verify(verifying, "x", signature), Crypto.verify(secret, "", signature)]
+  let short = Crypto.sign("abc".to_bytes(), "")
+  "${mac} ${hex_encode(verifying)} ${hex_encode(signature)} ${checks} ${short}"
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let caps = Capabilities {
+        crypto: Some(Box::new(StandardCrypto)),
+        ..Default::default()
+    };
+    let result = Interpreter::with_capabilities(caps)
+        .eval_module(&module)
+        .unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8 d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b [true, false, false] Err(ed25519 private keys are 32 bytes, got 3)"),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_crypto_loads_keys_and_mocks_signatures() {
+    let source = r#"
+module example
+fn main() -> Text effects(Crypto, Env) {
+  let key = Crypto.key_from_env("SIGNING_KEY", "base64").unwrap()
+  let signature = Crypto.sign(key, "payload").unwrap()
+  let verifying = Crypto.public_key(key).unwrap()
+  let results = [Crypto.key_from_env("HEX_KEY", "hex"), Crypto.key_from_env("HEX_KEY", "raw"), Crypto.key_from_env("SIGNING_KEY", "hex"), Crypto.key_from_env("MISSING", "raw")]
+  let checks = [Crypto.verify(verifying, "payload", signature), Crypto.verify(verifying, "other", signature)]
+  "${signature.len()} ${checks} ${results}"
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let vars = std::collections::BTreeMap::from([
+        ("SIGNING_KEY".to_string(), "dGVzdA==\n".to_string()),
+        ("HEX_KEY".to_string(), " 0aff ".to_string()),
+    ]);
+    let caps = Capabilities {
+        crypto: Some(Box::new(MockCrypto)),
+        env: Some(Box::new(FixedEnv::new(vars, vec![]))),
+        ..Default::default()
+    };
+    let result = Interpreter::with_capabilities(caps)
+        .eval_module(&module)
+        .unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "64 [true, false] [Ok(Bytes(0aff)), Ok(Bytes(30616666)), Err(environment variable `SIGNING_KEY` is not valid hex: invalid hex digit `G` at index 1), Err(environment variable `MISSING` is not set)]"),
+        "{:?}",
+        result
+    );
+
+    // Crypto is gated: without it, even key loading fails
+    let err = parse_and_eval(
+        "module example\nfn main() -> Bytes effects(Crypto) {\n  Crypto.hmac_sha256(\"k\", \"d\")\n}\n",
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "E4004");
+}
+
+#[test]
+fn test_compress_round_trips_deterministically() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let packed = compress("hello hello hello hello")
+  let empty = hex_encode(compress(""))
+  let results = [decompress(packed), decompress("plain".to_bytes())]
+  "${empty} ${packed.len()} ${results}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "1f8b08000000000000ff03000000000000000000 28 [Ok(Bytes(68656c6c6f2068656c6c6f2068656c6c6f2068656c6c6f)), Err(invalid gzip data: unexpected end of file)]"),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_fs_gzip_on_memory_fs() {
+    let source = r#"
+module example
+fn main() -> Text effects(Fs) {
+  Fs.write_gzip("logs/app.log.gz", "GET /\nPOST /login\n").unwrap()
+  let lines = Fs.read_gzip("logs/app.log.gz").unwrap().lines()
+  let results = [Fs.read_gzip("notes.txt"), Fs.read_gzip("missing.gz")]
+  "${lines} ${results}"
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let fs = MemoryFs::new(std::collections::BTreeMap::from([(
+        "notes.txt".to_string(),
+        "not gzip".to_string(),
+    )]));
+    let caps = Capabilities {
+        fs: Some(Box::new(fs.clone())),
+        ..Default::default()
+    };
+    let result = Interpreter::with_capabilities(caps)
+        .eval_module(&module)
+        .unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "[GET /, POST /login] [Err(Failed to read 'notes.txt': invalid gzip data: unexpected end of file), Err(Failed to read 'missing.gz': no such file)]"),
+        "{:?}",
+        result
+    );
+    // The compressed file is binary; it is stored as written
+    assert!(fs
+        .read_bytes("logs/app.log.gz")
+        .unwrap()
+        .starts_with(&[0x1f, 0x8b]));
+    assert!(fs.read("logs/app.log.gz").is_err());
+}
+
+/// Interceptor that logs each hook it runs and applies a small file policy
+struct Policy {
+    name: &'static str,
+    log: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+}
+
+impl EffectInterceptor for Policy {
+    fn before(&mut self, effect: &str, method: &str, args: &mut Vec<Value>) -> Intercept {
+        self.log
+            .borrow_mut()
+            .push(format!("{} before {}.{}", self.name, effect, method));
+        match (effect, method, args.as_mut_slice()) {
+            ("Fs", "write", [Value::Text(path), _]) if path.starts_with("secret") => {
+                Intercept::Respond(Ok(Value::Err(Box::new(Value::Text("denied".to_string())))))
+            }
+            ("Fs", "write", [_, Value::Text(content)]) => {
+                *content = content.to_uppercase();
+                Intercept::Proceed
+            }
+            ("Clock", "now", _) => Intercept::Respond(Ok(Value::Int(42))),
+            ("Net", _, _) => Intercept::Respond(Err(RuntimeError::new("E4005", "Net is off"))),
+            _ => Intercept::Proceed,
+        }
+    }
+
+    fn after(
+        &mut self,
+        effect: &str,
+        method: &str,
+        result: Result<Value, RuntimeError>,
+    ) -> Result<Value, RuntimeError> {
+        self.log
+            .borrow_mut()
+            .push(format!("{} after {}.{}", self.name, effect, method));
+        result
+    }
+}
+
+#[test]
+fn test_interceptors_wrap_effect_calls() {
+    let source = r#"
+module example
+fn main() -> Text effects(Fs, Clock) {
+  Fs.write("a.txt", "hello").unwrap()
+  let denied = match Fs.write("secret.txt", "x") {
+    Ok(_) => "written"
+    Err(e) => e
+  }
+  let now = Clock.now()
+  let text = read_file("a.txt").unwrap()
+  "${text} ${denied} ${now}"
+}
+
+fn fetch() -> Text effects(Net) {
+  Net.get("https://example.com").unwrap()
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let fs = MemoryFs::new(std::collections::BTreeMap::new());
+    let log = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut interpreter = Interpreter::with_capabilities(Capabilities {
+        fs: Some(Box::new(fs.clone())),
+        ..Default::default()
+    });
+    interpreter.add_interceptor(Box::new(Policy {
+        name: "outer",
+        log: log.clone(),
+    }));
+    interpreter.add_interceptor(Box::new(Policy {
+        name: "inner",
+        log: log.clone(),
+    }));
+
+    // Writes are rewritten, the secret write is answered without running,
+    // and the clock is answered without a Clock capability
+    let result = interpreter.eval_module_entry(&module, "main").unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "HELLO denied 42"),
+        "{:?}",
+        result
+    );
+    assert_eq!(fs.read("a.txt").unwrap(), "HELLO");
+    assert!(!fs.exists("secret.txt"));
+    assert_eq!(
+        log.borrow()[..4],
+        [
+            "outer before Fs.write",
+            "inner before Fs.write",
+            "inner after Fs.write",
+            "outer after Fs.write",
+        ]
+    );
+    // The outer interceptor answered, so the inner one never saw the call
+    assert_eq!(
+        log.borrow()[4..6],
+        ["outer before Fs.write", "outer before Clock.now"]
+    );
+    // Builtins like `read_file` are intercepted too
+    assert!(log.borrow().contains(&"inner before Fs.read".to_string()));
+
+    // An error answer vetoes the call and stops the program
+    let error = interpreter.eval_module_entry(&module, "fetch").unwrap_err();
+    assert_eq!(error.code, "E4005");
+    assert!(error.message.contains("Net is off"));
+}
+
+#[test]
+fn test_transactional_fs_holds_back_writes() {
+    let source = r#"
+module example
+fn main() -> Text effects(Fs) {
+  Fs.write("notes.txt", "new").unwrap()
+  Fs.write("same.txt", "same").unwrap()
+  Fs.write("out/report.txt", "done").unwrap()
+  let notes = Fs.read("notes.txt").unwrap()
+  let created = Fs.exists("out/report.txt")
+  "${notes} ${created}"
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let host = MemoryFs::new(std::collections::BTreeMap::from([
+        ("notes.txt".to_string(), "old".to_string()),
+        ("same.txt".to_string(), "same".to_string()),
+    ]));
+    let fs = TransactionalFs::new(host.clone());
+    let caps = Capabilities {
+        fs: Some(Box::new(fs.clone())),
+        ..Default::default()
+    };
+    let result = Interpreter::with_capabilities(caps)
+        .eval_module(&module)
+        .unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "new true"),
+        "{:?}",
+        result
+    );
+    assert_eq!(host.files()["notes.txt"], "old");
+    // A write that changes nothing is not reported
+    assert_eq!(
+        fs.pending(),
+        vec![
+            PendingWrite {
+                path: "notes.txt".to_string(),
+                before: Some(b"old".to_vec()),
+                after: b"new".to_vec(),
+            },
+            PendingWrite {
+                path: "out/report.txt".to_string(),
+                before: None,
+                after: b"done".to_vec(),
+            },
+        ]
+    );
+    fs.commit().unwrap();
+    assert_eq!(host.files()["notes.txt"], "new");
+    assert_eq!(host.files()["out/report.txt"], "done");
+    assert!(fs.pending().is_empty());
+}
+
+#[test]
+fn test_args_parse() {
+    let source = r#"
+module greet
+fn main() -> Result[{ name: Text, count: Int, loud: Bool, dry_run: Bool, rest: List[Text] }, { message: Text, code: Int }] effects(Env) {
+  Args.parse({
+    name = Args.positional("Who to greet"),
+    count = Args.option(1, "How many times"),
+    loud = Args.flag("Shout"),
+    dry_run = Args.flag("Print nothing"),
+    rest = Args.rest("More names"),
+  }, "Print a greeting")
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let run = |args: &[&str]| {
+        let env = FixedEnv::new(
+            Default::default(),
+            args.iter().map(|arg| arg.to_string()).collect(),
+        );
+        let caps = Capabilities {
+            env: Some(Box::new(env)),
+            ..Default::default()
+        };
+        match Interpreter::with_capabilities(caps).eval_module(&module) {
+            Ok(Value::Ok(parsed)) => match *parsed {
+                Value::Record(fields) => Ok(fields),
+                other => panic!("expected a record, got {:?}", other),
+            },
+            Ok(Value::Err(error)) => match *error {
+                Value::Record(fields) => Err((
+                    format_value(&fields["message"]),
+                    format_value(&fields["code"]),
+                )),
+                other => panic!("expected a record, got {:?}", other),
+            },
+            other => panic!("expected a Result, got {:?}", other),
+        }
+    };
+
+    let parsed = run(&["Ada", "--count=3", "--dry-run", "Grace", "--", "--loud"]).unwrap();
+    assert_eq!(format_value(&parsed["name"]), "Ada");
+    assert_eq!(format_value(&parsed["count"]), "3");
+    assert_eq!(format_value(&parsed["loud"]), "false");
+    assert_eq!(format_value(&parsed["dry_run"]), "true");
+    assert_eq!(format_value(&parsed["rest"]), "[Grace, --loud]");
+    let parsed = run(&["--count", "2", "--loud", "Ada"]).unwrap();
+    assert_eq!(format_value(&parsed["count"]), "2");
+    assert_eq!(format_value(&parsed["loud"]), "true");
+
+    let (help, code) = run(&["Ada", "--help"]).unwrap_err();
+    assert_eq!(code, "0");
+    assert_eq!(
+        help,
+        "Print a greeting\n\nUsage: greet [OPTIONS] <name> [rest]...\n\nArguments:\n  <name>         Who to greet\n  [rest]...      More names\n\nOptions:\n  --count <INT>  How many times [default: 1]\n  --loud         Shout\n  --dry-run      Print nothing\n  -h, --help     Print help"
+    );
+    let usage = "\n\nUsage: greet [OPTIONS] <name> [rest]...";
+    for (args, message) in [
+        (&[][..], "error: missing argument <name>"),
+        (&["Ada", "--count"][..], "error: `--count` needs a value"),
+        (
+            &["Ada", "--count", "many"][..],
+            "error: invalid value `many` for `--count`: expected INT",
+        ),
+        (&["Ada", "--loud=yes"][..], "error: `--loud` takes no value"),
+        (&["Ada", "-v"][..], "error: unknown option `-v`"),
+    ] {
+        assert_eq!(
+            run(args).unwrap_err(),
+            (format!("{}{}", message, usage), "2".to_string())
+        );
+    }
+}
+
+#[test]
+fn test_restricted_capabilities() {
+    let mut caps = Capabilities {
+        console: Some(Box::new(MockConsole::new())),
+        fs: Some(Box::new(MemoryFs::new(Default::default()))),
+        clock: Some(Box::new(FixedClock::new(0))),
+        ..Default::default()
+    };
+    caps.restrict_to(&["Console".to_string(), "Net".to_string()]);
+    assert!(caps.console.is_some());
+    assert!(caps.fs.is_none());
+    assert!(caps.clock.is_none());
+    assert!(caps.net.is_none());
+}
+
+#[test]
+fn test_scripted_console_records_interleaved_io() {
+    let console = MockConsole::scripted(vec![
+        ConsoleStep::ExpectOutput("Enter name:".to_string()),
+        ConsoleStep::Send("Ada".to_string()),
+        ConsoleStep::ExpectOutput("Hello, Ada".to_string()),
+    ]);
+    console.print("Enter name: ");
+    assert_eq!(console.read_line(), Some("Ada".to_string()));
+    console.println("Hello, Ada!");
+    assert_eq!(console.finish_script(), None);
+    assert_eq!(
+        console.events(),
+        [
+            ConsoleEvent::Output("Enter name: ".to_string()),
+            ConsoleEvent::Input("Ada".to_string()),
+            ConsoleEvent::Output("Hello, Ada!\n".to_string()),
+        ]
+    );
+
+    // Output the script expects before a read must come before it
+    let console = MockConsole::scripted(vec![
+        ConsoleStep::ExpectOutput("Name?".to_string()),
+        ConsoleStep::Send("Ada".to_string()),
+    ]);
+    console.print("Enter name: ");
+    assert_eq!(console.read_line(), None);
+    assert_eq!(
+        console.script_failure().as_deref(),
+        Some("expected output \"Name?\", but the program printed \"Enter name: \"")
+    );
+
+    // Input left in the script fails once the run is over
+    let console = MockConsole::scripted(vec![ConsoleStep::Send("Ada".to_string())]);
+    assert_eq!(
+        console.finish_script().as_deref(),
+        Some("the program never read the input \"Ada\"")
+    );
+
+    // An unscripted console reads nothing and never fails
+    let console = MockConsole::new();
+    assert_eq!(console.read_line(), None);
+    assert_eq!(console.finish_script(), None);
+}
+
+#[test]
+fn test_mock_console_captures_styles() {
+    let console = MockConsole::tty();
+    let ok = style::style("ok", "green", true, console.color()).unwrap();
+    let failed = style::style("failed", "red", false, console.color()).unwrap();
+    assert_eq!(ok, "\x1b[1;32mok\x1b[0m");
+    console.println(&format!("{} then {}", ok, failed));
+    assert_eq!(console.output(), ["ok then failed\n"]);
+    assert_eq!(
+        console.styles(),
+        [
+            StyledText {
+                text: "ok".to_string(),
+                color: Some("green".to_string()),
+                bold: true,
+            },
+            StyledText {
+                text: "failed".to_string(),
+                color: Some("red".to_string()),
+                bold: false,
+            },
+        ]
+    );
+
+    // Off a terminal the text is left alone
+    assert_eq!(style::style("ok", "green", true, false).unwrap(), "ok");
+    assert!(style::style("ok", "purple", false, true).is_err());
+}
+
+#[test]
+fn test_progress_events() {
+    let source = r#"
+module example
+fn main() effects(Progress) {
+  Progress.advance(1)
+  Progress.start("download", 3)
+  for i in range(0, 3) {
+    Progress.advance(1)
+  }
+  Progress.start("index", 0)
+  Progress.advance(40)
+  Progress.finish()
+  Progress.finish()
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let progress = MockProgress::default();
+    let mut interpreter = Interpreter::with_capabilities(Capabilities {
+        progress: Some(Box::new(progress.clone())),
+        ..Default::default()
+    });
+    interpreter.eval_module(&module).unwrap();
+    let advance = |task: &str, done, total| ProgressEvent::Advance {
+        task: task.to_string(),
+        done,
+        total,
+    };
+    assert_eq!(
+        progress.events(),
+        [
+            ProgressEvent::Start {
+                task: "download".to_string(),
+                total: 3,
+            },
+            advance("download", 1, 3),
+            advance("download", 2, 3),
+            advance("download", 3, 3),
+            ProgressEvent::Finish {
+                task: "download".to_string(),
+                done: 3,
+                total: 3,
+            },
+            ProgressEvent::Start {
+                task: "index".to_string(),
+                total: 0,
+            },
+            advance("index", 40, 0),
+            ProgressEvent::Finish {
+                task: "index".to_string(),
+                done: 40,
+                total: 0,
+            },
+        ]
+    );
+
+    let error = parse_and_eval(
+        "module example\nfn main() effects(Progress) {\n  Progress.start(\"x\", 1)\n}\n",
+    )
+    .unwrap_err();
+    assert_eq!(error.code, "E4004");
+}
+
+#[test]
+fn test_effect_audit_by_call_site() {
+    let source = r#"
+module example
+fn main() -> Int effects(Fs, Net, Rand, Clock) {
+  let config = Fs.read("config.txt").unwrap()
+  Fs.write("out.txt", config).unwrap()
+  let body = Net.get("https://api.example.com/v1/items?page=2")
+  let rolls = [Rand.int(1, 6), Rand.int(1, 6)]
+  Clock.now() + Clock.now()
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("audit.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let caps = Capabilities {
+        fs: Some(Box::new(MemoryFs::new(std::collections::BTreeMap::from([
+            ("config.txt".to_string(), "debug".to_string()),
+        ])))),
+        net: Some(Box::new(MockNet::default())),
+        rand: Some(Box::new(SeededRand::new(7))),
+        clock: Some(Box::new(FixedClock::new(1000))),
+        ..Default::default()
+    };
+    let mut interpreter = Interpreter::with_capabilities(caps);
+    assert!(interpreter.audit().is_none());
+    interpreter.start_audit();
+    interpreter.eval_module(&module).unwrap();
+    let audit = interpreter.audit().unwrap();
+    assert_eq!(
+        audit.files_read().into_iter().collect::<Vec<_>>(),
+        ["config.txt"]
+    );
+    assert_eq!(
+        audit.files_written().into_iter().collect::<Vec<_>>(),
+        ["out.txt"]
+    );
+    assert_eq!(
+        audit.hosts().into_iter().collect::<Vec<_>>(),
+        ["api.example.com"]
+    );
+    assert_eq!(audit.random_values(), 2);
+    assert_eq!(audit.clock_reads(), 2);
+    let events: Vec<(String, String, u64)> = audit
+        .events()
+        .into_iter()
+        .map(|event| (event.site, event.operation, event.count))
+        .collect();
+    assert_eq!(
+        events,
+        [
+            ("audit.astra:4:16".to_string(), "Fs.read".to_string(), 1),
+            ("audit.astra:5:3".to_string(), "Fs.write".to_string(), 1),
+            ("audit.astra:6:14".to_string(), "Net.get".to_string(), 1),
+            ("audit.astra:7:16".to_string(), "Rand.int".to_string(), 1),
+            ("audit.astra:7:32".to_string(), "Rand.int".to_string(), 1),
+            ("audit.astra:8:3".to_string(), "Clock.now".to_string(), 1),
+            ("audit.astra:8:17".to_string(), "Clock.now".to_string(), 1),
+        ]
+    );
+}
+
+#[test]
+fn test_attenuated_fs_capability_values() {
+    let source = r#"
+module example
+fn save(fs: Fs, path: Text) -> Text effects() {
+  match fs.write(path, "x") {
+    Ok(_) => "ok"
+    Err(e) => e
+  }
+}
+fn main() -> Text effects(Fs) {
+  let data = Fs.scoped("/data")
+  let results = [
+    save(Fs, "/etc/x"),
+    save(data, "/data/a.txt"),
+    save(data, "/data/../etc/x"),
+    save(data.read_only(), "/data/b.txt"),
+    save(data.scoped("/"), "/c.txt"),
+  ]
+  let escaped = data.exists("/etc/x")
+  let read = data.read_only().read("/data/a.txt")
+  "${results} ${escaped} ${read}"
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let fs = MemoryFs::default();
+    let caps = Capabilities {
+        fs: Some(Box::new(fs.clone())),
+        ..Default::default()
+    };
+    let result = Interpreter::with_capabilities(caps)
+        .eval_module(&module)
+        .unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "[ok, ok, Failed to write '/data/../etc/x': outside the directories this capability allows, Failed to write '/data/b.txt': the capability is read-only, Failed to write '/c.txt': outside the directories this capability allows] false Ok(x)"),
+        "{:?}",
+        result
+    );
+    assert_eq!(
+        fs.files().keys().collect::<Vec<_>>(),
+        vec!["/data/a.txt", "/etc/x"]
+    );
+}
+
+#[test]
+fn test_serve_routes_synthetic_requests() {
+    let source = r#"
+module example
+fn show_user(req: {params: Map[Text, Text], query: Map[Text, Text]}) -> {status: Int, body: Text} {
+  let id = req.params.get("id").unwrap_or("?")
+  let fields = req.query.get("fields").unwrap_or("all")
+  { status = 200, body = "user ${id} (${fields})" }
+}
+fn create_user(req: {body: Text}) -> {status: Int, body: Text} {
+  { status = 201, body = "created ${req.body}" }
+}
+fn main() -> Text effects(Serve) {
+  Serve.route("get", "/users/:id", show_user)
+  Serve.route("POST", "/users", create_user)
+  Serve.route("*", "/static/*", fn(req) { { body = req.path } })
+  let found = Serve.request("GET", "/users/42?fields=name", "")
+  let created = Serve.request("POST", "/users", "ada")
+  let asset = Serve.request("HEAD", "/static/css/site.css", "")
+  let missing = Serve.request("GET", "/nowhere", "")
+  let wrong_method = Serve.request("DELETE", "/users", "")
+  "${found.status} ${found.body}; ${created.status} ${created.body}; ${asset.status} ${asset.body}; ${missing.status} ${wrong_method.status}"
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let caps = Capabilities {
+        serve: Some(Box::new(SyntheticServe::default())),
+        ..Default::default()
+    };
+    let result = Interpreter::with_capabilities(caps)
+        .eval_module(&module)
+        .unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "200 user 42 (name); 201 created ada; 200 /static/css/site.css; 404 405"),
+        "{:?}",
+        result
+    );
+
+    // Serve is gated like every other effect
+    let err = parse_and_eval(
+        "module example\nfn main() effects(Serve) {\n  Serve.route(\"GET\", \"/\", fn(req) { {} })\n}\n",
+    )
+    .unwrap_err();
+    assert_eq!(err.code, "E4004");
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_db_query_and_execute() {
+    let source = r#"
+module example
+fn main() -> Text effects(Db) {
+  Db.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, score REAL)")
+  let added = Db.execute("INSERT INTO users (name, score) VALUES (?1, ?2), (?3, ?4)", ["ada", 9.5, "bob", None])
+  let rows = Db.query("SELECT id, name, score FROM users WHERE name = ?1", ["ada"]).unwrap()
+  let first = rows.get(0).unwrap()
+  let missing = match Db.query("SELECT * FROM nowhere") {
+    Ok(_) => "found"
+    Err(_) => "no table"
+  }
+  "${added.unwrap()} ${rows.len()} ${first.id} ${first.name} ${first.score}; ${missing}"
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let caps = Capabilities {
+        db: Some(Box::new(SqliteDb::in_memory())),
+        ..Default::default()
+    };
+    let result = Interpreter::with_capabilities(caps)
+        .eval_module(&module)
+        .unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "2 1 1 ada 9.5; no table"),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_db_requires_capability() {
+    let err =
+        parse_and_eval("module example\nfn main() effects(Db) {\n  Db.query(\"SELECT 1\")\n}\n")
+            .unwrap_err();
+    assert_eq!(err.code, "E4004");
+}
+
+#[test]
+fn test_serve_requests_answers_queued_requests() {
+    let source = r#"
+module example
+fn echo(req: {method: Text, body: Text}) -> {status: Int, headers: Map[Text, Text], body: Text} {
+  { status = 200, headers = Map.from([("X-Method", req.method)]), body = req.body }
+}
+fn broken(req: {body: Text}) -> {status: Int} {
+  { status = 1000 }
+}
+fn main() effects(Serve) {
+  Serve.route("POST", "/echo", echo)
+  Serve.route("GET", "/broken", broken)
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let request = |method: &str, url: &str, body: &str| HttpRequest {
+        method: method.to_string(),
+        url: url.to_string(),
+        body: body.to_string(),
+        ..Default::default()
+    };
+    let serve = SyntheticServe::new(vec![
+        request("POST", "/echo", "ping"),
+        request("GET", "/broken", ""),
+        request("GET", "/echo", ""),
+    ]);
+    let caps = Capabilities {
+        serve: Some(Box::new(serve.clone())),
+        ..Default::default()
+    };
+    let mut interpreter = Interpreter::with_capabilities(caps);
+    interpreter.eval_module(&module).unwrap();
+    assert_eq!(interpreter.route_count(), 2);
+    interpreter.serve_requests().unwrap();
+
+    let responses = serve.responses();
+    let summary: Vec<(u16, &str)> = responses
+        .iter()
+        .map(|r| (r.status, r.body.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (200, "ping"),
+            (500, "Internal Server Error"),
+            (405, "Method Not Allowed")
+        ]
+    );
+    assert_eq!(
+        responses[0].headers,
+        vec![("X-Method".to_string(), "POST".to_string())]
+    );
+}
+
+#[test]
+fn test_websocket_with_scripted_messages() {
+    let source = r#"
+module example
+fn main() -> Text effects(Net) {
+  let ws = Net.ws_connect("wss://agent.example/stream").unwrap()
+  ws.send("start").unwrap()
+  let first = ws.recv(Duration.seconds(5))
+  let second = ws.recv()
+  let drained = ws.recv(Duration.millis(10))
+  ws.close().unwrap()
+  let after = [ws.send("late"), ws.close()]
+  "${first} ${second} ${drained} ${ws.recv()} ${after}"
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let websocket = ScriptedWebSocket::new(vec!["token: He".to_string(), "token: llo".to_string()]);
+    let caps = Capabilities {
+        net: Some(Box::new(
+            MockNet::default().with_websocket(websocket.clone()),
+        )),
+        ..Default::default()
+    };
+    let result = Interpreter::with_capabilities(caps)
+        .eval_module(&module)
+        .unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "Ok(Some(token: He)) Ok(Some(token: llo)) Ok(None) Err(connection closed) [Err(connection closed), Ok(())]"),
+        "{:?}",
+        result
+    );
+    assert_eq!(websocket.sent(), vec!["start".to_string()]);
+    assert!(websocket.is_closed());
+
+    // Without a scripted connection, connecting fails as a value
+    let caps = Capabilities {
+        net: Some(Box::new(MockNet::default())),
+        ..Default::default()
+    };
+    let source = "module example\nfn main() -> Bool effects(Net) {\n  Net.ws_connect(\"ws://localhost\").is_err()\n}\n";
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let result = Interpreter::with_capabilities(caps)
+        .eval_module(&module)
+        .unwrap();
+    assert!(matches!(result, Value::Bool(true)));
+}
+
+#[test]
+fn test_rpc_call_and_batch() {
+    /// A JSON-RPC server behind `Net.post`: `add` sums its params, anything
+    /// else is an unknown method. Batch replies come back in reverse order.
+    struct RpcServer {
+        bodies: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+    }
+    impl NetCapability for RpcServer {
+        fn get(&self, _url: &str) -> Result<Value, String> {
+            Err("GET is not supported".to_string())
+        }
+        fn post(&self, url: &str, body: &str) -> Result<Value, String> {
+            if url != "http://tools.local/rpc" {
+                return Err(format!("connection refused: {}", url));
+            }
+            self.bodies.borrow_mut().push(body.to_string());
+            let answer = |request: &serde_json::Value| match request["method"].as_str() {
+                Some("add") => serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": request["params"].as_array().unwrap().iter().filter_map(|n| n.as_i64()).sum::<i64>(),
+                }),
+                _ => serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": {"code": -32601, "message": "Method not found", "data": {"method": request["method"]}},
+                }),
+            };
+            let request: serde_json::Value = serde_json::from_str(body).unwrap();
+            let reply = match request.as_array() {
+                Some(batch) => serde_json::Value::Array(batch.iter().rev().map(answer).collect()),
+                None => answer(&request),
+            };
+            Ok(Value::Text(reply.to_string()))
+        }
+    }
+
+    let source = r#"
+module example
+fn main() -> Text effects(Net) {
+  let sum = rpc_call("http://tools.local/rpc", "add", [1, 2, 3])
+  let unknown = match rpc_call("http://tools.local/rpc", "subtract", [1]) {
+    Ok(_) => "ok"
+    Err(e) => "${e.code} ${e.message} ${e.data}"
+  }
+  let batch = rpc_batch("http://tools.local/rpc", [("add", [10, 5]), ("nope", None), ("add", [])])
+  let down = match rpc_call("http://elsewhere.local/rpc", "add", [1]) {
+    Ok(_) => "ok"
+    Err(e) => "${e.code} ${e.message}"
+  }
+  "${sum} | ${unknown} | ${batch.len()} ${batch[0]} ${batch[2]} | ${down}"
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let bodies = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let caps = Capabilities {
+        net: Some(Box::new(RpcServer {
+            bodies: bodies.clone(),
+        })),
+        ..Default::default()
+    };
+    let result = Interpreter::with_capabilities(caps)
+        .eval_module(&module)
+        .unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "Ok(6) | -32601 Method not found Map({method: subtract}) | 3 Ok(15) Ok(0) | -32000 connection refused: http://elsewhere.local/rpc"),
+        "{:?}",
+        result
+    );
+    // Ids count up across calls, and a batch is sent as one request
+    assert_eq!(
+        bodies.borrow().as_slice(),
+        [
+            r#"{"jsonrpc":"2.0","id":1,"method":"add","params":[1,2,3]}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"subtract","params":[1]}"#,
+            r#"[{"jsonrpc":"2.0","id":3,"method":"add","params":[10,5]},{"jsonrpc":"2.0","id":4,"method":"nope"},{"jsonrpc":"2.0","id":5,"method":"add","params":[]}]"#,
+        ]
+    );
+}
+
+#[test]
+fn test_rpc_reply_decoding() {
+    let error = |result: &Value| match result {
+        Value::Err(inner) => match inner.as_ref() {
+            Value::Record(fields) => match (&fields["code"], &fields["message"]) {
+                (Value::Int(code), Value::Text(message)) => (*code, message.clone()),
+                _ => panic!("{:?}", fields),
+            },
+            other => panic!("{:?}", other),
+        },
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(
+        error(&rpc::decode_reply("<html>", &[1], false)[0]),
+        (
+            -32700,
+            "invalid JSON in response: Unexpected character in JSON: '<'".to_string()
+        )
+    );
+    let results = rpc::decode_reply(r#"[{"jsonrpc":"2.0","id":2,"result":null}]"#, &[1, 2], true);
+    assert_eq!(
+        error(&results[0]),
+        (-32603, "no response for request 1".to_string())
+    );
+    assert!(matches!(&results[1], Value::Ok(inner) if matches!(**inner, Value::None)));
+    // An error for the whole batch answers every call in it
+    let results = rpc::decode_reply(
+        r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"Invalid Request"}}"#,
+        &[7, 8],
+        true,
+    );
+    assert!(results
+        .iter()
+        .all(|result| error(result) == (-32600, "Invalid Request".to_string())));
+    // A single reply carrying another request's id is not the answer
+    assert_eq!(
+        error(&rpc::decode_reply(r#"{"jsonrpc":"2.0","id":9,"result":42}"#, &[1], false)[0]),
+        (
+            -32603,
+            "response id 9 does not match the request".to_string()
+        )
+    );
+    assert!(matches!(
+        &rpc::decode_reply(r#"{"jsonrpc":"2.0","id":1,"result":42}"#, &[1], false)[0],
+        Value::Ok(inner) if matches!(**inner, Value::Int(42))
+    ));
+}
+
+#[test]
+fn test_user_parse_int_shadows_builtin() {
+    let source = r#"
+module example
+fn parse_int(s: Text) -> Int {
+  s.len()
+}
+fn main() -> Int {
+  parse_int("abc")
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Int(3)));
+}
+
+#[test]
+fn test_json_parse_int() {
+    let source = r#"
+module example
+fn main() -> Int {
+  json_parse("42")
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Int(42)));
+}
+
+#[test]
+fn test_json_parse_float() {
+    let source = r#"
+module example
+fn main() -> Float {
+  json_parse("1.23")
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    if let Value::Float(f) = result {
+        assert!((f - 1.23).abs() < 0.001);
+    } else {
+        panic!("Expected Float, got {:?}", result);
+    }
+}
+
+#[test]
+fn test_json_parse_string() {
+    let source = r#"
+module example
+fn main() -> Text {
+  json_parse("\"hello world\"")
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(matches!(result, Value::Text(ref s) if s == "hello world"));
 }
 
 #[test]
@@ -3393,6 +5454,29 @@ fn main() -> Int {
     assert!(matches!(result, Value::Int(3)));
 }
 
+#[test]
+fn test_regex_positions_count_characters() {
+    let source = r#"
+module example
+fn main() -> Text {
+  let text = "café bar"
+  let found = match text.find_pattern("bar") {
+    Some(m) => text.substring(m.start, m.end)
+    None => "no match"
+  }
+  let ends = regex_find_all("é|r", text).map(fn(m) { m.end })
+  let sep = ","
+  "${found} ${ends.map(fn(n) { to_text(n) }).join(sep)}"
+}
+"#;
+    let result = parse_and_eval(source).unwrap();
+    assert!(
+        matches!(result, Value::Text(ref s) if s == "bar 4,8"),
+        "{:?}",
+        result
+    );
+}
+
 #[test]
 fn test_regex_replace() {
     let source = r#"
@@ -3739,3 +5823,539 @@ fn test_net_serve_query_params() {
 
     drop(handle);
 }
+
+#[test]
+fn test_eval_module_entry_runs_named_function() {
+    let source = r#"
+module example
+
+fn main() -> Int {
+  1
+}
+
+fn migrate() -> Int {
+  2
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let lexer = Lexer::new(&source_file);
+    let mut parser = Parser::new(lexer, source_file.clone());
+    let module = parser.parse_module().expect("parse failed");
+
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval_module_entry(&module, "migrate").unwrap();
+    assert!(matches!(result, Value::Int(2)));
+}
+
+#[test]
+fn test_eval_module_entry_requires_the_entry_function() {
+    let source = r#"
+module example
+
+fn migrate(dry_run: Bool) -> Int {
+  2
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let lexer = Lexer::new(&source_file);
+    let mut parser = Parser::new(lexer, source_file.clone());
+    let module = parser.parse_module().expect("parse failed");
+
+    let error = Interpreter::new()
+        .eval_module_entry(&module, "migrat")
+        .unwrap_err();
+    assert_eq!(error.code, "E4029");
+    assert_eq!(error.message, "entry function `migrat` is not defined");
+    let error = Interpreter::new()
+        .eval_module_entry(&module, "migrate")
+        .unwrap_err();
+    assert_eq!(error.code, "E4029");
+    assert!(error.message.contains("must not take parameters"));
+
+    // Evaluating a module without `main` only loads it
+    let result = Interpreter::new().eval_module(&module).unwrap();
+    assert!(matches!(result, Value::Unit));
+}
+
+#[test]
+fn test_cfg_selects_definitions() {
+    let source = r#"module example
+
+@cfg(test)
+fn greeting() -> Text {
+  "test"
+}
+
+@cfg(feature = "loud")
+fn greeting() -> Text {
+  "LOUD"
+}
+
+fn main() -> Text {
+  greeting()
+}
+"#;
+    let module = crate::parser::parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    let run = |cfg: crate::cfg::Cfg| {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_cfg(cfg);
+        interpreter.eval_module(&module)
+    };
+    assert!(matches!(run(crate::cfg::Cfg::test()), Ok(Value::Text(s)) if s == "test"));
+    assert!(matches!(
+        run(crate::cfg::Cfg::default().with_features(["loud"])),
+        Ok(Value::Text(s)) if s == "LOUD"
+    ));
+    assert!(run(crate::cfg::Cfg::default()).is_err());
+}
+
+#[test]
+fn test_assert_matches_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let module = crate::parser::parse_source(
+        "module example\n\nfn main() -> Unit {\n  assert_matches_file(\"a\\nb\", \"golden/out.txt\")\n}\n",
+        &PathBuf::from("test.astra"),
+    )
+    .unwrap();
+    let run = |update: bool| {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_golden_files(GoldenFiles {
+            fs: Box::new(RootedFs::new(dir.path())),
+            update,
+        });
+        interpreter.eval_module(&module)
+    };
+
+    let missing = run(false).unwrap_err();
+    assert!(missing.message.contains("does not exist"));
+
+    assert!(run(true).is_ok());
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("golden/out.txt")).unwrap(),
+        "a\nb"
+    );
+    assert!(run(false).is_ok());
+
+    std::fs::write(dir.path().join("golden/out.txt"), "a\nc").unwrap();
+    let mismatch = run(false).unwrap_err();
+    assert_eq!(mismatch.code, "E4021");
+    assert!(mismatch.message.contains("-c\n+b"));
+}
+
+#[test]
+fn test_assert_matches_file_outside_tests() {
+    let result = parse_and_eval(
+        "module example\n\nfn main() -> Unit {\n  assert_matches_file(1, \"out.txt\")\n}\n",
+    );
+    assert!(result
+        .unwrap_err()
+        .message
+        .contains("capability not available"));
+}
+
+#[test]
+fn test_eval_limits() {
+    let source_file = SourceFile::new(
+        PathBuf::from("test.astra"),
+        "module example\n\nfn down() -> Int {\n  down()\n}\n\nfn deep() -> Int {\n  1 + deep()\n}\n\nfn spin() -> Int {\n  while true {}\n  0\n}\n".to_string(),
+    );
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let run = |entry: &str| {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_limits(EvalLimits {
+            max_steps: 10_000,
+            max_call_depth: 3,
+        });
+        interpreter.eval_module_entry(&module, entry).unwrap_err()
+    };
+
+    let depth = run("deep");
+    assert_eq!(depth.code, "E4022");
+    assert!(depth.message.contains("call depth"));
+
+    let steps = run("spin");
+    assert_eq!(steps.code, "E4022");
+    assert!(steps.message.contains("evaluation steps"));
+
+    // Self tail calls reuse one frame, so only the step limit stops them
+    let tail = run("down");
+    assert!(tail.message.contains("evaluation steps"));
+}
+
+#[test]
+fn test_max_call_depth() {
+    // Run in a thread with a larger stack to avoid overflow in debug builds
+    let handle = std::thread::Builder::new()
+        .stack_size(64 * 1024 * 1024)
+        .spawn(|| {
+            let source_file = SourceFile::new(
+                PathBuf::from("test.astra"),
+                "module example\n\nfn deep(n: Int) -> Int {\n  if n == 0 { 0 } else { 1 + deep(n - 1) }\n}\n\nfn shallow() -> Int {\n  deep(5)\n}\n\nfn too_deep() -> Int {\n  deep(100)\n}\n".to_string(),
+            );
+            let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+            let module = parser.parse_module().unwrap();
+            let run = |entry: &str| {
+                let mut interpreter = Interpreter::new();
+                interpreter.set_max_call_depth(20);
+                interpreter.eval_module_entry(&module, entry)
+            };
+
+            assert!(matches!(run("shallow"), Ok(Value::Int(5))));
+
+            let error = run("too_deep").unwrap_err();
+            assert_eq!(error.code, "E4028");
+            assert!(error.message.starts_with("stack overflow: more than 20 nested calls"));
+            // The trace is attached once, with the middle of the stack elided
+            assert_eq!(error.message.matches("Stack trace:").count(), 1);
+            assert!(error.message.contains("  9: deep"));
+            assert!(error.message.contains("... 5 more frames"));
+            assert!(!error.message.contains("  10: deep"));
+            assert!(error.message.contains("  19: deep"));
+        })
+        .unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_cost_report() {
+    let source_file = SourceFile::new(
+        PathBuf::from("test.astra"),
+        "module example\n\nfn small() effects(Console) {\n  let xs = [1, 2]\n  Console.println(\"a\")\n  println(\"b\")\n}\n\nfn large() effects(Console) {\n  let xs = [1, 2, 3, 4, 5, 6, 7, 8]\n  Console.println(\"a\")\n  println(\"b\")\n}\n".to_string(),
+    );
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let run = |entry: &str| {
+        let mut interpreter = Interpreter::with_capabilities(Capabilities {
+            console: Some(Box::new(MockConsole::new())),
+            ..Default::default()
+        });
+        interpreter.track_memory();
+        interpreter.eval_module_entry(&module, entry).unwrap();
+        interpreter.cost()
+    };
+
+    let small = run("small");
+    assert_eq!(small, run("small"));
+    assert_eq!(small.effect_calls.get("Console"), Some(&2));
+    let large = run("large");
+    assert!(large.steps > small.steps);
+    assert_eq!(
+        large.peak_memory.unwrap() - small.peak_memory.unwrap(),
+        6 * value_size(&Value::Int(0))
+    );
+
+    // Memory is only estimated when asked for
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_module_entry(&module, "small").ok();
+    assert_eq!(interpreter.cost().peak_memory, None);
+}
+
+#[test]
+fn test_contract_modes() {
+    let source_file = SourceFile::new(
+        PathBuf::from("test.astra"),
+        "module example\n\npublic fn outer(n: Int) -> Int\n  requires n > 0\n{\n  inner(n)\n}\n\nfn inner(n: Int) -> Int\n  ensures result > 10\n{\n  n\n}\n\nfn bad_outer() -> Int {\n  outer(0)\n}\n\nfn bad_inner() -> Int {\n  outer(5)\n}\n".to_string(),
+    );
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let run = |entry: &str, mode: ContractMode| {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_contract_mode(mode);
+        interpreter.eval_module_entry(&module, entry)
+    };
+
+    assert_eq!(
+        run("bad_outer", ContractMode::All).unwrap_err().code,
+        "E3001"
+    );
+    assert_eq!(
+        run("bad_inner", ContractMode::All).unwrap_err().code,
+        "E3002"
+    );
+    // Boundary mode checks only the public function
+    assert_eq!(
+        run("bad_outer", ContractMode::Boundary).unwrap_err().code,
+        "E3001"
+    );
+    assert!(matches!(
+        run("bad_inner", ContractMode::Boundary),
+        Ok(Value::Int(5))
+    ));
+    assert!(matches!(
+        run("bad_outer", ContractMode::Off),
+        Ok(Value::Int(0))
+    ));
+}
+
+#[test]
+fn test_budget_exceeded() {
+    let source_file = SourceFile::new(
+        PathBuf::from("test.astra"),
+        "module example\n\nfn chatty() effects(Console) {\n  Console.println(\"a\")\n  println(\"b\")\n  Console.println(\"c\")\n}\n\nfn hungry() -> Int {\n  let xs = [1, 2, 3, 4, 5, 6, 7, 8]\n  len(xs)\n}\n\nfn spin() -> Int {\n  while true {}\n  0\n}\n".to_string(),
+    );
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().unwrap();
+    let run = |entry: &str, budget: Budget| {
+        let mut interpreter = Interpreter::with_capabilities(Capabilities {
+            console: Some(Box::new(MockConsole::new())),
+            ..Default::default()
+        });
+        interpreter.set_budget(budget);
+        interpreter.eval_module_entry(&module, entry)
+    };
+
+    let effects = Budget {
+        max_effect_calls: [("console".to_string(), 2)].into(),
+        ..Default::default()
+    };
+    let error = run("chatty", effects).unwrap_err();
+    assert_eq!(error.code, "E4030");
+    assert!(error.message.contains("`max_effect_calls.console` = 2"));
+
+    let memory = Budget {
+        max_memory: Some(64),
+        ..Default::default()
+    };
+    let error = run("hungry", memory).unwrap_err();
+    assert!(error.message.contains("`max_memory` = 64"));
+
+    let steps = Budget {
+        max_steps: Some(1000),
+        ..Default::default()
+    };
+    let error = run("spin", steps.clone()).unwrap_err();
+    assert!(error.message.contains("`max_steps` = 1000"));
+
+    // Runs within budget are unaffected
+    assert!(run("hungry", steps).is_ok());
+}
+
+#[test]
+fn test_closures_share_cached_bodies() {
+    let source = r#"
+module example
+
+fn double(x: Int) -> Int {
+  x * 2
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().expect("parse failed");
+    let mut interpreter = Interpreter::new();
+    interpreter.load_module(&module).unwrap();
+
+    let body = |value: Option<Value>| match value {
+        Some(Value::Closure { body, .. }) => body.clone(),
+        other => panic!("expected closure, got {:?}", other),
+    };
+    let first = body(interpreter.env.lookup("double"));
+    let second = body(interpreter.env.lookup("double"));
+    assert!(std::rc::Rc::ptr_eq(&first, &second));
+
+    // Each load starts a fresh cache, so long sessions don't accumulate bodies
+    interpreter.load_module(&module).unwrap();
+    let reloaded = body(interpreter.env.lookup("double"));
+    assert!(!std::rc::Rc::ptr_eq(&first, &reloaded));
+}
+
+#[test]
+fn test_node_cache_rebuilds_repeated_ids() {
+    let mut cache = body_cache::NodeCache::new();
+    let id = NodeId::new();
+    let span =
+        |start| crate::diagnostics::Span::new(PathBuf::from("a.astra"), start, start, 1, 1, 1, 1);
+    let first = cache.alloc(id, &span(0), || 1);
+    assert!(std::rc::Rc::ptr_eq(
+        &first,
+        &cache.alloc(id, &span(0), || 2)
+    ));
+    // The same ID on another node, as in a deserialized AST, isn't aliased
+    assert_eq!(*cache.alloc(id, &span(7), || 2), 2);
+}
+
+#[test]
+fn test_eval_str_in_loaded_module() {
+    let source = r#"
+module example
+
+fn double(x: Int) -> Int {
+  x * 2
+}
+"#;
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().expect("parse failed");
+    let mut interpreter = Interpreter::new();
+    interpreter.load_module(&module).unwrap();
+
+    assert!(matches!(
+        interpreter.eval_str("double(21)"),
+        Ok(Value::Int(42))
+    ));
+    let doubled = interpreter.eval_str("[1, 2].map(double)").unwrap();
+    assert_eq!(format_value(&doubled), "[2, 4]");
+    // `?` at the top gives the error it returns
+    let returned = interpreter.eval_str("Err(\"no\")?").unwrap();
+    assert!(
+        matches!(returned, Value::Err(inner) if matches!(*inner, Value::Text(ref t) if t == "no"))
+    );
+
+    let error = interpreter.eval_str("double(1) double").unwrap_err();
+    assert_eq!(error.code, "E0001");
+    assert_eq!(error.span.map(|span| span.start_col), Some(11));
+    let error = interpreter.eval_str("triple(1)").unwrap_err();
+    assert!(error.message.contains("triple"), "{}", error);
+}
+
+#[test]
+fn test_scratch_reuses_buffers() {
+    let mut scratch = body_cache::Scratch::new();
+    let mut buffer = scratch.take();
+    buffer.push(Value::Int(1));
+    let capacity = buffer.capacity();
+    scratch.give(buffer);
+
+    let reused = scratch.take();
+    assert!(reused.is_empty());
+    assert_eq!(reused.capacity(), capacity);
+}
+
+#[cfg(feature = "jit")]
+fn jit_interpreter(source: &str) -> Interpreter {
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let module = parser.parse_module().expect("parse failed");
+    let mut interpreter = Interpreter::new();
+    interpreter.set_jit_threshold(2);
+    interpreter.load_module(&module).unwrap();
+    interpreter
+}
+
+#[cfg(feature = "jit")]
+fn call_named(
+    interpreter: &mut Interpreter,
+    name: &str,
+    args: Vec<Value>,
+) -> Result<Value, RuntimeError> {
+    let func = interpreter.env.lookup(name).unwrap();
+    interpreter.call_function(func, args)
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn test_jit_compiles_hot_numeric_functions() {
+    let mut interpreter = jit_interpreter(
+        r#"
+module example
+
+fn fib(n: Int) -> Int {
+  if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+}
+
+fn sum_to(n: Int, acc: Int) -> Int {
+  if n <= 0 { acc } else { sum_to(n - 1, acc + n) }
+}
+
+fn mean_square(n: Int) -> Float {
+  let mut i = 0
+  let mut total = 0.0
+  let mut x = 1.5
+  while i < n {
+    total = total + x * x
+    x = x + 1.0
+    i = i + 1
+  }
+  total / 3.0
+}
+
+fn greet(name: Text) -> Text {
+  "hi " + name
+}
+"#,
+    );
+    for _ in 0..3 {
+        let value = call_named(&mut interpreter, "fib", vec![Value::Int(15)]).unwrap();
+        assert!(matches!(value, Value::Int(610)));
+        let value = call_named(
+            &mut interpreter,
+            "sum_to",
+            vec![Value::Int(100_000), Value::Int(0)],
+        )
+        .unwrap();
+        assert!(matches!(value, Value::Int(5_000_050_000)));
+        let value = call_named(&mut interpreter, "mean_square", vec![Value::Int(3)]).unwrap();
+        assert!(matches!(value, Value::Float(f) if (f - (2.25 + 6.25 + 12.25) / 3.0).abs() < 1e-9));
+        call_named(&mut interpreter, "greet", vec![Value::Text("a".into())]).unwrap();
+    }
+    assert_eq!(interpreter.jit_compiled(), ["fib", "mean_square", "sum_to"]);
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn test_jit_falls_back_on_runtime_errors() {
+    let mut interpreter = jit_interpreter(
+        r#"
+module example
+
+fn ratio(a: Int, b: Int) -> Int {
+  a / b
+}
+"#,
+    );
+    for _ in 0..3 {
+        let value = call_named(
+            &mut interpreter,
+            "ratio",
+            vec![Value::Int(7), Value::Int(2)],
+        );
+        assert!(matches!(value, Ok(Value::Int(3))));
+    }
+    assert_eq!(interpreter.jit_compiled(), ["ratio"]);
+    let error = call_named(
+        &mut interpreter,
+        "ratio",
+        vec![Value::Int(7), Value::Int(0)],
+    )
+    .unwrap_err();
+    assert_eq!(error.code, "E4003");
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn test_jit_enforces_max_call_depth() {
+    // Warm-up calls run interpreted, so give them room in debug builds
+    let handle = std::thread::Builder::new()
+        .stack_size(64 * 1024 * 1024)
+        .spawn(|| {
+            let mut interpreter = jit_interpreter(
+                r#"
+module example
+
+fn deep(n: Int) -> Int {
+  if n == 0 { 0 } else { 1 + deep(n - 1) }
+}
+"#,
+            );
+            interpreter.set_max_call_depth(50);
+            for _ in 0..3 {
+                let value = call_named(&mut interpreter, "deep", vec![Value::Int(5)]);
+                assert!(matches!(value, Ok(Value::Int(5))));
+            }
+            assert_eq!(interpreter.jit_compiled(), ["deep"]);
+
+            // 50 nested calls fit, 51 don't, however deep the recursion goes
+            let value = call_named(&mut interpreter, "deep", vec![Value::Int(49)]);
+            assert!(matches!(value, Ok(Value::Int(49))));
+            for n in [50, 100_000_000] {
+                let error = call_named(&mut interpreter, "deep", vec![Value::Int(n)]).unwrap_err();
+                assert_eq!(error.code, "E4028");
+                assert!(error
+                    .message
+                    .starts_with("stack overflow: more than 50 nested calls"));
+            }
+        })
+        .unwrap();
+    handle.join().unwrap();
+}
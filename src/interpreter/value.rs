@@ -1,9 +1,14 @@
 //! Runtime value types for the Astra interpreter.
 
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+use rust_decimal::Decimal;
 
 use crate::parser::ast::*;
 
+use super::capabilities::CapabilityValue;
 use super::environment::Environment;
 
 /// Runtime value
@@ -16,12 +21,20 @@ pub enum Value {
     Int(i64),
     /// Float
     Float(f64),
+    /// Exact decimal number
+    Decimal(Decimal),
+    /// Span of time in milliseconds
+    Duration(i64),
+    /// Instant a deadline expires, in milliseconds since the Unix epoch
+    Deadline(i64),
+    /// Raw bytes
+    Bytes(Vec<u8>),
     /// Boolean
     Bool(bool),
     /// Text string
     Text(String),
     /// Record value
-    Record(HashMap<String, Value>),
+    Record(IndexMap<String, Value>),
     /// Enum variant
     Variant {
         name: String,
@@ -31,7 +44,7 @@ pub enum Value {
     Closure {
         name: Option<String>,
         params: Vec<String>,
-        body: ClosureBody,
+        body: Rc<ClosureBody>,
         env: Environment,
     },
     /// Option::Some
@@ -63,6 +76,29 @@ pub enum Value {
         /// Arguments to pass when resolving
         args: Vec<Value>,
     },
+    /// Open WebSocket connection, by its id in the interpreter's table
+    WebSocket(u64),
+    /// Mutable cell from `Ref.new(v)`; copies of a Ref share one cell
+    Ref(Rc<RefCell<Value>>),
+    /// Capability passed as a value, such as `Fs` or `fs.read_only()`
+    Capability(CapabilityValue),
+    /// A builtin namespace such as `Map` or `Duration`, or a user-defined
+    /// effect, as the receiver of its operations
+    Namespace(String),
+}
+
+/// Apply `f` to what a Ref holds, or return `cyclic` when the Ref is already
+/// being walked further up, so walking a Ref that contains itself stops.
+/// The cell stays borrowed mutably while `f` runs; walks never change it.
+pub(super) fn with_ref_contents<T>(
+    cell: &RefCell<Value>,
+    f: impl FnOnce(&Value) -> T,
+    cyclic: T,
+) -> T {
+    match cell.try_borrow_mut() {
+        Ok(contents) => f(&contents),
+        Err(_) => cyclic,
+    }
 }
 
 /// Closure body containing the AST block and optional contracts
@@ -74,6 +110,14 @@ pub struct ClosureBody {
     pub requires: Vec<Expr>,
     /// Postconditions (ensures clauses)
     pub ensures: Vec<Expr>,
+    /// Whether the contract is checked in [`ContractMode::Boundary`]: true
+    /// for `public` functions and trait methods
+    ///
+    /// [`ContractMode::Boundary`]: super::ContractMode::Boundary
+    pub public: bool,
+    /// Names a lambda's body mentions, captured where the lambda is created
+    /// (empty for named functions, which see the globals at call time)
+    pub captures: Vec<String>,
 }
 
 /// Compare two values for equality
@@ -82,6 +126,11 @@ pub fn values_equal(left: &Value, right: &Value) -> bool {
         (Value::Unit, Value::Unit) => true,
         (Value::Int(a), Value::Int(b)) => a == b,
         (Value::Float(a), Value::Float(b)) => a == b,
+        (Value::Decimal(a), Value::Decimal(b)) => a == b,
+        (Value::Duration(a), Value::Duration(b)) | (Value::Deadline(a), Value::Deadline(b)) => {
+            a == b
+        }
+        (Value::Bytes(a), Value::Bytes(b)) => a == b,
         (Value::Bool(a), Value::Bool(b)) => a == b,
         (Value::Text(a), Value::Text(b)) => a == b,
         (Value::None, Value::None) => true,
@@ -118,15 +167,176 @@ pub fn values_equal(left: &Value, right: &Value) -> bool {
         (Value::Closure { .. }, Value::Closure { .. }) => false,
         (Value::VariantConstructor { .. }, Value::VariantConstructor { .. }) => false,
         (Value::Future { .. }, Value::Future { .. }) => false,
+        (Value::WebSocket(a), Value::WebSocket(b)) => a == b,
+        (Value::Capability(a), Value::Capability(b)) => a == b,
+        (Value::Namespace(a), Value::Namespace(b)) => a == b,
+        // Two Refs are equal when they are the same cell, not when they hold
+        // equal values at the moment
+        (Value::Ref(a), Value::Ref(b)) => Rc::ptr_eq(a, b),
         _ => false,
     }
 }
 
+/// The differences between two values, one line per differing leaf, each
+/// prefixed with its path (`.field`, `[index]`, `.Variant`). Used by
+/// `assert_eq` to point at what differs in large values.
+pub fn value_diff(left: &Value, right: &Value) -> Vec<String> {
+    let mut lines = Vec::new();
+    diff_into(left, right, "", &mut lines);
+    lines
+}
+
+fn diff_into(left: &Value, right: &Value, path: &str, lines: &mut Vec<String>) {
+    if values_equal(left, right) {
+        return;
+    }
+    let at = |path: &str| {
+        if path.is_empty() {
+            "value".to_string()
+        } else {
+            path.to_string()
+        }
+    };
+    match (left, right) {
+        (Value::Record(l), Value::Record(r)) => {
+            let mut keys: Vec<&String> = l.keys().chain(r.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let field = format!("{}.{}", path, key);
+                match (l.get(key), r.get(key)) {
+                    (Some(a), Some(b)) => diff_into(a, b, &field, lines),
+                    (Some(a), None) => {
+                        lines.push(format!("{}: only in left: {}", field, show_value(a)))
+                    }
+                    (None, Some(b)) => {
+                        lines.push(format!("{}: only in right: {}", field, show_value(b)))
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::List(l), Value::List(r)) | (Value::Tuple(l), Value::Tuple(r)) => {
+            for i in 0..l.len().max(r.len()) {
+                let index = format!("{}[{}]", path, i);
+                match (l.get(i), r.get(i)) {
+                    (Some(a), Some(b)) => diff_into(a, b, &index, lines),
+                    (Some(a), None) => {
+                        lines.push(format!("{}: only in left: {}", index, show_value(a)))
+                    }
+                    (None, Some(b)) => {
+                        lines.push(format!("{}: only in right: {}", index, show_value(b)))
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Map(l), Value::Map(r)) => {
+            for (key, a) in l {
+                let entry = format!("{}[{}]", path, show_value(key));
+                match r.iter().find(|(k, _)| values_equal(k, key)) {
+                    Some((_, b)) => diff_into(a, b, &entry, lines),
+                    None => lines.push(format!("{}: only in left: {}", entry, show_value(a))),
+                }
+            }
+            for (key, b) in r {
+                if !l.iter().any(|(k, _)| values_equal(k, key)) {
+                    let entry = format!("{}[{}]", path, show_value(key));
+                    lines.push(format!("{}: only in right: {}", entry, show_value(b)));
+                }
+            }
+        }
+        (Value::Set(l), Value::Set(r)) => {
+            for a in l.iter().filter(|a| !r.iter().any(|b| values_equal(a, b))) {
+                lines.push(format!("{}: only in left: {}", at(path), show_value(a)));
+            }
+            for b in r.iter().filter(|b| !l.iter().any(|a| values_equal(a, b))) {
+                lines.push(format!("{}: only in right: {}", at(path), show_value(b)));
+            }
+        }
+        (Value::Some(a), Value::Some(b)) => diff_into(a, b, &format!("{}.Some", path), lines),
+        (Value::Ok(a), Value::Ok(b)) => diff_into(a, b, &format!("{}.Ok", path), lines),
+        (Value::Err(a), Value::Err(b)) => diff_into(a, b, &format!("{}.Err", path), lines),
+        (
+            Value::Variant {
+                name: n1,
+                data: Some(a),
+            },
+            Value::Variant {
+                name: n2,
+                data: Some(b),
+            },
+        ) if n1 == n2 => diff_into(a, b, &format!("{}.{}", path, n1), lines),
+        _ => lines.push(format!(
+            "{}: {} != {}",
+            at(path),
+            show_value(left),
+            show_value(right)
+        )),
+    }
+}
+
+/// Format a value for an assertion message, quoting text so that `"1"` and
+/// `1` can be told apart
+pub fn show_value(value: &Value) -> String {
+    match value {
+        Value::Text(s) => format!("{:?}", s),
+        other => format_value(other),
+    }
+}
+
+/// Estimated bytes for one value, before its contents
+const VALUE_BYTES: usize = 16;
+
+/// Estimated bytes held by a value and its contents. The estimate is the same
+/// on every platform, so memory costs can be compared between runs; closures
+/// count without the environment they capture.
+pub fn value_size(value: &Value) -> usize {
+    VALUE_BYTES
+        + match value {
+            Value::Text(s) => s.len(),
+            Value::Record(fields) => fields
+                .iter()
+                .map(|(name, value)| name.len() + value_size(value))
+                .sum(),
+            Value::Variant { name, data } => name.len() + data.as_deref().map_or(0, value_size),
+            Value::Some(inner) | Value::Ok(inner) | Value::Err(inner) => value_size(inner),
+            Value::List(items) | Value::Tuple(items) | Value::Set(items) => {
+                items.iter().map(value_size).sum()
+            }
+            Value::Map(entries) => entries
+                .iter()
+                .map(|(key, value)| value_size(key) + value_size(value))
+                .sum(),
+            Value::Future { args, .. } => args.iter().map(value_size).sum(),
+            Value::Ref(cell) => with_ref_contents(cell, value_size, 0),
+            Value::Unit
+            | Value::Int(_)
+            | Value::Float(_)
+            | Value::Decimal(_)
+            | Value::Duration(_)
+            | Value::Deadline(_)
+            | Value::Bytes(_)
+            | Value::Bool(_)
+            | Value::None
+            | Value::Closure { .. }
+            | Value::VariantConstructor { .. }
+            | Value::WebSocket(_)
+            | Value::Capability(_)
+            | Value::Namespace(_) => 0,
+        }
+}
+
 /// Compare two values for ordering (used by sort)
 pub fn compare_values(a: &Value, b: &Value) -> std::cmp::Ordering {
     match (a, b) {
         (Value::Int(a), Value::Int(b)) => a.cmp(b),
         (Value::Float(a), Value::Float(b)) => a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal),
+        (Value::Decimal(a), Value::Decimal(b)) => a.cmp(b),
+        (Value::Duration(a), Value::Duration(b)) | (Value::Deadline(a), Value::Deadline(b)) => {
+            a.cmp(b)
+        }
+        (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
         (Value::Text(a), Value::Text(b)) => a.cmp(b),
         (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
         _ => std::cmp::Ordering::Equal,
@@ -154,6 +364,14 @@ fn type_tag(v: &Value) -> u8 {
         Value::Closure { .. } => 15,
         Value::VariantConstructor { .. } => 16,
         Value::Future { .. } => 17,
+        Value::Decimal(_) => 18,
+        Value::Duration(_) => 19,
+        Value::Deadline(_) => 20,
+        Value::Bytes(_) => 21,
+        Value::WebSocket(_) => 22,
+        Value::Ref(_) => 23,
+        Value::Capability(_) => 24,
+        Value::Namespace(_) => 25,
     }
 }
 
@@ -170,13 +388,20 @@ pub fn compare_values_total(a: &Value, b: &Value) -> std::cmp::Ordering {
         (Value::Unit, Value::Unit) => Ordering::Equal,
         (Value::Int(x), Value::Int(y)) => x.cmp(y),
         (Value::Float(x), Value::Float(y)) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
+        (Value::Decimal(x), Value::Decimal(y)) => x.cmp(y),
+        (Value::Duration(x), Value::Duration(y)) | (Value::Deadline(x), Value::Deadline(y)) => {
+            x.cmp(y)
+        }
+        (Value::Bytes(x), Value::Bytes(y)) => x.cmp(y),
         (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
         (Value::Text(x), Value::Text(y)) => x.cmp(y),
         (Value::None, Value::None) => Ordering::Equal,
-        (Value::Some(x), Value::Some(y)) | (Value::Ok(x), Value::Ok(y)) | (Value::Err(x), Value::Err(y)) => {
-            compare_values_total(x, y)
-        }
-        (Value::Tuple(xs), Value::Tuple(ys)) | (Value::List(xs), Value::List(ys)) | (Value::Set(xs), Value::Set(ys)) => {
+        (Value::Some(x), Value::Some(y))
+        | (Value::Ok(x), Value::Ok(y))
+        | (Value::Err(x), Value::Err(y)) => compare_values_total(x, y),
+        (Value::Tuple(xs), Value::Tuple(ys))
+        | (Value::List(xs), Value::List(ys))
+        | (Value::Set(xs), Value::Set(ys)) => {
             for (x, y) in xs.iter().zip(ys.iter()) {
                 let c = compare_values_total(x, y);
                 if c != Ordering::Equal {
@@ -276,18 +501,67 @@ pub fn sorted_set_from(mut elements: Vec<Value>) -> Vec<Value> {
     elements
 }
 
+/// Render a float the same way on every platform: the shortest digits that
+/// parse back to the same value, always with a decimal point or exponent,
+/// switching to scientific notation below 1e-6 and from 1e21 up
+pub fn format_float(f: f64) -> String {
+    if f.is_nan() {
+        return "NaN".to_string();
+    }
+    if f.is_infinite() {
+        return if f > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    let scientific = format!("{:e}", f);
+    let exponent: i32 = scientific
+        .rsplit_once('e')
+        .and_then(|(_, exp)| exp.parse().ok())
+        .unwrap_or(0);
+    if f != 0.0 && !(-6..21).contains(&exponent) {
+        return scientific;
+    }
+    let text = f.to_string();
+    if text.contains('.') {
+        text
+    } else {
+        format!("{}.0", text)
+    }
+}
+
+/// Format `f` with exactly `digits` digits after the decimal point
+pub fn format_fixed(f: f64, digits: usize) -> String {
+    if !f.is_finite() {
+        return format_float(f);
+    }
+    format!("{:.*}", digits, f)
+}
+
+/// Format `f` with `digits` significant digits, in scientific notation when
+/// the exponent is below -6 or not less than `digits`
+pub fn format_precision(f: f64, digits: usize) -> String {
+    if !f.is_finite() {
+        return format_float(f);
+    }
+    let scientific = format!("{:.*e}", digits.saturating_sub(1), f);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i64 = exponent.parse().unwrap_or(0);
+    if exponent < -6 || exponent >= digits as i64 {
+        format!("{}e{}", mantissa, exponent)
+    } else {
+        let decimals = (digits as i64 - 1 - exponent).max(0) as usize;
+        format!("{:.*}", decimals, f)
+    }
+}
+
 /// Format a value for display
 pub fn format_value(value: &Value) -> String {
     match value {
         Value::Unit => "()".to_string(),
         Value::Int(n) => n.to_string(),
-        Value::Float(f) => {
-            if f.fract() == 0.0 {
-                format!("{:.1}", f)
-            } else {
-                f.to_string()
-            }
-        }
+        Value::Float(f) => format_float(*f),
+        Value::Decimal(d) => super::decimal::format_decimal(d),
+        Value::Duration(millis) => super::duration::format_duration(*millis),
+        Value::Deadline(at) => format!("Deadline({})", at),
+        Value::Bytes(bytes) => format!("Bytes({})", super::encoding::hex_encode(bytes)),
         Value::Bool(b) => b.to_string(),
         Value::Text(s) => s.clone(),
         Value::Record(fields) => {
@@ -307,6 +581,13 @@ pub fn format_value(value: &Value) -> String {
         Value::Closure { .. } => "<closure>".to_string(),
         Value::VariantConstructor { name, .. } => format!("<constructor:{}>", name),
         Value::Future { .. } => "<future>".to_string(),
+        Value::WebSocket(_) => "<websocket>".to_string(),
+        Value::Capability(cap) => format!("<{} capability>", cap.effect),
+        Value::Namespace(name) => format!("<{}>", name),
+        Value::Ref(cell) => format!(
+            "Ref({})",
+            with_ref_contents(cell, format_value, "...".to_string())
+        ),
         Value::List(items) => {
             let item_strs: Vec<String> = items.iter().map(format_value).collect();
             format!("[{}]", item_strs.join(", "))
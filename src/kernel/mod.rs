@@ -0,0 +1,864 @@
+//! Jupyter kernel (`astra kernel`)
+//!
+//! Speaks version 5.3 of the Jupyter messaging protocol so notebooks and
+//! `jupyter console` can drive a persistent interpreter. A cell holding
+//! definitions (functions, types, imports, ...) loads them; any other cell
+//! runs as statements in the interpreter's global scope, so `let` bindings
+//! carry over to later cells. A cell's final expression is shown with rich
+//! output, and parse and runtime errors are rendered as diagnostics framed
+//! in the cell's source.
+//!
+//! Requests are served one at a time on the thread that owns the
+//! interpreter; each socket accepts its connections on a thread of its own.
+
+mod zmtp;
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+use sha2::Sha256;
+
+use crate::diagnostics::{Diagnostic, DiagnosticBag, RenderOptions};
+use crate::interpreter::{
+    format_value, json_stringify_value, ConsoleCapability, Interpreter, RuntimeError, Value,
+};
+use crate::parser::ast::{Block, Item};
+use crate::parser::parse_source;
+use zmtp::SocketType;
+
+/// Version of the messaging protocol spoken
+const PROTOCOL_VERSION: &str = "5.3";
+
+/// Frame separating routing identities from the signed parts of a message
+const DELIMITER: &[u8] = b"<IDS|MSG>";
+
+/// Ports and signing key from the connection file Jupyter starts a kernel with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionInfo {
+    pub ip: String,
+    pub transport: String,
+    pub shell_port: u16,
+    pub iopub_port: u16,
+    pub stdin_port: u16,
+    pub control_port: u16,
+    pub hb_port: u16,
+    /// HMAC key; messages are unsigned when empty
+    #[serde(default)]
+    pub key: String,
+    #[serde(default)]
+    pub signature_scheme: String,
+}
+
+/// Serve the kernel described by a connection file until a client shuts it
+/// down. `prepare` creates the interpreter, given the console that sends
+/// program output to the notebook.
+pub fn run(
+    connection_file: &Path,
+    prepare: impl FnOnce(Box<dyn ConsoleCapability>) -> Interpreter,
+) -> io::Result<()> {
+    let text = std::fs::read_to_string(connection_file)?;
+    let info: ConnectionInfo =
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Kernel::bind(info)?.serve(prepare)
+}
+
+/// Write the kernel spec that lets Jupyter start `astra kernel`, returning
+/// the directory it was written to
+pub fn install_kernelspec() -> io::Result<PathBuf> {
+    let dir = jupyter_data_dir()
+        .ok_or_else(|| io::Error::other("could not find the Jupyter data directory"))?
+        .join("kernels")
+        .join("astra");
+    std::fs::create_dir_all(&dir)?;
+    let executable = std::env::current_exe()?;
+    let spec = json!({
+        "argv": [executable, "kernel", "{connection_file}"],
+        "display_name": "Astra",
+        "language": "astra",
+    });
+    std::fs::write(
+        dir.join("kernel.json"),
+        serde_json::to_string_pretty(&spec).unwrap_or_default(),
+    )?;
+    Ok(dir)
+}
+
+/// Where Jupyter looks for user kernel specs
+fn jupyter_data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("JUPYTER_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if cfg!(windows) {
+        return std::env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("jupyter"));
+    }
+    let home = PathBuf::from(std::env::var_os("HOME")?);
+    if cfg!(target_os = "macos") {
+        return Some(home.join("Library").join("Jupyter"));
+    }
+    let data = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".local").join("share"));
+    Some(data.join("jupyter"))
+}
+
+/// A kernel with its sockets bound
+pub struct Kernel {
+    info: ConnectionInfo,
+    shell: TcpListener,
+    control: TcpListener,
+    stdin: TcpListener,
+    iopub: TcpListener,
+    heartbeat: TcpListener,
+}
+
+impl Kernel {
+    /// Bind the sockets of a connection; port 0 picks a free port
+    pub fn bind(mut info: ConnectionInfo) -> io::Result<Self> {
+        if info.transport != "tcp" {
+            return Err(io::Error::other(format!(
+                "unsupported transport '{}'",
+                info.transport
+            )));
+        }
+        if !info.key.is_empty() && info.signature_scheme != "hmac-sha256" {
+            return Err(io::Error::other(format!(
+                "unsupported signature scheme '{}'",
+                info.signature_scheme
+            )));
+        }
+        let bind = |port: &mut u16| -> io::Result<TcpListener> {
+            let listener = TcpListener::bind((info.ip.as_str(), *port))?;
+            *port = listener.local_addr()?.port();
+            Ok(listener)
+        };
+        let shell = bind(&mut info.shell_port)?;
+        let control = bind(&mut info.control_port)?;
+        let stdin = bind(&mut info.stdin_port)?;
+        let iopub = bind(&mut info.iopub_port)?;
+        let heartbeat = bind(&mut info.hb_port)?;
+        Ok(Self {
+            info,
+            shell,
+            control,
+            stdin,
+            iopub,
+            heartbeat,
+        })
+    }
+
+    /// The connection, with the ports actually bound
+    pub fn connection(&self) -> &ConnectionInfo {
+        &self.info
+    }
+
+    /// Serve requests until a client shuts the kernel down
+    pub fn serve(
+        self,
+        prepare: impl FnOnce(Box<dyn ConsoleCapability>) -> Interpreter,
+    ) -> io::Result<()> {
+        let (sender, requests) = mpsc::channel();
+        accept_requests(self.shell, sender.clone());
+        accept_requests(self.control, sender.clone());
+        accept_requests(self.stdin, sender);
+        let iopub = IoPub::accept(self.iopub);
+        accept_heartbeats(self.heartbeat);
+
+        let output = Rc::new(RefCell::new(String::new()));
+        let interpreter = prepare(Box::new(CellConsole {
+            output: output.clone(),
+        }));
+        let mut server = Server {
+            session: Session::new(interpreter, output),
+            messages: Messages::new(&self.info.key),
+            iopub,
+            execution_count: 0,
+        };
+        while let Ok(request) = requests.recv() {
+            let Some(message) = server.messages.parse(&request.frames) else {
+                continue;
+            };
+            if server.handle(&message, &request.peer)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A message received on a shell, control, or stdin connection
+struct Request {
+    frames: Vec<Vec<u8>>,
+    peer: Peer,
+}
+
+/// The connection a request arrived on, to reply to
+type Peer = Arc<Mutex<TcpStream>>;
+
+/// Accept ROUTER connections, forwarding their messages to the kernel
+fn accept_requests(listener: TcpListener, requests: mpsc::Sender<Request>) {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let requests = requests.clone();
+            thread::spawn(move || -> io::Result<()> {
+                zmtp::handshake(&mut stream, SocketType::Router)?;
+                let peer = Arc::new(Mutex::new(stream.try_clone()?));
+                while let Some(frames) = zmtp::read_message(&mut stream)? {
+                    let peer = peer.clone();
+                    if requests.send(Request { frames, peer }).is_err() {
+                        break;
+                    }
+                }
+                Ok(())
+            });
+        }
+    });
+}
+
+/// Accept heartbeat connections and echo every message back
+fn accept_heartbeats(listener: TcpListener) {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            thread::spawn(move || -> io::Result<()> {
+                zmtp::handshake(&mut stream, SocketType::Rep)?;
+                while let Some(frames) = zmtp::read_message(&mut stream)? {
+                    zmtp::write_message(&mut stream, &frames)?;
+                }
+                Ok(())
+            });
+        }
+    });
+}
+
+/// Subscribers to the iopub channel; every message goes to all of them
+#[derive(Clone, Default)]
+struct IoPub {
+    subscribers: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl IoPub {
+    fn accept(listener: TcpListener) -> Self {
+        let iopub = Self::default();
+        let subscribers = iopub.subscribers.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let subscribers = subscribers.clone();
+                thread::spawn(move || -> io::Result<()> {
+                    zmtp::handshake(&mut stream, SocketType::Pub)?;
+                    subscribers.lock().unwrap().push(stream.try_clone()?);
+                    // Subscriptions aren't filtered on: drain them until the
+                    // subscriber disconnects
+                    while zmtp::read_message(&mut stream)?.is_some() {}
+                    Ok(())
+                });
+            }
+        });
+        iopub
+    }
+
+    fn publish(&self, frames: &[Vec<u8>]) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain_mut(|stream| zmtp::write_message(stream, frames).is_ok());
+    }
+}
+
+/// A Jupyter message
+#[derive(Debug, Clone)]
+struct Message {
+    /// Routing identities before the delimiter
+    identities: Vec<Vec<u8>>,
+    header: Json,
+    content: Json,
+}
+
+impl Message {
+    fn msg_type(&self) -> &str {
+        self.header["msg_type"].as_str().unwrap_or("")
+    }
+}
+
+/// Signs, verifies, and numbers the messages of one kernel session
+struct Messages {
+    key: Vec<u8>,
+    session: String,
+    sent: u64,
+}
+
+impl Messages {
+    fn new(key: &str) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        Self {
+            key: key.as_bytes().to_vec(),
+            session: format!("{:08x}-{:016x}", std::process::id(), nanos as u64),
+            sent: 0,
+        }
+    }
+
+    /// HMAC-SHA256 of the signed parts as hex, or nothing without a key
+    fn sign(&self, parts: &[&[u8]]) -> String {
+        if self.key.is_empty() {
+            return String::new();
+        }
+        let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(&self.key) else {
+            return String::new();
+        };
+        for part in parts {
+            mac.update(part);
+        }
+        hex(&mac.finalize().into_bytes())
+    }
+
+    /// Whether `signature` is the hex HMAC-SHA256 of the signed parts,
+    /// compared in constant time; without a key it has to be empty
+    fn verify(&self, parts: &[&[u8]], signature: &[u8]) -> bool {
+        if self.key.is_empty() {
+            return signature.is_empty();
+        }
+        let (Some(signature), Ok(mut mac)) =
+            (unhex(signature), Hmac::<Sha256>::new_from_slice(&self.key))
+        else {
+            return false;
+        };
+        for part in parts {
+            mac.update(part);
+        }
+        mac.verify_slice(&signature).is_ok()
+    }
+
+    /// Decode a message, dropping it if its signature doesn't match
+    fn parse(&self, frames: &[Vec<u8>]) -> Option<Message> {
+        let delimiter = frames.iter().position(|frame| frame == DELIMITER)?;
+        let [signature, header, parent, metadata, content, ..] = &frames[delimiter + 1..] else {
+            return None;
+        };
+        if !self.verify(&[header, parent, metadata, content], signature) {
+            return None;
+        }
+        Some(Message {
+            identities: frames[..delimiter].to_vec(),
+            header: serde_json::from_slice(header).ok()?,
+            content: serde_json::from_slice(content).ok()?,
+        })
+    }
+
+    /// Encode a message of `msg_type` in response to `parent`
+    fn encode(
+        &mut self,
+        identities: Vec<Vec<u8>>,
+        msg_type: &str,
+        parent: &Message,
+        content: Json,
+    ) -> Vec<Vec<u8>> {
+        self.sent += 1;
+        let header = json!({
+            "msg_id": format!("{}-{}", self.session, self.sent),
+            "session": self.session,
+            "username": "kernel",
+            "date": timestamp(),
+            "msg_type": msg_type,
+            "version": PROTOCOL_VERSION,
+        });
+        let parts = [
+            header.to_string().into_bytes(),
+            parent.header.to_string().into_bytes(),
+            b"{}".to_vec(),
+            content.to_string().into_bytes(),
+        ];
+        let signature = self.sign(&[&parts[0], &parts[1], &parts[2], &parts[3]]);
+        let mut frames = identities;
+        frames.push(DELIMITER.to_vec());
+        frames.push(signature.into_bytes());
+        frames.extend(parts);
+        frames
+    }
+}
+
+/// Handles requests against the session
+struct Server {
+    session: Session,
+    messages: Messages,
+    iopub: IoPub,
+    execution_count: u64,
+}
+
+impl Server {
+    /// Handle a request, returning whether the kernel should shut down
+    fn handle(&mut self, request: &Message, peer: &Peer) -> io::Result<bool> {
+        let msg_type = request.msg_type().to_string();
+        let Some(kind) = msg_type.strip_suffix("_request") else {
+            // Replies on stdin are never asked for
+            return Ok(false);
+        };
+        self.publish(request, "status", json!({ "execution_state": "busy" }));
+        let mut shutdown = false;
+        let reply = match kind {
+            "kernel_info" => Some(kernel_info()),
+            "execute" => Some(self.execute(request)),
+            "is_complete" => {
+                let code = request.content["code"].as_str().unwrap_or("");
+                Some(json!({ "status": completeness(code) }))
+            }
+            "complete" => {
+                let cursor = request.content["cursor_pos"].as_u64().unwrap_or(0);
+                Some(json!({
+                    "status": "ok",
+                    "matches": [],
+                    "cursor_start": cursor,
+                    "cursor_end": cursor,
+                    "metadata": {},
+                }))
+            }
+            "inspect" => {
+                Some(json!({ "status": "ok", "found": false, "data": {}, "metadata": {} }))
+            }
+            "history" => Some(json!({ "status": "ok", "history": [] })),
+            "comm_info" => Some(json!({ "status": "ok", "comms": {} })),
+            "interrupt" => Some(json!({ "status": "ok" })),
+            "shutdown" => {
+                shutdown = true;
+                let restart = request.content["restart"].as_bool().unwrap_or(false);
+                Some(json!({ "status": "ok", "restart": restart }))
+            }
+            _ => None,
+        };
+        if let Some(content) = reply {
+            let frames = self.messages.encode(
+                request.identities.clone(),
+                &format!("{}_reply", kind),
+                request,
+                content,
+            );
+            zmtp::write_message(&mut *peer.lock().unwrap(), &frames)?;
+        }
+        self.publish(request, "status", json!({ "execution_state": "idle" }));
+        Ok(shutdown)
+    }
+
+    /// Run a cell, publishing its output, and return the reply content
+    fn execute(&mut self, request: &Message) -> Json {
+        let code = request.content["code"].as_str().unwrap_or("");
+        let silent = request.content["silent"].as_bool().unwrap_or(false);
+        let store_history = request.content["store_history"]
+            .as_bool()
+            .unwrap_or(!silent);
+        if store_history {
+            self.execution_count += 1;
+        }
+        let count = self.execution_count;
+        if !silent {
+            self.publish(
+                request,
+                "execute_input",
+                json!({ "code": code, "execution_count": count }),
+            );
+        }
+
+        let result = self.session.execute(code);
+        let output = self.session.take_output();
+        if !silent && !output.is_empty() {
+            self.publish(
+                request,
+                "stream",
+                json!({ "name": "stdout", "text": output }),
+            );
+        }
+        match result {
+            Ok(value) => {
+                if let (Some(value), false) = (value, silent) {
+                    self.publish(
+                        request,
+                        "execute_result",
+                        json!({
+                            "execution_count": count,
+                            "data": display_data(&value),
+                            "metadata": {},
+                        }),
+                    );
+                }
+                json!({
+                    "status": "ok",
+                    "execution_count": count,
+                    "user_expressions": {},
+                    "payload": [],
+                })
+            }
+            Err(error) => {
+                let content = json!({
+                    "ename": error.ename,
+                    "evalue": error.evalue,
+                    "traceback": error.traceback,
+                });
+                if !silent {
+                    self.publish(request, "error", content.clone());
+                }
+                let mut reply = content;
+                reply["status"] = json!("error");
+                reply["execution_count"] = json!(count);
+                reply
+            }
+        }
+    }
+
+    fn publish(&mut self, parent: &Message, msg_type: &str, content: Json) {
+        let topic = format!("kernel.{}.{}", self.messages.session, msg_type);
+        let frames = self
+            .messages
+            .encode(vec![topic.into_bytes()], msg_type, parent, content);
+        self.iopub.publish(&frames);
+    }
+}
+
+fn kernel_info() -> Json {
+    json!({
+        "status": "ok",
+        "protocol_version": PROTOCOL_VERSION,
+        "implementation": "astra",
+        "implementation_version": env!("CARGO_PKG_VERSION"),
+        "language_info": {
+            "name": "astra",
+            "version": env!("CARGO_PKG_VERSION"),
+            "mimetype": "text/x-astra",
+            "file_extension": ".astra",
+        },
+        "banner": format!("Astra {}", env!("CARGO_PKG_VERSION")),
+        "help_links": [],
+    })
+}
+
+/// Whether a cell is ready to run: `incomplete` while brackets are open
+fn completeness(code: &str) -> &'static str {
+    let mut depth = 0i64;
+    let mut in_text = false;
+    let mut chars = code.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_text => {
+                chars.next();
+            }
+            '"' => in_text = !in_text,
+            '{' | '(' | '[' if !in_text => depth += 1,
+            '}' | ')' | ']' if !in_text => depth -= 1,
+            _ => {}
+        }
+    }
+    match depth {
+        0 if !in_text => "complete",
+        d if d < 0 => "invalid",
+        _ => "incomplete",
+    }
+}
+
+/// A cell that failed to parse or run
+#[derive(Debug)]
+struct CellError {
+    ename: String,
+    evalue: String,
+    traceback: Vec<String>,
+}
+
+/// The persistent interpreter cells run in
+struct Session {
+    interpreter: Interpreter,
+    output: Rc<RefCell<String>>,
+    /// Source of every cell run so far, to frame errors raised in its code
+    cells: HashMap<PathBuf, String>,
+}
+
+impl Session {
+    fn new(interpreter: Interpreter, output: Rc<RefCell<String>>) -> Self {
+        Self {
+            interpreter,
+            output,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Console output since the last call
+    fn take_output(&mut self) -> String {
+        self.output.take()
+    }
+
+    /// Run a cell, returning the value of its final expression
+    fn execute(&mut self, code: &str) -> Result<Option<Value>, CellError> {
+        let path = PathBuf::from(format!("In[{}]", self.cells.len() + 1));
+        self.cells.insert(path.clone(), code.to_string());
+
+        // Both wrappers end their first line before the cell, so lines in
+        // diagnostics are one past the cell's own
+        let definitions = parse_source(&format!("module cell\n{}", code), &path);
+        let statements = match definitions {
+            Ok(module) => {
+                return match self.interpreter.load_module(&module) {
+                    Ok(()) => Ok(None),
+                    Err(error) => Err(self.runtime_error(error)),
+                };
+            }
+            Err(definition_errors) => {
+                let wrapped = format!("module cell fn __cell__() {{\n{}\n}}", code);
+                match parse_source(&wrapped, &path) {
+                    Ok(module) => module,
+                    // Report whichever reading of the cell got further
+                    Err(statement_errors) => {
+                        let reach = |bag: &DiagnosticBag| {
+                            bag.diagnostics().first().map(|d| d.span.start_line)
+                        };
+                        let errors = if reach(&definition_errors) > reach(&statement_errors) {
+                            definition_errors
+                        } else {
+                            statement_errors
+                        };
+                        return Err(self.parse_error(errors));
+                    }
+                }
+            }
+        };
+        let Some(Item::FnDef(cell)) = statements.items.first() else {
+            return Ok(None);
+        };
+        match run_block(&mut self.interpreter, &cell.body) {
+            Ok(Some(Value::Unit)) => Ok(None),
+            Ok(value) => Ok(value),
+            Err(error) if error.is_return => Ok(error.early_return.map(|value| *value)),
+            Err(error) => Err(self.runtime_error(error)),
+        }
+    }
+
+    fn parse_error(&self, errors: DiagnosticBag) -> CellError {
+        let diagnostics = errors.diagnostics();
+        let mut traceback = Vec::new();
+        for diagnostic in diagnostics {
+            traceback.extend(self.render(diagnostic.clone()));
+        }
+        let first = diagnostics.first();
+        CellError {
+            ename: first.map(|d| d.code.clone()).unwrap_or_default(),
+            evalue: first.map(|d| d.message.clone()).unwrap_or_default(),
+            traceback,
+        }
+    }
+
+    fn runtime_error(&self, error: RuntimeError) -> CellError {
+        let mut diagnostic = Diagnostic::error(error.code).message(error.message.clone());
+        if let Some(span) = error.span {
+            diagnostic = diagnostic.span(span);
+        }
+        CellError {
+            ename: error.code.to_string(),
+            evalue: error.message,
+            traceback: self.render(diagnostic.build()),
+        }
+    }
+
+    /// Render a diagnostic framed in the cell it points into
+    fn render(&self, mut diagnostic: Diagnostic) -> Vec<String> {
+        let source = match self.cells.get(&diagnostic.span.file) {
+            Some(source) => {
+                let span = &mut diagnostic.span;
+                span.start_line = span.start_line.saturating_sub(1);
+                span.end_line = span.end_line.saturating_sub(1);
+                for label in &mut diagnostic.labels {
+                    if label.span.file == span.file {
+                        label.span.start_line = label.span.start_line.saturating_sub(1);
+                        label.span.end_line = label.span.end_line.saturating_sub(1);
+                    }
+                }
+                source.clone()
+            }
+            None => std::fs::read_to_string(&diagnostic.span.file).unwrap_or_default(),
+        };
+        diagnostic
+            .render(
+                &source,
+                RenderOptions {
+                    color: true,
+                    ..Default::default()
+                },
+            )
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// Run a block's statements in the current scope and evaluate its final
+/// expression
+fn run_block(interpreter: &mut Interpreter, block: &Block) -> Result<Option<Value>, RuntimeError> {
+    for stmt in &block.stmts {
+        interpreter.eval_stmt(stmt)?;
+    }
+    block
+        .expr
+        .as_deref()
+        .map(|expr| interpreter.eval_expr(expr))
+        .transpose()
+}
+
+/// MIME bundle for a value: plain text always, JSON for structured values
+/// and JSON text, and an HTML table for records and lists of records
+fn display_data(value: &Value) -> Json {
+    let mut data = serde_json::Map::new();
+    data.insert("text/plain".into(), json!(format_value(value)));
+    let structured = match value {
+        Value::Record(_) | Value::List(_) | Value::Tuple(_) | Value::Map(_) | Value::Set(_) => {
+            serde_json::from_str(&json_stringify_value(value)).ok()
+        }
+        Value::Text(text) => serde_json::from_str::<Json>(text)
+            .ok()
+            .filter(|json| json.is_object() || json.is_array()),
+        _ => None,
+    };
+    if let Some(json) = structured {
+        data.insert("application/json".into(), json);
+    }
+    if let Some(html) = html_table(value) {
+        data.insert("text/html".into(), json!(html));
+    }
+    Json::Object(data)
+}
+
+fn html_table(value: &Value) -> Option<String> {
+    let cell = |value: Option<&Value>| {
+        format!(
+            "<td>{}</td>",
+            value
+                .map(|v| escape_html(&format_value(v)))
+                .unwrap_or_default()
+        )
+    };
+    match value {
+        Value::Record(fields) => {
+            let mut names: Vec<&String> = fields.keys().collect();
+            names.sort();
+            let rows: String = names
+                .into_iter()
+                .map(|name| {
+                    format!(
+                        "<tr><th>{}</th>{}</tr>",
+                        escape_html(name),
+                        cell(fields.get(name))
+                    )
+                })
+                .collect();
+            Some(format!("<table>{}</table>", rows))
+        }
+        Value::List(items) if !items.is_empty() => {
+            let mut columns = BTreeSet::new();
+            for item in items {
+                let Value::Record(fields) = item else {
+                    return None;
+                };
+                columns.extend(fields.keys());
+            }
+            let header: String = columns
+                .iter()
+                .map(|name| format!("<th>{}</th>", escape_html(name)))
+                .collect();
+            let rows: String = items
+                .iter()
+                .filter_map(|item| match item {
+                    Value::Record(fields) => Some(format!(
+                        "<tr>{}</tr>",
+                        columns
+                            .iter()
+                            .map(|name| cell(fields.get(*name)))
+                            .collect::<String>()
+                    )),
+                    _ => None,
+                })
+                .collect();
+            Some(format!("<table><tr>{}</tr>{}</table>", header, rows))
+        }
+        _ => None,
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The bytes hex text spells, or `None` if it isn't whole hex bytes
+fn unhex(text: &[u8]) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    let digit = |b: u8| (b as char).to_digit(16);
+    text.chunks(2)
+        .map(|pair| Some((digit(pair[0])? * 16 + digit(pair[1])?) as u8))
+        .collect()
+}
+
+/// The current UTC time in ISO 8601, as message headers carry it
+fn timestamp() -> String {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = elapsed.as_secs() as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        elapsed.subsec_micros()
+    )
+}
+
+/// Console that collects a cell's output for the notebook
+struct CellConsole {
+    output: Rc<RefCell<String>>,
+}
+
+impl ConsoleCapability for CellConsole {
+    fn print(&self, text: &str) {
+        self.output.borrow_mut().push_str(text);
+    }
+
+    fn println(&self, text: &str) {
+        let mut output = self.output.borrow_mut();
+        output.push_str(text);
+        output.push('\n');
+    }
+
+    fn read_line(&self) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests;
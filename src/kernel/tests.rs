@@ -0,0 +1,212 @@
+use super::*;
+use indexmap::IndexMap;
+use std::time::Duration;
+
+fn session() -> Session {
+    let output = Rc::new(RefCell::new(String::new()));
+    let interpreter = Interpreter::with_capabilities(crate::interpreter::Capabilities {
+        console: Some(Box::new(CellConsole {
+            output: output.clone(),
+        })),
+        ..Default::default()
+    });
+    Session::new(interpreter, output)
+}
+
+#[test]
+fn test_cells_share_a_persistent_interpreter() {
+    let mut session = session();
+    assert!(matches!(
+        session.execute("fn double(n: Int) -> Int {\n  n * 2\n}"),
+        Ok(None)
+    ));
+    assert!(matches!(session.execute("let x = double(21)"), Ok(None)));
+    assert!(matches!(session.execute("x + 1"), Ok(Some(Value::Int(43)))));
+
+    assert!(matches!(
+        session.execute("Console.println(to_text(x))"),
+        Ok(None)
+    ));
+    assert_eq!(session.take_output(), "42\n");
+    assert!(matches!(session.execute(""), Ok(None)));
+}
+
+#[test]
+fn test_cell_errors_are_framed_in_the_cell() {
+    let mut session = session();
+    let error = session.execute("let a = 1\nlet b = (a +) * 2").unwrap_err();
+    let traceback = error.traceback.join("\n");
+    assert!(traceback.contains("In[1]:2:"), "{}", traceback);
+    assert!(traceback.contains("let b = (a +) * 2"), "{}", traceback);
+    assert!(!traceback.contains("__cell__"), "{}", traceback);
+
+    let error = session.execute("let n = 0\n10 / n").unwrap_err();
+    assert!(error.ename.starts_with("E4"), "{:?}", error);
+    assert!(!error.evalue.is_empty());
+}
+
+#[test]
+fn test_display_data_for_records_and_lists() {
+    let record = |name: &str, age: i64| {
+        Value::Record(IndexMap::from([
+            ("name".to_string(), Value::Text(name.to_string())),
+            ("age".to_string(), Value::Int(age)),
+        ]))
+    };
+    let data = display_data(&Value::List(vec![record("Ada", 36), record("<b>", 1)]));
+    assert_eq!(
+        data["application/json"],
+        json!([{"age": 36, "name": "Ada"}, {"age": 1, "name": "<b>"}])
+    );
+    let html = data["text/html"].as_str().unwrap();
+    assert!(html.starts_with("<table><tr><th>age</th><th>name</th></tr>"));
+    assert!(html.contains("&lt;b&gt;"));
+
+    let data = display_data(&Value::Text(r#"{"ok": true}"#.to_string()));
+    assert_eq!(data["application/json"], json!({"ok": true}));
+
+    let data = display_data(&Value::Int(3));
+    assert_eq!(data, json!({"text/plain": "3"}));
+}
+
+#[test]
+fn test_completeness() {
+    assert_eq!(completeness("let x = 1"), "complete");
+    assert_eq!(completeness("fn f() {\n  1"), "incomplete");
+    assert_eq!(completeness("\"}\" + \"{"), "incomplete");
+    assert_eq!(completeness("1 }"), "invalid");
+}
+
+#[test]
+fn test_signatures_are_verified() {
+    let mut client = Messages::new("secret");
+    let parent = Message {
+        identities: Vec::new(),
+        header: json!({}),
+        content: Json::Null,
+    };
+    let mut frames = client.encode(Vec::new(), "kernel_info_request", &parent, json!({}));
+    let signature = frames.iter().position(|frame| frame == DELIMITER).unwrap() + 1;
+    assert!(Messages::new("secret").parse(&frames).is_some());
+    assert!(Messages::new("wrong").parse(&frames).is_none());
+
+    // Tampered, truncated, and non-hex signatures are all dropped
+    frames[signature][0] = if frames[signature][0] == b'0' {
+        b'1'
+    } else {
+        b'0'
+    };
+    assert!(Messages::new("secret").parse(&frames).is_none());
+    frames[signature].truncate(10);
+    assert!(Messages::new("secret").parse(&frames).is_none());
+    frames[signature] = b"zz".to_vec();
+    assert!(Messages::new("secret").parse(&frames).is_none());
+}
+
+#[test]
+fn test_oversized_frames_are_rejected() {
+    // A long frame announcing 2^62 bytes, before any handshake
+    let mut wire = vec![0x02];
+    wire.extend_from_slice(&(1u64 << 62).to_be_bytes());
+    let error = zmtp::read_message(&mut wire.as_slice()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+/// Connect to a kernel socket as a client
+fn connect(port: u16, socket: SocketType) -> TcpStream {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    zmtp::handshake(&mut stream, socket).unwrap();
+    stream
+}
+
+/// Read iopub messages until one of `msg_type`
+fn next_published(iopub: &mut TcpStream, client: &Messages, msg_type: &str) -> Message {
+    loop {
+        let frames = zmtp::read_message(iopub).unwrap().unwrap();
+        let message = client.parse(&frames).expect("iopub messages are signed");
+        if message.msg_type() == msg_type {
+            return message;
+        }
+    }
+}
+
+#[test]
+fn test_kernel_serves_execute_requests() {
+    let kernel = Kernel::bind(ConnectionInfo {
+        ip: "127.0.0.1".to_string(),
+        transport: "tcp".to_string(),
+        shell_port: 0,
+        iopub_port: 0,
+        stdin_port: 0,
+        control_port: 0,
+        hb_port: 0,
+        key: "secret".to_string(),
+        signature_scheme: "hmac-sha256".to_string(),
+    })
+    .unwrap();
+    let info = kernel.connection().clone();
+    let server = thread::spawn(move || kernel.serve(|_| Interpreter::new()));
+
+    let mut iopub = connect(info.iopub_port, SocketType::Sub);
+    zmtp::write_message(&mut iopub, &[vec![1]]).unwrap();
+    let mut heartbeat = connect(info.hb_port, SocketType::Req);
+    zmtp::write_message(&mut heartbeat, &[b"ping".to_vec()]).unwrap();
+    assert_eq!(
+        zmtp::read_message(&mut heartbeat).unwrap(),
+        Some(vec![b"ping".to_vec()])
+    );
+    // Give the kernel time to add the subscriber before it publishes
+    thread::sleep(Duration::from_millis(100));
+
+    let mut shell = connect(info.shell_port, SocketType::Dealer);
+    let mut client = Messages::new("secret");
+    let parent = Message {
+        identities: Vec::new(),
+        header: json!({}),
+        content: Json::Null,
+    };
+    let mut request = |shell: &mut TcpStream, msg_type: &str, content: Json| {
+        let frames = client.encode(Vec::new(), msg_type, &parent, content);
+        zmtp::write_message(shell, &frames).unwrap();
+        let frames = zmtp::read_message(shell).unwrap().unwrap();
+        Messages::new("secret")
+            .parse(&frames)
+            .expect("replies are signed")
+    };
+
+    let reply = request(&mut shell, "kernel_info_request", json!({}));
+    assert_eq!(reply.msg_type(), "kernel_info_reply");
+    assert_eq!(reply.content["language_info"]["name"], "astra");
+
+    let reply = request(
+        &mut shell,
+        "execute_request",
+        json!({ "code": "let xs = [1, 2]\nxs", "silent": false }),
+    );
+    assert_eq!(reply.content["status"], "ok");
+    assert_eq!(reply.content["execution_count"], 1);
+    let observer = Messages::new("secret");
+    let result = next_published(&mut iopub, &observer, "execute_result");
+    assert_eq!(result.content["data"]["application/json"], json!([1, 2]));
+
+    let reply = request(
+        &mut shell,
+        "execute_request",
+        json!({ "code": "undefined_name" }),
+    );
+    assert_eq!(reply.content["status"], "error");
+    let error = next_published(&mut iopub, &observer, "error");
+    assert_eq!(error.content["ename"], reply.content["ename"]);
+
+    // Messages signed with another key are dropped
+    let mut forged = Messages::new("wrong");
+    let frames = forged.encode(Vec::new(), "shutdown_request", &parent, json!({}));
+    zmtp::write_message(&mut shell, &frames).unwrap();
+
+    let reply = request(&mut shell, "shutdown_request", json!({ "restart": false }));
+    assert_eq!(reply.msg_type(), "shutdown_reply");
+    server.join().unwrap().unwrap();
+}
@@ -0,0 +1,155 @@
+//! Minimal ZMTP 3.0 for the kernel's sockets
+//!
+//! Jupyter clients reach kernels over ZeroMQ. A kernel only accepts TCP
+//! connections with the NULL security mechanism (messages are signed at the
+//! Jupyter layer), so this speaks the wire protocol directly: a greeting, a
+//! `READY` command naming the socket type, then multipart messages.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+
+/// Greeting signature; the last byte announces ZMTP 3
+const SIGNATURE: [u8; 10] = [0xff, 0, 0, 0, 0, 0, 0, 0, 1, 0x7f];
+
+/// More frames follow in this message
+const MORE: u8 = 0x01;
+/// The frame size takes 8 bytes
+const LONG: u8 = 0x02;
+/// The frame is a command rather than part of a message
+const COMMAND: u8 = 0x04;
+
+/// Largest frame accepted, so a peer can't make the kernel allocate an
+/// arbitrary size it announces before anything is authenticated
+const MAX_FRAME: u64 = 64 * 1024 * 1024;
+
+/// ZeroMQ socket types of the kernel, and of the clients tests connect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketType {
+    Router,
+    Pub,
+    Rep,
+    #[cfg(test)]
+    Dealer,
+    #[cfg(test)]
+    Sub,
+    #[cfg(test)]
+    Req,
+}
+
+impl SocketType {
+    fn name(self) -> &'static str {
+        match self {
+            SocketType::Router => "ROUTER",
+            SocketType::Pub => "PUB",
+            SocketType::Rep => "REP",
+            #[cfg(test)]
+            SocketType::Dealer => "DEALER",
+            #[cfg(test)]
+            SocketType::Sub => "SUB",
+            #[cfg(test)]
+            SocketType::Req => "REQ",
+        }
+    }
+}
+
+/// Exchange greetings and `READY` commands on a new connection as `socket`
+pub fn handshake(stream: &mut TcpStream, socket: SocketType) -> io::Result<()> {
+    let mut greeting = [0u8; 64];
+    greeting[..10].copy_from_slice(&SIGNATURE);
+    greeting[10] = 3;
+    greeting[12..16].copy_from_slice(b"NULL");
+    stream.write_all(&greeting)?;
+
+    let mut theirs = [0u8; 64];
+    stream.read_exact(&mut theirs)?;
+    if theirs[0] != 0xff || theirs[9] & 1 == 0 || theirs[10] < 3 {
+        return Err(invalid("peer does not speak ZMTP 3"));
+    }
+    if &theirs[12..17] != b"NULL\0" {
+        return Err(invalid("only the NULL security mechanism is supported"));
+    }
+
+    let mut ready = vec![5];
+    ready.extend_from_slice(b"READY");
+    let (name, value) = ("Socket-Type", socket.name());
+    ready.push(name.len() as u8);
+    ready.extend_from_slice(name.as_bytes());
+    ready.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    ready.extend_from_slice(value.as_bytes());
+    write_frame(stream, COMMAND, &ready)?;
+
+    match read_frame(stream)? {
+        Some((flags, body)) if flags & COMMAND != 0 && body.get(1..6) == Some(b"READY") => Ok(()),
+        Some(_) => Err(invalid("expected a READY command")),
+        None => Err(io::ErrorKind::UnexpectedEof.into()),
+    }
+}
+
+/// Read the next multipart message, skipping commands; `None` once the peer
+/// has disconnected
+pub fn read_message(stream: &mut impl Read) -> io::Result<Option<Vec<Vec<u8>>>> {
+    let mut frames = Vec::new();
+    loop {
+        let Some((flags, body)) = read_frame(stream)? else {
+            return Ok(None);
+        };
+        if flags & COMMAND != 0 {
+            continue;
+        }
+        frames.push(body);
+        if flags & MORE == 0 {
+            return Ok(Some(frames));
+        }
+    }
+}
+
+/// Write a multipart message
+pub fn write_message(stream: &mut impl Write, frames: &[Vec<u8>]) -> io::Result<()> {
+    let mut buffer = Vec::new();
+    for (i, frame) in frames.iter().enumerate() {
+        let more = if i + 1 < frames.len() { MORE } else { 0 };
+        write_frame(&mut buffer, more, frame)?;
+    }
+    stream.write_all(&buffer)?;
+    stream.flush()
+}
+
+fn read_frame(stream: &mut impl Read) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut flags = [0u8; 1];
+    match stream.read_exact(&mut flags) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let flags = flags[0];
+    let size = if flags & LONG != 0 {
+        let mut size = [0u8; 8];
+        stream.read_exact(&mut size)?;
+        let size = u64::from_be_bytes(size);
+        if size > MAX_FRAME {
+            return Err(invalid("frame exceeds the maximum size"));
+        }
+        size as usize
+    } else {
+        let mut size = [0u8; 1];
+        stream.read_exact(&mut size)?;
+        size[0] as usize
+    };
+    let mut body = vec![0u8; size];
+    stream.read_exact(&mut body)?;
+    Ok(Some((flags, body)))
+}
+
+fn write_frame(stream: &mut impl Write, flags: u8, body: &[u8]) -> io::Result<()> {
+    if body.len() > u8::MAX as usize {
+        stream.write_all(&[flags | LONG])?;
+        stream.write_all(&(body.len() as u64).to_be_bytes())?;
+    } else {
+        stream.write_all(&[flags, body.len() as u8])?;
+    }
+    stream.write_all(body)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -5,17 +5,39 @@
 
 #![allow(clippy::result_large_err)]
 
+pub mod baseline;
 pub mod cache;
+pub mod cfg;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod cli;
+pub mod dap;
+pub mod debugger;
 pub mod diagnostics;
 pub mod effects;
+pub mod embed;
+#[cfg(feature = "astra-ffi")]
+pub mod ffi;
 pub mod formatter;
+pub mod fuzz;
+pub mod graph;
 pub mod interpreter;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod kernel;
 pub mod lsp;
 pub mod manifest;
 pub mod parser;
+#[cfg(feature = "python")]
+mod python;
+pub mod rename;
+pub mod resolve;
+pub mod semantic_diff;
+pub mod semantic_tokens;
+pub mod symbols;
 pub mod testing;
+pub mod text_diff;
 pub mod typechecker;
+#[cfg(feature = "wasm")]
+mod wasm;
 
 /// Re-export commonly used types
 pub mod prelude {
@@ -3,8 +3,10 @@
 //! Provides IDE integration via the LSP protocol over stdio:
 //! - Diagnostics (errors/warnings from type checker)
 //! - Go-to-definition for functions and types
+//! - Find all references
 //! - Hover information (type info)
 //! - Document symbols
+//! - Semantic tokens
 
 use std::collections::HashMap;
 use std::io::{self, BufRead, Read as IoRead, Write as IoWrite};
@@ -16,6 +18,8 @@ use crate::parser::ast::*;
 use crate::parser::lexer::Lexer;
 use crate::parser::parser::Parser;
 use crate::parser::span::SourceFile;
+use crate::resolve::{find_definition, BindingKind, SymbolTable};
+use crate::semantic_tokens::{classify, SemanticKind};
 use crate::typechecker::TypeChecker;
 
 /// Run the LSP server on stdin/stdout
@@ -32,6 +36,8 @@ struct LspServer {
     modules: HashMap<String, Module>,
     /// Cached diagnostics per URI (for code actions)
     cached_diagnostics: HashMap<String, Vec<crate::diagnostics::Diagnostic>>,
+    /// Definitions of imported modules per URI, from the last type check
+    symbol_tables: HashMap<String, SymbolTable>,
     /// Whether the server has been initialized
     initialized: bool,
 }
@@ -42,6 +48,7 @@ impl LspServer {
             documents: HashMap::new(),
             modules: HashMap::new(),
             cached_diagnostics: HashMap::new(),
+            symbol_tables: HashMap::new(),
             initialized: false,
         }
     }
@@ -92,12 +99,20 @@ impl LspServer {
                                 },
                                 "hoverProvider": true,
                                 "definitionProvider": true,
+                                "referencesProvider": true,
                                 "documentSymbolProvider": true,
                                 "completionProvider": {
                                     "triggerCharacters": [".", ":"]
                                 },
                                 "codeActionProvider": {
                                     "codeActionKinds": ["quickfix"]
+                                },
+                                "semanticTokensProvider": {
+                                    "legend": {
+                                        "tokenTypes": SemanticKind::ALL.map(SemanticKind::name),
+                                        "tokenModifiers": ["declaration"]
+                                    },
+                                    "full": true
                                 }
                             },
                             "serverInfo": {
@@ -160,6 +175,7 @@ impl LspServer {
                 let uri = params["textDocument"]["uri"].as_str()?.to_string();
                 self.documents.remove(&uri);
                 self.modules.remove(&uri);
+                self.symbol_tables.remove(&uri);
                 self.cached_diagnostics.remove(&uri);
                 // Clear diagnostics
                 let notification = json!({
@@ -196,6 +212,17 @@ impl LspServer {
                 })
             }
 
+            "textDocument/references" => {
+                let result = self.handle_references(&params);
+                id.map(|id| {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "result": result
+                    })
+                })
+            }
+
             "textDocument/documentSymbol" => {
                 let result = self.handle_document_symbols(&params);
                 id.map(|id| {
@@ -229,6 +256,17 @@ impl LspServer {
                 })
             }
 
+            "textDocument/semanticTokens/full" => {
+                let result = self.handle_semantic_tokens(&params);
+                id.map(|id| {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "result": result
+                    })
+                })
+            }
+
             _ => {
                 // Unknown method - return error for requests, ignore notifications
                 id.map(|id| {
@@ -284,7 +322,9 @@ impl LspServer {
 
         // Run type checker with search paths for cross-file resolution
         let mut checker = TypeChecker::new();
-        // Add workspace root as search path
+        // Add the document's project root and the workspace root as search paths
+        let project_root = crate::cache::find_project_root(source_file.path());
+        checker.add_search_path(project_root);
         if let Ok(cwd) = std::env::current_dir() {
             checker.add_search_path(cwd);
         }
@@ -292,6 +332,8 @@ impl LspServer {
             Ok(()) => checker.diagnostics().clone(),
             Err(bag) => bag,
         };
+        self.symbol_tables
+            .insert(uri.to_string(), checker.symbol_table().clone());
 
         // Cache diagnostics for code actions
         self.cached_diagnostics
@@ -332,123 +374,127 @@ impl LspServer {
         // Find the item at the cursor position
         for item in &module.items {
             match item {
-                Item::FnDef(def) => {
-                    if span_contains(&def.span, line, col) {
-                        let params_str: Vec<String> = def
-                            .params
-                            .iter()
-                            .map(|p| format!("{}: {}", p.name, format_type_expr(&p.ty)))
-                            .collect();
-                        let ret_str = def
-                            .return_type
-                            .as_ref()
-                            .map(|t| format!(" -> {}", format_type_expr(t)))
-                            .unwrap_or_default();
-                        let effects_str = if def.effects.is_empty() {
-                            String::new()
-                        } else {
-                            format!(" effects({})", def.effects.join(", "))
-                        };
-
-                        let type_params_str = if def.type_params.is_empty() {
-                            String::new()
-                        } else {
-                            format!("[{}]", def.type_params.join(", "))
-                        };
-
-                        let hover_text = format!(
+                Item::FnDef(def) if span_contains(&def.span, line, col) => {
+                    let params_str: Vec<String> = def
+                        .params
+                        .iter()
+                        .map(|p| format!("{}: {}", p.name, format_type_expr(&p.ty)))
+                        .collect();
+                    let ret_str = def
+                        .return_type
+                        .as_ref()
+                        .map(|t| format!(" -> {}", format_type_expr(t)))
+                        .unwrap_or_default();
+                    let effects_str = if def.effects.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" effects({})", def.effects.join(", "))
+                    };
+
+                    let type_params_str = if def.type_params.is_empty() {
+                        String::new()
+                    } else {
+                        format!("[{}]", def.type_params.join(", "))
+                    };
+
+                    let hover_text = with_doc(
+                        format!(
                             "```astra\nfn {}{}({}){}{}\n```",
                             def.name,
                             type_params_str,
                             params_str.join(", "),
                             ret_str,
                             effects_str,
-                        );
-                        return json!({
-                            "contents": {
-                                "kind": "markdown",
-                                "value": hover_text
-                            }
-                        });
-                    }
+                        ),
+                        &def.doc,
+                    );
+                    return json!({
+                        "contents": {
+                            "kind": "markdown",
+                            "value": hover_text
+                        }
+                    });
                 }
-                Item::TypeDef(def) => {
-                    if span_contains(&def.span, line, col) {
-                        let hover_text = format!(
+                Item::TypeDef(def) if span_contains(&def.span, line, col) => {
+                    let hover_text = with_doc(
+                        format!(
                             "```astra\ntype {} = {}\n```",
                             def.name,
                             format_type_expr(&def.value)
-                        );
-                        return json!({
-                            "contents": {
-                                "kind": "markdown",
-                                "value": hover_text
-                            }
-                        });
-                    }
+                        ),
+                        &def.doc,
+                    );
+                    return json!({
+                        "contents": {
+                            "kind": "markdown",
+                            "value": hover_text
+                        }
+                    });
                 }
-                Item::EnumDef(def) => {
-                    if span_contains(&def.span, line, col) {
-                        let variants: Vec<String> = def
-                            .variants
-                            .iter()
-                            .map(|v| {
-                                if v.fields.is_empty() {
-                                    v.name.clone()
-                                } else {
-                                    let fields: Vec<String> = v
-                                        .fields
-                                        .iter()
-                                        .map(|f| format!("{}: {}", f.name, format_type_expr(&f.ty)))
-                                        .collect();
-                                    format!("{}({})", v.name, fields.join(", "))
-                                }
-                            })
-                            .collect();
-                        let hover_text = format!(
+                Item::EnumDef(def) if span_contains(&def.span, line, col) => {
+                    let variants: Vec<String> = def
+                        .variants
+                        .iter()
+                        .map(|v| {
+                            if v.fields.is_empty() {
+                                v.name.clone()
+                            } else {
+                                let fields: Vec<String> = v
+                                    .fields
+                                    .iter()
+                                    .map(|f| format!("{}: {}", f.name, format_type_expr(&f.ty)))
+                                    .collect();
+                                format!("{}({})", v.name, fields.join(", "))
+                            }
+                        })
+                        .collect();
+                    let hover_text = with_doc(
+                        format!(
                             "```astra\nenum {} {{\n  {}\n}}\n```",
                             def.name,
                             variants.join("\n  ")
-                        );
-                        return json!({
-                            "contents": {
-                                "kind": "markdown",
-                                "value": hover_text
-                            }
-                        });
-                    }
+                        ),
+                        &def.doc,
+                    );
+                    return json!({
+                        "contents": {
+                            "kind": "markdown",
+                            "value": hover_text
+                        }
+                    });
                 }
-                Item::TraitDef(def) => {
-                    if span_contains(&def.span, line, col) {
-                        let methods: Vec<String> = def
-                            .methods
-                            .iter()
-                            .map(|m| {
-                                let ps: Vec<String> = m
-                                    .params
-                                    .iter()
-                                    .map(|p| format!("{}: {}", p.name, format_type_expr(&p.ty)))
-                                    .collect();
-                                let ret = m
-                                    .return_type
-                                    .as_ref()
-                                    .map(|t| format!(" -> {}", format_type_expr(t)))
-                                    .unwrap_or_default();
-                                format!("fn {}({}){}", m.name, ps.join(", "), ret)
-                            })
-                            .collect();
-                        let hover_text = format!(
+                Item::TraitDef(def) if span_contains(&def.span, line, col) => {
+                    let methods: Vec<String> = def
+                        .methods
+                        .iter()
+                        .map(|m| {
+                            let ps: Vec<String> = m
+                                .params
+                                .iter()
+                                .map(|p| format!("{}: {}", p.name, format_type_expr(&p.ty)))
+                                .collect();
+                            let ret = m
+                                .return_type
+                                .as_ref()
+                                .map(|t| format!(" -> {}", format_type_expr(t)))
+                                .unwrap_or_default();
+                            format!("fn {}({}){}", m.name, ps.join(", "), ret)
+                        })
+                        .collect();
+                    let hover_text = with_doc(
+                        format!(
                             "```astra\ntrait {} {{\n  {}\n}}\n```",
                             def.name,
                             methods.join("\n  ")
-                        );
-                        return json!({
-                            "contents": {
-                                "kind": "markdown",
-                                "value": hover_text
-                            }
-                        });
-                    }
+                        ),
+                        &def.doc,
+                    );
+                    return json!({
+                        "contents": {
+                            "kind": "markdown",
+                            "value": hover_text
+                        }
+                    });
                 }
                 _ => {}
             }
@@ -498,13 +544,16 @@ impl LspServer {
                                                 } else {
                                                     format!(" effects({})", def.effects.join(", "))
                                                 };
-                                                let hover_text = format!(
-                                                    "```astra\nfn {}({}){}{}\n```\n*from {}*",
-                                                    def.name,
-                                                    params_str.join(", "),
-                                                    ret_str,
-                                                    effects_str,
-                                                    import.path.segments.join("."),
+                                                let hover_text = with_doc(
+                                                    format!(
+                                                        "```astra\nfn {}({}){}{}\n```\n*from {}*",
+                                                        def.name,
+                                                        params_str.join(", "),
+                                                        ret_str,
+                                                        effects_str,
+                                                        import.path.segments.join("."),
+                                                    ),
+                                                    &def.doc,
                                                 );
                                                 return json!({
                                                     "contents": {
@@ -540,6 +589,24 @@ impl LspServer {
             None => return Value::Null,
         };
 
+        // Resolve the name under the cursor, following imports through the
+        // symbol table from the last type check
+        let sf = SourceFile::new(std::path::PathBuf::from(uri_to_path(uri)), source.clone());
+        let lexer = Lexer::new(&sf);
+        let mut parser = Parser::new(lexer, sf.clone());
+        if let Ok(module) = parser.parse_module() {
+            let symbols = self.symbol_tables.get(uri).cloned().unwrap_or_default();
+            let definition = find_definition(&module, &sf, line + 1, col + 1, &symbols);
+            // An import that the type checker could not resolve falls back to
+            // the search by name below
+            if let Some(def) = definition.filter(|d| d.kind != BindingKind::Import) {
+                return json!({
+                    "uri": path_to_uri(&def.span.file),
+                    "range": span_to_range(&def.span)
+                });
+            }
+        }
+
         // Find the identifier at the cursor position
         let ident = find_ident_at_position(source, line, col);
         if ident.is_empty() {
@@ -633,6 +700,105 @@ impl LspServer {
         Value::Null
     }
 
+    /// Handle textDocument/references
+    fn handle_references(&self, params: &Value) -> Value {
+        let uri = match params["textDocument"]["uri"].as_str() {
+            Some(u) => u,
+            None => return Value::Null,
+        };
+        let line = params["position"]["line"].as_u64().unwrap_or(0) as usize;
+        let col = params["position"]["character"].as_u64().unwrap_or(0) as usize;
+        let include_declaration = params["context"]["includeDeclaration"]
+            .as_bool()
+            .unwrap_or(true);
+
+        let source = match self.documents.get(uri) {
+            Some(s) => s,
+            None => return Value::Null,
+        };
+
+        // Reparse the current text: the cached module may be from an older
+        // version of the document if the latest one has syntax errors
+        let sf = SourceFile::new(std::path::PathBuf::from(uri_to_path(uri)), source.clone());
+        let lexer = Lexer::new(&sf);
+        let mut parser = Parser::new(lexer, sf.clone());
+        let module = match parser.parse_module() {
+            Ok(m) => m,
+            Err(_) => return Value::Null,
+        };
+
+        let spans =
+            crate::resolve::find_references(&module, &sf, line + 1, col + 1, include_declaration);
+        Value::Array(
+            spans
+                .iter()
+                .map(|span| {
+                    json!({
+                        "uri": uri,
+                        "range": span_to_range(span)
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Handle textDocument/semanticTokens/full
+    fn handle_semantic_tokens(&self, params: &Value) -> Value {
+        let uri = match params["textDocument"]["uri"].as_str() {
+            Some(u) => u,
+            None => return Value::Null,
+        };
+        let source = match self.documents.get(uri) {
+            Some(s) => s,
+            None => return Value::Null,
+        };
+
+        // Classify the current text, by context alone while it doesn't parse
+        let sf = SourceFile::new(std::path::PathBuf::from(uri_to_path(uri)), source.clone());
+        let lexer = Lexer::new(&sf);
+        let mut parser = Parser::new(lexer, sf.clone());
+        let module = parser.parse_module().ok();
+
+        // Each token is five integers relative to the previous one: line
+        // delta, start character, length, type, and modifiers. Tokens may
+        // not span lines, so multi-line ones are split.
+        let mut data = Vec::new();
+        let (mut previous_line, mut previous_col) = (0, 0);
+        for token in classify(&sf, module.as_ref()) {
+            let kind = SemanticKind::ALL
+                .iter()
+                .position(|k| *k == token.kind)
+                .unwrap_or(0);
+            let text = &source[token.span.start..token.span.end];
+            for (i, piece) in text.split('\n').enumerate() {
+                let line = token.span.start_line.saturating_sub(1) + i;
+                let col = if i == 0 {
+                    token.span.start_col.saturating_sub(1)
+                } else {
+                    0
+                };
+                let length = piece.trim_end_matches('\r').chars().count();
+                if length == 0 {
+                    continue;
+                }
+                let delta_col = if line == previous_line {
+                    col - previous_col
+                } else {
+                    col
+                };
+                data.extend([
+                    line - previous_line,
+                    delta_col,
+                    length,
+                    kind,
+                    usize::from(token.declaration),
+                ]);
+                (previous_line, previous_col) = (line, col);
+            }
+        }
+        json!({ "data": data })
+    }
+
     /// Handle textDocument/documentSymbol
     fn handle_document_symbols(&self, params: &Value) -> Value {
         let uri = match params["textDocument"]["uri"].as_str() {
@@ -782,9 +948,34 @@ impl LspServer {
 
         // Add keywords
         for keyword in &[
-            "fn", "let", "mut", "if", "else", "match", "for", "while", "return", "break",
-            "continue", "import", "from", "type", "enum", "trait", "impl", "effect", "test",
-            "property", "true", "false", "Some", "None", "Ok", "Err",
+            "fn",
+            "let",
+            "mut",
+            "if",
+            "else",
+            "match",
+            "for",
+            "while",
+            "return",
+            "break",
+            "continue",
+            "import",
+            "from",
+            "type",
+            "enum",
+            "trait",
+            "impl",
+            "effect",
+            "test",
+            "property",
+            "before_each",
+            "after_each",
+            "true",
+            "false",
+            "Some",
+            "None",
+            "Ok",
+            "Err",
         ] {
             items.push(json!({
                 "label": keyword,
@@ -854,7 +1045,7 @@ impl LspServer {
 }
 
 /// Read the Content-Length header from the input stream
-fn read_content_length(reader: &mut impl BufRead) -> io::Result<usize> {
+pub(crate) fn read_content_length(reader: &mut impl BufRead) -> io::Result<usize> {
     let mut header = String::new();
     loop {
         header.clear();
@@ -905,13 +1096,29 @@ fn diagnostic_to_lsp(diag: &crate::diagnostics::Diagnostic) -> Value {
         Severity::Hint => 4,
     };
 
-    json!({
+    let mut lsp = json!({
         "range": span_to_range(&diag.span),
         "severity": severity,
         "code": diag.code,
         "source": "astra",
         "message": diag.message,
-    })
+    });
+    if !diag.labels.is_empty() {
+        lsp["relatedInformation"] = diag
+            .labels
+            .iter()
+            .map(|label| {
+                json!({
+                    "location": {
+                        "uri": path_to_uri(&label.span.file),
+                        "range": span_to_range(&label.span),
+                    },
+                    "message": label.message,
+                })
+            })
+            .collect();
+    }
+    lsp
 }
 
 /// Convert an Astra span to an LSP range
@@ -976,6 +1183,14 @@ fn find_ident_at_position(source: &str, line: usize, col: usize) -> String {
     chars[start..end].iter().collect()
 }
 
+/// Append an item's doc comment, if any, to its hover text
+fn with_doc(hover_text: String, doc: &Option<String>) -> String {
+    match doc {
+        Some(doc) => format!("{}\n\n{}", hover_text, doc),
+        None => hover_text,
+    }
+}
+
 /// Format a TypeExpr as a string for display
 fn format_type_expr(ty: &TypeExpr) -> String {
     match ty {
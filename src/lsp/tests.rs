@@ -64,10 +64,14 @@ fn test_diagnostic_to_lsp() {
     let diag = crate::diagnostics::Diagnostic {
         code: "E1001".to_string(),
         message: "Type mismatch".to_string(),
+        catalog: None,
         severity: Severity::Error,
         span: test_span(),
         notes: vec![],
         suggestions: vec![],
+        labels: vec![],
+        related: vec![],
+        hole: None,
     };
     let lsp = diagnostic_to_lsp(&diag);
     assert_eq!(lsp["severity"], 1);
@@ -104,6 +108,7 @@ fn test_code_action_from_suggestion() {
     let diag = crate::diagnostics::Diagnostic {
         code: "E1002".to_string(),
         message: "Unknown identifier 'prnt'".to_string(),
+        catalog: None,
         severity: Severity::Error,
         span: Span::new(std::path::PathBuf::from("/test.astra"), 0, 4, 1, 1, 1, 4),
         notes: vec![],
@@ -113,6 +118,9 @@ fn test_code_action_from_suggestion() {
                 "print",
             )),
         ],
+        labels: vec![],
+        related: vec![],
+        hole: None,
     };
     server
         .cached_diagnostics
@@ -155,3 +163,150 @@ fn test_code_action_no_diagnostics() {
     let result = server.handle_code_action(&params);
     assert_eq!(result, json!([]));
 }
+
+#[test]
+fn test_diagnostic_labels_to_related_information() {
+    let diag = crate::diagnostics::Diagnostic::warning("W0006")
+        .message("shadowed")
+        .span(test_span())
+        .label(test_span(), "previous binding here")
+        .build();
+    let lsp = diagnostic_to_lsp(&diag);
+    assert_eq!(
+        lsp["relatedInformation"][0]["message"],
+        "previous binding here"
+    );
+}
+
+#[test]
+fn test_references() {
+    let mut server = LspServer::new();
+    let uri = "file:///tmp/refs.astra";
+    server.documents.insert(
+        uri.to_string(),
+        "module m\n\nfn one() -> Int {\n  1\n}\n\nfn two() -> Int {\n  one() + one()\n}\n"
+            .to_string(),
+    );
+    let params = json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": 2, "character": 4 },
+        "context": { "includeDeclaration": false }
+    });
+    let result = server.handle_references(&params);
+    let lines: Vec<(u64, u64)> = result
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|loc| {
+            assert_eq!(loc["uri"], uri);
+            let start = &loc["range"]["start"];
+            (
+                start["line"].as_u64().unwrap(),
+                start["character"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(lines, vec![(7, 2), (7, 10)]);
+}
+
+#[test]
+fn test_definition_follows_symbol_table() {
+    let mut server = LspServer::new();
+    let uri = "file:///tmp/app.astra";
+    server.documents.insert(
+        uri.to_string(),
+        "module app\n\nimport std.text.{trim}\n\nfn f(s: Text) -> Text {\n  trim(s)\n}\n"
+            .to_string(),
+    );
+    let text_source = "module std.text\n\npublic fn trim(s: Text) -> Text {\n  s\n}\n";
+    let text_path = std::path::PathBuf::from("/lib/text.astra");
+    let text_module = crate::parser::parse_source(text_source, &text_path).unwrap();
+    let mut symbols = SymbolTable::new();
+    symbols.add_module(
+        "std.text",
+        &text_module,
+        &SourceFile::new(text_path, text_source.to_string()),
+    );
+    server.symbol_tables.insert(uri.to_string(), symbols);
+
+    let params = json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": 5, "character": 3 }
+    });
+    let result = server.handle_definition(&params);
+    assert_eq!(result["uri"], "file:///lib/text.astra");
+    assert_eq!(result["range"]["start"]["line"], 2);
+    assert_eq!(result["range"]["start"]["character"], 10);
+
+    // Local names resolve within the document
+    let params = json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": 5, "character": 8 }
+    });
+    let result = server.handle_definition(&params);
+    assert_eq!(result["uri"], uri);
+    assert_eq!(result["range"]["start"]["line"], 4);
+    assert_eq!(result["range"]["start"]["character"], 5);
+}
+
+#[test]
+fn test_hover_shows_doc_comment() {
+    let mut server = LspServer::new();
+    let uri = "file:///tmp/hover.astra";
+    let source = "module m\n\n## Adds one.\nfn inc(x: Int) -> Int {\n  x + 1\n}\n";
+    let module =
+        crate::parser::parse_source(source, &std::path::PathBuf::from("/tmp/hover.astra")).unwrap();
+    server.documents.insert(uri.to_string(), source.to_string());
+    server.modules.insert(uri.to_string(), module);
+    let params = json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": 3, "character": 4 }
+    });
+    let result = server.handle_hover(&params);
+    assert_eq!(
+        result["contents"]["value"],
+        "```astra\nfn inc(x: Int) -> Int\n```\n\nAdds one."
+    );
+}
+
+#[test]
+fn test_semantic_tokens() {
+    let mut server = LspServer::new();
+    let uri = "file:///tmp/tokens.astra";
+    server.documents.insert(
+        uri.to_string(),
+        "module m\n\nfn inc(n: Int) -> Int {\n  n + 1\n}\n".to_string(),
+    );
+    let result = server.handle_semantic_tokens(&json!({ "textDocument": { "uri": uri } }));
+    let data: Vec<u64> = result["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n.as_u64().unwrap())
+        .collect();
+    let index =
+        |kind: SemanticKind| SemanticKind::ALL.iter().position(|k| *k == kind).unwrap() as u64;
+    let mut tokens = data.chunks(5);
+    // `module`, then `m` on the same line
+    assert_eq!(
+        tokens.next(),
+        Some(&[0, 0, 6, index(SemanticKind::Keyword), 0][..])
+    );
+    assert_eq!(
+        tokens.next(),
+        Some(&[0, 7, 1, index(SemanticKind::Namespace), 0][..])
+    );
+    // `fn` two lines down, then the declarations of `inc` and `n`
+    assert_eq!(
+        tokens.next(),
+        Some(&[2, 0, 2, index(SemanticKind::Keyword), 0][..])
+    );
+    assert_eq!(
+        tokens.next(),
+        Some(&[0, 3, 3, index(SemanticKind::Function), 1][..])
+    );
+    assert_eq!(
+        tokens.next(),
+        Some(&[0, 4, 1, index(SemanticKind::Parameter), 1][..])
+    );
+}
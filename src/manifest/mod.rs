@@ -2,8 +2,10 @@
 
 pub mod registry;
 
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::interpreter::{Budget, ContractMode};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 /// Astra project manifest
@@ -27,6 +29,26 @@ pub struct Manifest {
     /// Feature flags
     #[serde(default)]
     pub features: HashMap<String, Vec<String>>,
+
+    /// Lint configuration
+    #[serde(default)]
+    pub lint: LintConfig,
+
+    /// Execution budgets
+    #[serde(default)]
+    pub limits: Limits,
+
+    /// Contract checking
+    #[serde(default)]
+    pub contracts: ContractsConfig,
+
+    /// Database for the Db effect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub db: Option<DbConfig>,
+
+    /// Binary targets (`[[bin]]` entries)
+    #[serde(default, rename = "bin", skip_serializing_if = "Vec::is_empty")]
+    pub bins: Vec<BinTarget>,
 }
 
 /// Package information
@@ -107,6 +129,142 @@ pub struct NativeTarget {
     pub opt_level: u8,
 }
 
+/// A binary target declared with `[[bin]]`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinTarget {
+    /// Target name (selected with `astra run --bin <name>`)
+    pub name: String,
+
+    /// Entry file, relative to the project root
+    pub path: String,
+
+    /// Name of the function to run
+    #[serde(default = "default_bin_main")]
+    pub main: String,
+}
+
+fn default_bin_main() -> String {
+    "main".to_string()
+}
+
+/// Lint configuration (`[lint]` section)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LintConfig {
+    /// Default level for every lint
+    #[serde(default)]
+    pub level: LintLevel,
+
+    /// Per-code overrides (e.g., `W0002 = "deny"` or `unused_import = "deny"`),
+    /// keyed by code once parsed
+    #[serde(default)]
+    pub rules: HashMap<String, LintLevel>,
+}
+
+/// Execution budgets (`[limits]` section), enforced by `astra run` and by each
+/// test in `astra test`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Limits {
+    /// Most expressions to evaluate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_steps: Option<u64>,
+
+    /// Most estimated bytes bound to variables at once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory: Option<usize>,
+
+    /// Most calls per effect, keyed by lowercase effect name
+    /// (e.g., `max_effect_calls.net = 10`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub max_effect_calls: BTreeMap<String, u64>,
+}
+
+/// Contract checking (`[contracts]` section), for `astra run` and `astra test`
+/// unless `--contracts` is passed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractsConfig {
+    /// Which `requires`/`ensures` clauses are checked
+    #[serde(default)]
+    pub mode: ContractMode,
+}
+
+/// Database for the Db effect (`[db]` section). `astra run` grants Db only
+/// when this is set, and only to this file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DbConfig {
+    /// SQLite database file, relative to the project root
+    pub path: String,
+}
+
+impl Limits {
+    /// The budget the interpreter enforces for these limits
+    pub fn budget(&self) -> Budget {
+        Budget {
+            max_steps: self.max_steps,
+            max_memory: self.max_memory,
+            max_effect_calls: self
+                .max_effect_calls
+                .iter()
+                .map(|(effect, max)| (effect.to_ascii_lowercase(), *max))
+                .collect(),
+        }
+    }
+}
+
+/// How a lint is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// Suppress the lint entirely
+    Allow,
+    /// Report the lint as a warning
+    #[default]
+    Warn,
+    /// Report the lint as an error
+    Deny,
+}
+
+impl LintConfig {
+    /// Get the effective level for a lint code
+    pub fn level_for(&self, code: &str) -> LintLevel {
+        self.rules.get(code).copied().unwrap_or(self.level)
+    }
+
+    /// Apply configured levels to lint diagnostics (W-codes).
+    ///
+    /// Allowed lints are dropped and denied lints become errors; all other
+    /// diagnostics pass through unchanged.
+    pub fn apply(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        diagnostics
+            .into_iter()
+            .filter_map(|mut d| {
+                if !is_lint_code(&d.code) {
+                    return Some(d);
+                }
+                match self.level_for(&d.code) {
+                    LintLevel::Allow => None,
+                    LintLevel::Warn => Some(d),
+                    LintLevel::Deny => {
+                        d.severity = Severity::Error;
+                        Some(d)
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Whether a diagnostic code belongs to a lint (W0xxx) rather than an error
+pub fn is_lint_code(code: &str) -> bool {
+    code.starts_with('W')
+}
+
+/// The code for a lint given by name (as in `@allow(...)`) or by code, in
+/// any case; `None` if it is not a known lint
+pub fn lint_code(name: &str) -> Option<&'static str> {
+    use crate::diagnostics::error_codes::warnings::code_for_name;
+    code_for_name(&name.to_ascii_lowercase()).or_else(|| code_for_name(&name.to_ascii_uppercase()))
+}
+
 /// Dependency specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -173,7 +331,49 @@ impl Manifest {
 
     /// Parse a manifest from TOML string
     pub fn parse(content: &str) -> Result<Self, ManifestError> {
-        toml::from_str(content).map_err(|e| ManifestError::Parse(e.to_string()))
+        let mut manifest: Self =
+            toml::from_str(content).map_err(|e| ManifestError::Parse(e.to_string()))?;
+        manifest.validate()?;
+        manifest.lint.rules = manifest
+            .lint
+            .rules
+            .into_iter()
+            .map(|(name, level)| match lint_code(&name) {
+                Some(code) => Ok((code.to_string(), level)),
+                None => Err(ManifestError::Validation(format!(
+                    "unknown lint `{}` in [lint.rules]",
+                    name
+                ))),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(manifest)
+    }
+
+    /// Validate cross-field constraints that serde cannot express
+    fn validate(&self) -> Result<(), ManifestError> {
+        let mut seen = std::collections::HashSet::new();
+        for bin in &self.bins {
+            if !seen.insert(bin.name.as_str()) {
+                return Err(ManifestError::Validation(format!(
+                    "duplicate binary target `{}`",
+                    bin.name
+                )));
+            }
+        }
+        if let Some(db) = &self.db {
+            let path = Path::new(&db.path);
+            if path.is_absolute()
+                || path
+                    .components()
+                    .any(|c| matches!(c, std::path::Component::ParentDir))
+            {
+                return Err(ManifestError::Validation(format!(
+                    "[db] path `{}` must be inside the project",
+                    db.path
+                )));
+            }
+        }
+        Ok(())
     }
 
     /// Serialize the manifest to TOML
@@ -185,6 +385,122 @@ impl Manifest {
     pub fn entry_point(&self) -> &str {
         self.package.main.as_deref().unwrap_or("src/main.astra")
     }
+
+    /// Get all binary targets.
+    ///
+    /// A manifest without `[[bin]]` entries has a single implicit target
+    /// named after the package, using `package.main` as its entry file.
+    pub fn bin_targets(&self) -> Vec<BinTarget> {
+        if !self.bins.is_empty() {
+            return self.bins.clone();
+        }
+        vec![BinTarget {
+            name: self.package.name.clone(),
+            path: self.entry_point().to_string(),
+            main: default_bin_main(),
+        }]
+    }
+
+    /// Select a binary target by name, defaulting when only one exists
+    pub fn find_bin(&self, name: Option<&str>) -> Result<BinTarget, ManifestError> {
+        let targets = self.bin_targets();
+        let names = || {
+            targets
+                .iter()
+                .map(|t| t.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match name {
+            Some(name) => targets
+                .iter()
+                .find(|t| t.name == name)
+                .cloned()
+                .ok_or_else(|| {
+                    ManifestError::Validation(format!(
+                        "no binary target named `{}` (available: {})",
+                        name,
+                        names()
+                    ))
+                }),
+            None if targets.len() == 1 => Ok(targets[0].clone()),
+            None => Err(ManifestError::Validation(format!(
+                "multiple binary targets; use `--bin` to pick one (available: {})",
+                names()
+            ))),
+        }
+    }
+}
+
+const BUILTIN_EFFECTS: [&str; 11] = [
+    "Console", "Fs", "Net", "Clock", "Rand", "Env", "Crypto", "Serve", "Db", "Progress", "Metrics",
+];
+
+/// Inline manifest of a single-file script, written as `#:` lines after its
+/// `#!` line:
+///
+/// ```text
+/// #!/usr/bin/env astra
+/// #: capabilities = ["Console", "Fs"]
+/// #: [dependencies]
+/// #: textutil = { path = "../textutil" }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptManifest {
+    /// Effects the script may use; the others are withheld. Every effect is
+    /// granted when this is omitted.
+    #[serde(default)]
+    pub capabilities: Option<Vec<String>>,
+
+    /// Dependencies, with paths relative to the script
+    #[serde(default)]
+    pub dependencies: HashMap<String, Dependency>,
+
+    /// Execution budgets, as in `[limits]`
+    #[serde(default)]
+    pub limits: Limits,
+}
+
+impl ScriptManifest {
+    /// The inline manifest in a script's header, if it has one
+    pub fn from_source(source: &str) -> Result<Option<Self>, ManifestError> {
+        let Some(header) = crate::parser::lexer::script_header(source) else {
+            return Ok(None);
+        };
+        let lines: Vec<&str> = header
+            .lines()
+            .filter_map(|line| line.strip_prefix("#:"))
+            .map(|line| line.strip_prefix(' ').unwrap_or(line))
+            .collect();
+        if lines.is_empty() {
+            return Ok(None);
+        }
+        let manifest: Self =
+            toml::from_str(&lines.join("\n")).map_err(|e| ManifestError::Parse(e.to_string()))?;
+        for effect in manifest.capabilities.iter().flatten() {
+            if !BUILTIN_EFFECTS.contains(&effect.as_str()) {
+                return Err(ManifestError::Validation(format!(
+                    "unknown capability `{}` (expected one of {})",
+                    effect,
+                    BUILTIN_EFFECTS.join(", ")
+                )));
+            }
+        }
+        Ok(Some(manifest))
+    }
+
+    /// Effects in `declared` that the manifest does not grant
+    pub fn withheld<'a>(&self, declared: &'a [String]) -> Vec<&'a str> {
+        match &self.capabilities {
+            Some(granted) => declared
+                .iter()
+                .filter(|effect| !granted.contains(effect))
+                .map(String::as_str)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
 }
 
 /// Manifest errors
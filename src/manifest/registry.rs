@@ -74,13 +74,22 @@ impl PackageRegistry {
 
     /// Resolve all dependencies from a manifest
     pub fn resolve(&mut self, manifest: &Manifest) -> Result<Vec<ResolvedPackage>, ManifestError> {
+        self.resolve_dependencies(&manifest.dependencies)
+    }
+
+    /// Resolve dependencies declared outside an `astra.toml`, such as a
+    /// script's inline manifest
+    pub fn resolve_dependencies(
+        &mut self,
+        dependencies: &HashMap<String, Dependency>,
+    ) -> Result<Vec<ResolvedPackage>, ManifestError> {
         // Ensure cache directory exists
         std::fs::create_dir_all(&self.cache_dir)
             .map_err(|e| ManifestError::Io(format!("Failed to create cache dir: {}", e)))?;
 
         let mut packages = Vec::new();
 
-        for (name, dep) in &manifest.dependencies {
+        for (name, dep) in dependencies {
             let resolved = self.resolve_dependency(name, dep)?;
             self.resolved.insert(name.clone(), resolved.clone());
             packages.push(resolved);
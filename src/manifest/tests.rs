@@ -50,3 +50,241 @@ extra = ["http/extra"]
     assert!(manifest.dependencies.contains_key("std"));
     assert!(manifest.dependencies.contains_key("http"));
 }
+
+#[test]
+fn test_parse_bin_targets() {
+    let content = r#"
+[package]
+name = "tools"
+version = "0.1.0"
+
+[[bin]]
+name = "server"
+path = "src/server.astra"
+
+[[bin]]
+name = "migrate"
+path = "src/migrate.astra"
+main = "run_migrations"
+"#;
+
+    let manifest = Manifest::parse(content).unwrap();
+    assert_eq!(manifest.bins.len(), 2);
+    assert_eq!(manifest.bins[0].main, "main");
+    assert_eq!(manifest.bins[1].main, "run_migrations");
+
+    let target = manifest.find_bin(Some("migrate")).unwrap();
+    assert_eq!(target.path, "src/migrate.astra");
+    assert!(manifest.find_bin(None).is_err());
+    assert!(manifest.find_bin(Some("missing")).is_err());
+}
+
+#[test]
+fn test_implicit_bin_target() {
+    let content = r#"
+[package]
+name = "app"
+version = "0.1.0"
+main = "src/app.astra"
+"#;
+
+    let manifest = Manifest::parse(content).unwrap();
+    let target = manifest.find_bin(None).unwrap();
+    assert_eq!(target.name, "app");
+    assert_eq!(target.path, "src/app.astra");
+    assert_eq!(target.main, "main");
+}
+
+#[test]
+fn test_duplicate_bin_names_rejected() {
+    let content = r#"
+[package]
+name = "app"
+version = "0.1.0"
+
+[[bin]]
+name = "cli"
+path = "a.astra"
+
+[[bin]]
+name = "cli"
+path = "b.astra"
+"#;
+
+    assert!(matches!(
+        Manifest::parse(content),
+        Err(ManifestError::Validation(_))
+    ));
+}
+
+#[test]
+fn test_parse_lint_config() {
+    let content = r#"
+[package]
+name = "app"
+version = "0.1.0"
+
+[lint]
+level = "warn"
+
+[lint.rules]
+W0001 = "deny"
+W0002 = "allow"
+"#;
+
+    let manifest = Manifest::parse(content).unwrap();
+    assert_eq!(manifest.lint.level_for("W0001"), LintLevel::Deny);
+    assert_eq!(manifest.lint.level_for("W0002"), LintLevel::Allow);
+    assert_eq!(manifest.lint.level_for("W0003"), LintLevel::Warn);
+}
+
+#[test]
+fn test_parse_lint_config_by_name() {
+    let content = r#"
+[package]
+name = "app"
+version = "0.1.0"
+
+[lint.rules]
+unused_variable = "deny"
+w0002 = "allow"
+"#;
+
+    let manifest = Manifest::parse(content).unwrap();
+    assert_eq!(manifest.lint.level_for("W0001"), LintLevel::Deny);
+    assert_eq!(manifest.lint.level_for("W0002"), LintLevel::Allow);
+}
+
+#[test]
+fn test_parse_lint_config_rejects_unknown_lints() {
+    for rule in ["W9999", "E1001", "unused_thing"] {
+        let content = format!(
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[lint.rules]\n{} = \"deny\"\n",
+            rule
+        );
+        let err = Manifest::parse(&content).unwrap_err();
+        assert!(
+            matches!(&err, ManifestError::Validation(msg) if msg.contains(rule)),
+            "{}",
+            err
+        );
+    }
+}
+
+#[test]
+fn test_parse_limits() {
+    let content = r#"
+[package]
+name = "app"
+version = "0.1.0"
+
+[limits]
+max_steps = 100000
+max_memory = 65536
+max_effect_calls.net = 10
+max_effect_calls.Fs = 3
+"#;
+
+    let manifest = Manifest::parse(content).unwrap();
+    assert_eq!(manifest.limits.max_steps, Some(100_000));
+    let budget = manifest.limits.budget();
+    assert_eq!(budget.max_memory, Some(65536));
+    assert_eq!(budget.max_effect_calls.get("net"), Some(&10));
+    assert_eq!(budget.max_effect_calls.get("fs"), Some(&3));
+
+    let minimal = Manifest::parse("[package]\nname = \"app\"\nversion = \"0.1.0\"\n").unwrap();
+    assert_eq!(minimal.limits, Limits::default());
+}
+
+#[test]
+fn test_parse_contract_mode() {
+    let manifest = Manifest::parse(
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[contracts]\nmode = \"boundary\"\n",
+    )
+    .unwrap();
+    assert_eq!(manifest.contracts.mode, ContractMode::Boundary);
+
+    let minimal = Manifest::parse("[package]\nname = \"app\"\nversion = \"0.1.0\"\n").unwrap();
+    assert_eq!(minimal.contracts.mode, ContractMode::All);
+    assert!(Manifest::parse(
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[contracts]\nmode = \"some\"\n"
+    )
+    .is_err());
+}
+
+#[test]
+fn test_parse_db_config() {
+    let manifest = Manifest::parse(
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[db]\npath = \"data/app.db\"\n",
+    )
+    .unwrap();
+    assert_eq!(manifest.db.unwrap().path, "data/app.db");
+
+    for path in ["/var/app.db", "../shared.db"] {
+        let content = format!(
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[db]\npath = \"{}\"\n",
+            path
+        );
+        assert!(matches!(
+            Manifest::parse(&content),
+            Err(ManifestError::Validation(_))
+        ));
+    }
+}
+
+#[test]
+fn test_script_manifest_from_header() {
+    let source = "#!/usr/bin/env astra\n#: capabilities = [\"Console\", \"Fs\"]\n#: limits.max_steps = 1000\n#: [dependencies]\n#: textutil = { path = \"lib/textutil\" }\nmodule tool\n";
+    let script = ScriptManifest::from_source(source).unwrap().unwrap();
+    assert_eq!(
+        script.capabilities,
+        Some(vec!["Console".to_string(), "Fs".to_string()])
+    );
+    assert_eq!(script.limits.max_steps, Some(1000));
+    assert!(script.dependencies.contains_key("textutil"));
+    let declared = ["Console".to_string(), "Net".to_string()];
+    assert_eq!(script.withheld(&declared), ["Net"]);
+    assert!(ScriptManifest::default().withheld(&declared).is_empty());
+
+    // A shebang alone, or no header, is not a manifest
+    assert!(
+        ScriptManifest::from_source("#!/usr/bin/env astra\nmodule tool\n")
+            .unwrap()
+            .is_none()
+    );
+    assert!(ScriptManifest::from_source("module tool\n")
+        .unwrap()
+        .is_none());
+    assert!(matches!(
+        ScriptManifest::from_source("#: capabilities = [\"Disk\"]\nmodule tool\n"),
+        Err(ManifestError::Validation(_))
+    ));
+    assert!(matches!(
+        ScriptManifest::from_source("#: permissions = []\nmodule tool\n"),
+        Err(ManifestError::Parse(_))
+    ));
+}
+
+#[test]
+fn test_lint_config_apply() {
+    use crate::diagnostics::{Diagnostic, Severity};
+
+    let mut lint = LintConfig::default();
+    lint.rules.insert("W0001".to_string(), LintLevel::Deny);
+    lint.rules.insert("W0002".to_string(), LintLevel::Allow);
+
+    let diagnostics = vec![
+        Diagnostic::warning("W0001")
+            .message("unused variable")
+            .build(),
+        Diagnostic::warning("W0002")
+            .message("unused import")
+            .build(),
+        Diagnostic::error("E1001").message("type mismatch").build(),
+    ];
+    let applied = lint.apply(diagnostics);
+    assert_eq!(applied.len(), 2);
+    assert_eq!(applied[0].code, "W0001");
+    assert!(matches!(applied[0].severity, Severity::Error));
+    assert_eq!(applied[1].code, "E1001");
+}
@@ -33,6 +33,13 @@ pub struct Module {
     pub id: NodeId,
     pub span: Span,
     pub name: ModulePath,
+    /// Script header before the module: a `#!` line and `#:` inline
+    /// manifest lines, verbatim (see [`script_header`](super::lexer::script_header))
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    /// Module doc comment from `##!` lines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
     pub items: Vec<Item>,
 }
 
@@ -47,6 +54,7 @@ pub struct ModulePath {
 /// Top-level items in a module
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
+#[allow(clippy::large_enum_variant)]
 pub enum Item {
     Import(ImportDecl),
     TypeDef(TypeDef),
@@ -57,6 +65,38 @@ pub enum Item {
     EffectDef(EffectDecl),
     Test(TestBlock),
     Property(PropertyBlock),
+    TestHook(TestHook),
+    MacroDef(MacroDef),
+}
+
+impl Item {
+    /// Source span of the item, excluding its doc comment and attributes
+    pub fn span(&self) -> &Span {
+        match self {
+            Item::Import(def) => &def.span,
+            Item::TypeDef(def) => &def.span,
+            Item::EnumDef(def) => &def.span,
+            Item::FnDef(def) => &def.span,
+            Item::TraitDef(def) => &def.span,
+            Item::ImplBlock(def) => &def.span,
+            Item::EffectDef(def) => &def.span,
+            Item::Test(def) => &def.span,
+            Item::Property(def) => &def.span,
+            Item::TestHook(def) => &def.span,
+            Item::MacroDef(def) => &def.span,
+        }
+    }
+
+    /// Attributes attached to the item (only imports, types, enums, and functions take them)
+    pub fn attributes(&self) -> &[Attribute] {
+        match self {
+            Item::Import(def) => &def.attributes,
+            Item::TypeDef(def) => &def.attributes,
+            Item::EnumDef(def) => &def.attributes,
+            Item::FnDef(def) => &def.attributes,
+            _ => &[],
+        }
+    }
 }
 
 /// Import declaration
@@ -68,6 +108,9 @@ pub struct ImportDecl {
     pub kind: ImportKind,
     /// P4.3: Re-exports (public import)
     pub public: bool,
+    /// Attributes such as `@allow(unused_variable)`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Attribute>,
 }
 
 /// Kind of import
@@ -90,6 +133,12 @@ pub struct TypeDef {
     pub type_params: Vec<String>,
     pub value: TypeExpr,
     pub invariant: Option<Box<Expr>>,
+    /// Attributes such as `@allow(unused_variable)`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Attribute>,
+    /// Doc comment from the `##` lines before the definition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
 }
 
 /// Enum definition
@@ -100,6 +149,12 @@ pub struct EnumDef {
     pub name: String,
     pub type_params: Vec<String>,
     pub variants: Vec<Variant>,
+    /// Attributes such as `@allow(unused_variable)`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Attribute>,
+    /// Doc comment from the `##` lines before the definition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
 }
 
 /// Enum variant
@@ -128,6 +183,9 @@ pub struct TraitDef {
     pub name: String,
     pub type_params: Vec<String>,
     pub methods: Vec<TraitMethod>,
+    /// Doc comment from the `##` lines before the definition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
 }
 
 /// A method signature in a trait
@@ -138,6 +196,41 @@ pub struct TraitMethod {
     pub name: String,
     pub params: Vec<Param>,
     pub return_type: Option<TypeExpr>,
+    /// Preconditions every implementation inherits
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<Expr>,
+    /// Postconditions every implementation inherits
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ensures: Vec<Expr>,
+}
+
+impl TraitMethod {
+    /// This method's `requires` and `ensures` clauses as they apply to
+    /// `method`, an implementation of it: each parameter is renamed to the
+    /// implementation's parameter in the same position
+    pub fn contracts_for(&self, method: &FnDef) -> (Vec<Expr>, Vec<Expr>) {
+        let renames: std::collections::HashMap<String, String> = self
+            .params
+            .iter()
+            .zip(&method.params)
+            .filter(|(declared, implemented)| declared.name != implemented.name)
+            .map(|(declared, implemented)| (declared.name.clone(), implemented.name.clone()))
+            .collect();
+        let rename = |clauses: &[Expr]| -> Vec<Expr> {
+            clauses
+                .iter()
+                .map(|clause| {
+                    let mut clause = clause.clone();
+                    super::rewrite::rewrite_expr(
+                        &mut super::rewrite::RenameIdents(&renames),
+                        &mut clause,
+                    );
+                    clause
+                })
+                .collect()
+        };
+        (rename(&self.requires), rename(&self.ensures))
+    }
 }
 
 /// Impl block (e.g., `impl Show for Int { fn to_text(self) -> Text { ... } }`)
@@ -159,6 +252,50 @@ pub struct EffectDecl {
     pub operations: Vec<TraitMethod>,
 }
 
+/// Macro definition (e.g., `macro square { ($x) => $x * $x }`)
+///
+/// Templates are expressions in which `$name` placeholders appear as
+/// identifiers named `$name` (or `$name...` for a spliced variadic).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroDef {
+    pub id: NodeId,
+    pub span: Span,
+    pub name: String,
+    pub rules: Vec<MacroRule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+}
+
+/// One `(pattern) => template` rule of a macro
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroRule {
+    pub id: NodeId,
+    pub span: Span,
+    pub params: Vec<MacroParam>,
+    pub template: Expr,
+}
+
+/// A `$name`, `$name: ident`, or trailing `$name...` in a macro pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroParam {
+    pub span: Span,
+    /// The name without its `$`
+    pub name: String,
+    pub fragment: MacroFragment,
+    /// Whether the parameter takes all remaining arguments
+    #[serde(default)]
+    pub variadic: bool,
+}
+
+/// What a macro parameter accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MacroFragment {
+    /// Any expression
+    Expr,
+    /// A plain identifier, usable in binding positions of the template
+    Ident,
+}
+
 /// Function definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FnDef {
@@ -177,9 +314,30 @@ pub struct FnDef {
     pub params: Vec<Param>,
     pub return_type: Option<TypeExpr>,
     pub effects: Vec<String>,
+    /// Span of the `effects(...)` clause, or an empty span after the
+    /// signature where the clause would go
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effects_span: Option<Span>,
     pub requires: Vec<Expr>,
     pub ensures: Vec<Expr>,
     pub body: Block,
+    /// Attributes such as `@allow(unused_variable)`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Attribute>,
+    /// Doc comment from the `##` lines before the definition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+}
+
+/// Attribute attached to an item or `let` statement (e.g., `@allow(unused_variable)`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attribute {
+    pub span: Span,
+    pub name: String,
+    pub args: Vec<String>,
+    /// `key = "value"` arguments, such as `@cfg(feature = "net")`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub named_args: Vec<(String, String)>,
 }
 
 /// Visibility modifier
@@ -220,6 +378,35 @@ pub struct PropertyBlock {
     pub body: Block,
 }
 
+/// `before_each` or `after_each` block that runs around every test in the module
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestHook {
+    pub id: NodeId,
+    pub span: Span,
+    pub kind: HookKind,
+    pub using: Option<UsingClause>,
+    pub body: Block,
+}
+
+/// When a test hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HookKind {
+    /// Runs before each test; its value is the test's `fixture`
+    BeforeEach,
+    /// Runs after each test, whether it passed or failed
+    AfterEach,
+}
+
+impl HookKind {
+    /// The keyword that introduces the hook
+    pub fn keyword(self) -> &'static str {
+        match self {
+            HookKind::BeforeEach => "before_each",
+            HookKind::AfterEach => "after_each",
+        }
+    }
+}
+
 /// Using clause for capability injection
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UsingClause {
@@ -291,6 +478,9 @@ pub enum Stmt {
         mutable: bool,
         ty: Option<TypeExpr>,
         value: Box<Expr>,
+        /// Attributes such as `@allow(unused_variable)`
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        attributes: Vec<Attribute>,
     },
     /// Let destructuring binding (e.g., `let {x, y} = expr`)
     LetPattern {
@@ -299,6 +489,9 @@ pub enum Stmt {
         pattern: Pattern,
         ty: Option<TypeExpr>,
         value: Box<Expr>,
+        /// Attributes such as `@allow(unused_variable)`
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        attributes: Vec<Attribute>,
     },
     /// Assignment
     Assign {
@@ -321,6 +514,30 @@ pub enum Stmt {
     },
 }
 
+impl Stmt {
+    /// Get the span of this statement
+    pub fn span(&self) -> &Span {
+        match self {
+            Stmt::Let { span, .. }
+            | Stmt::LetPattern { span, .. }
+            | Stmt::Assign { span, .. }
+            | Stmt::Expr { span, .. }
+            | Stmt::Return { span, .. } => span,
+        }
+    }
+
+    /// The node ID of this statement
+    pub fn id_mut(&mut self) -> &mut NodeId {
+        match self {
+            Stmt::Let { id, .. }
+            | Stmt::LetPattern { id, .. }
+            | Stmt::Assign { id, .. }
+            | Stmt::Expr { id, .. }
+            | Stmt::Return { id, .. } => id,
+        }
+    }
+}
+
 /// Expression
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -336,6 +553,12 @@ pub enum Expr {
         span: Span,
         value: f64,
     },
+    /// Decimal literal (`1.50d`), keeping the scale it was written with
+    DecimalLit {
+        id: NodeId,
+        span: Span,
+        value: rust_decimal::Decimal,
+    },
     BoolLit {
         id: NodeId,
         span: Span,
@@ -375,6 +598,9 @@ pub enum Expr {
         span: Span,
         expr: Box<Expr>,
         field: String,
+        /// Span of the field name
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        field_span: Option<Span>,
     },
 
     // Operations
@@ -403,6 +629,9 @@ pub enum Expr {
         receiver: Box<Expr>,
         method: String,
         args: Vec<Expr>,
+        /// Span of the method name
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        method_span: Option<Span>,
     },
 
     // Control flow
@@ -529,11 +758,31 @@ pub enum Expr {
         expr: Box<Expr>,
     },
 
+    /// Bounded quantifier: `forall x in xs: cond` or `exists x in xs: cond`
+    Quantifier {
+        id: NodeId,
+        span: Span,
+        kind: QuantifierKind,
+        binding: String,
+        iter: Box<Expr>,
+        body: Box<Expr>,
+    },
+
     // Special
     Hole {
         id: NodeId,
         span: Span,
     },
+
+    /// Macro invocation `name!(args)`; the parser fills in `expansion`
+    MacroCall {
+        id: NodeId,
+        span: Span,
+        name: String,
+        args: Vec<Expr>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expansion: Option<Box<Expr>>,
+    },
 }
 
 impl Expr {
@@ -542,6 +791,7 @@ impl Expr {
         match self {
             Expr::IntLit { span, .. }
             | Expr::FloatLit { span, .. }
+            | Expr::DecimalLit { span, .. }
             | Expr::BoolLit { span, .. }
             | Expr::TextLit { span, .. }
             | Expr::UnitLit { span, .. }
@@ -570,7 +820,89 @@ impl Expr {
             | Expr::Range { span, .. }
             | Expr::IndexAccess { span, .. }
             | Expr::Await { span, .. }
-            | Expr::Hole { span, .. } => span,
+            | Expr::Quantifier { span, .. }
+            | Expr::Hole { span, .. }
+            | Expr::MacroCall { span, .. } => span,
+        }
+    }
+
+    /// Get the node ID of this expression
+    pub fn id(&self) -> NodeId {
+        match self {
+            Expr::IntLit { id, .. }
+            | Expr::FloatLit { id, .. }
+            | Expr::DecimalLit { id, .. }
+            | Expr::BoolLit { id, .. }
+            | Expr::TextLit { id, .. }
+            | Expr::UnitLit { id, .. }
+            | Expr::Ident { id, .. }
+            | Expr::QualifiedIdent { id, .. }
+            | Expr::Record { id, .. }
+            | Expr::FieldAccess { id, .. }
+            | Expr::Binary { id, .. }
+            | Expr::Unary { id, .. }
+            | Expr::Call { id, .. }
+            | Expr::MethodCall { id, .. }
+            | Expr::If { id, .. }
+            | Expr::Match { id, .. }
+            | Expr::Block { id, .. }
+            | Expr::Try { id, .. }
+            | Expr::TryElse { id, .. }
+            | Expr::ListLit { id, .. }
+            | Expr::TupleLit { id, .. }
+            | Expr::MapLit { id, .. }
+            | Expr::Lambda { id, .. }
+            | Expr::ForIn { id, .. }
+            | Expr::While { id, .. }
+            | Expr::Break { id, .. }
+            | Expr::Continue { id, .. }
+            | Expr::StringInterp { id, .. }
+            | Expr::Range { id, .. }
+            | Expr::IndexAccess { id, .. }
+            | Expr::Await { id, .. }
+            | Expr::Quantifier { id, .. }
+            | Expr::Hole { id, .. }
+            | Expr::MacroCall { id, .. } => *id,
+        }
+    }
+
+    /// The node ID of this expression, to give a copy of it fresh IDs
+    pub fn id_mut(&mut self) -> &mut NodeId {
+        match self {
+            Expr::IntLit { id, .. }
+            | Expr::FloatLit { id, .. }
+            | Expr::DecimalLit { id, .. }
+            | Expr::BoolLit { id, .. }
+            | Expr::TextLit { id, .. }
+            | Expr::UnitLit { id, .. }
+            | Expr::Ident { id, .. }
+            | Expr::QualifiedIdent { id, .. }
+            | Expr::Record { id, .. }
+            | Expr::FieldAccess { id, .. }
+            | Expr::Binary { id, .. }
+            | Expr::Unary { id, .. }
+            | Expr::Call { id, .. }
+            | Expr::MethodCall { id, .. }
+            | Expr::If { id, .. }
+            | Expr::Match { id, .. }
+            | Expr::Block { id, .. }
+            | Expr::Try { id, .. }
+            | Expr::TryElse { id, .. }
+            | Expr::ListLit { id, .. }
+            | Expr::TupleLit { id, .. }
+            | Expr::MapLit { id, .. }
+            | Expr::Lambda { id, .. }
+            | Expr::ForIn { id, .. }
+            | Expr::While { id, .. }
+            | Expr::Break { id, .. }
+            | Expr::Continue { id, .. }
+            | Expr::StringInterp { id, .. }
+            | Expr::Range { id, .. }
+            | Expr::IndexAccess { id, .. }
+            | Expr::Await { id, .. }
+            | Expr::Quantifier { id, .. }
+            | Expr::Hole { id, .. }
+            | Expr::MacroCall { id, .. } => id,
         }
     }
 }
@@ -640,6 +972,24 @@ impl UnaryOp {
     }
 }
 
+/// Whether a quantifier needs its condition to hold for every element or
+/// for at least one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuantifierKind {
+    Forall,
+    Exists,
+}
+
+impl QuantifierKind {
+    /// Get the keyword for this quantifier
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuantifierKind::Forall => "forall",
+            QuantifierKind::Exists => "exists",
+        }
+    }
+}
+
 /// Lambda parameter (may omit type for inference)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LambdaParam {
@@ -696,6 +1046,9 @@ pub enum Pattern {
         id: NodeId,
         span: Span,
         fields: Vec<(String, Pattern)>,
+        /// Ends with `..`: the record may have fields the pattern leaves out
+        #[serde(default)]
+        rest: bool,
     },
     Variant {
         id: NodeId,
@@ -710,6 +1063,38 @@ pub enum Pattern {
     },
 }
 
+impl Pattern {
+    /// Get the span of this pattern
+    pub fn span(&self) -> &Span {
+        match self {
+            Pattern::Wildcard { span, .. }
+            | Pattern::Ident { span, .. }
+            | Pattern::IntLit { span, .. }
+            | Pattern::FloatLit { span, .. }
+            | Pattern::BoolLit { span, .. }
+            | Pattern::TextLit { span, .. }
+            | Pattern::Record { span, .. }
+            | Pattern::Variant { span, .. }
+            | Pattern::Tuple { span, .. } => span,
+        }
+    }
+
+    /// The node ID of this pattern
+    pub fn id_mut(&mut self) -> &mut NodeId {
+        match self {
+            Pattern::Wildcard { id, .. }
+            | Pattern::Ident { id, .. }
+            | Pattern::IntLit { id, .. }
+            | Pattern::FloatLit { id, .. }
+            | Pattern::BoolLit { id, .. }
+            | Pattern::TextLit { id, .. }
+            | Pattern::Record { id, .. }
+            | Pattern::Variant { id, .. }
+            | Pattern::Tuple { id, .. } => id,
+        }
+    }
+}
+
 /// Part of an interpolated string
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum StringPart {
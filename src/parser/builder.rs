@@ -0,0 +1,848 @@
+//! Builders for constructing AST nodes in code.
+//!
+//! Code generators, refactoring tools, and hosts that synthesize programs
+//! build modules here instead of templating source text. Every node gets a
+//! fresh [`NodeId`] and a synthetic span in the [`GENERATED`] file; render
+//! the result with [`crate::formatter::emit`] (or [`ModuleBuilder::emit`]).
+
+use crate::diagnostics::Span;
+
+use super::ast::*;
+
+/// File name of the spans given to built nodes
+pub const GENERATED: &str = "<generated>";
+
+fn span() -> Span {
+    Span::file(GENERATED)
+}
+
+/// Builds a [`Module`]
+#[derive(Debug, Clone)]
+pub struct ModuleBuilder {
+    name: String,
+    doc: Option<String>,
+    items: Vec<Item>,
+}
+
+impl ModuleBuilder {
+    /// A module named by a dotted path, such as `app.models`
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            doc: None,
+            items: Vec::new(),
+        }
+    }
+
+    /// Module doc comment, rendered as `##!` lines
+    pub fn doc(mut self, doc: impl Into<String>) -> Self {
+        self.doc = Some(doc.into());
+        self
+    }
+
+    /// `import path`
+    pub fn import(self, path: &str) -> Self {
+        self.import_kind(path, ImportKind::Module)
+    }
+
+    /// `import path.{A, B}`
+    pub fn import_items(self, path: &str, items: &[&str]) -> Self {
+        let items = items.iter().map(|item| item.to_string()).collect();
+        self.import_kind(path, ImportKind::Items(items))
+    }
+
+    fn import_kind(self, path: &str, kind: ImportKind) -> Self {
+        self.item(Item::Import(ImportDecl {
+            id: NodeId::new(),
+            span: span(),
+            path: module_path(path),
+            kind,
+            public: false,
+            attributes: Vec::new(),
+        }))
+    }
+
+    pub fn function(self, function: FnBuilder) -> Self {
+        self.item(Item::FnDef(function.build()))
+    }
+
+    pub fn test(self, test: TestBuilder) -> Self {
+        self.item(Item::Test(test.build()))
+    }
+
+    /// Any item, for the kinds without a builder
+    pub fn item(mut self, item: Item) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    pub fn build(self) -> Module {
+        Module {
+            id: NodeId::new(),
+            span: span(),
+            name: module_path(&self.name),
+            header: None,
+            doc: self.doc,
+            items: self.items,
+        }
+    }
+
+    /// The module as canonical source
+    pub fn emit(self) -> String {
+        crate::formatter::emit(&self.build())
+    }
+}
+
+fn module_path(path: &str) -> ModulePath {
+    ModulePath {
+        id: NodeId::new(),
+        span: span(),
+        segments: path.split('.').map(str::to_string).collect(),
+    }
+}
+
+/// Builds a function definition
+#[derive(Debug, Clone)]
+pub struct FnBuilder {
+    def: FnDef,
+}
+
+impl FnBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            def: FnDef {
+                id: NodeId::new(),
+                span: span(),
+                visibility: Visibility::Private,
+                is_async: false,
+                name: name.into(),
+                type_params: Vec::new(),
+                type_param_bounds: Vec::new(),
+                params: Vec::new(),
+                return_type: None,
+                effects: Vec::new(),
+                effects_span: None,
+                requires: Vec::new(),
+                ensures: Vec::new(),
+                body: BlockBuilder::new().build(),
+                attributes: Vec::new(),
+                doc: None,
+            },
+        }
+    }
+
+    /// Make the function `public`
+    pub fn public(mut self) -> Self {
+        self.def.visibility = Visibility::Public;
+        self
+    }
+
+    /// Make the function `async`
+    pub fn async_(mut self) -> Self {
+        self.def.is_async = true;
+        self
+    }
+
+    /// Doc comment, rendered as `##` lines
+    pub fn doc(mut self, doc: impl Into<String>) -> Self {
+        self.def.doc = Some(doc.into());
+        self
+    }
+
+    /// An attribute such as `@allow(unused_variable)`
+    pub fn attribute(mut self, name: &str, args: &[&str]) -> Self {
+        self.def.attributes.push(Attribute {
+            span: span(),
+            name: name.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            named_args: Vec::new(),
+        });
+        self
+    }
+
+    pub fn type_param(mut self, name: impl Into<String>) -> Self {
+        self.def.type_params.push(name.into());
+        self
+    }
+
+    pub fn param(mut self, name: impl Into<String>, ty: TypeBuilder) -> Self {
+        self.def.params.push(Param {
+            id: NodeId::new(),
+            span: span(),
+            name: name.into(),
+            ty: ty.build(),
+            pattern: None,
+        });
+        self
+    }
+
+    pub fn returns(mut self, ty: TypeBuilder) -> Self {
+        self.def.return_type = Some(ty.build());
+        self
+    }
+
+    /// Add an effect to the `effects(...)` clause
+    pub fn effect(mut self, name: impl Into<String>) -> Self {
+        self.def.effects.push(name.into());
+        self
+    }
+
+    pub fn requires(mut self, condition: ExprBuilder) -> Self {
+        self.def.requires.push(condition.build());
+        self
+    }
+
+    pub fn ensures(mut self, condition: ExprBuilder) -> Self {
+        self.def.ensures.push(condition.build());
+        self
+    }
+
+    pub fn body(mut self, body: BlockBuilder) -> Self {
+        self.def.body = body.build();
+        self
+    }
+
+    pub fn build(self) -> FnDef {
+        self.def
+    }
+}
+
+/// Builds a `test` block
+#[derive(Debug, Clone)]
+pub struct TestBuilder {
+    name: String,
+    bindings: Vec<EffectBinding>,
+    body: Block,
+}
+
+impl TestBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            bindings: Vec::new(),
+            body: BlockBuilder::new().build(),
+        }
+    }
+
+    /// Bind `effect` to `value` in the test's `using effects(...)` clause
+    pub fn using(mut self, effect: impl Into<String>, value: ExprBuilder) -> Self {
+        self.bindings.push(EffectBinding {
+            id: NodeId::new(),
+            span: span(),
+            effect: effect.into(),
+            value: Box::new(value.build()),
+        });
+        self
+    }
+
+    pub fn body(mut self, body: BlockBuilder) -> Self {
+        self.body = body.build();
+        self
+    }
+
+    pub fn build(self) -> TestBlock {
+        TestBlock {
+            id: NodeId::new(),
+            span: span(),
+            name: self.name,
+            using: (!self.bindings.is_empty()).then(|| UsingClause {
+                id: NodeId::new(),
+                span: span(),
+                bindings: self.bindings,
+            }),
+            body: self.body,
+        }
+    }
+}
+
+/// Builds a block: statements and an optional final value
+#[derive(Debug, Clone, Default)]
+pub struct BlockBuilder {
+    stmts: Vec<Stmt>,
+    value: Option<Expr>,
+}
+
+impl BlockBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `let name = value`
+    pub fn let_(self, name: impl Into<String>, value: ExprBuilder) -> Self {
+        self.binding(name.into(), false, value)
+    }
+
+    /// `let mut name = value`
+    pub fn let_mut(self, name: impl Into<String>, value: ExprBuilder) -> Self {
+        self.binding(name.into(), true, value)
+    }
+
+    fn binding(self, name: String, mutable: bool, value: ExprBuilder) -> Self {
+        self.stmt(Stmt::Let {
+            id: NodeId::new(),
+            span: span(),
+            name,
+            mutable,
+            ty: None,
+            value: Box::new(value.build()),
+            attributes: Vec::new(),
+        })
+    }
+
+    /// `let pattern = value`
+    pub fn let_pattern(self, pattern: PatternBuilder, value: ExprBuilder) -> Self {
+        self.stmt(Stmt::LetPattern {
+            id: NodeId::new(),
+            span: span(),
+            pattern: pattern.build(),
+            ty: None,
+            value: Box::new(value.build()),
+            attributes: Vec::new(),
+        })
+    }
+
+    /// `target = value`
+    pub fn assign(self, target: ExprBuilder, value: ExprBuilder) -> Self {
+        self.stmt(Stmt::Assign {
+            id: NodeId::new(),
+            span: span(),
+            target: Box::new(target.build()),
+            value: Box::new(value.build()),
+        })
+    }
+
+    /// An expression evaluated for its effects
+    pub fn expr(self, expr: ExprBuilder) -> Self {
+        self.stmt(Stmt::Expr {
+            id: NodeId::new(),
+            span: span(),
+            expr: Box::new(expr.build()),
+        })
+    }
+
+    /// `return value`
+    pub fn return_(self, value: Option<ExprBuilder>) -> Self {
+        self.stmt(Stmt::Return {
+            id: NodeId::new(),
+            span: span(),
+            value: value.map(|value| Box::new(value.build())),
+        })
+    }
+
+    /// Any statement
+    pub fn stmt(mut self, stmt: Stmt) -> Self {
+        self.stmts.push(stmt);
+        self
+    }
+
+    /// The value of the block
+    pub fn value(mut self, value: ExprBuilder) -> Self {
+        self.value = Some(value.build());
+        self
+    }
+
+    pub fn build(self) -> Block {
+        Block {
+            id: NodeId::new(),
+            span: span(),
+            stmts: self.stmts,
+            expr: self.value.map(Box::new),
+        }
+    }
+}
+
+/// Builds an expression. Constructors make leaves and compound forms;
+/// methods and the arithmetic operators wrap the expression built so far, so
+/// `(a + b).method("abs", [])` builds `(a + b).abs()`.
+#[derive(Debug, Clone)]
+pub struct ExprBuilder(Expr);
+
+impl From<ExprBuilder> for Expr {
+    fn from(builder: ExprBuilder) -> Self {
+        builder.0
+    }
+}
+
+impl From<Expr> for ExprBuilder {
+    fn from(expr: Expr) -> Self {
+        ExprBuilder(expr)
+    }
+}
+
+fn exprs(items: impl IntoIterator<Item = ExprBuilder>) -> Vec<Expr> {
+    items.into_iter().map(ExprBuilder::build).collect()
+}
+
+impl ExprBuilder {
+    pub fn int(value: i64) -> Self {
+        Self(Expr::IntLit {
+            id: NodeId::new(),
+            span: span(),
+            value,
+        })
+    }
+
+    pub fn float(value: f64) -> Self {
+        Self(Expr::FloatLit {
+            id: NodeId::new(),
+            span: span(),
+            value,
+        })
+    }
+
+    pub fn bool(value: bool) -> Self {
+        Self(Expr::BoolLit {
+            id: NodeId::new(),
+            span: span(),
+            value,
+        })
+    }
+
+    pub fn text(value: impl Into<String>) -> Self {
+        Self(Expr::TextLit {
+            id: NodeId::new(),
+            span: span(),
+            value: value.into(),
+        })
+    }
+
+    /// `()`
+    pub fn unit() -> Self {
+        Self(Expr::UnitLit {
+            id: NodeId::new(),
+            span: span(),
+        })
+    }
+
+    /// `???`
+    pub fn hole() -> Self {
+        Self(Expr::Hole {
+            id: NodeId::new(),
+            span: span(),
+        })
+    }
+
+    /// A name: a local, parameter, function, variant, or effect
+    pub fn ident(name: impl Into<String>) -> Self {
+        Self(Expr::Ident {
+            id: NodeId::new(),
+            span: span(),
+            name: name.into(),
+        })
+    }
+
+    pub fn list(elements: impl IntoIterator<Item = ExprBuilder>) -> Self {
+        Self(Expr::ListLit {
+            id: NodeId::new(),
+            span: span(),
+            elements: exprs(elements),
+        })
+    }
+
+    pub fn tuple(elements: impl IntoIterator<Item = ExprBuilder>) -> Self {
+        Self(Expr::TupleLit {
+            id: NodeId::new(),
+            span: span(),
+            elements: exprs(elements),
+        })
+    }
+
+    /// `{ name = value, ... }`
+    pub fn record<'a>(fields: impl IntoIterator<Item = (&'a str, ExprBuilder)>) -> Self {
+        Self(Expr::Record {
+            id: NodeId::new(),
+            span: span(),
+            fields: fields
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), Box::new(value.build())))
+                .collect(),
+        })
+    }
+
+    /// `fn(params) { body }`, with parameter types left to inference
+    pub fn lambda(params: &[&str], body: BlockBuilder) -> Self {
+        Self(Expr::Lambda {
+            id: NodeId::new(),
+            span: span(),
+            params: params
+                .iter()
+                .map(|name| LambdaParam {
+                    id: NodeId::new(),
+                    span: span(),
+                    name: name.to_string(),
+                    ty: None,
+                })
+                .collect(),
+            return_type: None,
+            body: Box::new(body.build()),
+        })
+    }
+
+    /// `if cond { then }`, or with an `else` block
+    pub fn if_(cond: ExprBuilder, then: BlockBuilder, otherwise: Option<BlockBuilder>) -> Self {
+        Self(Expr::If {
+            id: NodeId::new(),
+            span: span(),
+            cond: Box::new(cond.build()),
+            then_branch: Box::new(then.build()),
+            else_branch: otherwise.map(|block| Box::new(Self::block(block).build())),
+        })
+    }
+
+    /// `match scrutinee { pattern => body, ... }`
+    pub fn match_(
+        scrutinee: ExprBuilder,
+        arms: impl IntoIterator<Item = (PatternBuilder, ExprBuilder)>,
+    ) -> Self {
+        Self(Expr::Match {
+            id: NodeId::new(),
+            span: span(),
+            expr: Box::new(scrutinee.build()),
+            arms: arms
+                .into_iter()
+                .map(|(pattern, body)| MatchArm {
+                    id: NodeId::new(),
+                    span: span(),
+                    pattern: pattern.build(),
+                    guard: None,
+                    body: Box::new(body.build()),
+                })
+                .collect(),
+        })
+    }
+
+    /// `for binding in iter { body }`
+    pub fn for_in(binding: impl Into<String>, iter: ExprBuilder, body: BlockBuilder) -> Self {
+        Self(Expr::ForIn {
+            id: NodeId::new(),
+            span: span(),
+            binding: binding.into(),
+            pattern: None,
+            iter: Box::new(iter.build()),
+            body: Box::new(body.build()),
+        })
+    }
+
+    /// `forall binding in iter: body` or `exists binding in iter: body`
+    pub fn quantifier(
+        kind: QuantifierKind,
+        binding: impl Into<String>,
+        iter: ExprBuilder,
+        body: ExprBuilder,
+    ) -> Self {
+        Self(Expr::Quantifier {
+            id: NodeId::new(),
+            span: span(),
+            kind,
+            binding: binding.into(),
+            iter: Box::new(iter.build()),
+            body: Box::new(body.build()),
+        })
+    }
+
+    /// `while cond { body }`
+    pub fn while_(cond: ExprBuilder, body: BlockBuilder) -> Self {
+        Self(Expr::While {
+            id: NodeId::new(),
+            span: span(),
+            cond: Box::new(cond.build()),
+            body: Box::new(body.build()),
+        })
+    }
+
+    /// A block used as an expression
+    pub fn block(block: BlockBuilder) -> Self {
+        Self(Expr::Block {
+            id: NodeId::new(),
+            span: span(),
+            block: Box::new(block.build()),
+        })
+    }
+
+    /// `self(args)`
+    pub fn call(self, args: impl IntoIterator<Item = ExprBuilder>) -> Self {
+        Self(Expr::Call {
+            id: NodeId::new(),
+            span: span(),
+            func: Box::new(self.0),
+            args: exprs(args),
+        })
+    }
+
+    /// `self.method(args)`
+    pub fn method(
+        self,
+        method: impl Into<String>,
+        args: impl IntoIterator<Item = ExprBuilder>,
+    ) -> Self {
+        Self(Expr::MethodCall {
+            id: NodeId::new(),
+            span: span(),
+            receiver: Box::new(self.0),
+            method: method.into(),
+            args: exprs(args),
+            method_span: None,
+        })
+    }
+
+    /// `self.field`
+    pub fn field(self, field: impl Into<String>) -> Self {
+        Self(Expr::FieldAccess {
+            id: NodeId::new(),
+            span: span(),
+            expr: Box::new(self.0),
+            field: field.into(),
+            field_span: None,
+        })
+    }
+
+    /// `self[index]`
+    pub fn index(self, index: ExprBuilder) -> Self {
+        Self(Expr::IndexAccess {
+            id: NodeId::new(),
+            span: span(),
+            expr: Box::new(self.0),
+            index: Box::new(index.build()),
+        })
+    }
+
+    /// `self?`
+    pub fn try_(self) -> Self {
+        Self(Expr::Try {
+            id: NodeId::new(),
+            span: span(),
+            expr: Box::new(self.0),
+        })
+    }
+
+    /// `self ?else fallback`
+    pub fn or_else(self, fallback: ExprBuilder) -> Self {
+        Self(Expr::TryElse {
+            id: NodeId::new(),
+            span: span(),
+            expr: Box::new(self.0),
+            else_expr: Box::new(fallback.build()),
+        })
+    }
+
+    /// `await self`
+    pub fn await_(self) -> Self {
+        Self(Expr::Await {
+            id: NodeId::new(),
+            span: span(),
+            expr: Box::new(self.0),
+        })
+    }
+
+    pub fn unary(self, op: UnaryOp) -> Self {
+        Self(Expr::Unary {
+            id: NodeId::new(),
+            span: span(),
+            op,
+            expr: Box::new(self.0),
+        })
+    }
+
+    /// `self op right`
+    pub fn binary(self, op: BinaryOp, right: ExprBuilder) -> Self {
+        Self(Expr::Binary {
+            id: NodeId::new(),
+            span: span(),
+            op,
+            left: Box::new(self.0),
+            right: Box::new(right.build()),
+        })
+    }
+
+    pub fn eq(self, right: ExprBuilder) -> Self {
+        self.binary(BinaryOp::Eq, right)
+    }
+
+    pub fn lt(self, right: ExprBuilder) -> Self {
+        self.binary(BinaryOp::Lt, right)
+    }
+
+    pub fn gt(self, right: ExprBuilder) -> Self {
+        self.binary(BinaryOp::Gt, right)
+    }
+
+    pub fn and(self, right: ExprBuilder) -> Self {
+        self.binary(BinaryOp::And, right)
+    }
+
+    pub fn or(self, right: ExprBuilder) -> Self {
+        self.binary(BinaryOp::Or, right)
+    }
+
+    /// `self |> function`
+    pub fn pipe(self, function: ExprBuilder) -> Self {
+        self.binary(BinaryOp::Pipe, function)
+    }
+
+    pub fn build(self) -> Expr {
+        self.0
+    }
+}
+
+/// Arithmetic operators build binary expressions: `a + b` builds `a + b`
+macro_rules! binary_operator {
+    ($trait:ident, $method:ident, $op:ident) => {
+        impl std::ops::$trait for ExprBuilder {
+            type Output = ExprBuilder;
+
+            fn $method(self, right: ExprBuilder) -> ExprBuilder {
+                self.binary(BinaryOp::$op, right)
+            }
+        }
+    };
+}
+
+binary_operator!(Add, add, Add);
+binary_operator!(Sub, sub, Sub);
+binary_operator!(Mul, mul, Mul);
+binary_operator!(Div, div, Div);
+binary_operator!(Rem, rem, Mod);
+
+/// Builds a type expression
+#[derive(Debug, Clone)]
+pub struct TypeBuilder(TypeExpr);
+
+impl TypeBuilder {
+    /// A type by name, such as `Int` or `User`
+    pub fn named(name: impl Into<String>) -> Self {
+        Self::generic(name, [])
+    }
+
+    /// A type applied to arguments, such as `List[Int]`
+    pub fn generic(name: impl Into<String>, args: impl IntoIterator<Item = TypeBuilder>) -> Self {
+        Self(TypeExpr::Named {
+            id: NodeId::new(),
+            span: span(),
+            name: name.into(),
+            args: args.into_iter().map(TypeBuilder::build).collect(),
+        })
+    }
+
+    /// `(A, B)`
+    pub fn tuple(elements: impl IntoIterator<Item = TypeBuilder>) -> Self {
+        Self(TypeExpr::Tuple {
+            id: NodeId::new(),
+            span: span(),
+            elements: elements.into_iter().map(TypeBuilder::build).collect(),
+        })
+    }
+
+    /// `(A, B) -> R`
+    pub fn function(params: impl IntoIterator<Item = TypeBuilder>, ret: TypeBuilder) -> Self {
+        Self(TypeExpr::Function {
+            id: NodeId::new(),
+            span: span(),
+            params: params.into_iter().map(TypeBuilder::build).collect(),
+            ret: Box::new(ret.build()),
+            effects: Vec::new(),
+        })
+    }
+
+    pub fn build(self) -> TypeExpr {
+        self.0
+    }
+}
+
+/// Builds a pattern
+#[derive(Debug, Clone)]
+pub struct PatternBuilder(Pattern);
+
+impl PatternBuilder {
+    /// `_`
+    pub fn wildcard() -> Self {
+        Self(Pattern::Wildcard {
+            id: NodeId::new(),
+            span: span(),
+        })
+    }
+
+    /// A binding
+    pub fn ident(name: impl Into<String>) -> Self {
+        Self(Pattern::Ident {
+            id: NodeId::new(),
+            span: span(),
+            name: name.into(),
+        })
+    }
+
+    pub fn int(value: i64) -> Self {
+        Self(Pattern::IntLit {
+            id: NodeId::new(),
+            span: span(),
+            value,
+        })
+    }
+
+    pub fn text(value: impl Into<String>) -> Self {
+        Self(Pattern::TextLit {
+            id: NodeId::new(),
+            span: span(),
+            value: value.into(),
+        })
+    }
+
+    pub fn bool(value: bool) -> Self {
+        Self(Pattern::BoolLit {
+            id: NodeId::new(),
+            span: span(),
+            value,
+        })
+    }
+
+    /// `Name(fields)`, or `Name` without fields
+    pub fn variant(
+        name: impl Into<String>,
+        fields: impl IntoIterator<Item = PatternBuilder>,
+    ) -> Self {
+        Self(Pattern::Variant {
+            id: NodeId::new(),
+            span: span(),
+            name: name.into(),
+            fields: fields.into_iter().map(PatternBuilder::build).collect(),
+        })
+    }
+
+    /// `(a, b)`
+    pub fn tuple(elements: impl IntoIterator<Item = PatternBuilder>) -> Self {
+        Self(Pattern::Tuple {
+            id: NodeId::new(),
+            span: span(),
+            elements: elements.into_iter().map(PatternBuilder::build).collect(),
+        })
+    }
+
+    /// `{ name = pattern, ... }`
+    pub fn record<'a>(fields: impl IntoIterator<Item = (&'a str, PatternBuilder)>) -> Self {
+        Self(Pattern::Record {
+            id: NodeId::new(),
+            span: span(),
+            fields: fields
+                .into_iter()
+                .map(|(name, pattern)| (name.to_string(), pattern.build()))
+                .collect(),
+            rest: false,
+        })
+    }
+
+    /// `{ name = pattern, ..., .. }`, ignoring fields not listed
+    pub fn record_rest<'a>(fields: impl IntoIterator<Item = (&'a str, PatternBuilder)>) -> Self {
+        let mut pattern = Self::record(fields);
+        if let Pattern::Record { rest, .. } = &mut pattern.0 {
+            *rest = true;
+        }
+        pattern
+    }
+
+    pub fn build(self) -> Pattern {
+        self.0
+    }
+}
+
+#[cfg(test)]
+#[path = "builder_tests.rs"]
+mod tests;
@@ -0,0 +1,125 @@
+use super::*;
+use crate::embed::{run, CapabilityConfig};
+
+fn int() -> TypeBuilder {
+    TypeBuilder::named("Int")
+}
+
+fn program() -> ModuleBuilder {
+    use ExprBuilder as E;
+    let classify = FnBuilder::new("classify")
+        .doc("Sign of a number")
+        .param("n", int())
+        .returns(TypeBuilder::named("Text"))
+        .body(BlockBuilder::new().value(E::match_(
+            E::ident("n").lt(E::int(0)),
+            [
+                (PatternBuilder::bool(true), E::text("negative")),
+                (PatternBuilder::wildcard(), E::text("non-negative")),
+            ],
+        )));
+    let sum = FnBuilder::new("sum")
+        .public()
+        .param("xs", TypeBuilder::generic("List", [int()]))
+        .returns(int())
+        .ensures(E::ident("result").gt(E::int(-1)).or(E::bool(true)))
+        .body(
+            BlockBuilder::new()
+                .let_mut("total", E::int(0))
+                .expr(E::for_in(
+                    "x",
+                    E::ident("xs"),
+                    BlockBuilder::new()
+                        .assign(E::ident("total"), E::ident("total") + E::ident("x")),
+                ))
+                .value(E::ident("total")),
+        );
+    let main = FnBuilder::new("main").effect("Console").body(
+        BlockBuilder::new()
+            .let_("xs", E::list([E::int(1), E::int(2), E::int(3)]))
+            .expr(E::ident("Console").method(
+                "println",
+                [E::ident("to_text").call([E::ident("sum").call([E::ident("xs")])])],
+            ))
+            .expr(E::ident("Console").method(
+                "println",
+                [E::ident("classify").call([E::int(0) - E::int(2)])],
+            )),
+    );
+    let test =
+        TestBuilder::new("sum adds").body(BlockBuilder::new().expr(E::ident("assert_eq").call([
+            E::ident("sum").call([E::list([E::int(2), E::int(2)])]),
+            E::int(4),
+        ])));
+    ModuleBuilder::new("generated.stats")
+        .doc("Built in code")
+        .import("std.math")
+        .function(classify)
+        .function(sum)
+        .function(main)
+        .test(test)
+}
+
+#[test]
+fn test_built_module_emits_canonical_source() {
+    let source = program().emit();
+    assert!(
+        source.starts_with("##! Built in code\nmodule generated.stats\n"),
+        "{}",
+        source
+    );
+    assert!(source.contains("import std.math\n"), "{}", source);
+    assert!(
+        source.contains("## Sign of a number\nfn classify(n: Int) -> Text"),
+        "{}",
+        source
+    );
+    assert!(
+        source.contains("public fn sum(xs: List[Int]) -> Int"),
+        "{}",
+        source
+    );
+    assert!(
+        source.contains("  ensures result > -1 or true\n"),
+        "{}",
+        source
+    );
+    assert!(source.contains("    total = total + x\n"), "{}", source);
+    assert!(source.contains("test \"sum adds\" {"), "{}", source);
+
+    let parsed = crate::parser::parse_source(&source, std::path::Path::new("gen.astra")).unwrap();
+    assert_eq!(crate::formatter::emit(&parsed), source);
+}
+
+#[test]
+fn test_built_module_runs() {
+    let config = CapabilityConfig {
+        console: true,
+        ..Default::default()
+    };
+    let result = run(&program().emit(), 0, &config);
+    assert!(result.ok, "{:?}", result);
+    assert_eq!(result.output, "6\nnegative\n");
+}
+
+#[test]
+fn test_nodes_get_fresh_ids_and_synthetic_spans() {
+    let a = ExprBuilder::ident("a").build();
+    let b = ExprBuilder::ident("a").build();
+    assert_ne!(a.id(), b.id());
+    assert_eq!(a.span().file, std::path::PathBuf::from(GENERATED));
+
+    let call = ExprBuilder::ident("f")
+        .call([ExprBuilder::int(1)])
+        .method("abs", [])
+        .build();
+    assert_eq!(
+        crate::formatter::Formatter::new().expr_text(&call),
+        "f(1).abs()"
+    );
+    let sum = ((ExprBuilder::int(1) + ExprBuilder::int(2)) * ExprBuilder::int(3)).build();
+    assert_eq!(
+        crate::formatter::Formatter::new().expr_text(&sum),
+        "(1 + 2) * 3"
+    );
+}
@@ -0,0 +1,1001 @@
+//! Lossless concrete syntax tree.
+//!
+//! The AST drops what tools that rewrite source need to keep: whitespace,
+//! comments, punctuation, and the exact spelling of every token. The CST
+//! keeps all of it, so printing a tree reproduces its source byte for byte.
+//!
+//! Trees come in two layers. Green nodes ([`GreenNode`], [`GreenToken`]) are
+//! immutable, reference-counted, and know only their kind, text, and
+//! children; an edit builds new green nodes along the path to the root and
+//! shares everything else. Red nodes ([`SyntaxNode`], [`SyntaxToken`]) wrap
+//! green ones with a parent and an offset, for navigation.
+//!
+//! Every token of the source is a leaf: trivia (whitespace and comments)
+//! become tokens of their own, placed before the token they precede. A
+//! node's leading trivia belongs to its parent. Nodes are the AST's items,
+//! statements, expressions, patterns, and types, nested by their spans, so a
+//! source the parser rejects still yields a tree: a flat [`SyntaxKind::Module`]
+//! of tokens, alongside the parse errors.
+
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+
+use super::ast::*;
+use super::lexer::{tokenize_lossless, TokenKind, TriviaKind};
+use super::parser::Parser;
+use super::span::SourceFile;
+use super::Lexer;
+use crate::diagnostics::{Diagnostic, Span};
+
+/// Kind of a node or token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    // Items
+    Module,
+    ModuleName,
+    Import,
+    TypeDef,
+    EnumDef,
+    Variant,
+    Field,
+    TraitDef,
+    TraitMethod,
+    ImplBlock,
+    EffectDef,
+    MacroDef,
+    MacroRule,
+    FnDef,
+    Attribute,
+    Param,
+    Test,
+    Property,
+    TestHook,
+    UsingClause,
+    EffectBinding,
+    TypeExpr,
+    // Statements
+    Block,
+    LetStmt,
+    AssignStmt,
+    ExprStmt,
+    ReturnStmt,
+    // Expressions
+    Literal,
+    NameRef,
+    RecordExpr,
+    FieldExpr,
+    BinaryExpr,
+    UnaryExpr,
+    CallExpr,
+    MethodCallExpr,
+    IfExpr,
+    MatchExpr,
+    MatchArm,
+    BlockExpr,
+    TryExpr,
+    ListExpr,
+    TupleExpr,
+    MapExpr,
+    LambdaExpr,
+    LambdaParam,
+    ForExpr,
+    WhileExpr,
+    BreakExpr,
+    ContinueExpr,
+    InterpolatedText,
+    RangeExpr,
+    IndexExpr,
+    AwaitExpr,
+    QuantifierExpr,
+    Hole,
+    MacroCall,
+    Pattern,
+    // Tokens
+    /// A token of the language; see [`SyntaxToken::token_kind`]
+    Token,
+    Whitespace,
+    Comment,
+    DocComment,
+    /// Input the lexer could not recognize
+    Error,
+}
+
+impl SyntaxKind {
+    /// Whitespace and comments
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::Whitespace | SyntaxKind::Comment | SyntaxKind::DocComment
+        )
+    }
+}
+
+/// An immutable leaf: a token or trivia with its exact text
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenToken(Rc<GreenTokenData>);
+
+#[derive(Debug, PartialEq)]
+struct GreenTokenData {
+    kind: SyntaxKind,
+    token: Option<TokenKind>,
+    text: String,
+}
+
+impl GreenToken {
+    /// A leaf of `kind`; `token` is the lexer's kind for [`SyntaxKind::Token`]
+    pub fn new(kind: SyntaxKind, token: Option<TokenKind>, text: impl Into<String>) -> Self {
+        Self(Rc::new(GreenTokenData {
+            kind,
+            token,
+            text: text.into(),
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.kind
+    }
+
+    pub fn token_kind(&self) -> Option<&TokenKind> {
+        self.0.token.as_ref()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.0.text
+    }
+}
+
+/// A child of a green node
+#[derive(Debug, Clone, PartialEq)]
+pub enum GreenElement {
+    Node(GreenNode),
+    Token(GreenToken),
+}
+
+impl GreenElement {
+    fn width(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.width(),
+            GreenElement::Token(token) => token.text().len(),
+        }
+    }
+}
+
+/// An immutable interior node
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenNode(Rc<GreenNodeData>);
+
+#[derive(Debug, PartialEq)]
+struct GreenNodeData {
+    kind: SyntaxKind,
+    width: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        let width = children.iter().map(GreenElement::width).sum();
+        Self(Rc::new(GreenNodeData {
+            kind,
+            width,
+            children,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.kind
+    }
+
+    /// Length of the node's text in bytes
+    pub fn width(&self) -> usize {
+        self.0.width
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.0.children
+    }
+
+    /// A copy of this node with child `index` replaced
+    pub fn replace_child(&self, index: usize, child: GreenElement) -> GreenNode {
+        let mut children = self.0.children.clone();
+        children[index] = child;
+        GreenNode::new(self.kind(), children)
+    }
+
+    /// A copy of this node with `children` spliced in place of `range`
+    pub fn splice_children(&self, range: Range<usize>, children: Vec<GreenElement>) -> GreenNode {
+        let mut all = self.0.children.clone();
+        all.splice(range, children);
+        GreenNode::new(self.kind(), all)
+    }
+}
+
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in self.children() {
+            match child {
+                GreenElement::Node(node) => write!(f, "{}", node)?,
+                GreenElement::Token(token) => f.write_str(token.text())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A node positioned in a tree
+#[derive(Debug, Clone)]
+pub struct SyntaxNode(Rc<SyntaxNodeData>);
+
+#[derive(Debug)]
+struct SyntaxNodeData {
+    green: GreenNode,
+    parent: Option<SyntaxNode>,
+    /// Position among the parent's children
+    index: usize,
+    offset: usize,
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0.green.0, &other.0.green.0) && self.0.offset == other.0.offset
+    }
+}
+
+impl SyntaxNode {
+    /// The root of a tree
+    pub fn new_root(green: GreenNode) -> Self {
+        Self(Rc::new(SyntaxNodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &GreenNode {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<&SyntaxNode> {
+        self.0.parent.as_ref()
+    }
+
+    /// Position among the parent's children, tokens included
+    pub fn index(&self) -> usize {
+        self.0.index
+    }
+
+    /// Byte range of the node's text in the source
+    pub fn text_range(&self) -> Range<usize> {
+        self.0.offset..self.0.offset + self.0.green.width()
+    }
+
+    /// The node's exact source text, trivia included
+    pub fn text(&self) -> String {
+        self.0.green.to_string()
+    }
+
+    /// Child nodes and tokens, in order
+    pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut elements = Vec::new();
+        for (index, child) in self.0.green.children().iter().enumerate() {
+            elements.push(match child {
+                GreenElement::Node(green) => {
+                    SyntaxElement::Node(SyntaxNode(Rc::new(SyntaxNodeData {
+                        green: green.clone(),
+                        parent: Some(self.clone()),
+                        index,
+                        offset,
+                    })))
+                }
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    parent: self.clone(),
+                    index,
+                    offset,
+                }),
+            });
+            offset += child.width();
+        }
+        elements
+    }
+
+    /// Child nodes, in order
+    pub fn children(&self) -> Vec<SyntaxNode> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|element| match element {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    /// This node and every node below it, in pre-order
+    pub fn descendants(&self) -> Vec<SyntaxNode> {
+        let mut nodes = vec![self.clone()];
+        for child in self.children() {
+            nodes.extend(child.descendants());
+        }
+        nodes
+    }
+
+    /// Every token below this node, trivia included, in source order
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    /// The innermost node whose text covers `range`
+    pub fn covering_node(&self, range: Range<usize>) -> SyntaxNode {
+        let inner = self.children().into_iter().find(|child| {
+            let text = child.text_range();
+            text.start <= range.start && range.end <= text.end
+        });
+        match inner {
+            Some(child) => child.covering_node(range),
+            None => self.clone(),
+        }
+    }
+
+    /// The root of the tree after replacing this node with `replacement`
+    pub fn replace_with(&self, replacement: GreenNode) -> GreenNode {
+        match self.parent() {
+            Some(parent) => parent.replace_with(
+                parent
+                    .green()
+                    .replace_child(self.index(), GreenElement::Node(replacement)),
+            ),
+            None => replacement,
+        }
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.green.fmt(f)
+    }
+}
+
+/// A token positioned in a tree
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    green: GreenToken,
+    parent: SyntaxNode,
+    index: usize,
+    offset: usize,
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind()
+    }
+
+    /// The lexer's kind, for tokens that aren't trivia or errors
+    pub fn token_kind(&self) -> Option<&TokenKind> {
+        self.green.token_kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn text_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.text().len()
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        self.kind().is_trivia()
+    }
+
+    pub fn green(&self) -> &GreenToken {
+        &self.green
+    }
+
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+
+    /// The root of the tree after replacing this token with `replacement`
+    pub fn replace_with(&self, replacement: GreenToken) -> GreenNode {
+        self.parent.replace_with(
+            self.parent
+                .green()
+                .replace_child(self.index, GreenElement::Token(replacement)),
+        )
+    }
+}
+
+/// A node or a token
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind(),
+            SyntaxElement::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn text_range(&self) -> Range<usize> {
+        match self {
+            SyntaxElement::Node(node) => node.text_range(),
+            SyntaxElement::Token(token) => token.text_range(),
+        }
+    }
+}
+
+/// A concrete syntax tree and the errors met parsing it
+#[derive(Debug, Clone)]
+pub struct Parse {
+    pub root: SyntaxNode,
+    /// Parse errors; the tree is flat when there are any
+    pub errors: Vec<Diagnostic>,
+}
+
+/// Parse `source` into a concrete syntax tree
+pub fn parse(source: &str, path: &Path) -> Parse {
+    let source_file = SourceFile::new(path.to_path_buf(), source.to_string());
+    let mut parser = Parser::new(Lexer::new(&source_file), source_file.clone());
+    let (nodes, errors) = match parser.parse_module() {
+        Ok(module) => {
+            let mut collector = Collector::default();
+            collector.module(&module);
+            (collector.nodes, Vec::new())
+        }
+        Err(bag) => (Vec::new(), bag.diagnostics().to_vec()),
+    };
+    Parse {
+        root: SyntaxNode::new_root(build(&source_file, nodes)),
+        errors,
+    }
+}
+
+/// A lexical token with the trivia before it
+struct Unit {
+    trivia: Vec<GreenToken>,
+    /// `None` for the end of input, which only carries trailing trivia
+    token: Option<GreenToken>,
+    start: usize,
+    end: usize,
+}
+
+/// Build the green tree of a source from the spans of its AST nodes
+fn build(source: &SourceFile, nodes: Vec<(SyntaxKind, Span)>) -> GreenNode {
+    let units: Vec<Unit> = tokenize_lossless(source)
+        .into_iter()
+        .map(|token| Unit {
+            trivia: token
+                .leading_trivia
+                .into_iter()
+                .map(|trivia| {
+                    let kind = match trivia.kind {
+                        TriviaKind::Whitespace => SyntaxKind::Whitespace,
+                        TriviaKind::LineComment | TriviaKind::Shebang => SyntaxKind::Comment,
+                        TriviaKind::DocComment => SyntaxKind::DocComment,
+                    };
+                    GreenToken::new(kind, None, trivia.text)
+                })
+                .collect(),
+            token: match token.kind {
+                Some(TokenKind::Eof) => None,
+                Some(kind) => Some(GreenToken::new(SyntaxKind::Token, Some(kind), token.text)),
+                None => Some(GreenToken::new(SyntaxKind::Error, None, token.text)),
+            },
+            start: token.span.start,
+            end: token.span.end,
+        })
+        .collect();
+
+    // Each node as the range of units it covers, outer nodes first
+    let mut ranges: Vec<(SyntaxKind, usize, usize)> = nodes
+        .into_iter()
+        .filter_map(|(kind, span)| {
+            let first = units.iter().position(|u| u.start >= span.start)?;
+            let last = units[first..]
+                .iter()
+                .take_while(|u| u.token.is_some() && u.end <= span.end)
+                .count();
+            (last > 0).then_some((kind, first, first + last))
+        })
+        .collect();
+    ranges.sort_by_key(|&(_, first, last)| (first, std::cmp::Reverse(last)));
+
+    let mut next = 0;
+    let children = build_children(&units, &ranges, &mut next, 0, units.len(), true);
+    GreenNode::new(SyntaxKind::Module, children)
+}
+
+/// Children covering units `lo..hi`, consuming the node ranges inside them.
+/// The trivia of unit `lo` goes to the caller unless `with_leading_trivia`.
+fn build_children(
+    units: &[Unit],
+    ranges: &[(SyntaxKind, usize, usize)],
+    next: &mut usize,
+    lo: usize,
+    hi: usize,
+    with_leading_trivia: bool,
+) -> Vec<GreenElement> {
+    let mut children = Vec::new();
+    let mut at = lo;
+    while at < hi {
+        // Ranges that start inside an earlier sibling or cross this node's
+        // end can't nest: drop them
+        while let Some(&(_, first, last)) = ranges.get(*next) {
+            if first < at || (first == at && last > hi) {
+                *next += 1;
+            } else {
+                break;
+            }
+        }
+        if at > lo || with_leading_trivia {
+            children.extend(units[at].trivia.iter().cloned().map(GreenElement::Token));
+        }
+        match ranges.get(*next) {
+            Some(&(kind, first, last)) if first == at => {
+                *next += 1;
+                let inner = build_children(units, ranges, next, first, last, false);
+                children.push(GreenElement::Node(GreenNode::new(kind, inner)));
+                at = last;
+            }
+            _ => {
+                if let Some(token) = &units[at].token {
+                    children.push(GreenElement::Token(token.clone()));
+                }
+                at += 1;
+            }
+        }
+    }
+    children
+}
+
+/// Collects the kind and span of every AST node, parents before children
+#[derive(Default)]
+struct Collector {
+    nodes: Vec<(SyntaxKind, Span)>,
+}
+
+impl Collector {
+    fn push(&mut self, kind: SyntaxKind, span: &Span) {
+        self.nodes.push((kind, span.clone()));
+    }
+
+    fn module(&mut self, module: &Module) {
+        self.push(SyntaxKind::ModuleName, &module.name.span);
+        for item in &module.items {
+            for attribute in item.attributes() {
+                self.push(SyntaxKind::Attribute, &attribute.span);
+            }
+            match item {
+                Item::Import(import) => {
+                    self.push(SyntaxKind::Import, &import.span);
+                    self.push(SyntaxKind::ModuleName, &import.path.span);
+                }
+                Item::TypeDef(def) => {
+                    self.push(SyntaxKind::TypeDef, &def.span);
+                    self.type_expr(&def.value);
+                    if let Some(invariant) = &def.invariant {
+                        self.expr(invariant);
+                    }
+                }
+                Item::EnumDef(def) => {
+                    self.push(SyntaxKind::EnumDef, &def.span);
+                    for variant in &def.variants {
+                        self.push(SyntaxKind::Variant, &variant.span);
+                        self.fields(&variant.fields);
+                    }
+                }
+                Item::FnDef(def) => self.fn_def(def),
+                Item::TraitDef(def) => {
+                    self.push(SyntaxKind::TraitDef, &def.span);
+                    for method in &def.methods {
+                        self.trait_method(method);
+                    }
+                }
+                Item::ImplBlock(block) => {
+                    self.push(SyntaxKind::ImplBlock, &block.span);
+                    self.type_expr(&block.target_type);
+                    for method in &block.methods {
+                        self.fn_def(method);
+                    }
+                }
+                Item::EffectDef(def) => {
+                    self.push(SyntaxKind::EffectDef, &def.span);
+                    for operation in &def.operations {
+                        self.trait_method(operation);
+                    }
+                }
+                Item::Test(test) => {
+                    self.push(SyntaxKind::Test, &test.span);
+                    self.using(test.using.as_ref());
+                    self.block(&test.body);
+                }
+                Item::Property(property) => {
+                    self.push(SyntaxKind::Property, &property.span);
+                    self.using(property.using.as_ref());
+                    self.block(&property.body);
+                }
+                Item::TestHook(hook) => {
+                    self.push(SyntaxKind::TestHook, &hook.span);
+                    self.using(hook.using.as_ref());
+                    self.block(&hook.body);
+                }
+                Item::MacroDef(def) => {
+                    self.push(SyntaxKind::MacroDef, &def.span);
+                    for rule in &def.rules {
+                        self.push(SyntaxKind::MacroRule, &rule.span);
+                        self.expr(&rule.template);
+                    }
+                }
+            }
+        }
+    }
+
+    fn fn_def(&mut self, def: &FnDef) {
+        self.push(SyntaxKind::FnDef, &def.span);
+        self.params(&def.params);
+        if let Some(ty) = &def.return_type {
+            self.type_expr(ty);
+        }
+        for clause in def.requires.iter().chain(&def.ensures) {
+            self.expr(clause);
+        }
+        self.block(&def.body);
+    }
+
+    fn trait_method(&mut self, method: &TraitMethod) {
+        self.push(SyntaxKind::TraitMethod, &method.span);
+        self.params(&method.params);
+        if let Some(ty) = &method.return_type {
+            self.type_expr(ty);
+        }
+        for clause in method.requires.iter().chain(&method.ensures) {
+            self.expr(clause);
+        }
+    }
+
+    fn params(&mut self, params: &[Param]) {
+        for param in params {
+            self.push(SyntaxKind::Param, &param.span);
+            if let Some(pattern) = &param.pattern {
+                self.pattern(pattern);
+            }
+            self.type_expr(&param.ty);
+        }
+    }
+
+    fn fields(&mut self, fields: &[Field]) {
+        for field in fields {
+            self.push(SyntaxKind::Field, &field.span);
+            self.type_expr(&field.ty);
+        }
+    }
+
+    fn using(&mut self, using: Option<&UsingClause>) {
+        if let Some(using) = using {
+            self.push(SyntaxKind::UsingClause, &using.span);
+            for binding in &using.bindings {
+                self.push(SyntaxKind::EffectBinding, &binding.span);
+                self.expr(&binding.value);
+            }
+        }
+    }
+
+    fn type_expr(&mut self, ty: &TypeExpr) {
+        match ty {
+            TypeExpr::Named { span, args, .. } => {
+                self.push(SyntaxKind::TypeExpr, span);
+                for arg in args {
+                    self.type_expr(arg);
+                }
+            }
+            TypeExpr::Record { span, fields, .. } => {
+                self.push(SyntaxKind::TypeExpr, span);
+                self.fields(fields);
+            }
+            TypeExpr::Function {
+                span, params, ret, ..
+            } => {
+                self.push(SyntaxKind::TypeExpr, span);
+                for param in params {
+                    self.type_expr(param);
+                }
+                self.type_expr(ret);
+            }
+            TypeExpr::Tuple { span, elements, .. } => {
+                self.push(SyntaxKind::TypeExpr, span);
+                for element in elements {
+                    self.type_expr(element);
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.push(SyntaxKind::Block, &block.span);
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Let {
+                span,
+                ty,
+                value,
+                attributes,
+                ..
+            } => {
+                for attribute in attributes {
+                    self.push(SyntaxKind::Attribute, &attribute.span);
+                }
+                self.push(SyntaxKind::LetStmt, span);
+                if let Some(ty) = ty {
+                    self.type_expr(ty);
+                }
+                self.expr(value);
+            }
+            Stmt::LetPattern {
+                span,
+                pattern,
+                ty,
+                value,
+                attributes,
+                ..
+            } => {
+                for attribute in attributes {
+                    self.push(SyntaxKind::Attribute, &attribute.span);
+                }
+                self.push(SyntaxKind::LetStmt, span);
+                self.pattern(pattern);
+                if let Some(ty) = ty {
+                    self.type_expr(ty);
+                }
+                self.expr(value);
+            }
+            Stmt::Assign {
+                span,
+                target,
+                value,
+                ..
+            } => {
+                self.push(SyntaxKind::AssignStmt, span);
+                self.expr(target);
+                self.expr(value);
+            }
+            Stmt::Expr { span, expr, .. } => {
+                self.push(SyntaxKind::ExprStmt, span);
+                self.expr(expr);
+            }
+            Stmt::Return { span, value, .. } => {
+                self.push(SyntaxKind::ReturnStmt, span);
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        let kind = match expr {
+            Expr::IntLit { .. }
+            | Expr::FloatLit { .. }
+            | Expr::DecimalLit { .. }
+            | Expr::BoolLit { .. }
+            | Expr::TextLit { .. }
+            | Expr::UnitLit { .. } => SyntaxKind::Literal,
+            Expr::Ident { .. } | Expr::QualifiedIdent { .. } => SyntaxKind::NameRef,
+            Expr::Record { .. } => SyntaxKind::RecordExpr,
+            Expr::FieldAccess { .. } => SyntaxKind::FieldExpr,
+            Expr::Binary { .. } => SyntaxKind::BinaryExpr,
+            Expr::Unary { .. } => SyntaxKind::UnaryExpr,
+            Expr::Call { .. } => SyntaxKind::CallExpr,
+            Expr::MethodCall { .. } => SyntaxKind::MethodCallExpr,
+            Expr::If { .. } => SyntaxKind::IfExpr,
+            Expr::Match { .. } => SyntaxKind::MatchExpr,
+            Expr::Block { .. } => SyntaxKind::BlockExpr,
+            Expr::Try { .. } | Expr::TryElse { .. } => SyntaxKind::TryExpr,
+            Expr::ListLit { .. } => SyntaxKind::ListExpr,
+            Expr::TupleLit { .. } => SyntaxKind::TupleExpr,
+            Expr::MapLit { .. } => SyntaxKind::MapExpr,
+            Expr::Lambda { .. } => SyntaxKind::LambdaExpr,
+            Expr::ForIn { .. } => SyntaxKind::ForExpr,
+            Expr::While { .. } => SyntaxKind::WhileExpr,
+            Expr::Break { .. } => SyntaxKind::BreakExpr,
+            Expr::Continue { .. } => SyntaxKind::ContinueExpr,
+            Expr::StringInterp { .. } => SyntaxKind::InterpolatedText,
+            Expr::Range { .. } => SyntaxKind::RangeExpr,
+            Expr::IndexAccess { .. } => SyntaxKind::IndexExpr,
+            Expr::Await { .. } => SyntaxKind::AwaitExpr,
+            Expr::Quantifier { .. } => SyntaxKind::QuantifierExpr,
+            Expr::Hole { .. } => SyntaxKind::Hole,
+            Expr::MacroCall { .. } => SyntaxKind::MacroCall,
+        };
+        self.push(kind, expr.span());
+        match expr {
+            Expr::If {
+                cond,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expr(cond);
+                self.block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.expr(else_branch);
+                }
+            }
+            Expr::Match { expr, arms, .. } => {
+                self.expr(expr);
+                for arm in arms {
+                    self.push(SyntaxKind::MatchArm, &arm.span);
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                }
+            }
+            Expr::Block { block, .. } => self.block(block),
+            Expr::Lambda {
+                params,
+                return_type,
+                body,
+                ..
+            } => {
+                for param in params {
+                    self.push(SyntaxKind::LambdaParam, &param.span);
+                    if let Some(ty) = &param.ty {
+                        self.type_expr(ty);
+                    }
+                }
+                if let Some(ty) = return_type {
+                    self.type_expr(ty);
+                }
+                self.block(body);
+            }
+            Expr::ForIn {
+                pattern,
+                iter,
+                body,
+                ..
+            } => {
+                if let Some(pattern) = pattern {
+                    self.pattern(pattern);
+                }
+                self.expr(iter);
+                self.block(body);
+            }
+            Expr::While { cond, body, .. } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            Expr::Quantifier { iter, body, .. } => {
+                self.expr(iter);
+                self.expr(body);
+            }
+            Expr::Record { fields, .. } => {
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            Expr::FieldAccess { expr, .. }
+            | Expr::Unary { expr, .. }
+            | Expr::Try { expr, .. }
+            | Expr::Await { expr, .. } => self.expr(expr),
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Call { func, args, .. } => {
+                self.expr(func);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                self.expr(receiver);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            // The expansion's spans point into the macro definition
+            Expr::MacroCall { args, .. } => {
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::TryElse {
+                expr, else_expr, ..
+            } => {
+                self.expr(expr);
+                self.expr(else_expr);
+            }
+            Expr::ListLit { elements, .. } | Expr::TupleLit { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::MapLit { entries, .. } => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::Range { start, end, .. } => {
+                self.expr(start);
+                self.expr(end);
+            }
+            Expr::IndexAccess { expr, index, .. } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            // Interpolated expressions sit inside a single text token
+            Expr::StringInterp { .. }
+            | Expr::IntLit { .. }
+            | Expr::FloatLit { .. }
+            | Expr::DecimalLit { .. }
+            | Expr::BoolLit { .. }
+            | Expr::TextLit { .. }
+            | Expr::UnitLit { .. }
+            | Expr::Ident { .. }
+            | Expr::QualifiedIdent { .. }
+            | Expr::Break { .. }
+            | Expr::Continue { .. }
+            | Expr::Hole { .. } => {}
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Wildcard { span, .. }
+            | Pattern::Ident { span, .. }
+            | Pattern::IntLit { span, .. }
+            | Pattern::FloatLit { span, .. }
+            | Pattern::BoolLit { span, .. }
+            | Pattern::TextLit { span, .. } => self.push(SyntaxKind::Pattern, span),
+            Pattern::Record { span, fields, .. } => {
+                self.push(SyntaxKind::Pattern, span);
+                for (_, field) in fields {
+                    self.pattern(field);
+                }
+            }
+            Pattern::Variant { span, fields, .. } => {
+                self.push(SyntaxKind::Pattern, span);
+                for field in fields {
+                    self.pattern(field);
+                }
+            }
+            Pattern::Tuple { span, elements, .. } => {
+                self.push(SyntaxKind::Pattern, span);
+                for element in elements {
+                    self.pattern(element);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "cst_tests.rs"]
+mod tests;
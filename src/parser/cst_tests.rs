@@ -0,0 +1,152 @@
+use super::*;
+use std::path::PathBuf;
+
+const SOURCE: &str = r#"module app
+
+## Doubles a number
+fn double(n: Int) -> Int {
+  n * 2  # the answer, halved
+}
+
+fn main() effects(Console) {
+  let xs = [1, double(2)]
+  match xs.head() {
+    None => Console.println("empty")
+    Some(x) => Console.println("${x}")
+  }
+}
+"#;
+
+fn tree(source: &str) -> Parse {
+    parse(source, &PathBuf::from("test.astra"))
+}
+
+fn kinds(node: &SyntaxNode) -> Vec<SyntaxKind> {
+    node.children().iter().map(SyntaxNode::kind).collect()
+}
+
+#[test]
+fn test_tree_reproduces_its_source() {
+    let parsed = tree(SOURCE);
+    assert!(parsed.errors.is_empty());
+    assert_eq!(parsed.root.text(), SOURCE);
+    assert_eq!(parsed.root.text_range(), 0..SOURCE.len());
+
+    for entry in std::fs::read_dir("examples").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|e| e == "astra") {
+            let source = std::fs::read_to_string(&path).unwrap();
+            assert_eq!(
+                parse(&source, &path).root.text(),
+                source,
+                "{}",
+                path.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_nodes_nest_by_span() {
+    use SyntaxKind::*;
+    let root = tree(SOURCE).root;
+    assert_eq!(root.kind(), Module);
+    assert_eq!(kinds(&root), vec![ModuleName, FnDef, FnDef]);
+
+    let double = &root.children()[1];
+    assert_eq!(kinds(double), vec![Param, TypeExpr, Block]);
+    let body = &double.children()[2];
+    assert_eq!(kinds(body), vec![BinaryExpr]);
+    assert_eq!(body.children()[0].text(), "n * 2");
+    assert_eq!(kinds(&body.children()[0]), vec![NameRef, Literal]);
+
+    let call = root
+        .descendants()
+        .into_iter()
+        .find(|node| node.kind() == CallExpr)
+        .unwrap();
+    assert_eq!(call.text(), "double(2)");
+    assert_eq!(call.parent().unwrap().kind(), ListExpr);
+    assert_eq!(&SOURCE[call.text_range()], "double(2)");
+    assert_eq!(root.covering_node(call.text_range()), call);
+}
+
+#[test]
+fn test_trivia_are_tokens_of_the_enclosing_node() {
+    let root = tree(SOURCE).root;
+    let double = &root.children()[1];
+    let doc = root
+        .children_with_tokens()
+        .into_iter()
+        .filter_map(|element| match element {
+            SyntaxElement::Token(token) => Some(token),
+            SyntaxElement::Node(_) => None,
+        })
+        .find(|token| token.kind() == SyntaxKind::DocComment)
+        .unwrap();
+    assert_eq!(doc.text(), "## Doubles a number");
+    assert!(doc.text_range().end <= double.text_range().start);
+    assert!(double.text().starts_with("fn double"));
+
+    let comment = double
+        .tokens()
+        .into_iter()
+        .find(|token| token.kind() == SyntaxKind::Comment)
+        .unwrap();
+    assert_eq!(comment.text(), "# the answer, halved");
+    assert_eq!(comment.parent().kind(), SyntaxKind::Block);
+
+    let fn_keyword = &double.tokens()[0];
+    assert_eq!(fn_keyword.token_kind(), Some(&TokenKind::Fn));
+    assert!(!fn_keyword.is_trivia());
+}
+
+#[test]
+fn test_edits_rebuild_the_path_to_the_root() {
+    let root = tree(SOURCE).root;
+    let literal = root
+        .descendants()
+        .into_iter()
+        .find(|node| node.kind() == SyntaxKind::Literal && node.text() == "2")
+        .unwrap();
+    let token = &literal.tokens()[0];
+    let edited = token.replace_with(GreenToken::new(
+        SyntaxKind::Token,
+        Some(TokenKind::IntLit(3)),
+        "3",
+    ));
+    let edited = SyntaxNode::new_root(edited);
+    assert_eq!(edited.text(), SOURCE.replacen("n * 2", "n * 3", 1));
+    // Untouched subtrees are shared with the original tree
+    assert!(Rc::ptr_eq(
+        &edited.children()[2].green().0,
+        &root.children()[2].green().0
+    ));
+
+    let body = edited.children()[1].children()[2].clone();
+    let replaced = body.replace_with(GreenNode::new(
+        SyntaxKind::Block,
+        vec![GreenElement::Token(GreenToken::new(
+            SyntaxKind::Token,
+            None,
+            "{ n + n }",
+        ))],
+    ));
+    assert!(replaced
+        .to_string()
+        .contains("fn double(n: Int) -> Int { n + n }\n"));
+}
+
+#[test]
+fn test_unparsable_source_is_a_flat_module() {
+    let source = "module m\nfn f( {\n  # still here\n";
+    let parse = tree(source);
+    assert!(!parse.errors.is_empty());
+    assert_eq!(parse.root.text(), source);
+    assert!(parse.root.children().is_empty());
+    assert!(parse
+        .root
+        .tokens()
+        .iter()
+        .any(|token| token.kind() == SyntaxKind::Comment));
+}
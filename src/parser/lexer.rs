@@ -3,18 +3,23 @@
 use crate::diagnostics::Span;
 use crate::parser::span::SourceFile;
 use logos::Logos;
+use serde::Serialize;
 
 /// Token types for Astra
 #[derive(Logos, Debug, Clone, PartialEq)]
 #[logos(skip r"[ \t\r\n]+")]
 pub enum TokenKind {
     // Keywords
+    #[token("after_each")]
+    AfterEach,
     #[token("and")]
     And,
     #[token("as")]
     As,
     #[token("assert")]
     Assert,
+    #[token("before_each")]
+    BeforeEach,
     #[token("else")]
     Else,
     #[token("effects")]
@@ -86,11 +91,21 @@ pub enum TokenKind {
     Await,
     #[token("async")]
     Async,
+    #[token("macro")]
+    Macro,
 
     // Literals
-    #[regex(r"[0-9]+\.[0-9]+", priority = 3, callback = |lex| lex.slice().parse::<f64>().ok())]
+    #[regex(r"[0-9]+\.[0-9]+([eE][+-]?[0-9]+)?", priority = 3, callback = |lex| lex.slice().parse::<f64>().ok())]
+    #[regex(r"[0-9]+[eE][+-]?[0-9]+", priority = 3, callback = |lex| lex.slice().parse::<f64>().ok())]
     FloatLit(f64),
 
+    /// Exact decimal literal such as `1.50d`; keeps the digits as written so the scale survives
+    #[regex(r"[0-9]+(\.[0-9]+)?d", priority = 4, callback = |lex| {
+        let s = lex.slice();
+        s[..s.len() - 1].to_string()
+    })]
+    DecimalLit(String),
+
     #[regex(r"[0-9]+", |lex| lex.slice().parse::<i64>().ok())]
     IntLit(i64),
 
@@ -107,6 +122,10 @@ pub enum TokenKind {
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", priority = 1, callback = |lex| lex.slice().to_string())]
     Ident(String),
 
+    /// A `$name` placeholder in a macro definition, including the `$`
+    #[regex(r"\$[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_string())]
+    MacroVar(String),
+
     // Operators
     #[token("+")]
     Plus,
@@ -135,6 +154,8 @@ pub enum TokenKind {
     EqEq,
     #[token("!=")]
     BangEq,
+    #[token("!")]
+    Bang,
     #[token("<")]
     Lt,
     #[token(">")]
@@ -176,6 +197,8 @@ pub enum TokenKind {
     PipeArrow,
     #[token("|")]
     Pipe,
+    #[token("...")]
+    Ellipsis,
     #[token("..=")]
     DotDotEq,
     #[token("..")]
@@ -186,6 +209,8 @@ pub enum TokenKind {
     Underscore,
     #[token("???")]
     Hole,
+    #[token("@")]
+    At,
 
     // Comments
     #[regex(r"##[^\n]*", |lex| lex.slice().to_string())]
@@ -217,11 +242,18 @@ fn multiline_string_callback(lex: &mut logos::Lexer<TokenKind>) -> Option<String
 pub struct Token {
     pub kind: TokenKind,
     pub span: Span,
+    /// Doc comments (`##` and `##!` lines) on their own lines before the
+    /// token, in source order
+    pub docs: Vec<String>,
 }
 
 impl Token {
     pub fn new(kind: TokenKind, span: Span) -> Self {
-        Self { kind, span }
+        Self {
+            kind,
+            span,
+            docs: Vec::new(),
+        }
     }
 }
 
@@ -232,6 +264,8 @@ pub struct Lexer<'a> {
     peeked: Option<Token>,
     peeked2: Option<Token>,
     at_eof: bool,
+    /// Doc comments read since the last token
+    docs: Vec<String>,
 }
 
 impl<'a> Lexer<'a> {
@@ -243,6 +277,7 @@ impl<'a> Lexer<'a> {
             peeked: None,
             peeked2: None,
             at_eof: false,
+            docs: Vec::new(),
         }
     }
 
@@ -258,24 +293,38 @@ impl<'a> Lexer<'a> {
 
     fn read_token(&mut self) -> Token {
         if self.at_eof {
-            return Token::new(
+            let mut token = Token::new(
                 TokenKind::Eof,
                 self.source
                     .span(self.source.content().len(), self.source.content().len()),
             );
+            token.docs = std::mem::take(&mut self.docs);
+            return token;
         }
 
         loop {
             match self.logos_lexer.next() {
                 Some(Ok(kind)) => {
-                    // Skip comments
-                    match &kind {
-                        TokenKind::LineComment(_) | TokenKind::DocComment(_) => continue,
+                    // Skip comments, keeping doc comments that start a line
+                    match kind {
+                        TokenKind::LineComment(_) => continue,
+                        TokenKind::DocComment(text) => {
+                            let start = self.logos_lexer.span().start;
+                            let line_start = self.source.content()[..start]
+                                .rfind('\n')
+                                .map_or(0, |i| i + 1);
+                            if self.source.content()[line_start..start].trim().is_empty() {
+                                self.docs.push(text);
+                            }
+                            continue;
+                        }
                         _ => {}
                     }
                     let span_range = self.logos_lexer.span();
                     let span = self.source.span(span_range.start, span_range.end);
-                    return Token::new(kind, span);
+                    let mut token = Token::new(kind, span);
+                    token.docs = std::mem::take(&mut self.docs);
+                    return token;
                 }
                 Some(Err(())) => {
                     // On error, skip the character and continue
@@ -286,11 +335,13 @@ impl<'a> Lexer<'a> {
                 }
                 None => {
                     self.at_eof = true;
-                    return Token::new(
+                    let mut token = Token::new(
                         TokenKind::Eof,
                         self.source
                             .span(self.source.content().len(), self.source.content().len()),
                     );
+                    token.docs = std::mem::take(&mut self.docs);
+                    return token;
                 }
             }
         }
@@ -320,6 +371,144 @@ impl<'a> Lexer<'a> {
         self.peek().kind == TokenKind::Eof
     }
 }
+/// Kind of trivia: source text that is not part of any token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TriviaKind {
+    Whitespace,
+    LineComment,
+    DocComment,
+    /// A `#!` line starting the file, as in `#!/usr/bin/env astra`
+    Shebang,
+}
+
+/// Whitespace or a comment preceding a token
+#[derive(Debug, Clone, Serialize)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+/// A token with its exact source text and the trivia before it.
+///
+/// Concatenating each token's leading trivia and text, in order, reproduces
+/// the source exactly (see [`reconstruct`]).
+#[derive(Debug, Clone)]
+pub struct LosslessToken {
+    /// Token kind, or `None` for input the lexer could not recognize
+    pub kind: Option<TokenKind>,
+    pub text: String,
+    pub span: Span,
+    pub leading_trivia: Vec<Trivia>,
+}
+
+impl LosslessToken {
+    /// Name of the token kind without its payload (e.g., `Ident`, `IntLit`)
+    pub fn kind_name(&self) -> String {
+        match &self.kind {
+            Some(kind) => {
+                let debug = format!("{:?}", kind);
+                debug.split('(').next().unwrap_or_default().to_string()
+            }
+            None => "Error".to_string(),
+        }
+    }
+}
+
+/// Tokenize a source file without dropping whitespace, comments, or invalid
+/// input. The last token is always `Eof` and carries any trailing trivia.
+pub fn tokenize_lossless(source: &SourceFile) -> Vec<LosslessToken> {
+    let content = source.content();
+    let mut logos_lexer = TokenKind::lexer(content);
+    let mut tokens = Vec::new();
+    let mut trivia = Vec::new();
+    let mut offset = 0;
+
+    let push_whitespace = |trivia: &mut Vec<Trivia>, start: usize, end: usize| {
+        if start < end {
+            trivia.push(Trivia {
+                kind: TriviaKind::Whitespace,
+                text: content[start..end].to_string(),
+                span: source.span(start, end),
+            });
+        }
+    };
+
+    while let Some(result) = logos_lexer.next() {
+        let range = logos_lexer.span();
+        push_whitespace(&mut trivia, offset, range.start);
+        offset = range.end;
+        let text = content[range.clone()].to_string();
+        let span = source.span(range.start, range.end);
+        match result {
+            Ok(TokenKind::LineComment(_)) if range.start == 0 && text.starts_with("#!") => trivia
+                .push(Trivia {
+                    kind: TriviaKind::Shebang,
+                    text,
+                    span,
+                }),
+            Ok(TokenKind::LineComment(_)) => trivia.push(Trivia {
+                kind: TriviaKind::LineComment,
+                text,
+                span,
+            }),
+            Ok(TokenKind::DocComment(_)) => trivia.push(Trivia {
+                kind: TriviaKind::DocComment,
+                text,
+                span,
+            }),
+            result => tokens.push(LosslessToken {
+                kind: result.ok(),
+                text,
+                span,
+                leading_trivia: std::mem::take(&mut trivia),
+            }),
+        }
+    }
+    push_whitespace(&mut trivia, offset, content.len());
+    tokens.push(LosslessToken {
+        kind: Some(TokenKind::Eof),
+        text: String::new(),
+        span: source.span(content.len(), content.len()),
+        leading_trivia: trivia,
+    });
+    tokens
+}
+
+/// The `#!` line that makes a file an executable script, without its newline
+pub fn shebang(content: &str) -> Option<&str> {
+    let line = content.lines().next()?;
+    line.starts_with("#!").then_some(line)
+}
+
+/// The header of a single-file script: its [`shebang`] line, if any, and
+/// the `#:` lines of its inline manifest that follow, with their newlines.
+///
+/// The header ends at the first other line. The lexer reads it as comments.
+pub fn script_header(content: &str) -> Option<&str> {
+    let mut end = 0;
+    for line in content.split_inclusive('\n') {
+        let is_header = line.starts_with("#:") || (end == 0 && line.starts_with("#!"));
+        if !is_header {
+            break;
+        }
+        end += line.len();
+    }
+    (end > 0).then(|| &content[..end])
+}
+
+/// Rebuild source text from lossless tokens
+pub fn reconstruct(tokens: &[LosslessToken]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        for trivia in &token.leading_trivia {
+            out.push_str(&trivia.text);
+        }
+        out.push_str(&token.text);
+    }
+    out
+}
+
 #[cfg(test)]
 #[path = "lexer_tests.rs"]
 mod tests;
@@ -39,6 +39,32 @@ fn test_literals() {
     );
 }
 
+#[test]
+fn test_scientific_float_literals() {
+    assert_eq!(
+        lex("1.5e9 2E-3 1e+2 3.25 7"),
+        vec![
+            TokenKind::FloatLit(1.5e9),
+            TokenKind::FloatLit(2e-3),
+            TokenKind::FloatLit(100.0),
+            TokenKind::FloatLit(3.25),
+            TokenKind::IntLit(7),
+        ]
+    );
+}
+
+#[test]
+fn test_decimal_literals_keep_their_digits() {
+    assert_eq!(
+        lex("1.50d 7d 0.10"),
+        vec![
+            TokenKind::DecimalLit("1.50".to_string()),
+            TokenKind::DecimalLit("7".to_string()),
+            TokenKind::FloatLit(0.1),
+        ]
+    );
+}
+
 #[test]
 fn test_identifiers() {
     assert_eq!(
@@ -119,3 +145,80 @@ fn test_range_tokens() {
         ]
     );
 }
+
+#[test]
+fn test_lossless_tokens_reconstruct_source() {
+    let source = "## Adds one\nfn inc(x: Int) -> Int {  # bump\n  x + 1 $\n}\n\n";
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let tokens = tokenize_lossless(&source_file);
+
+    assert_eq!(reconstruct(&tokens), source);
+
+    let first = &tokens[0];
+    assert_eq!(first.kind, Some(TokenKind::Fn));
+    assert_eq!(first.leading_trivia[0].kind, TriviaKind::DocComment);
+    assert_eq!(first.leading_trivia[1].kind, TriviaKind::Whitespace);
+
+    let invalid = tokens.iter().find(|t| t.text == "$").unwrap();
+    assert_eq!(invalid.kind_name(), "Error");
+
+    let last = tokens.last().unwrap();
+    assert_eq!(last.kind_name(), "Eof");
+    assert_eq!(last.leading_trivia[0].text, "\n\n");
+}
+
+#[test]
+fn test_shebang_and_script_header() {
+    let source =
+        "#!/usr/bin/env astra\n#: capabilities = [\"Console\"]\nmodule s\n# #!not a shebang\n";
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), source.to_string());
+    let tokens = tokenize_lossless(&source_file);
+    assert_eq!(reconstruct(&tokens), source);
+    let kinds: Vec<TriviaKind> = tokens[0].leading_trivia.iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            TriviaKind::Shebang,
+            TriviaKind::Whitespace,
+            TriviaKind::LineComment,
+            TriviaKind::Whitespace
+        ]
+    );
+    assert_eq!(lex(source), lex("module s"));
+
+    assert_eq!(shebang(source), Some("#!/usr/bin/env astra"));
+    assert_eq!(shebang("module s\n#!x\n"), None);
+    assert_eq!(
+        script_header(source),
+        Some("#!/usr/bin/env astra\n#: capabilities = [\"Console\"]\n")
+    );
+    assert_eq!(
+        script_header("#: limits.max_steps = 10\n\n#: ignored\n"),
+        Some("#: limits.max_steps = 10\n")
+    );
+    assert_eq!(script_header("# plain comment\n#: ignored\n"), None);
+}
+
+#[test]
+fn test_lossless_token_kind_names() {
+    let source_file = SourceFile::new(PathBuf::from("test.astra"), "foo 42".to_string());
+    let names: Vec<String> = tokenize_lossless(&source_file)
+        .iter()
+        .map(|t| t.kind_name())
+        .collect();
+    assert_eq!(names, vec!["Ident", "IntLit", "Eof"]);
+}
+
+#[test]
+fn test_tokens_carry_own_line_doc_comments() {
+    let source_file = SourceFile::new(
+        PathBuf::from("test.astra"),
+        "##! Module\n## Adds one\n# note\nfn inc() ## not a doc\n{\n}\n".to_string(),
+    );
+    let mut lexer = Lexer::new(&source_file);
+    let fn_token = lexer.next_token();
+    assert_eq!(fn_token.kind, TokenKind::Fn);
+    assert_eq!(fn_token.docs, vec!["##! Module", "## Adds one"]);
+    assert!(lexer.next_token().docs.is_empty());
+    assert!(lexer.next_token().docs.is_empty());
+}
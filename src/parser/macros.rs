@@ -0,0 +1,475 @@
+//! Macro expansion
+//!
+//! A `macro` item maps argument patterns to expression templates:
+//!
+//! ```text
+//! macro max {
+//!   ($a) => $a,
+//!   ($a, $rest...) => { let m = max!($rest...); if $a > m { $a } else { m } }
+//! }
+//! ```
+//!
+//! [`expand`] replaces each `name!(args)` in a module with the template of
+//! the first rule whose pattern accepts the arguments, after the parser has
+//! built the module and before anything checks it. Expansion is hygienic:
+//! names the template binds (`m` above) are renamed apart for every
+//! expansion, so they can neither capture nor shadow the caller's variables.
+//! Nodes from a template keep spans in the macro definition and nodes from
+//! arguments keep spans at the call site.
+
+use std::collections::HashMap;
+
+use super::ast::*;
+use super::rewrite::{rewrite_expr, rewrite_module, Rewriter, Walk};
+use crate::diagnostics::error_codes::syntax::{
+    DUPLICATE_MACRO, MACRO_RECURSION_LIMIT, NO_MATCHING_MACRO_RULE, UNKNOWN_MACRO,
+};
+use crate::diagnostics::{Diagnostic, Span};
+
+/// How deeply macro invocations may nest in each other's expansions
+pub const MAX_DEPTH: usize = 64;
+
+/// Expand every macro invocation in `module`, filling in their `expansion`
+pub fn expand(module: &mut Module) -> Vec<Diagnostic> {
+    let mut expander = Expander {
+        macros: HashMap::new(),
+        errors: Vec::new(),
+        call_sites: Vec::new(),
+        expansions: 0,
+    };
+    for item in &module.items {
+        if let Item::MacroDef(def) = item {
+            if let Some(previous) = expander.macros.insert(def.name.clone(), def.clone()) {
+                expander.errors.push(
+                    Diagnostic::error(DUPLICATE_MACRO)
+                        .message(format!("Macro `{}` is defined more than once", def.name))
+                        .span(def.span.clone())
+                        .label(previous.span, "first defined here")
+                        .build(),
+                );
+            }
+        }
+    }
+    rewrite_module(&mut expander, module);
+    expander.errors
+}
+
+struct Expander {
+    macros: HashMap<String, MacroDef>,
+    errors: Vec<Diagnostic>,
+    /// Invocations being expanded, outermost first
+    call_sites: Vec<Span>,
+    /// Expansions so far, numbering the names each one binds
+    expansions: usize,
+}
+
+impl Rewriter for Expander {
+    fn enter_expr(&mut self, expr: &mut Expr) -> Walk {
+        let Expr::MacroCall {
+            span,
+            name,
+            args,
+            expansion,
+            ..
+        } = expr
+        else {
+            return Walk::Continue;
+        };
+        if expansion.is_some() {
+            return Walk::Skip;
+        }
+        let Some(def) = self.macros.get(name.as_str()) else {
+            self.errors.push(
+                Diagnostic::error(UNKNOWN_MACRO)
+                    .message(format!("Unknown macro `{}!`", name))
+                    .span(span.clone())
+                    .build(),
+            );
+            return Walk::Skip;
+        };
+        if self.call_sites.len() == MAX_DEPTH {
+            self.errors.push(
+                Diagnostic::error(MACRO_RECURSION_LIMIT)
+                    .message(format!(
+                        "Expanding `{}!` nests more than {} macro invocations deep",
+                        name, MAX_DEPTH
+                    ))
+                    .span(span.clone())
+                    .label(def.span.clone(), "macro defined here")
+                    .label(self.call_sites[0].clone(), "expanded from here")
+                    .build(),
+            );
+            return Walk::Skip;
+        }
+        self.expansions += 1;
+        let mut expanded = match instantiate(def, args, span, self.expansions) {
+            Ok(expanded) => expanded,
+            Err(diag) => {
+                self.errors.push(diag);
+                return Walk::Skip;
+            }
+        };
+        self.call_sites.push(span.clone());
+        rewrite_expr(self, &mut expanded);
+        self.call_sites.pop();
+        *expansion = Some(Box::new(expanded));
+        Walk::Skip
+    }
+}
+
+/// What a macro parameter is bound to in one expansion
+enum Binding {
+    One(Box<Expr>),
+    Many(Vec<Expr>),
+}
+
+/// The template of the first rule of `def` accepting `args`, with its
+/// parameters substituted and its own bindings renamed apart
+fn instantiate(
+    def: &MacroDef,
+    args: &[Expr],
+    call_span: &Span,
+    expansion: usize,
+) -> Result<Expr, Diagnostic> {
+    let Some((rule, bindings)) = def
+        .rules
+        .iter()
+        .find_map(|rule| match_rule(rule, args).map(|bindings| (rule, bindings)))
+    else {
+        return Err(Diagnostic::error(NO_MATCHING_MACRO_RULE)
+            .message(format!(
+                "No rule of macro `{}!` accepts {}",
+                def.name,
+                describe_args(def, args)
+            ))
+            .span(call_span.clone())
+            .label(def.span.clone(), "macro defined here")
+            .build());
+    };
+
+    let mut template = rule.template.clone();
+    let mut hygiene = Hygiene {
+        renames: HashMap::new(),
+        expansion,
+    };
+    rewrite_expr(&mut hygiene, &mut template);
+    rewrite_expr(&mut hygiene, &mut template);
+
+    let mut substitute = Substitute {
+        bindings,
+        macro_name: &def.name,
+        call_span,
+        error: None,
+    };
+    rewrite_expr(&mut substitute, &mut template);
+    if let Some(diag) = substitute.error {
+        return Err(diag);
+    }
+    rewrite_expr(&mut Renumber, &mut template);
+    Ok(template)
+}
+
+/// Why no rule matched: the number of arguments, or what they are
+fn describe_args(def: &MacroDef, args: &[Expr]) -> String {
+    let takes = |rule: &MacroRule| match rule.params.last() {
+        Some(last) if last.variadic => args.len() >= rule.params.len() - 1,
+        _ => args.len() == rule.params.len(),
+    };
+    match args.len() {
+        _ if def.rules.iter().any(takes) => "these arguments".to_string(),
+        1 => "1 argument".to_string(),
+        n => format!("{} arguments", n),
+    }
+}
+
+/// Bind the parameters of `rule` to `args`, if its pattern accepts them
+fn match_rule(rule: &MacroRule, args: &[Expr]) -> Option<HashMap<String, Binding>> {
+    let variadic = rule.params.last().is_some_and(|param| param.variadic);
+    let fixed = rule.params.len() - usize::from(variadic);
+    if args.len() < fixed || (!variadic && args.len() > fixed) {
+        return None;
+    }
+    let accepts = |param: &MacroParam, arg: &Expr| match param.fragment {
+        MacroFragment::Expr => true,
+        MacroFragment::Ident => matches!(arg, Expr::Ident { .. }),
+    };
+    let mut bindings = HashMap::new();
+    for (param, arg) in rule.params.iter().zip(args) {
+        if param.variadic {
+            break;
+        }
+        if !accepts(param, arg) {
+            return None;
+        }
+        bindings.insert(
+            format!("${}", param.name),
+            Binding::One(Box::new(arg.clone())),
+        );
+    }
+    if variadic {
+        let param = &rule.params[fixed];
+        let rest = &args[fixed..];
+        if !rest.iter().all(|arg| accepts(param, arg)) {
+            return None;
+        }
+        bindings.insert(format!("${}", param.name), Binding::Many(rest.to_vec()));
+    }
+    Some(bindings)
+}
+
+/// Gives every node of an expansion a fresh ID, so that expansions of one
+/// macro don't share IDs with each other or with the template (closure bodies
+/// are cached by the ID of their lambda)
+struct Renumber;
+
+impl Rewriter for Renumber {
+    fn enter_block(&mut self, block: &mut Block) -> Walk {
+        block.id = NodeId::new();
+        Walk::Continue
+    }
+
+    fn enter_stmt(&mut self, stmt: &mut Stmt) -> Walk {
+        *stmt.id_mut() = NodeId::new();
+        Walk::Continue
+    }
+
+    fn enter_expr(&mut self, expr: &mut Expr) -> Walk {
+        *expr.id_mut() = NodeId::new();
+        Walk::Continue
+    }
+
+    fn enter_pattern(&mut self, pattern: &mut Pattern) -> Walk {
+        *pattern.id_mut() = NodeId::new();
+        Walk::Continue
+    }
+}
+
+/// Renames the names a template binds to ones unique to the expansion
+///
+/// The first walk collects binders and the second renames them and their
+/// uses, so uses before a binder in source order are renamed too.
+struct Hygiene {
+    renames: HashMap<String, String>,
+    expansion: usize,
+}
+
+impl Hygiene {
+    fn bind(&mut self, name: &mut String) {
+        if name.starts_with('$') || name == "_" {
+            return;
+        }
+        let fresh = match self.renames.get(name) {
+            Some(fresh) => fresh.clone(),
+            None if name.contains('#') => return,
+            None => {
+                // `#` starts a comment, so no source name can collide
+                let fresh = format!("{}#{}", name, self.expansion);
+                self.renames.insert(name.clone(), fresh.clone());
+                fresh
+            }
+        };
+        *name = fresh;
+    }
+}
+
+impl Rewriter for Hygiene {
+    fn enter_stmt(&mut self, stmt: &mut Stmt) -> Walk {
+        if let Stmt::Let { name, .. } = stmt {
+            self.bind(name);
+        }
+        Walk::Continue
+    }
+
+    fn enter_pattern(&mut self, pattern: &mut Pattern) -> Walk {
+        if let Pattern::Ident { name, .. } = pattern {
+            self.bind(name);
+        }
+        Walk::Continue
+    }
+
+    fn enter_expr(&mut self, expr: &mut Expr) -> Walk {
+        match expr {
+            Expr::Ident { name, .. } => {
+                if let Some(fresh) = self.renames.get(name) {
+                    *name = fresh.clone();
+                }
+            }
+            Expr::Lambda { params, .. } => {
+                for param in params {
+                    self.bind(&mut param.name);
+                }
+            }
+            Expr::ForIn {
+                binding,
+                pattern: None,
+                ..
+            }
+            | Expr::Quantifier { binding, .. } => self.bind(binding),
+            _ => {}
+        }
+        Walk::Continue
+    }
+}
+
+/// Replaces `$name` placeholders with the arguments bound to them
+struct Substitute<'a> {
+    bindings: HashMap<String, Binding>,
+    macro_name: &'a str,
+    call_span: &'a Span,
+    error: Option<Diagnostic>,
+}
+
+impl Substitute<'_> {
+    fn fail(&mut self, span: &Span, message: String) {
+        if self.error.is_none() {
+            self.error = Some(
+                Diagnostic::error(NO_MATCHING_MACRO_RULE)
+                    .message(message)
+                    .span(span.clone())
+                    .label(
+                        self.call_span.clone(),
+                        format!("in this expansion of `{}!`", self.macro_name),
+                    )
+                    .build(),
+            );
+        }
+    }
+
+    /// Substitute a placeholder in a binding position, which takes an
+    /// identifier argument's name
+    fn name(&mut self, name: &mut String, span: &Span) {
+        if !name.starts_with('$') {
+            return;
+        }
+        let arg = match self.bindings.get(name.as_str()) {
+            Some(Binding::One(arg)) => Some(arg.as_ref()),
+            Some(Binding::Many(_)) => None,
+            None => return self.unknown(name.clone(), span),
+        };
+        match arg {
+            Some(Expr::Ident { name: arg, .. }) => *name = arg.clone(),
+            _ => {
+                let message = format!(
+                    "`{}` is used as a name in macro `{}!`, so its argument must be an identifier",
+                    name, self.macro_name
+                );
+                self.fail(span, message);
+            }
+        }
+    }
+
+    fn unknown(&mut self, name: String, span: &Span) {
+        let message = format!(
+            "`{}` is not a parameter of this rule of macro `{}!`",
+            name.trim_end_matches("..."),
+            self.macro_name
+        );
+        self.fail(span, message);
+    }
+
+    /// Splice `$rest...` placeholders in an argument or element list
+    fn splice(&mut self, exprs: &mut Vec<Expr>) {
+        if !exprs.iter().any(is_splice) {
+            return;
+        }
+        let mut spliced = Vec::with_capacity(exprs.len());
+        for expr in exprs.drain(..) {
+            let Expr::Ident { name, span, .. } = &expr else {
+                spliced.push(expr);
+                continue;
+            };
+            let Some(var) = name.strip_suffix("...") else {
+                spliced.push(expr);
+                continue;
+            };
+            match self.bindings.get(var) {
+                Some(Binding::Many(args)) => spliced.extend(args.iter().cloned()),
+                Some(Binding::One(arg)) => spliced.push(*arg.clone()),
+                None => self.unknown(var.to_string(), span),
+            }
+        }
+        *exprs = spliced;
+    }
+}
+
+fn is_splice(expr: &Expr) -> bool {
+    matches!(expr, Expr::Ident { name, .. } if name.starts_with('$') && name.ends_with("..."))
+}
+
+impl Rewriter for Substitute<'_> {
+    fn enter_stmt(&mut self, stmt: &mut Stmt) -> Walk {
+        if let Stmt::Let { name, span, .. } = stmt {
+            let span = span.clone();
+            self.name(name, &span);
+        }
+        Walk::Continue
+    }
+
+    fn enter_pattern(&mut self, pattern: &mut Pattern) -> Walk {
+        if let Pattern::Ident { name, span, .. } = pattern {
+            let span = span.clone();
+            self.name(name, &span);
+        }
+        Walk::Continue
+    }
+
+    fn enter_expr(&mut self, expr: &mut Expr) -> Walk {
+        let span = expr.span().clone();
+        match expr {
+            Expr::Ident { name, .. } if name.starts_with('$') => {
+                if name.ends_with("...") {
+                    let message = format!(
+                        "`{}` can only be spliced into arguments or list elements",
+                        name
+                    );
+                    self.fail(&span, message);
+                    return Walk::Skip;
+                }
+                match self.bindings.get(name.as_str()) {
+                    // Arguments are the caller's code: leave them as written
+                    Some(Binding::One(arg)) => *expr = *arg.clone(),
+                    Some(Binding::Many(_)) => {
+                        let message = format!(
+                            "`{}` takes several arguments; splice it with `{}...`",
+                            name, name
+                        );
+                        self.fail(&span, message);
+                    }
+                    None => self.unknown(name.clone(), &span),
+                }
+                return Walk::Skip;
+            }
+            Expr::Call { args, .. }
+            | Expr::MethodCall { args, .. }
+            | Expr::MacroCall { args, .. }
+            | Expr::ListLit { elements: args, .. }
+            | Expr::TupleLit { elements: args, .. } => self.splice(args),
+            Expr::Lambda { params, .. } => {
+                for param in params {
+                    let span = param.span.clone();
+                    self.name(&mut param.name, &span);
+                }
+            }
+            Expr::ForIn {
+                binding,
+                pattern: None,
+                ..
+            }
+            | Expr::Quantifier { binding, .. } => self.name(binding, &span),
+            _ => {}
+        }
+        if let Expr::FieldAccess { field, .. } | Expr::MethodCall { method: field, .. } = expr {
+            self.name(field, &span);
+        }
+        if let Expr::Record { fields, .. } = expr {
+            for (field, _) in fields {
+                self.name(field, &span);
+            }
+        }
+        Walk::Continue
+    }
+}
+
+#[cfg(test)]
+#[path = "macros_tests.rs"]
+mod tests;
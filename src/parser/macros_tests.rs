@@ -0,0 +1,173 @@
+use super::*;
+use crate::embed::{run, CapabilityConfig};
+use crate::parser::parse_source;
+use std::path::Path;
+
+const MACROS: &str = r#"
+macro max {
+  ($a) => $a,
+  ($a, $rest...) => {
+    let m = max!($rest...)
+    if $a > m { $a } else { m }
+  },
+}
+
+macro swap($x: ident, $y: ident) => {
+  let tmp = $x
+  $x = $y
+  $y = tmp
+}
+"#;
+
+fn module(body: &str) -> Result<Module, Vec<Diagnostic>> {
+    let source = format!("module app\n{}\n{}", MACROS, body);
+    parse_source(&source, Path::new("app.astra")).map_err(|bag| bag.take())
+}
+
+fn output(body: &str) -> String {
+    let source = format!("module app\n{}\n{}", MACROS, body);
+    let config = CapabilityConfig {
+        console: true,
+        ..Default::default()
+    };
+    let result = run(&source, 0, &config);
+    assert!(result.ok, "{:?}", result);
+    result.output
+}
+
+fn error(body: &str) -> Diagnostic {
+    let errors = module(body).unwrap_err();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    errors.into_iter().next().unwrap()
+}
+
+#[test]
+fn test_rules_match_in_order_and_recurse() {
+    let out = output(
+        "fn main() effects(Console) {\n  Console.println(\"${max!(7)} ${max!(3, 9, 4)} ${max!(2, 1)}\")\n}\n",
+    );
+    assert_eq!(out, "7 9 2\n");
+}
+
+#[test]
+fn test_expansion_is_hygienic() {
+    // The template's `m` and `tmp` neither capture nor shadow the caller's
+    let out = output(
+        r#"fn main() effects(Console) {
+  let m = 100
+  let tmp = 5
+  let mut a = 1
+  let mut b = 2
+  swap!(a, b)
+  Console.println("${max!(m, 1)} ${a} ${b} ${tmp}")
+}
+"#,
+    );
+    assert_eq!(out, "100 2 1 5\n");
+}
+
+#[test]
+fn test_expansions_of_one_macro_make_distinct_closures() {
+    // Each expansion's lambda runs its own body, not the first expansion's
+    let out = output(
+        r#"macro adder($n) => fn(x: Int) { x + $n }
+
+fn main() effects(Console) {
+  let add1 = adder!(1)
+  let add100 = adder!(100)
+  Console.println("${add100(0)} ${add1(0)}")
+}
+"#,
+    );
+    assert_eq!(out, "100 1\n");
+}
+
+#[test]
+fn test_invocations_keep_their_source_and_expansion() {
+    let module = module("fn f(n: Int) -> Int {\n  max!(n, 0)\n}\n").unwrap();
+    let Some(Item::FnDef(f)) = module.items.last() else {
+        panic!("expected a function");
+    };
+    let Some(Expr::MacroCall {
+        name,
+        args,
+        expansion: Some(expansion),
+        ..
+    }) = f.body.expr.as_deref()
+    else {
+        panic!("expected an expanded invocation");
+    };
+    assert_eq!(name, "max");
+    assert_eq!(args.len(), 2);
+    assert!(matches!(**expansion, Expr::Block { .. }));
+
+    let source = crate::formatter::emit(&module);
+    assert!(source.contains("  ($a, $rest...) => {\n"), "{}", source);
+    assert!(
+        source.contains("macro swap($x: ident, $y: ident) => {"),
+        "{}",
+        source
+    );
+    assert!(source.contains("  max!(n, 0)\n"), "{}", source);
+}
+
+#[test]
+fn test_expansion_errors_point_at_call_and_definition() {
+    let diag = error("fn f() -> Int {\n  min!(1, 2)\n}\n");
+    assert_eq!(diag.code, UNKNOWN_MACRO);
+    assert_eq!(diag.message, "Unknown macro `min!`");
+
+    let diag = error("fn f() -> Int {\n  max!()\n}\n");
+    assert_eq!(diag.code, NO_MATCHING_MACRO_RULE);
+    assert_eq!(diag.labels[0].message, "macro defined here");
+    assert!(diag.span.start > diag.labels[0].span.end);
+
+    // `$x: ident` rejects other expressions
+    let diag = error("fn f() {\n  let mut a = 1\n  swap!(a, 2)\n}\n");
+    assert_eq!(diag.code, NO_MATCHING_MACRO_RULE);
+    assert_eq!(
+        diag.message,
+        "No rule of macro `swap!` accepts these arguments"
+    );
+
+    let diag = error("macro max($a) => $a\n");
+    assert_eq!(diag.code, DUPLICATE_MACRO);
+
+    let diag = error("macro forever($x) => forever!($x)\nfn f() -> Int {\n  forever!(1)\n}\n");
+    assert_eq!(diag.code, MACRO_RECURSION_LIMIT);
+    assert_eq!(diag.labels[1].message, "expanded from here");
+
+    let diag = error("macro bind($x) => { let $x = 1 }\nfn f() {\n  bind!(1 + 1)\n}\n");
+    assert!(
+        diag.message.contains("must be an identifier"),
+        "{}",
+        diag.message
+    );
+    assert_eq!(diag.labels[0].message, "in this expansion of `bind!`");
+}
+
+#[test]
+fn test_type_errors_in_templates_label_the_invocation() {
+    let source = "module app\nmacro bad($x) => {\n  let n: Int = \"text\"\n  $x + n\n}\nfn f() -> Int {\n  bad!(1)\n}\n";
+    let module = parse_source(source, Path::new("app.astra")).unwrap();
+    let mut checker = crate::typechecker::TypeChecker::new();
+    let diags = checker.check_module(&module).unwrap_err().take();
+    let diag = &diags[0];
+    assert_eq!(&source[diag.span.start..diag.span.end], "\"text\"");
+    let label = diag.labels.last().unwrap();
+    assert_eq!(label.message, "in this expansion of `bad!`");
+    assert_eq!(&source[label.span.start..label.span.end], "bad!(1)");
+}
+
+#[test]
+fn test_placeholders_are_only_allowed_in_templates() {
+    let errors = parse_source(
+        "module app\nfn f() -> Int {\n  $x\n}\n",
+        Path::new("app.astra"),
+    )
+    .unwrap_err()
+    .take();
+    assert!(errors[0]
+        .message
+        .contains("only be used in a macro template"));
+}
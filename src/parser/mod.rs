@@ -7,10 +7,15 @@
 //! - Span tracking
 
 pub mod ast;
+pub mod builder;
+pub mod cst;
 pub mod lexer;
+pub mod macros;
 #[allow(clippy::module_inception)]
 pub mod parser;
+pub mod rewrite;
 pub mod span;
+pub mod visit;
 
 pub use ast::*;
 pub use lexer::Lexer;
@@ -43,5 +48,13 @@ pub fn parse_source(source: &str, path: &Path) -> Result<Module, DiagnosticBag>
     let mut parser = Parser::new(lexer, source_file.clone());
     parser.parse_module()
 }
+
+/// Parse source code holding a single expression, such as `total * 2`
+pub fn parse_expression(source: &str, path: &Path) -> Result<Expr, DiagnosticBag> {
+    let source_file = SourceFile::new(path.to_path_buf(), source.to_string());
+    let lexer = Lexer::new(&source_file);
+    let mut parser = Parser::new(lexer, source_file.clone());
+    parser.parse_expression()
+}
 #[cfg(test)]
 mod tests;
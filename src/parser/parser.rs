@@ -11,11 +11,16 @@ use crate::parser::span::SourceFile;
 /// Parser for Astra source code
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
-    #[allow(dead_code)]
     source: SourceFile,
     errors: DiagnosticBag,
     peeked: Option<Token>,
     peeked2: Option<Token>,
+    /// Span of the most recently consumed token
+    previous: Option<Span>,
+    /// Lines of `##!` module doc comments seen so far
+    module_docs: Vec<String>,
+    /// Whether `$name` placeholders are allowed (inside a macro template)
+    in_template: bool,
 }
 
 impl<'a> Parser<'a> {
@@ -27,12 +32,47 @@ impl<'a> Parser<'a> {
             errors: DiagnosticBag::new(),
             peeked: None,
             peeked2: None,
+            previous: None,
+            module_docs: Vec::new(),
+            in_template: false,
         }
     }
 
-    /// Parse a complete module
+    /// Parse a complete module and expand its macro invocations
     pub fn parse_module(&mut self) -> Result<Module, DiagnosticBag> {
+        let mut module = self.parse_module_unexpanded()?;
+        for diag in super::macros::expand(&mut module) {
+            self.errors.push(diag);
+        }
+        if self.errors.has_errors() {
+            return Err(self.errors.clone());
+        }
+        Ok(module)
+    }
+
+    /// Parse a single expression that makes up the whole source
+    pub fn parse_expression(&mut self) -> Result<Expr, DiagnosticBag> {
+        let expr = self.parse_expr().and_then(|expr| {
+            if self.is_eof() {
+                Ok(expr)
+            } else {
+                Err(self.error_unexpected("end of expression"))
+            }
+        });
+        match expr {
+            Ok(expr) if !self.errors.has_errors() => Ok(expr),
+            Ok(_) => Err(self.errors.clone()),
+            Err(diag) => {
+                self.errors.push(diag);
+                Err(self.errors.clone())
+            }
+        }
+    }
+
+    /// Parse a complete module, leaving macro invocations unexpanded
+    fn parse_module_unexpanded(&mut self) -> Result<Module, DiagnosticBag> {
         let start_span = self.current_span();
+        self.take_docs();
 
         // Parse module declaration
         self.expect(TokenKind::Module)?;
@@ -42,6 +82,19 @@ impl<'a> Parser<'a> {
         let mut items = Vec::new();
         while !self.is_eof() {
             match self.parse_item() {
+                Ok(Item::TestHook(hook)) if has_hook(&items, hook.kind) => {
+                    self.errors.push(
+                        Diagnostic::error(
+                            crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN,
+                        )
+                        .message(format!(
+                            "A module can have only one `{}` block",
+                            hook.kind.keyword()
+                        ))
+                        .span(hook.span)
+                        .build(),
+                    );
+                }
                 Ok(item) => items.push(item),
                 Err(diag) => {
                     self.errors.push(diag);
@@ -54,11 +107,13 @@ impl<'a> Parser<'a> {
             return Err(self.errors.clone());
         }
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(Module {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
             name,
+            header: super::lexer::script_header(self.source.content()).map(str::to_string),
+            doc: doc_text(&self.module_docs),
             items,
         })
     }
@@ -80,7 +135,7 @@ impl<'a> Parser<'a> {
             }
         }
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(ModulePath {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -89,6 +144,126 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_item(&mut self) -> Result<Item, Diagnostic> {
+        let doc = self.take_docs();
+        let attributes = self.parse_attributes()?;
+        let span = self.current_span();
+        let mut item = self.parse_item_inner()?;
+        match &mut item {
+            Item::TypeDef(def) => def.doc = doc,
+            Item::EnumDef(def) => def.doc = doc,
+            Item::FnDef(def) => def.doc = doc,
+            Item::TraitDef(def) => def.doc = doc,
+            Item::MacroDef(def) => def.doc = doc,
+            _ => {}
+        }
+        if attributes.is_empty() {
+            return Ok(item);
+        }
+        match item {
+            Item::Import(mut decl) => {
+                decl.attributes = attributes;
+                Ok(Item::Import(decl))
+            }
+            Item::TypeDef(mut def) => {
+                def.attributes = attributes;
+                Ok(Item::TypeDef(def))
+            }
+            Item::EnumDef(mut def) => {
+                def.attributes = attributes;
+                Ok(Item::EnumDef(def))
+            }
+            Item::FnDef(mut def) => {
+                def.attributes = attributes;
+                Ok(Item::FnDef(def))
+            }
+            _ => Err(
+                Diagnostic::error(crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN)
+                    .message(
+                        "Attributes are only supported on imports, types, enums, and functions",
+                    )
+                    .span(span)
+                    .build(),
+            ),
+        }
+    }
+
+    /// Take the doc comments before the next token: `##!` lines are added
+    /// to the module's doc, and the `##` lines are returned as the item's
+    fn take_docs(&mut self) -> Option<String> {
+        let docs = std::mem::take(&mut self.peek_mut().docs);
+        let mut item_docs = Vec::new();
+        for line in docs {
+            match line.strip_prefix("##!") {
+                Some(module_line) => self.module_docs.push(module_line.to_string()),
+                None => item_docs.push(line["##".len()..].to_string()),
+            }
+        }
+        doc_text(&item_docs)
+    }
+
+    /// Parse zero or more attributes: `@name` or `@name(arg, key = "value", ...)`
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute>, Diagnostic> {
+        let mut attributes = Vec::new();
+        while self.check(TokenKind::At) {
+            let start_span = self.current_span();
+            self.advance();
+            let name = self.expect_ident()?;
+            let mut args = Vec::new();
+            let mut named_args = Vec::new();
+            if self.check(TokenKind::LParen) {
+                self.advance();
+                while !self.check(TokenKind::RParen) {
+                    let token = self.advance();
+                    match token.kind {
+                        TokenKind::Ident(key) if self.check(TokenKind::Eq) => {
+                            self.advance();
+                            let value = self.advance();
+                            match value.kind {
+                                TokenKind::TextLit(value) => named_args.push((key, value)),
+                                _ => {
+                                    return Err(Diagnostic::error(
+                                        crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN,
+                                    )
+                                    .message(format!(
+                                        "Expected a string value for attribute argument `{}`",
+                                        key
+                                    ))
+                                    .span(value.span)
+                                    .build())
+                                }
+                            }
+                        }
+                        TokenKind::Ident(arg) | TokenKind::TextLit(arg) => args.push(arg),
+                        // `test` is a keyword, but `@cfg(test)` names it as a flag
+                        TokenKind::Test => args.push("test".to_string()),
+                        _ => {
+                            return Err(Diagnostic::error(
+                                crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN,
+                            )
+                            .message("Expected identifier or string in attribute arguments")
+                            .span(token.span)
+                            .build())
+                        }
+                    }
+                    if !self.check(TokenKind::Comma) {
+                        break;
+                    }
+                    self.advance();
+                }
+                self.expect(TokenKind::RParen)?;
+            }
+            let end_span = self.previous_span();
+            attributes.push(Attribute {
+                span: start_span.merge(&end_span),
+                name,
+                args,
+                named_args,
+            });
+        }
+        Ok(attributes)
+    }
+
+    fn parse_item_inner(&mut self) -> Result<Item, Diagnostic> {
         let token = self.peek();
         match &token.kind {
             TokenKind::Import => self.parse_import_item().map(Item::Import),
@@ -109,8 +284,15 @@ impl<'a> Parser<'a> {
             TokenKind::Trait => self.parse_trait_def().map(Item::TraitDef),
             TokenKind::Impl => self.parse_impl_block().map(Item::ImplBlock),
             TokenKind::Effect => self.parse_effect_def().map(Item::EffectDef),
+            TokenKind::Macro => self.parse_macro_def().map(Item::MacroDef),
             TokenKind::Test => self.parse_test().map(Item::Test),
             TokenKind::Property => self.parse_property().map(Item::Property),
+            TokenKind::BeforeEach => self
+                .parse_test_hook(HookKind::BeforeEach)
+                .map(Item::TestHook),
+            TokenKind::AfterEach => self
+                .parse_test_hook(HookKind::AfterEach)
+                .map(Item::TestHook),
             _ => Err(self.error_unexpected("item")),
         }
     }
@@ -149,13 +331,14 @@ impl<'a> Parser<'a> {
             ImportKind::Module
         };
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(ImportDecl {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
             path,
             kind,
             public,
+            attributes: Vec::new(),
         })
     }
 
@@ -175,7 +358,7 @@ impl<'a> Parser<'a> {
             None
         };
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(TypeDef {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -183,6 +366,8 @@ impl<'a> Parser<'a> {
             type_params,
             value,
             invariant,
+            attributes: Vec::new(),
+            doc: None,
         })
     }
 
@@ -203,13 +388,15 @@ impl<'a> Parser<'a> {
             variants.push(self.parse_variant()?);
         }
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(EnumDef {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
             name,
             type_params,
             variants,
+            attributes: Vec::new(),
+            doc: None,
         })
     }
 
@@ -236,7 +423,7 @@ impl<'a> Parser<'a> {
             Vec::new()
         };
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(Variant {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -251,7 +438,7 @@ impl<'a> Parser<'a> {
         self.expect(TokenKind::Colon)?;
         let ty = self.parse_type_expr()?;
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(Field {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -268,7 +455,7 @@ impl<'a> Parser<'a> {
         self.expect(TokenKind::LBrace)?;
         let methods = self.parse_fn_signatures()?;
         self.expect(TokenKind::RBrace)?;
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
 
         Ok(TraitDef {
             id: NodeId::new(),
@@ -276,6 +463,7 @@ impl<'a> Parser<'a> {
             name,
             type_params,
             methods,
+            doc: None,
         })
     }
 
@@ -294,7 +482,7 @@ impl<'a> Parser<'a> {
             methods.push(self.parse_fn_def()?);
         }
         self.expect(TokenKind::RBrace)?;
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
 
         Ok(ImplBlock {
             id: NodeId::new(),
@@ -311,8 +499,20 @@ impl<'a> Parser<'a> {
         let name = self.expect_ident()?;
         self.expect(TokenKind::LBrace)?;
         let operations = self.parse_fn_signatures()?;
+        // Handlers are plain values, so there is no implementation to hold to a contract
+        if let Some(clause) = operations
+            .iter()
+            .find_map(|op| op.requires.first().or(op.ensures.first()))
+        {
+            return Err(Diagnostic::error(
+                crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN,
+            )
+            .message("Effect operations cannot have `requires` or `ensures` clauses")
+            .span(clause.span().clone())
+            .build());
+        }
         self.expect(TokenKind::RBrace)?;
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
 
         Ok(EffectDecl {
             id: NodeId::new(),
@@ -322,6 +522,121 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parse `macro name { (pattern) => template, ... }`, or the single-rule
+    /// shorthand `macro name(pattern) => template`
+    fn parse_macro_def(&mut self) -> Result<MacroDef, Diagnostic> {
+        let start_span = self.current_span();
+        self.expect(TokenKind::Macro)?;
+        let name = self.expect_ident()?;
+        let mut rules = Vec::new();
+        if self.check(TokenKind::LParen) {
+            rules.push(self.parse_macro_rule()?);
+        } else {
+            self.expect(TokenKind::LBrace)?;
+            // Rules are separated by commas, since a template can be
+            // followed by `(` on the next line
+            while !self.check(TokenKind::RBrace) && !self.is_eof() {
+                rules.push(self.parse_macro_rule()?);
+                if !self.check(TokenKind::Comma) {
+                    break;
+                }
+                self.advance();
+            }
+            self.expect(TokenKind::RBrace)?;
+            if rules.is_empty() {
+                return Err(Diagnostic::error(
+                    crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN,
+                )
+                .message(format!("Macro `{}` needs at least one rule", name))
+                .span(start_span.merge(&self.previous_span()))
+                .build());
+            }
+        }
+        let end_span = self.previous_span();
+
+        Ok(MacroDef {
+            id: NodeId::new(),
+            span: start_span.merge(&end_span),
+            name,
+            rules,
+            doc: None,
+        })
+    }
+
+    /// Parse one `($a, $b: ident, $rest...) => template` macro rule
+    fn parse_macro_rule(&mut self) -> Result<MacroRule, Diagnostic> {
+        let start_span = self.current_span();
+        self.expect(TokenKind::LParen)?;
+        let mut params: Vec<MacroParam> = Vec::new();
+        while !self.check(TokenKind::RParen) {
+            if params.last().is_some_and(|param| param.variadic) {
+                return Err(self.error_unexpected("`)` after a variadic macro parameter"));
+            }
+            let token = self.advance();
+            let TokenKind::MacroVar(var) = token.kind else {
+                return Err(Diagnostic::error(
+                    crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN,
+                )
+                .message(format!(
+                    "Expected a macro parameter like `$name`, found {:?}",
+                    token.kind
+                ))
+                .span(token.span)
+                .build());
+            };
+            let fragment = if self.check(TokenKind::Colon) {
+                self.advance();
+                let kind_span = self.current_span();
+                match self.expect_ident()?.as_str() {
+                    "expr" => MacroFragment::Expr,
+                    "ident" => MacroFragment::Ident,
+                    other => {
+                        return Err(Diagnostic::error(
+                            crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN,
+                        )
+                        .message(format!(
+                            "Unknown macro fragment `{}`; expected `expr` or `ident`",
+                            other
+                        ))
+                        .span(kind_span)
+                        .build())
+                    }
+                }
+            } else {
+                MacroFragment::Expr
+            };
+            let variadic = self.check(TokenKind::Ellipsis);
+            if variadic {
+                self.advance();
+            }
+            params.push(MacroParam {
+                span: token.span.merge(&self.previous_span()),
+                name: var[1..].to_string(),
+                fragment,
+                variadic,
+            });
+            if !self.check(TokenKind::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        self.expect(TokenKind::RParen)?;
+        self.expect(TokenKind::FatArrow)?;
+
+        let in_template = std::mem::replace(&mut self.in_template, true);
+        let template = self.parse_expr();
+        self.in_template = in_template;
+        let template = template?;
+        let end_span = self.previous_span();
+
+        Ok(MacroRule {
+            id: NodeId::new(),
+            span: start_span.merge(&end_span),
+            params,
+            template,
+        })
+    }
+
     /// Parse a block of function signatures (used by trait and effect definitions).
     /// Parses `fn name(params) -> RetType` entries until `}`.
     fn parse_fn_signatures(&mut self) -> Result<Vec<TraitMethod>, Diagnostic> {
@@ -352,12 +667,25 @@ impl<'a> Parser<'a> {
             };
             let method_end = self.current_span();
 
+            let mut requires = Vec::new();
+            while self.check(TokenKind::Requires) {
+                self.advance();
+                requires.push(self.parse_expr()?);
+            }
+            let mut ensures = Vec::new();
+            while self.check(TokenKind::Ensures) {
+                self.advance();
+                ensures.push(self.parse_expr()?);
+            }
+
             methods.push(TraitMethod {
                 id: NodeId::new(),
                 span: method_start.merge(&method_end),
                 name: method_name,
                 params,
                 return_type,
+                requires,
+                ensures,
             });
         }
         Ok(methods)
@@ -395,18 +723,14 @@ impl<'a> Parser<'a> {
             None
         };
 
-        let effects = if self.check(TokenKind::Effects) {
+        let signature_end = self.previous_span();
+        let (effects, effects_span) = if self.check(TokenKind::Effects) {
+            let clause_start = self.current_span();
             self.advance();
-            self.expect(TokenKind::LParen)?;
-            let mut effects = vec![self.expect_ident()?];
-            while self.check(TokenKind::Comma) {
-                self.advance();
-                effects.push(self.expect_ident()?);
-            }
-            self.expect(TokenKind::RParen)?;
-            effects
+            let effects = self.parse_effect_list()?;
+            (effects, clause_start.merge(&self.previous_span()))
         } else {
-            Vec::new()
+            (Vec::new(), signature_end.end_point())
         };
 
         // Parse optional requires clauses
@@ -425,7 +749,7 @@ impl<'a> Parser<'a> {
 
         let body = self.parse_block()?;
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(FnDef {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -437,9 +761,12 @@ impl<'a> Parser<'a> {
             params,
             return_type,
             effects,
+            effects_span: Some(effects_span),
             requires,
             ensures,
             body,
+            attributes: Vec::new(),
+            doc: None,
         })
     }
 
@@ -466,7 +793,7 @@ impl<'a> Parser<'a> {
             let pattern = self.parse_pattern()?;
             self.expect(TokenKind::Colon)?;
             let ty = self.parse_type_expr()?;
-            let end_span = self.current_span();
+            let end_span = self.previous_span();
             // Use a generated name for the parameter
             let name = format!("__destructured_{}", NodeId::new().0);
             return Ok(Param {
@@ -482,7 +809,7 @@ impl<'a> Parser<'a> {
 
         // Check for `self` parameter (no type annotation)
         if name == "self" && !self.check(TokenKind::Colon) {
-            let end_span = self.current_span();
+            let end_span = self.previous_span();
             return Ok(Param {
                 id: NodeId::new(),
                 span: start_span.merge(&end_span),
@@ -500,7 +827,7 @@ impl<'a> Parser<'a> {
         self.expect(TokenKind::Colon)?;
         let ty = self.parse_type_expr()?;
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(Param {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -524,7 +851,7 @@ impl<'a> Parser<'a> {
 
         let body = self.parse_block()?;
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(TestBlock {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -548,7 +875,7 @@ impl<'a> Parser<'a> {
 
         let body = self.parse_block()?;
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(PropertyBlock {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -558,7 +885,47 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parse a `before_each` or `after_each` block
+    fn parse_test_hook(&mut self, kind: HookKind) -> Result<TestHook, Diagnostic> {
+        let start_span = self.current_span();
+        self.advance();
+
+        // Parse optional using clause
+        let using = if self.check(TokenKind::Using) {
+            Some(self.parse_using_clause()?)
+        } else {
+            None
+        };
+
+        let body = self.parse_block()?;
+
+        let end_span = self.previous_span();
+        Ok(TestHook {
+            id: NodeId::new(),
+            span: start_span.merge(&end_span),
+            kind,
+            using,
+            body,
+        })
+    }
+
     /// Parse `using effects(Effect = Expr, ...)` clause
+    /// The parenthesized effects of an `effects(...)` clause, which may be
+    /// empty to say a function uses none
+    fn parse_effect_list(&mut self) -> Result<Vec<String>, Diagnostic> {
+        self.expect(TokenKind::LParen)?;
+        let mut effects = Vec::new();
+        if !self.check(TokenKind::RParen) {
+            effects.push(self.expect_ident()?);
+            while self.check(TokenKind::Comma) {
+                self.advance();
+                effects.push(self.expect_ident()?);
+            }
+        }
+        self.expect(TokenKind::RParen)?;
+        Ok(effects)
+    }
+
     fn parse_using_clause(&mut self) -> Result<UsingClause, Diagnostic> {
         let start_span = self.current_span();
         self.expect(TokenKind::Using)?;
@@ -580,7 +947,7 @@ impl<'a> Parser<'a> {
 
         self.expect(TokenKind::RParen)?;
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(UsingClause {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -595,7 +962,7 @@ impl<'a> Parser<'a> {
         self.expect(TokenKind::Eq)?;
         let value = self.parse_expr()?;
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(EffectBinding {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -622,7 +989,7 @@ impl<'a> Parser<'a> {
             }
             self.expect(TokenKind::RBrace)?;
 
-            let end_span = self.current_span();
+            let end_span = self.previous_span();
             Ok(TypeExpr::Record {
                 id: NodeId::new(),
                 span: start_span.merge(&end_span),
@@ -658,19 +1025,12 @@ impl<'a> Parser<'a> {
                 // Optional effects
                 let effects = if self.check(TokenKind::Effects) {
                     self.advance();
-                    self.expect(TokenKind::LParen)?;
-                    let mut effects = vec![self.expect_ident()?];
-                    while self.check(TokenKind::Comma) {
-                        self.advance();
-                        effects.push(self.expect_ident()?);
-                    }
-                    self.expect(TokenKind::RParen)?;
-                    effects
+                    self.parse_effect_list()?
                 } else {
                     Vec::new()
                 };
 
-                let end_span = self.current_span();
+                let end_span = self.previous_span();
                 Ok(TypeExpr::Function {
                     id: NodeId::new(),
                     span: start_span.merge(&end_span),
@@ -680,7 +1040,7 @@ impl<'a> Parser<'a> {
                 })
             } else if types.is_empty() {
                 // () = Unit type
-                let end_span = self.current_span();
+                let end_span = self.previous_span();
                 Ok(TypeExpr::Named {
                     id: NodeId::new(),
                     span: start_span.merge(&end_span),
@@ -692,7 +1052,7 @@ impl<'a> Parser<'a> {
                 Ok(types.into_iter().next().unwrap())
             } else {
                 // (T1, T2, ...) = tuple type
-                let end_span = self.current_span();
+                let end_span = self.previous_span();
                 Ok(TypeExpr::Tuple {
                     id: NodeId::new(),
                     span: start_span.merge(&end_span),
@@ -703,7 +1063,7 @@ impl<'a> Parser<'a> {
             let name = self.expect_ident()?;
             let args = self.parse_optional_type_args()?;
 
-            let end_span = self.current_span();
+            let end_span = self.previous_span();
             Ok(TypeExpr::Named {
                 id: NodeId::new(),
                 span: start_span.merge(&end_span),
@@ -765,7 +1125,7 @@ impl<'a> Parser<'a> {
         let (stmts, expr) = self.parse_block_stmts()?;
         self.expect(TokenKind::RBrace)?;
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(Block {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -781,6 +1141,7 @@ impl<'a> Parser<'a> {
         let mut expr = None;
 
         while !self.check(TokenKind::RBrace) && !self.is_eof() {
+            let start = self.current_span().start;
             match self.parse_block_element(&mut stmts, &mut expr) {
                 Ok(done) => {
                     if done {
@@ -792,6 +1153,10 @@ impl<'a> Parser<'a> {
                     // next statement boundary so we can report multiple errors.
                     self.errors.push(diag);
                     self.recover_to_next_stmt();
+                    // Stuck on an item keyword: leave the block to item-level recovery
+                    if self.current_span().start == start {
+                        break;
+                    }
                 }
             }
         }
@@ -807,7 +1172,29 @@ impl<'a> Parser<'a> {
         stmts: &mut Vec<Stmt>,
         expr: &mut Option<Box<Expr>>,
     ) -> Result<bool, Diagnostic> {
-        if self.check(TokenKind::Let) || self.check(TokenKind::Return) {
+        if self.check(TokenKind::At) {
+            let attributes = self.parse_attributes()?;
+            if !self.check(TokenKind::Let) {
+                return Err(Diagnostic::error(
+                    crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN,
+                )
+                .message("Attributes in a block can only be applied to `let` statements")
+                .span(self.current_span())
+                .build());
+            }
+            let mut stmt = self.parse_stmt()?;
+            if let Stmt::Let {
+                attributes: attrs, ..
+            }
+            | Stmt::LetPattern {
+                attributes: attrs, ..
+            } = &mut stmt
+            {
+                *attrs = attributes;
+            }
+            stmts.push(stmt);
+            Ok(false)
+        } else if self.check(TokenKind::Let) || self.check(TokenKind::Return) {
             stmts.push(self.parse_stmt()?);
             Ok(false)
         } else if self.check(TokenKind::Fn) && matches!(self.peek2().kind, TokenKind::Ident(_)) {
@@ -885,13 +1272,14 @@ impl<'a> Parser<'a> {
                 };
                 self.expect(TokenKind::Eq)?;
                 let value = Box::new(self.parse_expr()?);
-                let end_span = self.current_span();
+                let end_span = self.previous_span();
                 return Ok(Stmt::LetPattern {
                     id: NodeId::new(),
                     span: start_span.merge(&end_span),
                     pattern,
                     ty,
                     value,
+                    attributes: Vec::new(),
                 });
             }
 
@@ -909,7 +1297,7 @@ impl<'a> Parser<'a> {
             self.expect(TokenKind::Eq)?;
             let value = Box::new(self.parse_expr()?);
 
-            let end_span = self.current_span();
+            let end_span = self.previous_span();
             Ok(Stmt::Let {
                 id: NodeId::new(),
                 span: start_span.merge(&end_span),
@@ -917,6 +1305,7 @@ impl<'a> Parser<'a> {
                 mutable,
                 ty,
                 value,
+                attributes: Vec::new(),
             })
         } else if self.check(TokenKind::Return) {
             self.advance();
@@ -926,7 +1315,7 @@ impl<'a> Parser<'a> {
                 Some(Box::new(self.parse_expr()?))
             };
 
-            let end_span = self.current_span();
+            let end_span = self.previous_span();
             Ok(Stmt::Return {
                 id: NodeId::new(),
                 span: start_span.merge(&end_span),
@@ -1068,7 +1457,7 @@ impl<'a> Parser<'a> {
                     }
                 }
                 self.expect(TokenKind::RParen)?;
-                let end_span = self.current_span();
+                let end_span = self.previous_span();
                 expr = Expr::Call {
                     id: NodeId::new(),
                     span: start_span.merge(&end_span),
@@ -1086,6 +1475,7 @@ impl<'a> Parser<'a> {
                 } else {
                     self.expect_ident()?
                 };
+                let name_span = self.previous_span();
 
                 if self.check(TokenKind::LParen) {
                     self.advance();
@@ -1101,21 +1491,23 @@ impl<'a> Parser<'a> {
                         }
                     }
                     self.expect(TokenKind::RParen)?;
-                    let end_span = self.current_span();
+                    let end_span = self.previous_span();
                     expr = Expr::MethodCall {
                         id: NodeId::new(),
                         span: start_span.merge(&end_span),
                         receiver: Box::new(expr),
                         method: name,
                         args,
+                        method_span: Some(name_span),
                     };
                 } else {
-                    let end_span = self.current_span();
+                    let end_span = self.previous_span();
                     expr = Expr::FieldAccess {
                         id: NodeId::new(),
                         span: start_span.merge(&end_span),
                         expr: Box::new(expr),
                         field: name,
+                        field_span: Some(name_span),
                     };
                 }
             } else if self.check(TokenKind::QuestionElse) {
@@ -1132,7 +1524,7 @@ impl<'a> Parser<'a> {
             } else if self.check(TokenKind::Question) {
                 let start_span = self.expr_span(&expr);
                 self.advance();
-                let end_span = self.current_span();
+                let end_span = self.previous_span();
                 expr = Expr::Try {
                     id: NodeId::new(),
                     span: start_span.merge(&end_span),
@@ -1145,7 +1537,7 @@ impl<'a> Parser<'a> {
                 self.advance();
                 let index = self.parse_expr()?;
                 self.expect(TokenKind::RBracket)?;
-                let end_span = self.current_span();
+                let end_span = self.previous_span();
                 expr = Expr::IndexAccess {
                     id: NodeId::new(),
                     span: start_span.merge(&end_span),
@@ -1166,6 +1558,9 @@ impl<'a> Parser<'a> {
         match &token.kind {
             TokenKind::FloatLit(n) => {
                 let value = *n;
+                if !value.is_finite() {
+                    return Err(self.float_out_of_range(&token.span));
+                }
                 self.advance();
                 Ok(Expr::FloatLit {
                     id: NodeId::new(),
@@ -1173,6 +1568,24 @@ impl<'a> Parser<'a> {
                     value,
                 })
             }
+            TokenKind::DecimalLit(text) => {
+                // Checked here so an out-of-range literal never reaches run time
+                let value = rust_decimal::Decimal::from_str_exact(text).map_err(|_| {
+                    Diagnostic::error(crate::diagnostics::error_codes::syntax::INVALID_NUMBER)
+                        .message(format!(
+                            "Invalid number literal: `{}d` does not fit in a Decimal",
+                            text
+                        ))
+                        .span(token.span.clone())
+                        .build()
+                })?;
+                self.advance();
+                Ok(Expr::DecimalLit {
+                    id: NodeId::new(),
+                    span: token.span,
+                    value,
+                })
+            }
             TokenKind::IntLit(n) => {
                 let value = *n;
                 self.advance();
@@ -1202,7 +1615,7 @@ impl<'a> Parser<'a> {
                 let value = s.clone();
                 self.advance();
                 // Check for string interpolation: "${...}"
-                if value.contains("${") {
+                if find_interpolation(&value).is_some() {
                     self.parse_string_interp(&value, &token.span)
                 } else {
                     match unescape_string(&value) {
@@ -1225,7 +1638,7 @@ impl<'a> Parser<'a> {
                 self.advance();
                 let dedented = dedent_multiline_string(&raw);
                 // Check for string interpolation: "${...}"
-                if dedented.contains("${") {
+                if find_interpolation(&dedented).is_some() {
                     self.parse_string_interp(&dedented, &token.span)
                 } else {
                     match unescape_string(&dedented) {
@@ -1243,6 +1656,16 @@ impl<'a> Parser<'a> {
                     }
                 }
             }
+            TokenKind::Ident(name) if self.peek2().kind == TokenKind::Bang => {
+                let name = name.clone();
+                self.parse_macro_call(name)
+            }
+            // `exists` is only a keyword when a binding follows, so `Fs.exists` still works
+            TokenKind::Ident(name)
+                if name == "exists" && matches!(self.peek2().kind, TokenKind::Ident(_)) =>
+            {
+                self.parse_quantifier_expr(QuantifierKind::Exists)
+            }
             TokenKind::Ident(name) => {
                 let name = name.clone();
                 self.advance();
@@ -1252,6 +1675,27 @@ impl<'a> Parser<'a> {
                     name,
                 })
             }
+            TokenKind::MacroVar(name) if self.in_template => {
+                let mut name = name.clone();
+                self.advance();
+                let mut span = token.span;
+                if self.check(TokenKind::Ellipsis) {
+                    self.advance();
+                    name.push_str("...");
+                    span = span.merge(&self.previous_span());
+                }
+                Ok(Expr::Ident {
+                    id: NodeId::new(),
+                    span,
+                    name,
+                })
+            }
+            TokenKind::MacroVar(name) => Err(Diagnostic::error(
+                crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN,
+            )
+            .message(format!("`{}` can only be used in a macro template", name))
+            .span(token.span)
+            .build()),
             TokenKind::LBrace => self.parse_brace_expr(),
             TokenKind::LParen => {
                 self.advance();
@@ -1274,7 +1718,7 @@ impl<'a> Parser<'a> {
                         elements.push(self.parse_expr()?);
                     }
                     self.expect(TokenKind::RParen)?;
-                    let end_span = self.current_span();
+                    let end_span = self.previous_span();
                     return Ok(Expr::TupleLit {
                         id: NodeId::new(),
                         span: token.span.merge(&end_span),
@@ -1287,6 +1731,7 @@ impl<'a> Parser<'a> {
             TokenKind::LBracket => self.parse_list_expr(),
             TokenKind::Fn => self.parse_lambda_expr(),
             TokenKind::For => self.parse_for_expr(),
+            TokenKind::Forall => self.parse_quantifier_expr(QuantifierKind::Forall),
             TokenKind::While => self.parse_while_expr(),
             TokenKind::If => self.parse_if_expr(),
             TokenKind::Match => self.parse_match_expr(),
@@ -1316,6 +1761,31 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parse a macro invocation `name!(args)`, leaving it unexpanded
+    fn parse_macro_call(&mut self, name: String) -> Result<Expr, Diagnostic> {
+        let start_span = self.current_span();
+        self.advance();
+        self.expect(TokenKind::Bang)?;
+        self.expect(TokenKind::LParen)?;
+        let mut args = Vec::new();
+        while !self.check(TokenKind::RParen) {
+            args.push(self.parse_expr()?);
+            if !self.check(TokenKind::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        self.expect(TokenKind::RParen)?;
+        let end_span = self.previous_span();
+        Ok(Expr::MacroCall {
+            id: NodeId::new(),
+            span: start_span.merge(&end_span),
+            name,
+            args,
+            expansion: None,
+        })
+    }
+
     /// Parse a `{...}` expression, disambiguating between record literals and block expressions.
     ///
     /// Record: `{ name = expr, ... }`
@@ -1327,7 +1797,7 @@ impl<'a> Parser<'a> {
         // Empty braces: `{}` → empty record
         if self.check(TokenKind::RBrace) {
             self.advance();
-            let end_span = self.current_span();
+            let end_span = self.previous_span();
             return Ok(Expr::Record {
                 id: NodeId::new(),
                 span: start_span.merge(&end_span),
@@ -1360,6 +1830,7 @@ impl<'a> Parser<'a> {
                 | TokenKind::False
                 | TokenKind::IntLit(_)
                 | TokenKind::FloatLit(_)
+                | TokenKind::DecimalLit(_)
                 | TokenKind::TextLit(_)
                 | TokenKind::MultilineTextLit(_)
                 | TokenKind::LBracket
@@ -1400,7 +1871,7 @@ impl<'a> Parser<'a> {
                         fields.push((field_name, Box::new(field_value)));
                     }
                     self.expect(TokenKind::RBrace)?;
-                    let end_span = self.current_span();
+                    let end_span = self.previous_span();
                     return Ok(Expr::Record {
                         id: NodeId::new(),
                         span: start_span.merge(&end_span),
@@ -1409,7 +1880,7 @@ impl<'a> Parser<'a> {
                 } else if self.check(TokenKind::RBrace) {
                     // Single-field record: `{ name = expr }`
                     self.advance();
-                    let end_span = self.current_span();
+                    let end_span = self.previous_span();
                     return Ok(Expr::Record {
                         id: NodeId::new(),
                         span: start_span.merge(&end_span),
@@ -1483,7 +1954,7 @@ impl<'a> Parser<'a> {
                         }
                     }
                     self.expect(TokenKind::RBrace)?;
-                    let end_span = self.current_span();
+                    let end_span = self.previous_span();
                     return Ok(Expr::Block {
                         id: NodeId::new(),
                         span: start_span.merge(&end_span),
@@ -1508,7 +1979,7 @@ impl<'a> Parser<'a> {
     fn parse_block_body(&mut self, start_span: Span) -> Result<Expr, Diagnostic> {
         let (stmts, expr) = self.parse_block_stmts()?;
         self.expect(TokenKind::RBrace)?;
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
 
         Ok(Expr::Block {
             id: NodeId::new(),
@@ -1554,7 +2025,7 @@ impl<'a> Parser<'a> {
         };
 
         let body = Box::new(self.parse_block()?);
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
 
         Ok(Stmt::Let {
             id: NodeId::new(),
@@ -1569,6 +2040,7 @@ impl<'a> Parser<'a> {
                 return_type,
                 body,
             }),
+            attributes: Vec::new(),
         })
     }
 
@@ -1589,7 +2061,7 @@ impl<'a> Parser<'a> {
         }
 
         self.expect(TokenKind::RBracket)?;
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
 
         Ok(Expr::ListLit {
             id: NodeId::new(),
@@ -1626,7 +2098,7 @@ impl<'a> Parser<'a> {
         };
 
         let body = Box::new(self.parse_block()?);
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
 
         Ok(Expr::Lambda {
             id: NodeId::new(),
@@ -1646,7 +2118,7 @@ impl<'a> Parser<'a> {
         } else {
             None
         };
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(LambdaParam {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -1671,7 +2143,7 @@ impl<'a> Parser<'a> {
         self.expect(TokenKind::In)?;
         let iter = Box::new(self.parse_expr()?);
         let body = Box::new(self.parse_block()?);
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
 
         Ok(Expr::ForIn {
             id: NodeId::new(),
@@ -1683,6 +2155,28 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// `forall x in xs: cond` or `exists x in xs: cond`; the condition extends
+    /// as far as an expression can
+    fn parse_quantifier_expr(&mut self, kind: QuantifierKind) -> Result<Expr, Diagnostic> {
+        let start_span = self.current_span();
+        self.advance(); // `forall` or `exists`
+        let binding = self.expect_ident()?;
+        self.expect(TokenKind::In)?;
+        let iter = Box::new(self.parse_expr()?);
+        self.expect(TokenKind::Colon)?;
+        let body = Box::new(self.parse_expr()?);
+        let end_span = self.previous_span();
+
+        Ok(Expr::Quantifier {
+            id: NodeId::new(),
+            span: start_span.merge(&end_span),
+            kind,
+            binding,
+            iter,
+            body,
+        })
+    }
+
     fn parse_while_expr(&mut self) -> Result<Expr, Diagnostic> {
         let start_span = self.current_span();
         self.expect(TokenKind::While)?;
@@ -1701,7 +2195,7 @@ impl<'a> Parser<'a> {
         }
 
         let body = Box::new(self.parse_block()?);
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
 
         Ok(Expr::While {
             id: NodeId::new(),
@@ -1748,7 +2242,7 @@ impl<'a> Parser<'a> {
                 None
             };
 
-            let end_span = self.current_span();
+            let end_span = self.previous_span();
             Ok(Expr::If {
                 id: NodeId::new(),
                 span: start_span.merge(&end_span),
@@ -1775,7 +2269,7 @@ impl<'a> Parser<'a> {
                 None
             };
 
-            let end_span = self.current_span();
+            let end_span = self.previous_span();
             Ok(Expr::If {
                 id: NodeId::new(),
                 span: start_span.merge(&end_span),
@@ -1804,7 +2298,7 @@ impl<'a> Parser<'a> {
             vec![self.parse_expr()?]
         };
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
 
         // Parse assert as a call to the builtin assert function
         Ok(Expr::Call {
@@ -1834,7 +2328,7 @@ impl<'a> Parser<'a> {
         }
 
         self.expect(TokenKind::RBrace)?;
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
 
         Ok(Expr::Match {
             id: NodeId::new(),
@@ -1859,7 +2353,7 @@ impl<'a> Parser<'a> {
         self.expect(TokenKind::FatArrow)?;
         let body = Box::new(self.parse_expr()?);
 
-        let end_span = self.current_span();
+        let end_span = self.previous_span();
         Ok(MatchArm {
             id: NodeId::new(),
             span: start_span.merge(&end_span),
@@ -1914,6 +2408,15 @@ impl<'a> Parser<'a> {
                     value,
                 })
             }
+            TokenKind::MacroVar(name) if self.in_template => {
+                let name = name.clone();
+                self.advance();
+                Ok(Pattern::Ident {
+                    id: NodeId::new(),
+                    span: token.span,
+                    name,
+                })
+            }
             TokenKind::Ident(name) => {
                 let name = name.clone();
                 self.advance();
@@ -1932,7 +2435,7 @@ impl<'a> Parser<'a> {
                         }
                     }
                     self.expect(TokenKind::RParen)?;
-                    let end_span = self.current_span();
+                    let end_span = self.previous_span();
                     Ok(Pattern::Variant {
                         id: NodeId::new(),
                         span: token.span.merge(&end_span),
@@ -1960,20 +2463,32 @@ impl<'a> Parser<'a> {
                 }
             }
             TokenKind::LBrace => {
-                // Record pattern: { x, y } or { x = pat, y = pat }
+                // Record pattern: { x, y }, { x: pat, y }, or { x, .. }; `x = pat` is
+                // the older spelling of `x: pat`
                 self.advance();
                 let mut fields = Vec::new();
+                let mut rest = false;
                 if !self.check(TokenKind::RBrace) {
                     loop {
+                        if self.check(TokenKind::DotDot) {
+                            // `..` ignores the remaining fields and must come last
+                            self.advance();
+                            rest = true;
+                            if self.check(TokenKind::Comma) {
+                                self.advance();
+                            }
+                            break;
+                        }
                         let field_name = self.expect_ident()?;
-                        let field_pat = if self.check(TokenKind::Eq) {
+                        let field_pat = if self.check(TokenKind::Eq) || self.check(TokenKind::Colon)
+                        {
                             self.advance();
                             self.parse_pattern()?
                         } else {
                             // Shorthand: `{ x }` means `{ x = x }`
                             Pattern::Ident {
                                 id: NodeId::new(),
-                                span: self.current_span(),
+                                span: self.previous_span(),
                                 name: field_name.clone(),
                             }
                         };
@@ -1988,15 +2503,19 @@ impl<'a> Parser<'a> {
                     }
                 }
                 self.expect(TokenKind::RBrace)?;
-                let end_span = self.current_span();
+                let end_span = self.previous_span();
                 Ok(Pattern::Record {
                     id: NodeId::new(),
                     span: token.span.merge(&end_span),
                     fields,
+                    rest,
                 })
             }
             TokenKind::FloatLit(n) => {
                 let value = *n;
+                if !value.is_finite() {
+                    return Err(self.float_out_of_range(&token.span));
+                }
                 self.advance();
                 Ok(Pattern::FloatLit {
                     id: NodeId::new(),
@@ -2019,7 +2538,7 @@ impl<'a> Parser<'a> {
                     }
                 }
                 self.expect(TokenKind::RParen)?;
-                let end_span = self.current_span();
+                let end_span = self.previous_span();
                 Ok(Pattern::Tuple {
                     id: NodeId::new(),
                     span: token.span.merge(&end_span),
@@ -2039,6 +2558,14 @@ impl<'a> Parser<'a> {
         self.peeked.clone().unwrap()
     }
 
+    /// Mutable access to the next token
+    fn peek_mut(&mut self) -> &mut Token {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lexer.next_token());
+        }
+        self.peeked.as_mut().unwrap()
+    }
+
     /// Peek at the second upcoming token (two-token lookahead)
     fn peek2(&mut self) -> Token {
         // Ensure first peeked token is buffered
@@ -2050,7 +2577,7 @@ impl<'a> Parser<'a> {
     }
 
     fn advance(&mut self) -> Token {
-        if let Some(token) = self.peeked.take() {
+        let token = if let Some(token) = self.peeked.take() {
             // Move peeked2 into peeked if it exists
             if self.peeked2.is_some() {
                 self.peeked = self.peeked2.take();
@@ -2058,7 +2585,9 @@ impl<'a> Parser<'a> {
             token
         } else {
             self.lexer.next_token()
-        }
+        };
+        self.previous = Some(token.span.clone());
+        token
     }
 
     fn is_eof(&mut self) -> bool {
@@ -2069,6 +2598,14 @@ impl<'a> Parser<'a> {
         self.peek().span.clone()
     }
 
+    /// Span of the last consumed token, used to end a node's span
+    fn previous_span(&mut self) -> Span {
+        match &self.previous {
+            Some(span) => span.clone(),
+            None => self.current_span(),
+        }
+    }
+
     fn check(&mut self, kind: TokenKind) -> bool {
         std::mem::discriminant(&self.peek().kind) == std::mem::discriminant(&kind)
     }
@@ -2100,10 +2637,24 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// The error for a float literal too large for a Float, such as `1e400`,
+    /// which the lexer reads as infinity; like an Int literal that doesn't
+    /// fit, it never reaches run time
+    fn float_out_of_range(&self, span: &Span) -> Diagnostic {
+        Diagnostic::error(crate::diagnostics::error_codes::syntax::INVALID_NUMBER)
+            .message(format!(
+                "Invalid number literal: `{}` does not fit in a Float",
+                &self.source.content()[span.start..span.end]
+            ))
+            .span(span.clone())
+            .build()
+    }
+
     fn expect_ident(&mut self) -> Result<String, Diagnostic> {
         let token = self.advance();
         match token.kind {
             TokenKind::Ident(name) => Ok(name),
+            TokenKind::MacroVar(name) if self.in_template => Ok(name),
             _ => Err(
                 Diagnostic::error(crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN)
                     .message(format!("Expected identifier, found {:?}", token.kind))
@@ -2137,8 +2688,11 @@ impl<'a> Parser<'a> {
     fn parse_string_interp(&mut self, raw: &str, span: &Span) -> Result<Expr, Diagnostic> {
         let mut parts = Vec::new();
         let mut remaining = raw;
+        // Byte offset of the literal's contents, just past the opening quote
+        let base = span.start + 1;
+        let aligned = self.source.content().get(base..base + raw.len()) == Some(raw);
 
-        while let Some(dollar_pos) = remaining.find("${") {
+        while let Some(dollar_pos) = find_interpolation(remaining) {
             // Add literal part before ${
             if dollar_pos > 0 {
                 match unescape_string(&remaining[..dollar_pos]) {
@@ -2183,6 +2737,27 @@ impl<'a> Parser<'a> {
             }
 
             let expr_str = &remaining[expr_start..end_pos];
+            // Parse in place when the literal appears verbatim in the source
+            // (not dedented), so the expression's spans point into the file
+            if aligned {
+                let offset = base + (raw.len() - remaining.len()) + expr_start;
+                match self.parse_embedded_expr(offset, expr_str) {
+                    Some(expr) => parts.push(StringPart::Expr(Box::new(expr))),
+                    None => {
+                        return Err(Diagnostic::error(
+                            crate::diagnostics::error_codes::syntax::UNEXPECTED_TOKEN,
+                        )
+                        .message(format!(
+                            "Invalid expression in string interpolation: {}",
+                            expr_str
+                        ))
+                        .span(span.clone())
+                        .build());
+                    }
+                }
+                remaining = &remaining[end_pos + 1..];
+                continue;
+            }
             // Parse the expression string
             let source_file = crate::parser::span::SourceFile::new(
                 std::path::PathBuf::from("<interp>"),
@@ -2190,8 +2765,10 @@ impl<'a> Parser<'a> {
             );
             let lexer = Lexer::new(&source_file);
             let mut parser = Parser::new(lexer, source_file.clone());
-            // Parse module, extract the expression from the function body
-            match parser.parse_module() {
+            parser.in_template = self.in_template;
+            // Parse module, extract the expression from the function body;
+            // macro calls in it are expanded along with the enclosing module
+            match parser.parse_module_unexpanded() {
                 Ok(module) => {
                     if let Some(Item::FnDef(f)) = module.items.first() {
                         if let Some(expr) = &f.body.expr {
@@ -2239,10 +2816,30 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parse an expression that appears at byte `offset` of the source, such
+    /// as the contents of `${...}`, keeping its spans relative to the file
+    fn parse_embedded_expr(&self, offset: usize, text: &str) -> Option<Expr> {
+        // Blank out everything before the expression, keeping byte offsets and
+        // line breaks, so positions computed by the nested parser line up
+        let padding: String = self.source.content()[..offset]
+            .chars()
+            .map(|c| match c {
+                '\n' => "\n".to_string(),
+                c => " ".repeat(c.len_utf8()),
+            })
+            .collect();
+        let source_file = SourceFile::new(self.source.path().clone(), padding + text);
+        let lexer = Lexer::new(&source_file);
+        let mut parser = Parser::new(lexer, source_file.clone());
+        let expr = parser.parse_expr().ok()?;
+        parser.check(TokenKind::Eof).then_some(expr)
+    }
+
     fn expr_span(&self, expr: &Expr) -> Span {
         match expr {
             Expr::IntLit { span, .. }
             | Expr::FloatLit { span, .. }
+            | Expr::DecimalLit { span, .. }
             | Expr::BoolLit { span, .. }
             | Expr::TextLit { span, .. }
             | Expr::UnitLit { span, .. }
@@ -2271,7 +2868,9 @@ impl<'a> Parser<'a> {
             | Expr::Range { span, .. }
             | Expr::IndexAccess { span, .. }
             | Expr::Await { span, .. }
-            | Expr::Hole { span, .. } => span.clone(),
+            | Expr::Quantifier { span, .. }
+            | Expr::Hole { span, .. }
+            | Expr::MacroCall { span, .. } => span.clone(),
         }
     }
 
@@ -2287,7 +2886,9 @@ impl<'a> Parser<'a> {
                 | TokenKind::Trait
                 | TokenKind::Impl
                 | TokenKind::Test
-                | TokenKind::Property => return,
+                | TokenKind::Property
+                | TokenKind::BeforeEach
+                | TokenKind::AfterEach => return,
                 _ => {
                     self.advance();
                 }
@@ -2314,7 +2915,9 @@ impl<'a> Parser<'a> {
                 | TokenKind::Trait
                 | TokenKind::Impl
                 | TokenKind::Test
-                | TokenKind::Property => return,
+                | TokenKind::Property
+                | TokenKind::BeforeEach
+                | TokenKind::AfterEach => return,
                 _ => {
                     self.advance();
                 }
@@ -2323,8 +2926,30 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// Whether `items` already contain a test hook of this kind
+fn has_hook(items: &[Item], kind: HookKind) -> bool {
+    items
+        .iter()
+        .any(|item| matches!(item, Item::TestHook(hook) if hook.kind == kind))
+}
+
 /// Process escape sequences in a string literal.
 /// Returns Err with a description if an invalid escape sequence is found.
+/// Byte offset of the first `${` in raw literal text that isn't escaped as `\${`
+fn find_interpolation(raw: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in raw.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '$' && raw[i + 1..].starts_with('{') {
+            return Some(i);
+        }
+    }
+    None
+}
+
 fn unescape_string(s: &str) -> Result<String, (String, char)> {
     let mut result = String::with_capacity(s.len());
     let mut chars = s.chars();
@@ -2393,6 +3018,23 @@ fn dedent_multiline_string(s: &str) -> String {
         .collect::<Vec<_>>()
         .join("\n")
 }
+
+/// Join doc comment lines (without their `##` marker) into doc text,
+/// dropping the single space that conventionally follows the marker
+fn doc_text(lines: &[String]) -> Option<String> {
+    if lines.is_empty() {
+        return None;
+    }
+    let text: Vec<&str> = lines
+        .iter()
+        .map(|line| {
+            let line = line.trim_end();
+            line.strip_prefix(' ').unwrap_or(line)
+        })
+        .collect();
+    Some(text.join("\n"))
+}
+
 #[cfg(test)]
 #[path = "parser_tests.rs"]
 mod tests;
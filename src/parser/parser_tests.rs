@@ -30,6 +30,15 @@ fn test_unescape_invalid_sequences() {
     assert_eq!(err.1, 'q');
 }
 
+#[test]
+fn test_find_interpolation_skips_escaped_dollars() {
+    assert_eq!(find_interpolation("a ${b}"), Some(2));
+    assert_eq!(find_interpolation(r"a \${b}"), None);
+    assert_eq!(find_interpolation(r"a \\${b}"), Some(4));
+    assert_eq!(find_interpolation(r"\${a} ${b}"), Some(6));
+    assert_eq!(find_interpolation("$5 {}"), None);
+}
+
 #[test]
 fn test_dedent_multiline_string() {
     // Basic dedent
@@ -48,3 +57,103 @@ fn test_dedent_multiline_string() {
     let input4 = "\n    hello\n    ";
     assert_eq!(dedent_multiline_string(input4), "hello");
 }
+
+#[test]
+fn test_doc_comments_attach_to_items() {
+    let module = crate::parser::parse_source(
+        "##! Shapes.\n##!\n##!   Indented.\nmodule shapes\n\n## A point.\ntype Point = { x: Int, y: Int }\n\n## Kinds of shape.\n## Two lines.\nenum Shape =\n  | Dot\n\n## Has an area.\ntrait Area {\n  fn area(self) -> Int\n}\n\n## Makes a point.\n@allow(unused_function)\nfn origin() -> Point {\n  { x = 0, y = 0 }\n}\n\nfn plain() -> Int {\n  1 ## trailing\n}\n",
+        &std::path::PathBuf::from("test.astra"),
+    )
+    .unwrap();
+    assert_eq!(module.doc.as_deref(), Some("Shapes.\n\n  Indented."));
+    let docs: Vec<Option<&str>> = module
+        .items
+        .iter()
+        .map(|item| match item {
+            Item::TypeDef(def) => def.doc.as_deref(),
+            Item::EnumDef(def) => def.doc.as_deref(),
+            Item::TraitDef(def) => def.doc.as_deref(),
+            Item::FnDef(def) => def.doc.as_deref(),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        docs,
+        vec![
+            Some("A point."),
+            Some("Kinds of shape.\nTwo lines."),
+            Some("Has an area."),
+            Some("Makes a point."),
+            None,
+        ]
+    );
+}
+
+#[test]
+fn test_attribute_named_args() {
+    let module = crate::parser::parse_source(
+        "module m\n\n@cfg(test, feature = \"net\")\nfn fetch() -> Int {\n  1\n}\n",
+        &std::path::PathBuf::from("test.astra"),
+    )
+    .unwrap();
+    let attrs = module.items[0].attributes();
+    assert_eq!(attrs[0].name, "cfg");
+    assert_eq!(attrs[0].args, vec!["test".to_string()]);
+    assert_eq!(
+        attrs[0].named_args,
+        vec![("feature".to_string(), "net".to_string())]
+    );
+
+    let err = crate::parser::parse_source(
+        "module m\n\n@cfg(feature = net)\nfn f() -> Int {\n  1\n}\n",
+        &std::path::PathBuf::from("test.astra"),
+    )
+    .unwrap_err();
+    assert!(err.diagnostics()[0]
+        .message
+        .contains("Expected a string value for attribute argument `feature`"));
+}
+
+#[test]
+fn test_parse_test_hooks() {
+    let module = crate::parser::parse_source(
+        "module m\n\nbefore_each using effects(Clock = Clock.fixed(1)) {\n  { n = 1 }\n}\n\nafter_each {\n  ()\n}\n",
+        &std::path::PathBuf::from("test.astra"),
+    )
+    .unwrap();
+    let kinds: Vec<(HookKind, bool)> = module
+        .items
+        .iter()
+        .map(|item| match item {
+            Item::TestHook(hook) => (hook.kind, hook.using.is_some()),
+            _ => panic!("expected a test hook"),
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![(HookKind::BeforeEach, true), (HookKind::AfterEach, false)]
+    );
+
+    let err = crate::parser::parse_source(
+        "module m\n\nafter_each {\n  ()\n}\n\nafter_each {\n  ()\n}\n",
+        &std::path::PathBuf::from("test.astra"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.diagnostics()[0].message,
+        "A module can have only one `after_each` block"
+    );
+}
+
+#[test]
+fn test_item_keyword_in_block_recovers() {
+    for source in [
+        "module m\nfn f() -> Int { test",
+        "module m\nfn f() -> Int { property \"p\" }\n\nfn g() -> Int { 1 }\n",
+        "module m\nfn f() -> Int { type }\n",
+    ] {
+        let err = crate::parser::parse_source(source, &std::path::PathBuf::from("test.astra"))
+            .unwrap_err();
+        assert!(!err.diagnostics().is_empty());
+    }
+}
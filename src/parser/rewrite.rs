@@ -0,0 +1,459 @@
+//! Traversal of the whole AST with enter and exit hooks per node kind.
+//!
+//! [`Visitor`] sees every item, block, statement, expression, pattern, and
+//! type of a module through shared references, for lints and analyses.
+//! [`Rewriter`] has the same hooks over mutable references, for passes that
+//! transform the tree in place (desugaring, macro expansion, codemods).
+//!
+//! Nodes are visited in source order. `enter_*` runs before a node's
+//! children and may return [`Walk::Skip`] to leave them unvisited; `exit_*`
+//! runs after them. A rewriter that replaces a node in `enter_*` has the
+//! replacement's children walked; one that replaces it in `exit_*` does not.
+//! A macro invocation's expansion is walked in place of its arguments once
+//! the parser has expanded it. For expressions only, the closure walkers of
+//! [`super::visit`] are simpler.
+
+use super::ast::*;
+
+/// Whether to walk the children of a node just entered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Walk {
+    #[default]
+    Continue,
+    /// Leave the children unvisited; the node's `exit_*` hook still runs
+    Skip,
+}
+
+/// Defines a hook trait and its walk functions, over shared references or,
+/// given `mut`, mutable ones
+macro_rules! define_walker {
+    (
+        $(#[$doc:meta])*
+        $trait:ident,
+        $module:ident,
+        $item:ident,
+        $fn_def:ident,
+        $block:ident,
+        $stmt:ident,
+        $expr:ident,
+        $pattern:ident,
+        $ty:ident
+        $(, $m:ident)?
+    ) => {
+        $(#[$doc])*
+        pub trait $trait {
+            fn enter_item(&mut self, _item: &$($m)? Item) -> Walk {
+                Walk::Continue
+            }
+            fn exit_item(&mut self, _item: &$($m)? Item) {}
+            fn enter_block(&mut self, _block: &$($m)? Block) -> Walk {
+                Walk::Continue
+            }
+            fn exit_block(&mut self, _block: &$($m)? Block) {}
+            fn enter_stmt(&mut self, _stmt: &$($m)? Stmt) -> Walk {
+                Walk::Continue
+            }
+            fn exit_stmt(&mut self, _stmt: &$($m)? Stmt) {}
+            fn enter_expr(&mut self, _expr: &$($m)? Expr) -> Walk {
+                Walk::Continue
+            }
+            fn exit_expr(&mut self, _expr: &$($m)? Expr) {}
+            fn enter_pattern(&mut self, _pattern: &$($m)? Pattern) -> Walk {
+                Walk::Continue
+            }
+            fn exit_pattern(&mut self, _pattern: &$($m)? Pattern) {}
+            fn enter_type(&mut self, _ty: &$($m)? TypeExpr) -> Walk {
+                Walk::Continue
+            }
+            fn exit_type(&mut self, _ty: &$($m)? TypeExpr) {}
+        }
+
+        /// Walk every item of a module
+        pub fn $module<V: $trait + ?Sized>(v: &mut V, module: &$($m)? Module) {
+            for item in &$($m)? module.items {
+                $item(v, item);
+            }
+        }
+
+        /// Walk an item and everything in it
+        pub fn $item<V: $trait + ?Sized>(v: &mut V, item: &$($m)? Item) {
+            if v.enter_item(item) == Walk::Continue {
+                match &$($m)? *item {
+                    // Templates are only meaningful once substituted
+                    Item::Import(_) | Item::MacroDef(_) => {}
+                    Item::TypeDef(def) => {
+                        $ty(v, &$($m)? def.value);
+                        if let Some(invariant) = &$($m)? def.invariant {
+                            $expr(v, invariant);
+                        }
+                    }
+                    Item::EnumDef(def) => {
+                        for variant in &$($m)? def.variants {
+                            for field in &$($m)? variant.fields {
+                                $ty(v, &$($m)? field.ty);
+                            }
+                        }
+                    }
+                    Item::FnDef(def) => $fn_def(v, def),
+                    Item::TraitDef(def) => {
+                        for method in &$($m)? def.methods {
+                            for param in &$($m)? method.params {
+                                $ty(v, &$($m)? param.ty);
+                            }
+                            if let Some(ty) = &$($m)? method.return_type {
+                                $ty(v, ty);
+                            }
+                            for clause in &$($m)? method.requires {
+                                $expr(v, clause);
+                            }
+                            for clause in &$($m)? method.ensures {
+                                $expr(v, clause);
+                            }
+                        }
+                    }
+                    Item::ImplBlock(block) => {
+                        $ty(v, &$($m)? block.target_type);
+                        for method in &$($m)? block.methods {
+                            $fn_def(v, method);
+                        }
+                    }
+                    Item::EffectDef(def) => {
+                        for operation in &$($m)? def.operations {
+                            for param in &$($m)? operation.params {
+                                $ty(v, &$($m)? param.ty);
+                            }
+                            if let Some(ty) = &$($m)? operation.return_type {
+                                $ty(v, ty);
+                            }
+                        }
+                    }
+                    Item::Test(TestBlock { using, body, .. })
+                    | Item::Property(PropertyBlock { using, body, .. })
+                    | Item::TestHook(TestHook { using, body, .. }) => {
+                        if let Some(using) = using {
+                            for binding in &$($m)? using.bindings {
+                                $expr(v, &$($m)? binding.value);
+                            }
+                        }
+                        $block(v, body);
+                    }
+                }
+            }
+            v.exit_item(item);
+        }
+
+        /// Walk a function's parameters, signature types, contracts, and
+        /// body; the function's item hooks are not called
+        pub fn $fn_def<V: $trait + ?Sized>(v: &mut V, def: &$($m)? FnDef) {
+            for param in &$($m)? def.params {
+                if let Some(pattern) = &$($m)? param.pattern {
+                    $pattern(v, pattern);
+                }
+                $ty(v, &$($m)? param.ty);
+            }
+            if let Some(ty) = &$($m)? def.return_type {
+                $ty(v, ty);
+            }
+            for clause in &$($m)? def.requires {
+                $expr(v, clause);
+            }
+            for clause in &$($m)? def.ensures {
+                $expr(v, clause);
+            }
+            $block(v, &$($m)? def.body);
+        }
+
+        /// Walk a block and its statements and value
+        pub fn $block<V: $trait + ?Sized>(v: &mut V, block: &$($m)? Block) {
+            if v.enter_block(block) == Walk::Continue {
+                for stmt in &$($m)? block.stmts {
+                    $stmt(v, stmt);
+                }
+                if let Some(value) = &$($m)? block.expr {
+                    $expr(v, value);
+                }
+            }
+            v.exit_block(block);
+        }
+
+        /// Walk a statement and everything in it
+        pub fn $stmt<V: $trait + ?Sized>(v: &mut V, stmt: &$($m)? Stmt) {
+            if v.enter_stmt(stmt) == Walk::Continue {
+                match &$($m)? *stmt {
+                    Stmt::Let { ty, value, .. } => {
+                        if let Some(ty) = ty {
+                            $ty(v, ty);
+                        }
+                        $expr(v, value);
+                    }
+                    Stmt::LetPattern {
+                        pattern, ty, value, ..
+                    } => {
+                        $pattern(v, pattern);
+                        if let Some(ty) = ty {
+                            $ty(v, ty);
+                        }
+                        $expr(v, value);
+                    }
+                    Stmt::Assign { target, value, .. } => {
+                        $expr(v, target);
+                        $expr(v, value);
+                    }
+                    Stmt::Expr { expr, .. } => $expr(v, expr),
+                    Stmt::Return { value, .. } => {
+                        if let Some(value) = value {
+                            $expr(v, value);
+                        }
+                    }
+                }
+            }
+            v.exit_stmt(stmt);
+        }
+
+        /// Walk an expression and everything nested in it
+        pub fn $expr<V: $trait + ?Sized>(v: &mut V, expr: &$($m)? Expr) {
+            if v.enter_expr(expr) == Walk::Continue {
+                match &$($m)? *expr {
+                    Expr::IntLit { .. }
+                    | Expr::FloatLit { .. }
+                    | Expr::DecimalLit { .. }
+                    | Expr::BoolLit { .. }
+                    | Expr::TextLit { .. }
+                    | Expr::UnitLit { .. }
+                    | Expr::Ident { .. }
+                    | Expr::QualifiedIdent { .. }
+                    | Expr::Break { .. }
+                    | Expr::Continue { .. }
+                    | Expr::Hole { .. } => {}
+                    Expr::Record { fields, .. } => {
+                        for (_, value) in fields {
+                            $expr(v, value);
+                        }
+                    }
+                    Expr::FieldAccess { expr, .. }
+                    | Expr::Unary { expr, .. }
+                    | Expr::Try { expr, .. }
+                    | Expr::Await { expr, .. } => $expr(v, expr),
+                    Expr::Binary { left, right, .. } => {
+                        $expr(v, left);
+                        $expr(v, right);
+                    }
+                    Expr::Call { func, args, .. } => {
+                        $expr(v, func);
+                        for arg in args {
+                            $expr(v, arg);
+                        }
+                    }
+                    Expr::MethodCall { receiver, args, .. } => {
+                        $expr(v, receiver);
+                        for arg in args {
+                            $expr(v, arg);
+                        }
+                    }
+                    Expr::If {
+                        cond,
+                        then_branch,
+                        else_branch,
+                        ..
+                    } => {
+                        $expr(v, cond);
+                        $block(v, then_branch);
+                        if let Some(else_branch) = else_branch {
+                            $expr(v, else_branch);
+                        }
+                    }
+                    Expr::Match { expr, arms, .. } => {
+                        $expr(v, expr);
+                        for arm in arms {
+                            $pattern(v, &$($m)? arm.pattern);
+                            if let Some(guard) = &$($m)? arm.guard {
+                                $expr(v, guard);
+                            }
+                            $expr(v, &$($m)? arm.body);
+                        }
+                    }
+                    Expr::Block { block, .. } => $block(v, block),
+                    Expr::TryElse {
+                        expr, else_expr, ..
+                    } => {
+                        $expr(v, expr);
+                        $expr(v, else_expr);
+                    }
+                    Expr::ListLit { elements, .. } | Expr::TupleLit { elements, .. } => {
+                        for element in elements {
+                            $expr(v, element);
+                        }
+                    }
+                    Expr::MapLit { entries, .. } => {
+                        for (key, value) in entries {
+                            $expr(v, key);
+                            $expr(v, value);
+                        }
+                    }
+                    Expr::Lambda {
+                        params,
+                        return_type,
+                        body,
+                        ..
+                    } => {
+                        for param in params {
+                            if let Some(ty) = &$($m)? param.ty {
+                                $ty(v, ty);
+                            }
+                        }
+                        if let Some(ty) = return_type {
+                            $ty(v, ty);
+                        }
+                        $block(v, body);
+                    }
+                    Expr::ForIn {
+                        pattern,
+                        iter,
+                        body,
+                        ..
+                    } => {
+                        if let Some(pattern) = pattern {
+                            $pattern(v, pattern);
+                        }
+                        $expr(v, iter);
+                        $block(v, body);
+                    }
+                    Expr::While { cond, body, .. } => {
+                        $expr(v, cond);
+                        $block(v, body);
+                    }
+                    Expr::Quantifier { iter, body, .. } => {
+                        $expr(v, iter);
+                        $expr(v, body);
+                    }
+                    Expr::StringInterp { parts, .. } => {
+                        for part in parts {
+                            if let StringPart::Expr(expr) = part {
+                                $expr(v, expr);
+                            }
+                        }
+                    }
+                    Expr::Range { start, end, .. } => {
+                        $expr(v, start);
+                        $expr(v, end);
+                    }
+                    Expr::IndexAccess { expr, index, .. } => {
+                        $expr(v, expr);
+                        $expr(v, index);
+                    }
+                    Expr::MacroCall {
+                        args, expansion, ..
+                    } => match expansion {
+                        Some(expansion) => $expr(v, expansion),
+                        None => {
+                            for arg in args {
+                                $expr(v, arg);
+                            }
+                        }
+                    },
+                }
+            }
+            v.exit_expr(expr);
+        }
+
+        /// Walk a pattern and its subpatterns
+        pub fn $pattern<V: $trait + ?Sized>(v: &mut V, pattern: &$($m)? Pattern) {
+            if v.enter_pattern(pattern) == Walk::Continue {
+                match &$($m)? *pattern {
+                    Pattern::Record { fields, .. } => {
+                        for (_, field) in fields {
+                            $pattern(v, field);
+                        }
+                    }
+                    Pattern::Variant { fields, .. } | Pattern::Tuple { elements: fields, .. } => {
+                        for field in fields {
+                            $pattern(v, field);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            v.exit_pattern(pattern);
+        }
+
+        /// Walk a type expression and the types in it
+        pub fn $ty<V: $trait + ?Sized>(v: &mut V, ty: &$($m)? TypeExpr) {
+            if v.enter_type(ty) == Walk::Continue {
+                match &$($m)? *ty {
+                    TypeExpr::Named { args, .. } => {
+                        for arg in args {
+                            $ty(v, arg);
+                        }
+                    }
+                    TypeExpr::Record { fields, .. } => {
+                        for field in fields {
+                            $ty(v, &$($m)? field.ty);
+                        }
+                    }
+                    TypeExpr::Function { params, ret, .. } => {
+                        for param in params {
+                            $ty(v, param);
+                        }
+                        $ty(v, ret);
+                    }
+                    TypeExpr::Tuple { elements, .. } => {
+                        for element in elements {
+                            $ty(v, element);
+                        }
+                    }
+                }
+            }
+            v.exit_type(ty);
+        }
+    };
+}
+
+define_walker!(
+    /// Read-only hooks called while walking the AST; every hook defaults to
+    /// doing nothing
+    Visitor,
+    visit_module,
+    visit_item,
+    visit_fn,
+    visit_block,
+    visit_stmt,
+    visit_expr,
+    visit_pattern,
+    visit_type
+);
+
+define_walker!(
+    /// Hooks that may rewrite nodes in place while walking the AST; every
+    /// hook defaults to doing nothing
+    Rewriter,
+    rewrite_module,
+    rewrite_item,
+    rewrite_fn,
+    rewrite_block,
+    rewrite_stmt,
+    rewrite_expr,
+    rewrite_pattern,
+    rewrite_type,
+    mut
+);
+
+/// Renames identifiers from the keys of a map to its values, such as a trait
+/// method's parameters to those of an implementation. Bindings introduced
+/// inside the expression are not tracked, so a lambda parameter with a
+/// renamed name is renamed too.
+pub struct RenameIdents<'a>(pub &'a std::collections::HashMap<String, String>);
+
+impl Rewriter for RenameIdents<'_> {
+    fn enter_expr(&mut self, expr: &mut Expr) -> Walk {
+        if let Expr::Ident { name, .. } = expr {
+            if let Some(renamed) = self.0.get(name) {
+                *name = renamed.clone();
+            }
+        }
+        Walk::Continue
+    }
+}
+
+#[cfg(test)]
+#[path = "rewrite_tests.rs"]
+mod tests;
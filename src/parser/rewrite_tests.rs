@@ -0,0 +1,204 @@
+use super::*;
+use crate::parser::parse_source;
+use std::path::Path;
+
+const SOURCE: &str = r#"module app
+
+type Pair = { left: Int, right: Option[Int] }
+
+fn total(xs: List[Int]) -> Int
+  requires xs.len() > 0
+{
+  let mut sum = 0
+  for x in xs {
+    sum = sum + x * 1
+  }
+  match sum {
+    0 => 1 + 1
+    n if n > 100 => n
+    _ => sum
+  }
+}
+
+test "total" {
+  assert_eq(total([2, 3]), 5)
+}
+"#;
+
+fn module(source: &str) -> Module {
+    parse_source(source, Path::new("test.astra")).unwrap()
+}
+
+/// Counts nodes by kind, skipping the insides of test blocks
+#[derive(Default)]
+struct Census {
+    items: usize,
+    blocks: usize,
+    stmts: usize,
+    exprs: usize,
+    patterns: usize,
+    types: usize,
+    calls: Vec<String>,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl Visitor for Census {
+    fn enter_item(&mut self, item: &Item) -> Walk {
+        self.items += 1;
+        match item {
+            Item::Test(_) => Walk::Skip,
+            _ => Walk::Continue,
+        }
+    }
+    fn enter_block(&mut self, _block: &Block) -> Walk {
+        self.blocks += 1;
+        Walk::Continue
+    }
+    fn enter_stmt(&mut self, _stmt: &Stmt) -> Walk {
+        self.stmts += 1;
+        Walk::Continue
+    }
+    fn enter_expr(&mut self, expr: &Expr) -> Walk {
+        self.exprs += 1;
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+        if let Expr::MethodCall { method, .. } = expr {
+            self.calls.push(method.clone());
+        }
+        Walk::Continue
+    }
+    fn exit_expr(&mut self, _expr: &Expr) {
+        self.depth -= 1;
+    }
+    fn enter_pattern(&mut self, _pattern: &Pattern) -> Walk {
+        self.patterns += 1;
+        Walk::Continue
+    }
+    fn enter_type(&mut self, _ty: &TypeExpr) -> Walk {
+        self.types += 1;
+        Walk::Continue
+    }
+}
+
+#[test]
+fn test_visitor_reaches_every_node_kind() {
+    let mut census = Census::default();
+    visit_module(&mut census, &module(SOURCE));
+    assert_eq!(census.items, 3);
+    // Function body, loop body, and nothing from the skipped test
+    assert_eq!(census.blocks, 2);
+    assert_eq!(census.stmts, 3);
+    assert_eq!(census.calls, vec!["len"]);
+    // Pair fields, Option's argument, the parameter, and the return type
+    assert_eq!(census.types, 7);
+    // The three match arms
+    assert_eq!(census.patterns, 3);
+    // Every entered expression was exited
+    assert_eq!(census.depth, 0);
+    assert_eq!(census.max_depth, 4);
+    assert_eq!(census.exprs, 23);
+}
+
+/// Folds additions and multiplications of integer literals
+struct Fold;
+
+impl Rewriter for Fold {
+    fn exit_expr(&mut self, expr: &mut Expr) {
+        if let Expr::Binary {
+            id,
+            span,
+            op,
+            left,
+            right,
+        } = expr
+        {
+            let (Expr::IntLit { value: a, .. }, Expr::IntLit { value: b, .. }) =
+                (left.as_ref(), right.as_ref())
+            else {
+                return;
+            };
+            let value = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Mul => a * b,
+                _ => return,
+            };
+            *expr = Expr::IntLit {
+                id: *id,
+                span: span.clone(),
+                value,
+            };
+        }
+    }
+}
+
+/// Renames a variable in expressions and patterns
+struct Rename(&'static str, &'static str);
+
+impl Rewriter for Rename {
+    fn enter_expr(&mut self, expr: &mut Expr) -> Walk {
+        if let Expr::Ident { name, .. } = expr {
+            if name == self.0 {
+                *name = self.1.to_string();
+            }
+        }
+        Walk::Continue
+    }
+    fn enter_stmt(&mut self, stmt: &mut Stmt) -> Walk {
+        if let Stmt::Let { name, .. } = stmt {
+            if name == self.0 {
+                *name = self.1.to_string();
+            }
+        }
+        Walk::Continue
+    }
+}
+
+#[test]
+fn test_rewriters_transform_in_place() {
+    let mut module = module(SOURCE);
+    rewrite_module(&mut Fold, &mut module);
+    rewrite_module(&mut Rename("sum", "acc"), &mut module);
+    let source = crate::formatter::emit(&module);
+    assert!(source.contains("  let mut acc = 0\n"), "{}", source);
+    assert!(source.contains("    acc = acc + x * 1\n"), "{}", source);
+    assert!(source.contains("    0 => 2\n"), "{}", source);
+    assert!(source.contains("    _ => acc\n"), "{}", source);
+    assert!(source.contains("assert_eq(total([2, 3]), 5)"), "{}", source);
+    assert!(parse_source(&source, Path::new("test.astra")).is_ok());
+}
+
+/// Replaces `x` with `x + 1` on the way in, so the replacement is walked
+struct Increment(usize);
+
+impl Rewriter for Increment {
+    fn enter_expr(&mut self, expr: &mut Expr) -> Walk {
+        match expr {
+            Expr::Ident { name, .. } if name == "x" && self.0 < 3 => {
+                self.0 += 1;
+                let ident = expr.clone();
+                *expr = crate::parser::builder::ExprBuilder::from(ident)
+                    .binary(BinaryOp::Add, crate::parser::builder::ExprBuilder::int(1))
+                    .build();
+                Walk::Continue
+            }
+            Expr::Lambda { .. } => Walk::Skip,
+            _ => Walk::Continue,
+        }
+    }
+}
+
+#[test]
+fn test_replacements_made_on_entry_are_walked() {
+    let mut module = module("module m\nfn f(x: Int) -> Int {\n  (fn(x) { x })(x) * x\n}\n");
+    let mut increment = Increment(0);
+    rewrite_module(&mut increment, &mut module);
+    assert_eq!(increment.0, 3);
+    let source = crate::formatter::emit(&module);
+    // The lambda is skipped, and the argument is rewritten until the limit
+    assert!(
+        source.contains("    x\n  }(x + 1 + 1 + 1) * x\n"),
+        "{}",
+        source
+    );
+}
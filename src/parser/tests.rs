@@ -216,3 +216,208 @@ test "simple test" {
         panic!("expected test block");
     }
 }
+
+#[test]
+fn test_parse_attributes_on_items_and_lets() {
+    let source = r#"module mymod
+
+@allow(unused_function)
+fn helper() -> Int {
+  @allow(unused_variable, shadowed_binding)
+  let x = 1
+  2
+}
+"#;
+    let module = parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    let Item::FnDef(def) = &module.items[0] else {
+        panic!("expected fn");
+    };
+    assert_eq!(def.attributes.len(), 1);
+    assert_eq!(def.attributes[0].name, "allow");
+    assert_eq!(def.attributes[0].args, vec!["unused_function"]);
+    let Stmt::Let { attributes, .. } = &def.body.stmts[0] else {
+        panic!("expected let");
+    };
+    assert_eq!(
+        attributes[0].args,
+        vec!["unused_variable", "shadowed_binding"]
+    );
+}
+
+#[test]
+fn test_parse_attribute_on_expression_statement_errors() {
+    let source = "module mymod\n\nfn f() -> Int {\n  @allow(unused_variable)\n  1\n}\n";
+    assert!(parse_source(source, &PathBuf::from("test.astra")).is_err());
+}
+
+#[test]
+fn test_parse_quantifiers() {
+    let source = "module m\n\nfn f(xs: List[Int]) -> Bool\n  requires forall x in xs: x > 0 and x < 10\n{\n  Fs.exists(\"a\") or exists y in xs: y == 1\n}\n";
+    let module = parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    let Item::FnDef(def) = &module.items[0] else {
+        panic!("expected fn def");
+    };
+    // The condition takes in everything after the colon
+    let Expr::Quantifier {
+        kind: QuantifierKind::Forall,
+        binding,
+        body,
+        ..
+    } = &def.requires[0]
+    else {
+        panic!("expected forall, got {:?}", def.requires[0]);
+    };
+    assert_eq!(binding, "x");
+    assert!(matches!(
+        **body,
+        Expr::Binary {
+            op: BinaryOp::And,
+            ..
+        }
+    ));
+    let Some(Expr::Binary { left, right, .. }) = def.body.expr.as_deref() else {
+        panic!("expected `or`");
+    };
+    assert!(matches!(**left, Expr::MethodCall { ref method, .. } if method == "exists"));
+    assert!(matches!(
+        **right,
+        Expr::Quantifier {
+            kind: QuantifierKind::Exists,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_parse_trait_method_contracts() {
+    let source = "module m\n\ntrait Stack {\n  fn pop(self: Self, n: Int) -> Int\n    requires n > 0\n    ensures result >= 0\n}\n";
+    let module = parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    let Item::TraitDef(def) = &module.items[0] else {
+        panic!("expected trait");
+    };
+    assert_eq!(def.methods[0].requires.len(), 1);
+    assert_eq!(def.methods[0].ensures.len(), 1);
+
+    // Effect operations have no implementation to hold to a contract
+    let source =
+        "module m\n\neffect Log {\n  fn write(msg: Text) -> Unit\n    requires msg != \"\"\n}\n";
+    assert!(parse_source(source, &PathBuf::from("test.astra")).is_err());
+}
+
+#[test]
+fn test_string_interp_spans_point_into_file() {
+    let source = "module m\n\nfn f(n: Int) -> Text {\n  \"é ${n + 1}\"\n}\n";
+    let module = parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    let Item::FnDef(def) = &module.items[0] else {
+        panic!("expected fn");
+    };
+    let Some(Expr::StringInterp { parts, .. }) = def.body.expr.as_deref() else {
+        panic!("expected interpolated string");
+    };
+    let StringPart::Expr(expr) = &parts[1] else {
+        panic!("expected expression part");
+    };
+    let span = expr.span();
+    assert_eq!(span.file, PathBuf::from("test.astra"));
+    assert_eq!(&source[span.start..span.end], "n + 1");
+    assert_eq!((span.start_line, span.start_col), (4, 9));
+}
+
+#[test]
+fn test_parse_empty_effects_clause() {
+    let source = "module m
+
+fn backup(fs: Fs) -> Unit effects() {
+  ()
+}
+";
+    let module = parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    let Item::FnDef(def) = &module.items[0] else {
+        panic!("expected fn");
+    };
+    assert!(def.effects.is_empty());
+}
+
+#[test]
+fn test_parse_expression() {
+    let expr = parse_expression("total * 2", &PathBuf::from("<eval>")).unwrap();
+    assert!(matches!(expr, Expr::Binary { .. }));
+    assert!(parse_expression("1 +", &PathBuf::from("<eval>")).is_err());
+    // The whole source must be one expression
+    assert!(parse_expression("a b", &PathBuf::from("<eval>")).is_err());
+}
+
+#[test]
+fn test_parse_record_pattern_rename_and_rest() {
+    let source = r#"module example
+
+fn f(p: { x: Int, y: Int }) -> Int {
+  let { x, y: vertical, .. } = p
+  let { y = old } = p
+  x + vertical + old
+}
+"#;
+    let module = parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    let Item::FnDef(fndef) = &module.items[0] else {
+        panic!("expected fn def");
+    };
+    let fields = |stmt: &Stmt| match stmt {
+        Stmt::LetPattern {
+            pattern: Pattern::Record { fields, rest, .. },
+            ..
+        } => (
+            fields
+                .iter()
+                .map(|(field, pattern)| match pattern {
+                    Pattern::Ident { name, .. } => format!("{}: {}", field, name),
+                    other => panic!("expected ident pattern, got {:?}", other),
+                })
+                .collect::<Vec<_>>(),
+            *rest,
+        ),
+        other => panic!("expected record pattern, got {:?}", other),
+    };
+    assert_eq!(
+        fields(&fndef.body.stmts[0]),
+        (vec!["x: x".to_string(), "y: vertical".to_string()], true)
+    );
+    assert_eq!(
+        fields(&fndef.body.stmts[1]),
+        (vec!["y: old".to_string()], false)
+    );
+}
+
+#[test]
+fn test_parse_decimal_literal_out_of_range() {
+    let source = "module example\n\nfn f() -> Decimal {\n  99999999999999999999999999999999d\n}\n";
+    let errors = parse_source(source, &PathBuf::from("test.astra")).unwrap_err();
+    assert_eq!(errors.diagnostics()[0].code, "E0003");
+    assert!(errors.diagnostics()[0]
+        .message
+        .contains("does not fit in a Decimal"));
+
+    let source = "module example\n\nfn f() -> Decimal {\n  1.50d\n}\n";
+    let module = parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    let Item::FnDef(fndef) = &module.items[0] else {
+        panic!("expected fn def");
+    };
+    match fndef.body.expr.as_deref() {
+        Some(Expr::DecimalLit { value, .. }) => assert_eq!(value.to_string(), "1.50"),
+        other => panic!("expected decimal literal, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_float_literal_out_of_range() {
+    let source = "module example\n\nfn f() -> Float {\n  1e400\n}\n";
+    let errors = parse_source(source, &PathBuf::from("test.astra")).unwrap_err();
+    assert_eq!(errors.diagnostics()[0].code, "E0003");
+    assert!(errors.diagnostics()[0]
+        .message
+        .contains("`1e400` does not fit in a Float"));
+    assert!(parse_source(
+        "module example\n\nfn f() -> Float {\n  1.5e308\n}\n",
+        &PathBuf::from("test.astra")
+    )
+    .is_ok());
+}
@@ -0,0 +1,164 @@
+//! Read-only traversal of expressions in the AST.
+//!
+//! The walkers call a closure on every expression in source order
+//! (pre-order), descending into blocks, statements, match arms, lambdas,
+//! interpolated strings, and macro expansions. Analyses that only need to
+//! see expressions (call graphs, reference search) build on these instead of
+//! matching every node.
+
+use super::ast::*;
+
+/// Call `f` on `expr` and every expression nested in it
+pub fn walk_expr<'a>(expr: &'a Expr, f: &mut impl FnMut(&'a Expr)) {
+    f(expr);
+    match expr {
+        Expr::IntLit { .. }
+        | Expr::FloatLit { .. }
+        | Expr::DecimalLit { .. }
+        | Expr::BoolLit { .. }
+        | Expr::TextLit { .. }
+        | Expr::UnitLit { .. }
+        | Expr::Ident { .. }
+        | Expr::QualifiedIdent { .. }
+        | Expr::Break { .. }
+        | Expr::Continue { .. }
+        | Expr::Hole { .. } => {}
+        Expr::Record { fields, .. } => {
+            for (_, value) in fields {
+                walk_expr(value, f);
+            }
+        }
+        Expr::FieldAccess { expr, .. }
+        | Expr::Unary { expr, .. }
+        | Expr::Try { expr, .. }
+        | Expr::Await { expr, .. } => walk_expr(expr, f),
+        Expr::Binary { left, right, .. } => {
+            walk_expr(left, f);
+            walk_expr(right, f);
+        }
+        Expr::Call { func, args, .. } => {
+            walk_expr(func, f);
+            for arg in args {
+                walk_expr(arg, f);
+            }
+        }
+        Expr::MethodCall { receiver, args, .. } => {
+            walk_expr(receiver, f);
+            for arg in args {
+                walk_expr(arg, f);
+            }
+        }
+        Expr::If {
+            cond,
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            walk_expr(cond, f);
+            walk_block(then_branch, f);
+            if let Some(else_branch) = else_branch {
+                walk_expr(else_branch, f);
+            }
+        }
+        Expr::Match { expr, arms, .. } => {
+            walk_expr(expr, f);
+            for arm in arms {
+                if let Some(guard) = &arm.guard {
+                    walk_expr(guard, f);
+                }
+                walk_expr(&arm.body, f);
+            }
+        }
+        Expr::Block { block, .. } => walk_block(block, f),
+        Expr::TryElse {
+            expr, else_expr, ..
+        } => {
+            walk_expr(expr, f);
+            walk_expr(else_expr, f);
+        }
+        Expr::ListLit { elements, .. } | Expr::TupleLit { elements, .. } => {
+            for element in elements {
+                walk_expr(element, f);
+            }
+        }
+        Expr::MapLit { entries, .. } => {
+            for (key, value) in entries {
+                walk_expr(key, f);
+                walk_expr(value, f);
+            }
+        }
+        Expr::Lambda { body, .. } => walk_block(body, f),
+        Expr::ForIn { iter, body, .. } => {
+            walk_expr(iter, f);
+            walk_block(body, f);
+        }
+        Expr::Quantifier { iter, body, .. } => {
+            walk_expr(iter, f);
+            walk_expr(body, f);
+        }
+        Expr::While { cond, body, .. } => {
+            walk_expr(cond, f);
+            walk_block(body, f);
+        }
+        Expr::StringInterp { parts, .. } => {
+            for part in parts {
+                if let StringPart::Expr(expr) = part {
+                    walk_expr(expr, f);
+                }
+            }
+        }
+        Expr::Range { start, end, .. } => {
+            walk_expr(start, f);
+            walk_expr(end, f);
+        }
+        Expr::IndexAccess { expr, index, .. } => {
+            walk_expr(expr, f);
+            walk_expr(index, f);
+        }
+        Expr::MacroCall {
+            args, expansion, ..
+        } => match expansion {
+            Some(expansion) => walk_expr(expansion, f),
+            None => {
+                for arg in args {
+                    walk_expr(arg, f);
+                }
+            }
+        },
+    }
+}
+
+/// Call `f` on every expression in a block
+pub fn walk_block<'a>(block: &'a Block, f: &mut impl FnMut(&'a Expr)) {
+    for stmt in &block.stmts {
+        walk_stmt(stmt, f);
+    }
+    if let Some(expr) = &block.expr {
+        walk_expr(expr, f);
+    }
+}
+
+/// Call `f` on every expression in a statement
+pub fn walk_stmt<'a>(stmt: &'a Stmt, f: &mut impl FnMut(&'a Expr)) {
+    match stmt {
+        Stmt::Let { value, .. } | Stmt::LetPattern { value, .. } => walk_expr(value, f),
+        Stmt::Assign { target, value, .. } => {
+            walk_expr(target, f);
+            walk_expr(value, f);
+        }
+        Stmt::Expr { expr, .. } => walk_expr(expr, f),
+        Stmt::Return { value, .. } => {
+            if let Some(value) = value {
+                walk_expr(value, f);
+            }
+        }
+    }
+}
+
+/// Call `f` on every expression in a function: contracts first, then the body
+pub fn walk_fn<'a>(def: &'a FnDef, f: &mut impl FnMut(&'a Expr)) {
+    for clause in def.requires.iter().chain(&def.ensures) {
+        walk_expr(clause, f);
+    }
+    walk_block(&def.body, f);
+}
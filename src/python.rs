@@ -0,0 +1,205 @@
+//! Python bindings (feature `python`)
+//!
+//! Built as the `astra` extension module with maturin (see `pyproject.toml`):
+//!
+//! ```python
+//! import astra
+//!
+//! astra.check(source)             # {"ok": ..., "diagnostics": [...]}
+//! astra.run(source, seed=42, capabilities={"console": print, "fs": {"a.txt": "hi"}})
+//! ```
+//!
+//! Capabilities take the values of [`CapabilityConfig`], or Python objects
+//! that implement them: a callable for `console` (called with each piece of
+//! output), `net` (called with the URL and the POST body or `None`), and
+//! `clock` (returning milliseconds), and an object with `read`, `write`, and
+//! `exists` methods for `fs`. Exceptions raised by `fs` and `net` become
+//! `Err` values in the program; those raised by `console` and `clock` are
+//! re-raised once the run ends.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::embed::{self, CapabilityConfig};
+use crate::interpreter::{
+    Capabilities, ClockCapability, ConsoleCapability, FsCapability, NetCapability, Value,
+};
+
+/// First exception raised by a callback that can't report errors to the program
+type Raised = Rc<RefCell<Option<PyErr>>>;
+
+/// Parse and type check a module
+#[pyfunction]
+fn check(py: Python<'_>, source: &str) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| embed::check(source));
+    to_python(py, &serde_json::to_value(result).unwrap_or_default())
+}
+
+/// Run a module's `main` in a sandbox with the given capabilities
+#[pyfunction]
+#[pyo3(signature = (source, seed = 0, capabilities = None))]
+fn run(
+    py: Python<'_>,
+    source: &str,
+    seed: u64,
+    capabilities: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    let raised = Raised::default();
+    let mut host = Capabilities::default();
+    let mut config = serde_json::Map::new();
+    if let Some(capabilities) = capabilities {
+        let json = py.import("json")?;
+        for (key, value) in capabilities.iter() {
+            let key: String = key.extract()?;
+            let callback = || Callback {
+                function: value.clone().unbind(),
+                raised: raised.clone(),
+            };
+            match key.as_str() {
+                "console" if value.is_callable() => host.console = Some(Box::new(callback())),
+                "net" if value.is_callable() => host.net = Some(Box::new(callback())),
+                "clock" if value.is_callable() => host.clock = Some(Box::new(callback())),
+                "fs" if value.hasattr("read")? => host.fs = Some(Box::new(callback())),
+                _ => {
+                    let text: String = json.call_method1("dumps", (&value,))?.extract()?;
+                    let value = serde_json::from_str(&text)
+                        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+                    config.insert(key, value);
+                }
+            }
+        }
+    }
+    let config: CapabilityConfig = serde_json::from_value(config.into())
+        .map_err(|e| PyValueError::new_err(format!("invalid capabilities: {}", e)))?;
+
+    let result = embed::run_with(source, seed, &config, host);
+    if let Some(error) = raised.take() {
+        return Err(error);
+    }
+    to_python(py, &serde_json::to_value(result).unwrap_or_default())
+}
+
+/// A capability implemented by a Python callable or object
+struct Callback {
+    function: PyObject,
+    raised: Raised,
+}
+
+impl Callback {
+    /// Remember the first exception, to re-raise after the run
+    fn raise(&self, error: PyErr) {
+        self.raised.borrow_mut().get_or_insert(error);
+    }
+}
+
+impl ConsoleCapability for Callback {
+    fn print(&self, text: &str) {
+        Python::with_gil(|py| {
+            if let Err(error) = self.function.call1(py, (text,)) {
+                self.raise(error);
+            }
+        })
+    }
+
+    fn println(&self, text: &str) {
+        self.print(&format!("{}\n", text))
+    }
+
+    fn read_line(&self) -> Option<String> {
+        None
+    }
+}
+
+impl NetCapability for Callback {
+    fn get(&self, url: &str) -> Result<Value, String> {
+        self.request(url, None)
+    }
+
+    fn post(&self, url: &str, body: &str) -> Result<Value, String> {
+        self.request(url, Some(body))
+    }
+}
+
+impl Callback {
+    fn request(&self, url: &str, body: Option<&str>) -> Result<Value, String> {
+        Python::with_gil(|py| {
+            self.function
+                .call1(py, (url, body))
+                .and_then(|response| response.extract::<String>(py))
+                .map(Value::Text)
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+impl ClockCapability for Callback {
+    fn now(&self) -> i64 {
+        Python::with_gil(|py| {
+            match self
+                .function
+                .call0(py)
+                .and_then(|now| now.extract::<i64>(py))
+            {
+                Ok(now) => now,
+                Err(error) => {
+                    self.raise(error);
+                    0
+                }
+            }
+        })
+    }
+
+    fn sleep(&self, _millis: u64) {}
+}
+
+impl FsCapability for Callback {
+    fn read(&self, path: &str) -> Result<String, String> {
+        Python::with_gil(|py| {
+            self.function
+                .call_method1(py, "read", (path,))
+                .and_then(|contents| contents.extract::<String>(py))
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn write(&self, path: &str, content: &str) -> Result<(), String> {
+        Python::with_gil(|py| {
+            self.function
+                .call_method1(py, "write", (path, content))
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        Python::with_gil(|py| {
+            self.function
+                .call_method1(py, "exists", (path,))
+                .and_then(|exists| exists.extract::<bool>(py))
+                .unwrap_or(false)
+        })
+    }
+}
+
+/// Convert a JSON result to Python dicts, lists, and scalars
+fn to_python(py: Python<'_>, value: &serde_json::Value) -> PyResult<PyObject> {
+    let json = py.import("json")?;
+    Ok(json.call_method1("loads", (value.to_string(),))?.unbind())
+}
+
+/// The `astra` Python module
+#[pymodule]
+fn astra(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(check, module)?)?;
+    module.add_function(wrap_pyfunction!(run, module)?)?;
+    module.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "python_tests.rs"]
+mod tests;
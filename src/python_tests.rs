@@ -0,0 +1,72 @@
+use super::*;
+use pyo3::ffi::c_str;
+
+/// Run Python `code` with the `astra` module imported
+fn run_python(code: &std::ffi::CStr) {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let module = PyModule::new(py, "astra").unwrap();
+        astra(&module).unwrap();
+        let globals = PyDict::new(py);
+        globals.set_item("astra", module).unwrap();
+        py.run(code, Some(&globals), None).unwrap();
+    })
+}
+
+#[test]
+fn test_check_and_run_from_python() {
+    run_python(c_str!(
+        r#"
+source = 'module m\n\nfn main() -> Int effects(Console, Fs) {\n  Console.println(Fs.read("a.txt").unwrap())\n  42\n}\n'
+assert astra.check(source)["ok"]
+result = astra.run(source, seed=1, capabilities={"console": True, "fs": {"a.txt": "hi"}})
+assert result["ok"], result
+assert result["value"] == "42"
+assert result["output"] == "hi\n"
+"#
+    ));
+}
+
+#[test]
+fn test_capability_callbacks_from_python() {
+    run_python(c_str!(
+        r#"
+printed = []
+class Files:
+    def read(self, path):
+        return "from " + path
+    def write(self, path, content):
+        printed.append(path + "=" + content)
+    def exists(self, path):
+        return True
+
+source = 'module m\n\nfn main() -> Int effects(Console, Fs, Net, Clock) {\n  Console.println(Fs.read("x").unwrap())\n  Fs.write("y", Net.get("http://a").unwrap())\n  Clock.now()\n}\n'
+result = astra.run(source, capabilities={
+    "console": printed.append,
+    "fs": Files(),
+    "net": lambda url, body: "got " + url,
+    "clock": lambda: 7,
+})
+assert result["ok"], result
+assert result["value"] == "7"
+assert printed == ["from x\n", "y=got http://a"], printed
+"#
+    ));
+}
+
+#[test]
+fn test_console_callback_exceptions_are_reraised() {
+    run_python(c_str!(
+        r#"
+def fail(text):
+    raise RuntimeError("console is closed")
+
+source = 'module m\n\nfn main() -> Unit effects(Console) {\n  Console.println("hi")\n}\n'
+try:
+    astra.run(source, capabilities={"console": fail})
+    raise AssertionError("expected the callback's exception")
+except RuntimeError as error:
+    assert str(error) == "console is closed"
+"#
+    ));
+}
@@ -0,0 +1,327 @@
+//! Rename refactoring across a project.
+//!
+//! A rename rewrites the declaration of a name and every reference found by
+//! the resolver, including imports of top-level definitions in other files.
+//! Before anything is written, each rewritten file is resolved again and
+//! compared with the original: a rename that would make a reference resolve
+//! to a different binding (shadowing or capture), or that would define a
+//! top-level name twice, is refused with the conflicting spans.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::diagnostics::Span;
+use crate::parser::ast::Module;
+use crate::parser::lexer::{Lexer, TokenKind};
+use crate::parser::parse_source;
+use crate::parser::span::SourceFile;
+use crate::resolve::{qualified_uses, resolve_module, BindingKind, Resolution};
+
+/// The rewritten text of one file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileRename {
+    pub path: PathBuf,
+    /// Spans of the replaced names in the original text
+    pub edits: Vec<Span>,
+    #[serde(skip)]
+    pub original: String,
+    #[serde(skip)]
+    pub renamed: String,
+}
+
+/// A reason the rename would change the meaning of the program
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Conflict {
+    /// Span in the original text
+    pub span: Span,
+    pub message: String,
+}
+
+/// Why a rename was refused
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenameError {
+    /// No name at the given location
+    NoName,
+    /// The new name is not a valid identifier
+    InvalidName(String),
+    /// The name cannot be renamed from this location
+    Unsupported(String),
+    /// The rewritten program would not parse
+    Parse(PathBuf),
+    /// The rename would change what other names refer to
+    Conflicts(Vec<Conflict>),
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::NoName => write!(f, "no name found at this location"),
+            RenameError::InvalidName(name) => write!(f, "`{}` is not a valid identifier", name),
+            RenameError::Unsupported(reason) => write!(f, "{}", reason),
+            RenameError::Parse(path) => {
+                write!(f, "renamed source of {} does not parse", path.display())
+            }
+            RenameError::Conflicts(conflicts) => {
+                write!(
+                    f,
+                    "rename conflicts with {} existing name(s)",
+                    conflicts.len()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenameError {}
+
+/// Rename the name at a 1-indexed `line` and `col` of `file` to `new_name`.
+///
+/// `sources` holds the path and text of every file of the project, including
+/// `file`; files that import a renamed top-level definition are rewritten
+/// too. Returns the files that change, in the order of `sources`.
+pub fn rename(
+    sources: &[(PathBuf, String)],
+    file: &Path,
+    line: usize,
+    col: usize,
+    new_name: &str,
+) -> Result<Vec<FileRename>, RenameError> {
+    if !is_identifier(new_name) {
+        return Err(RenameError::InvalidName(new_name.to_string()));
+    }
+    let (_, target_text) = sources
+        .iter()
+        .find(|(path, _)| path == file)
+        .ok_or(RenameError::NoName)?;
+    let target = parse(file, target_text)?;
+    let resolution = resolve_module(&target.module, &target.source);
+    let index = resolution
+        .binding_at(line, col)
+        .ok_or(RenameError::NoName)?;
+    let binding = &resolution.bindings[index];
+
+    if binding.kind == BindingKind::Import {
+        let path = binding.path.as_deref().unwrap_or_default();
+        // An alias is local to the importing module; other imported names
+        // are renamed where they are defined
+        if path.rsplit('.').next() == Some(binding.name.as_str()) {
+            return Err(RenameError::Unsupported(format!(
+                "`{}` is imported from `{}`; rename it where it is defined",
+                binding.name, path
+            )));
+        }
+    }
+    if binding.name == new_name {
+        return Ok(Vec::new());
+    }
+
+    let mut renames = Vec::new();
+    let mut conflicts = Vec::new();
+    let qualified = is_top_level(binding.kind)
+        .then(|| format!("{}.{}", target.module.name.segments.join("."), binding.name));
+
+    for (path, text) in sources {
+        let edits: Vec<Span> = if path == file {
+            let mut edits = vec![binding.span.clone()];
+            edits.extend(resolution.references_to(index).into_iter().cloned());
+            edits.sort_by_key(|s| s.start);
+            edits
+        } else if let Some(qualified) = &qualified {
+            // Only files that mention the name can import it
+            if !text.contains(binding.name.as_str()) {
+                continue;
+            }
+            let Ok(parsed) = parse(path, text) else {
+                continue;
+            };
+            qualified_uses(&parsed.module, &parsed.source, qualified)
+        } else {
+            continue;
+        };
+        if edits.is_empty() {
+            continue;
+        }
+
+        let renamed = apply(text, &edits, new_name);
+        let old = parse(path, text)?;
+        let new = parse(path, &renamed)?;
+        conflicts.extend(check(&old, &new, &edits, new_name));
+        renames.push(FileRename {
+            path: path.clone(),
+            edits,
+            original: text.clone(),
+            renamed,
+        });
+    }
+
+    if conflicts.is_empty() {
+        Ok(renames)
+    } else {
+        Err(RenameError::Conflicts(conflicts))
+    }
+}
+
+struct Parsed {
+    module: Module,
+    source: SourceFile,
+}
+
+fn parse(path: &Path, text: &str) -> Result<Parsed, RenameError> {
+    let module = parse_source(text, path).map_err(|_| RenameError::Parse(path.to_path_buf()))?;
+    Ok(Parsed {
+        module,
+        source: SourceFile::new(path.to_path_buf(), text.to_string()),
+    })
+}
+
+fn is_identifier(name: &str) -> bool {
+    let source = SourceFile::new(PathBuf::new(), name.to_string());
+    let mut lexer = Lexer::new(&source);
+    matches!(lexer.next_token().kind, TokenKind::Ident(ident) if ident == name)
+        && lexer.next_token().kind == TokenKind::Eof
+}
+
+/// Kinds of binding that live at module level
+fn is_top_level(kind: BindingKind) -> bool {
+    matches!(
+        kind,
+        BindingKind::Function
+            | BindingKind::Type
+            | BindingKind::Enum
+            | BindingKind::Variant
+            | BindingKind::Trait
+            | BindingKind::Effect
+    )
+}
+
+/// Replace the text of each span (sorted, non-overlapping) with `new_name`
+fn apply(text: &str, edits: &[Span], new_name: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for edit in edits {
+        out.push_str(&text[last..edit.start]);
+        out.push_str(new_name);
+        last = edit.end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// Byte offset in the renamed text of `offset` in the original text
+fn map_offset(offset: usize, edits: &[Span], new_name: &str) -> usize {
+    edits
+        .iter()
+        .filter(|e| e.end <= offset)
+        .fold(offset, |acc, e| acc + new_name.len() - (e.end - e.start))
+}
+
+/// Byte offset in the original text of `offset` in the renamed text
+fn unmap_offset(offset: usize, edits: &[Span], new_name: &str) -> usize {
+    let mut shift = 0isize;
+    for edit in edits {
+        let new_start = (edit.start as isize + shift) as usize;
+        if offset < new_start + new_name.len() {
+            // Offsets inside a replaced name map to its start
+            return if offset >= new_start {
+                edit.start
+            } else {
+                (offset as isize - shift) as usize
+            };
+        }
+        shift += new_name.len() as isize - (edit.end - edit.start) as isize;
+    }
+    (offset as isize - shift) as usize
+}
+
+/// Compare what each reference resolves to before and after the rename
+fn check(old: &Parsed, new: &Parsed, edits: &[Span], new_name: &str) -> Vec<Conflict> {
+    let before = resolve_module(&old.module, &old.source);
+    let after = resolve_module(&new.module, &new.source);
+    let renamed: Vec<usize> = (0..before.bindings.len())
+        .filter(|&i| {
+            edits
+                .iter()
+                .any(|e| e.start == before.bindings[i].span.start)
+        })
+        .collect();
+    let mut conflicts = Vec::new();
+
+    // A second top-level definition of the new name
+    for &index in &renamed {
+        let binding = &before.bindings[index];
+        if !is_top_level(binding.kind) && binding.kind != BindingKind::Import {
+            continue;
+        }
+        for other in &before.bindings {
+            if other.name == new_name
+                && (is_top_level(other.kind) || other.kind == BindingKind::Import)
+            {
+                conflicts.push(Conflict {
+                    span: other.span.clone(),
+                    message: format!("`{}` is already defined here", new_name),
+                });
+            }
+        }
+    }
+
+    // Every reference must still resolve to the same declaration
+    let declaration_after = |resolution: &Resolution, start: usize| {
+        resolution
+            .references
+            .iter()
+            .find(|r| r.span.start == start)
+            .map(|r| resolution.bindings[r.binding].span.start)
+    };
+    for reference in &before.references {
+        let expected = map_offset(
+            before.bindings[reference.binding].span.start,
+            edits,
+            new_name,
+        );
+        let found = declaration_after(&after, map_offset(reference.span.start, edits, new_name));
+        if found != Some(expected) {
+            let message = match found {
+                Some(start) => {
+                    let start = unmap_offset(start, edits, new_name);
+                    let declared = old.source.span(start, start);
+                    format!(
+                        "this reference would resolve to the `{}` declared at line {}",
+                        new_name, declared.start_line
+                    )
+                }
+                None => "this reference would no longer resolve".to_string(),
+            };
+            conflicts.push(Conflict {
+                span: reference.span.clone(),
+                message,
+            });
+        }
+    }
+
+    // No other name may be captured by the renamed binding
+    let renamed_after: Vec<usize> = renamed
+        .iter()
+        .map(|&i| map_offset(before.bindings[i].span.start, edits, new_name))
+        .collect();
+    for reference in &after.references {
+        let declared = after.bindings[reference.binding].span.start;
+        let start = unmap_offset(reference.span.start, edits, new_name);
+        let edited = edits.iter().any(|e| e.start == start);
+        if renamed_after.contains(&declared) && !edited {
+            conflicts.push(Conflict {
+                span: old.source.span(start, start + new_name.len()),
+                message: format!("`{}` here would refer to the renamed binding", new_name),
+            });
+        }
+    }
+
+    conflicts.sort_by_key(|c| c.span.start);
+    conflicts.dedup();
+    conflicts
+}
+
+#[cfg(test)]
+#[path = "rename_tests.rs"]
+mod tests;
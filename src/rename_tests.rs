@@ -0,0 +1,119 @@
+use super::*;
+
+fn project(files: &[(&str, &str)]) -> Vec<(PathBuf, String)> {
+    files
+        .iter()
+        .map(|(path, text)| (PathBuf::from(path), text.to_string()))
+        .collect()
+}
+
+fn renamed(result: &[FileRename], path: &str) -> String {
+    result
+        .iter()
+        .find(|r| r.path == Path::new(path))
+        .map(|r| r.renamed.clone())
+        .unwrap_or_default()
+}
+
+const SHAPES: &str = r#"module shapes
+
+public fn area(r: Float) -> Float {
+  r * r
+}
+
+fn double_area(r: Float) -> Float {
+  area(r) * 2.0
+}
+"#;
+
+const APP: &str = r#"module app
+
+import shapes.{area}
+import shapes
+
+fn main() -> Float {
+  area(1.0) + shapes.area(2.0)
+}
+"#;
+
+#[test]
+fn test_rename_across_files() {
+    let sources = project(&[("shapes.astra", SHAPES), ("app.astra", APP)]);
+    let result = rename(&sources, Path::new("shapes.astra"), 3, 12, "surface").unwrap();
+    assert_eq!(result.len(), 2);
+    assert_eq!(
+        renamed(&result, "shapes.astra"),
+        SHAPES
+            .replace("fn area(", "fn surface(")
+            .replace("  area(", "  surface(")
+    );
+    assert_eq!(
+        renamed(&result, "app.astra"),
+        APP.replace("{area}", "{surface}")
+            .replace("area(", "surface(")
+    );
+}
+
+#[test]
+fn test_rename_local() {
+    let sources = project(&[("shapes.astra", SHAPES), ("app.astra", APP)]);
+    let result = rename(&sources, Path::new("shapes.astra"), 4, 3, "radius").unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].edits.len(), 3);
+    assert!(renamed(&result, "shapes.astra")
+        .contains("fn area(radius: Float) -> Float {\n  radius * radius"));
+}
+
+#[test]
+fn test_rename_refuses_duplicate_definition() {
+    let sources = project(&[("shapes.astra", SHAPES)]);
+    let Err(RenameError::Conflicts(conflicts)) =
+        rename(&sources, Path::new("shapes.astra"), 7, 4, "area")
+    else {
+        panic!("expected a conflict");
+    };
+    assert_eq!(conflicts[0].span.start_line, 3);
+    assert!(conflicts[0].message.contains("already defined"));
+}
+
+#[test]
+fn test_rename_refuses_shadowing_and_capture() {
+    let source = r#"module m
+
+fn f(a: Int) -> Int {
+  let b = 1
+  a + b
+}
+"#;
+    let sources = project(&[("m.astra", source)]);
+    // Renaming `b` to `a` makes `a + b` read `a + a`
+    let Err(RenameError::Conflicts(conflicts)) = rename(&sources, Path::new("m.astra"), 4, 7, "a")
+    else {
+        panic!("expected a conflict");
+    };
+    assert_eq!(
+        (conflicts[0].span.start_line, conflicts[0].span.start_col),
+        (5, 3)
+    );
+}
+
+#[test]
+fn test_rename_rejects_invalid_names_and_imports() {
+    let sources = project(&[("shapes.astra", SHAPES), ("app.astra", APP)]);
+    assert_eq!(
+        rename(&sources, Path::new("shapes.astra"), 3, 12, "fn").unwrap_err(),
+        RenameError::InvalidName("fn".into())
+    );
+    assert_eq!(
+        rename(&sources, Path::new("shapes.astra"), 3, 12, "a b").unwrap_err(),
+        RenameError::InvalidName("a b".into())
+    );
+    assert!(matches!(
+        rename(&sources, Path::new("app.astra"), 7, 3, "x"),
+        Err(RenameError::Unsupported(_))
+    ));
+    assert_eq!(
+        rename(&sources, Path::new("app.astra"), 2, 1, "x").unwrap_err(),
+        RenameError::NoName
+    );
+}
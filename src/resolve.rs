@@ -0,0 +1,841 @@
+//! Name resolution: links every use of a name to the binding it refers to.
+//!
+//! Bindings are the top-level items of a module (functions, types, enums and
+//! their variants, traits, effects, and imported names) and the local names
+//! introduced by parameters, `let`, lambdas, `for` loops, and patterns.
+//! Resolution follows lexical scoping, so a shadowed name resolves to the
+//! innermost binding. Names that resolve to nothing in the module (builtins,
+//! methods, fields) are not recorded.
+//!
+//! The AST does not record the span of a binding's name, so name spans are
+//! recovered from the identifier tokens of the source. Used by
+//! `astra refs`, `astra rename`, and by the language server's
+//! `textDocument/references`.
+//!
+//! Imported names resolve to the import that brings them in. To follow them
+//! into other modules, the type checker records the definitions of every
+//! module it imports in a [`SymbolTable`], which [`find_definition`] consults
+//! for go-to-definition.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::diagnostics::Span;
+use crate::parser::ast::*;
+use crate::parser::lexer::{Lexer, Token, TokenKind};
+use crate::parser::span::SourceFile;
+
+/// What a binding names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BindingKind {
+    Function,
+    Type,
+    Enum,
+    Variant,
+    Trait,
+    Effect,
+    /// A module or item brought in by an import
+    Import,
+    /// A module, as the target of an import
+    Module,
+    Param,
+    /// A `let`, lambda, loop, or pattern variable
+    Local,
+}
+
+/// A name introduced into a scope
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Binding {
+    pub name: String,
+    pub kind: BindingKind,
+    /// Span of the name at its declaration
+    pub span: Span,
+    /// Qualified name an import refers to (e.g., `std.text.trim`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// A use of a binding
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reference {
+    /// Span of the name at the use
+    pub span: Span,
+    /// Index of the binding in [`Resolution::bindings`]
+    pub binding: usize,
+}
+
+/// The bindings of a module and the references to them
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Resolution {
+    pub bindings: Vec<Binding>,
+    /// References in source order
+    pub references: Vec<Reference>,
+}
+
+impl Resolution {
+    /// The binding declared or referenced at a 1-indexed line and column
+    pub fn binding_at(&self, line: usize, col: usize) -> Option<usize> {
+        self.references
+            .iter()
+            .find(|r| position_in(&r.span, line, col))
+            .map(|r| r.binding)
+            .or_else(|| {
+                self.bindings
+                    .iter()
+                    .position(|b| position_in(&b.span, line, col))
+            })
+    }
+
+    /// Spans of the references to `binding`, in source order
+    pub fn references_to(&self, binding: usize) -> Vec<&Span> {
+        self.references
+            .iter()
+            .filter(|r| r.binding == binding)
+            .map(|r| &r.span)
+            .collect()
+    }
+}
+
+/// Whether a 1-indexed position falls on a span, including just past its end
+/// (where editors put the cursor after typing a name)
+fn position_in(span: &Span, line: usize, col: usize) -> bool {
+    (span.start_line, span.start_col) <= (line, col) && (line, col) <= (span.end_line, span.end_col)
+}
+
+/// Resolve the names of a module; `source` must be the text it was parsed from
+pub fn resolve_module(module: &Module, source: &SourceFile) -> Resolution {
+    let mut resolver = Resolver::new(source);
+    resolver.module(module);
+    resolver.resolution.references.sort_by_key(|r| r.span.start);
+    resolver.resolution
+}
+
+/// Find the declaration and all uses of the name at a 1-indexed line and
+/// column, in source order; empty when no binding is there
+pub fn find_references(
+    module: &Module,
+    source: &SourceFile,
+    line: usize,
+    col: usize,
+    include_declaration: bool,
+) -> Vec<Span> {
+    let resolution = resolve_module(module, source);
+    let Some(binding) = resolution.binding_at(line, col) else {
+        return Vec::new();
+    };
+    let mut spans: Vec<Span> = resolution
+        .references_to(binding)
+        .into_iter()
+        .cloned()
+        .collect();
+    if include_declaration {
+        spans.push(resolution.bindings[binding].span.clone());
+        spans.sort_by_key(|s| s.start);
+    }
+    spans
+}
+
+/// Where a name is defined
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Definition {
+    pub name: String,
+    pub kind: BindingKind,
+    /// Name of the defining module
+    pub module: String,
+    /// Span of the name at its definition
+    pub span: Span,
+}
+
+/// Top-level definitions of imported modules, keyed by qualified name
+/// (`std.text.trim`, or `std.text` for the module itself)
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    definitions: HashMap<String, Definition>,
+}
+
+impl SymbolTable {
+    /// Create an empty symbol table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `module`, imported as `path`, and its top-level definitions;
+    /// `source` must be the text it was parsed from
+    pub fn add_module(&mut self, path: &str, module: &Module, source: &SourceFile) {
+        let module_name = module.name.segments.join(".");
+        self.definitions.insert(
+            path.to_string(),
+            Definition {
+                name: module_name.clone(),
+                kind: BindingKind::Module,
+                module: module_name.clone(),
+                span: module.name.span.clone(),
+            },
+        );
+        for binding in resolve_module(module, source).bindings {
+            let top_level = matches!(
+                binding.kind,
+                BindingKind::Function
+                    | BindingKind::Type
+                    | BindingKind::Enum
+                    | BindingKind::Variant
+                    | BindingKind::Trait
+                    | BindingKind::Effect
+            );
+            if top_level {
+                self.definitions.insert(
+                    format!("{}.{}", path, binding.name),
+                    Definition {
+                        name: binding.name,
+                        kind: binding.kind,
+                        module: module_name.clone(),
+                        span: binding.span,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Look up a definition by qualified name
+    pub fn get(&self, qualified: &str) -> Option<&Definition> {
+        self.definitions.get(qualified)
+    }
+}
+
+/// Find the definition of the name at a 1-indexed line and column.
+///
+/// Local names resolve within the module. Imported names, and `name` in
+/// `module.name` on an imported module, resolve through `symbols`; an import
+/// whose module is not in `symbols` resolves to the import itself.
+pub fn find_definition(
+    module: &Module,
+    source: &SourceFile,
+    line: usize,
+    col: usize,
+    symbols: &SymbolTable,
+) -> Option<Definition> {
+    let resolution = resolve_module(module, source);
+    if let Some(index) = resolution.binding_at(line, col) {
+        let binding = &resolution.bindings[index];
+        if let Some(definition) = binding.path.as_deref().and_then(|p| symbols.get(p)) {
+            return Some(definition.clone());
+        }
+        return Some(Definition {
+            name: binding.name.clone(),
+            kind: binding.kind,
+            module: module.name.segments.join("."),
+            span: binding.span.clone(),
+        });
+    }
+
+    // `module.name`, where the receiver is an imported module
+    let tokens = tokens(source);
+    let at = tokens
+        .iter()
+        .position(|t| matches!(t.kind, TokenKind::Ident(_)) && position_in(&t.span, line, col))?;
+    let qualified = member_path(&resolution, &tokens, at)?;
+    symbols.get(&qualified).cloned()
+}
+
+/// Find the uses in `module` of `qualified`, a top-level definition of
+/// another module (e.g., `std.text.trim`): the name in imports that bring it
+/// in, the uses of those imports, and `module.name` on an import of its
+/// module. Spans are in source order.
+pub fn qualified_uses(module: &Module, source: &SourceFile, qualified: &str) -> Vec<Span> {
+    let resolution = resolve_module(module, source);
+    let mut spans = Vec::new();
+    for (index, binding) in resolution.bindings.iter().enumerate() {
+        if binding.path.as_deref() == Some(qualified) {
+            spans.push(binding.span.clone());
+            spans.extend(resolution.references_to(index).into_iter().cloned());
+        }
+    }
+    let tokens = tokens(source);
+    for (at, token) in tokens.iter().enumerate() {
+        if matches!(token.kind, TokenKind::Ident(_))
+            && member_path(&resolution, &tokens, at).as_deref() == Some(qualified)
+        {
+            spans.push(token.span.clone());
+        }
+    }
+    spans.sort_by_key(|s| s.start);
+    spans
+}
+
+/// Qualified name of the identifier token at `at` when it is `name` in
+/// `module.name` and `module` is an imported module
+fn member_path(resolution: &Resolution, tokens: &[Token], at: usize) -> Option<String> {
+    let (TokenKind::Ident(name), Some(dot), Some(receiver)) = (
+        &tokens[at].kind,
+        at.checked_sub(1).map(|i| &tokens[i]),
+        at.checked_sub(2).map(|i| &tokens[i]),
+    ) else {
+        return None;
+    };
+    if dot.kind != TokenKind::Dot || !matches!(receiver.kind, TokenKind::Ident(_)) {
+        return None;
+    }
+    let receiver = resolution
+        .references
+        .iter()
+        .find(|r| r.span.start == receiver.span.start)?;
+    let binding = &resolution.bindings[receiver.binding];
+    if binding.kind != BindingKind::Import {
+        return None;
+    }
+    Some(format!("{}.{}", binding.path.as_ref()?, name))
+}
+
+fn tokens(source: &SourceFile) -> Vec<Token> {
+    let mut lexer = Lexer::new(source);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        if token.kind == TokenKind::Eof {
+            return tokens;
+        }
+        tokens.push(token);
+    }
+}
+
+struct Resolver {
+    /// Identifier tokens of the source, in order
+    idents: Vec<(String, Span)>,
+    scopes: Vec<HashMap<String, usize>>,
+    resolution: Resolution,
+}
+
+impl Resolver {
+    fn new(source: &SourceFile) -> Self {
+        let idents = tokens(source)
+            .into_iter()
+            .filter_map(|token| match token.kind {
+                TokenKind::Ident(name) => Some((name, token.span)),
+                _ => None,
+            })
+            .collect();
+        Self {
+            idents,
+            scopes: vec![HashMap::new()],
+            resolution: Resolution::default(),
+        }
+    }
+
+    /// Span of the first `name` token inside `within`
+    fn name_span(&self, within: &Span, name: &str) -> Span {
+        self.idents
+            .iter()
+            .find(|(text, span)| {
+                text == name && span.start >= within.start && span.end <= within.end
+            })
+            .map(|(_, span)| span.clone())
+            .unwrap_or_else(|| within.clone())
+    }
+
+    /// Span of the last `name` token in the byte range `start..end`
+    fn last_name_span(&self, start: usize, end: usize, name: &str) -> Option<Span> {
+        self.idents
+            .iter()
+            .rev()
+            .find(|(text, span)| text == name && span.start >= start && span.end <= end)
+            .map(|(_, span)| span.clone())
+    }
+
+    fn declare(&mut self, name: &str, kind: BindingKind, span: Span) -> usize {
+        let index = self.resolution.bindings.len();
+        self.resolution.bindings.push(Binding {
+            name: name.to_string(),
+            kind,
+            span,
+            path: None,
+        });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), index);
+        }
+        index
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    fn refer(&mut self, name: &str, span: Span) {
+        if let Some(binding) = self.lookup(name) {
+            self.resolution.references.push(Reference { span, binding });
+        }
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn module(&mut self, module: &Module) {
+        // Top-level names are visible throughout the module
+        for item in &module.items {
+            match item {
+                Item::FnDef(def) => {
+                    let span = self.name_span(&def.span, &def.name);
+                    self.declare(&def.name, BindingKind::Function, span);
+                }
+                Item::TypeDef(def) => {
+                    let span = self.name_span(&def.span, &def.name);
+                    self.declare(&def.name, BindingKind::Type, span);
+                }
+                Item::EnumDef(def) => {
+                    let span = self.name_span(&def.span, &def.name);
+                    self.declare(&def.name, BindingKind::Enum, span);
+                    for variant in &def.variants {
+                        let span = self.name_span(&variant.span, &variant.name);
+                        self.declare(&variant.name, BindingKind::Variant, span);
+                    }
+                }
+                Item::TraitDef(def) => {
+                    let span = self.name_span(&def.span, &def.name);
+                    self.declare(&def.name, BindingKind::Trait, span);
+                }
+                Item::EffectDef(def) => {
+                    let span = self.name_span(&def.span, &def.name);
+                    self.declare(&def.name, BindingKind::Effect, span);
+                }
+                Item::Import(import) => self.import(import),
+                Item::ImplBlock(_)
+                | Item::Test(_)
+                | Item::Property(_)
+                | Item::TestHook(_)
+                | Item::MacroDef(_) => {}
+            }
+        }
+
+        for item in &module.items {
+            match item {
+                Item::FnDef(def) => self.fn_def(def),
+                Item::TypeDef(def) => {
+                    self.type_expr(&def.value);
+                    if let Some(invariant) = &def.invariant {
+                        self.expr(invariant);
+                    }
+                }
+                Item::EnumDef(def) => {
+                    for variant in &def.variants {
+                        for field in &variant.fields {
+                            self.type_expr(&field.ty);
+                        }
+                    }
+                }
+                Item::TraitDef(def) => self.methods(&def.methods),
+                Item::EffectDef(def) => self.methods(&def.operations),
+                Item::ImplBlock(block) => {
+                    let span = self.name_span(&block.span, &block.trait_name);
+                    self.refer(&block.trait_name, span);
+                    self.type_expr(&block.target_type);
+                    for method in &block.methods {
+                        self.fn_def(method);
+                    }
+                }
+                Item::Test(test) => self.test(test.using.as_ref(), &test.body),
+                Item::Property(prop) => self.test(prop.using.as_ref(), &prop.body),
+                Item::TestHook(hook) => self.test(hook.using.as_ref(), &hook.body),
+                Item::Import(_) | Item::MacroDef(_) => {}
+            }
+        }
+    }
+
+    fn import(&mut self, import: &ImportDecl) {
+        let (start, end) = (import.span.start, import.span.end);
+        let module_path = import.path.segments.join(".");
+        let names: Vec<(&String, String)> = match &import.kind {
+            ImportKind::Module => import
+                .path
+                .segments
+                .last()
+                .map(|last| (last, module_path.clone()))
+                .into_iter()
+                .collect(),
+            ImportKind::Alias(alias) => vec![(alias, module_path.clone())],
+            ImportKind::Items(names) => names
+                .iter()
+                .map(|name| (name, format!("{}.{}", module_path, name)))
+                .collect(),
+        };
+        for (name, path) in names {
+            let span = self
+                .last_name_span(start, end, name)
+                .unwrap_or_else(|| import.span.clone());
+            let index = self.declare(name, BindingKind::Import, span);
+            self.resolution.bindings[index].path = Some(path);
+        }
+    }
+
+    fn fn_def(&mut self, def: &FnDef) {
+        for param in &def.params {
+            self.type_expr(&param.ty);
+        }
+        if let Some(ret) = &def.return_type {
+            self.type_expr(ret);
+        }
+        // Effects are listed last in the signature, before contracts and body
+        let signature_end = def
+            .requires
+            .iter()
+            .chain(&def.ensures)
+            .map(|e| e.span().start)
+            .chain([def.body.span.start])
+            .min()
+            .unwrap_or(def.span.end);
+        for effect in &def.effects {
+            if let Some(span) = self.last_name_span(def.span.start, signature_end, effect) {
+                self.refer(effect, span);
+            }
+        }
+
+        self.push_scope();
+        for param in &def.params {
+            match &param.pattern {
+                Some(pattern) => self.pattern(pattern),
+                None => {
+                    let span = self.name_span(&param.span, &param.name);
+                    self.declare(&param.name, BindingKind::Param, span);
+                }
+            }
+        }
+        for clause in def.requires.iter().chain(&def.ensures) {
+            self.expr(clause);
+        }
+        self.block(&def.body);
+        self.pop_scope();
+    }
+
+    fn methods(&mut self, methods: &[TraitMethod]) {
+        for method in methods {
+            for param in &method.params {
+                self.type_expr(&param.ty);
+            }
+            if let Some(ret) = &method.return_type {
+                self.type_expr(ret);
+            }
+            if method.requires.is_empty() && method.ensures.is_empty() {
+                continue;
+            }
+            self.push_scope();
+            for param in &method.params {
+                let span = self.name_span(&param.span, &param.name);
+                self.declare(&param.name, BindingKind::Param, span);
+            }
+            for clause in method.requires.iter().chain(&method.ensures) {
+                self.expr(clause);
+            }
+            self.pop_scope();
+        }
+    }
+
+    fn test(&mut self, using: Option<&UsingClause>, body: &Block) {
+        if let Some(using) = using {
+            for binding in &using.bindings {
+                let span = self.name_span(&binding.span, &binding.effect);
+                self.refer(&binding.effect, span);
+                self.expr(&binding.value);
+            }
+        }
+        self.block(body);
+    }
+
+    fn type_expr(&mut self, ty: &TypeExpr) {
+        match ty {
+            TypeExpr::Named {
+                span, name, args, ..
+            } => {
+                let span = self.name_span(span, name);
+                self.refer(name, span);
+                for arg in args {
+                    self.type_expr(arg);
+                }
+            }
+            TypeExpr::Record { fields, .. } => {
+                for field in fields {
+                    self.type_expr(&field.ty);
+                }
+            }
+            TypeExpr::Function { params, ret, .. } => {
+                for param in params {
+                    self.type_expr(param);
+                }
+                self.type_expr(ret);
+            }
+            TypeExpr::Tuple { elements, .. } => {
+                for element in elements {
+                    self.type_expr(element);
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.push_scope();
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr);
+        }
+        self.pop_scope();
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Let {
+                span,
+                name,
+                ty,
+                value,
+                ..
+            } => {
+                if let Some(ty) = ty {
+                    self.type_expr(ty);
+                }
+                // The value is resolved before the name comes into scope
+                self.expr(value);
+                let span = self.name_span(span, name);
+                self.declare(name, BindingKind::Local, span);
+            }
+            Stmt::LetPattern {
+                pattern, ty, value, ..
+            } => {
+                if let Some(ty) = ty {
+                    self.type_expr(ty);
+                }
+                self.expr(value);
+                self.pattern(pattern);
+            }
+            Stmt::Assign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            Stmt::Expr { expr, .. } => self.expr(expr),
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Ident { span, name, .. } => {
+                // A bare variant name matches the variant rather than binding
+                let variant = self
+                    .lookup(name)
+                    .filter(|&b| self.resolution.bindings[b].kind == BindingKind::Variant);
+                match variant {
+                    Some(binding) => self.resolution.references.push(Reference {
+                        span: span.clone(),
+                        binding,
+                    }),
+                    None => {
+                        self.declare(name, BindingKind::Local, span.clone());
+                    }
+                }
+            }
+            Pattern::Variant {
+                span, name, fields, ..
+            } => {
+                let span = self.name_span(span, name);
+                self.refer(name, span);
+                for field in fields {
+                    self.pattern(field);
+                }
+            }
+            Pattern::Record { fields, .. } => {
+                for (_, field) in fields {
+                    self.pattern(field);
+                }
+            }
+            Pattern::Tuple { elements, .. } => {
+                for element in elements {
+                    self.pattern(element);
+                }
+            }
+            Pattern::Wildcard { .. }
+            | Pattern::IntLit { .. }
+            | Pattern::FloatLit { .. }
+            | Pattern::BoolLit { .. }
+            | Pattern::TextLit { .. } => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::IntLit { .. }
+            | Expr::FloatLit { .. }
+            | Expr::DecimalLit { .. }
+            | Expr::BoolLit { .. }
+            | Expr::TextLit { .. }
+            | Expr::UnitLit { .. }
+            | Expr::QualifiedIdent { .. }
+            | Expr::Break { .. }
+            | Expr::Continue { .. }
+            | Expr::Hole { .. } => {}
+            Expr::Ident { span, name, .. } => self.refer(name, span.clone()),
+            Expr::Record { fields, .. } => {
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            Expr::FieldAccess { expr, .. }
+            | Expr::Unary { expr, .. }
+            | Expr::Try { expr, .. }
+            | Expr::Await { expr, .. } => self.expr(expr),
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Call { func, args, .. } => {
+                self.expr(func);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                self.expr(receiver);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            // Arguments are written in the caller's scope
+            Expr::MacroCall { args, .. } => {
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::If {
+                cond,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expr(cond);
+                self.block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.expr(else_branch);
+                }
+            }
+            Expr::Match { expr, arms, .. } => {
+                self.expr(expr);
+                for arm in arms {
+                    self.push_scope();
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                    self.pop_scope();
+                }
+            }
+            Expr::Block { block, .. } => self.block(block),
+            Expr::TryElse {
+                expr, else_expr, ..
+            } => {
+                self.expr(expr);
+                self.expr(else_expr);
+            }
+            Expr::ListLit { elements, .. } | Expr::TupleLit { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::MapLit { entries, .. } => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::Lambda {
+                params,
+                return_type,
+                body,
+                ..
+            } => {
+                for param in params {
+                    if let Some(ty) = &param.ty {
+                        self.type_expr(ty);
+                    }
+                }
+                if let Some(ret) = return_type {
+                    self.type_expr(ret);
+                }
+                self.push_scope();
+                for param in params {
+                    let span = self.name_span(&param.span, &param.name);
+                    self.declare(&param.name, BindingKind::Param, span);
+                }
+                self.block(body);
+                self.pop_scope();
+            }
+            Expr::Quantifier {
+                span,
+                binding,
+                iter,
+                body,
+                ..
+            } => {
+                self.expr(iter);
+                self.push_scope();
+                let span = self.name_span(span, binding);
+                self.declare(binding, BindingKind::Local, span);
+                self.expr(body);
+                self.pop_scope();
+            }
+            Expr::ForIn {
+                span,
+                binding,
+                pattern,
+                iter,
+                body,
+                ..
+            } => {
+                self.expr(iter);
+                self.push_scope();
+                match pattern {
+                    Some(pattern) => self.pattern(pattern),
+                    None => {
+                        let span = self.name_span(span, binding);
+                        self.declare(binding, BindingKind::Local, span);
+                    }
+                }
+                self.block(body);
+                self.pop_scope();
+            }
+            Expr::While { cond, body, .. } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            Expr::StringInterp { parts, .. } => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            Expr::Range { start, end, .. } => {
+                self.expr(start);
+                self.expr(end);
+            }
+            Expr::IndexAccess { expr, index, .. } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "resolve_tests.rs"]
+mod tests;
@@ -0,0 +1,205 @@
+use super::*;
+use crate::parser::parse_source;
+use std::path::PathBuf;
+
+fn source_file(source: &str) -> SourceFile {
+    SourceFile::new(PathBuf::from("test.astra"), source.to_string())
+}
+
+/// (line, col) of each reference found at `line:col`
+fn refs(source: &str, line: usize, col: usize, include_declaration: bool) -> Vec<(usize, usize)> {
+    let module = parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    find_references(
+        &module,
+        &source_file(source),
+        line,
+        col,
+        include_declaration,
+    )
+    .iter()
+    .map(|s| (s.start_line, s.start_col))
+    .collect()
+}
+
+const SOURCE: &str = r#"module app
+
+enum Shape =
+  | Circle(r: Float)
+  | Dot
+
+fn area(s: Shape) -> Float {
+  match s {
+    Circle(r) => r * r
+    Dot => 0.0
+  }
+}
+
+fn total(shapes: List[Shape]) -> Float {
+  let x = 1.0
+  let x = x + area(Dot)
+  x
+}
+"#;
+
+#[test]
+fn test_references_to_function() {
+    // From the declaration and from a use
+    assert_eq!(refs(SOURCE, 7, 4, true), vec![(7, 4), (16, 15)]);
+    assert_eq!(refs(SOURCE, 16, 16, false), vec![(16, 15)]);
+}
+
+#[test]
+fn test_references_to_type_and_variants() {
+    assert_eq!(refs(SOURCE, 3, 6, true), vec![(3, 6), (7, 12), (14, 23)]);
+    // Both constructor and pattern uses of a variant
+    assert_eq!(refs(SOURCE, 5, 5, true), vec![(5, 5), (10, 5), (16, 20)]);
+    assert_eq!(refs(SOURCE, 4, 5, false), vec![(9, 5)]);
+}
+
+#[test]
+fn test_references_follow_scoping() {
+    // Parameter `s` and pattern variable `r`
+    assert_eq!(refs(SOURCE, 7, 9, true), vec![(7, 9), (8, 9)]);
+    assert_eq!(refs(SOURCE, 9, 12, true), vec![(9, 12), (9, 18), (9, 22)]);
+    // The first `x` is used only in the value of the second
+    assert_eq!(refs(SOURCE, 15, 7, true), vec![(15, 7), (16, 11)]);
+    assert_eq!(refs(SOURCE, 16, 7, true), vec![(16, 7), (17, 3)]);
+}
+
+#[test]
+fn test_references_imports_and_effects() {
+    let source = r#"module app
+
+import std.math
+import std.text.{trim}
+
+effect Logger {
+  fn log(msg: Text) -> Unit
+}
+
+fn run(s: Text) -> Int effects(Logger) {
+  Logger.log(trim(s))
+  math.abs(-1)
+}
+"#;
+    assert_eq!(refs(source, 3, 12, true), vec![(3, 12), (12, 3)]);
+    assert_eq!(refs(source, 4, 18, true), vec![(4, 18), (11, 14)]);
+    assert_eq!(refs(source, 6, 8, true), vec![(6, 8), (10, 32), (11, 3)]);
+}
+
+#[test]
+fn test_references_lambdas_and_loops() {
+    let source = r#"module app
+
+fn run(xs: List[Int]) -> Int {
+  let f = fn(x: Int) { x + 1 }
+  for x in xs {
+    f(x)
+  }
+  0
+}
+"#;
+    assert_eq!(refs(source, 4, 14, true), vec![(4, 14), (4, 24)]);
+    assert_eq!(refs(source, 5, 7, true), vec![(5, 7), (6, 7)]);
+    assert_eq!(refs(source, 4, 7, true), vec![(4, 7), (6, 5)]);
+}
+
+#[test]
+fn test_no_references_off_a_name() {
+    assert!(refs(SOURCE, 1, 1, true).is_empty());
+    assert!(refs(SOURCE, 8, 3, true).is_empty());
+}
+
+#[test]
+fn test_references_in_interpolation() {
+    let source = "module app\n\nfn greet(name: Text) -> Text {\n  \"hi ${name}\"\n}\n";
+    assert_eq!(refs(source, 3, 10, true), vec![(3, 10), (4, 9)]);
+}
+
+fn definition(
+    source: &str,
+    line: usize,
+    col: usize,
+    symbols: &SymbolTable,
+) -> Option<(String, BindingKind, usize, usize)> {
+    let module = parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    find_definition(&module, &source_file(source), line, col, symbols)
+        .map(|d| (d.module, d.kind, d.span.start_line, d.span.start_col))
+}
+
+fn text_module_symbols() -> SymbolTable {
+    let source = "module std.text\n\npublic fn trim(s: Text) -> Text {\n  s\n}\n";
+    let path = PathBuf::from("stdlib/text.astra");
+    let module = parse_source(source, &path).unwrap();
+    let mut symbols = SymbolTable::new();
+    symbols.add_module(
+        "std.text",
+        &module,
+        &SourceFile::new(path, source.to_string()),
+    );
+    symbols
+}
+
+#[test]
+fn test_definition_of_local_names() {
+    let symbols = SymbolTable::new();
+    let def = |line, col| definition(SOURCE, line, col, &symbols);
+    assert_eq!(
+        def(16, 16),
+        Some(("app".into(), BindingKind::Function, 7, 4))
+    );
+    assert_eq!(def(10, 5), Some(("app".into(), BindingKind::Variant, 5, 5)));
+    assert_eq!(def(17, 3), Some(("app".into(), BindingKind::Local, 16, 7)));
+    assert_eq!(def(8, 3), None);
+}
+
+#[test]
+fn test_definition_across_modules() {
+    let source = r#"module app
+
+import std.text
+import std.text.{trim}
+import std.list
+
+fn run(s: Text) -> Text {
+  text.trim(trim(s))
+}
+"#;
+    let symbols = text_module_symbols();
+    let def = |line, col| definition(source, line, col, &symbols);
+    // An imported item and `module.name` resolve into the other module
+    assert_eq!(
+        def(8, 13),
+        Some(("std.text".into(), BindingKind::Function, 3, 11))
+    );
+    assert_eq!(
+        def(8, 8),
+        Some(("std.text".into(), BindingKind::Function, 3, 11))
+    );
+    // The module name resolves to the module declaration
+    assert_eq!(
+        def(8, 3),
+        Some(("std.text".into(), BindingKind::Module, 1, 8))
+    );
+    // A module missing from the symbol table resolves to the import
+    assert_eq!(def(5, 12), Some(("app".into(), BindingKind::Import, 5, 12)));
+}
+
+#[test]
+fn test_qualified_uses() {
+    let source = r#"module app
+
+import std.text
+import std.text.{trim}
+
+fn run(s: Text) -> Text {
+  text.trim(trim(s))
+}
+"#;
+    let module = parse_source(source, &PathBuf::from("test.astra")).unwrap();
+    let uses: Vec<(usize, usize)> = qualified_uses(&module, &source_file(source), "std.text.trim")
+        .iter()
+        .map(|s| (s.start_line, s.start_col))
+        .collect();
+    assert_eq!(uses, vec![(4, 18), (7, 8), (7, 13)]);
+}
@@ -0,0 +1,377 @@
+//! Semantic diff between two versions of a module.
+//!
+//! Compares top-level items by kind and name rather than by text, reporting
+//! added and removed items, signature and visibility changes, effect
+//! additions and removals, contract changes, and body changes. Each change is
+//! flagged as breaking when it can affect existing callers of a public item.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::formatter::Formatter;
+use crate::parser::ast::*;
+
+/// Kind of top-level item a change applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemKind {
+    Fn,
+    Type,
+    Enum,
+    Trait,
+    Effect,
+}
+
+impl ItemKind {
+    /// Keyword used to declare this kind of item
+    pub fn keyword(self) -> &'static str {
+        match self {
+            ItemKind::Fn => "fn",
+            ItemKind::Type => "type",
+            ItemKind::Enum => "enum",
+            ItemKind::Trait => "trait",
+            ItemKind::Effect => "effect",
+        }
+    }
+}
+
+/// What changed about an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    VisibilityChanged,
+    SignatureChanged,
+    EffectsAdded,
+    EffectsRemoved,
+    ContractChanged,
+    BodyChanged,
+    VariantAdded,
+    VariantRemoved,
+    DefinitionChanged,
+}
+
+/// A single semantic change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub item: ItemKind,
+    /// Item name (`Enum.Variant` for variant changes)
+    pub name: String,
+    /// Relevant text before the change (signature, contract, definition)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// Relevant text after the change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Effects added or removed, for effect changes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<String>,
+    /// Whether existing callers of a public item may break
+    pub breaking: bool,
+}
+
+impl Change {
+    fn new(kind: ChangeKind, item: ItemKind, name: &str) -> Self {
+        Self {
+            kind,
+            item,
+            name: name.to_string(),
+            before: None,
+            after: None,
+            effects: Vec::new(),
+            breaking: false,
+        }
+    }
+
+    fn texts(mut self, before: String, after: String) -> Self {
+        self.before = Some(before);
+        self.after = Some(after);
+        self
+    }
+
+    fn breaking(mut self, breaking: bool) -> Self {
+        self.breaking = breaking;
+        self
+    }
+}
+
+/// Compare two versions of a module, returning changes in source order
+/// (items of the new module first, then removed items)
+pub fn diff_modules(old: &Module, new: &Module) -> Vec<Change> {
+    let mut f = Formatter::new();
+    let old_items = index_items(&old.items);
+    let new_items = index_items(&new.items);
+    let mut changes = Vec::new();
+
+    for (key, item) in &new_items {
+        match old_items.iter().find(|(k, _)| k == key) {
+            Some((_, old_item)) => diff_item(&mut f, old_item, item, &mut changes),
+            None => {
+                let (kind, name) = key;
+                changes.push(Change::new(ChangeKind::Added, *kind, name));
+            }
+        }
+    }
+    for ((kind, name), item) in &old_items {
+        if !new_items.iter().any(|((k, n), _)| k == kind && n == name) {
+            changes.push(Change::new(ChangeKind::Removed, *kind, name).breaking(is_public(item)));
+        }
+    }
+    changes
+}
+
+type ItemKey = (ItemKind, String);
+
+fn index_items(items: &[Item]) -> Vec<(ItemKey, &Item)> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let key = match item {
+                Item::FnDef(def) => (ItemKind::Fn, def.name.clone()),
+                Item::TypeDef(def) => (ItemKind::Type, def.name.clone()),
+                Item::EnumDef(def) => (ItemKind::Enum, def.name.clone()),
+                Item::TraitDef(def) => (ItemKind::Trait, def.name.clone()),
+                Item::EffectDef(def) => (ItemKind::Effect, def.name.clone()),
+                _ => return None,
+            };
+            Some((key, item))
+        })
+        .collect()
+}
+
+/// Types, enums, traits, and effects have no visibility modifier and are
+/// always visible to importers; functions are public only when marked so
+fn is_public(item: &Item) -> bool {
+    match item {
+        Item::FnDef(def) => def.visibility == Visibility::Public,
+        _ => true,
+    }
+}
+
+fn diff_item(f: &mut Formatter, old: &Item, new: &Item, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Item::FnDef(old), Item::FnDef(new)) => diff_fn(f, old, new, changes),
+        (Item::EnumDef(old), Item::EnumDef(new)) => diff_enum(f, old, new, changes),
+        (Item::TypeDef(old), Item::TypeDef(new)) => {
+            let before = typedef_text(f, old);
+            let after = typedef_text(f, new);
+            if before != after {
+                changes.push(
+                    Change::new(ChangeKind::DefinitionChanged, ItemKind::Type, &new.name)
+                        .texts(before, after)
+                        .breaking(true),
+                );
+            }
+        }
+        (Item::TraitDef(old), Item::TraitDef(new)) => {
+            let before = methods_text(f, &old.methods);
+            let after = methods_text(f, &new.methods);
+            if before != after {
+                changes.push(
+                    Change::new(ChangeKind::DefinitionChanged, ItemKind::Trait, &new.name)
+                        .texts(before, after)
+                        .breaking(true),
+                );
+            }
+        }
+        (Item::EffectDef(old), Item::EffectDef(new)) => {
+            let before = methods_text(f, &old.operations);
+            let after = methods_text(f, &new.operations);
+            if before != after {
+                changes.push(
+                    Change::new(ChangeKind::DefinitionChanged, ItemKind::Effect, &new.name)
+                        .texts(before, after)
+                        .breaking(true),
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+fn diff_fn(f: &mut Formatter, old: &FnDef, new: &FnDef, changes: &mut Vec<Change>) {
+    let public = old.visibility == Visibility::Public;
+
+    if old.visibility != new.visibility {
+        changes.push(
+            Change::new(ChangeKind::VisibilityChanged, ItemKind::Fn, &new.name)
+                .texts(
+                    visibility_text(old.visibility),
+                    visibility_text(new.visibility),
+                )
+                .breaking(public),
+        );
+    }
+
+    // Signatures are compared without effects, which are reported separately
+    let before = fn_signature_without_effects(f, old);
+    let after = fn_signature_without_effects(f, new);
+    if before != after {
+        changes.push(
+            Change::new(ChangeKind::SignatureChanged, ItemKind::Fn, &new.name)
+                .texts(before, after)
+                .breaking(public),
+        );
+    }
+
+    let added: Vec<String> = new
+        .effects
+        .iter()
+        .filter(|e| !old.effects.contains(e))
+        .cloned()
+        .collect();
+    let removed: Vec<String> = old
+        .effects
+        .iter()
+        .filter(|e| !new.effects.contains(e))
+        .cloned()
+        .collect();
+    for (kind, effects) in [
+        (ChangeKind::EffectsAdded, added),
+        (ChangeKind::EffectsRemoved, removed),
+    ] {
+        if !effects.is_empty() {
+            let mut change = Change::new(kind, ItemKind::Fn, &new.name)
+                .texts(old.effects.join(", "), new.effects.join(", "))
+                // Callers must declare newly added effects themselves
+                .breaking(public && kind == ChangeKind::EffectsAdded);
+            change.effects = effects;
+            changes.push(change);
+        }
+    }
+
+    let before = contract_text(f, old);
+    let after = contract_text(f, new);
+    if before != after {
+        changes.push(
+            Change::new(ChangeKind::ContractChanged, ItemKind::Fn, &new.name)
+                .texts(before, after)
+                .breaking(public),
+        );
+    }
+
+    if f.block_text(&old.body) != f.block_text(&new.body) {
+        changes.push(Change::new(
+            ChangeKind::BodyChanged,
+            ItemKind::Fn,
+            &new.name,
+        ));
+    }
+}
+
+fn diff_enum(f: &mut Formatter, old: &EnumDef, new: &EnumDef, changes: &mut Vec<Change>) {
+    let old_variants: HashMap<&str, String> = old
+        .variants
+        .iter()
+        .map(|v| (v.name.as_str(), variant_text(f, v)))
+        .collect();
+    let new_variants: HashMap<&str, String> = new
+        .variants
+        .iter()
+        .map(|v| (v.name.as_str(), variant_text(f, v)))
+        .collect();
+
+    for variant in &new.variants {
+        let name = format!("{}.{}", new.name, variant.name);
+        match old_variants.get(variant.name.as_str()) {
+            // A new variant makes existing exhaustive matches non-exhaustive
+            None => changes
+                .push(Change::new(ChangeKind::VariantAdded, ItemKind::Enum, &name).breaking(true)),
+            Some(before) if *before != new_variants[variant.name.as_str()] => changes.push(
+                Change::new(ChangeKind::DefinitionChanged, ItemKind::Enum, &name)
+                    .texts(before.clone(), new_variants[variant.name.as_str()].clone())
+                    .breaking(true),
+            ),
+            Some(_) => {}
+        }
+    }
+    for variant in &old.variants {
+        if !new_variants.contains_key(variant.name.as_str()) {
+            let name = format!("{}.{}", old.name, variant.name);
+            changes.push(
+                Change::new(ChangeKind::VariantRemoved, ItemKind::Enum, &name).breaking(true),
+            );
+        }
+    }
+}
+
+fn visibility_text(visibility: Visibility) -> String {
+    match visibility {
+        Visibility::Public => "public".to_string(),
+        Visibility::Private => "private".to_string(),
+    }
+}
+
+fn fn_signature_without_effects(f: &mut Formatter, def: &FnDef) -> String {
+    let def = FnDef {
+        effects: Vec::new(),
+        effects_span: None,
+        visibility: Visibility::Private,
+        ..def.clone()
+    };
+    f.fn_signature(&def)
+}
+
+fn contract_text(f: &mut Formatter, def: &FnDef) -> String {
+    let mut clauses = Vec::new();
+    for req in &def.requires {
+        clauses.push(format!("requires {}", f.expr_text(req)));
+    }
+    for ens in &def.ensures {
+        clauses.push(format!("ensures {}", f.expr_text(ens)));
+    }
+    clauses.join("\n")
+}
+
+fn typedef_text(f: &mut Formatter, def: &TypeDef) -> String {
+    let mut text = f.type_expr_text(&def.value);
+    if let Some(invariant) = &def.invariant {
+        text.push_str(&format!(" invariant {}", f.expr_text(invariant)));
+    }
+    text
+}
+
+fn variant_text(f: &mut Formatter, variant: &Variant) -> String {
+    if variant.fields.is_empty() {
+        return variant.name.clone();
+    }
+    let fields: Vec<String> = variant
+        .fields
+        .iter()
+        .map(|field| format!("{}: {}", field.name, f.type_expr_text(&field.ty)))
+        .collect();
+    format!("{}({})", variant.name, fields.join(", "))
+}
+
+fn methods_text(f: &mut Formatter, methods: &[TraitMethod]) -> String {
+    methods
+        .iter()
+        .map(|m| {
+            let params: Vec<String> = m
+                .params
+                .iter()
+                .map(|p| format!("{}: {}", p.name, f.type_expr_text(&p.ty)))
+                .collect();
+            let ret = m
+                .return_type
+                .as_ref()
+                .map(|t| format!(" -> {}", f.type_expr_text(t)))
+                .unwrap_or_default();
+            let mut contracts = String::new();
+            for clause in &m.requires {
+                contracts.push_str(&format!("\n  requires {}", f.expr_text(clause)));
+            }
+            for clause in &m.ensures {
+                contracts.push_str(&format!("\n  ensures {}", f.expr_text(clause)));
+            }
+            format!("fn {}({}){}{}", m.name, params.join(", "), ret, contracts)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+#[path = "semantic_diff_tests.rs"]
+mod tests;
@@ -0,0 +1,96 @@
+use super::*;
+use crate::parser::parse_source;
+use std::path::PathBuf;
+
+fn diff(old: &str, new: &str) -> Vec<Change> {
+    let old = parse_source(old, &PathBuf::from("old.astra")).unwrap();
+    let new = parse_source(new, &PathBuf::from("new.astra")).unwrap();
+    diff_modules(&old, &new)
+}
+
+fn kinds(changes: &[Change]) -> Vec<(ChangeKind, &str)> {
+    changes.iter().map(|c| (c.kind, c.name.as_str())).collect()
+}
+
+#[test]
+fn test_identical_modules_have_no_changes() {
+    let source = "module m\n\nfn f(x: Int) -> Int {\n  x\n}\n";
+    // Formatting differences are not semantic changes
+    let reformatted = "module m\nfn f(x:Int)->Int{ x }\n";
+    assert!(diff(source, reformatted).is_empty());
+}
+
+#[test]
+fn test_added_and_removed_functions() {
+    let changes = diff(
+        "module m\n\npublic fn old() -> Int {\n  1\n}\n",
+        "module m\n\nfn new() -> Int {\n  1\n}\n",
+    );
+    assert_eq!(
+        kinds(&changes),
+        vec![(ChangeKind::Added, "new"), (ChangeKind::Removed, "old")]
+    );
+    assert!(!changes[0].breaking);
+    assert!(changes[1].breaking, "removing a public fn is breaking");
+}
+
+#[test]
+fn test_signature_and_effect_changes() {
+    let changes = diff(
+        "module m\n\npublic fn save(x: Int) -> Unit effects(Console) {\n  ()\n}\n",
+        "module m\n\npublic fn save(x: Int, y: Int) -> Unit effects(Console, Fs) {\n  ()\n}\n",
+    );
+    assert_eq!(
+        kinds(&changes),
+        vec![
+            (ChangeKind::SignatureChanged, "save"),
+            (ChangeKind::EffectsAdded, "save"),
+        ]
+    );
+    assert_eq!(
+        changes[0].before.as_deref(),
+        Some("fn save(x: Int) -> Unit")
+    );
+    assert_eq!(
+        changes[0].after.as_deref(),
+        Some("fn save(x: Int, y: Int) -> Unit")
+    );
+    assert_eq!(changes[1].effects, vec!["Fs".to_string()]);
+    assert!(changes[1].breaking);
+}
+
+#[test]
+fn test_contract_and_body_changes() {
+    let changes = diff(
+        "module m\n\nfn div(a: Int, b: Int) -> Int\n  requires b != 0\n{\n  a / b\n}\n",
+        "module m\n\nfn div(a: Int, b: Int) -> Int\n  requires b > 0\n{\n  a / b + 0\n}\n",
+    );
+    assert_eq!(
+        kinds(&changes),
+        vec![
+            (ChangeKind::ContractChanged, "div"),
+            (ChangeKind::BodyChanged, "div"),
+        ]
+    );
+    assert_eq!(changes[0].after.as_deref(), Some("requires b > 0"));
+    assert!(!changes[0].breaking, "private fns never break callers");
+}
+
+#[test]
+fn test_enum_variant_changes() {
+    let changes = diff(
+        "module m\n\nenum Shape =\n  | Circle(r: Float)\n  | Square(s: Float)\n",
+        "module m\n\nenum Shape =\n  | Circle(r: Int)\n  | Triangle(b: Float)\n",
+    );
+    assert_eq!(
+        kinds(&changes),
+        vec![
+            (ChangeKind::DefinitionChanged, "Shape.Circle"),
+            (ChangeKind::VariantAdded, "Shape.Triangle"),
+            (ChangeKind::VariantRemoved, "Shape.Square"),
+        ]
+    );
+    let json = serde_json::to_value(&changes[1]).unwrap();
+    assert_eq!(json["kind"], "variant_added");
+    assert_eq!(json["item"], "enum");
+}
@@ -0,0 +1,249 @@
+//! Semantic token classification: the category of every meaningful span of
+//! a source, for syntax highlighting that knows what names refer to.
+//!
+//! Tokens come from the lossless lexer, so comments are classified too.
+//! Identifiers are classified by what they resolve to (see [`crate::resolve`]):
+//! a parameter is highlighted as one wherever it is used. Names that resolve
+//! to nothing in the module (builtins, methods, fields, built-in effects) are
+//! classified from their context. Without a parsed module, only the lexical
+//! categories are reliable. Used by `astra tokens --semantic` and by the
+//! language server's `textDocument/semanticTokens/full`.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::diagnostics::Span;
+use crate::parser::ast::Module;
+use crate::parser::lexer::{tokenize_lossless, LosslessToken, TokenKind, TriviaKind};
+use crate::parser::span::SourceFile;
+use crate::resolve::{resolve_module, BindingKind};
+
+/// Category of a span; names follow the LSP's semantic token types, with
+/// `effect` and `contract` added
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SemanticKind {
+    Keyword,
+    /// `requires`, `ensures`, and `invariant`
+    Contract,
+    Function,
+    Method,
+    Parameter,
+    Variable,
+    Property,
+    /// Types, enums, and traits
+    Type,
+    /// Enum variants, including `Some`, `None`, `Ok`, and `Err`
+    EnumMember,
+    Effect,
+    /// Modules in `module` declarations and imports
+    Namespace,
+    /// Attribute names, as in `@cfg`
+    Decorator,
+    String,
+    Number,
+    Comment,
+    Operator,
+}
+
+impl SemanticKind {
+    /// Every kind, in the order of the language server's legend
+    pub const ALL: [SemanticKind; 16] = [
+        SemanticKind::Keyword,
+        SemanticKind::Contract,
+        SemanticKind::Function,
+        SemanticKind::Method,
+        SemanticKind::Parameter,
+        SemanticKind::Variable,
+        SemanticKind::Property,
+        SemanticKind::Type,
+        SemanticKind::EnumMember,
+        SemanticKind::Effect,
+        SemanticKind::Namespace,
+        SemanticKind::Decorator,
+        SemanticKind::String,
+        SemanticKind::Number,
+        SemanticKind::Comment,
+        SemanticKind::Operator,
+    ];
+
+    /// Name of the kind, as serialized
+    pub fn name(self) -> &'static str {
+        match self {
+            SemanticKind::Keyword => "keyword",
+            SemanticKind::Contract => "contract",
+            SemanticKind::Function => "function",
+            SemanticKind::Method => "method",
+            SemanticKind::Parameter => "parameter",
+            SemanticKind::Variable => "variable",
+            SemanticKind::Property => "property",
+            SemanticKind::Type => "type",
+            SemanticKind::EnumMember => "enumMember",
+            SemanticKind::Effect => "effect",
+            SemanticKind::Namespace => "namespace",
+            SemanticKind::Decorator => "decorator",
+            SemanticKind::String => "string",
+            SemanticKind::Number => "number",
+            SemanticKind::Comment => "comment",
+            SemanticKind::Operator => "operator",
+        }
+    }
+}
+
+/// A classified span
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SemanticToken {
+    pub span: Span,
+    pub kind: SemanticKind,
+    /// The span declares the name rather than using it
+    pub declaration: bool,
+}
+
+/// Effects every program can name without declaring them
+const BUILTIN_EFFECTS: [&str; 11] = [
+    "Console", "Fs", "Net", "Clock", "Rand", "Env", "Crypto", "Serve", "Db", "Progress", "Metrics",
+];
+
+/// Classify the spans of `source`, in source order. `module` must have been
+/// parsed from `source`; without it, names are classified from context only.
+pub fn classify(source: &SourceFile, module: Option<&Module>) -> Vec<SemanticToken> {
+    // Binding kind of each name by start offset, and whether it declares it
+    let mut names: HashMap<usize, (BindingKind, bool)> = HashMap::new();
+    if let Some(module) = module {
+        let resolution = resolve_module(module, source);
+        for reference in &resolution.references {
+            let kind = resolution.bindings[reference.binding].kind;
+            names.insert(reference.span.start, (kind, false));
+        }
+        for binding in &resolution.bindings {
+            names.insert(binding.span.start, (binding.kind, true));
+        }
+    }
+
+    let tokens = tokenize_lossless(source);
+    let mut classified = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        for trivia in &token.leading_trivia {
+            if trivia.kind != TriviaKind::Whitespace {
+                classified.push(SemanticToken {
+                    span: trivia.span.clone(),
+                    kind: SemanticKind::Comment,
+                    declaration: false,
+                });
+            }
+        }
+        let Some(kind) = &token.kind else { continue };
+        let (kind, declaration) = match kind {
+            TokenKind::Ident(name) => match names.get(&token.span.start) {
+                Some(&(binding, declaration)) => {
+                    (binding_kind(binding, name, &tokens, i), declaration)
+                }
+                None => (unresolved_kind(name, &tokens, i), false),
+            },
+            other => match lexical_kind(other) {
+                Some(kind) => (kind, false),
+                None => continue,
+            },
+        };
+        classified.push(SemanticToken {
+            span: token.span.clone(),
+            kind,
+            declaration,
+        });
+    }
+    classified
+}
+
+/// Category of a name that resolves to a binding
+fn binding_kind(
+    binding: BindingKind,
+    name: &str,
+    tokens: &[LosslessToken],
+    at: usize,
+) -> SemanticKind {
+    match binding {
+        BindingKind::Function => SemanticKind::Function,
+        BindingKind::Type | BindingKind::Enum | BindingKind::Trait => SemanticKind::Type,
+        BindingKind::Variant => SemanticKind::EnumMember,
+        BindingKind::Effect => SemanticKind::Effect,
+        BindingKind::Module => SemanticKind::Namespace,
+        BindingKind::Param => SemanticKind::Parameter,
+        BindingKind::Local => SemanticKind::Variable,
+        // An import can bring in a module or any item: go by its use
+        BindingKind::Import => match unresolved_kind(name, tokens, at) {
+            SemanticKind::Variable => SemanticKind::Namespace,
+            kind => kind,
+        },
+    }
+}
+
+/// Category of a name from its context: the tokens around it and its case
+fn unresolved_kind(name: &str, tokens: &[LosslessToken], at: usize) -> SemanticKind {
+    let kind_at = |i: Option<usize>| i.and_then(|i| tokens.get(i)).and_then(|t| t.kind.as_ref());
+    let previous = kind_at(at.checked_sub(1));
+    let called = matches!(
+        kind_at(Some(at + 1)),
+        Some(TokenKind::LParen) | Some(TokenKind::Bang)
+    );
+    match previous {
+        Some(TokenKind::At) => return SemanticKind::Decorator,
+        Some(TokenKind::Module) | Some(TokenKind::Import) => return SemanticKind::Namespace,
+        Some(TokenKind::Dot) => {
+            // Segments of a module path stay namespaces
+            let in_path = tokens[..at]
+                .iter()
+                .rev()
+                .take_while(|t| matches!(t.kind, Some(TokenKind::Ident(_)) | Some(TokenKind::Dot)))
+                .count();
+            let before_path = kind_at((at - in_path).checked_sub(1));
+            if matches!(
+                before_path,
+                Some(TokenKind::Module) | Some(TokenKind::Import)
+            ) {
+                return SemanticKind::Namespace;
+            }
+            return if called {
+                SemanticKind::Method
+            } else {
+                SemanticKind::Property
+            };
+        }
+        _ => {}
+    }
+    if BUILTIN_EFFECTS.contains(&name) {
+        SemanticKind::Effect
+    } else if matches!(name, "Some" | "None" | "Ok" | "Err") {
+        SemanticKind::EnumMember
+    } else if name.starts_with(char::is_uppercase) {
+        SemanticKind::Type
+    } else if called {
+        SemanticKind::Function
+    } else {
+        SemanticKind::Variable
+    }
+}
+
+/// Category of a token that isn't a name
+fn lexical_kind(kind: &TokenKind) -> Option<SemanticKind> {
+    use TokenKind::*;
+    Some(match kind {
+        Requires | Ensures | Invariant => SemanticKind::Contract,
+        AfterEach | And | As | Assert | BeforeEach | Else | Effects | Enum | False | Fn | For
+        | Forall | If | Import | In | Let | Match | Module | Mut | Not | Or | Property | Public
+        | Return | Test | Then | True | Type | Using | While | Break | Continue | Trait | Impl
+        | Effect | Await | Async | Macro => SemanticKind::Keyword,
+        IntLit(_) | FloatLit(_) | DecimalLit(_) => SemanticKind::Number,
+        TextLit(_) | MultilineTextLit(_) => SemanticKind::String,
+        Plus | Minus | Star | Slash | Percent | PlusEq | MinusEq | StarEq | SlashEq | PercentEq
+        | EqEq | BangEq | Lt | Gt | LtEq | GtEq | Question | QuestionElse | Eq | Arrow
+        | FatArrow | PipeArrow | Pipe | DotDotEq | DotDot | Bang | Ellipsis => {
+            SemanticKind::Operator
+        }
+        MacroVar(_) => SemanticKind::Parameter,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+#[path = "semantic_tokens_tests.rs"]
+mod tests;
@@ -0,0 +1,124 @@
+use super::*;
+use crate::parser::parse_source;
+use std::path::PathBuf;
+
+const SOURCE: &str = r#"module app
+
+import std.math
+
+## A shape
+enum Shape =
+  | Circle(r: Float)
+  | Dot
+
+effect Logger {
+  fn log(msg: Text) -> Unit
+}
+
+fn area(s: Shape, scale: Float) -> Float
+  requires scale > 0.0
+{
+  let k = scale * 2.0
+  match s {
+    Circle(r) => r * k
+    Dot => math.pi()
+  }
+}
+
+fn main() effects(Console) {
+  Console.println(to_text(area(Dot, 1.0)))
+}
+"#;
+
+/// (text, kind, declaration) of each classified span
+fn classified(source: &str, parse: bool) -> Vec<(String, SemanticKind, bool)> {
+    let path = PathBuf::from("test.astra");
+    let module = parse.then(|| parse_source(source, &path).unwrap());
+    let source_file = SourceFile::new(path, source.to_string());
+    classify(&source_file, module.as_ref())
+        .into_iter()
+        .map(|t| {
+            (
+                source[t.span.start..t.span.end].to_string(),
+                t.kind,
+                t.declaration,
+            )
+        })
+        .collect()
+}
+
+/// Kinds of every span with text `text`
+fn kinds_of(tokens: &[(String, SemanticKind, bool)], text: &str) -> Vec<(SemanticKind, bool)> {
+    tokens
+        .iter()
+        .filter(|(t, _, _)| t == text)
+        .map(|(_, kind, declaration)| (*kind, *declaration))
+        .collect()
+}
+
+#[test]
+fn test_names_are_classified_by_resolution() {
+    use SemanticKind::*;
+    let tokens = classified(SOURCE, true);
+    assert_eq!(
+        kinds_of(&tokens, "area"),
+        vec![(Function, true), (Function, false)]
+    );
+    assert_eq!(
+        kinds_of(&tokens, "scale"),
+        vec![(Parameter, true), (Parameter, false), (Parameter, false)]
+    );
+    assert_eq!(
+        kinds_of(&tokens, "k"),
+        vec![(Variable, true), (Variable, false)]
+    );
+    assert_eq!(
+        kinds_of(&tokens, "Shape"),
+        vec![(Type, true), (Type, false)]
+    );
+    assert_eq!(
+        kinds_of(&tokens, "Dot"),
+        vec![(EnumMember, true), (EnumMember, false), (EnumMember, false)]
+    );
+    assert_eq!(kinds_of(&tokens, "Logger"), vec![(Effect, true)]);
+    assert_eq!(kinds_of(&tokens, "Console"), vec![(Effect, false); 2]);
+}
+
+#[test]
+fn test_context_classifies_unresolved_names() {
+    use SemanticKind::*;
+    let tokens = classified(SOURCE, true);
+    assert_eq!(kinds_of(&tokens, "app"), vec![(Namespace, false)]);
+    assert_eq!(kinds_of(&tokens, "std"), vec![(Namespace, false)]);
+    assert_eq!(
+        kinds_of(&tokens, "math"),
+        vec![(Namespace, true), (Namespace, false)]
+    );
+    assert_eq!(kinds_of(&tokens, "pi"), vec![(Method, false)]);
+    assert_eq!(kinds_of(&tokens, "println"), vec![(Method, false)]);
+    assert_eq!(kinds_of(&tokens, "to_text"), vec![(Function, false)]);
+    assert_eq!(kinds_of(&tokens, "Float")[0], (Type, false));
+}
+
+#[test]
+fn test_keywords_contracts_literals_and_comments() {
+    use SemanticKind::*;
+    let tokens = classified(SOURCE, true);
+    assert_eq!(kinds_of(&tokens, "requires"), vec![(Contract, false)]);
+    assert_eq!(kinds_of(&tokens, "fn")[0], (Keyword, false));
+    assert_eq!(kinds_of(&tokens, "effects"), vec![(Keyword, false)]);
+    assert_eq!(kinds_of(&tokens, "2.0"), vec![(Number, false)]);
+    assert_eq!(kinds_of(&tokens, "## A shape"), vec![(Comment, false)]);
+    assert_eq!(kinds_of(&tokens, "=>"), vec![(Operator, false); 2]);
+    // Punctuation is not classified
+    assert!(kinds_of(&tokens, "(").is_empty());
+}
+
+#[test]
+fn test_classify_without_a_module() {
+    use SemanticKind::*;
+    let tokens = classified("module m\nfn f(x: Int) -> Int {\n  x + \"a\"", false);
+    assert_eq!(kinds_of(&tokens, "x"), vec![(Variable, false); 2]);
+    assert_eq!(kinds_of(&tokens, "Int"), vec![(Type, false); 2]);
+    assert_eq!(kinds_of(&tokens, "\"a\""), vec![(String, false)]);
+}
@@ -0,0 +1,151 @@
+//! Symbol index: the top-level definitions of a module with their signatures.
+//!
+//! Used by `astra symbols` for code navigation and for building retrieval
+//! context over a codebase.
+
+use serde::Serialize;
+
+use crate::diagnostics::Span;
+use crate::formatter::Formatter;
+use crate::parser::ast::*;
+
+/// Kind of a top-level definition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Fn,
+    Type,
+    Enum,
+    Trait,
+    Effect,
+}
+
+/// A top-level definition
+#[derive(Debug, Clone, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Name of the module that defines the symbol
+    pub module: String,
+    /// One-line declaration (e.g., `public fn read(path: Text) -> Text effects(Fs)`)
+    pub signature: String,
+    /// Whether importers can use the symbol; types, enums, traits, and effects
+    /// are always visible
+    pub public: bool,
+    /// Declared effects (functions only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<String>,
+    /// Doc comment (functions, types, enums, and traits)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+/// Collect the symbols defined by a module, in source order
+pub fn collect_symbols(module: &Module) -> Vec<Symbol> {
+    let mut f = Formatter::new();
+    let module_name = module.name.segments.join(".");
+    let symbol =
+        |name: &str, kind, signature, public, effects, doc: &Option<String>, span: &Span| Symbol {
+            name: name.to_string(),
+            kind,
+            module: module_name.clone(),
+            signature,
+            public,
+            effects,
+            doc: doc.clone(),
+            span: span.clone(),
+        };
+
+    module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::FnDef(def) => Some(symbol(
+                &def.name,
+                SymbolKind::Fn,
+                f.fn_signature(def),
+                def.visibility == Visibility::Public,
+                def.effects.clone(),
+                &def.doc,
+                &def.span,
+            )),
+            Item::TypeDef(def) => Some(symbol(
+                &def.name,
+                SymbolKind::Type,
+                format!(
+                    "type {}{} = {}",
+                    def.name,
+                    type_params_text(&def.type_params),
+                    f.type_expr_text(&def.value)
+                ),
+                true,
+                Vec::new(),
+                &def.doc,
+                &def.span,
+            )),
+            Item::EnumDef(def) => {
+                let variants: Vec<String> = def
+                    .variants
+                    .iter()
+                    .map(|v| {
+                        if v.fields.is_empty() {
+                            return v.name.clone();
+                        }
+                        let fields: Vec<String> = v
+                            .fields
+                            .iter()
+                            .map(|field| format!("{}: {}", field.name, f.type_expr_text(&field.ty)))
+                            .collect();
+                        format!("{}({})", v.name, fields.join(", "))
+                    })
+                    .collect();
+                Some(symbol(
+                    &def.name,
+                    SymbolKind::Enum,
+                    format!(
+                        "enum {}{} = {}",
+                        def.name,
+                        type_params_text(&def.type_params),
+                        variants.join(" | ")
+                    ),
+                    true,
+                    Vec::new(),
+                    &def.doc,
+                    &def.span,
+                ))
+            }
+            Item::TraitDef(def) => Some(symbol(
+                &def.name,
+                SymbolKind::Trait,
+                format!("trait {}{}", def.name, type_params_text(&def.type_params)),
+                true,
+                Vec::new(),
+                &def.doc,
+                &def.span,
+            )),
+            Item::EffectDef(def) => Some(symbol(
+                &def.name,
+                SymbolKind::Effect,
+                format!("effect {}", def.name),
+                true,
+                Vec::new(),
+                &None,
+                &def.span,
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+fn type_params_text(params: &[String]) -> String {
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("[{}]", params.join(", "))
+    }
+}
+
+#[cfg(test)]
+#[path = "symbols_tests.rs"]
+mod tests;
@@ -0,0 +1,59 @@
+use super::*;
+use crate::parser::parse_source;
+use std::path::PathBuf;
+
+fn symbols(source: &str) -> Vec<Symbol> {
+    collect_symbols(&parse_source(source, &PathBuf::from("test.astra")).unwrap())
+}
+
+#[test]
+fn test_collect_symbols() {
+    let found = symbols(
+        r#"module shapes.core
+
+type Point = { x: Int, y: Int }
+
+enum Shape =
+  | Circle(r: Float)
+  | Dot
+
+trait Area {
+  fn area(self) -> Float
+}
+
+## Writes a point to disk.
+public fn save(p: Point) -> Unit effects(Fs) {
+  ()
+}
+
+fn helper() -> Int {
+  1
+}
+"#,
+    );
+    let summary: Vec<(&str, SymbolKind, bool)> = found
+        .iter()
+        .map(|s| (s.name.as_str(), s.kind, s.public))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("Point", SymbolKind::Type, true),
+            ("Shape", SymbolKind::Enum, true),
+            ("Area", SymbolKind::Trait, true),
+            ("save", SymbolKind::Fn, true),
+            ("helper", SymbolKind::Fn, false),
+        ]
+    );
+    assert!(found.iter().all(|s| s.module == "shapes.core"));
+    assert_eq!(found[0].signature, "type Point = { x: Int, y: Int }");
+    assert_eq!(found[1].signature, "enum Shape = Circle(r: Float) | Dot");
+    assert_eq!(
+        found[3].signature,
+        "public fn save(p: Point) -> Unit effects(Fs)"
+    );
+    assert_eq!(found[3].effects, vec!["Fs".to_string()]);
+    assert_eq!(found[3].span.start_line, 14);
+    assert_eq!(found[3].doc.as_deref(), Some("Writes a point to disk."));
+    assert_eq!(found[4].doc, None);
+}
@@ -0,0 +1,89 @@
+//! Persisted property-test failures
+//!
+//! When a property test fails, its seed and shrunken input are written to
+//! `.astra-failures` in the project root. Later runs of `astra test` replay
+//! these cases before generating new ones, and drop them once they pass.
+
+use crate::interpreter::RandDraw;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Name of the failures file in the project root
+pub const FAILURES_FILE: &str = ".astra-failures";
+
+/// A failing case of a property test
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyFailure {
+    /// Qualified name of the property (`module::name`)
+    pub test: String,
+    /// Seed of the iteration that first failed
+    pub seed: u64,
+    /// The shrunken values drawn from `Rand`
+    pub inputs: Vec<RandDraw>,
+    /// Error reported for the shrunken input
+    pub error: String,
+}
+
+/// The on-disk set of failing cases
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FailureStore {
+    failures: Vec<PropertyFailure>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl FailureStore {
+    /// Load the failures file, returning an empty store on any error.
+    pub fn load(project_root: &Path) -> Self {
+        std::fs::read_to_string(project_root.join(FAILURES_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the store back if it changed. The file is removed once no
+    /// failures are left.
+    pub fn save(&self, project_root: &Path) -> Result<(), String> {
+        if !self.changed {
+            return Ok(());
+        }
+        let path = project_root.join(FAILURES_FILE);
+        if self.failures.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .map_err(|e| format!("Failed to remove {}: {}", FAILURES_FILE, e))?;
+            }
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize failures: {}", e))?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", FAILURES_FILE, e))
+    }
+
+    /// The recorded failure of a property, if any
+    pub fn get(&self, test: &str) -> Option<&PropertyFailure> {
+        self.failures.iter().find(|f| f.test == test)
+    }
+
+    /// Record a failure, replacing any earlier one for the same property
+    pub fn record(&mut self, failure: PropertyFailure) {
+        if self.get(&failure.test) == Some(&failure) {
+            return;
+        }
+        self.remove(&failure.test);
+        self.failures.push(failure);
+        self.changed = true;
+    }
+
+    /// Forget the failure of a property that passes again
+    pub fn remove(&mut self, test: &str) {
+        let before = self.failures.len();
+        self.failures.retain(|f| f.test != test);
+        self.changed |= self.failures.len() != before;
+    }
+
+    /// Whether no failures are recorded
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
@@ -5,6 +5,8 @@
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+pub mod failures;
+
 /// Test result status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -98,6 +100,83 @@ impl Default for TestConfig {
     }
 }
 
+/// Full name of a test: the module path and the test name, as in
+/// `app.math::addition`
+pub fn qualified_test_name(module: &str, name: &str) -> String {
+    format!("{}::{}", module, name)
+}
+
+/// Whether a test's qualified name matches a filter.
+///
+/// A filter containing `::` selects by module: `app.math::` runs every test in
+/// `app.math` and its submodules, and `app.math::add` only those whose name
+/// contains `add`. Any other filter matches a substring of the qualified name.
+pub fn matches_filter(filter: &str, qualified_name: &str) -> bool {
+    let Some((module_filter, name_filter)) = filter.split_once("::") else {
+        return qualified_name.contains(filter);
+    };
+    let (module, name) = qualified_name
+        .split_once("::")
+        .unwrap_or(("", qualified_name));
+    let module_matches = module_filter.is_empty()
+        || module == module_filter
+        || module
+            .strip_prefix(module_filter)
+            .is_some_and(|rest| rest.starts_with('.'));
+    module_matches && name.contains(name_filter)
+}
+
+/// How long one test took to run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestTiming {
+    /// Qualified test name
+    pub name: String,
+    /// Wall time, including every case of a property test
+    pub duration: Duration,
+    /// Expressions the interpreter evaluated
+    pub steps: u64,
+}
+
+/// The `n` slowest tests, slowest first. Ties keep the order the tests ran in.
+pub fn slowest(timings: &[TestTiming], n: usize) -> Vec<&TestTiming> {
+    let mut sorted: Vec<&TestTiming> = timings.iter().collect();
+    sorted.sort_by_key(|t| std::cmp::Reverse(t.duration));
+    sorted.truncate(n);
+    sorted
+}
+
+/// Parse a duration such as `500ms`, `2s`, `1.5s`, or `1m`
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let (number, unit_secs) = if let Some(ms) = text.strip_suffix("ms") {
+        (ms, 0.001)
+    } else if let Some(s) = text.strip_suffix('s') {
+        (s, 1.0)
+    } else if let Some(m) = text.strip_suffix('m') {
+        (m, 60.0)
+    } else {
+        return Err(format!(
+            "invalid duration `{}`: expected a unit of ms, s, or m",
+            text
+        ));
+    };
+    match number.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 0.0 => {
+            Ok(Duration::from_secs_f64(value * unit_secs))
+        }
+        _ => Err(format!("invalid duration `{}`", text)),
+    }
+}
+
+/// Format a test duration as milliseconds, or seconds from one second up
+pub fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
+
 /// Test runner
 pub struct TestRunner {
     config: TestConfig,
@@ -223,6 +302,8 @@ pub mod assert {
 
 /// Property testing support
 pub mod property {
+    use crate::interpreter::RandDraw;
+
     /// Generator for random values
     pub trait Generator<T> {
         fn generate(&self, rng: &mut dyn FnMut() -> u64) -> T;
@@ -274,6 +355,71 @@ pub mod property {
         }
     }
 
+    /// Smaller candidates for a single recorded draw, all within its range
+    pub fn shrink_draw(draw: &RandDraw) -> Vec<RandDraw> {
+        match *draw {
+            RandDraw::Int { value, min, max } => {
+                let mut values: Vec<i64> = IntGenerator { min, max }
+                    .shrink(value)
+                    .into_iter()
+                    .filter(|v| (min..=max).contains(v))
+                    .collect();
+                // Ranges that exclude zero shrink towards the bound nearest to it
+                let target = 0.clamp(min, max);
+                if value != target && !values.contains(&target) {
+                    values.push(target);
+                }
+                values
+                    .into_iter()
+                    .map(|value| RandDraw::Int { value, min, max })
+                    .collect()
+            }
+            RandDraw::Bool(value) => BoolGenerator
+                .shrink(value)
+                .into_iter()
+                .map(RandDraw::Bool)
+                .collect(),
+            RandDraw::Float(value) if value != 0.0 => vec![RandDraw::Float(0.0)],
+            RandDraw::Float(_) => vec![],
+        }
+    }
+
+    /// Shrink the draws of a failing property run.
+    ///
+    /// `fails` replays a script of draws and returns the draws actually made
+    /// and the error if the property still fails. At most `max_runs` replays
+    /// are attempted.
+    pub fn shrink_draws<E, F>(
+        draws: Vec<RandDraw>,
+        error: E,
+        max_runs: usize,
+        mut fails: F,
+    ) -> (Vec<RandDraw>, E)
+    where
+        F: FnMut(&[RandDraw]) -> Option<(Vec<RandDraw>, E)>,
+    {
+        let mut smallest = (draws, error);
+        let mut runs = 0;
+        'improve: loop {
+            for i in 0..smallest.0.len() {
+                for candidate in shrink_draw(&smallest.0[i]) {
+                    if runs == max_runs {
+                        break 'improve;
+                    }
+                    runs += 1;
+                    let mut attempt = smallest.0.clone();
+                    attempt[i] = candidate;
+                    if let Some(failure) = fails(&attempt) {
+                        smallest = failure;
+                        continue 'improve;
+                    }
+                }
+            }
+            break;
+        }
+        smallest
+    }
+
     /// Run a property test
     pub fn run_property<T, F>(
         generator: &dyn Generator<T>,
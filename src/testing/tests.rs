@@ -20,3 +20,129 @@ fn test_property_basic() {
 
     assert!(result.is_ok());
 }
+
+#[test]
+fn test_qualified_test_name() {
+    assert_eq!(
+        qualified_test_name("app.math", "addition works"),
+        "app.math::addition works"
+    );
+}
+
+#[test]
+fn test_matches_filter() {
+    let name = "app.math::addition works";
+    // Plain filters match anywhere in the qualified name
+    assert!(matches_filter("addition", name));
+    assert!(matches_filter("math", name));
+    assert!(!matches_filter("subtraction", name));
+
+    // Module filters match the module and its submodules
+    assert!(matches_filter("app.math::", name));
+    assert!(matches_filter("app::", name));
+    assert!(matches_filter("::", name));
+    assert!(!matches_filter("app.mat::", name));
+    assert!(!matches_filter("math::", name));
+
+    // The part after `::` must occur in the test name
+    assert!(matches_filter("app.math::add", name));
+    assert!(!matches_filter("app.math::sub", name));
+    assert!(matches_filter("::works", name));
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+    assert_eq!(parse_duration("2s"), Ok(Duration::from_secs(2)));
+    assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+    assert_eq!(parse_duration("1m"), Ok(Duration::from_secs(60)));
+    assert!(parse_duration("500").is_err());
+    assert!(parse_duration("-1s").is_err());
+    assert!(parse_duration("fast ms").is_err());
+}
+
+#[test]
+fn test_slowest() {
+    let timing = |name: &str, millis: u64| TestTiming {
+        name: name.to_string(),
+        duration: Duration::from_millis(millis),
+        steps: 0,
+    };
+    let timings = vec![
+        timing("a", 10),
+        timing("b", 30),
+        timing("c", 20),
+        timing("d", 30),
+    ];
+    let names: Vec<&str> = slowest(&timings, 3)
+        .iter()
+        .map(|t| t.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["b", "d", "c"]);
+    assert_eq!(slowest(&timings, 10).len(), 4);
+
+    assert_eq!(format_duration(Duration::from_millis(812)), "812.0ms");
+    assert_eq!(format_duration(Duration::from_millis(1234)), "1.23s");
+}
+
+#[test]
+fn test_shrink_draws() {
+    use crate::interpreter::RandDraw;
+
+    let int = |value| RandDraw::Int {
+        value,
+        min: -1000,
+        max: 1000,
+    };
+    // Fails whenever the first int is at least 37 or the bool is true
+    let fails = |draws: &[RandDraw]| {
+        let failing = match draws {
+            [RandDraw::Int { value, .. }, RandDraw::Bool(flag)] => *value >= 37 || *flag,
+            _ => false,
+        };
+        failing.then(|| (draws.to_vec(), "failed"))
+    };
+    let (smallest, error) =
+        property::shrink_draws(vec![int(912), RandDraw::Bool(false)], "failed", 1000, fails);
+    assert_eq!(smallest, vec![int(37), RandDraw::Bool(false)]);
+    assert_eq!(error, "failed");
+
+    // Ranges that exclude zero shrink towards their lower bound
+    assert!(property::shrink_draw(&RandDraw::Int {
+        value: 50,
+        min: 10,
+        max: 100
+    })
+    .contains(&RandDraw::Int {
+        value: 10,
+        min: 10,
+        max: 100
+    }));
+}
+
+#[test]
+fn test_failure_store_round_trip() {
+    use crate::interpreter::RandDraw;
+    use failures::{FailureStore, PropertyFailure, FAILURES_FILE};
+
+    let dir = tempfile::tempdir().unwrap();
+    let failure = PropertyFailure {
+        test: "app::sorted".to_string(),
+        seed: 45,
+        inputs: vec![RandDraw::Bool(true)],
+        error: "assertion failed".to_string(),
+    };
+
+    let mut store = FailureStore::load(dir.path());
+    assert!(store.is_empty());
+    store.record(failure.clone());
+    store.save(dir.path()).unwrap();
+
+    let mut store = FailureStore::load(dir.path());
+    assert_eq!(store.get("app::sorted"), Some(&failure));
+
+    // The file goes away once the last failure passes
+    store.remove("app::sorted");
+    store.save(dir.path()).unwrap();
+    assert!(!dir.path().join(FAILURES_FILE).exists());
+}
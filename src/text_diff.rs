@@ -0,0 +1,229 @@
+//! Line-based text diffs in unified format.
+//!
+//! Used to preview source rewrites (`astra rename`) before they are applied.
+//! Lines are compared with Myers' algorithm, which finds a shortest edit
+//! script, and grouped into hunks with three lines of context.
+
+/// Lines of unchanged context around each hunk
+const CONTEXT: usize = 3;
+
+/// One line of a hunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+/// A group of nearby changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// First line of the hunk in the old text (1-indexed)
+    pub old_start: usize,
+    pub old_len: usize,
+    /// First line of the hunk in the new text (1-indexed)
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// The differences between two texts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextDiff {
+    pub hunks: Vec<Hunk>,
+}
+
+impl TextDiff {
+    /// Compare `old` and `new` line by line
+    pub fn new(old: &str, new: &str) -> Self {
+        let a: Vec<&str> = old.lines().collect();
+        let b: Vec<&str> = new.lines().collect();
+        Self {
+            hunks: hunks(&a, &b, &edit_script(&a, &b)),
+        }
+    }
+
+    /// Whether the texts have the same lines
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    /// Render as a unified diff with `--- old_label` and `+++ new_label`
+    /// headers; empty when there are no changes
+    pub fn unified(&self, old_label: &str, new_label: &str) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+        for hunk in &self.hunks {
+            out.push_str(&format!(
+                "@@ -{} +{} @@\n",
+                range_text(hunk.old_start, hunk.old_len),
+                range_text(hunk.new_start, hunk.new_len)
+            ));
+            for line in &hunk.lines {
+                let (prefix, text) = match line {
+                    DiffLine::Context(text) => (' ', text),
+                    DiffLine::Removed(text) => ('-', text),
+                    DiffLine::Added(text) => ('+', text),
+                };
+                out.push(prefix);
+                out.push_str(text);
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+/// `start,len` as in unified diff headers; an empty range is reported at the
+/// line before it
+fn range_text(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start.saturating_sub(1)),
+        1 => start.to_string(),
+        _ => format!("{},{}", start, len),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// Line `.0` of the old text equals line `.1` of the new text
+    Equal(usize, usize),
+    /// Line of the old text removed
+    Delete(usize),
+    /// Line of the new text added
+    Insert(usize),
+}
+
+/// Shortest edit script turning `a` into `b` (Myers, 1986)
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<Op> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    let mut trace = Vec::new();
+
+    // Forward pass: furthest reaching path on each diagonal k = x - y
+    'search: for d in 0..=max as isize {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+                v[i + 1]
+            } else {
+                v[i - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Backtrack through the saved frontiers
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k =
+            if k == -d || (k != d && v[(k - 1 + offset) as usize] < v[(k + 1 + offset) as usize]) {
+                k + 1
+            } else {
+                k - 1
+            };
+        let prev_x = v[(prev_k + offset) as usize];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            ops.push(Op::Equal(x as usize, y as usize));
+        }
+        if d > 0 {
+            if x == prev_x {
+                ops.push(Op::Insert(prev_y as usize));
+            } else {
+                ops.push(Op::Delete(prev_x as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    ops
+}
+
+/// Group an edit script into hunks with surrounding context
+fn hunks(a: &[&str], b: &[&str], ops: &[Op]) -> Vec<Hunk> {
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, Op::Equal(..)))
+        .map(|(i, _)| i)
+        .collect();
+
+    // Ranges of ops to show, merging changes whose context overlaps
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &i in &changes {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(ops.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            // Line positions before the first op of the hunk
+            let (mut old_line, mut new_line) = (0, 0);
+            for op in &ops[..start] {
+                match op {
+                    Op::Equal(..) => {
+                        old_line += 1;
+                        new_line += 1;
+                    }
+                    Op::Delete(_) => old_line += 1,
+                    Op::Insert(_) => new_line += 1,
+                }
+            }
+            let mut hunk = Hunk {
+                old_start: old_line + 1,
+                old_len: 0,
+                new_start: new_line + 1,
+                new_len: 0,
+                lines: Vec::new(),
+            };
+            for op in &ops[start..end] {
+                match *op {
+                    Op::Equal(i, _) => {
+                        hunk.old_len += 1;
+                        hunk.new_len += 1;
+                        hunk.lines.push(DiffLine::Context(a[i].to_string()));
+                    }
+                    Op::Delete(i) => {
+                        hunk.old_len += 1;
+                        hunk.lines.push(DiffLine::Removed(a[i].to_string()));
+                    }
+                    Op::Insert(j) => {
+                        hunk.new_len += 1;
+                        hunk.lines.push(DiffLine::Added(b[j].to_string()));
+                    }
+                }
+            }
+            hunk
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "text_diff_tests.rs"]
+mod tests;
@@ -0,0 +1,52 @@
+use super::*;
+
+#[test]
+fn test_identical_texts_have_no_hunks() {
+    let diff = TextDiff::new("a\nb\n", "a\nb\n");
+    assert!(diff.is_empty());
+    assert_eq!(diff.unified("a", "b"), "");
+}
+
+#[test]
+fn test_unified_diff_single_change() {
+    let old = "1\n2\n3\n4\n5\n6\n7\n8\n";
+    let new = "1\n2\n3\n4\nfive\n6\n7\n8\n";
+    assert_eq!(
+        TextDiff::new(old, new).unified("a/x.astra", "b/x.astra"),
+        "--- a/x.astra\n+++ b/x.astra\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n"
+    );
+}
+
+#[test]
+fn test_distant_changes_form_separate_hunks() {
+    let old: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+    let new: String = (1..=20)
+        .map(|i| match i {
+            2 => "two\n".to_string(),
+            19 => "nineteen\n".to_string(),
+            _ => format!("{}\n", i),
+        })
+        .collect();
+    let diff = TextDiff::new(&old, &new);
+    assert_eq!(diff.hunks.len(), 2);
+    assert_eq!((diff.hunks[0].old_start, diff.hunks[0].old_len), (1, 5));
+    assert_eq!((diff.hunks[1].new_start, diff.hunks[1].new_len), (16, 5));
+}
+
+#[test]
+fn test_insertions_and_deletions() {
+    let diff = TextDiff::new("a\nb\nc\n", "a\nc\nd\n");
+    assert_eq!(
+        diff.hunks[0].lines,
+        vec![
+            DiffLine::Context("a".into()),
+            DiffLine::Removed("b".into()),
+            DiffLine::Context("c".into()),
+            DiffLine::Added("d".into()),
+        ]
+    );
+    assert_eq!(
+        TextDiff::new("", "x\n").unified("a", "b"),
+        "--- a\n+++ b\n@@ -0,0 +1 @@\n+x\n"
+    );
+}
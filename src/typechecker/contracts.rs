@@ -0,0 +1,764 @@
+//! Check-time analysis of simple preconditions.
+//!
+//! Each call to a function of the module that has `requires` clauses is
+//! checked against what is known about its arguments: an interval for Int
+//! values, along with values they are known not to equal, whether an Option
+//! is `Some` or `None`, and Bool constants. Facts come from literals,
+//! immutable `let` bindings, the enclosing function's own `requires`, the
+//! conditions of `if` and `and`/`or`, and early exits such as
+//! `if b == 0 { return 0 }`. A clause those facts make false can never hold,
+//! and the call is reported as E3006. A clause they make true is proved;
+//! anything else is left to the runtime check.
+
+use std::collections::HashMap;
+
+use crate::diagnostics::error_codes::contracts::PRECONDITION_ALWAYS_VIOLATED;
+use crate::diagnostics::{Diagnostic, Note, Span};
+use crate::parser::ast::*;
+
+/// Calls in `items` whose arguments can never satisfy the callee's
+/// preconditions
+pub(super) fn violated_preconditions(items: &[&Item]) -> Vec<Diagnostic> {
+    let contracts = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::FnDef(def) if !def.requires.is_empty() => Some((def.name.as_str(), def)),
+            _ => None,
+        })
+        .collect();
+    let mut analysis = Analysis {
+        contracts,
+        diagnostics: Vec::new(),
+    };
+    if analysis.contracts.is_empty() {
+        return Vec::new();
+    }
+    for item in items {
+        match item {
+            Item::FnDef(def) => analysis.function(def),
+            Item::ImplBlock(block) => {
+                for method in &block.methods {
+                    analysis.function(method);
+                }
+            }
+            Item::Test(TestBlock { body, .. })
+            | Item::Property(PropertyBlock { body, .. })
+            | Item::TestHook(TestHook { body, .. }) => analysis.block(body, &Env::new()),
+            _ => {}
+        }
+    }
+    analysis.diagnostics
+}
+
+/// What is known about a value
+#[derive(Debug, Clone, PartialEq)]
+enum Fact {
+    Unknown,
+    Bool(bool),
+    Int(Interval),
+    /// An Option known to be `Some` (true) or `None` (false)
+    Option(bool),
+}
+
+/// The Int values from `lo` to `hi` inclusive, unbounded where `None`,
+/// other than those in `excluded`
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Interval {
+    lo: Option<i64>,
+    hi: Option<i64>,
+    excluded: Vec<i64>,
+}
+
+impl Interval {
+    fn exact(value: i64) -> Self {
+        Interval {
+            lo: Some(value),
+            hi: Some(value),
+            excluded: Vec::new(),
+        }
+    }
+
+    fn value(&self) -> Option<i64> {
+        self.lo.filter(|lo| Some(*lo) == self.hi)
+    }
+
+    fn contains(&self, value: i64) -> bool {
+        self.lo.is_none_or(|lo| lo <= value)
+            && self.hi.is_none_or(|hi| value <= hi)
+            && !self.excluded.contains(&value)
+    }
+
+    /// Pull excluded bounds inward. `None` when no value is left, which
+    /// happens only on paths that cannot run.
+    fn normalize(mut self) -> Option<Self> {
+        while let Some(lo) = self.lo.filter(|lo| self.excluded.contains(lo)) {
+            self.lo = lo.checked_add(1);
+        }
+        while let Some(hi) = self.hi.filter(|hi| self.excluded.contains(hi)) {
+            self.hi = hi.checked_sub(1);
+        }
+        let (lo, hi) = (self.lo, self.hi);
+        self.excluded
+            .retain(|v| lo.is_none_or(|lo| lo < *v) && hi.is_none_or(|hi| *v < hi));
+        match (self.lo, self.hi) {
+            (Some(lo), Some(hi)) if lo > hi => None,
+            _ => Some(self),
+        }
+    }
+
+    fn add(&self, other: &Interval) -> Interval {
+        Interval {
+            lo: self.lo.zip(other.lo).and_then(|(a, b)| a.checked_add(b)),
+            hi: self.hi.zip(other.hi).and_then(|(a, b)| a.checked_add(b)),
+            excluded: Vec::new(),
+        }
+    }
+
+    fn neg(&self) -> Interval {
+        Interval {
+            lo: self.hi.and_then(i64::checked_neg),
+            hi: self.lo.and_then(i64::checked_neg),
+            excluded: self
+                .excluded
+                .iter()
+                .filter_map(|v| v.checked_neg())
+                .collect(),
+        }
+    }
+
+    fn mul(&self, other: &Interval) -> Interval {
+        let (Some(a), Some(b), Some(c), Some(d)) = (self.lo, self.hi, other.lo, other.hi) else {
+            return Interval::default();
+        };
+        let products = [
+            a.checked_mul(c),
+            a.checked_mul(d),
+            b.checked_mul(c),
+            b.checked_mul(d),
+        ];
+        if products.iter().any(Option::is_none) {
+            return Interval::default();
+        }
+        let products = products.map(Option::unwrap_or_default);
+        Interval {
+            lo: products.iter().min().copied(),
+            hi: products.iter().max().copied(),
+            excluded: Vec::new(),
+        }
+    }
+
+    /// Whether every value here is below every value of `other`, or none is
+    fn lt(&self, other: &Interval) -> Option<bool> {
+        if matches!((self.hi, other.lo), (Some(hi), Some(lo)) if hi < lo) {
+            Some(true)
+        } else if matches!((self.lo, other.hi), (Some(lo), Some(hi)) if lo >= hi) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    fn le(&self, other: &Interval) -> Option<bool> {
+        if matches!((self.hi, other.lo), (Some(hi), Some(lo)) if hi <= lo) {
+            Some(true)
+        } else if matches!((self.lo, other.hi), (Some(lo), Some(hi)) if lo > hi) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    fn eq(&self, other: &Interval) -> Option<bool> {
+        match (self.value(), other.value()) {
+            (Some(a), Some(b)) => Some(a == b),
+            (Some(v), None) if !other.contains(v) => Some(false),
+            (None, Some(v)) if !self.contains(v) => Some(false),
+            _ if self.lt(other) == Some(true) || other.lt(self) == Some(true) => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Keep only the values that compare to `other` as `op` says
+    fn refine(&self, op: BinaryOp, other: &Interval) -> Option<Interval> {
+        let mut refined = self.clone();
+        match op {
+            BinaryOp::Lt => {
+                refined.hi = min_bound(self.hi, other.hi.and_then(|h| h.checked_sub(1)))
+            }
+            BinaryOp::Le => refined.hi = min_bound(self.hi, other.hi),
+            BinaryOp::Gt => {
+                refined.lo = max_bound(self.lo, other.lo.and_then(|l| l.checked_add(1)))
+            }
+            BinaryOp::Ge => refined.lo = max_bound(self.lo, other.lo),
+            BinaryOp::Eq => {
+                refined.lo = max_bound(self.lo, other.lo);
+                refined.hi = min_bound(self.hi, other.hi);
+                refined.excluded.extend(other.excluded.iter().copied());
+            }
+            BinaryOp::Ne => match other.value() {
+                Some(v) => refined.excluded.push(v),
+                None => return Some(refined),
+            },
+            _ => return Some(refined),
+        }
+        refined.normalize()
+    }
+
+    fn describe(&self) -> Option<String> {
+        if let Some(value) = self.value() {
+            return Some(format!("is {}", value));
+        }
+        let mut parts = Vec::new();
+        match (self.lo, self.hi) {
+            (Some(lo), Some(hi)) => parts.push(format!("is between {} and {}", lo, hi)),
+            (Some(lo), None) => parts.push(format!("is at least {}", lo)),
+            (None, Some(hi)) => parts.push(format!("is at most {}", hi)),
+            (None, None) => {}
+        }
+        if !self.excluded.is_empty() {
+            let excluded: Vec<String> = self.excluded.iter().map(i64::to_string).collect();
+            parts.push(format!("is not {}", excluded.join(" or ")));
+        }
+        (!parts.is_empty()).then(|| parts.join(" and "))
+    }
+}
+
+fn min_bound(a: Option<i64>, b: Option<i64>) -> Option<i64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn max_bound(a: Option<i64>, b: Option<i64>) -> Option<i64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// The comparison that holds when `op` does not
+fn negate(op: BinaryOp) -> Option<BinaryOp> {
+    Some(match op {
+        BinaryOp::Lt => BinaryOp::Ge,
+        BinaryOp::Le => BinaryOp::Gt,
+        BinaryOp::Gt => BinaryOp::Le,
+        BinaryOp::Ge => BinaryOp::Lt,
+        BinaryOp::Eq => BinaryOp::Ne,
+        BinaryOp::Ne => BinaryOp::Eq,
+        _ => return None,
+    })
+}
+
+/// `op` with its operands swapped
+fn flip(op: BinaryOp) -> BinaryOp {
+    match op {
+        BinaryOp::Lt => BinaryOp::Gt,
+        BinaryOp::Le => BinaryOp::Ge,
+        BinaryOp::Gt => BinaryOp::Lt,
+        BinaryOp::Ge => BinaryOp::Le,
+        other => other,
+    }
+}
+
+/// A local binding
+#[derive(Debug, Clone)]
+enum Local {
+    /// A parameter or `let` binding, which keeps its value
+    Fixed(Fact),
+    /// A `let mut` binding, about which nothing is assumed
+    Mutable,
+}
+
+/// Bindings in scope, by name. Names that aren't here are globals.
+type Env = HashMap<String, Local>;
+
+struct Analysis<'a> {
+    /// Functions with `requires` clauses, by name
+    contracts: HashMap<&'a str, &'a FnDef>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Analysis<'_> {
+    fn function(&mut self, def: &FnDef) {
+        let mut env = Env::new();
+        for param in &def.params {
+            env.insert(param.name.clone(), Local::Fixed(Fact::Unknown));
+            if let Some(pattern) = &param.pattern {
+                bind_pattern(pattern, &mut env);
+            }
+        }
+        for clause in &def.requires {
+            self.expr(clause, &env);
+        }
+        // The body runs only when the function's own preconditions hold
+        for clause in &def.requires {
+            assume(clause, true, &mut env);
+        }
+        let mut ensures_env = env.clone();
+        ensures_env.insert("result".to_string(), Local::Fixed(Fact::Unknown));
+        for clause in &def.ensures {
+            self.expr(clause, &ensures_env);
+        }
+        self.block(&def.body, &env);
+    }
+
+    fn block(&mut self, block: &Block, env: &Env) {
+        let mut env = env.clone();
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let {
+                    name,
+                    mutable,
+                    value,
+                    ..
+                } => {
+                    self.expr(value, &env);
+                    let local = if *mutable {
+                        Local::Mutable
+                    } else {
+                        Local::Fixed(eval(value, &env))
+                    };
+                    env.insert(name.clone(), local);
+                }
+                Stmt::LetPattern { pattern, value, .. } => {
+                    self.expr(value, &env);
+                    bind_pattern(pattern, &mut env);
+                }
+                Stmt::Assign { target, value, .. } => {
+                    self.expr(target, &env);
+                    self.expr(value, &env);
+                }
+                Stmt::Expr { expr, .. } => {
+                    self.expr(expr, &env);
+                    // After `if cond { return ... }`, `cond` is false
+                    if let Expr::If {
+                        cond,
+                        then_branch,
+                        else_branch: None,
+                        ..
+                    } = &**expr
+                    {
+                        if exits(then_branch) {
+                            assume(cond, false, &mut env);
+                        }
+                    }
+                }
+                Stmt::Return { value, .. } => {
+                    if let Some(value) = value {
+                        self.expr(value, &env);
+                    }
+                }
+            }
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr, &env);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, env: &Env) {
+        match expr {
+            Expr::IntLit { .. }
+            | Expr::FloatLit { .. }
+            | Expr::DecimalLit { .. }
+            | Expr::BoolLit { .. }
+            | Expr::TextLit { .. }
+            | Expr::UnitLit { .. }
+            | Expr::Ident { .. }
+            | Expr::QualifiedIdent { .. }
+            | Expr::Break { .. }
+            | Expr::Continue { .. }
+            | Expr::Hole { .. } => {}
+            Expr::Call {
+                span, func, args, ..
+            } => {
+                self.expr(func, env);
+                for arg in args {
+                    self.expr(arg, env);
+                }
+                if let Expr::Ident { name, .. } = &**func {
+                    if !env.contains_key(name) {
+                        if let Some(def) = self.contracts.get(name.as_str()).copied() {
+                            self.call(def, args, span, env);
+                        }
+                    }
+                }
+            }
+            Expr::Binary {
+                op: op @ (BinaryOp::And | BinaryOp::Or),
+                left,
+                right,
+                ..
+            } => {
+                // The right side runs only when the left didn't decide the result
+                self.expr(left, env);
+                let mut right_env = env.clone();
+                assume(left, *op == BinaryOp::And, &mut right_env);
+                self.expr(right, &right_env);
+            }
+            Expr::Binary { left, right, .. } => {
+                self.expr(left, env);
+                self.expr(right, env);
+            }
+            Expr::If {
+                cond,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expr(cond, env);
+                let mut then_env = env.clone();
+                assume(cond, true, &mut then_env);
+                self.block(then_branch, &then_env);
+                if let Some(else_branch) = else_branch {
+                    let mut else_env = env.clone();
+                    assume(cond, false, &mut else_env);
+                    self.expr(else_branch, &else_env);
+                }
+            }
+            Expr::Match { expr, arms, .. } => {
+                self.expr(expr, env);
+                for arm in arms {
+                    let mut arm_env = env.clone();
+                    bind_pattern(&arm.pattern, &mut arm_env);
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard, &arm_env);
+                        assume(guard, true, &mut arm_env);
+                    }
+                    self.expr(&arm.body, &arm_env);
+                }
+            }
+            Expr::Lambda { params, body, .. } => {
+                let mut lambda_env = env.clone();
+                for param in params {
+                    lambda_env.insert(param.name.clone(), Local::Fixed(Fact::Unknown));
+                }
+                self.block(body, &lambda_env);
+            }
+            Expr::ForIn {
+                binding,
+                pattern,
+                iter,
+                body,
+                ..
+            } => {
+                self.expr(iter, env);
+                let mut body_env = env.clone();
+                match pattern {
+                    Some(pattern) => bind_pattern(pattern, &mut body_env),
+                    None => {
+                        body_env.insert(binding.clone(), Local::Fixed(Fact::Unknown));
+                    }
+                }
+                self.block(body, &body_env);
+            }
+            Expr::Quantifier {
+                binding,
+                iter,
+                body,
+                ..
+            } => {
+                self.expr(iter, env);
+                let mut body_env = env.clone();
+                body_env.insert(binding.clone(), Local::Fixed(Fact::Unknown));
+                self.expr(body, &body_env);
+            }
+            Expr::While { cond, body, .. } => {
+                self.expr(cond, env);
+                let mut body_env = env.clone();
+                assume(cond, true, &mut body_env);
+                self.block(body, &body_env);
+            }
+            Expr::Block { block, .. } => self.block(block, env),
+            Expr::Record { fields, .. } => {
+                for (_, value) in fields {
+                    self.expr(value, env);
+                }
+            }
+            Expr::FieldAccess { expr, .. }
+            | Expr::Unary { expr, .. }
+            | Expr::Try { expr, .. }
+            | Expr::Await { expr, .. } => self.expr(expr, env),
+            Expr::MethodCall { receiver, args, .. } => {
+                self.expr(receiver, env);
+                for arg in args {
+                    self.expr(arg, env);
+                }
+            }
+            Expr::TryElse {
+                expr, else_expr, ..
+            } => {
+                self.expr(expr, env);
+                self.expr(else_expr, env);
+            }
+            Expr::ListLit { elements, .. } | Expr::TupleLit { elements, .. } => {
+                for element in elements {
+                    self.expr(element, env);
+                }
+            }
+            Expr::MapLit { entries, .. } => {
+                for (key, value) in entries {
+                    self.expr(key, env);
+                    self.expr(value, env);
+                }
+            }
+            Expr::StringInterp { parts, .. } => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        self.expr(expr, env);
+                    }
+                }
+            }
+            Expr::Range { start, end, .. } => {
+                self.expr(start, env);
+                self.expr(end, env);
+            }
+            Expr::IndexAccess { expr, index, .. } => {
+                self.expr(expr, env);
+                self.expr(index, env);
+            }
+            Expr::MacroCall { expansion, .. } => {
+                if let Some(expansion) = expansion {
+                    self.expr(expansion, env);
+                }
+            }
+        }
+    }
+
+    /// Report the first of `def`'s preconditions that the arguments make false
+    fn call(&mut self, def: &FnDef, args: &[Expr], span: &Span, env: &Env) {
+        if args.len() != def.params.len() {
+            return;
+        }
+        let params: Env = def
+            .params
+            .iter()
+            .zip(args)
+            .map(|(param, arg)| (param.name.clone(), Local::Fixed(eval(arg, env))))
+            .collect();
+        let Some(clause) = def
+            .requires
+            .iter()
+            .find(|clause| eval(clause, &params) == Fact::Bool(false))
+        else {
+            return;
+        };
+        let mut diag = Diagnostic::error(PRECONDITION_ALWAYS_VIOLATED)
+            .message(format!(
+                "This call to `{}` always violates its precondition `{}`",
+                def.name,
+                crate::formatter::Formatter::new().expr_text(clause)
+            ))
+            .span(span.clone())
+            .label(clause.span().clone(), "precondition declared here");
+        for param in &def.params {
+            let mentioned = mentions(clause, &param.name);
+            let description = match params.get(&param.name) {
+                Some(Local::Fixed(fact)) if mentioned => describe(fact),
+                _ => None,
+            };
+            if let Some(description) = description {
+                diag = diag.note(Note::new(format!("`{}` {} here", param.name, description)));
+            }
+        }
+        self.diagnostics.push(diag.build());
+    }
+}
+
+/// Whether a block always leaves the enclosing function or loop iteration
+fn exits(block: &Block) -> bool {
+    let leaves = |expr: &Expr| matches!(expr, Expr::Break { .. } | Expr::Continue { .. });
+    block.stmts.iter().any(|stmt| match stmt {
+        Stmt::Return { .. } => true,
+        Stmt::Expr { expr, .. } => leaves(expr),
+        _ => false,
+    }) || block.expr.as_deref().is_some_and(leaves)
+}
+
+fn bind_pattern(pattern: &Pattern, env: &mut Env) {
+    match pattern {
+        Pattern::Ident { name, .. } => {
+            env.insert(name.clone(), Local::Fixed(Fact::Unknown));
+        }
+        Pattern::Variant { fields, .. } => {
+            for inner in fields {
+                bind_pattern(inner, env);
+            }
+        }
+        Pattern::Record { fields, .. } => {
+            for (_, inner) in fields {
+                bind_pattern(inner, env);
+            }
+        }
+        Pattern::Tuple { elements, .. } => {
+            for inner in elements {
+                bind_pattern(inner, env);
+            }
+        }
+        Pattern::Wildcard { .. }
+        | Pattern::IntLit { .. }
+        | Pattern::FloatLit { .. }
+        | Pattern::BoolLit { .. }
+        | Pattern::TextLit { .. } => {}
+    }
+}
+
+fn mentions(expr: &Expr, name: &str) -> bool {
+    let mut found = false;
+    crate::parser::visit::walk_expr(expr, &mut |e| {
+        found |= matches!(e, Expr::Ident { name: n, .. } if n == name);
+    });
+    found
+}
+
+fn describe(fact: &Fact) -> Option<String> {
+    match fact {
+        Fact::Unknown => None,
+        Fact::Bool(b) => Some(format!("is {}", b)),
+        Fact::Int(interval) => interval.describe(),
+        Fact::Option(true) => Some("is Some".to_string()),
+        Fact::Option(false) => Some("is None".to_string()),
+    }
+}
+
+/// What is known about the value of `expr`
+fn eval(expr: &Expr, env: &Env) -> Fact {
+    match expr {
+        Expr::IntLit { value, .. } => Fact::Int(Interval::exact(*value)),
+        Expr::BoolLit { value, .. } => Fact::Bool(*value),
+        Expr::Ident { name, .. } => match env.get(name) {
+            Some(Local::Fixed(fact)) => fact.clone(),
+            Some(Local::Mutable) => Fact::Unknown,
+            None if name == "None" => Fact::Option(false),
+            None => Fact::Unknown,
+        },
+        Expr::Call { func, args, .. } if args.len() == 1 => match &**func {
+            Expr::Ident { name, .. } if name == "Some" && !env.contains_key(name) => {
+                Fact::Option(true)
+            }
+            _ => Fact::Unknown,
+        },
+        Expr::MethodCall {
+            receiver,
+            method,
+            args,
+            ..
+        } if args.is_empty() => match (eval(receiver, env), method.as_str()) {
+            (Fact::Option(some), "is_some") => Fact::Bool(some),
+            (Fact::Option(some), "is_none") => Fact::Bool(!some),
+            _ => Fact::Unknown,
+        },
+        Expr::Unary { op, expr, .. } => match (op, eval(expr, env)) {
+            (UnaryOp::Neg, Fact::Int(interval)) => Fact::Int(interval.neg()),
+            (UnaryOp::Not, Fact::Bool(b)) => Fact::Bool(!b),
+            _ => Fact::Unknown,
+        },
+        Expr::Binary {
+            op, left, right, ..
+        } => binary(*op, eval(left, env), eval(right, env)),
+        _ => Fact::Unknown,
+    }
+}
+
+fn binary(op: BinaryOp, left: Fact, right: Fact) -> Fact {
+    let decided = |result: Option<bool>| result.map_or(Fact::Unknown, Fact::Bool);
+    match (op, left, right) {
+        (BinaryOp::And, Fact::Bool(false), _) | (BinaryOp::And, _, Fact::Bool(false)) => {
+            Fact::Bool(false)
+        }
+        (BinaryOp::And, Fact::Bool(true), Fact::Bool(true)) => Fact::Bool(true),
+        (BinaryOp::Or, Fact::Bool(true), _) | (BinaryOp::Or, _, Fact::Bool(true)) => {
+            Fact::Bool(true)
+        }
+        (BinaryOp::Or, Fact::Bool(false), Fact::Bool(false)) => Fact::Bool(false),
+        (BinaryOp::Add, Fact::Int(a), Fact::Int(b)) => Fact::Int(a.add(&b)),
+        (BinaryOp::Sub, Fact::Int(a), Fact::Int(b)) => Fact::Int(a.add(&b.neg())),
+        (BinaryOp::Mul, Fact::Int(a), Fact::Int(b)) => Fact::Int(a.mul(&b)),
+        (BinaryOp::Lt, Fact::Int(a), Fact::Int(b)) => decided(a.lt(&b)),
+        (BinaryOp::Le, Fact::Int(a), Fact::Int(b)) => decided(a.le(&b)),
+        (BinaryOp::Gt, Fact::Int(a), Fact::Int(b)) => decided(b.lt(&a)),
+        (BinaryOp::Ge, Fact::Int(a), Fact::Int(b)) => decided(b.le(&a)),
+        (BinaryOp::Eq, Fact::Int(a), Fact::Int(b)) => decided(a.eq(&b)),
+        (BinaryOp::Ne, Fact::Int(a), Fact::Int(b)) => decided(a.eq(&b).map(|eq| !eq)),
+        (BinaryOp::Eq, Fact::Option(a), Fact::Option(b)) if !a || !b => Fact::Bool(a == b),
+        (BinaryOp::Ne, Fact::Option(a), Fact::Option(b)) if !a || !b => Fact::Bool(a != b),
+        (BinaryOp::Eq, Fact::Bool(a), Fact::Bool(b)) => Fact::Bool(a == b),
+        (BinaryOp::Ne, Fact::Bool(a), Fact::Bool(b)) => Fact::Bool(a != b),
+        _ => Fact::Unknown,
+    }
+}
+
+/// Narrow what `env` knows given that `cond` evaluated to `truth`
+fn assume(cond: &Expr, truth: bool, env: &mut Env) {
+    match cond {
+        Expr::Unary {
+            op: UnaryOp::Not,
+            expr,
+            ..
+        } => assume(expr, !truth, env),
+        Expr::Binary {
+            op: BinaryOp::And,
+            left,
+            right,
+            ..
+        } if truth => {
+            assume(left, true, env);
+            assume(right, true, env);
+        }
+        Expr::Binary {
+            op: BinaryOp::Or,
+            left,
+            right,
+            ..
+        } if !truth => {
+            assume(left, false, env);
+            assume(right, false, env);
+        }
+        Expr::Binary {
+            op, left, right, ..
+        } => {
+            let Some(op) = (if truth { Some(*op) } else { negate(*op) }) else {
+                return;
+            };
+            let (left_fact, right_fact) = (eval(left, env), eval(right, env));
+            narrow(left, op, &right_fact, env);
+            narrow(right, flip(op), &left_fact, env);
+        }
+        Expr::MethodCall {
+            receiver,
+            method,
+            args,
+            ..
+        } if args.is_empty() => match method.as_str() {
+            "is_some" => set(receiver, Fact::Option(truth), env),
+            "is_none" => set(receiver, Fact::Option(!truth), env),
+            _ => {}
+        },
+        Expr::Ident { .. } => set(cond, Fact::Bool(truth), env),
+        _ => {}
+    }
+}
+
+/// Narrow a binding given that it compares to a value as `op` says
+fn narrow(expr: &Expr, op: BinaryOp, other: &Fact, env: &mut Env) {
+    let current = eval(expr, env);
+    let narrowed = match (current, other) {
+        (Fact::Int(interval), Fact::Int(other)) => interval.refine(op, other).map(Fact::Int),
+        (Fact::Unknown, Fact::Int(other)) => Interval::default().refine(op, other).map(Fact::Int),
+        (_, Fact::Option(false)) if op == BinaryOp::Eq => Some(Fact::Option(false)),
+        (_, Fact::Option(false)) if op == BinaryOp::Ne => Some(Fact::Option(true)),
+        _ => None,
+    };
+    if let Some(fact) = narrowed {
+        set(expr, fact, env);
+    }
+}
+
+/// Record a fact about a fixed local binding; anything else is left alone
+fn set(expr: &Expr, fact: Fact, env: &mut Env) {
+    if let Expr::Ident { name, .. } = expr {
+        if let Some(local @ Local::Fixed(_)) = env.get_mut(name) {
+            *local = Local::Fixed(fact);
+        }
+    }
+}
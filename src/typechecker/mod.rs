@@ -3,16 +3,27 @@
 //! Implements type checking, inference, exhaustiveness checking, effect enforcement,
 //! and lint checks (W0001-W0007).
 
-use crate::diagnostics::{Diagnostic, DiagnosticBag, Edit, Note, Span, Suggestion};
+use crate::diagnostics::{
+    Applicability, Diagnostic, DiagnosticBag, DiagnosticBuilder, Edit, HoleBinding, HoleContext,
+    Note, Span, Suggestion,
+};
 use crate::parser::ast::*;
 use std::collections::{HashMap, HashSet};
 
+mod contracts;
+
+/// Effects provided by the runtime
+const BUILTIN_EFFECTS: [&str; 11] = [
+    "Console", "Fs", "Net", "Clock", "Rand", "Env", "Crypto", "Serve", "Db", "Progress", "Metrics",
+];
+
 /// Format a Type as a human-readable string for suggestions.
 fn format_type(ty: &Type) -> String {
     match ty {
         Type::Unit => "Unit".to_string(),
         Type::Int => "Int".to_string(),
         Type::Float => "Float".to_string(),
+        Type::Decimal => "Decimal".to_string(),
         Type::Bool => "Bool".to_string(),
         Type::Text => "Text".to_string(),
         Type::Json => "Json".to_string(),
@@ -131,12 +142,72 @@ fn find_similar_name(name: &str, env: &TypeEnv) -> Option<String> {
     best.map(|(name, _)| name)
 }
 
+/// The candidate most similar to `name`, within the same distance as
+/// [`find_similar_name`] allows; `None` when two are equally close, since
+/// either could be meant
+fn closest_name<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = 3.min(name.len().div_ceil(2));
+    let mut ranked: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(dist, _)| *dist <= max_distance)
+        .collect();
+    ranked.sort();
+    match ranked.as_slice() {
+        [(best, candidate), (next, _), ..] if best < next => Some(candidate),
+        [(_, candidate)] => Some(candidate),
+        _ => None,
+    }
+}
+
+/// Every method of a built-in effect or namespace, as the interpreter
+/// dispatches them; `None` for receivers whose methods aren't a fixed set
+fn namespace_methods(namespace: &str) -> Option<&'static [&'static str]> {
+    Some(match namespace {
+        "Console" => &["print", "println", "read_line", "is_tty"],
+        "Fs" => &[
+            "read",
+            "write",
+            "exists",
+            "read_gzip",
+            "write_gzip",
+            "read_only",
+            "scoped",
+        ],
+        "Net" => &["get", "post", "serve", "ws_connect"],
+        "Clock" => &["now", "sleep", "today", "deadline", "remaining", "expired"],
+        "Rand" => &["int", "float", "bool", "hex", "uuid"],
+        "Env" => &["get", "args"],
+        "Crypto" => &[
+            "hmac_sha256",
+            "key_from_env",
+            "key_from_file",
+            "public_key",
+            "sign",
+            "verify",
+        ],
+        "Serve" => &["route", "request"],
+        "Db" => &["query", "execute"],
+        "Progress" => &["start", "advance", "finish"],
+        "Metrics" => &["counter", "gauge", "timing", "snapshot"],
+        "Args" => &["flag", "option", "positional", "rest", "parse"],
+        "Duration" => &["millis", "seconds", "minutes", "hours", "days"],
+        "Map" | "Set" => &["new", "from"],
+        "Ref" => &["new"],
+        "Bytes" => &["from_list"],
+        _ => return None,
+    })
+}
+
 /// Tracks a variable definition for unused-variable lint (W0001)
 #[derive(Debug, Clone)]
 struct VarBinding {
     name: String,
     span: Span,
     used: bool,
+    /// The `let` keyword that declared it, where `mut` can be added
+    let_keyword: Option<Span>,
 }
 
 /// Tracks lint state within a scope
@@ -148,6 +219,51 @@ struct LintScope {
     defined_names: HashSet<String>,
 }
 
+/// A typed hole (`???`) found while checking, reported once its expected
+/// type is known
+#[derive(Debug, Clone)]
+struct HoleSite {
+    id: NodeId,
+    span: Span,
+    /// Local bindings in scope at the hole, innermost first
+    bindings: Vec<(String, Type)>,
+    /// The function containing the hole
+    function: Option<FnContext>,
+}
+
+/// Signature and contract of the function being checked
+#[derive(Debug, Clone)]
+struct FnContext {
+    name: String,
+    effects: Vec<String>,
+    signature: String,
+    requires: Vec<String>,
+    ensures: Vec<String>,
+    return_type: Type,
+}
+
+/// Number of candidate fills reported for each typed hole
+const HOLE_CANDIDATES: usize = 5;
+
+/// Number of argument combinations tried for each function or constructor
+/// when synthesizing hole candidates
+const HOLE_CALL_ARGS: usize = 3;
+
+/// A lint suppression from an `@allow(...)` attribute
+#[derive(Debug, Clone)]
+struct Suppression {
+    /// Lint name as written in the attribute
+    name: String,
+    /// Resolved lint code (None if the name is unknown)
+    code: Option<&'static str>,
+    /// Span of the attributed item or statement
+    scope: Span,
+    /// Span of the attribute itself
+    attr_span: Span,
+    /// Whether this suppression silenced at least one diagnostic
+    used: bool,
+}
+
 /// Built-in types
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Type {
@@ -157,6 +273,8 @@ pub enum Type {
     Int,
     /// Float type
     Float,
+    /// Exact decimal type
+    Decimal,
     /// Boolean type
     Bool,
     /// Text/string type
@@ -451,6 +569,8 @@ impl Substitution {
 pub struct TypeEnv {
     /// Variable types
     bindings: HashMap<String, Type>,
+    /// Variables in `bindings` declared with `let mut`
+    mutable: HashSet<String>,
     /// Type definitions
     type_defs: HashMap<String, TypeDef>,
     /// Enum definitions
@@ -473,6 +593,7 @@ impl TypeEnv {
     pub fn child(&self) -> Self {
         Self {
             bindings: HashMap::new(),
+            mutable: HashSet::new(),
             type_defs: HashMap::new(),
             enum_defs: HashMap::new(),
             fn_defs: HashMap::new(),
@@ -483,9 +604,25 @@ impl TypeEnv {
 
     /// Define a variable's type
     pub fn define(&mut self, name: String, ty: Type) {
+        self.mutable.remove(&name);
         self.bindings.insert(name, ty);
     }
 
+    /// Define a `let mut` variable's type
+    pub fn define_mut(&mut self, name: String, ty: Type) {
+        self.mutable.insert(name.clone());
+        self.bindings.insert(name, ty);
+    }
+
+    /// Whether the innermost variable with this name was declared `let mut`
+    pub fn is_mutable(&self, name: &str) -> bool {
+        if self.bindings.contains_key(name) {
+            self.mutable.contains(name)
+        } else {
+            self.parent.as_ref().is_some_and(|p| p.is_mutable(name))
+        }
+    }
+
     /// Look up a variable's type
     pub fn lookup(&self, name: &str) -> Option<&Type> {
         self.bindings
@@ -508,6 +645,26 @@ impl TypeEnv {
         self.fn_defs.insert(def.name.clone(), def);
     }
 
+    /// Variables bound below the module scope (parameters and locals),
+    /// innermost first, without shadowed names
+    fn local_bindings(&self) -> Vec<(String, Type)> {
+        let mut seen = HashSet::new();
+        let mut bindings = Vec::new();
+        let mut env = self;
+        while let Some(parent) = &env.parent {
+            let mut names: Vec<&String> = env.bindings.keys().collect();
+            names.sort();
+            for name in names {
+                let ty = &env.bindings[name];
+                if *ty != Type::TypeParam(name.clone()) && seen.insert(name.clone()) {
+                    bindings.push((name.clone(), ty.clone()));
+                }
+            }
+            env = parent;
+        }
+        bindings
+    }
+
     /// Look up a type definition
     pub fn lookup_type(&self, name: &str) -> Option<&TypeDef> {
         self.type_defs
@@ -554,7 +711,8 @@ enum MatchTypeKind {
     /// User-defined enum: must cover all variants
     Enum {
         _name: String,
-        variants: Vec<String>,
+        /// Variant names with their number of fields
+        variants: Vec<(String, usize)>,
     },
     /// Unknown or unconstrained type (skip exhaustiveness)
     Other,
@@ -601,6 +759,24 @@ pub struct TypeChecker {
     resolved_modules: HashSet<String>,
     /// v1.1: Substitution for HM type inference unification
     subst: Substitution,
+    /// Lint suppressions from `@allow(...)` attributes
+    suppressions: Vec<Suppression>,
+    /// Inferred type of each checked expression, keyed by node ID
+    node_types: HashMap<NodeId, Type>,
+    /// Definitions of imported modules, for name resolution across modules
+    symbols: crate::resolve::SymbolTable,
+    /// Typed holes in checking order
+    holes: Vec<HoleSite>,
+    /// Types the context expects of holes, keyed by node ID
+    hole_types: HashMap<NodeId, Type>,
+    /// The function whose body is being checked
+    current_fn: Option<FnContext>,
+    /// Functions and types marked `@deprecated`, with the attribute's message
+    deprecated: HashMap<String, Option<String>>,
+    /// Configuration that decides which `@cfg(...)` items are checked
+    cfg: crate::cfg::Cfg,
+    /// Type of the `fixture` that the module's `before_each` hook provides to tests
+    fixture_type: Option<Type>,
 }
 
 impl TypeChecker {
@@ -618,6 +794,15 @@ impl TypeChecker {
             search_paths: Vec::new(),
             resolved_modules: HashSet::new(),
             subst: Substitution::new(),
+            suppressions: Vec::new(),
+            node_types: HashMap::new(),
+            symbols: crate::resolve::SymbolTable::new(),
+            holes: Vec::new(),
+            hole_types: HashMap::new(),
+            current_fn: None,
+            deprecated: HashMap::new(),
+            cfg: crate::cfg::Cfg::test(),
+            fixture_type: None,
         }
     }
 
@@ -626,6 +811,11 @@ impl TypeChecker {
         self.search_paths.push(path);
     }
 
+    /// Set the configuration for `@cfg(...)` items (test builds by default)
+    pub fn set_cfg(&mut self, cfg: crate::cfg::Cfg) {
+        self.cfg = cfg;
+    }
+
     /// B1: Resolve import path segments to a filesystem path
     fn resolve_module_path(&self, segments: &[String]) -> Option<std::path::PathBuf> {
         let relative = segments.join("/") + ".astra";
@@ -675,7 +865,7 @@ impl TypeChecker {
         if self.resolved_modules.contains(&module_key) {
             return;
         }
-        self.resolved_modules.insert(module_key);
+        self.resolved_modules.insert(module_key.clone());
 
         let file_path = match self.resolve_module_path(segments) {
             Some(p) => p,
@@ -690,10 +880,12 @@ impl TypeChecker {
         let source_file = crate::parser::span::SourceFile::new(file_path.clone(), source.clone());
         let lexer = crate::parser::lexer::Lexer::new(&source_file);
         let mut parser = crate::parser::parser::Parser::new(lexer, source_file.clone());
-        let module = match parser.parse_module() {
+        let mut module = match parser.parse_module() {
             Ok(m) => m,
             Err(_) => return,
         };
+        module.items.retain(|item| self.cfg.includes(item));
+        self.symbols.add_module(&module_key, &module, &source_file);
 
         // Extract type information from the module's items
         let filter = match &import.kind {
@@ -710,6 +902,7 @@ impl TypeChecker {
                             continue;
                         }
                     }
+                    self.register_deprecation(&fn_def.name, &fn_def.attributes);
                     // Build function type from signature
                     let param_types: Vec<Type> = fn_def
                         .params
@@ -736,6 +929,7 @@ impl TypeChecker {
                             continue;
                         }
                     }
+                    self.register_deprecation(&def.name, &def.attributes);
                     self.env.register_type(def.clone());
                 }
                 Item::EnumDef(def) => {
@@ -748,6 +942,7 @@ impl TypeChecker {
                             }
                         }
                     }
+                    self.register_deprecation(&def.name, &def.attributes);
                     self.env.register_enum(def.clone());
                     let enum_type = Type::Named(def.name.clone(), vec![]);
                     for variant in &def.variants {
@@ -807,7 +1002,8 @@ impl TypeChecker {
                     .collect();
                 let mut diag =
                     Diagnostic::error(crate::diagnostics::error_codes::syntax::MODULE_NOT_FOUND)
-                        .message(format!("Module not found: `std.{}`", module_name))
+                        .catalog("E0011")
+                        .arg("module", module_name)
                         .span(import.span.clone());
 
                 if !available.is_empty() {
@@ -847,7 +1043,8 @@ impl TypeChecker {
                         Diagnostic::warning(
                             crate::diagnostics::error_codes::warnings::UNUSED_VARIABLE,
                         )
-                        .message(format!("Unused variable `{}`", binding.name))
+                        .catalog("W0001")
+                        .arg("name", &binding.name)
                         .span(binding.span.clone())
                         .note(Note::new(format!(
                             "prefix with `_` to suppress this warning: `_{}`",
@@ -871,17 +1068,16 @@ impl TypeChecker {
         // W0006: Check for shadowing in current scope
         if let Some(scope) = self.lint_scopes.last() {
             if scope.defined_names.contains(name) && !name.starts_with('_') {
-                self.diagnostics.push(
-                    Diagnostic::warning(
-                        crate::diagnostics::error_codes::warnings::SHADOWED_BINDING,
-                    )
-                    .message(format!(
-                        "Variable `{}` shadows a previous binding in the same scope",
-                        name
-                    ))
-                    .span(span.clone())
-                    .build(),
-                );
+                let mut diag = Diagnostic::warning(
+                    crate::diagnostics::error_codes::warnings::SHADOWED_BINDING,
+                )
+                .catalog("W0006")
+                .arg("name", name)
+                .span(span.clone());
+                if let Some(previous) = scope.vars.iter().rev().find(|v| v.name == name) {
+                    diag = diag.label(previous.span.clone(), "previous binding here");
+                }
+                self.diagnostics.push(diag.build());
             }
         }
 
@@ -890,6 +1086,7 @@ impl TypeChecker {
                 name: name.to_string(),
                 span: span.clone(),
                 used: false,
+                let_keyword: None,
             });
             scope.defined_names.insert(name.to_string());
         }
@@ -903,14 +1100,25 @@ impl TypeChecker {
                 self.lint_define_var(name, span);
             }
             Pattern::Tuple { elements, .. } => {
-                for elem in elements {
-                    self.register_pattern_vars(elem, env, Type::Unknown);
+                let elem_types = match self.subst.apply(&ty) {
+                    Type::Tuple(types) if types.len() == elements.len() => types,
+                    _ => vec![Type::Unknown; elements.len()],
+                };
+                for (elem, elem_ty) in elements.iter().zip(elem_types) {
+                    self.register_pattern_vars(elem, env, elem_ty);
                 }
             }
             Pattern::Record { fields, .. } => {
+                let field_types = match self.subst.apply(&ty) {
+                    Type::Record(types) => types,
+                    _ => Vec::new(),
+                };
                 for (name, pat) in fields {
-                    let _ = name; // field name
-                    self.register_pattern_vars(pat, env, Type::Unknown);
+                    let field_ty = field_types
+                        .iter()
+                        .find(|(field, _)| field == name)
+                        .map_or(Type::Unknown, |(_, ty)| ty.clone());
+                    self.register_pattern_vars(pat, env, field_ty);
                 }
             }
             Pattern::Variant { fields, .. } => {
@@ -945,10 +1153,68 @@ impl TypeChecker {
         }
     }
 
+    /// E1017: an assignment to a binding not declared with `let mut`. When
+    /// the binding comes from a `let`, the fix adds `mut` to it.
+    fn check_assignment(&mut self, name: &str, span: &Span, env: &TypeEnv) {
+        if env.lookup(name).is_none() || env.is_mutable(name) {
+            return;
+        }
+        let declared = self
+            .lint_scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.vars.iter().rev().find(|v| v.name == name))
+            .map(|binding| (binding.span.clone(), binding.let_keyword.clone()));
+        let mut diag =
+            Diagnostic::error(crate::diagnostics::error_codes::types::ASSIGN_TO_IMMUTABLE)
+                .catalog("E1017")
+                .arg("name", name)
+                .span(span.clone());
+        match declared {
+            Some((declared, Some(keyword))) => {
+                diag = diag.label(declared, "declared here").suggestion(
+                    Suggestion::new(format!("Declare `{}` with `let mut`", name))
+                        .with_edit(Edit::new(keyword, "let mut"))
+                        .with_applicability(Applicability::MachineApplicable),
+                );
+            }
+            Some((declared, None)) => {
+                diag = diag.label(declared, "declared here").note(Note::new(format!(
+                    "parameters and pattern bindings cannot be assigned; copy it with `let mut {} = {}` first",
+                    name, name
+                )));
+            }
+            None => {}
+        }
+        self.diagnostics.push(diag.build());
+    }
+
     /// Check a module
     pub fn check_module(&mut self, module: &Module) -> Result<(), DiagnosticBag> {
+        // Items whose `@cfg(...)` doesn't hold are skipped entirely
+        let items: Vec<&Item> = module
+            .items
+            .iter()
+            .filter(|item| self.cfg.includes(item))
+            .collect();
+
         // First pass: collect all type/enum/fn/trait/impl definitions and imports
-        for item in &module.items {
+        for item in &items {
+            match item {
+                Item::Import(ImportDecl {
+                    attributes, span, ..
+                })
+                | Item::TypeDef(TypeDef {
+                    attributes, span, ..
+                })
+                | Item::EnumDef(EnumDef {
+                    attributes, span, ..
+                })
+                | Item::FnDef(FnDef {
+                    attributes, span, ..
+                }) => self.register_suppressions(attributes, span),
+                _ => {}
+            }
             match item {
                 Item::Import(import) => {
                     // Validate that the import path resolves to a known module
@@ -975,8 +1241,12 @@ impl TypeChecker {
                     };
                     self.imports.push((import_name, import.span.clone(), false));
                 }
-                Item::TypeDef(def) => self.env.register_type(def.clone()),
+                Item::TypeDef(def) => {
+                    self.register_deprecation(&def.name, &def.attributes);
+                    self.env.register_type(def.clone());
+                }
                 Item::EnumDef(def) => {
+                    self.register_deprecation(&def.name, &def.attributes);
                     self.env.register_enum(def.clone());
                     // Register each variant as a callable constructor
                     let enum_type = Type::Named(def.name.clone(), vec![]);
@@ -1003,6 +1273,7 @@ impl TypeChecker {
                     }
                 }
                 Item::FnDef(def) => {
+                    self.register_deprecation(&def.name, &def.attributes);
                     self.env.register_fn(def.clone());
                     // Track for unused function lint
                     if def.name != "main" {
@@ -1047,22 +1318,33 @@ impl TypeChecker {
             }
         }
 
-        // Second pass: type check all items
-        for item in &module.items {
+        // Second pass: type check all items, starting with the `before_each`
+        // hook since tests see its value as `fixture`
+        let (before_each, rest): (Vec<&Item>, Vec<&Item>) = items.iter().partition(
+            |item| matches!(item, Item::TestHook(hook) if hook.kind == HookKind::BeforeEach),
+        );
+        for item in before_each.into_iter().chain(rest) {
             self.check_item(item);
         }
 
+        // E3006: calls whose arguments can never satisfy a precondition
+        for diag in contracts::violated_preconditions(&items) {
+            self.diagnostics.push(diag);
+        }
+
         // W0002: Emit warnings for unused imports
         for (name, span, used) in &self.imports {
             if !*used {
                 self.diagnostics.push(
                     Diagnostic::warning(crate::diagnostics::error_codes::warnings::UNUSED_IMPORT)
-                        .message(format!("Unused import `{}`", name))
+                        .catalog("W0002")
+                        .arg("name", name)
                         .span(span.clone())
                         .note(Note::new("remove this import if it is no longer needed"))
                         .suggestion(
                             Suggestion::new("Remove this import")
-                                .with_edit(Edit::new(span.clone(), "")),
+                                .with_edit(Edit::new(span.clone(), ""))
+                                .with_applicability(Applicability::MachineApplicable),
                         )
                         .build(),
                 );
@@ -1078,7 +1360,8 @@ impl TypeChecker {
             {
                 self.diagnostics.push(
                     Diagnostic::warning(crate::diagnostics::error_codes::warnings::UNUSED_FUNCTION)
-                        .message(format!("Function `{}` is defined but never used", name))
+                        .catalog("W0008")
+                        .arg("name", name)
                         .span(span.clone())
                         .note(Note::new(
                             "if this is intentional, prefix the name with `_` or make it `public`",
@@ -1088,6 +1371,9 @@ impl TypeChecker {
             }
         }
 
+        self.report_holes();
+        self.apply_suppressions();
+
         if self.diagnostics.has_errors() {
             Err(self.diagnostics.clone())
         } else {
@@ -1095,11 +1381,164 @@ impl TypeChecker {
         }
     }
 
+    /// Record `@allow(...)` attributes covering `scope`
+    fn register_suppressions(&mut self, attributes: &[Attribute], scope: &Span) {
+        for attr in attributes.iter().filter(|a| a.name == "allow") {
+            for name in &attr.args {
+                self.suppressions.push(Suppression {
+                    name: name.clone(),
+                    code: crate::diagnostics::error_codes::warnings::code_for_name(name),
+                    scope: scope.clone(),
+                    attr_span: attr.span.clone(),
+                    used: false,
+                });
+            }
+        }
+    }
+
+    /// Record a `@deprecated` or `@deprecated("message")` attribute of `name`
+    fn register_deprecation(&mut self, name: &str, attributes: &[Attribute]) {
+        if let Some(attr) = attributes.iter().find(|a| a.name == "deprecated") {
+            self.deprecated
+                .insert(name.to_string(), attr.args.first().cloned());
+        }
+    }
+
+    /// W0004: warn about a use of a deprecated function or type
+    fn check_deprecated_use(&mut self, name: &str, span: &Span) {
+        let Some(message) = self.deprecated.get(name) else {
+            return;
+        };
+        // A deprecated function may keep using other deprecated items
+        if self.in_deprecated_fn() {
+            return;
+        }
+        let mut diag = Diagnostic::warning(crate::diagnostics::error_codes::warnings::DEPRECATED)
+            .message(format!("`{}` is deprecated", name))
+            .span(span.clone());
+        if let Some(message) = message {
+            diag = diag.note(Note::new(message.clone()));
+        }
+        self.diagnostics.push(diag.build());
+    }
+
+    fn in_deprecated_fn(&self) -> bool {
+        self.current_fn
+            .as_ref()
+            .is_some_and(|f| self.deprecated.contains_key(&f.name))
+    }
+
+    /// W0004: warn about deprecated types named in a type annotation
+    fn check_deprecated_types(&mut self, ty: &TypeExpr) {
+        match ty {
+            TypeExpr::Named {
+                name, args, span, ..
+            } => {
+                if !self.current_type_params.contains(name) {
+                    self.check_deprecated_use(name, span);
+                }
+                for arg in args {
+                    self.check_deprecated_types(arg);
+                }
+            }
+            TypeExpr::Record { fields, .. } => {
+                for field in fields {
+                    self.check_deprecated_types(&field.ty);
+                }
+            }
+            TypeExpr::Function { params, ret, .. } => {
+                for param in params {
+                    self.check_deprecated_types(param);
+                }
+                self.check_deprecated_types(ret);
+            }
+            TypeExpr::Tuple { elements, .. } => {
+                for element in elements {
+                    self.check_deprecated_types(element);
+                }
+            }
+        }
+    }
+
+    /// Whether `name` is a local variable in the current function
+    fn is_local_var(&self, name: &str) -> bool {
+        self.lint_scopes
+            .iter()
+            .any(|scope| scope.vars.iter().any(|binding| binding.name == name))
+    }
+
+    /// Drop lints covered by an `@allow(...)` and report suppressions that
+    /// no longer suppress anything (W0009)
+    fn apply_suppressions(&mut self) {
+        if self.suppressions.is_empty() {
+            return;
+        }
+        let diagnostics = std::mem::take(&mut self.diagnostics).take();
+        for diagnostic in diagnostics {
+            // Prefer the innermost suppression so outer ones aren't credited
+            let suppression = self
+                .suppressions
+                .iter_mut()
+                .filter(|s| {
+                    s.code == Some(diagnostic.code.as_str())
+                        && s.scope.file == diagnostic.span.file
+                        && s.scope.start <= diagnostic.span.start
+                        && diagnostic.span.end <= s.scope.end
+                })
+                .min_by_key(|s| s.scope.end - s.scope.start);
+            match suppression {
+                Some(s) => s.used = true,
+                None => self.diagnostics.push(diagnostic),
+            }
+        }
+
+        for s in std::mem::take(&mut self.suppressions) {
+            if s.used {
+                continue;
+            }
+            let message = match s.code {
+                Some(code) => format!(
+                    "Unused suppression: `@allow({})` does not suppress any {} diagnostic",
+                    s.name, code
+                ),
+                None => format!("Unknown lint `{}` in `@allow`", s.name),
+            };
+            self.diagnostics.push(
+                Diagnostic::warning(crate::diagnostics::error_codes::warnings::UNUSED_SUPPRESSION)
+                    .message(message)
+                    .span(s.attr_span.clone())
+                    .note(Note::new(
+                        "remove the suppression so new problems at this location are reported",
+                    ))
+                    .build(),
+            );
+        }
+    }
+
     /// Get diagnostics (including non-error diagnostics like warnings)
     pub fn diagnostics(&self) -> &DiagnosticBag {
         &self.diagnostics
     }
 
+    /// Definitions of the modules imported by the checked module
+    pub fn symbol_table(&self) -> &crate::resolve::SymbolTable {
+        &self.symbols
+    }
+
+    /// Inferred type of an expression checked by this checker, with type
+    /// variables resolved as far as inference got
+    pub fn expr_type(&self, id: NodeId) -> Option<Type> {
+        self.node_types.get(&id).map(|ty| self.subst.apply(ty))
+    }
+
+    /// Display names of the inferred types of all checked expressions
+    pub fn expr_type_names(&self) -> HashMap<NodeId, String> {
+        self.node_types
+            .keys()
+            .filter_map(|id| Some((*id, format_type(&self.expr_type(*id)?))))
+            .collect()
+    }
+
     /// Check a single item
     fn check_item(&mut self, item: &Item) {
         match item {
@@ -1160,9 +1599,21 @@ impl TypeChecker {
                         }
                     }
                 }
-                // Check each method in the impl block
+                // Check each method in the impl block, under the trait's
+                // contract as well as its own
+                let trait_def = self.env.lookup_trait(&impl_block.trait_name).cloned();
                 for method in &impl_block.methods {
-                    self.check_fndef(method);
+                    match trait_def
+                        .as_ref()
+                        .and_then(|t| t.methods.iter().find(|m| m.name == method.name))
+                    {
+                        Some(declared) => {
+                            let method =
+                                self.inherit_contract(&impl_block.trait_name, declared, method);
+                            self.check_fndef(&method);
+                        }
+                        None => self.check_fndef(method),
+                    }
                 }
             }
             Item::EffectDef(_) => {
@@ -1170,12 +1621,66 @@ impl TypeChecker {
             }
             Item::Test(test) => self.check_test(test),
             Item::Property(prop) => self.check_property(prop),
+            Item::TestHook(hook) => self.check_test_hook(hook),
+            Item::MacroDef(_) => {
+                // Templates are checked where they are expanded
+            }
+        }
+    }
+
+    /// Label the diagnostics raised since `from` that point into a macro
+    /// definition with the invocation whose expansion raised them
+    fn label_expansion(&mut self, from: usize, call: &Span, name: &str) {
+        let mut diagnostics = std::mem::take(&mut self.diagnostics).take();
+        for diag in &mut diagnostics[from..] {
+            let in_arguments = diag.span.file == call.file
+                && diag.span.start >= call.start
+                && diag.span.end <= call.end;
+            if !in_arguments {
+                diag.labels.push(crate::diagnostics::Label::new(
+                    call.clone(),
+                    format!("in this expansion of `{}!`", name),
+                ));
+            }
+        }
+        for diag in diagnostics {
+            self.diagnostics.push(diag);
+        }
+    }
+
+    /// `requires` and `ensures` clauses are Bool expressions over the
+    /// parameters; `ensures` can also refer to `result`
+    fn check_contract(&mut self, def: &FnDef, fn_env: &TypeEnv, return_type: &Type) {
+        let mut ensures_env = fn_env.clone();
+        ensures_env.define("result".to_string(), return_type.clone());
+        let clauses = def
+            .requires
+            .iter()
+            .map(|clause| (clause, fn_env))
+            .chain(def.ensures.iter().map(|clause| (clause, &ensures_env)));
+        let mut effects = HashSet::new();
+        for (clause, env) in clauses {
+            let ty = self.check_expr_with_effects(clause, env, &mut effects);
+            if ty != Type::Bool && ty != Type::Unknown {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        crate::diagnostics::error_codes::contracts::INVALID_CONTRACT_EXPR,
+                    )
+                    .message(format!(
+                        "Contract clause of `{}` must be a Bool expression, found {:?}",
+                        def.name, ty
+                    ))
+                    .span(clause.span().clone())
+                    .build(),
+                );
+            }
         }
     }
 
     fn check_typedef(&mut self, def: &TypeDef) {
         // Resolve the underlying type expression to verify it references valid types
         let _resolved = self.resolve_type_expr(&def.value);
+        self.check_deprecated_types(&def.value);
 
         // If the typedef has an invariant, type-check it
         if let Some(invariant) = &def.invariant {
@@ -1200,9 +1705,9 @@ impl TypeChecker {
 
     fn check_enumdef(&mut self, def: &EnumDef) {
         // Check for duplicate variant names
-        let mut seen_variants = HashSet::new();
+        let mut seen_variants: HashMap<&String, &Span> = HashMap::new();
         for variant in &def.variants {
-            if !seen_variants.insert(&variant.name) {
+            if let Some(first) = seen_variants.get(&variant.name) {
                 self.diagnostics.push(
                     Diagnostic::error(crate::diagnostics::error_codes::types::DUPLICATE_FIELD)
                         .message(format!(
@@ -1210,14 +1715,17 @@ impl TypeChecker {
                             variant.name, def.name
                         ))
                         .span(variant.span.clone())
+                        .label((*first).clone(), "first defined here")
                         .build(),
                 );
+            } else {
+                seen_variants.insert(&variant.name, &variant.span);
             }
 
             // Check for duplicate field names within each variant
-            let mut seen_fields = HashSet::new();
+            let mut seen_fields: HashMap<&String, &Span> = HashMap::new();
             for field in &variant.fields {
-                if !seen_fields.insert(&field.name) {
+                if let Some(first) = seen_fields.get(&field.name) {
                     self.diagnostics.push(
                         Diagnostic::error(crate::diagnostics::error_codes::types::DUPLICATE_FIELD)
                             .message(format!(
@@ -1225,16 +1733,59 @@ impl TypeChecker {
                                 field.name, variant.name
                             ))
                             .span(field.span.clone())
+                            .label((*first).clone(), "first defined here")
                             .build(),
                     );
+                } else {
+                    seen_fields.insert(&field.name, &field.span);
                 }
 
                 // Resolve each field type to check it's valid
                 let _resolved = self.resolve_type_expr(&field.ty);
+                self.check_deprecated_types(&field.ty);
             }
         }
     }
 
+    /// An impl method with the `requires` and `ensures` clauses of the trait
+    /// method it implements ahead of its own
+    fn inherit_contract(
+        &mut self,
+        trait_name: &str,
+        declared: &TraitMethod,
+        method: &FnDef,
+    ) -> FnDef {
+        let mut method = method.clone();
+        if declared.requires.is_empty() && declared.ensures.is_empty() {
+            return method;
+        }
+        if declared.params.len() != method.params.len() {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    crate::diagnostics::error_codes::contracts::CONTRACT_BINDING_UNAVAILABLE,
+                )
+                .message(format!(
+                    "`{}` cannot inherit the contract of `{}.{}`: the trait declares {} parameter(s) but this implementation has {}",
+                    method.name,
+                    trait_name,
+                    declared.name,
+                    declared.params.len(),
+                    method.params.len()
+                ))
+                .span(method.span.clone())
+                .note(Note::new(
+                    "the trait's clauses refer to its parameters by position",
+                ))
+                .build(),
+            );
+            return method;
+        }
+        let (requires, ensures) = declared.contracts_for(&method);
+        method.requires.splice(0..0, requires);
+        method.ensures.splice(0..0, ensures);
+        method
+    }
+
     fn check_fndef(&mut self, def: &FnDef) {
         let mut fn_env = self.env.child();
 
@@ -1260,9 +1811,55 @@ impl TypeChecker {
             }
         }
 
+        // Holes in the body are reported with the function's contract
+        let mut formatter = crate::formatter::Formatter::new();
+        let return_type = def
+            .return_type
+            .as_ref()
+            .map_or(Type::Unknown, |t| self.resolve_type_expr(t));
+        self.check_contract(def, &fn_env, &return_type);
+        self.current_fn = Some(FnContext {
+            name: def.name.clone(),
+            effects: def.effects.clone(),
+            signature: formatter.fn_signature(def),
+            requires: def
+                .requires
+                .iter()
+                .map(|e| formatter.expr_text(e))
+                .collect(),
+            ensures: def.ensures.iter().map(|e| formatter.expr_text(e)).collect(),
+            return_type: return_type.clone(),
+        });
+        for param in &def.params {
+            self.check_deprecated_types(&param.ty);
+        }
+        if let Some(ty) = &def.return_type {
+            self.check_deprecated_types(ty);
+            // W0012: a pure function hands out a fresh mutable cell on every call
+            if def.effects.is_empty() && mentions_ref(&return_type) {
+                let (TypeExpr::Named { span, .. }
+                | TypeExpr::Record { span, .. }
+                | TypeExpr::Function { span, .. }
+                | TypeExpr::Tuple { span, .. }) = ty;
+                self.diagnostics.push(
+                    Diagnostic::warning(crate::diagnostics::error_codes::warnings::REF_ESCAPE)
+                        .message(format!("Pure function `{}` returns a Ref", def.name))
+                        .span(span.clone())
+                        .note(Note::new(
+                            "callers can change the returned state without declaring an effect; return the value, or keep the Ref inside a function with effects",
+                        ))
+                        .build(),
+                );
+            }
+        }
+
         // Check body and collect effects used
         let mut effects_used = HashSet::new();
         let body_type = self.check_block_with_effects(&def.body, &mut fn_env, &mut effects_used);
+        if let Some(tail) = &def.body.expr {
+            self.expect_hole(tail, &return_type);
+        }
+        self.current_fn = None;
 
         // P1: Infer return type for private functions without explicit annotation
         if def.return_type.is_none() && def.visibility == crate::parser::ast::Visibility::Private {
@@ -1289,34 +1886,85 @@ impl TypeChecker {
         self.current_type_params = old_type_params;
 
         // C4: Effect enforcement - check that used effects are declared
-        let declared_effects: HashSet<String> = def.effects.iter().cloned().collect();
-        for used_effect in &effects_used {
-            if !declared_effects.contains(used_effect) {
-                self.diagnostics.push(
-                    Diagnostic::error(
-                        crate::diagnostics::error_codes::effects::EFFECT_NOT_DECLARED,
-                    )
-                    .message(format!(
-                        "Effect `{}` used but not declared in function `{}`",
-                        used_effect, def.name
-                    ))
+        let mut undeclared: Vec<&String> = effects_used
+            .iter()
+            .filter(|e| !def.effects.contains(e))
+            .collect();
+        undeclared.sort();
+        let mut declared = def.effects.clone();
+        declared.extend(undeclared.iter().map(|e| e.to_string()));
+        declared.sort();
+        for used_effect in &undeclared {
+            let mut suggestion = Suggestion::new(format!(
+                "Add `effects({})` to the function signature",
+                declared.join(", ")
+            ));
+            if def.effects_span.is_some() {
+                // Rewrite the whole signature as the formatter lays it out, so
+                // the fix leaves nothing for `astra fmt` to change
+                let fixed = FnDef {
+                    effects: declared.clone(),
+                    ..def.clone()
+                };
+                let indent = " ".repeat(def.span.start_col.saturating_sub(1));
+                let header = crate::formatter::Formatter::new()
+                    .fn_header(&fixed)
+                    .replace('\n', &format!("\n{}", indent));
+                let body = &def.body.span;
+                let span = Span::new(
+                    def.span.file.clone(),
+                    def.span.start,
+                    body.start,
+                    def.span.start_line,
+                    def.span.start_col,
+                    body.start_line,
+                    body.start_col,
+                );
+                suggestion = suggestion
+                    .with_edit(Edit::new(span, format!("{}\n{}", header, indent)))
+                    .with_applicability(Applicability::MachineApplicable);
+            }
+            self.diagnostics.push(
+                Diagnostic::error(crate::diagnostics::error_codes::effects::EFFECT_NOT_DECLARED)
+                    .catalog("E2001")
+                    .arg("effect", used_effect)
+                    .arg("function", &def.name)
                     .span(def.span.clone())
                     .note(Note::new(format!(
                         "function `{}` must declare `effects({})` or remove this call",
                         def.name, used_effect
                     )))
-                    .suggestion(Suggestion::new(format!(
-                        "Add `effects({})` to the function signature",
-                        effects_used.iter().cloned().collect::<Vec<_>>().join(", ")
-                    )))
+                    .suggestion(suggestion)
                     .build(),
-                );
-            }
+            );
+        }
+    }
+
+    /// Environment for a test body, with the `before_each` hook's `fixture`
+    fn test_env(&self) -> TypeEnv {
+        let mut env = self.env.child();
+        if let Some(fixture) = &self.fixture_type {
+            env.define("fixture".to_string(), fixture.clone());
+        }
+        env
+    }
+
+    fn check_test_hook(&mut self, hook: &TestHook) {
+        let mut hook_env = match hook.kind {
+            HookKind::BeforeEach => self.env.child(),
+            HookKind::AfterEach => self.test_env(),
+        };
+        let mut effects_used = HashSet::new();
+        self.push_lint_scope();
+        let ty = self.check_block_with_effects(&hook.body, &mut hook_env, &mut effects_used);
+        self.pop_lint_scope();
+        if hook.kind == HookKind::BeforeEach {
+            self.fixture_type = Some(self.subst.apply(&ty));
         }
     }
 
     fn check_test(&mut self, test: &TestBlock) {
-        let mut test_env = self.env.child();
+        let mut test_env = self.test_env();
         let mut effects_used = HashSet::new();
         self.push_lint_scope();
         self.check_block_with_effects(&test.body, &mut test_env, &mut effects_used);
@@ -1324,7 +1972,7 @@ impl TypeChecker {
     }
 
     fn check_property(&mut self, prop: &PropertyBlock) {
-        let mut prop_env = self.env.child();
+        let mut prop_env = self.test_env();
         let mut effects_used = HashSet::new();
         self.push_lint_scope();
         self.check_block_with_effects(&prop.body, &mut prop_env, &mut effects_used);
@@ -1352,7 +2000,7 @@ impl TypeChecker {
                     Diagnostic::warning(
                         crate::diagnostics::error_codes::warnings::UNREACHABLE_CODE,
                     )
-                    .message("Unreachable code after return statement")
+                    .catalog("W0003")
                     .span(stmt_span)
                     .note(Note::new("this code will never be executed"))
                     .build(),
@@ -1373,7 +2021,7 @@ impl TypeChecker {
                     Diagnostic::warning(
                         crate::diagnostics::error_codes::warnings::UNREACHABLE_CODE,
                     )
-                    .message("Unreachable expression after return statement")
+                    .catalog("W0003.expression")
                     .span(expr.span().clone())
                     .note(Note::new("this expression will never be evaluated"))
                     .build(),
@@ -1397,49 +2045,89 @@ impl TypeChecker {
                 ty,
                 value,
                 span,
+                attributes,
+                mutable,
                 ..
             } => {
+                self.register_suppressions(attributes, span);
                 let value_type = self.check_expr_with_effects(value, env, effects);
 
                 let declared_type = ty.as_ref().map(|t| self.resolve_type_expr(t));
+                if let Some(ty) = ty {
+                    self.check_deprecated_types(ty);
+                }
 
                 if let Some(declared) = &declared_type {
+                    self.expect_hole(value, declared);
                     if !self.types_compatible(&value_type, declared) {
                         let type_display = format_type(&value_type);
                         let mut suggestion = Suggestion::new(format!(
                             "Change the type annotation to `{}`",
                             type_display
                         ));
+                        let mut diag = Diagnostic::error(
+                            crate::diagnostics::error_codes::types::TYPE_MISMATCH,
+                        )
+                        .catalog("E1001.annotation")
+                        .arg("expected", format!("{:?}", declared))
+                        .arg("found", format!("{:?}", value_type))
+                        .span(value.span().clone());
                         // If we have the type expression span, add a concrete edit
                         if let Some(type_expr) = ty {
                             let type_span = type_expr_span(type_expr);
-                            suggestion = suggestion.with_edit(Edit::new(type_span, &type_display));
+                            suggestion =
+                                suggestion.with_edit(Edit::new(type_span.clone(), &type_display));
+                            diag = diag.label(type_span, "expected because of this annotation");
                         }
-                        self.diagnostics.push(
-                            Diagnostic::error(
-                                crate::diagnostics::error_codes::types::TYPE_MISMATCH,
-                            )
-                            .message(format!(
-                                "Expected type {:?}, found {:?}",
-                                declared, value_type
-                            ))
-                            .suggestion(suggestion)
-                            .build(),
-                        );
+                        self.diagnostics.push(diag.suggestion(suggestion).build());
                     }
                 }
 
-                env.define(name.clone(), declared_type.unwrap_or(value_type));
+                let ty = declared_type.unwrap_or(value_type);
+                if *mutable {
+                    env.define_mut(name.clone(), ty);
+                } else {
+                    env.define(name.clone(), ty);
+                }
 
                 // Track for lint (W0001 unused var, W0006 shadowed binding)
                 self.lint_define_var(name, span);
+                // A local `fn` is a `let` starting where its lambda does
+                if value.span().start > span.start {
+                    if let Some(binding) = self
+                        .lint_scopes
+                        .last_mut()
+                        .and_then(|scope| scope.vars.last_mut())
+                    {
+                        binding.let_keyword = Some(Span::new(
+                            span.file.clone(),
+                            span.start,
+                            span.start + "let".len(),
+                            span.start_line,
+                            span.start_col,
+                            span.start_line,
+                            span.start_col + "let".len(),
+                        ));
+                    }
+                }
             }
-            Stmt::LetPattern { pattern, value, .. } => {
-                self.check_expr_with_effects(value, env, effects);
+            Stmt::LetPattern {
+                pattern,
+                value,
+                span,
+                attributes,
+                ..
+            } => {
+                self.register_suppressions(attributes, span);
+                let value_type = self.check_expr_with_effects(value, env, effects);
+                self.check_record_patterns(pattern, &value_type);
                 // Bind pattern variables into the environment
                 collect_pattern_bindings(pattern, env);
             }
             Stmt::Assign { target, value, .. } => {
+                if let Expr::Ident { name, span, .. } = target.as_ref() {
+                    self.check_assignment(name, span, env);
+                }
                 let _target_type = self.check_expr_with_effects(target, env, effects);
                 let _value_type = self.check_expr_with_effects(value, env, effects);
             }
@@ -1449,6 +2137,9 @@ impl TypeChecker {
             Stmt::Return { value, .. } => {
                 if let Some(v) = value {
                     self.check_expr_with_effects(v, env, effects);
+                    if let Some(ret) = self.current_fn.as_ref().map(|f| f.return_type.clone()) {
+                        self.expect_hole(v, &ret);
+                    }
                 }
             }
         }
@@ -1460,9 +2151,16 @@ impl TypeChecker {
         env: &TypeEnv,
         effects: &mut HashSet<String>,
     ) -> Type {
+        let ty = self.infer_expr(expr, env, effects);
+        self.node_types.insert(expr.id(), ty.clone());
+        ty
+    }
+
+    fn infer_expr(&mut self, expr: &Expr, env: &TypeEnv, effects: &mut HashSet<String>) -> Type {
         match expr {
             Expr::IntLit { .. } => Type::Int,
             Expr::FloatLit { .. } => Type::Float,
+            Expr::DecimalLit { .. } => Type::Decimal,
             Expr::BoolLit { .. } => Type::Bool,
             Expr::TextLit { .. } => Type::Text,
             Expr::UnitLit { .. } => Type::Unit,
@@ -1470,12 +2168,91 @@ impl TypeChecker {
                 // Built-in constructors and effects are always available
                 match name.as_str() {
                     "Some" | "None" | "Ok" | "Err" => Type::Unknown,
-                    "Console" | "Fs" | "Net" | "Clock" | "Rand" | "Env" | "Map" | "Set" => {
+                    // An effect named as a value is the host's capability,
+                    // which only code with that effect can hand out
+                    effect if BUILTIN_EFFECTS.contains(&effect) && env.lookup(name).is_none() => {
+                        effects.insert(name.clone());
+                        Type::Named(name.clone(), vec![])
+                    }
+                    "Map" | "Set" => Type::Unknown,
+                    "Duration" | "Bytes" | "Ref" | "Args" if env.lookup(name).is_none() => {
                         Type::Unknown
                     }
-                    "assert" | "assert_eq" | "print" | "println" | "len" | "to_text" | "range"
-                    | "abs" | "min" | "max" | "pow" | "to_int" | "to_float" | "sqrt" | "floor"
-                    | "ceil" | "round" => Type::Unknown,
+                    "assert"
+                    | "assert_eq"
+                    | "assert_ne"
+                    | "assert_approx_eq"
+                    | "assert_contains"
+                    | "assert_err_code"
+                    | "assert_matches_file"
+                    | "dbg"
+                    | "print"
+                    | "println"
+                    | "len"
+                    | "to_text"
+                    | "range"
+                    | "abs"
+                    | "min"
+                    | "max"
+                    | "pow"
+                    | "to_int"
+                    | "to_float"
+                    | "to_decimal"
+                    | "base64_encode"
+                    | "hex_encode"
+                    | "sha256"
+                    | "sqrt"
+                    | "floor"
+                    | "ceil"
+                    | "round" => Type::Unknown,
+                    // User functions named `parse_int`/`parse_float` predate these
+                    "parse_int" if env.lookup_fn(name).is_none() => Type::Function {
+                        params: vec![Type::Text, Type::Int],
+                        ret: Box::new(Type::Result(
+                            Box::new(Type::Int),
+                            Box::new(Type::Named("ParseError".to_string(), vec![])),
+                        )),
+                        effects: vec![],
+                    },
+                    "parse_float" if env.lookup_fn(name).is_none() => Type::Function {
+                        params: vec![Type::Text],
+                        ret: Box::new(Type::Result(
+                            Box::new(Type::Float),
+                            Box::new(Type::Named("ParseError".to_string(), vec![])),
+                        )),
+                        effects: vec![],
+                    },
+                    "compress" if env.lookup_fn(name).is_none() => Type::Function {
+                        params: vec![Type::Unknown],
+                        ret: Box::new(bytes_type()),
+                        effects: vec![],
+                    },
+                    "decompress" if env.lookup_fn(name).is_none() => Type::Function {
+                        params: vec![bytes_type()],
+                        ret: Box::new(Type::Result(Box::new(bytes_type()), Box::new(Type::Text))),
+                        effects: vec![],
+                    },
+                    "hash" if env.lookup_fn(name).is_none() => Type::Function {
+                        params: vec![Type::Unknown],
+                        ret: Box::new(Type::Int),
+                        effects: vec![],
+                    },
+                    "base64_decode" | "hex_decode" => Type::Function {
+                        params: vec![Type::Text],
+                        ret: Box::new(Type::Result(
+                            Box::new(bytes_type()),
+                            Box::new(encoding_error_type()),
+                        )),
+                        effects: vec![],
+                    },
+                    "parse_decimal" => Type::Function {
+                        params: vec![Type::Text],
+                        ret: Box::new(Type::Result(
+                            Box::new(Type::Decimal),
+                            Box::new(Type::Named("ParseError".to_string(), vec![])),
+                        )),
+                        effects: vec![],
+                    },
                     // v1.1: JSON builtins
                     "json_parse" => Type::Function {
                         params: vec![Type::Text],
@@ -1534,6 +2311,24 @@ impl TypeChecker {
                         ret: Box::new(Type::Text),
                         effects: vec!["Net".to_string()],
                     },
+                    "style" if env.lookup_fn(name).is_none() => Type::Function {
+                        params: vec![Type::Text, Type::Text, Type::Bool],
+                        ret: Box::new(Type::Text),
+                        effects: vec!["Console".to_string()],
+                    },
+                    "rpc_call" if env.lookup_fn(name).is_none() => Type::Function {
+                        params: vec![Type::Text, Type::Text, Type::Json],
+                        ret: Box::new(rpc_result_type()),
+                        effects: vec!["Net".to_string()],
+                    },
+                    "rpc_batch" if env.lookup_fn(name).is_none() => Type::Function {
+                        params: vec![
+                            Type::Text,
+                            Type::List(Box::new(Type::Tuple(vec![Type::Text, Type::Json]))),
+                        ],
+                        ret: Box::new(Type::List(Box::new(rpc_result_type()))),
+                        effects: vec!["Net".to_string()],
+                    },
                     "random_int" => Type::Function {
                         params: vec![Type::Int, Type::Int],
                         ret: Box::new(Type::Int),
@@ -1555,6 +2350,9 @@ impl TypeChecker {
                         effects: vec!["Env".to_string()],
                     },
                     _ => {
+                        if !self.is_local_var(name) {
+                            self.check_deprecated_use(name, span);
+                        }
                         // Mark variable as used for W0001 lint
                         self.lint_use_var(name);
                         // Track function references for unused function lint
@@ -1566,7 +2364,8 @@ impl TypeChecker {
                             let mut diag = Diagnostic::error(
                                 crate::diagnostics::error_codes::types::UNKNOWN_IDENTIFIER,
                             )
-                            .message(format!("Unknown identifier: {}", name))
+                            .catalog("E1002")
+                            .arg("name", name)
                             .span(span.clone());
 
                             // Suggest similar names from the environment
@@ -1586,10 +2385,25 @@ impl TypeChecker {
                 }
             }
             Expr::Binary {
-                op, left, right, ..
+                op,
+                left,
+                right,
+                span,
+                ..
             } => {
                 let left_ty = self.check_expr_with_effects(left, env, effects);
                 let right_ty = self.check_expr_with_effects(right, env, effects);
+                match op {
+                    BinaryOp::And | BinaryOp::Or => {
+                        self.expect_hole(left, &Type::Bool);
+                        self.expect_hole(right, &Type::Bool);
+                    }
+                    BinaryOp::Pipe => {}
+                    _ => {
+                        self.expect_hole(left, &right_ty);
+                        self.expect_hole(right, &left_ty);
+                    }
+                }
 
                 match op {
                     // Comparison operators always return Bool
@@ -1607,8 +2421,35 @@ impl TypeChecker {
                     | BinaryOp::Mul
                     | BinaryOp::Div
                     | BinaryOp::Mod => {
-                        if left_ty == Type::Int && right_ty == Type::Int {
+                        let decimal = left_ty == Type::Decimal || right_ty == Type::Decimal;
+                        let duration = left_ty == duration_type() || right_ty == duration_type();
+                        if duration {
+                            self.check_duration_arithmetic(*op, &left_ty, &right_ty, span)
+                        } else if left_ty == Type::Int && right_ty == Type::Int {
                             Type::Int
+                        } else if decimal && (left_ty == Type::Float || right_ty == Type::Float) {
+                            self.diagnostics.push(
+                                Diagnostic::error(
+                                    crate::diagnostics::error_codes::types::TYPE_MISMATCH,
+                                )
+                                .message(format!(
+                                    "Cannot mix `Decimal` and `Float` in `{}`",
+                                    op.as_str()
+                                ))
+                                .span(span.clone())
+                                .note(Note::new(
+                                    "convert with `to_decimal(x)` or `d.to_float()` so the rounding is explicit",
+                                ))
+                                .build(),
+                            );
+                            Type::Unknown
+                        } else if decimal {
+                            Type::Decimal
+                        } else if left_ty == bytes_type()
+                            && right_ty == bytes_type()
+                            && *op == BinaryOp::Add
+                        {
+                            bytes_type()
                         } else if left_ty == Type::Float || right_ty == Type::Float {
                             Type::Float
                         } else if left_ty == Type::Text
@@ -1637,10 +2478,11 @@ impl TypeChecker {
                 ..
             } => {
                 let cond_ty = self.check_expr_with_effects(cond, env, effects);
+                self.expect_hole(cond, &Type::Bool);
                 if cond_ty != Type::Bool && cond_ty != Type::Unknown {
                     self.diagnostics.push(
                         Diagnostic::error(crate::diagnostics::error_codes::types::TYPE_MISMATCH)
-                            .message("Condition must be Bool")
+                            .catalog("E1001.condition")
                             .build(),
                     );
                 }
@@ -1655,7 +2497,7 @@ impl TypeChecker {
                             Diagnostic::error(
                                 crate::diagnostics::error_codes::types::TYPE_MISMATCH,
                             )
-                            .message("If branches have different types")
+                            .catalog("E1001.if_branches")
                             .build(),
                         );
                     }
@@ -1675,6 +2517,7 @@ impl TypeChecker {
                 // Check each arm body with pattern bindings in scope
                 let mut first_arm_ty = Type::Unit;
                 for (i, arm) in arms.iter().enumerate() {
+                    self.check_record_patterns(&arm.pattern, &scrutinee_ty);
                     let mut arm_env = env.clone();
                     collect_pattern_bindings(&arm.pattern, &mut arm_env);
                     // Check guard expression if present
@@ -1688,12 +2531,26 @@ impl TypeChecker {
                 }
 
                 // C2: Exhaustiveness checking
-                let patterns: Vec<&Pattern> = arms.iter().map(|a| &a.pattern).collect();
-                self.check_match_exhaustiveness(&scrutinee_ty, &patterns, span, env);
+                self.check_match_exhaustiveness(&scrutinee_ty, arms, span, env);
 
                 first_arm_ty
             }
             Expr::Call { func, args, .. } => {
+                // W0010: `dbg(x)` has the type of `x` and shouldn't be committed
+                if let Expr::Ident { name, span, .. } = func.as_ref() {
+                    if name == "dbg" && args.len() == 1 && !self.is_local_var(name) {
+                        self.diagnostics.push(
+                            Diagnostic::warning(
+                                crate::diagnostics::error_codes::warnings::DBG_CALL,
+                            )
+                            .catalog("W0010")
+                            .span(span.clone())
+                            .note(Note::new("remove it once you are done debugging"))
+                            .build(),
+                        );
+                        return self.check_expr_with_effects(&args[0], env, effects);
+                    }
+                }
                 let func_ty = self.check_expr_with_effects(func, env, effects);
 
                 let arg_types: Vec<Type> = args
@@ -1701,6 +2558,35 @@ impl TypeChecker {
                     .map(|arg| self.check_expr_with_effects(arg, env, effects))
                     .collect();
 
+                // W0004: `to_int`/`to_float` on Text is superseded by `parse_int`/`parse_float`
+                if let (Expr::Ident { name, span, .. }, [arg_ty]) = (func.as_ref(), &arg_types[..])
+                {
+                    let replacement = match name.as_str() {
+                        "to_int" => Some("parse_int(text, 10)"),
+                        "to_float" => Some("parse_float(text)"),
+                        _ => None,
+                    };
+                    if let Some(replacement) = replacement {
+                        if self.subst.apply(arg_ty) == Type::Text
+                            && !self.is_local_var(name)
+                            && !self.in_deprecated_fn()
+                        {
+                            self.diagnostics.push(
+                                Diagnostic::warning(
+                                    crate::diagnostics::error_codes::warnings::DEPRECATED,
+                                )
+                                .message(format!("`{}` on Text is deprecated", name))
+                                .span(span.clone())
+                                .note(Note::new(format!(
+                                    "use `{}`, which returns a Result saying why parsing failed",
+                                    replacement
+                                )))
+                                .build(),
+                            );
+                        }
+                    }
+                }
+
                 if let Type::Function {
                     params,
                     ret,
@@ -1716,16 +2602,18 @@ impl TypeChecker {
                             Diagnostic::error(
                                 crate::diagnostics::error_codes::types::WRONG_ARGUMENT_COUNT,
                             )
-                            .message(format!(
-                                "Expected {} argument(s) but found {}",
-                                params.len(),
-                                arg_types.len()
-                            ))
+                            .catalog("E1007")
+                            .arg("expected", params.len())
+                            .arg("found", arg_types.len())
                             .span(func.span().clone())
                             .build(),
                         );
                     }
 
+                    for (arg, param_ty) in args.iter().zip(params) {
+                        self.expect_hole(arg, param_ty);
+                    }
+
                     // B1: Check argument types against parameter types
                     for (i, (param_ty, arg_ty)) in params.iter().zip(arg_types.iter()).enumerate() {
                         if !self.types_compatible(param_ty, arg_ty) {
@@ -1733,12 +2621,10 @@ impl TypeChecker {
                                 Diagnostic::error(
                                     crate::diagnostics::error_codes::types::TYPE_MISMATCH,
                                 )
-                                .message(format!(
-                                    "Type mismatch in argument {}: expected `{}`, found `{}`",
-                                    i + 1,
-                                    format_type(param_ty),
-                                    format_type(arg_ty)
-                                ))
+                                .catalog("E1001.argument")
+                                .arg("index", i + 1)
+                                .arg("expected", format_type(param_ty))
+                                .arg("found", format_type(arg_ty))
                                 .span(if i < args.len() {
                                     args[i].span().clone()
                                 } else {
@@ -1803,8 +2689,7 @@ impl TypeChecker {
             }
             // C4: Track effect usage from qualified identifiers (e.g., Console.println)
             Expr::QualifiedIdent { module, .. } => {
-                let known_effects = ["Console", "Fs", "Net", "Clock", "Rand", "Env"];
-                if known_effects.contains(&module.as_str()) {
+                if BUILTIN_EFFECTS.contains(&module.as_str()) {
                     effects.insert(module.clone());
                 }
                 Type::Unknown
@@ -1812,22 +2697,126 @@ impl TypeChecker {
             // C4: Track effect usage from method calls (e.g., Console.println())
             Expr::MethodCall {
                 receiver,
-                method: _,
+                method,
                 args,
+                span,
+                method_span,
                 ..
             } => {
                 // Check if receiver is an effect name
                 if let Expr::Ident { name, .. } = receiver.as_ref() {
-                    let known_effects = ["Console", "Fs", "Net", "Clock", "Rand", "Env"];
-                    if known_effects.contains(&name.as_str()) {
+                    if BUILTIN_EFFECTS.contains(&name.as_str()) {
                         effects.insert(name.clone());
                     }
+                    // Loading a key reads it through another capability
+                    match (name.as_str(), method.as_str()) {
+                        ("Crypto", "key_from_env") => effects.insert("Env".to_string()),
+                        ("Crypto", "key_from_file") => effects.insert("Fs".to_string()),
+                        // Parsing reads the arguments from Env
+                        ("Args", "parse") if env.lookup(name).is_none() => {
+                            effects.insert("Env".to_string())
+                        }
+                        _ => false,
+                    };
                 }
-                self.check_expr_with_effects(receiver, env, effects);
-                for arg in args {
-                    self.check_expr_with_effects(arg, env, effects);
+                let receiver_ty = self.check_expr_with_effects(receiver, env, effects);
+                // A WebSocket talks over the network it was opened on
+                if matches!(self.subst.apply(&receiver_ty), Type::Named(ref name, _) if name == "WebSocket")
+                {
+                    effects.insert("Net".to_string());
+                }
+                let arg_types: Vec<Type> = args
+                    .iter()
+                    .map(|arg| self.check_expr_with_effects(arg, env, effects))
+                    .collect();
+                let receiver_ty = self.subst.apply(&receiver_ty);
+                let namespace = match (receiver.as_ref(), &receiver_ty) {
+                    (Expr::Ident { name, .. }, _) if env.lookup(name).is_none() => {
+                        Some(name.as_str())
+                    }
+                    // A capability value has its effect's methods, which use
+                    // the capability it holds rather than the caller's effects
+                    (_, Type::Named(name, _)) if BUILTIN_EFFECTS.contains(&name.as_str()) => {
+                        Some(name.as_str())
+                    }
+                    _ => None,
+                };
+                let signature = match (namespace, receiver_ty.clone()) {
+                    (Some("Fs"), _) => fs_method_signature(method),
+                    (Some("Duration"), _) => duration_constructor_signature(method),
+                    (Some("Args"), _) => args_method_signature(method, &arg_types),
+                    (Some("Clock"), _) => clock_method_signature(method),
+                    (Some("Rand"), _) => rand_method_signature(method),
+                    (Some("Crypto"), _) => crypto_method_signature(method),
+                    (Some("Serve"), _) => serve_method_signature(method),
+                    (Some("Db"), _) => db_method_signature(method),
+                    (Some("Progress"), _) => progress_method_signature(method),
+                    (Some("Metrics"), _) => metrics_method_signature(method),
+                    (Some("Net"), _) if method == "ws_connect" => Some(MethodSignature {
+                        params: vec![Type::Text],
+                        required: 1,
+                        ret: Type::Result(Box::new(websocket_type()), Box::new(Type::Text)),
+                    }),
+                    (Some("Ref"), _) if method == "new" => Some(MethodSignature {
+                        params: vec![Type::Unknown],
+                        required: 1,
+                        ret: ref_type(arg_types.first().cloned().unwrap_or(Type::Unknown)),
+                    }),
+                    (Some("Bytes"), _) if method == "from_list" => Some(MethodSignature {
+                        params: vec![Type::List(Box::new(Type::Int))],
+                        required: 1,
+                        ret: bytes_type(),
+                    }),
+                    (_, Type::Text) => text_method_signature(method),
+                    (_, Type::Float) => float_method_signature(method),
+                    (_, Type::Decimal) => decimal_method_signature(method),
+                    (_, Type::Named(name, _)) if name == "Duration" => {
+                        duration_method_signature(method)
+                    }
+                    (_, Type::Named(name, _)) if name == "Bytes" => bytes_method_signature(method),
+                    (_, Type::Named(name, _)) if name == "WebSocket" => {
+                        websocket_method_signature(method)
+                    }
+                    (_, Type::Named(name, args)) if name == "Ref" => ref_method_signature(
+                        method,
+                        args.into_iter().next().unwrap_or(Type::Unknown),
+                    ),
+                    _ => None,
+                };
+                match signature {
+                    Some(signature) => self.check_method_args(
+                        namespace
+                            .map_or_else(|| format_type(&receiver_ty), str::to_string)
+                            .as_str(),
+                        method,
+                        &signature,
+                        args,
+                        &arg_types,
+                        span,
+                    ),
+                    None => {
+                        let methods = namespace.and_then(|ns| Some((ns, namespace_methods(ns)?)));
+                        if let Some((namespace, methods)) = methods {
+                            if !methods.contains(&method.as_str()) {
+                                let name_span = method_span.as_ref().unwrap_or(span);
+                                self.report_unknown_member(
+                                    Diagnostic::error(
+                                        crate::diagnostics::error_codes::types::UNKNOWN_METHOD,
+                                    )
+                                    .catalog("E1018")
+                                    .arg("method", method)
+                                    .arg("receiver", namespace),
+                                    method,
+                                    methods,
+                                    "methods",
+                                    name_span,
+                                    method_span.is_some(),
+                                );
+                            }
+                        }
+                        Type::Unknown
+                    }
                 }
-                Type::Unknown
             }
             Expr::Record { fields, .. } => {
                 let field_types: Vec<_> = fields
@@ -1841,14 +2830,35 @@ impl TypeChecker {
                     .collect();
                 Type::Record(field_types)
             }
-            Expr::FieldAccess { expr, field, .. } => {
+            Expr::FieldAccess {
+                expr,
+                field,
+                span,
+                field_span,
+                ..
+            } => {
                 let expr_ty = self.check_expr_with_effects(expr, env, effects);
                 if let Type::Record(fields) = expr_ty {
-                    fields
-                        .iter()
-                        .find(|(n, _)| n == field)
-                        .map(|(_, t)| t.clone())
-                        .unwrap_or(Type::Unknown)
+                    match fields.iter().find(|(n, _)| n == field) {
+                        Some((_, ty)) => ty.clone(),
+                        None => {
+                            let names: Vec<&str> = fields.iter().map(|(n, _)| n.as_str()).collect();
+                            self.report_unknown_member(
+                                Diagnostic::error(
+                                    crate::diagnostics::error_codes::types::UNKNOWN_FIELD,
+                                )
+                                .catalog("E1006")
+                                .arg("field", field)
+                                .arg("type", format_type(&Type::Record(fields.clone()))),
+                                field,
+                                &names,
+                                "fields",
+                                field_span.as_ref().unwrap_or(span),
+                                field_span.is_some(),
+                            );
+                            Type::Unknown
+                        }
+                    }
                 } else if expr_ty == Type::Json {
                     // Field access on Json returns Json (dynamic access)
                     Type::Json
@@ -1887,6 +2897,9 @@ impl TypeChecker {
                 let mut lambda_env = env.clone();
                 self.push_lint_scope();
                 for param in params {
+                    if let Some(ty) = &param.ty {
+                        self.check_deprecated_types(ty);
+                    }
                     let ty = param
                         .ty
                         .as_ref()
@@ -1923,20 +2936,52 @@ impl TypeChecker {
                 body,
                 ..
             } => {
-                self.check_expr_with_effects(iter, env, effects);
+                let iter_ty = self.check_expr_with_effects(iter, env, effects);
+                let elem_ty = self.element_type(&iter_ty);
                 let mut loop_env = env.clone();
                 self.push_lint_scope();
                 // E8: If there's a destructuring pattern, register pattern vars
                 if let Some(pat) = pattern {
-                    self.register_pattern_vars(pat, &mut loop_env, Type::Unknown);
+                    self.check_record_patterns(pat, &elem_ty);
+                    self.register_pattern_vars(pat, &mut loop_env, elem_ty);
                 } else {
-                    loop_env.define(binding.clone(), Type::Unknown);
+                    loop_env.define(binding.clone(), elem_ty);
                     self.lint_define_var(binding, iter.span());
                 }
                 self.check_block_with_effects(body, &mut loop_env, effects);
                 self.pop_lint_scope();
                 Type::Unit
             }
+            Expr::Quantifier {
+                span,
+                kind,
+                binding,
+                iter,
+                body,
+                ..
+            } => {
+                let iter_ty = self.check_expr_with_effects(iter, env, effects);
+                let elem_ty = self.element_type(&iter_ty);
+                let mut body_env = env.clone();
+                self.push_lint_scope();
+                body_env.define(binding.clone(), elem_ty);
+                self.lint_define_var(binding, span);
+                let body_ty = self.check_expr_with_effects(body, &body_env, effects);
+                self.pop_lint_scope();
+                if !self.types_compatible(&body_ty, &Type::Bool) {
+                    self.diagnostics.push(
+                        Diagnostic::error(crate::diagnostics::error_codes::types::TYPE_MISMATCH)
+                            .message(format!(
+                                "`{}` condition must be Bool, found {:?}",
+                                kind.as_str(),
+                                body_ty
+                            ))
+                            .span(body.span().clone())
+                            .build(),
+                    );
+                }
+                Type::Bool
+            }
             Expr::While { cond, body, .. } => {
                 self.check_expr_with_effects(cond, env, effects);
                 let mut loop_env = env.clone();
@@ -2021,15 +3066,326 @@ impl TypeChecker {
                 // P6.5: await just checks the inner expression
                 self.check_expr_with_effects(expr, env, effects)
             }
-            Expr::Hole { span, .. } => {
-                self.diagnostics.push(
-                    Diagnostic::info("H0001")
-                        .message("Typed hole - type unknown")
-                        .span(span.clone())
-                        .build(),
-                );
+            Expr::Hole { id, span } => {
+                self.holes.push(HoleSite {
+                    id: *id,
+                    span: span.clone(),
+                    bindings: env.local_bindings(),
+                    function: self.current_fn.clone(),
+                });
                 Type::Unknown
             }
+            Expr::MacroCall {
+                span,
+                name,
+                expansion,
+                ..
+            } => {
+                let Some(expansion) = expansion else {
+                    return Type::Unknown;
+                };
+                let before = self.diagnostics.len();
+                let ty = self.check_expr_with_effects(expansion, env, effects);
+                self.label_expansion(before, span, name);
+                ty
+            }
+        }
+    }
+
+    /// Record the type the context expects of `expr` if it is a hole, or
+    /// of the holes it evaluates to (block tails, branches, and match arms)
+    fn expect_hole(&mut self, expr: &Expr, expected: &Type) {
+        match expr {
+            Expr::Hole { id, .. } => {
+                let expected = self.subst.apply(expected);
+                if !matches!(expected, Type::Unknown | Type::Var(_)) {
+                    self.hole_types.entry(*id).or_insert(expected);
+                }
+            }
+            Expr::Block { block, .. } => {
+                if let Some(tail) = &block.expr {
+                    self.expect_hole(tail, expected);
+                }
+            }
+            Expr::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                if let Some(tail) = &then_branch.expr {
+                    self.expect_hole(tail, expected);
+                }
+                if let Some(else_expr) = else_branch {
+                    self.expect_hole(else_expr, expected);
+                }
+            }
+            Expr::Match { arms, .. } => {
+                for arm in arms {
+                    self.expect_hole(&arm.body, expected);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Emit an H0001 diagnostic for each hole with its expected type, the
+    /// bindings that could fill it, and the enclosing function's contract
+    fn report_holes(&mut self) {
+        for hole in std::mem::take(&mut self.holes) {
+            let expected = self.hole_types.get(&hole.id).cloned();
+            let bindings: Vec<HoleBinding> = hole
+                .bindings
+                .iter()
+                .map(|(name, ty)| (name, self.subst.apply(ty)))
+                .filter(|(_, ty)| match &expected {
+                    Some(expected) => *ty != Type::Unknown && self.types_compatible(ty, expected),
+                    None => true,
+                })
+                .map(|(name, ty)| HoleBinding {
+                    name: name.clone(),
+                    ty: format_type(&ty),
+                })
+                .collect();
+
+            let candidates = match &expected {
+                Some(expected) => self.hole_candidates(&hole, expected),
+                None => Vec::new(),
+            };
+
+            let mut diag = Diagnostic::info(crate::diagnostics::error_codes::holes::TYPED_HOLE)
+                .message(match &expected {
+                    Some(ty) => format!("Typed hole of type `{}`", format_type(ty)),
+                    None => "Typed hole - type unknown".to_string(),
+                })
+                .span(hole.span.clone());
+            if !bindings.is_empty() {
+                let names: Vec<String> = bindings
+                    .iter()
+                    .map(|b| format!("`{}: {}`", b.name, b.ty))
+                    .collect();
+                diag = diag.note(Note::new(format!(
+                    "bindings in scope that fit: {}",
+                    names.join(", ")
+                )));
+            }
+            if !candidates.is_empty() {
+                let fills: Vec<String> = candidates.iter().map(|c| format!("`{}`", c)).collect();
+                diag = diag.note(Note::new(format!("possible fills: {}", fills.join(", "))));
+            }
+            if let Some(function) = &hole.function {
+                for clause in &function.requires {
+                    diag = diag.note(Note::new(format!("the function requires {}", clause)));
+                }
+                for clause in &function.ensures {
+                    diag = diag.note(Note::new(format!("the function ensures {}", clause)));
+                }
+            }
+            self.diagnostics.push(
+                diag.hole(HoleContext {
+                    expected_type: expected.as_ref().map(format_type),
+                    bindings,
+                    candidates,
+                    function: hole.function.as_ref().map(|f| f.signature.clone()),
+                    requires: hole
+                        .function
+                        .as_ref()
+                        .map(|f| f.requires.clone())
+                        .unwrap_or_default(),
+                    ensures: hole.function.map(|f| f.ensures).unwrap_or_default(),
+                })
+                .build(),
+            );
+        }
+    }
+
+    /// Enumerate well-typed expressions that could fill a hole, best first:
+    /// local variables, then nullary constructors and module-level values,
+    /// then constructor applications and single function calls whose
+    /// arguments are local variables
+    fn hole_candidates(&self, hole: &HoleSite, expected: &Type) -> Vec<String> {
+        let fits = |ty: &Type, expected: &Type| {
+            let ty = self.subst.apply(ty);
+            is_concrete(&ty) && self.types_compatible(&ty, expected)
+        };
+        // Local variables that could be passed as an argument of type `ty`
+        let args_for = |ty: &Type| -> Vec<&str> {
+            hole.bindings
+                .iter()
+                .filter(|(_, binding_ty)| fits(binding_ty, ty))
+                .map(|(name, _)| name.as_str())
+                .collect()
+        };
+        let calls = |name: &str, params: &[Type]| -> Vec<String> {
+            let choices: Vec<Vec<&str>> = params.iter().map(&args_for).collect();
+            let mut combos: Vec<Vec<&str>> = vec![Vec::new()];
+            for options in &choices {
+                combos = combos
+                    .iter()
+                    .flat_map(|combo| {
+                        options.iter().map(move |arg| {
+                            let mut combo = combo.clone();
+                            combo.push(*arg);
+                            combo
+                        })
+                    })
+                    .take(HOLE_CALL_ARGS)
+                    .collect();
+            }
+            combos
+                .into_iter()
+                .map(|args| format!("{}({})", name, args.join(", ")))
+                .collect()
+        };
+
+        // (rank, expression); lower ranks are simpler
+        let mut candidates: Vec<(usize, String)> = Vec::new();
+        for (name, ty) in &hole.bindings {
+            if fits(ty, expected) {
+                candidates.push((0, name.clone()));
+            }
+        }
+        match expected {
+            Type::Option(inner) => {
+                candidates.push((1, "None".to_string()));
+                candidates.extend(
+                    calls("Some", &[(**inner).clone()])
+                        .into_iter()
+                        .map(|c| (2, c)),
+                );
+            }
+            Type::Result(ok, err) => {
+                candidates.extend(calls("Ok", &[(**ok).clone()]).into_iter().map(|c| (2, c)));
+                candidates.extend(calls("Err", &[(**err).clone()]).into_iter().map(|c| (2, c)));
+            }
+            _ => {}
+        }
+
+        let variants: HashSet<&str> = self
+            .env
+            .enum_defs
+            .values()
+            .flat_map(|def| def.variants.iter().map(|v| v.name.as_str()))
+            .collect();
+        let function = hole.function.as_ref();
+        let mut globals: Vec<(&String, &Type)> = self.env.bindings.iter().collect();
+        globals.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, ty) in globals {
+            if hole.bindings.iter().any(|(local, _)| local == name) {
+                continue;
+            }
+            match ty {
+                Type::Function {
+                    params,
+                    ret,
+                    effects,
+                } => {
+                    if !fits(ret, expected) {
+                        continue;
+                    }
+                    if variants.contains(name.as_str()) {
+                        candidates.extend(calls(name, params).into_iter().map(|c| (2, c)));
+                        continue;
+                    }
+                    // Calls must not recurse or need undeclared effects
+                    let allowed = function.is_some_and(|f| {
+                        f.name != *name && effects.iter().all(|e| f.effects.contains(e))
+                    });
+                    if allowed {
+                        let rank = 3 + params.len();
+                        candidates.extend(calls(name, params).into_iter().map(|c| (rank, c)));
+                    }
+                }
+                _ if fits(ty, expected) => candidates.push((1, name.clone())),
+                _ => {}
+            }
+        }
+
+        candidates.sort_by_key(|(rank, _)| *rank);
+        let mut seen = HashSet::new();
+        candidates
+            .into_iter()
+            .map(|(_, expr)| expr)
+            .filter(|expr| seen.insert(expr.clone()))
+            .take(HOLE_CANDIDATES)
+            .collect()
+    }
+
+    /// Check the record patterns in `pattern` against the record types they
+    /// match: every field they name must exist (E1006), and without `..` they
+    /// must name every field (E1001)
+    fn check_record_patterns(&mut self, pattern: &Pattern, ty: &Type) {
+        match (pattern, self.subst.apply(ty)) {
+            (
+                Pattern::Record {
+                    fields, rest, span, ..
+                },
+                Type::Record(field_types),
+            ) => {
+                for (name, field_pattern) in fields {
+                    match field_types.iter().find(|(field, _)| field == name) {
+                        Some((_, field_ty)) => self.check_record_patterns(field_pattern, field_ty),
+                        None => self.diagnostics.push(
+                            Diagnostic::error(
+                                crate::diagnostics::error_codes::types::UNKNOWN_FIELD,
+                            )
+                            .catalog("E1006")
+                            .arg("field", name)
+                            .arg("type", format_type(&Type::Record(field_types.clone())))
+                            .span(field_pattern.span().clone())
+                            .build(),
+                        ),
+                    }
+                }
+                let missing: Vec<String> = field_types
+                    .iter()
+                    .filter(|(field, _)| !fields.iter().any(|(name, _)| name == field))
+                    .map(|(field, _)| format!("`{}`", field))
+                    .collect();
+                if !rest && !missing.is_empty() {
+                    // Insert `, ..` after the last field, or `..` into `{}`
+                    let edit = match fields.last() {
+                        Some((_, last)) => Edit::new(last.span().end_point(), ", .."),
+                        None => Edit::new(
+                            Span::new(
+                                span.file.clone(),
+                                span.start + 1,
+                                span.start + 1,
+                                span.start_line,
+                                span.start_col + 1,
+                                span.start_line,
+                                span.start_col + 1,
+                            ),
+                            "..",
+                        ),
+                    };
+                    self.diagnostics.push(
+                        Diagnostic::error(crate::diagnostics::error_codes::types::TYPE_MISMATCH)
+                            .message(format!(
+                                "Record pattern does not mention field(s) {}",
+                                missing.join(", ")
+                            ))
+                            .span(span.clone())
+                            .suggestion(
+                                Suggestion::new("Add `..` to ignore the other fields")
+                                    .with_edit(edit),
+                            )
+                            .build(),
+                    );
+                }
+            }
+            (Pattern::Tuple { elements, .. }, Type::Tuple(types))
+                if elements.len() == types.len() =>
+            {
+                for (element, element_ty) in elements.iter().zip(&types) {
+                    self.check_record_patterns(element, element_ty);
+                }
+            }
+            (Pattern::Variant { name, fields, .. }, Type::Option(inner)) if name == "Some" => {
+                if let [field] = fields.as_slice() {
+                    self.check_record_patterns(field, &inner);
+                }
+            }
+            _ => {}
         }
     }
 
@@ -2037,10 +3393,13 @@ impl TypeChecker {
     fn check_match_exhaustiveness(
         &mut self,
         scrutinee_ty: &Type,
-        patterns: &[&Pattern],
+        arms: &[MatchArm],
         match_span: &Span,
         env: &TypeEnv,
     ) {
+        let patterns: Vec<&Pattern> = arms.iter().map(|a| &a.pattern).collect();
+        let patterns = patterns.as_slice();
+
         // Determine the kind of type being matched
         let match_kind = self.infer_match_kind(scrutinee_ty, patterns, env);
 
@@ -2148,8 +3507,11 @@ impl TypeChecker {
                     .collect();
                 variants
                     .iter()
-                    .filter(|v| !covered.contains(v.as_str()))
-                    .cloned()
+                    .filter(|(name, _)| !covered.contains(name.as_str()))
+                    .map(|(name, fields)| match fields {
+                        0 => name.clone(),
+                        n => format!("{}({})", name, vec!["_"; *n].join(", ")),
+                    })
                     .collect()
             }
             MatchTypeKind::Other => {
@@ -2160,23 +3522,19 @@ impl TypeChecker {
 
         if !missing.is_empty() {
             let missing_display = missing.join(", ");
-            let suggestion_text = missing
-                .iter()
-                .map(|m| format!("    {} => ???", m))
-                .collect::<Vec<_>>()
-                .join("\n");
+            let mut suggestion =
+                Suggestion::new(format!("Add missing arm(s) `{}`", missing_display))
+                    .with_applicability(Applicability::MachineApplicable);
+            if let Some(edit) = missing_arms_edit(arms, match_span, &missing) {
+                suggestion = suggestion.with_edit(edit);
+            }
 
             self.diagnostics.push(
                 Diagnostic::error(crate::diagnostics::error_codes::types::NON_EXHAUSTIVE_MATCH)
-                    .message(format!(
-                        "Non-exhaustive match: missing pattern(s) `{}`",
-                        missing_display
-                    ))
+                    .catalog("E1004")
+                    .arg("missing", missing_display)
                     .span(match_span.clone())
-                    .suggestion(Suggestion::new(format!(
-                        "Add missing case(s):\n{}",
-                        suggestion_text
-                    )))
+                    .suggestion(suggestion)
                     .build(),
             );
         }
@@ -2198,7 +3556,11 @@ impl TypeChecker {
                 if let Some(enum_def) = env.lookup_enum(name) {
                     return MatchTypeKind::Enum {
                         _name: name.clone(),
-                        variants: enum_def.variants.iter().map(|v| v.name.clone()).collect(),
+                        variants: enum_def
+                            .variants
+                            .iter()
+                            .map(|v| (v.name.clone(), v.fields.len()))
+                            .collect(),
                     };
                 }
             }
@@ -2238,7 +3600,11 @@ impl TypeChecker {
             if let Some(enum_def) = self.find_enum_containing_variant(first_variant, env) {
                 return MatchTypeKind::Enum {
                     _name: enum_def.name.clone(),
-                    variants: enum_def.variants.iter().map(|v| v.name.clone()).collect(),
+                    variants: enum_def
+                        .variants
+                        .iter()
+                        .map(|v| (v.name.clone(), v.fields.len()))
+                        .collect(),
                 };
             }
         }
@@ -2266,6 +3632,7 @@ impl TypeChecker {
             TypeExpr::Named { name, args, .. } => match name.as_str() {
                 "Int" => Type::Int,
                 "Float" => Type::Float,
+                "Decimal" => Type::Decimal,
                 "Bool" => Type::Bool,
                 "Text" => Type::Text,
                 "Unit" => Type::Unit,
@@ -2326,6 +3693,145 @@ impl TypeChecker {
         }
     }
 
+    /// Check a call of a built-in method against its signature, returning
+    /// the method's result type
+    /// Type of Duration arithmetic: Durations add and subtract, scale by an
+    /// Int, and divide into a whole count. Anything else, such as adding raw
+    /// milliseconds, is reported.
+    fn check_duration_arithmetic(
+        &mut self,
+        op: BinaryOp,
+        left: &Type,
+        right: &Type,
+        span: &Span,
+    ) -> Type {
+        let duration = duration_type();
+        let is = |ty: &Type, expected: &Type| ty == expected || *ty == Type::Unknown;
+        let result = match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mod
+                if is(left, &duration) && is(right, &duration) =>
+            {
+                Some(duration.clone())
+            }
+            BinaryOp::Mul if is(left, &duration) && is(right, &Type::Int) => Some(duration.clone()),
+            BinaryOp::Mul if is(left, &Type::Int) && is(right, &duration) => Some(duration.clone()),
+            BinaryOp::Div if is(left, &duration) && *right == duration => Some(Type::Int),
+            BinaryOp::Div if is(left, &duration) && is(right, &Type::Int) => Some(duration.clone()),
+            _ => None,
+        };
+        result.unwrap_or_else(|| {
+            self.diagnostics.push(
+                Diagnostic::error(crate::diagnostics::error_codes::types::TYPE_MISMATCH)
+                    .catalog("E1001.operator")
+.arg("op", op.as_str())
+.arg("left", format_type(left))
+.arg("right", format_type(right))
+                    .span(span.clone())
+                    .note(Note::new(
+                        "wrap raw milliseconds with `Duration.millis(n)`; durations scale and divide by Int",
+                    ))
+                    .build(),
+            );
+            Type::Unknown
+        })
+    }
+
+    /// Report an unknown field or method: suggest the most similar of
+    /// `available` as a rename of `name_span` when it is exactly the name,
+    /// or list them all
+    fn report_unknown_member(
+        &mut self,
+        diag: DiagnosticBuilder,
+        name: &str,
+        available: &[&str],
+        kind: &str,
+        name_span: &Span,
+        exact_span: bool,
+    ) {
+        let mut diag = diag.span(name_span.clone());
+        match closest_name(name, available.iter().copied()) {
+            Some(similar) => {
+                diag = diag.note(Note::new(format!("did you mean `{}`?", similar)));
+                if exact_span {
+                    diag = diag.suggestion(
+                        Suggestion::new(format!("Replace with `{}`", similar))
+                            .with_edit(Edit::new(name_span.clone(), similar))
+                            .with_applicability(Applicability::MachineApplicable),
+                    );
+                }
+            }
+            None if !available.is_empty() => {
+                let mut names: Vec<String> =
+                    available.iter().map(|name| format!("`{}`", name)).collect();
+                names.sort();
+                diag = diag.note(Note::new(format!(
+                    "available {}: {}",
+                    kind,
+                    names.join(", ")
+                )));
+            }
+            None => {}
+        }
+        self.diagnostics.push(diag.build());
+    }
+
+    fn check_method_args(
+        &mut self,
+        receiver: &str,
+        method: &str,
+        signature: &MethodSignature,
+        args: &[Expr],
+        arg_types: &[Type],
+        span: &Span,
+    ) -> Type {
+        if arg_types.len() < signature.required || arg_types.len() > signature.params.len() {
+            let expected = if signature.required == signature.params.len() {
+                signature.required.to_string()
+            } else {
+                format!("{} to {}", signature.required, signature.params.len())
+            };
+            self.diagnostics.push(
+                Diagnostic::error(crate::diagnostics::error_codes::types::WRONG_ARGUMENT_COUNT)
+                    .catalog("E1007.method")
+                    .arg("receiver", receiver)
+                    .arg("method", method)
+                    .arg("expected", expected)
+                    .arg("found", arg_types.len())
+                    .span(span.clone())
+                    .build(),
+            );
+        }
+        for (i, (param_ty, arg_ty)) in signature.params.iter().zip(arg_types.iter()).enumerate() {
+            if !self.types_compatible(arg_ty, param_ty) {
+                self.diagnostics.push(
+                    Diagnostic::error(crate::diagnostics::error_codes::types::TYPE_MISMATCH)
+                        .message(format!(
+                            "Type mismatch in argument {} of `{}.{}`: expected `{}`, found `{}`",
+                            i + 1,
+                            receiver,
+                            method,
+                            format_type(param_ty),
+                            format_type(arg_ty)
+                        ))
+                        .span(args[i].span().clone())
+                        .build(),
+                );
+            }
+        }
+        signature.ret.clone()
+    }
+
+    /// The type of the elements `for` and quantifiers iterate over
+    fn element_type(&self, iter_ty: &Type) -> Type {
+        match self.subst.apply(iter_ty) {
+            Type::List(elem) => *elem,
+            Type::Text => Type::Text,
+            Type::Named(name, mut args) if name == "Set" && args.len() == 1 => args.remove(0),
+            Type::Named(name, args) if name == "Map" && args.len() == 2 => Type::Tuple(args),
+            _ => Type::Unknown,
+        }
+    }
+
     fn types_compatible(&self, actual: &Type, expected: &Type) -> bool {
         // v1.1: Use the substitution to resolve type variables before comparing
         let actual = self.subst.apply(actual);
@@ -2512,10 +4018,12 @@ impl TypeChecker {
                 }
             }
             // Named type that matches a type param name
-            Type::Named(name, args) if type_params.contains(name) && args.is_empty() => {
-                if !bindings.contains_key(name) {
-                    bindings.insert(name.clone(), actual.clone());
-                }
+            Type::Named(name, args)
+                if type_params.contains(name)
+                    && args.is_empty()
+                    && !bindings.contains_key(name) =>
+            {
+                bindings.insert(name.clone(), actual.clone());
             }
             Type::List(inner) => {
                 if let Type::List(actual_inner) = actual {
@@ -2602,11 +4110,486 @@ fn collect_pattern_bindings(pattern: &Pattern, env: &mut TypeEnv) {
     }
 }
 
+/// Whether a type is fully known (no unknowns, type variables, or type
+/// parameters)
+fn is_concrete(ty: &Type) -> bool {
+    match ty {
+        Type::Unknown | Type::Var(_) | Type::TypeParam(_) => false,
+        Type::Function { params, ret, .. } => params.iter().all(is_concrete) && is_concrete(ret),
+        Type::Record(fields) => fields.iter().all(|(_, ty)| is_concrete(ty)),
+        Type::Named(_, args) | Type::Tuple(args) => args.iter().all(is_concrete),
+        Type::Option(inner) | Type::List(inner) => is_concrete(inner),
+        Type::Result(ok, err) => is_concrete(ok) && is_concrete(err),
+        Type::Unit
+        | Type::Int
+        | Type::Float
+        | Type::Decimal
+        | Type::Bool
+        | Type::Text
+        | Type::Json => true,
+    }
+}
+
+/// Parameter and return types of a built-in method; the trailing
+/// `params.len() - required` parameters are optional
+struct MethodSignature {
+    params: Vec<Type>,
+    required: usize,
+    ret: Type,
+}
+
+/// Signature of a built-in `Text` method, if the checker knows it
+fn text_method_signature(method: &str) -> Option<MethodSignature> {
+    use Type::{Bool, Int, Text};
+    let list_of_text = || Type::List(Box::new(Text));
+    let (params, ret) = match method {
+        "len" | "char_len" | "byte_len" => (vec![], Int),
+        "count" => (vec![Text], Int),
+        "to_upper" | "to_lower" | "trim" | "trim_start" | "trim_end" => (vec![], Text),
+        "contains" | "starts_with" | "ends_with" | "equals_ignore_case" => (vec![Text], Bool),
+        "split" => (vec![Text], list_of_text()),
+        "replace" => (vec![Text, Text], Text),
+        "chars" | "lines" | "split_whitespace" => (vec![], list_of_text()),
+        "repeat" => (vec![Int], Text),
+        "index_of" => (vec![Text], Type::Option(Box::new(Int))),
+        "slice" | "substring" => (vec![Int, Int], Text),
+        "char_at" => (vec![Int], Type::Option(Box::new(Text))),
+        "to_bytes" => (vec![], bytes_type()),
+        "pad_start" | "pad_end" => {
+            return Some(MethodSignature {
+                params: vec![Int, Text],
+                required: 1,
+                ret: Text,
+            })
+        }
+        _ => return None,
+    };
+    Some(MethodSignature {
+        required: params.len(),
+        params,
+        ret,
+    })
+}
+
+/// Signature of a built-in `Float` method, if the checker knows it
+fn float_method_signature(method: &str) -> Option<MethodSignature> {
+    let (params, ret) = match method {
+        "is_nan" | "is_infinite" | "is_finite" => (vec![], Type::Bool),
+        "to_fixed" | "to_precision" => (vec![Type::Int], Type::Text),
+        _ => return None,
+    };
+    Some(MethodSignature {
+        required: params.len(),
+        params,
+        ret,
+    })
+}
+
+/// Signature of a built-in `Decimal` method, if the checker knows it
+fn decimal_method_signature(method: &str) -> Option<MethodSignature> {
+    let (params, ret) = match method {
+        "abs" => (vec![], Type::Decimal),
+        "scale" => (vec![], Type::Int),
+        "to_float" => (vec![], Type::Float),
+        "round" => (vec![Type::Int], Type::Decimal),
+        _ => return None,
+    };
+    Some(MethodSignature {
+        required: params.len(),
+        params,
+        ret,
+    })
+}
+
+fn bytes_type() -> Type {
+    Type::Named("Bytes".to_string(), vec![])
+}
+
+/// The `{code, message, index}` record in the Err of `base64_decode` and `hex_decode`
+fn encoding_error_type() -> Type {
+    Type::Record(vec![
+        ("code".to_string(), Type::Text),
+        ("index".to_string(), Type::Int),
+        ("message".to_string(), Type::Text),
+    ])
+}
+
+/// Signature of a built-in `Bytes` method, if the checker knows it
+fn bytes_method_signature(method: &str) -> Option<MethodSignature> {
+    let ret = match method {
+        "len" => Type::Int,
+        "is_empty" => Type::Bool,
+        "to_list" => Type::List(Box::new(Type::Int)),
+        "to_text" => Type::Option(Box::new(Type::Text)),
+        _ => return None,
+    };
+    Some(MethodSignature {
+        params: vec![],
+        required: 0,
+        ret,
+    })
+}
+
+/// What one JSON-RPC call gives: the result, or the `{code, message, data}` error
+fn rpc_result_type() -> Type {
+    Type::Result(
+        Box::new(Type::Json),
+        Box::new(Type::Record(vec![
+            ("code".to_string(), Type::Int),
+            ("data".to_string(), Type::Json),
+            ("message".to_string(), Type::Text),
+        ])),
+    )
+}
+
+fn websocket_type() -> Type {
+    Type::Named("WebSocket".to_string(), vec![])
+}
+
+/// Signature of a method on a connection from `Net.ws_connect`. Every call
+/// can fail once the connection drops, so each returns a Result.
+fn websocket_method_signature(method: &str) -> Option<MethodSignature> {
+    let (params, required, ok) = match method {
+        "send" => (vec![Type::Text], 1, Type::Unit),
+        "recv" => (vec![duration_type()], 0, Type::Option(Box::new(Type::Text))),
+        "close" => (vec![], 0, Type::Unit),
+        _ => return None,
+    };
+    Some(MethodSignature {
+        params,
+        required,
+        ret: Type::Result(Box::new(ok), Box::new(Type::Text)),
+    })
+}
+
+fn ref_type(contents: Type) -> Type {
+    Type::Named("Ref".to_string(), vec![contents])
+}
+
+/// Signature of a method on a `Ref[T]` cell
+fn ref_method_signature(method: &str, contents: Type) -> Option<MethodSignature> {
+    let (params, ret) = match method {
+        "get" => (vec![], contents),
+        "set" => (vec![contents], Type::Unit),
+        _ => return None,
+    };
+    Some(MethodSignature {
+        required: params.len(),
+        params,
+        ret,
+    })
+}
+
+/// Whether a type is or contains a `Ref`
+fn mentions_ref(ty: &Type) -> bool {
+    match ty {
+        Type::Named(name, args) => name == "Ref" || args.iter().any(mentions_ref),
+        Type::Option(inner) | Type::List(inner) => mentions_ref(inner),
+        Type::Result(ok, err) => mentions_ref(ok) || mentions_ref(err),
+        Type::Tuple(items) => items.iter().any(mentions_ref),
+        Type::Record(fields) => fields.iter().any(|(_, ty)| mentions_ref(ty)),
+        Type::Function { .. } => false,
+        Type::Unit
+        | Type::Int
+        | Type::Float
+        | Type::Decimal
+        | Type::Bool
+        | Type::Text
+        | Type::Var(_)
+        | Type::TypeParam(_)
+        | Type::Json
+        | Type::Unknown => false,
+    }
+}
+
+fn duration_type() -> Type {
+    Type::Named("Duration".to_string(), vec![])
+}
+
+fn deadline_type() -> Type {
+    Type::Named("Deadline".to_string(), vec![])
+}
+
+/// Signature of a `Duration.seconds(n)`-style constructor
+fn duration_constructor_signature(method: &str) -> Option<MethodSignature> {
+    match method {
+        "millis" | "seconds" | "minutes" | "hours" | "days" => Some(MethodSignature {
+            params: vec![Type::Int],
+            required: 1,
+            ret: duration_type(),
+        }),
+        _ => None,
+    }
+}
+
+/// Signature of an `Args` method. A spec made by `Args.flag` and the others
+/// is an `ArgSpec[T]`, and `Args.parse` turns a record of them into a record
+/// of the `T`s.
+fn args_method_signature(method: &str, arg_types: &[Type]) -> Option<MethodSignature> {
+    let spec = |ty: Type| Type::Named("ArgSpec".to_string(), vec![ty]);
+    let (params, ret) = match method {
+        "flag" => (vec![Type::Text], spec(Type::Bool)),
+        "positional" => (vec![Type::Text], spec(Type::Text)),
+        "rest" => (vec![Type::Text], spec(Type::List(Box::new(Type::Text)))),
+        "option" => (
+            vec![Type::Unknown, Type::Text],
+            spec(arg_types.first().cloned().unwrap_or(Type::Unknown)),
+        ),
+        "parse" => {
+            let parsed = match arg_types.first() {
+                Some(Type::Record(fields)) => Type::Record(
+                    fields
+                        .iter()
+                        .map(|(name, ty)| {
+                            let ty = match ty {
+                                Type::Named(spec, args) if spec == "ArgSpec" => {
+                                    args.first().cloned().unwrap_or(Type::Unknown)
+                                }
+                                _ => Type::Unknown,
+                            };
+                            (name.clone(), ty)
+                        })
+                        .collect(),
+                ),
+                _ => Type::Unknown,
+            };
+            let error = Type::Record(vec![
+                ("message".to_string(), Type::Text),
+                ("code".to_string(), Type::Int),
+            ]);
+            return Some(MethodSignature {
+                params: vec![Type::Unknown, Type::Text],
+                required: 1,
+                ret: Type::Result(Box::new(parsed), Box::new(error)),
+            });
+        }
+        _ => return None,
+    };
+    Some(MethodSignature {
+        required: params.len(),
+        params,
+        ret,
+    })
+}
+
+/// Signature of a built-in `Duration` method, if the checker knows it
+fn duration_method_signature(method: &str) -> Option<MethodSignature> {
+    let ret = match method {
+        "as_millis" | "as_seconds" | "as_minutes" | "as_hours" | "as_days" => Type::Int,
+        "abs" => duration_type(),
+        _ => return None,
+    };
+    Some(MethodSignature {
+        params: vec![],
+        required: 0,
+        ret,
+    })
+}
+
+/// Signature of a `Clock` method that works with durations and deadlines.
+/// `sleep` still takes a bare Int of milliseconds, so it is left unchecked.
+fn clock_method_signature(method: &str) -> Option<MethodSignature> {
+    let (params, ret) = match method {
+        "deadline" => (vec![duration_type()], deadline_type()),
+        "remaining" => (vec![deadline_type()], duration_type()),
+        "expired" => (vec![deadline_type()], Type::Bool),
+        _ => return None,
+    };
+    Some(MethodSignature {
+        required: params.len(),
+        params,
+        ret,
+    })
+}
+
+/// Signature of a built-in `Fs` method, including the attenuations that
+/// narrow a capability value
+fn fs_method_signature(method: &str) -> Option<MethodSignature> {
+    let fs = Type::Named("Fs".to_string(), vec![]);
+    let failable = |ok: Type| Type::Result(Box::new(ok), Box::new(Type::Text));
+    let (params, ret) = match method {
+        "read" => (vec![Type::Text], failable(Type::Text)),
+        "write" => (vec![Type::Text, Type::Text], failable(Type::Unit)),
+        "exists" => (vec![Type::Text], Type::Bool),
+        "read_only" => (vec![], fs),
+        "scoped" => (vec![Type::Text], fs),
+        _ => return None,
+    };
+    Some(MethodSignature {
+        required: params.len(),
+        params,
+        ret,
+    })
+}
+
+/// Signature of a built-in `Rand` method, if the checker knows it
+fn rand_method_signature(method: &str) -> Option<MethodSignature> {
+    let (params, ret) = match method {
+        "int" => (vec![Type::Int, Type::Int], Type::Int),
+        "bool" => (vec![], Type::Bool),
+        "float" => (vec![], Type::Float),
+        "uuid" => (vec![], Type::Text),
+        "hex" => (vec![Type::Int], Type::Text),
+        _ => return None,
+    };
+    Some(MethodSignature {
+        required: params.len(),
+        params,
+        ret,
+    })
+}
+
+/// Signature of a `Crypto` method. Data may be Text or Bytes, so it is left
+/// unchecked; keys and signatures are Bytes.
+fn crypto_method_signature(method: &str) -> Option<MethodSignature> {
+    let bytes_result = || Type::Result(Box::new(bytes_type()), Box::new(Type::Text));
+    let (params, ret) = match method {
+        "hmac_sha256" => (vec![Type::Unknown, Type::Unknown], bytes_type()),
+        "public_key" => (vec![bytes_type()], bytes_result()),
+        "sign" => (vec![bytes_type(), Type::Unknown], bytes_result()),
+        "verify" => (vec![bytes_type(), Type::Unknown, bytes_type()], Type::Bool),
+        "key_from_env" | "key_from_file" => (vec![Type::Text, Type::Text], bytes_result()),
+        _ => return None,
+    };
+    Some(MethodSignature {
+        required: params.len(),
+        params,
+        ret,
+    })
+}
+
+/// Signature of a `Serve` method. Handlers take a request record and return
+/// a response record whose fields are optional, so they are left unchecked.
+fn serve_method_signature(method: &str) -> Option<MethodSignature> {
+    let text_map = || Type::Named("Map".to_string(), vec![Type::Text, Type::Text]);
+    match method {
+        "route" => Some(MethodSignature {
+            params: vec![Type::Text, Type::Text, Type::Unknown],
+            required: 3,
+            ret: Type::Unit,
+        }),
+        "request" => Some(MethodSignature {
+            params: vec![Type::Text, Type::Text, Type::Text, text_map()],
+            required: 3,
+            ret: Type::Record(vec![
+                ("body".to_string(), Type::Text),
+                ("headers".to_string(), text_map()),
+                ("status".to_string(), Type::Int),
+            ]),
+        }),
+        _ => None,
+    }
+}
+
+/// Signature of a `Db` method. Parameters may mix types, and the columns of
+/// a row are only known at runtime, so rows are `Json` records.
+fn db_method_signature(method: &str) -> Option<MethodSignature> {
+    let ret = match method {
+        "query" => Type::List(Box::new(Type::Json)),
+        "execute" => Type::Int,
+        _ => return None,
+    };
+    Some(MethodSignature {
+        params: vec![Type::Text, Type::List(Box::new(Type::Unknown))],
+        required: 1,
+        ret: Type::Result(Box::new(ret), Box::new(Type::Text)),
+    })
+}
+
+/// Signature of a `Progress` method
+fn progress_method_signature(method: &str) -> Option<MethodSignature> {
+    let params = match method {
+        "start" => vec![Type::Text, Type::Int],
+        "advance" => vec![Type::Int],
+        "finish" => vec![],
+        _ => return None,
+    };
+    Some(MethodSignature {
+        required: params.len(),
+        params,
+        ret: Type::Unit,
+    })
+}
+
+/// Signature of a `Metrics` method. A gauge takes an Int or a Float.
+fn metrics_method_signature(method: &str) -> Option<MethodSignature> {
+    let map = |value: Type| Type::Named("Map".to_string(), vec![Type::Text, value]);
+    let (params, ret) = match method {
+        "counter" => (vec![Type::Text, Type::Int], Type::Unit),
+        "gauge" => (vec![Type::Text, Type::Unknown], Type::Unit),
+        "timing" => (vec![Type::Text, duration_type()], Type::Unit),
+        "snapshot" => (
+            vec![],
+            Type::Record(vec![
+                ("counters".to_string(), map(Type::Int)),
+                ("gauges".to_string(), map(Type::Float)),
+                (
+                    "timings".to_string(),
+                    map(Type::Record(vec![
+                        ("samples".to_string(), Type::Int),
+                        ("total".to_string(), duration_type()),
+                        ("min".to_string(), duration_type()),
+                        ("max".to_string(), duration_type()),
+                    ])),
+                ),
+            ]),
+        ),
+        _ => return None,
+    };
+    Some(MethodSignature {
+        required: params.len(),
+        params,
+        ret,
+    })
+}
+
 /// Check if a pattern is a catch-all (wildcard or plain identifier binding)
 fn is_catch_all(pattern: &Pattern) -> bool {
     matches!(pattern, Pattern::Wildcard { .. } | Pattern::Ident { .. })
 }
 
+/// An edit inserting a `pattern => ???` stub arm for each missing pattern
+/// before the closing brace of a match, indented like the existing arms
+fn missing_arms_edit(arms: &[MatchArm], match_span: &Span, missing: &[String]) -> Option<Edit> {
+    if match_span.end <= match_span.start || match_span.end_col < 2 {
+        return None;
+    }
+    let stubs: Vec<String> = missing.iter().map(|m| format!("{} => ???", m)).collect();
+    let brace = Span::new(
+        match_span.file.clone(),
+        match_span.end - 1,
+        match_span.end - 1,
+        match_span.end_line,
+        match_span.end_col - 1,
+        match_span.end_line,
+        match_span.end_col - 1,
+    );
+    let last_line = arms
+        .last()
+        .map_or(match_span.start_line, |arm| arm.span.end_line);
+
+    if last_line == match_span.end_line {
+        // Single-line match: `match x { A => 1 }`
+        return Some(Edit::new(brace, format!("{} ", stubs.join(" "))));
+    }
+    // The closing brace starts its own line
+    let brace_indent = match_span.end_col - 2;
+    let arm_indent = arms
+        .first()
+        .filter(|arm| arm.span.start_line > match_span.start_line)
+        .map_or(brace_indent + 2, |arm| arm.span.start_col - 1);
+    let separator = format!("\n{}", " ".repeat(arm_indent));
+    Some(Edit::new(
+        brace,
+        format!(
+            "{}{}\n{}",
+            " ".repeat(arm_indent.saturating_sub(brace_indent)),
+            stubs.join(&separator),
+            " ".repeat(brace_indent)
+        ),
+    ))
+}
+
 /// Check exhaustiveness of pattern matching (public API)
 pub fn check_exhaustiveness(
     scrutinee_type: &Type,
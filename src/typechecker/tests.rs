@@ -243,6 +243,68 @@ Some(n) => n
     );
 }
 
+/// Apply the edits of the first suggestion of the first diagnostic
+fn apply_first_suggestion(source: &str) -> String {
+    let diags = check_module(source).unwrap_err();
+    let suggestion = &diags.diagnostics()[0].suggestions[0];
+    assert!(suggestion.is_machine_applicable());
+    let mut fixed = source.to_string();
+    for edit in suggestion.edits.iter().rev() {
+        fixed.replace_range(edit.span.start..edit.span.end, &edit.replacement);
+    }
+    fixed
+}
+
+/// Assert that `astra fmt --check` would accept `source`
+fn assert_formatted(source: &str) {
+    let formatted = crate::formatter::emit(&parse_module(source));
+    assert_eq!(formatted.trim_start(), source.trim_start());
+}
+
+#[test]
+fn test_non_exhaustive_inserts_missing_arms() {
+    let source = r#"
+module example
+
+enum Shape =
+  | Circle(r: Float)
+  | Rect(w: Float, h: Float)
+  | Empty
+
+fn area(s: Shape) -> Float {
+  match s {
+    Circle(r) => r * r
+  }
+}
+"#;
+    let fixed = apply_first_suggestion(source);
+    assert!(
+        fixed.contains("    Circle(r) => r * r\n    Rect(_, _) => ???\n    Empty => ???\n  }\n}"),
+        "{}",
+        fixed
+    );
+    // The stubs leave only typed holes behind
+    assert!(check_module(&fixed).is_ok());
+}
+
+#[test]
+fn test_non_exhaustive_inserts_arms_in_single_line_match() {
+    let source = r#"
+module example
+
+fn main() -> Int {
+  let x: Option[Int] = Some(42)
+  match x { Some(n) => n }
+}
+"#;
+    let fixed = apply_first_suggestion(source);
+    assert!(
+        fixed.contains("match x { Some(n) => n None => ??? }"),
+        "{}",
+        fixed
+    );
+}
+
 // C4: Effect enforcement tests
 
 #[test]
@@ -275,6 +337,47 @@ fn greet() {
     assert!(d.message.contains("Console"));
 }
 
+#[test]
+fn test_effect_not_declared_inserts_clause() {
+    let source = r#"
+module example
+
+fn greet() -> Unit {
+  Console.println("hello")
+}
+"#;
+    let fixed = apply_first_suggestion(source);
+    assert!(
+        fixed.contains("fn greet() -> Unit\n  effects(Console)\n{"),
+        "{}",
+        fixed
+    );
+    assert!(check_module(&fixed).is_ok());
+    assert_formatted(&fixed);
+}
+
+#[test]
+fn test_effect_not_declared_extends_clause() {
+    let source = r#"
+module example
+
+fn load()
+  effects(Fs)
+{
+  Console.println("loading")
+  Fs.read("data.txt")
+}
+"#;
+    let fixed = apply_first_suggestion(source);
+    assert!(
+        fixed.contains("fn load()\n  effects(Console, Fs)\n{"),
+        "{}",
+        fixed
+    );
+    assert!(check_module(&fixed).is_ok());
+    assert_formatted(&fixed);
+}
+
 #[test]
 fn test_effect_enforcement_multiple_effects() {
     let source = r#"
@@ -311,6 +414,42 @@ fn do_stuff() effects(Console) {
     );
 }
 
+#[test]
+fn test_crypto_key_loading_needs_source_effect() {
+    let source = r#"
+module example
+
+fn sign(body: Text) -> Result[Bytes, Text] effects(Crypto) {
+  let key = Crypto.key_from_file("key.txt", "base64")?
+  Crypto.sign(key, body)
+}
+"#;
+    let diags = check_module(source).unwrap_err();
+    let missing: Vec<&str> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.code == "E2001")
+        .map(|d| d.message.as_str())
+        .collect();
+    assert_eq!(
+        missing,
+        vec!["Effect `Fs` used but not declared in function `sign`"]
+    );
+
+    let source = r#"
+module example
+
+fn check(key: Text, body: Text, signature: Bytes) -> Bool effects(Crypto) {
+  Crypto.verify(key, body, signature)
+}
+"#;
+    let diags = check_module(source).unwrap_err();
+    assert!(diags
+        .diagnostics()
+        .iter()
+        .any(|d| d.code == "E1001" && d.message.contains("Bytes")));
+}
+
 #[test]
 fn test_pure_function_no_effects() {
     let source = r#"
@@ -857,6 +996,280 @@ trait Show {
     assert!(result.is_ok(), "Trait definition should type-check");
 }
 
+#[test]
+fn test_quantifier_binds_elements_and_needs_bool() {
+    let source = r#"
+module example
+
+fn f(xs: List[Int]) -> Int
+  requires forall x in xs: x > 0
+{
+  let any_big: Bool = exists x in xs: x > 100
+  if any_big then 1 else 0
+}
+"#;
+    assert!(check_module(source).is_ok());
+
+    let source = r#"
+module example
+
+fn f(names: List[Text]) -> Bool {
+  forall n in names: n.len()
+}
+"#;
+    let diags = check_module_all_diags(source);
+    assert!(
+        diags
+            .diagnostics()
+            .iter()
+            .any(|d| d.message.contains("`forall` condition must be Bool")),
+        "got {:?}",
+        diags.diagnostics()
+    );
+}
+
+#[test]
+fn test_trait_method_contract_is_checked_in_impls() {
+    let source = r#"
+module example
+
+trait Sized {
+  fn take(self: Self, n: Int) -> Int
+    requires n
+}
+
+impl Sized for Int {
+  fn take(self: Int, count: Int) -> Int {
+    count
+  }
+}
+"#;
+    let diags = check_module_all_diags(source);
+    assert!(
+        diags.diagnostics().iter().any(|d| d.code == "E3004"),
+        "expected E3004, got {:?}",
+        diags.diagnostics()
+    );
+
+    let source = r#"
+module example
+
+trait Sized {
+  fn take(self: Self, n: Int) -> Int
+    requires n >= 0
+}
+
+impl Sized for Int {
+  fn take(self: Int) -> Int {
+    self
+  }
+}
+"#;
+    let diags = check_module_all_diags(source);
+    assert!(
+        diags.diagnostics().iter().any(|d| d.code == "E3005"),
+        "expected E3005, got {:?}",
+        diags.diagnostics()
+    );
+}
+
+#[test]
+fn test_capability_parameters() {
+    // Using a capability handed in needs no effect of its own
+    let source = r#"
+module example
+
+fn backup(fs: Fs, path: Text) -> Bool effects() {
+  fs.read_only().read(path).is_ok() and fs.scoped("/data").exists(path)
+}
+
+fn main() -> Bool effects(Fs) {
+  backup(Fs, "notes.txt")
+}
+"#;
+    assert!(check_module(source).is_ok());
+
+    // Handing out the host's capability needs its effect
+    let source = r#"
+module example
+
+fn backup(fs: Fs) -> Bool {
+  fs.exists("notes.txt")
+}
+
+fn main() -> Bool {
+  backup(Fs)
+}
+"#;
+    let diags = check_module_all_diags(source);
+    assert!(
+        diags
+            .diagnostics()
+            .iter()
+            .any(|d| d.code == "E2001" && d.message.contains("Fs")),
+        "expected E2001, got {:?}",
+        diags.diagnostics()
+    );
+
+    // A capability stands in only for its own effect
+    let source = r#"
+module example
+
+fn backup(fs: Fs) -> Bool {
+  fs.exists(1)
+}
+
+fn main() -> Bool effects(Console) {
+  backup(Console)
+}
+"#;
+    let diags = check_module_all_diags(source);
+    assert_eq!(
+        diags
+            .diagnostics()
+            .iter()
+            .filter(|d| d.code == "E1001")
+            .count(),
+        2,
+        "{:?}",
+        diags.diagnostics()
+    );
+}
+
+#[test]
+fn test_args_parse_record_shape() {
+    let source = r#"
+module example
+
+fn main() -> Int effects(Env) {
+  let spec = { file = Args.positional("Input"), times = Args.option(1, "Times") }
+  match Args.parse(spec) {
+    Ok(args) => args.times + args.file.len()
+    Err(e) => e.code
+  }
+}
+"#;
+    assert!(check_module(source).is_ok());
+
+    // The parsed fields have the types of their specs
+    let source = r#"
+module example
+
+fn main() -> Unit effects(Env) {
+  let _parsed: Result[{ times: Text }, { message: Text, code: Int }] =
+    Args.parse({ times = Args.option(1, "Times") })
+}
+"#;
+    let diags = check_module_all_diags(source);
+    assert!(
+        diags.diagnostics().iter().any(|d| d.code == "E1001"),
+        "{:?}",
+        diags.diagnostics()
+    );
+
+    // Parsing reads Env
+    let source = r#"
+module example
+
+fn main() -> Bool {
+  Args.parse({ verbose = Args.flag("More output") }).is_ok()
+}
+"#;
+    let diags = check_module_all_diags(source);
+    assert!(
+        diags
+            .diagnostics()
+            .iter()
+            .any(|d| d.code == "E2001" && d.message.contains("Env")),
+        "{:?}",
+        diags.diagnostics()
+    );
+}
+
+fn precondition_violations(body: &str) -> Vec<String> {
+    let source = format!(
+        r#"
+module example
+
+fn divide(a: Int, b: Int) -> Int
+  requires b != 0
+{{
+  a / b
+}}
+
+fn take(xs: List[Int], n: Int) -> List[Int]
+  requires n >= 0 and n <= 10
+{{
+  xs
+}}
+
+fn unwrap_or_zero(x: Option[Int]) -> Int
+  requires x.is_some()
+{{
+  match x {{
+    Some(v) => v
+    None => 0
+  }}
+}}
+
+{}
+"#,
+        body
+    );
+    check_module_all_diags(&source)
+        .diagnostics()
+        .iter()
+        .filter(|d| d.code == "E3006")
+        .map(|d| d.message.clone())
+        .collect()
+}
+
+#[test]
+fn test_calls_that_always_violate_a_precondition() {
+    let violations = precondition_violations("fn main() -> Int {\n  divide(10, 0)\n}");
+    assert_eq!(
+        violations,
+        vec!["This call to `divide` always violates its precondition `b != 0`"]
+    );
+
+    let violations = precondition_violations(
+        "fn main() -> Int {\n  let n = 5 - 6\n  let xs = take([1], n)\n  xs.len() + take([1], 4 * 3).len()\n}",
+    );
+    assert_eq!(violations.len(), 2, "got {:?}", violations);
+
+    let violations = precondition_violations("fn main() -> Int {\n  unwrap_or_zero(None)\n}");
+    assert_eq!(violations.len(), 1, "got {:?}", violations);
+
+    // Inside `if b == 0`, `b` is known to be 0
+    let violations = precondition_violations(
+        "fn f(a: Int, b: Int) -> Int {\n  if b == 0 { divide(a, b) } else { 1 }\n}",
+    );
+    assert_eq!(violations.len(), 1, "got {:?}", violations);
+}
+
+#[test]
+fn test_preconditions_proved_or_unknown_are_not_flagged() {
+    let bodies = [
+        // Checked by the caller first
+        "fn f(a: Int, b: Int) -> Int {\n  if b != 0 { divide(a, b) } else { 0 }\n}",
+        "fn f(a: Int, b: Int) -> Int {\n  if b == 0 {\n    return 0\n  }\n  divide(a, b)\n}",
+        "fn f(a: Int, b: Int) -> Bool {\n  b != 0 and divide(a, b) > 1\n}",
+        // Guaranteed by the caller's own precondition
+        "fn f(a: Int, b: Int) -> Int\n  requires b > 0\n{\n  divide(a, b)\n}",
+        // Nothing is known about `b`
+        "fn f(a: Int, b: Int) -> Int {\n  divide(a, b)\n}",
+        // A `let mut` binding may have changed
+        "fn f() -> Int {\n  let mut b = 0\n  b = 2\n  divide(4, b)\n}",
+        "fn f(x: Option[Int]) -> Int {\n  if x.is_some() { unwrap_or_zero(x) } else { 0 }\n}",
+        // A local `divide` shadows the function
+        "fn f(divide: (Int, Int) -> Int) -> Int {\n  divide(1, 0)\n}",
+    ];
+    for body in bodies {
+        let violations = precondition_violations(body);
+        assert!(violations.is_empty(), "{}: {:?}", body, violations);
+    }
+}
+
 #[test]
 fn test_impl_block_methods_checked() {
     let source = r#"
@@ -983,6 +1396,38 @@ fn calculate(value: Int) -> Int {
     );
 }
 
+#[test]
+fn test_suggestion_applicability() {
+    let source = r#"
+module example
+
+import std.math
+
+fn calculate(value: Int) -> Int {
+  valu + 1
+}
+"#;
+    let diags = check_module_all_diags(source);
+    let import = diags
+        .diagnostics()
+        .iter()
+        .find(|d| d.code == "W0002")
+        .expect("should warn about unused import");
+    assert_eq!(
+        import.suggestions[0].applicability,
+        Applicability::MachineApplicable
+    );
+    let unknown = diags
+        .diagnostics()
+        .iter()
+        .find(|d| d.code == "E1002")
+        .expect("should report unknown identifier");
+    assert_eq!(
+        unknown.suggestions[0].applicability,
+        Applicability::MaybeIncorrect
+    );
+}
+
 #[test]
 fn test_trait_constraint_satisfied() {
     // Should pass: Int implements Show
@@ -1563,3 +2008,1063 @@ fn extract_typechecker_builtins(src: &str) -> Vec<String> {
     }
     names
 }
+
+// @allow(...) suppression tests
+
+#[test]
+fn test_allow_attribute_suppresses_lint() {
+    let source = r#"
+module example
+
+@allow(unused_function)
+fn unused_helper() -> Int {
+  @allow(unused_variable)
+  let x = 1
+  42
+}
+
+fn main() -> Int {
+  0
+}
+"#;
+    let diags = check_module_all_diags(source);
+    assert!(
+        diags.diagnostics().is_empty(),
+        "lints should be suppressed: {:?}",
+        diags.diagnostics()
+    );
+}
+
+#[test]
+fn test_allow_attribute_only_covers_its_scope() {
+    let source = r#"
+module example
+
+fn main() -> Int {
+  @allow(unused_variable)
+  let x = 1
+  let y = 2
+  0
+}
+"#;
+    let diags = check_module_all_diags(source);
+    let warnings: Vec<_> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.code == "W0001")
+        .collect();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].message.contains("`y`"));
+}
+
+#[test]
+fn test_unused_suppression_warns() {
+    let source = r#"
+module example
+
+fn main() -> Int {
+  @allow(unused_variable)
+  let x = 1
+  @allow(no_such_lint)
+  let y = 2
+  x + y
+}
+"#;
+    let diags = check_module_all_diags(source);
+    let warnings: Vec<_> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.code == "W0009")
+        .collect();
+    assert_eq!(warnings.len(), 2, "{:?}", warnings);
+    assert!(warnings[0].message.contains("Unused suppression"));
+    assert!(warnings[1].message.contains("Unknown lint `no_such_lint`"));
+}
+
+#[test]
+fn test_dbg_call_warns() {
+    let source = r#"
+module example
+
+fn main() -> Int {
+  let x: Int = dbg(1 + 2)
+  @allow(dbg_call)
+  let y = dbg(x)
+  y
+}
+"#;
+    let diags = check_module_all_diags(source);
+    let codes: Vec<&str> = diags
+        .diagnostics()
+        .iter()
+        .map(|d| d.code.as_str())
+        .collect();
+    assert_eq!(codes, vec!["W0010"], "{:?}", diags);
+}
+
+#[test]
+fn test_assigning_immutable_binding_is_rejected() {
+    let source = r#"
+module example
+
+fn main(limit: Int) -> Int {
+  let total = 0
+  let mut count = 0
+  let add = fn(x: Int) {
+    let mut local = x
+    local = local + 1
+    total = total + local
+    count = count + 1
+  }
+  add(1)
+  limit = 3
+  total + count
+}
+"#;
+    let diags = check_module_all_diags(source);
+    let errors: Vec<&Diagnostic> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.code == "E1017")
+        .collect();
+    assert_eq!(errors.len(), 2, "{:?}", diags);
+    assert!(errors[0].message.contains("`total`"));
+    assert_eq!(errors[0].span.start_line, 10);
+    // The fix turns `let total` into `let mut total`
+    let edit = &errors[0].suggestions[0].edits[0];
+    assert_eq!(edit.replacement, "let mut");
+    assert_eq!(&source[edit.span.start..edit.span.end], "let");
+    assert_eq!(edit.span.start_line, 5);
+    // Parameters have no `let` to fix
+    assert!(errors[1].message.contains("`limit`"));
+    assert!(errors[1].suggestions.is_empty());
+}
+
+#[test]
+fn test_unknown_field_suggests_similar_field() {
+    let source = r#"
+module example
+
+fn main() -> Int {
+  let point = { x = 1, total = 2 }
+  point.totl + point.zzz
+}
+"#;
+    let diags = check_module_all_diags(source);
+    let errors: Vec<&Diagnostic> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.code == "E1006")
+        .collect();
+    assert_eq!(errors.len(), 2, "{:?}", diags);
+    let suggestion = &errors[0].suggestions[0];
+    assert!(suggestion.is_machine_applicable());
+    let edit = &suggestion.edits[0];
+    assert_eq!(edit.replacement, "total");
+    assert_eq!(&source[edit.span.start..edit.span.end], "totl");
+    // Nothing close: list what there is instead
+    assert!(errors[1].suggestions.is_empty());
+    assert_eq!(errors[1].notes[0].message, "available fields: `total`, `x`");
+}
+
+#[test]
+fn test_unknown_effect_method_suggests_similar_method() {
+    let source = r#"
+module example
+
+fn main() effects(Console, Clock) {
+  Console.printline("hi")
+  Console.printn("hi")
+  let _now = Clock.now()
+}
+"#;
+    let diags = check_module_all_diags(source);
+    let errors: Vec<&Diagnostic> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.code == "E1018")
+        .collect();
+    assert_eq!(errors.len(), 2, "{:?}", diags);
+    assert_eq!(errors[0].message, "Unknown method `printline` on `Console`");
+    let edit = &errors[0].suggestions[0].edits[0];
+    assert_eq!(edit.replacement, "println");
+    assert_eq!(&source[edit.span.start..edit.span.end], "printline");
+    // `print` and `println` are equally close, so neither is applied
+    assert!(errors[1].suggestions.is_empty());
+    assert!(errors[1].notes[0].message.contains("`print`, `println`"));
+}
+
+#[test]
+fn test_ref_types() {
+    assert!(check_module(
+        "module example\n\nfn count(items: List[Int]) -> Int {\n  let total: Ref[Int] = Ref.new(0)\n  for item in items {\n    total.set(total.get() + item)\n  }\n  total.get()\n}\n"
+    )
+    .is_ok());
+    let diags =
+        check_module("module example\n\nfn f() {\n  let r = Ref.new(1)\n  r.set(\"one\")\n}\n")
+            .unwrap_err();
+    assert!(diags.diagnostics().iter().any(|d| d.code == "E1001"));
+    let diags = check_module(
+        "module example\n\nfn f() {\n  let r = Ref.new(1)\n  let t: Text = r.get()\n}\n",
+    )
+    .unwrap_err();
+    assert!(diags.diagnostics().iter().any(|d| d.code == "E1001"));
+}
+
+#[test]
+fn test_ref_escaping_pure_function_warns() {
+    let source = r#"
+module example
+
+fn fresh() -> Option[Ref[Int]] {
+  Some(Ref.new(0))
+}
+
+fn counter() -> Ref[Int] effects(Clock) {
+  Ref.new(Clock.now())
+}
+
+fn main() -> Int effects(Clock) {
+  let a = fresh()
+  counter().get()
+}
+"#;
+    let diags = check_module_all_diags(source);
+    let warnings: Vec<&Diagnostic> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.code == "W0012")
+        .collect();
+    assert_eq!(warnings.len(), 1, "{:?}", diags);
+    assert!(warnings[0].message.contains("`fresh`"));
+}
+
+// Secondary labels
+
+#[test]
+fn test_shadowed_binding_labels_previous_binding() {
+    let source = r#"
+module example
+
+fn main() -> Int {
+  let x = 1
+  let x = 2
+  x
+}
+"#;
+    let diags = check_module_all_diags(source);
+    let shadow = diags
+        .diagnostics()
+        .iter()
+        .find(|d| d.code == "W0006")
+        .expect("expected W0006");
+    assert_eq!(shadow.labels.len(), 1);
+    assert_eq!(shadow.labels[0].message, "previous binding here");
+    assert_eq!(shadow.labels[0].span.start_line, 5);
+    assert_eq!(shadow.span.start_line, 6);
+}
+
+#[test]
+fn test_annotation_mismatch_labels_annotation() {
+    let source = r#"
+module example
+
+fn main() -> Int {
+  let y: Int = "a"
+  y
+}
+"#;
+    let diags = check_module_all_diags(source);
+    let mismatch = diags
+        .diagnostics()
+        .iter()
+        .find(|d| d.code == "E1001")
+        .expect("expected E1001");
+    assert_eq!(mismatch.span.start_line, 5);
+    assert_eq!(
+        mismatch.labels[0].message,
+        "expected because of this annotation"
+    );
+}
+
+#[test]
+fn test_duplicate_variant_labels_first_definition() {
+    let source = r#"
+module example
+
+enum E = A | B | A
+"#;
+    let diags = check_module_all_diags(source);
+    let dup = diags
+        .diagnostics()
+        .iter()
+        .find(|d| d.code == "E1005")
+        .expect("expected E1005");
+    assert_eq!(dup.labels[0].message, "first defined here");
+    assert!(dup.labels[0].span.start < dup.span.start);
+}
+
+#[test]
+fn test_symbol_table_records_imported_definitions() {
+    let module =
+        parse_module("module app\n\nimport std.math\n\nfn f() -> Int {\n  math.abs_val(-1)\n}\n");
+    let mut checker = TypeChecker::new();
+    checker.add_search_path(PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+    let _ = checker.check_module(&module);
+
+    let symbols = checker.symbol_table();
+    let abs = symbols
+        .get("std.math.abs_val")
+        .expect("abs_val is recorded");
+    assert_eq!(abs.module, "std.math");
+    assert_eq!(
+        abs.span.file,
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("stdlib/math.astra")
+    );
+    assert_eq!((abs.span.start_line, abs.span.start_col), (4, 11));
+    assert!(symbols.get("std.math").is_some());
+}
+
+// W0004: Deprecated items
+
+/// W0004 warnings as (line, message, notes), in source order
+fn deprecations(diags: &DiagnosticBag) -> Vec<(usize, String, Vec<String>)> {
+    let mut found: Vec<_> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.code == "W0004")
+        .map(|d| {
+            let notes = d.notes.iter().map(|n| n.message.clone()).collect();
+            (d.span.start_line, d.message.clone(), notes)
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+#[test]
+fn test_deprecated_function_and_type_uses() {
+    let diags = check_module_all_diags(
+        r#"module example
+
+@deprecated("use Meters instead")
+type Feet = Int
+
+@deprecated("use twice instead")
+fn double(x: Int) -> Int {
+  x * 2
+}
+
+fn twice(x: Int) -> Int {
+  x + x
+}
+
+fn measure(f: Feet) -> Int {
+  let d = 2
+  double(f) + d
+}
+
+fn shadowed() -> Int {
+  let double = 3
+  double
+}
+
+@deprecated
+fn legacy() -> Int {
+  double(1)
+}
+
+fn main() -> Int {
+  @allow(deprecated)
+  let x = legacy()
+  twice(x) + measure(1) + shadowed()
+}
+"#,
+    );
+    assert_eq!(
+        deprecations(&diags),
+        vec![
+            (
+                15,
+                "`Feet` is deprecated".to_string(),
+                vec!["use Meters instead".to_string()]
+            ),
+            (
+                17,
+                "`double` is deprecated".to_string(),
+                vec!["use twice instead".to_string()]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_deprecated_imported_function() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("shapes.astra"),
+        "module shapes\n\n@deprecated(\"use area instead\")\npublic fn old_area(r: Int) -> Int {\n  r * r\n}\n",
+    )
+    .unwrap();
+    let module = parse_module(
+        "module app\n\nimport shapes.{old_area}\n\nfn main() -> Int {\n  old_area(2)\n}\n",
+    );
+    let mut checker = TypeChecker::new();
+    checker.add_search_path(dir.path().to_path_buf());
+    let _ = checker.check_module(&module);
+    assert_eq!(
+        deprecations(checker.diagnostics()),
+        vec![(
+            6,
+            "`old_area` is deprecated".to_string(),
+            vec!["use area instead".to_string()]
+        )]
+    );
+}
+
+#[test]
+fn test_text_to_int_is_deprecated() {
+    let diags = check_module_all_diags(
+        r#"module example
+
+fn main() -> Int {
+  let a = to_int("1")
+  let b = to_float("2.5")
+  let c = to_int(2.5)
+  match parse_int("ff", 16) {
+    Ok(n) => n + a.unwrap_or(0) + c,
+    Err(_) => 0,
+  }
+}
+
+@deprecated
+fn legacy(s: Text) -> Option[Int] {
+  to_int(s)
+}
+"#,
+    );
+    let note = |s: &str| {
+        vec![format!(
+            "use `{}`, which returns a Result saying why parsing failed",
+            s
+        )]
+    };
+    assert_eq!(
+        deprecations(&diags),
+        vec![
+            (
+                4,
+                "`to_int` on Text is deprecated".to_string(),
+                note("parse_int(text, 10)")
+            ),
+            (
+                5,
+                "`to_float` on Text is deprecated".to_string(),
+                note("parse_float(text)")
+            ),
+        ]
+    );
+    assert!(!diags.has_errors(), "{:?}", diags);
+}
+
+/// Hole contexts of all H0001 diagnostics, in source order
+fn hole_contexts(source: &str) -> Vec<crate::diagnostics::HoleContext> {
+    let diags = check_module_all_diags(source);
+    let mut holes: Vec<_> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.code == "H0001")
+        .collect();
+    holes.sort_by_key(|d| d.span.start);
+    holes.iter().filter_map(|d| d.hole.clone()).collect()
+}
+
+#[test]
+fn test_hole_reports_expected_type_and_bindings() {
+    let source = r#"
+module example
+
+fn clamp(x: Int, lo: Int, hi: Int) -> Int
+  requires lo <= hi
+{
+  let label = "clamped"
+  if x < lo { ??? } else { max(x, hi) }
+}
+"#;
+    let holes = hole_contexts(source);
+    assert_eq!(holes.len(), 1);
+    let hole = &holes[0];
+    assert_eq!(hole.expected_type.as_deref(), Some("Int"));
+    let names: Vec<&str> = hole.bindings.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, vec!["hi", "lo", "x"]);
+    assert_eq!(
+        hole.function.as_deref(),
+        Some("fn clamp(x: Int, lo: Int, hi: Int) -> Int")
+    );
+    assert_eq!(hole.requires, vec!["lo <= hi"]);
+}
+
+#[test]
+fn test_hole_expected_type_from_context() {
+    let source = r#"
+module example
+
+fn greet(name: Text) -> Text {
+  name
+}
+
+fn main() -> Unit {
+  let count: Int = ???
+  let ok = ??? and count > 0
+  let msg = greet(???)
+}
+"#;
+    let expected: Vec<Option<String>> = hole_contexts(source)
+        .into_iter()
+        .map(|h| h.expected_type)
+        .collect();
+    assert_eq!(
+        expected,
+        vec![
+            Some("Int".to_string()),
+            Some("Bool".to_string()),
+            Some("Text".to_string())
+        ]
+    );
+}
+
+#[test]
+fn test_hole_without_context_is_unknown() {
+    let holes = hole_contexts("module example\n\nfn main() -> Unit {\n  let x = ???\n}\n");
+    assert_eq!(holes.len(), 1);
+    assert_eq!(holes[0].expected_type, None);
+}
+
+#[test]
+fn test_hole_candidates_are_ranked() {
+    let source = r#"
+module example
+
+enum Shape =
+  | Circle(r: Float)
+  | Empty
+
+fn area(s: Shape) -> Float {
+  match s {
+    Circle(r) => r * r
+    Empty => 0.0
+  }
+}
+
+fn grow(k: Float, s: Shape) -> Shape {
+  ???
+}
+
+fn total(a: Shape, b: Shape) -> Float {
+  area(a) + ???
+}
+
+fn find(x: Int) -> Option[Int] {
+  ???
+}
+"#;
+    let candidates: Vec<Vec<String>> = hole_contexts(source)
+        .into_iter()
+        .map(|h| h.candidates)
+        .collect();
+    assert_eq!(candidates[0], vec!["s", "Empty", "Circle(k)"]);
+    assert_eq!(candidates[1], vec!["area(a)", "area(b)"]);
+    assert_eq!(candidates[2], vec!["None", "Some(x)"]);
+}
+
+#[test]
+fn test_hole_candidates_skip_recursion_and_undeclared_effects() {
+    let source = r#"
+module example
+
+fn now() -> Int effects(Clock) {
+  Clock.now()
+}
+
+fn next(n: Int) -> Int {
+  ???
+}
+"#;
+    let holes = hole_contexts(source);
+    assert_eq!(holes[0].candidates, vec!["n"]);
+}
+
+#[test]
+fn test_cfg_items_are_skipped() {
+    let source = r#"module example
+
+@cfg(feature = "net")
+fn fetch() -> Int {
+  1
+}
+
+@cfg(test)
+fn fixture() -> Text {
+  "fixture"
+}
+
+fn main() -> Int {
+  fetch()
+}
+"#;
+    let module = parse_module(source);
+
+    // Without the feature, `fetch` doesn't exist
+    let mut checker = TypeChecker::new();
+    let err = checker.check_module(&module).unwrap_err();
+    assert!(err
+        .diagnostics()
+        .iter()
+        .any(|d| d.message.contains("fetch")));
+
+    let mut checker = TypeChecker::new();
+    checker.set_cfg(crate::cfg::Cfg::test().with_features(["net"]));
+    let _ = checker.check_module(&module);
+    assert!(!checker.diagnostics().has_errors());
+    // The test-only helper is checked in test builds, so it's reported as unused
+    assert!(checker
+        .diagnostics()
+        .diagnostics()
+        .iter()
+        .any(|d| d.message.contains("`fixture`")));
+
+    let mut checker = TypeChecker::new();
+    checker.set_cfg(crate::cfg::Cfg::default().with_features(["net"]));
+    let _ = checker.check_module(&module);
+    assert!(checker.diagnostics().diagnostics().is_empty());
+}
+
+#[test]
+fn test_fixture_from_before_each() {
+    // The hook comes after the tests but still provides their fixture
+    let diags = check_module_all_diags(
+        r#"module example
+
+test "reads fixture" {
+  let n: Int = fixture.count
+  assert(n == 1)
+}
+
+after_each {
+  assert(fixture.name == "x")
+}
+
+test "wrong type" {
+  let name: Int = fixture.name
+  assert(name == 1)
+}
+
+before_each {
+  { count = 1, name = "x" }
+}
+"#,
+    );
+    let errors: Vec<&str> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.is_error())
+        .map(|d| d.message.as_str())
+        .collect();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0], "Expected type Int, found Text");
+
+    // Without a before_each hook there is no fixture
+    assert!(check_module("module example\n\ntest \"t\" {\n  assert(fixture == 1)\n}\n").is_err());
+}
+
+#[test]
+fn test_text_method_signatures() {
+    assert!(check_module(
+        r#"module example
+
+fn main() -> Bool {
+  let n: Int = "a,b".count(",")
+  let padded: Text = "7".pad_start(3, "0")
+  let words: List[Text] = "a b".split_whitespace()
+  let first: Option[Text] = "abc".char_at(n)
+  "A".equals_ignore_case("a")
+}
+"#
+    )
+    .is_ok());
+
+    let diags = check_module_all_diags(
+        r#"module example
+
+fn main() -> Int {
+  let a: Int = "x".trim_end()
+  let b = "x".pad_end()
+  let c = "x".char_at("0")
+  0
+}
+"#,
+    );
+    let errors: Vec<(&str, &str)> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.is_error())
+        .map(|d| (d.code.as_str(), d.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            ("E1001", "Expected type Int, found Text"),
+            (
+                "E1007",
+                "`Text.pad_end` expects 1 to 2 argument(s) but found 0"
+            ),
+            (
+                "E1001",
+                "Type mismatch in argument 1 of `Text.char_at`: expected `Int`, found `Text`"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_decimal_types_and_float_mixing() {
+    assert!(check_module(
+        r#"module example
+
+fn price(qty: Int) -> Decimal {
+  let unit: Decimal = 2.50d
+  let rounded: Decimal = (unit * qty / 3).round(2)
+  let places: Int = rounded.scale()
+  rounded + places
+}
+"#
+    )
+    .is_ok());
+
+    let diags = check_module_all_diags(
+        r#"module example
+
+fn main() -> Decimal {
+  let wrong: Float = 1.5d * 2
+  let parsed: Result[Decimal, ParseError] = parse_decimal("1.5")
+  1.5d + 0.25
+}
+"#,
+    );
+    let errors: Vec<(&str, &str)> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.is_error())
+        .map(|d| (d.code.as_str(), d.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            ("E1001", "Expected type Float, found Decimal"),
+            ("E1001", "Cannot mix `Decimal` and `Float` in `+`"),
+        ]
+    );
+}
+
+#[test]
+fn test_duration_types_reject_raw_millis() {
+    assert!(check_module(
+        r#"module example
+
+fn backoff(attempt: Int) -> Duration {
+  Duration.millis(250) * attempt + Duration.seconds(1)
+}
+
+fn wait(d: Duration) -> Int effects(Clock) {
+  let deadline: Deadline = Clock.deadline(d)
+  let left: Duration = Clock.remaining(deadline)
+  left / Duration.millis(10) + left.as_seconds()
+}
+"#
+    )
+    .is_ok());
+
+    let diags = check_module_all_diags(
+        r#"module example
+
+fn main() -> Duration effects(Clock) {
+  let late = Clock.deadline(500)
+  Duration.seconds(1) + 500
+}
+"#,
+    );
+    let errors: Vec<(&str, &str)> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.is_error())
+        .map(|d| (d.code.as_str(), d.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (
+                "E1001",
+                "Type mismatch in argument 1 of `Clock.deadline`: expected `Duration`, found `Int`"
+            ),
+            ("E1001", "Cannot apply `+` to `Duration` and `Int`"),
+        ]
+    );
+}
+
+#[test]
+fn test_encoding_builtin_types() {
+    assert!(check_module(
+        r#"module example
+
+fn decode(text: Text) -> Int {
+  let encoded: Text = base64_encode(text.to_bytes())
+  match hex_decode(encoded) {
+    Ok(bytes) => bytes.len()
+    Err(e) => e.index
+  }
+}
+"#
+    )
+    .is_ok());
+
+    let diags = check_module_all_diags(
+        r#"module example
+
+fn main() -> Int {
+  let raw: Text = Bytes.from_list([1, 2])
+  let n: Text = "ab".to_bytes().len()
+  0
+}
+"#,
+    );
+    let errors: Vec<&str> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.is_error())
+        .map(|d| d.message.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Expected type Text, found Named(\"Bytes\", [])",
+            "Expected type Text, found Int",
+        ]
+    );
+}
+
+#[test]
+fn test_hash_returns_int() {
+    assert!(check_module(
+        "module example\n\nfn key(x: Text) -> Int {\n  hash([x, sha256(x)])\n}\n"
+    )
+    .is_ok());
+    assert!(check_module(
+        "module example\n\nfn key(x: Text) -> Int {\n  let k: Text = hash(x)\n  0\n}\n"
+    )
+    .is_err());
+    // A user function named `hash` keeps its own signature
+    assert!(check_module(
+        "module example\n\nfn hash(x: Text) -> Text {\n  x\n}\n\nfn key() -> Text {\n  hash(\"a\")\n}\n"
+    )
+    .is_ok());
+}
+
+#[test]
+fn test_compression_builtin_types() {
+    assert!(check_module(
+        "module example\n\nfn round_trip(text: Text) -> Result[Bytes, Text] {\n  decompress(compress(text))\n}\n"
+    )
+    .is_ok());
+    let diags = check_module(
+        "module example\n\nfn unpack(text: Text) -> Result[Bytes, Text] {\n  decompress(text)\n}\n",
+    )
+    .unwrap_err();
+    assert!(diags
+        .diagnostics()
+        .iter()
+        .any(|d| d.code == "E1001" && d.message.contains("Bytes")));
+}
+
+#[test]
+fn test_serve_method_types() {
+    assert!(check_module(
+        "module example\n\nfn probe() -> Int effects(Serve) {\n  Serve.route(\"GET\", \"/\", fn(req) { {} })\n  Serve.request(\"GET\", \"/\", \"\").status\n}\n"
+    )
+    .is_ok());
+    let diags = check_module(
+        "module example\n\nfn probe() effects(Serve) {\n  let body: Int = Serve.request(\"GET\", \"/\", \"\").body\n}\n",
+    )
+    .unwrap_err();
+    assert!(diags.diagnostics().iter().any(|d| d.code == "E1001"));
+    let diags = check_module(
+        "module example\n\nfn probe() {\n  Serve.route(\"GET\", \"/\", fn(req) { {} })\n}\n",
+    )
+    .unwrap_err();
+    assert!(diags.diagnostics().iter().any(|d| d.code == "E2001"));
+}
+
+#[test]
+fn test_db_method_types() {
+    assert!(check_module(
+        "module example\n\nfn count(db_name: Text) -> Result[Int, Text] effects(Db) {\n  Db.execute(\"INSERT INTO t VALUES (?1, ?2)\", [db_name, 1])\n}\n"
+    )
+    .is_ok());
+    let diags = check_module(
+        "module example\n\nfn rows() effects(Db) {\n  let rows: Int = Db.query(\"SELECT 1\")\n}\n",
+    )
+    .unwrap_err();
+    assert!(diags.diagnostics().iter().any(|d| d.code == "E1001"));
+    let diags = check_module("module example\n\nfn wipe() {\n  Db.execute(\"DELETE FROM t\")\n}\n")
+        .unwrap_err();
+    assert!(diags.diagnostics().iter().any(|d| d.code == "E2001"));
+}
+
+#[test]
+fn test_progress_method_types() {
+    assert!(check_module(
+        "module example\n\nfn work(items: List[Text]) effects(Progress) {\n  Progress.start(\"work\", items.len())\n  Progress.advance(1)\n  Progress.finish()\n}\n"
+    )
+    .is_ok());
+    let diags = check_module(
+        "module example\n\nfn work() effects(Progress) {\n  Progress.start(10, \"work\")\n}\n",
+    )
+    .unwrap_err();
+    assert!(diags.diagnostics().iter().any(|d| d.code == "E1001"));
+    let diags =
+        check_module("module example\n\nfn work() {\n  Progress.finish()\n}\n").unwrap_err();
+    assert!(diags.diagnostics().iter().any(|d| d.code == "E2001"));
+}
+
+#[test]
+fn test_metrics_method_types() {
+    assert!(check_module(
+        "module example\n\nfn requests() -> Option[Int] effects(Metrics) {\n  Metrics.gauge(\"load\", 0.5)\n  Metrics.timing(\"fetch\", Duration.millis(5))\n  Metrics.snapshot().counters.get(\"requests\")\n}\n"
+    )
+    .is_ok());
+    let diags = check_module(
+        "module example\n\nfn fetch() effects(Metrics) {\n  Metrics.timing(\"fetch\", 5)\n}\n",
+    )
+    .unwrap_err();
+    assert!(diags.diagnostics().iter().any(|d| d.code == "E1001"));
+    let diags = check_module("module example\n\nfn hit() {\n  Metrics.counter(\"hits\", 1)\n}\n")
+        .unwrap_err();
+    assert!(diags.diagnostics().iter().any(|d| d.code == "E2001"));
+}
+
+#[test]
+fn test_websocket_methods_need_net() {
+    assert!(check_module(
+        "module example\n\nfn next(ws: WebSocket) -> Result[Option[Text], Text] effects(Net) {\n  ws.recv(Duration.seconds(1))\n}\n"
+    )
+    .is_ok());
+    let diags = check_module(
+        "module example\n\nfn hang_up(ws: WebSocket) {\n  let closed: Text = ws.close()\n}\n",
+    )
+    .unwrap_err();
+    let codes: Vec<&str> = diags
+        .diagnostics()
+        .iter()
+        .map(|d| d.code.as_str())
+        .collect();
+    assert!(codes.contains(&"E1001"), "{:?}", codes);
+    assert!(codes.contains(&"E2001"), "{:?}", codes);
+}
+
+#[test]
+fn test_rpc_builtin_types() {
+    assert!(check_module(
+        "module example\n\nfn tools(url: Text) -> Int effects(Net) {\n  match rpc_call(url, \"tools/list\", []) {\n    Ok(_) => 0\n    Err(e) => e.code\n  }\n}\n"
+    )
+    .is_ok());
+    let diags = check_module(
+        "module example\n\nfn tools(url: Text) {\n  let results: Text = rpc_batch(url, [(\"ping\", [])])\n}\n",
+    )
+    .unwrap_err();
+    let codes: Vec<&str> = diags
+        .diagnostics()
+        .iter()
+        .map(|d| d.code.as_str())
+        .collect();
+    assert!(codes.contains(&"E1001"), "{:?}", codes);
+    assert!(codes.contains(&"E2001"), "{:?}", codes);
+}
+
+#[test]
+fn test_record_patterns_check_fields() {
+    let source = r#"module example
+
+fn f(p: { x: Int, y: Int, z: Int }) -> Int {
+  let { x, y: vertical, .. } = p
+  let { x, w } = p
+  match p {
+    { z, .. } => z + x + vertical
+  }
+}
+"#;
+    let diags = check_module_all_diags(source);
+    let errors: Vec<&Diagnostic> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.severity == crate::diagnostics::Severity::Error)
+        .collect();
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert_eq!(errors[0].code, "E1006");
+    assert!(errors[0].message.contains("'w'"));
+    assert_eq!(errors[1].code, "E1001");
+    assert!(errors[1].message.contains("`y`, `z`"));
+    // The fix appends `, ..` after the last field
+    let edit = &errors[1].suggestions[0].edits[0];
+    assert_eq!(edit.replacement, ", ..");
+    assert_eq!(&source[edit.span.start - 1..edit.span.start + 2], "w }");
+}
+
+#[test]
+fn test_for_loop_pattern_binds_element_types() {
+    let diags = check_module_all_diags(
+        r#"module example
+
+fn main() -> Int {
+  for (n, label) in [(1, "one"), (2, "two")] {
+    let a: Int = n
+    let b: Int = label
+  }
+  for {x, y} in [{ x = 1, y = true }] {
+    let c: Bool = y
+    let d: Text = x
+  }
+  for word in ["a", "b"] {
+    let e: Text = word
+  }
+  0
+}
+
+fn collections(m: Map[Text, Int], s: Set[Bool], t: Text) -> Int {
+  for (k, v) in m {
+    let f: Text = k
+    let g: Int = v
+  }
+  for flag in s {
+    let h: Bool = flag
+  }
+  for c in t {
+    let i: Int = c
+  }
+  0
+}
+"#,
+    );
+    let errors: Vec<&str> = diags
+        .diagnostics()
+        .iter()
+        .filter(|d| d.is_error())
+        .map(|d| d.message.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Expected type Int, found Text",
+            "Expected type Text, found Int",
+            "Expected type Int, found Text"
+        ]
+    );
+}
@@ -0,0 +1,57 @@
+//! JavaScript bindings (feature `wasm`)
+//!
+//! Built for browsers and Node with wasm-pack:
+//!
+//! ```js
+//! import init, { check, fmt, run } from "astra";
+//!
+//! await init();
+//! check(source);          // { ok, diagnostics }
+//! fmt(source);            // { ok, formatted, changed, diagnostics }
+//! run(source, 42n, { console: true, fs: { "a.txt": "hi" }, rand: true });
+//! ```
+//!
+//! Capabilities take the values of [`CapabilityConfig`]: runs see only the
+//! in-memory files, canned responses, and seeded `Rand` they are given.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::embed::{self, CapabilityConfig};
+
+/// Parse and type check a module
+#[wasm_bindgen]
+pub fn check(source: &str) -> Result<JsValue, JsError> {
+    to_js(&embed::check(source))
+}
+
+/// Format a module as `astra fmt` would
+#[wasm_bindgen]
+pub fn fmt(source: &str) -> Result<JsValue, JsError> {
+    to_js(&embed::fmt(source))
+}
+
+/// Run a module's `main` in a sandbox with the given capabilities
+#[wasm_bindgen]
+pub fn run(source: &str, seed: u64, capabilities: JsValue) -> Result<JsValue, JsError> {
+    let config: CapabilityConfig = if capabilities.is_undefined() || capabilities.is_null() {
+        CapabilityConfig::default()
+    } else {
+        serde_wasm_bindgen::from_value(capabilities)
+            .map_err(|e| JsError::new(&format!("invalid capabilities: {}", e)))?
+    };
+    to_js(&embed::run(source, seed, &config))
+}
+
+/// The toolchain version
+#[wasm_bindgen]
+pub fn version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+/// Convert a result to plain JavaScript objects (maps become objects)
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()))
+}